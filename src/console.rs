@@ -0,0 +1,514 @@
+//! Live instrumentation of the tasks, for an external console.
+//!
+//! An [`Endpoint`] streams the lifecycle events of the tasks of every runtime of the process,
+//! and of their I/O resources, to the consoles connected to it over TCP or a Unix socket, so a
+//! console tool can show the live states of the tasks, their wakes and their poll durations.
+//! Nothing is recorded while no console is connected, and the events are served by threads of
+//! their own, off the runtimes.
+//!
+//! [`Endpoint`]: struct.Endpoint.html
+//!
+//! # Format
+//!
+//! A connection receives one JSON object per line, whose `type` field names the event and
+//! whose `time` field is its instant, in microseconds since the first endpoint was opened.
+//! The tasks and the resources are identified by numbers, the ids of the resources are reused
+//! once they are deregistered.
+//!
+//! | `type`       | Fields                                       | Event                                      |
+//! |--------------|----------------------------------------------|--------------------------------------------|
+//! | `hello`      | `version`                                    | the connection is accepted                 |
+//! | `task`       | `task`, `name`, `location`, `state`, `polls` | a task is live when the console connects   |
+//! | `spawn`      | `task`, `name`, `location`                   | a task is spawned                          |
+//! | `wake`       | `task`                                       | a task is scheduled, first after its spawn |
+//! | `poll`       | `task`, `duration` in microseconds           | a poll of a task has returned              |
+//! | `exit`       | `task`, `outcome`                            | a task completed, aborted or panicked      |
+//! | `drop`       | `task`                                       | a task and its handle are dropped          |
+//! | `register`   | `resource`, `kind`                           | an I/O source is registered                |
+//! | `deregister` | `resource`                                   | an I/O source is deregistered              |
+//! | `lost`       | `events`                                     | events were dropped for a slow console     |
+//!
+//! After `hello`, a `task` event describes every task which is live on a runtime, its `state`
+//! is `idle`, `running` or `finished`. The `name` of a task is `null` if it is unnamed, its
+//! `location` is where it is spawned, and the `outcome` of an exit is `complete`, `abort` or
+//! `panic`. A task spawned while a console connects may be described by both a `task` and a
+//! `spawn` event, and the `task` events may follow some events of the tasks they describe.
+//!
+//! ```text
+//! {"type":"hello","time":0,"version":1}
+//! {"type":"spawn","time":120,"task":7,"name":"server","location":"src/main.rs:12:5"}
+//! {"type":"wake","time":121,"task":7}
+//! {"type":"poll","time":180,"task":7,"duration":58}
+//! ```
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use tio::console;
+//!
+//! // the console connects to 127.0.0.1:6669
+//! let endpoint = console::serve_tcp("127.0.0.1:6669").unwrap();
+//! tio::task::block_on(async {
+//!     // ...
+//! });
+//! drop(endpoint);
+//! ```
+
+use crate::runtime;
+use crate::task::TaskId;
+use once_cell::sync::Lazy;
+use std::fmt::Write as _;
+use std::io::{self, BufWriter, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+
+const CLIENTS_LOCK_POISONED: &str = "console clients lock poisoned";
+
+/// The version of the format, in the `hello` event.
+const VERSION: u32 = 1;
+
+/// The events buffered for a console, beyond which they are lost.
+const CAPACITY: usize = 1 << 14;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static EPOCH: Lazy<Instant> = Lazy::new(Instant::now);
+static NEXT_ENDPOINT: AtomicU64 = AtomicU64::new(0);
+static CLIENTS: Lazy<Mutex<Vec<Client>>> = Lazy::new(Default::default);
+
+/// A connected console.
+struct Client {
+    endpoint: u64,
+    sender: SyncSender<String>,
+    lost: Arc<AtomicU64>,
+}
+
+/// An event of the instrumentation.
+pub(crate) enum Event<'a> {
+    Spawn(TaskId, Option<&'a str>, &'static Location<'static>),
+    Wake(TaskId),
+    Poll(TaskId, Duration),
+    Exit(TaskId, &'static str),
+    Drop(TaskId),
+    #[cfg(feature = "event-loop")]
+    Register(usize, &'static str),
+    #[cfg(feature = "event-loop")]
+    Deregister(usize),
+}
+
+/// Sends an event to the connected consoles, if any.
+#[inline]
+pub(crate) fn emit(event: Event<'_>) {
+    if ENABLED.load(Ordering::Relaxed) {
+        send(event)
+    }
+}
+
+#[cold]
+fn send(event: Event<'_>) {
+    let line = match event {
+        Event::Spawn(id, name, location) => {
+            let mut line = header("spawn");
+            write_task(&mut line, id, name, location);
+            line
+        }
+        Event::Wake(id) => format!("{},\"task\":{}}}", header("wake"), id),
+        Event::Poll(id, dur) => format!(
+            "{},\"task\":{},\"duration\":{}}}",
+            header("poll"),
+            id,
+            dur.as_micros()
+        ),
+        Event::Exit(id, outcome) => format!(
+            "{},\"task\":{},\"outcome\":\"{}\"}}",
+            header("exit"),
+            id,
+            outcome
+        ),
+        Event::Drop(id) => format!("{},\"task\":{}}}", header("drop"), id),
+        #[cfg(feature = "event-loop")]
+        Event::Register(resource, kind) => {
+            let mut line =
+                format!("{},\"resource\":{},\"kind\":", header("register"), resource);
+            write_str(&mut line, kind);
+            line.push('}');
+            line
+        }
+        #[cfg(feature = "event-loop")]
+        Event::Deregister(resource) => {
+            format!("{},\"resource\":{}}}", header("deregister"), resource)
+        }
+    };
+    let mut clients = CLIENTS.lock().expect(CLIENTS_LOCK_POISONED);
+    clients.retain(|client| match client.sender.try_send(line.clone()) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            client.lost.fetch_add(1, Ordering::Relaxed);
+            true
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+    });
+    ENABLED.store(!clients.is_empty(), Ordering::Relaxed);
+}
+
+/// Starts an event line, open for more fields.
+#[inline]
+fn header(kind: &str) -> String {
+    format!(
+        "{{\"type\":\"{}\",\"time\":{}",
+        kind,
+        EPOCH.elapsed().as_micros()
+    )
+}
+
+fn write_task(
+    line: &mut String,
+    id: TaskId,
+    name: Option<&str>,
+    location: &Location<'_>,
+) {
+    let _ = write!(line, ",\"task\":{},\"name\":", id);
+    match name {
+        Some(name) => write_str(line, name),
+        None => line.push_str("null"),
+    }
+    line.push_str(",\"location\":");
+    write_str(line, &location.to_string());
+}
+
+/// Writes a JSON string.
+fn write_str(line: &mut String, s: &str) {
+    line.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(line, "\\u{:04x}", c as u32);
+            }
+            c => line.push(c),
+        }
+    }
+    line.push('"');
+}
+
+/// Accepts a console, sending it the live tasks before the events.
+fn accept<W>(endpoint: u64, stream: W)
+where
+    W: 'static + Send + Write,
+{
+    let (sender, receiver) = mpsc::sync_channel(CAPACITY);
+    let lost = Arc::new(AtomicU64::new(0));
+    {
+        let mut clients = CLIENTS.lock().expect(CLIENTS_LOCK_POISONED);
+        let _ =
+            sender.try_send(format!("{},\"version\":{}}}", header("hello"), VERSION));
+        clients.push(Client {
+            endpoint,
+            sender: sender.clone(),
+            lost: lost.clone(),
+        });
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+    // a task dropped by the dump emits an event, so the dump is taken out of the lock
+    for info in &runtime::live_tasks() {
+        let mut line = header("task");
+        write_task(&mut line, info.id(), info.name(), info.location());
+        let _ = write!(
+            line,
+            ",\"state\":\"{}\",\"polls\":{}}}",
+            info.state(),
+            info.polls()
+        );
+        if sender.try_send(line).is_err() {
+            lost.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    drop(sender);
+    let ret = thread::Builder::new()
+        .name("tio/console".to_string())
+        .spawn(move || write_events(stream, receiver, &lost));
+    // the client is removed on the next event, its receiver being dropped
+    if let Err(err) = ret {
+        log::error!("fail to start a console thread: {}", err);
+    }
+}
+
+/// Writes the events of a console until it disconnects, or its endpoint is closed.
+fn write_events<W: Write>(stream: W, receiver: Receiver<String>, lost: &AtomicU64) {
+    let mut stream = BufWriter::new(stream);
+    loop {
+        let line = match receiver.try_recv() {
+            Ok(line) => line,
+            // flush once the burst of events is written
+            Err(TryRecvError::Empty) => match stream.flush() {
+                Ok(()) => match receiver.recv() {
+                    Ok(line) => line,
+                    Err(_) => return,
+                },
+                Err(_) => return,
+            },
+            Err(TryRecvError::Disconnected) => break,
+        };
+        let events = lost.swap(0, Ordering::Relaxed);
+        if events > 0 {
+            let lost = format!("{},\"events\":{}}}", header("lost"), events);
+            if writeln!(stream, "{}", lost).is_err() {
+                return;
+            }
+        }
+        if writeln!(stream, "{}", line).is_err() {
+            return;
+        }
+    }
+    let _ = stream.flush();
+}
+
+/// The listener of an endpoint.
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+/// An endpoint serving the events to the consoles, created by [`serve_tcp`] or
+/// [`serve_unix`].
+///
+/// Dropping an endpoint stops it, and disconnects its consoles.
+///
+/// [`serve_tcp`]: fn.serve_tcp.html
+/// [`serve_unix`]: fn.serve_unix.html
+#[derive(Debug)]
+pub struct Endpoint {
+    id: u64,
+    addr: Option<SocketAddr>,
+    #[cfg(unix)]
+    path: Option<PathBuf>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Opens an endpoint accepting the consoles on a TCP address.
+///
+/// # Errors
+///
+/// This function fails if the address cannot be bound.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{BufRead, BufReader};
+/// use std::net::TcpStream;
+/// use tio::console;
+///
+/// let endpoint = console::serve_tcp("127.0.0.1:0").unwrap();
+/// let console = TcpStream::connect(endpoint.local_addr().unwrap()).unwrap();
+/// let mut lines = BufReader::new(console).lines();
+/// assert!(lines.next().unwrap().unwrap().starts_with("{\"type\":\"hello\""));
+/// ```
+pub fn serve_tcp(addr: impl ToSocketAddrs) -> io::Result<Endpoint> {
+    let listener = TcpListener::bind(addr)?;
+    let addr = listener.local_addr()?;
+    let mut endpoint = Endpoint::start(Listener::Tcp(listener))?;
+    endpoint.addr = Some(addr);
+    Ok(endpoint)
+}
+
+/// Opens an endpoint accepting the consoles on a Unix socket, bound to `path`.
+///
+/// The socket file is removed when the endpoint is dropped.
+///
+/// # Errors
+///
+/// This function fails if the path cannot be bound, like if it exists.
+#[cfg(unix)]
+#[cfg_attr(feature = "docs", doc(cfg(unix)))]
+pub fn serve_unix(path: impl AsRef<Path>) -> io::Result<Endpoint> {
+    let path = path.as_ref().to_path_buf();
+    let listener = UnixListener::bind(&path)?;
+    let mut endpoint = Endpoint::start(Listener::Unix(listener, path.clone()))?;
+    endpoint.path = Some(path);
+    Ok(endpoint)
+}
+
+impl Endpoint {
+    fn start(listener: Listener) -> io::Result<Self> {
+        Lazy::force(&EPOCH);
+        let id = NEXT_ENDPOINT.fetch_add(1, Ordering::Relaxed);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let thread = thread::Builder::new()
+            .name("tio/console".to_string())
+            .spawn({
+                let shutdown = shutdown.clone();
+                move || serve(id, listener, &shutdown)
+            })?;
+        Ok(Self {
+            id,
+            addr: None,
+            #[cfg(unix)]
+            path: None,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the address of a TCP endpoint, or `None` for a Unix one.
+    #[inline]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.addr
+    }
+}
+
+fn serve(endpoint: u64, listener: Listener, shutdown: &AtomicBool) {
+    loop {
+        let ret = match &listener {
+            Listener::Tcp(listener) => listener.accept().map(|(stream, _)| {
+                if !shutdown.load(Ordering::Acquire) {
+                    let _ = stream.set_nodelay(true);
+                    accept(endpoint, stream)
+                }
+            }),
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => listener.accept().map(|(stream, _)| {
+                if !shutdown.load(Ordering::Acquire) {
+                    accept(endpoint, stream)
+                }
+            }),
+        };
+        if shutdown.load(Ordering::Acquire) {
+            break;
+        }
+        if let Err(err) = ret {
+            log::warn!("fail to accept a console: {}", err);
+        }
+    }
+    #[cfg(unix)]
+    if let Listener::Unix(_, path) = &listener {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+impl Drop for Endpoint {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        // wake the accepting thread up
+        if let Some(mut addr) = self.addr {
+            if addr.ip().is_unspecified() {
+                addr.set_ip(match addr {
+                    SocketAddr::V4(_) => [127, 0, 0, 1].into(),
+                    SocketAddr::V6(_) => [0, 0, 0, 0, 0, 0, 0, 1].into(),
+                });
+            }
+            if let Ok(stream) = TcpStream::connect(addr) {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+        #[cfg(unix)]
+        if let Some(path) = &self.path {
+            let _ = UnixStream::connect(path);
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let mut clients = CLIENTS.lock().expect(CLIENTS_LOCK_POISONED);
+        clients.retain(|client| client.endpoint != self.id);
+        ENABLED.store(!clients.is_empty(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{serve_tcp, write_str};
+    use crate::runtime::Builder;
+    use crate::task;
+    use std::io::{self, BufRead, BufReader};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    #[test]
+    fn json() {
+        let mut line = String::new();
+        write_str(&mut line, "a\"b\\c\nd\u{1}");
+        assert_eq!(r#""a\"b\\c\nd\u0001""#, line);
+    }
+
+    #[test]
+    fn events() -> io::Result<()> {
+        let endpoint = serve_tcp("127.0.0.1:0")?;
+        let console = TcpStream::connect(endpoint.local_addr().unwrap())?;
+        console.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let mut lines = BufReader::new(console).lines();
+        assert!(lines.next().unwrap()?.starts_with("{\"type\":\"hello\""));
+
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let handle = task::Builder::new().name("traced").spawn_on_handle(
+            runtime.handle(),
+            async {
+                task::yield_now().await;
+            },
+        );
+        let id = handle.id();
+        runtime.block_on(handle).unwrap();
+        let task = format!("\"task\":{}", id);
+        let mut kinds = Vec::new();
+        for line in lines {
+            let line = line?;
+            // not a task whose id starts with the same digits
+            if !line.contains(&format!("{},", task))
+                && !line.contains(&format!("{}}}", task))
+            {
+                continue;
+            }
+            let kind = line.split('"').nth(3).unwrap().to_string();
+            if kind == "spawn" {
+                assert!(line.contains("\"name\":\"traced\""));
+                assert!(line.contains(file!()));
+            }
+            kinds.push(kind);
+            if kinds.last().map(String::as_str) == Some("drop") {
+                break;
+            }
+        }
+        assert_eq!(
+            vec!["spawn", "wake", "wake", "poll", "exit", "poll", "drop"],
+            kinds
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix() -> io::Result<()> {
+        use super::serve_unix;
+        use std::os::unix::net::UnixStream;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("console.sock");
+        let endpoint = serve_unix(&path)?;
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let live = task::Builder::new()
+            .name("live")
+            .spawn_on_handle(runtime.handle(), futures::future::pending::<()>());
+        let console = UnixStream::connect(&path)?;
+        console.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let mut lines = BufReader::new(console).lines();
+        assert!(lines.next().unwrap()?.starts_with("{\"type\":\"hello\""));
+        let task = format!("\"task\":{},\"name\":\"live\"", live.id());
+        assert!(lines.any(|line| line.unwrap().contains(&task)));
+        drop(endpoint);
+        assert!(!path.exists());
+        Ok(())
+    }
+}
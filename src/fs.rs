@@ -46,7 +46,7 @@ pub async fn read<P>(path: P) -> io::Result<Vec<u8>>
 where
     P: 'static + Send + AsRef<Path>,
 {
-    spawn_blocking(move || fs::read(path)).await
+    spawn_blocking(move || fs::read(path)).await?
 }
 
 /// Read the entire contents of a file into a string.
@@ -89,7 +89,7 @@ pub async fn read_to_string<P>(path: P) -> io::Result<String>
 where
     P: 'static + Send + AsRef<Path>,
 {
-    spawn_blocking(move || fs::read_to_string(path)).await
+    spawn_blocking(move || fs::read_to_string(path)).await?
 }
 
 /// Write a slice as the entire contents of a file.
@@ -114,5 +114,5 @@ where
     P: 'static + Send + AsRef<Path>,
     C: 'static + Send + AsRef<[u8]>,
 {
-    spawn_blocking(move || fs::write(path, contents)).await
+    spawn_blocking(move || fs::write(path, contents)).await?
 }
@@ -75,5 +75,6 @@ use std::sync::Arc;
 /// [`sync_all`]: struct.File.html#method.sync_all
 /// [`read`]: fn.read.html
 /// [`write`]: fn.write.html
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct File(Arc<fs::File>);
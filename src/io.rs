@@ -0,0 +1,132 @@
+//! Async I/O utilities, over the traits of [`futures::io`].
+//!
+//! The readers and writers of tio, like the sockets and the pipes of the child processes,
+//! implement [`AsyncRead`] and [`AsyncWrite`], so they work with every adapter of
+//! [`futures::io`]. This module adds the ones which fit the streams of tio, like the
+//! [`BufReader`] whose lines are a [`Stream`], the [`BufWriter`] flushing by vectored writes,
+//! the bidirectional adapters of [`AsyncReadExt`], [`copy_with_progress`], and [`splice`]
+//! moving the bytes between the sockets in the kernel.
+//!
+//! [`futures::io`]: https://docs.rs/futures/0.3/futures/io/index.html
+//! [`AsyncRead`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncRead.html
+//! [`AsyncWrite`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncWrite.html
+//! [`BufReader`]: struct.BufReader.html
+//! [`BufWriter`]: struct.BufWriter.html
+//! [`Stream`]: https://docs.rs/futures/0.3/futures/stream/trait.Stream.html
+//! [`AsyncReadExt`]: trait.AsyncReadExt.html
+//! [`copy_with_progress`]: fn.copy_with_progress.html
+//! [`splice`]: fn.splice.html
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use futures::io::Cursor;
+//! use futures::StreamExt;
+//! use tio::io::BufReader;
+//!
+//! let mut lines = BufReader::new(Cursor::new("hello\r\nworld\n")).lines();
+//!
+//! while let Some(line) = lines.next().await {
+//!     println!("{}", line?);
+//! }
+//! #
+//! # Ok(()) }) }
+//! ```
+
+mod buf_reader;
+mod buf_writer;
+mod chain;
+mod copy;
+mod split;
+mod take;
+
+#[cfg(all(unix, any(feature = "tcp", feature = "uds")))]
+mod splice;
+
+pub use buf_reader::BufReader;
+pub use buf_writer::BufWriter;
+pub use chain::Chain;
+pub use copy::copy_with_progress;
+pub use split::{Lines, Split};
+pub use take::Take;
+
+#[cfg(all(unix, any(feature = "tcp", feature = "uds")))]
+pub use splice::{splice, SpliceSocket};
+
+use futures::io::AsyncRead;
+
+/// An extension trait for the readers, adding the adapters of tio.
+///
+/// The adapters pass the writes through to the underlying reader, so an adapted socket can
+/// still answer its peer.
+///
+/// They share the names of the adapters of [`futures::io::AsyncReadExt`], so a call of them
+/// with both traits in scope is ambiguous; call them by the path of this trait then.
+///
+/// [`futures::io::AsyncReadExt`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncReadExt.html
+pub trait AsyncReadExt: AsyncRead {
+    /// Creates a reader of the bytes of this reader, then the ones of `next`.
+    ///
+    /// The writes go to `next`, so the bytes read ahead of a socket, like to detect its
+    /// protocol, can be put back before it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use futures::io::{AsyncReadExt, Cursor};
+    /// use tio::io;
+    ///
+    /// let mut socket = Cursor::new(b"HTTP/1.1".to_vec());
+    /// let mut preamble = [0; 4];
+    /// socket.read_exact(&mut preamble).await?;
+    ///
+    /// let mut data = String::new();
+    /// let mut chain = io::AsyncReadExt::chain(&preamble[..], socket);
+    /// chain.read_to_string(&mut data).await?;
+    /// assert_eq!("HTTP/1.1", data);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    fn chain<R>(self, next: R) -> Chain<Self, R>
+    where
+        R: AsyncRead,
+        Self: Sized,
+    {
+        Chain::new(self, next)
+    }
+
+    /// Creates a reader of at most `limit` bytes of this reader.
+    ///
+    /// The reader ends once `limit` bytes are read, leaving the rest to the reads of the
+    /// underlying reader, like the requests following a body of a known length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use futures::io::{AsyncReadExt, Cursor};
+    /// use tio::io;
+    ///
+    /// let mut body = io::AsyncReadExt::take(Cursor::new(b"hello world"), 5);
+    /// let mut data = String::new();
+    /// body.read_to_string(&mut data).await?;
+    /// assert_eq!("hello", data);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    fn take(self, limit: u64) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take::new(self, limit)
+    }
+}
+
+impl<R: AsyncRead + ?Sized> AsyncReadExt for R {}
@@ -0,0 +1,242 @@
+use super::{Lines, Split};
+use futures::io::{AsyncBufRead, AsyncRead};
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The capacity of the buffer by default, the same as std's.
+pub(super) const CAPACITY: usize = 8 * 1024;
+
+/// Adds buffering to any reader.
+///
+/// It can be excessively inefficient to work directly with a reader of few bytes per read,
+/// like a socket whose peer writes a line at a time. A `BufReader` reads large chunks into its
+/// buffer and serves the small reads from it, and its [`lines`] and [`split`] are streams,
+/// which read no more than the items polled.
+///
+/// The reader must be [`Unpin`], like every reader of tio; any other reader can be pinned
+/// by [`Box::pin`].
+///
+/// This type is an async version of [`std::io::BufReader`].
+///
+/// [`lines`]: #method.lines
+/// [`split`]: #method.split
+/// [`Unpin`]: https://doc.rust-lang.org/std/marker/trait.Unpin.html
+/// [`Box::pin`]: https://doc.rust-lang.org/std/boxed/struct.Box.html#method.pin
+/// [`std::io::BufReader`]: https://doc.rust-lang.org/std/io/struct.BufReader.html
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::io::{AsyncBufReadExt, Cursor};
+/// use tio::io::BufReader;
+///
+/// let mut reader = BufReader::new(Cursor::new("hello world"));
+/// let mut word = Vec::new();
+/// reader.read_until(b' ', &mut word).await?;
+/// assert_eq!(b"hello ", word.as_slice());
+/// assert_eq!(b"world", reader.buffer());
+/// #
+/// # Ok(()) }) }
+/// ```
+pub struct BufReader<R> {
+    inner: R,
+    buf: Box<[u8]>,
+    // the unread bytes of the buffer are `buf[pos..cap]`
+    pos: usize,
+    cap: usize,
+}
+
+impl<R> BufReader<R> {
+    /// Creates a new `BufReader` with a default buffer capacity, of 8 KiB.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(CAPACITY, inner)
+    }
+
+    /// Creates a new `BufReader` with the specified buffer capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            cap: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// It is inadvisable to directly read from the underlying reader, which skips the bytes
+    /// in the buffer.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the bytes read into the buffer but not consumed yet.
+    #[inline]
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.cap]
+    }
+
+    /// Unwraps this `BufReader`, returning the underlying reader.
+    ///
+    /// The bytes in the buffer are lost.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns a stream over the lines of this reader.
+    ///
+    /// Every line is a [`String`] without its newline, `\n` or `\r\n`. The last line needs
+    /// no newline, and a line which is not UTF-8 is an error of kind [`InvalidData`].
+    ///
+    /// [`String`]: https://doc.rust-lang.org/std/string/struct.String.html
+    /// [`InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use futures::io::Cursor;
+    /// use futures::TryStreamExt;
+    /// use tio::io::BufReader;
+    ///
+    /// let lines = BufReader::new(Cursor::new("hello\r\nworld\n")).lines();
+    /// assert_eq!(vec!["hello", "world"], lines.try_collect::<Vec<_>>().await?);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn lines(self) -> Lines<R> {
+        Lines::new(self)
+    }
+
+    /// Returns a stream over the contents of this reader split on the byte `delim`.
+    ///
+    /// Every item is the bytes up to the delimiter, without it. The last item needs no
+    /// delimiter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use futures::io::Cursor;
+    /// use futures::TryStreamExt;
+    /// use tio::io::BufReader;
+    ///
+    /// let records = BufReader::new(Cursor::new(b"a\0bc\0")).split(0);
+    /// let records = records.try_collect::<Vec<_>>().await?;
+    /// assert_eq!(vec![b"a".to_vec(), b"bc".to_vec()], records);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn split(self, delim: u8) -> Split<R> {
+        Split::new(self, delim)
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for BufReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // a read as large as the buffer gains nothing from it
+        if self.pos == self.cap && buf.len() >= self.buf.len() {
+            return Pin::new(&mut self.inner).poll_read(cx, buf);
+        }
+        let available = futures::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncBufRead for BufReader<R> {
+    fn poll_fill_buf(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.pos == this.cap {
+            this.cap =
+                futures::ready!(Pin::new(&mut this.inner).poll_read(cx, &mut this.buf))?;
+            this.pos = 0;
+        }
+        Poll::Ready(Ok(&this.buf[this.pos..this.cap]))
+    }
+
+    #[inline]
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        self.pos = (self.pos + amt).min(self.cap);
+    }
+}
+
+impl<R: Debug> Debug for BufReader<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufReader")
+            .field("reader", &self.inner)
+            .field(
+                "buffer",
+                &format_args!("{}/{}", self.cap - self.pos, self.buf.len()),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufReader;
+    use crate::task::block_on;
+    use futures::io::{AsyncBufReadExt, AsyncReadExt, Cursor};
+    use std::io;
+
+    #[test]
+    fn buffered() -> io::Result<()> {
+        block_on(async {
+            let mut reader = BufReader::with_capacity(4, Cursor::new(b"hello world"));
+            let mut buf = [0; 2];
+            reader.read_exact(&mut buf).await?;
+            assert_eq!(b"he", &buf);
+            assert_eq!(b"ll", reader.buffer());
+            assert_eq!(4, reader.get_ref().position());
+
+            // a large read takes the buffer first, then bypasses it
+            let mut buf = [0; 8];
+            assert_eq!(2, reader.read(&mut buf).await?);
+            assert_eq!(7, reader.read(&mut buf).await?);
+            assert_eq!(b"o world", &buf[..7]);
+            assert_eq!(0, reader.read(&mut buf).await?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn consume() -> io::Result<()> {
+        block_on(async {
+            let mut reader = BufReader::with_capacity(8, Cursor::new(b"hello world"));
+            assert_eq!(b"hello wo", reader.fill_buf().await?);
+            reader.consume_unpin(6);
+            assert_eq!(b"wo", reader.fill_buf().await?);
+            reader.consume_unpin(16);
+            assert_eq!(b"rld", reader.fill_buf().await?);
+            Ok(())
+        })
+    }
+}
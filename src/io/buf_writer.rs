@@ -0,0 +1,325 @@
+use super::buf_reader::CAPACITY;
+use futures::io::AsyncWrite;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The max number of buffers written at once, the least `IOV_MAX` of the platforms.
+const MAX_BUFS: usize = 64;
+
+/// Adds buffering to any writer, flushing the buffers by vectored writes.
+///
+/// It can be excessively inefficient to work directly with a writer of few bytes per write,
+/// like a socket whose protocol writes a header then a body. A `BufWriter` keeps the small
+/// writes in a buffer, and the owned buffers [queued] as they are, then writes them all at
+/// once by a vectored write on [`flush`], without coalescing them into one allocation.
+///
+/// The buffered bytes are written out once they exceed the capacity, when the writer is
+/// flushed or closed, but not when it is dropped, so it should be flushed before. The writer
+/// must be [`Unpin`], like every writer of tio.
+///
+/// This type is an async version of [`std::io::BufWriter`].
+///
+/// [queued]: #method.queue
+/// [`flush`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncWriteExt.html#method.flush
+/// [`Unpin`]: https://doc.rust-lang.org/std/marker/trait.Unpin.html
+/// [`std::io::BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::io::AsyncWriteExt;
+/// use tio::io::BufWriter;
+///
+/// let body = vec![b'a'; 16 * 1024];
+/// let mut writer = BufWriter::new(Vec::new());
+/// writer.write_all(b"HTTP/1.1 200 OK\r\n\r\n").await?;
+/// // not copied
+/// writer.queue(body);
+/// writer.flush().await?;
+/// assert_eq!(19 + 16 * 1024, writer.get_ref().len());
+/// #
+/// # Ok(()) }) }
+/// ```
+pub struct BufWriter<W> {
+    inner: W,
+    // the buffers to write, the copies of the small writes are appended to the last one
+    // unless it is queued
+    bufs: VecDeque<Vec<u8>>,
+    tail_queued: bool,
+    // the bytes of the first buffer written already
+    written: usize,
+    len: usize,
+    capacity: usize,
+}
+
+impl<W> BufWriter<W> {
+    /// Creates a new `BufWriter` with a default buffer capacity, of 8 KiB.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(CAPACITY, inner)
+    }
+
+    /// Creates a new `BufWriter` with the specified buffer capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            bufs: VecDeque::new(),
+            tail_queued: false,
+            written: 0,
+            len: 0,
+            capacity,
+        }
+    }
+
+    /// Queues a buffer, to be written as it is after the bytes buffered.
+    ///
+    /// The buffer is written without being copied, on the next flush, or the next write
+    /// exceeding the capacity.
+    #[inline]
+    pub fn queue(&mut self, buf: Vec<u8>) {
+        if !buf.is_empty() {
+            self.len += buf.len();
+            self.bufs.push_back(buf);
+            self.tail_queued = true;
+        }
+    }
+
+    /// Returns the number of bytes buffered and queued, but not written yet.
+    #[inline]
+    pub fn buffered(&self) -> usize {
+        self.len
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// It is inadvisable to directly write to the underlying writer, which skips the bytes
+    /// buffered.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps this `BufWriter`, returning the underlying writer.
+    ///
+    /// The bytes buffered are lost.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Marks `n` bytes of the buffers as written, dropping the ones written entirely.
+    fn advance(&mut self, mut n: usize) {
+        self.len -= n;
+        while let Some(front) = self.bufs.front() {
+            let left = front.len() - self.written;
+            if n < left {
+                self.written += n;
+                return;
+            }
+            n -= left;
+            self.written = 0;
+            self.bufs.pop_front();
+        }
+        self.tail_queued = false;
+    }
+}
+
+impl<W: AsyncWrite + Unpin> BufWriter<W> {
+    /// Writes out all the buffers, by as few vectored writes as possible.
+    fn poll_flush_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.len > 0 {
+            let mut slices = [IoSlice::new(&[]); MAX_BUFS];
+            let mut count = 0;
+            for (i, buf) in self.bufs.iter().take(MAX_BUFS).enumerate() {
+                let start = if i == 0 { self.written } else { 0 };
+                slices[i] = IoSlice::new(&buf[start..]);
+                count += 1;
+            }
+            let slices = &slices[..count];
+            let n = futures::ready!(
+                Pin::new(&mut self.inner).poll_write_vectored(cx, slices)
+            );
+            match n {
+                Ok(0) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write the buffered data",
+                    )))
+                }
+                Ok(n) => self.advance(n),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for BufWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.len + buf.len() > this.capacity {
+            futures::ready!(this.poll_flush_buf(cx))?;
+        }
+        if buf.len() >= this.capacity {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+        match this.bufs.back_mut() {
+            Some(tail) if !this.tail_queued => tail.extend_from_slice(buf),
+            _ => {
+                let mut tail = Vec::with_capacity(this.capacity);
+                tail.extend_from_slice(buf);
+                this.bufs.push_back(tail);
+                this.tail_queued = false;
+            }
+        }
+        this.len += buf.len();
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    #[inline]
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        futures::ready!(self.poll_flush_buf(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        futures::ready!(self.poll_flush_buf(cx))?;
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl<W: Debug> Debug for BufWriter<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufWriter")
+            .field("writer", &self.inner)
+            .field("buffered", &self.len)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufWriter;
+    use crate::task::block_on;
+    use futures::io::{AsyncWrite, AsyncWriteExt};
+    use std::io::{self, IoSlice};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// A writer of at most `max` bytes per write, recording the number of slices of every
+    /// write.
+    struct Recorder {
+        data: Vec<u8>,
+        writes: Vec<usize>,
+        max: usize,
+    }
+
+    impl AsyncWrite for Recorder {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.poll_write_vectored(cx, &[IoSlice::new(buf)])
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            self.writes.push(bufs.len());
+            let mut n = 0;
+            for buf in bufs {
+                let len = buf.len().min(self.max - n);
+                self.data.extend_from_slice(&buf[..len]);
+                n += len;
+            }
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn recorder(max: usize) -> Recorder {
+        Recorder {
+            data: Vec::new(),
+            writes: Vec::new(),
+            max,
+        }
+    }
+
+    #[test]
+    fn vectored() -> io::Result<()> {
+        block_on(async {
+            let mut writer = BufWriter::with_capacity(16, recorder(usize::MAX));
+            writer.write_all(b"head").await?;
+            writer.write_all(b"er ").await?;
+            writer.queue(b"body ".to_vec());
+            writer.queue(Vec::new());
+            writer.write_all(b"tail").await?;
+            assert_eq!(16, writer.buffered());
+            assert!(writer.get_ref().writes.is_empty());
+            writer.flush().await?;
+            assert_eq!(0, writer.buffered());
+            let inner = writer.into_inner();
+            assert_eq!(b"header body tail", inner.data.as_slice());
+            // one write of the buffer, the queued one and the one after it
+            assert_eq!(vec![3], inner.writes);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn partial() -> io::Result<()> {
+        block_on(async {
+            let mut writer = BufWriter::with_capacity(8, recorder(3));
+            writer.write_all(b"abcde").await?;
+            writer.queue(b"fghij".to_vec());
+            // over the capacity, written directly after the buffers
+            writer.write_all(b"klmnopqrst").await?;
+            writer.close().await?;
+            let inner = writer.into_inner();
+            assert_eq!(b"abcdefghijklmnopqrst", inner.data.as_slice());
+            assert_eq!(vec![2, 2, 1, 1, 1, 1, 1, 1], inner.writes);
+            Ok(())
+        })
+    }
+}
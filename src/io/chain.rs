@@ -0,0 +1,180 @@
+use futures::io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A reader of two readers in turn, created by [`AsyncReadExt::chain`].
+///
+/// The writes go to the second reader, so a socket chained after the bytes read ahead of it
+/// is still a socket.
+///
+/// [`AsyncReadExt::chain`]: trait.AsyncReadExt.html#method.chain
+#[must_use = "readers do nothing unless polled"]
+#[derive(Debug)]
+pub struct Chain<T, U> {
+    first: T,
+    second: U,
+    // whether the first reader has ended
+    done_first: bool,
+}
+
+impl<T, U> Chain<T, U> {
+    #[inline]
+    pub(super) fn new(first: T, second: U) -> Self {
+        Self {
+            first,
+            second,
+            done_first: false,
+        }
+    }
+
+    /// Gets references to the underlying readers.
+    #[inline]
+    pub fn get_ref(&self) -> (&T, &U) {
+        (&self.first, &self.second)
+    }
+
+    /// Gets mutable references to the underlying readers.
+    ///
+    /// It is inadvisable to directly read from the underlying readers.
+    #[inline]
+    pub fn get_mut(&mut self) -> (&mut T, &mut U) {
+        (&mut self.first, &mut self.second)
+    }
+
+    /// Unwraps this `Chain`, returning the underlying readers.
+    #[inline]
+    pub fn into_inner(self) -> (T, U) {
+        (self.first, self.second)
+    }
+}
+
+impl<T, U> AsyncRead for Chain<T, U>
+where
+    T: AsyncRead + Unpin,
+    U: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if !this.done_first {
+            match futures::ready!(Pin::new(&mut this.first).poll_read(cx, buf))? {
+                // an empty buffer tells nothing of the end
+                0 if !buf.is_empty() => this.done_first = true,
+                n => return Poll::Ready(Ok(n)),
+            }
+        }
+        Pin::new(&mut this.second).poll_read(cx, buf)
+    }
+}
+
+impl<T, U> AsyncBufRead for Chain<T, U>
+where
+    T: AsyncBufRead + Unpin,
+    U: AsyncBufRead + Unpin,
+{
+    fn poll_fill_buf(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if !this.done_first {
+            match futures::ready!(Pin::new(&mut this.first).poll_fill_buf(cx))? {
+                [] => this.done_first = true,
+                buf => return Poll::Ready(Ok(buf)),
+            }
+        }
+        Pin::new(&mut this.second).poll_fill_buf(cx)
+    }
+
+    #[inline]
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        if self.done_first {
+            Pin::new(&mut self.second).consume(amt)
+        } else {
+            Pin::new(&mut self.first).consume(amt)
+        }
+    }
+}
+
+impl<T: Unpin, U: AsyncWrite + Unpin> AsyncWrite for Chain<T, U> {
+    #[inline]
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.second).poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.second).poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.second).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.second).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{AsyncReadExt, BufReader};
+    use crate::task::block_on;
+    use futures::io::{AsyncBufReadExt, AsyncWriteExt, Cursor};
+    use std::io;
+
+    #[test]
+    fn preamble() -> io::Result<()> {
+        block_on(async {
+            // peek the first bytes, then put them back
+            let mut reader =
+                BufReader::with_capacity(4, Cursor::new(b"GET / HTTP/1.1".to_vec()));
+            let preamble = reader.fill_buf().await?.to_vec();
+            assert_eq!(b"GET ", preamble.as_slice());
+            let mut socket = reader.into_inner();
+            let mut chain = Cursor::new(preamble).chain(&mut socket);
+            let mut data = Vec::new();
+            futures::AsyncReadExt::read_to_end(&mut chain, &mut data).await?;
+            assert_eq!(b"GET / HTTP/1.1", data.as_slice());
+
+            // the writes go to the second
+            chain.write_all(b"!").await?;
+            assert_eq!(b"GET / HTTP/1.1!", socket.get_ref().as_slice());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn buffered() -> io::Result<()> {
+        block_on(async {
+            let mut chain = Cursor::new(b"hello\nwor").chain(Cursor::new(b"ld\n"));
+            let mut line = String::new();
+            chain.read_line(&mut line).await?;
+            assert_eq!("hello\n", line);
+            line.clear();
+            chain.read_line(&mut line).await?;
+            assert_eq!("world\n", line);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,99 @@
+use super::buf_reader::CAPACITY;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io;
+
+/// Copies the entire contents of a reader into a writer, reporting the progress on the way.
+///
+/// `progress` is called with the number of bytes copied so far each time at least `step` more
+/// bytes are copied, and once more with the total at the end, unless the total is reported
+/// already. A `step` of 0 reports every chunk written.
+///
+/// On success, returns the total number of bytes copied, once the writer is flushed.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::io::Cursor;
+/// use tio::io;
+///
+/// let reader = Cursor::new(vec![0; 20 * 1024]);
+/// let mut writer = Vec::new();
+/// let mut reports = Vec::new();
+/// let n = io::copy_with_progress(reader, &mut writer, 10 * 1024, |n| reports.push(n)).await?;
+/// assert_eq!(20 * 1024, n);
+/// assert_eq!(vec![16 * 1024, 20 * 1024], reports);
+/// #
+/// # Ok(()) }) }
+/// ```
+pub async fn copy_with_progress<R, W, F>(
+    mut reader: R,
+    writer: &mut W,
+    step: u64,
+    mut progress: F,
+) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + ?Sized,
+    F: FnMut(u64),
+{
+    let mut buf = vec![0; CAPACITY];
+    let mut copied = 0;
+    let mut reported = 0;
+    loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        writer.write_all(&buf[..n]).await?;
+        copied += n as u64;
+        if copied - reported >= step {
+            progress(copied);
+            reported = copied;
+        }
+    }
+    writer.flush().await?;
+    if copied != reported {
+        progress(copied);
+    }
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::copy_with_progress;
+    use crate::io::AsyncReadExt;
+    use crate::task::block_on;
+    use futures::io::Cursor;
+    use std::io;
+
+    #[test]
+    fn steps() -> io::Result<()> {
+        block_on(async {
+            // two chunks, of 3 and 5 bytes
+            let reader = Cursor::new(b"abc").chain(Cursor::new(b"defgh"));
+            let mut writer = Vec::new();
+            let mut reports = Vec::new();
+            let n =
+                copy_with_progress(reader, &mut writer, 4, |n| reports.push(n)).await?;
+            assert_eq!(8, n);
+            assert_eq!(b"abcdefgh", writer.as_slice());
+            assert_eq!(vec![8], reports);
+
+            let mut reports = Vec::new();
+            let reader = Cursor::new(b"abc");
+            copy_with_progress(reader, &mut Vec::new(), 0, |n| reports.push(n)).await?;
+            assert_eq!(vec![3], reports);
+
+            // nothing copied, nothing reported
+            let mut reports = Vec::new();
+            let reader = Cursor::new(b"");
+            copy_with_progress(reader, &mut Vec::new(), 0, |n| reports.push(n)).await?;
+            assert!(reports.is_empty());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,273 @@
+use super::buf_reader::CAPACITY;
+use crate::net::util;
+use futures::future;
+use rustix::net::{RecvFlags, SendFlags};
+use std::io;
+use std::os::unix::io::{AsFd, BorrowedFd};
+use std::task::{Context, Poll};
+
+#[cfg(target_os = "linux")]
+use rustix::pipe::{PipeFlags, SpliceFlags};
+
+#[cfg(feature = "tcp")]
+use crate::net::TcpStream;
+
+#[cfg(feature = "uds")]
+use crate::net::UnixStream;
+
+/// The bytes spliced at once, the default capacity of a pipe.
+#[cfg(target_os = "linux")]
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// A socket whose bytes can be moved by [`splice`], like a [`TcpStream`] or a [`UnixStream`].
+///
+/// This trait is sealed, it cannot be implemented outside of tio.
+///
+/// [`splice`]: fn.splice.html
+/// [`TcpStream`]: ../net/struct.TcpStream.html
+/// [`UnixStream`]: ../net/struct.UnixStream.html
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(unix, any(feature = "tcp", feature = "uds"))))
+)]
+pub trait SpliceSocket: AsFd + sealed::Sealed {}
+
+mod sealed {
+    use std::io;
+    use std::os::unix::io::BorrowedFd;
+    use std::task::{Context, Poll};
+
+    pub trait Sealed {
+        /// Polls `f` on the fd until the socket is not blocked reading.
+        fn poll_read_fd(
+            &self,
+            cx: &mut Context<'_>,
+            f: &mut dyn FnMut(BorrowedFd<'_>) -> io::Result<usize>,
+        ) -> Poll<io::Result<usize>>;
+
+        /// Polls `f` on the fd until the socket is not blocked writing.
+        fn poll_write_fd(
+            &self,
+            cx: &mut Context<'_>,
+            f: &mut dyn FnMut(BorrowedFd<'_>) -> io::Result<usize>,
+        ) -> Poll<io::Result<usize>>;
+    }
+}
+
+use sealed::Sealed;
+
+macro_rules! impl_splice_socket {
+    ($($ty:ident: $feature:literal),*) => {$(
+        #[cfg(feature = $feature)]
+        impl SpliceSocket for $ty {}
+
+        #[cfg(feature = $feature)]
+        impl Sealed for $ty {
+            #[inline]
+            fn poll_read_fd(
+                &self,
+                cx: &mut Context<'_>,
+                f: &mut dyn FnMut(BorrowedFd<'_>) -> io::Result<usize>,
+            ) -> Poll<io::Result<usize>> {
+                self.0.poll_read_with(cx, |source| f(util::borrow_fd(source)))
+            }
+
+            #[inline]
+            fn poll_write_fd(
+                &self,
+                cx: &mut Context<'_>,
+                f: &mut dyn FnMut(BorrowedFd<'_>) -> io::Result<usize>,
+            ) -> Poll<io::Result<usize>> {
+                self.0.poll_write_with(cx, |source| f(util::borrow_fd(source)))
+            }
+        }
+    )*};
+}
+
+impl_splice_socket!(TcpStream: "tcp", UnixStream: "uds");
+
+/// Moves at most `len` bytes from one socket to another, until the first one ends, without
+/// copying them to the userspace.
+///
+/// On Linux, the bytes are spliced into a pipe then out of it, staying in the kernel. On the
+/// other platforms, or if the sockets cannot be spliced, they are copied through a buffer
+/// instead. A TCP proxy splices both ways, with a `len` of `u64::MAX`.
+///
+/// On success, returns the number of bytes moved. On errors, the bytes read but not written
+/// yet are lost.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(not(feature = "tcp"))]
+/// # fn main() {}
+/// # #[cfg(feature = "tcp")]
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::future;
+/// use tio::io;
+/// use tio::net::{TcpListener, TcpStream};
+///
+/// let listener = TcpListener::bind("127.0.0.1:8080")?;
+/// loop {
+///     let (client, _) = listener.accept().await?;
+///     let upstream = TcpStream::connect("127.0.0.1:9090").await?;
+///     let (sent, received) = future::try_join(
+///         io::splice(&client, &upstream, u64::MAX),
+///         io::splice(&upstream, &client, u64::MAX),
+///     )
+///     .await?;
+/// }
+/// #
+/// # Ok(()) }) }
+/// ```
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(unix, any(feature = "tcp", feature = "uds"))))
+)]
+pub async fn splice<R, W>(reader: &R, writer: &W, len: u64) -> io::Result<u64>
+where
+    R: SpliceSocket + ?Sized,
+    W: SpliceSocket + ?Sized,
+{
+    #[cfg(target_os = "linux")]
+    match splice_pipe(reader, writer, len).await {
+        Err(err) if err.raw_os_error() == Some(libc::EINVAL) => (),
+        ret => return ret,
+    }
+    copy(reader, writer, len).await
+}
+
+/// Splices the bytes through a pipe, failing of `EINVAL` before moving any byte if the sockets
+/// cannot be spliced.
+#[cfg(target_os = "linux")]
+async fn splice_pipe<R, W>(reader: &R, writer: &W, len: u64) -> io::Result<u64>
+where
+    R: SpliceSocket + ?Sized,
+    W: SpliceSocket + ?Sized,
+{
+    let (pipe_r, pipe_w) =
+        rustix::pipe::pipe_with(PipeFlags::CLOEXEC | PipeFlags::NONBLOCK)?;
+    let flags = SpliceFlags::NONBLOCK | SpliceFlags::MOVE;
+    let mut moved = 0;
+    while moved < len {
+        let max = (len - moved).min(PIPE_CAPACITY as u64) as usize;
+        // the pipe is empty, so it never blocks
+        let mut pending = future::poll_fn(|cx| {
+            reader.poll_read_fd(cx, &mut |fd| {
+                Ok(rustix::pipe::splice(fd, None, &pipe_w, None, max, flags)?)
+            })
+        })
+        .await?;
+        if pending == 0 {
+            break;
+        }
+        while pending > 0 {
+            let n = future::poll_fn(|cx| {
+                writer.poll_write_fd(cx, &mut |fd| {
+                    let flags = flags | SpliceFlags::MORE;
+                    Ok(rustix::pipe::splice(
+                        &pipe_r, None, fd, None, pending, flags,
+                    )?)
+                })
+            })
+            .await?;
+            pending -= n;
+            moved += n as u64;
+        }
+    }
+    Ok(moved)
+}
+
+/// Copies the bytes through a buffer.
+async fn copy<R, W>(reader: &R, writer: &W, len: u64) -> io::Result<u64>
+where
+    R: SpliceSocket + ?Sized,
+    W: SpliceSocket + ?Sized,
+{
+    let mut buf = vec![0; CAPACITY];
+    let mut moved = 0;
+    while moved < len {
+        let max = (len - moved).min(buf.len() as u64) as usize;
+        let n = future::poll_fn(|cx| {
+            reader.poll_read_fd(cx, &mut |fd| {
+                Ok(rustix::net::recv(fd, &mut buf[..max], RecvFlags::empty())?.0)
+            })
+        })
+        .await?;
+        if n == 0 {
+            break;
+        }
+        let mut written = 0;
+        while written < n {
+            written += future::poll_fn(|cx| {
+                writer.poll_write_fd(cx, &mut |fd| {
+                    Ok(rustix::net::send(
+                        fd,
+                        &buf[written..n],
+                        SendFlags::NOSIGNAL,
+                    )?)
+                })
+            })
+            .await?;
+        }
+        moved += n as u64;
+    }
+    Ok(moved)
+}
+
+#[cfg(all(test, feature = "tcp", feature = "uds"))]
+mod tests {
+    use super::{copy, splice};
+    use crate::net::{TcpListener, TcpStream, UnixStream};
+    use crate::task::{self, block_on};
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use std::io;
+
+    /// Returns a connected pair of TCP streams.
+    async fn pair() -> io::Result<(TcpStream, TcpStream)> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let client = TcpStream::connect(listener.local_addr()?).await?;
+        Ok((client, listener.accept().await?.0))
+    }
+
+    #[test]
+    fn proxy() -> io::Result<()> {
+        block_on(async {
+            let (mut client, proxy_in) = pair().await?;
+            let (proxy_out, mut server) = pair().await?;
+            let data = (0..200_000).map(|i| i as u8).collect::<Vec<_>>();
+            let sender = task::spawn({
+                let data = data.clone();
+                async move {
+                    client.write_all(&data).await?;
+                    client.close().await
+                }
+            });
+            assert_eq!(200_000, splice(&proxy_in, &proxy_out, u64::MAX).await?);
+            sender.await.unwrap()?;
+            drop(proxy_out);
+            let mut received = Vec::new();
+            server.read_to_end(&mut received).await?;
+            assert!(data == received);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn len() -> io::Result<()> {
+        block_on(async {
+            let (mut client, proxy_in) = pair().await?;
+            let (mut proxy_out, mut peer) = UnixStream::pair()?;
+            client.write_all(b"hello world").await?;
+            // mixed sockets, and the fallback
+            assert_eq!(5, splice(&proxy_in, &proxy_out, 5).await?);
+            assert_eq!(6, copy(&proxy_in, &proxy_out, 6).await?);
+            proxy_out.close().await?;
+            let mut received = String::new();
+            peer.read_to_string(&mut received).await?;
+            assert_eq!("hello world", received);
+            Ok(())
+        })
+    }
+}
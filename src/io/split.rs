@@ -0,0 +1,180 @@
+use super::BufReader;
+use futures::io::{AsyncBufRead, AsyncRead};
+use futures::Stream;
+use std::io;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A stream over the contents of a reader split on a delimiter, created by
+/// [`BufReader::split`].
+///
+/// The reader is only read when an item is polled, and then no further than the delimiter
+/// of the item and the capacity of its buffer.
+///
+/// [`BufReader::split`]: struct.BufReader.html#method.split
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Split<R> {
+    reader: BufReader<R>,
+    delim: u8,
+    // the bytes of the pending item, read across the polls
+    item: Vec<u8>,
+}
+
+impl<R> Split<R> {
+    #[inline]
+    pub(super) fn new(reader: BufReader<R>, delim: u8) -> Self {
+        Self {
+            reader,
+            delim,
+            item: Vec::new(),
+        }
+    }
+
+    /// Unwraps this stream, returning the underlying reader.
+    ///
+    /// The bytes of a pending item are lost, but the bytes in the buffer are not.
+    #[inline]
+    pub fn into_inner(self) -> BufReader<R> {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for Split<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let delim = this.delim;
+        loop {
+            let available = match Pin::new(&mut this.reader).poll_fill_buf(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {
+                    continue
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(Ok(available)) => available,
+            };
+            // the end of the reader ends the last item, if any
+            if available.is_empty() {
+                if this.item.is_empty() {
+                    return Poll::Ready(None);
+                }
+                return Poll::Ready(Some(Ok(mem::take(&mut this.item))));
+            }
+            match available.iter().position(|&byte| byte == delim) {
+                Some(i) => {
+                    this.item.extend_from_slice(&available[..i]);
+                    Pin::new(&mut this.reader).consume(i + 1);
+                    return Poll::Ready(Some(Ok(mem::take(&mut this.item))));
+                }
+                None => {
+                    let n = available.len();
+                    this.item.extend_from_slice(available);
+                    Pin::new(&mut this.reader).consume(n);
+                }
+            }
+        }
+    }
+}
+
+/// A stream over the lines of a reader, created by [`BufReader::lines`].
+///
+/// The reader is only read when a line is polled, and then no further than the newline of
+/// the line and the capacity of its buffer.
+///
+/// [`BufReader::lines`]: struct.BufReader.html#method.lines
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Lines<R>(Split<R>);
+
+impl<R> Lines<R> {
+    #[inline]
+    pub(super) fn new(reader: BufReader<R>) -> Self {
+        Self(Split::new(reader, b'\n'))
+    }
+
+    /// Unwraps this stream, returning the underlying reader.
+    ///
+    /// The bytes of a pending line are lost, but the bytes in the buffer are not.
+    #[inline]
+    pub fn into_inner(self) -> BufReader<R> {
+        self.0.into_inner()
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut line = match futures::ready!(Pin::new(&mut self.0).poll_next(cx)) {
+            Some(line) => line?,
+            None => return Poll::Ready(None),
+        };
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        let line = String::from_utf8(line)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err));
+        Poll::Ready(Some(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::BufReader;
+    use crate::task::block_on;
+    use futures::io::Cursor;
+    use futures::{StreamExt, TryStreamExt};
+    use std::io;
+
+    #[test]
+    fn split() -> io::Result<()> {
+        block_on(async {
+            // the items span the buffers
+            let reader = BufReader::with_capacity(2, Cursor::new(b"hello,,world,tio"));
+            let items = reader.split(b',').try_collect::<Vec<_>>().await?;
+            let expected: Vec<&[u8]> = vec![b"hello", b"", b"world", b"tio"];
+            assert_eq!(expected, items);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn lines() -> io::Result<()> {
+        block_on(async {
+            let reader =
+                BufReader::with_capacity(3, Cursor::new(b"one\r\ntwo\n\nthree\n"));
+            let mut lines = reader.lines();
+            assert_eq!("one", lines.next().await.unwrap()?);
+            assert_eq!("two", lines.next().await.unwrap()?);
+            assert_eq!("", lines.next().await.unwrap()?);
+
+            // the reader is not read past the line polled
+            let mut reader = lines.into_inner();
+            assert_eq!(b"th", reader.buffer());
+            assert_eq!(12, reader.get_mut().position());
+            let lines = reader.lines().try_collect::<Vec<_>>().await?;
+            assert_eq!(vec!["three"], lines);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        block_on(async {
+            let mut lines = BufReader::new(Cursor::new(b"\xff\nok")).lines();
+            let err = lines.next().await.unwrap().unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert_eq!("ok", lines.next().await.unwrap().unwrap());
+            assert!(lines.next().await.is_none());
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use futures::io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A reader of at most a limit of bytes of another reader, created by
+/// [`AsyncReadExt::take`].
+///
+/// The writes go to the underlying reader, so a socket whose reads are limited, like to the
+/// length of a body, is still a socket.
+///
+/// [`AsyncReadExt::take`]: trait.AsyncReadExt.html#method.take
+#[must_use = "readers do nothing unless polled"]
+#[derive(Debug)]
+pub struct Take<R> {
+    inner: R,
+    limit: u64,
+}
+
+impl<R> Take<R> {
+    #[inline]
+    pub(super) fn new(inner: R, limit: u64) -> Self {
+        Self { inner, limit }
+    }
+
+    /// Returns the number of bytes that can be read before this reader ends.
+    ///
+    /// It is less than the limit once the bytes are read, and stays above 0 if the
+    /// underlying reader ends first.
+    #[inline]
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Sets the number of bytes that can be read before this reader ends, as if it were
+    /// just created.
+    #[inline]
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// It is inadvisable to directly read from the underlying reader.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwraps this `Take`, returning the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Take<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.limit == 0 {
+            return Poll::Ready(Ok(0));
+        }
+        let max = buf.len().min(self.limit.min(usize::MAX as u64) as usize);
+        let n =
+            futures::ready!(Pin::new(&mut self.inner).poll_read(cx, &mut buf[..max]))?;
+        self.limit -= n as u64;
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for Take<R> {
+    fn poll_fill_buf(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.limit == 0 {
+            return Poll::Ready(Ok(&[]));
+        }
+        let buf = futures::ready!(Pin::new(&mut this.inner).poll_fill_buf(cx))?;
+        let max = buf.len().min(this.limit.min(usize::MAX as u64) as usize);
+        Poll::Ready(Ok(&buf[..max]))
+    }
+
+    #[inline]
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        let amt = amt.min(self.limit.min(usize::MAX as u64) as usize);
+        self.limit -= amt as u64;
+        Pin::new(&mut self.inner).consume(amt)
+    }
+}
+
+impl<R: AsyncWrite + Unpin> AsyncWrite for Take<R> {
+    #[inline]
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{AsyncReadExt, BufReader};
+    use crate::task::block_on;
+    use futures::io::{AsyncBufReadExt, Cursor};
+    use std::io;
+
+    #[test]
+    fn limit() -> io::Result<()> {
+        block_on(async {
+            let mut body = Cursor::new(b"hello world").take(5);
+            let mut data = Vec::new();
+            futures::AsyncReadExt::read_to_end(&mut body, &mut data).await?;
+            assert_eq!(b"hello", data.as_slice());
+            assert_eq!(0, body.limit());
+
+            // the rest is left to the next read
+            body.set_limit(16);
+            data.clear();
+            futures::AsyncReadExt::read_to_end(&mut body, &mut data).await?;
+            assert_eq!(b" world", data.as_slice());
+            assert_eq!(10, body.limit());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn buffered() -> io::Result<()> {
+        block_on(async {
+            let mut body = BufReader::new(Cursor::new(b"one\ntwo\n")).take(6);
+            let mut line = String::new();
+            body.read_line(&mut line).await?;
+            assert_eq!("one\n", line);
+            line.clear();
+            body.read_line(&mut line).await?;
+            assert_eq!("tw", line);
+            assert_eq!(b"o\n", body.into_inner().buffer());
+            Ok(())
+        })
+    }
+}
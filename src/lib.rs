@@ -3,6 +3,34 @@
 #![cfg_attr(feature = "docs", warn(missing_docs))]
 #![cfg_attr(not(test), deny(unsafe_code))]
 
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "console")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "console")))]
+pub mod console;
+
 pub mod fs;
+pub mod io;
 pub mod net;
+
+#[cfg(all(unix, feature = "process"))]
+#[cfg_attr(feature = "docs", doc(cfg(all(unix, feature = "process"))))]
+pub mod process;
+
+#[cfg(feature = "timer")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+pub mod retry;
+
+pub mod runtime;
+
+#[cfg(feature = "timer")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+pub mod stream;
+
+pub mod sync;
 pub mod task;
+
+#[cfg(feature = "test-util")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "test-util")))]
+pub mod test;
@@ -0,0 +1,12 @@
+/// Emits a trace event of the runtime, compiled out unless the `trace-log` feature is enabled.
+///
+/// The event is a `log` record of the trace level, whose target names the component. There
+/// are no `tracing` spans or structured fields, the message carries everything.
+macro_rules! trace_event {
+    (target: $target:literal, $($arg:tt)+) => {
+        #[cfg(feature = "trace-log")]
+        {
+            log::trace!(target: $target, $($arg)+)
+        }
+    };
+}
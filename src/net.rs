@@ -8,26 +8,53 @@
 //!
 //! * [`TcpListener`] and [`TcpStream`] provide functionality for communication over TCP
 //! * [`UdpSocket`] provides functionality for communication over UDP
+//! * [`OwnedTcpStream`] and [`OwnedUdpSocket`] are the non-cloneable streams and sockets,
+//!   which save an indirection per operation
+//! * [`Datagrams`] receives and sends the datagrams of a connected socket as a stream and a sink
 //! * [`Resolver`] provides functionality to asynchronously resolve socket address
+//! * [`CachingResolver`] resolves the hosts asynchronously, caching them for their TTLs
+//! * [`AcceptPolicy`] keeps the listeners accepting through transient errors
+//! * [`Server`] serves the connections of a listener, and shuts down gracefully
+//! * [`sd_listen_fds`] takes the sockets passed by systemd to a socket-activated service
+//! * [`inetd_stream`] takes the connection passed by a super-server, like inetd
 //!
 //!
 //! [`TcpListener`]: struct.TcpListener.html
 //! [`TcpStream`]: struct.TcpStream.html
 //! [`UdpSocket`]: struct.UdpSocket.html
+//! [`OwnedTcpStream`]: struct.OwnedTcpStream.html
+//! [`OwnedUdpSocket`]: struct.OwnedUdpSocket.html
+//! [`Datagrams`]: struct.Datagrams.html
 //! [`Resolver`]: trait.Resolver.html
+//! [`CachingResolver`]: struct.CachingResolver.html
+//! [`AcceptPolicy`]: struct.AcceptPolicy.html
+//! [`Server`]: struct.Server.html
+//! [`sd_listen_fds`]: fn.sd_listen_fds.html
+//! [`inetd_stream`]: fn.inetd_stream.html
 //!
 //! # Platform-specific extensions
 //!
 //! APIs such as Unix domain sockets are available on certain platforms only. You can find
-//! platform-specific extensions in the [`uds`] submodule.
+//! platform-specific extensions in the [`uds`] and [`icmp`] submodules, and the VM sockets,
+//! the netlink sockets, the packet sockets, the TUN/TAP devices and the SCTP sockets of
+//! Linux in the [`vsock`], [`netlink`], [`packet`], [`tun`] and [`sctp`] submodules.
 //!
 //! [`uds`]: uds/index.html
+//! [`icmp`]: icmp/index.html
+//! [`vsock`]: vsock/index.html
+//! [`netlink`]: netlink/index.html
+//! [`packet`]: packet/index.html
+//! [`tun`]: tun/index.html
+//! [`sctp`]: sctp/index.html
 //!
 //! # Examples
 //!
 //! A simple UDP echo server:
 //!
 //! ```no_run
+//! # #[cfg(not(feature = "udp"))]
+//! # fn main() {}
+//! # #[cfg(feature = "udp")]
 //! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
 //! #
 //! use tio::net::UdpSocket;
@@ -44,22 +71,93 @@
 //! ```
 
 #[cfg(feature = "event-loop")]
-mod poll;
+#[cfg_attr(
+    not(any(
+        feature = "tcp",
+        feature = "udp",
+        feature = "uds",
+        feature = "vsock",
+        feature = "netlink",
+        feature = "packet",
+        feature = "icmp",
+        feature = "tun",
+        feature = "sctp"
+    )),
+    allow(dead_code)
+)]
+pub(crate) mod poll;
 
-mod util;
+pub(crate) mod util;
 pub use util::Resolver;
 
+mod resolver;
+pub use resolver::{CachingResolver, CachingResolverBuilder};
+
+#[cfg(all(
+    target_os = "linux",
+    any(
+        feature = "netlink",
+        feature = "packet",
+        feature = "tun",
+        feature = "sctp",
+        feature = "tcp",
+        feature = "udp"
+    )
+))]
+mod sys;
+
+#[cfg(all(target_os = "linux", any(feature = "tcp", feature = "udp")))]
+mod zerocopy;
+
+#[cfg(all(target_os = "linux", any(feature = "tcp", feature = "udp")))]
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", any(feature = "tcp", feature = "udp"))))
+)]
+pub use zerocopy::ZeroCopySend;
+
+#[cfg(any(feature = "tcp", all(unix, feature = "uds")))]
+mod accept;
+
+#[cfg(any(feature = "tcp", all(unix, feature = "uds")))]
+#[cfg_attr(feature = "docs", doc(cfg(any(feature = "tcp", feature = "uds"))))]
+pub use accept::AcceptPolicy;
+
+#[cfg(any(feature = "udp", all(unix, feature = "uds")))]
+mod datagrams;
+
+#[cfg(any(feature = "udp", all(unix, feature = "uds")))]
+pub use datagrams::{DatagramSocket, Datagrams};
+
+#[cfg(all(target_os = "linux", feature = "tcp", feature = "uds"))]
+mod activation;
+
+#[cfg(all(target_os = "linux", feature = "tcp", feature = "uds"))]
+pub use activation::{inetd_stream, sd_listen_fds, ListenSocket, StdioSocket};
+
+#[cfg(all(feature = "async-rt", feature = "timer"))]
+mod server;
+
+#[cfg(all(feature = "async-rt", feature = "timer"))]
+pub use server::Server;
+
 #[cfg(feature = "tcp")]
 mod tcp;
 
 #[cfg(feature = "tcp")]
-pub use tcp::{TcpListener, TcpStream};
+pub use tcp::{
+    Incoming, IntoIncoming, MultiListener, OwnedTcpStream, TcpListener,
+    TcpListenerBuilder, TcpStream,
+};
+
+#[cfg(feature = "socks5")]
+pub use tcp::Socks5Target;
 
 #[cfg(feature = "udp")]
 mod udp;
 
 #[cfg(feature = "udp")]
-pub use udp::UdpSocket;
+pub use udp::{OwnedUdpSocket, UdpSocket};
 
 #[cfg(all(unix, feature = "uds"))]
 #[cfg_attr(feature = "docs", doc(cfg(all(unix, feature = "uds"))))]
@@ -68,3 +166,36 @@ pub mod uds;
 #[cfg(all(unix, feature = "uds"))]
 #[doc(no_inline)]
 pub use uds::{UnixDatagram, UnixListener, UnixStream};
+
+#[cfg(all(target_os = "linux", feature = "vsock"))]
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", feature = "vsock")))
+)]
+pub mod vsock;
+
+#[cfg(all(target_os = "linux", feature = "netlink"))]
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", feature = "netlink")))
+)]
+pub mod netlink;
+
+#[cfg(all(target_os = "linux", feature = "packet"))]
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", feature = "packet")))
+)]
+pub mod packet;
+
+#[cfg(all(target_os = "linux", feature = "tun"))]
+#[cfg_attr(feature = "docs", doc(cfg(all(target_os = "linux", feature = "tun"))))]
+pub mod tun;
+
+#[cfg(all(target_os = "linux", feature = "sctp"))]
+#[cfg_attr(feature = "docs", doc(cfg(all(target_os = "linux", feature = "sctp"))))]
+pub mod sctp;
+
+#[cfg(all(unix, feature = "icmp"))]
+#[cfg_attr(feature = "docs", doc(cfg(all(unix, feature = "icmp"))))]
+pub mod icmp;
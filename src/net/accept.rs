@@ -0,0 +1,305 @@
+use crate::runtime::time::Delay;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The minimum backoff by default.
+const MIN_BACKOFF: Duration = Duration::from_millis(5);
+
+/// The maximum backoff by default.
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+/// A callback called with the errors retried.
+type OnError = Arc<dyn Fn(&io::Error) + Send + Sync>;
+
+/// A policy for the transient errors of accepting connections.
+///
+/// A listener fails to accept connections when the process runs out of file descriptors or
+/// memory, or when a connection is aborted before it is accepted. These errors pass
+/// eventually, but a server which gives up on them stops serving, and one which retries at once
+/// spins while the resources are exhausted.
+///
+/// With a policy, a listener retries on them instead of returning them:
+///
+/// * a connection which fails, like `ECONNABORTED`, is skipped at once;
+/// * an exhaustion of resources, like `EMFILE` or `ENFILE`, makes the listener sleep before it
+///   retries, with a backoff doubling from 5ms to 1s by default, and reset once a connection
+///   is accepted.
+///
+/// Other errors are returned, the same as without a policy. See [`is_transient`] for the
+/// errors retried, and [`TcpListener::set_accept_policy`] for setting the policy.
+///
+/// [`is_transient`]: #method.is_transient
+/// [`TcpListener::set_accept_policy`]: struct.TcpListener.html#method.set_accept_policy
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::prelude::*;
+/// use tio::net::{AcceptPolicy, TcpListener};
+///
+/// let mut listener = TcpListener::bind("127.0.0.1:8080")?;
+/// let policy = AcceptPolicy::new().on_error(|err| eprintln!("accept error: {}", err));
+/// listener.set_accept_policy(Some(policy));
+///
+/// while let Some(stream) = listener.next().await {
+///     let mut stream = stream?;
+///     stream.write_all(b"hello world").await?;
+/// }
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Clone)]
+pub struct AcceptPolicy {
+    min_backoff: Duration,
+    max_backoff: Duration,
+    on_error: Option<OnError>,
+}
+
+/// The kind of an error of accepting a connection.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ErrorKind {
+    /// The connection failed, not the listener.
+    Connection,
+    /// The process or the system is out of resources.
+    Resource,
+    /// The listener failed.
+    Fatal,
+}
+
+impl ErrorKind {
+    fn of(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset => {
+                return ErrorKind::Connection
+            }
+            io::ErrorKind::OutOfMemory => return ErrorKind::Resource,
+            _ => (),
+        }
+        #[cfg(unix)]
+        {
+            if let Some(libc::EMFILE) | Some(libc::ENFILE) | Some(libc::ENOBUFS) =
+                err.raw_os_error()
+            {
+                return ErrorKind::Resource;
+            }
+        }
+        ErrorKind::Fatal
+    }
+}
+
+impl AcceptPolicy {
+    /// Creates a policy with the default backoff, and no callback.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            min_backoff: MIN_BACKOFF,
+            max_backoff: MAX_BACKOFF,
+            on_error: None,
+        }
+    }
+
+    /// Sets the backoff of sleeping on an exhaustion of resources, doubling from `min` to
+    /// `max`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `min` is zero or greater than `max`.
+    #[inline]
+    pub fn backoff(mut self, min: Duration, max: Duration) -> Self {
+        assert!(
+            min > Duration::from_secs(0) && min <= max,
+            "the backoff must be positive and at most {:?}",
+            max
+        );
+        self.min_backoff = min;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets a callback called with each error retried, to log errors for example.
+    #[inline]
+    pub fn on_error<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&io::Error) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(f));
+        self
+    }
+
+    /// Returns `true` if `err` is retried by the policies.
+    #[inline]
+    pub fn is_transient(err: &io::Error) -> bool {
+        ErrorKind::of(err) != ErrorKind::Fatal
+    }
+}
+
+impl Default for AcceptPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for AcceptPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AcceptPolicy")
+            .field("min_backoff", &self.min_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("on_error", &self.on_error.is_some())
+            .finish()
+    }
+}
+
+/// The state of an accepting task under a policy.
+///
+/// It is kept by each caller, as a delay only wakes the task which polled it last.
+#[derive(Default)]
+pub(crate) struct Retry {
+    delay: Option<Delay>,
+    backoff: Option<Duration>,
+}
+
+impl Retry {
+    /// Polls `accept` until it accepts a connection or fails for good under `policy`.
+    pub(crate) fn poll<T, F>(
+        &mut self,
+        policy: Option<&AcceptPolicy>,
+        cx: &mut Context<'_>,
+        mut accept: F,
+    ) -> Poll<io::Result<T>>
+    where
+        F: FnMut(&mut Context<'_>) -> Poll<io::Result<T>>,
+    {
+        let policy = match policy {
+            Some(policy) => policy,
+            None => return accept(cx),
+        };
+        loop {
+            if let Some(delay) = &mut self.delay {
+                futures::ready!(Pin::new(delay).poll(cx));
+                self.delay = None;
+            }
+            let err = match futures::ready!(accept(cx)) {
+                Ok(accepted) => {
+                    self.backoff = None;
+                    return Poll::Ready(Ok(accepted));
+                }
+                Err(err) => err,
+            };
+            let kind = ErrorKind::of(&err);
+            if kind == ErrorKind::Fatal {
+                return Poll::Ready(Err(err));
+            }
+            if let Some(on_error) = &policy.on_error {
+                on_error(&err);
+            }
+            if kind == ErrorKind::Resource {
+                let backoff = match self.backoff {
+                    Some(backoff) => (backoff * 2).min(policy.max_backoff),
+                    None => policy.min_backoff,
+                };
+                self.backoff = Some(backoff);
+                self.delay = Some(Delay::new(backoff));
+            }
+        }
+    }
+}
+
+impl Clone for Retry {
+    /// A clone starts afresh, as the state belongs to a single caller.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Debug for Retry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Retry")
+            .field("sleeping", &self.delay.is_some())
+            .field("backoff", &self.backoff)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AcceptPolicy, Retry};
+    use crate::task;
+    use futures::future::poll_fn;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Poll;
+    use std::time::{Duration, Instant};
+
+    fn emfile() -> io::Error {
+        io::Error::from_raw_os_error(libc::EMFILE)
+    }
+
+    #[test]
+    fn transient() {
+        assert!(AcceptPolicy::is_transient(&emfile()));
+        assert!(AcceptPolicy::is_transient(
+            &io::ErrorKind::ConnectionAborted.into()
+        ));
+        assert!(!AcceptPolicy::is_transient(
+            &io::ErrorKind::InvalidInput.into()
+        ));
+    }
+
+    #[test]
+    fn backoff() {
+        task::block_on(async {
+            let errors = Arc::new(AtomicUsize::new(0));
+            let policy = AcceptPolicy::new()
+                .backoff(Duration::from_millis(10), Duration::from_millis(20))
+                .on_error({
+                    let errors = errors.clone();
+                    move |_| {
+                        errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+            // three exhaustions and an aborted connection before the connection
+            let mut results = vec![
+                Ok(()),
+                Err(io::ErrorKind::ConnectionAborted.into()),
+                Err(emfile()),
+                Err(emfile()),
+                Err(emfile()),
+            ];
+            let mut retry = Retry::default();
+            let start = Instant::now();
+            poll_fn(|cx| {
+                retry.poll(Some(&policy), cx, |_| Poll::Ready(results.pop().unwrap()))
+            })
+            .await
+            .unwrap();
+            assert_eq!(4, errors.load(Ordering::Relaxed));
+            // 10ms, 20ms and 20ms
+            assert!(start.elapsed() >= Duration::from_millis(50));
+        })
+    }
+
+    #[test]
+    fn fatal() {
+        task::block_on(async {
+            let mut retry = Retry::default();
+            let err = poll_fn(|cx| {
+                retry.poll::<(), _>(Some(&AcceptPolicy::new()), cx, |_| {
+                    Poll::Ready(Err(io::ErrorKind::InvalidInput.into()))
+                })
+            })
+            .await
+            .unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        })
+    }
+}
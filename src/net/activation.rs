@@ -0,0 +1,388 @@
+use super::uds::{UnixDatagram, UnixListener, UnixStream};
+use super::{TcpListener, TcpStream};
+use rustix::io::{Errno, FdFlags};
+use rustix::net::{sockopt, AddressFamily, SocketType};
+use std::env;
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The first fd passed by the service manager.
+const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// The name of the fds whose names are not passed.
+const UNKNOWN: &str = "unknown";
+
+/// Whether the fds passed are taken already.
+static CONSUMED: AtomicBool = AtomicBool::new(false);
+
+/// A socket passed by systemd, returned by [`sd_listen_fds`].
+///
+/// [`sd_listen_fds`]: fn.sd_listen_fds.html
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", feature = "tcp", feature = "uds")))
+)]
+#[derive(Debug)]
+pub enum ListenSocket {
+    /// A listening TCP socket, of `ListenStream=` an IP address or a port.
+    Tcp(TcpListener),
+    /// A listening Unix stream socket, of `ListenStream=` a path.
+    Unix(UnixListener),
+    /// A Unix datagram socket, of `ListenDatagram=` a path.
+    UnixDatagram(UnixDatagram),
+}
+
+/// A connection passed as the standard input, returned by [`inetd_stream`].
+///
+/// [`inetd_stream`]: fn.inetd_stream.html
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", feature = "tcp", feature = "uds")))
+)]
+#[derive(Debug)]
+pub enum StdioSocket {
+    /// A TCP connection.
+    Tcp(TcpStream),
+    /// A Unix stream connection.
+    Unix(UnixStream),
+}
+
+/// Takes the sockets passed by systemd to a socket-activated service, with their names.
+///
+/// The service manager opens the sockets of a `.socket` unit and passes them to the service
+/// it starts, which can be restarted without closing them, so no connection is refused in
+/// the meantime. The sockets are registered to the reactor, and named by the
+/// `FileDescriptorName=` of the unit, or "unknown".
+///
+/// The `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` environment variables are removed,
+/// so the processes spawned do not take the sockets again. Nothing is returned if the
+/// sockets are taken already, or if they are not passed to this process.
+///
+/// # Errors
+///
+/// This function fails of `InvalidData` if the variables are malformed, or a socket is none
+/// of the [`ListenSocket`]s, in which case all the sockets are closed.
+///
+/// [`ListenSocket`]: enum.ListenSocket.html
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use tio::net::{sd_listen_fds, ListenSocket, TcpListener};
+///
+/// let listener = match sd_listen_fds()?.pop() {
+///     Some((_, ListenSocket::Tcp(listener))) => listener,
+///     // not socket-activated
+///     _ => TcpListener::bind("127.0.0.1:8080")?,
+/// };
+/// #
+/// # Ok(()) }) }
+/// ```
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", feature = "tcp", feature = "uds")))
+)]
+pub fn sd_listen_fds() -> io::Result<Vec<(String, ListenSocket)>> {
+    if CONSUMED.swap(true, Ordering::SeqCst) {
+        return Ok(Vec::new());
+    }
+    let pid = env::var("LISTEN_PID").ok();
+    let fds = env::var("LISTEN_FDS").ok();
+    let names = env::var("LISTEN_FDNAMES").ok();
+    for var in &["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        env::remove_var(var);
+    }
+    match (pid, fds) {
+        (Some(pid), Some(fds)) if pid.parse() == Ok(std::process::id()) => {
+            listen_fds(SD_LISTEN_FDS_START, &fds, names.as_deref())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Returns the connection of a service run by a super-server, passed as the standard input.
+///
+/// inetd, and systemd with `Accept=yes` and `StandardInput=socket`, accept the connections
+/// themselves and run a process for each one, with the connection as its standard input and
+/// output. The stream returned is registered to the reactor, on a duplicate of the standard
+/// input, which is switched to the non-blocking mode.
+///
+/// Only the stream should be used to communicate, the standard output being the same socket,
+/// the bytes written to it are interleaved with the ones of the stream.
+///
+/// # Errors
+///
+/// This function fails of `InvalidData` if the standard input is not a connected TCP or Unix
+/// stream socket, like when the process is run from a terminal.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::prelude::*;
+/// use tio::net::{inetd_stream, StdioSocket};
+///
+/// match inetd_stream()? {
+///     StdioSocket::Tcp(mut stream) => {
+///         let peer = stream.peer_addr()?;
+///         stream.write_all(format!("hello {}\n", peer).as_bytes()).await?;
+///     }
+///     StdioSocket::Unix(mut stream) => stream.write_all(b"hello\n").await?,
+/// }
+/// #
+/// # Ok(()) }) }
+/// ```
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", feature = "tcp", feature = "uds")))
+)]
+pub fn inetd_stream() -> io::Result<StdioSocket> {
+    stdio_socket(io::stdin().as_fd())
+}
+
+/// Converts a connected socket into the stream of its type, on a duplicate of it.
+fn stdio_socket(fd: BorrowedFd<'_>) -> io::Result<StdioSocket> {
+    let raw = fd.as_raw_fd();
+    let ty = match sockopt::socket_type(fd) {
+        Err(Errno::NOTSOCK) => {
+            return Err(invalid_data(format!("fd {} is not a socket", raw)))
+        }
+        ret => ret?,
+    };
+    if ty != SocketType::STREAM || sockopt::socket_acceptconn(fd)? {
+        return Err(invalid_data(format!("fd {} is not a stream socket", raw)));
+    }
+    if let Err(err) = rustix::net::getpeername(fd) {
+        return Err(invalid_data(format!(
+            "fd {} is not connected: {}",
+            raw, err
+        )));
+    }
+    let socket = match sockopt::socket_domain(fd)? {
+        AddressFamily::INET | AddressFamily::INET6 => {
+            let stream = std::net::TcpStream::from(fd.try_clone_to_owned()?);
+            stream.set_nonblocking(true)?;
+            StdioSocket::Tcp(stream.into())
+        }
+        AddressFamily::UNIX => {
+            let stream = std::os::unix::net::UnixStream::from(fd.try_clone_to_owned()?);
+            stream.set_nonblocking(true)?;
+            StdioSocket::Unix(stream.into())
+        }
+        _ => {
+            return Err(invalid_data(format!(
+                "fd {} is neither a TCP nor a Unix socket",
+                raw
+            )))
+        }
+    };
+    Ok(socket)
+}
+
+/// Takes the `fds` sockets from `start`, named by `names` separated by colons.
+fn listen_fds(
+    start: RawFd,
+    fds: &str,
+    names: Option<&str>,
+) -> io::Result<Vec<(String, ListenSocket)>> {
+    let n = fds
+        .parse::<u16>()
+        .map_err(|_| invalid_data(format!("invalid LISTEN_FDS {:?}", fds)))?;
+    // owned at once, so they are all closed on errors
+    let owned = (start..start + RawFd::from(n))
+        .map(take_fd)
+        .collect::<Vec<_>>();
+    let names = match names {
+        Some(names) if names.split(':').count() == owned.len() => {
+            names.split(':').map(String::from).collect()
+        }
+        Some(names) => {
+            return Err(invalid_data(format!(
+                "LISTEN_FDNAMES {:?} does not name {} fds",
+                names, n
+            )))
+        }
+        None => vec![UNKNOWN.to_string(); owned.len()],
+    };
+    let mut sockets = Vec::with_capacity(owned.len());
+    for fd in owned {
+        rustix::io::fcntl_setfd(&fd, FdFlags::CLOEXEC)?;
+        sockets.push(classify(fd)?);
+    }
+    Ok(names.into_iter().zip(sockets).collect())
+}
+
+/// Converts a socket passed into the listener of its type.
+fn classify(fd: OwnedFd) -> io::Result<ListenSocket> {
+    let raw = fd.as_raw_fd();
+    let ty = match sockopt::socket_type(&fd) {
+        Err(Errno::NOTSOCK) => {
+            return Err(invalid_data(format!("fd {} is not a socket", raw)))
+        }
+        ret => ret?,
+    };
+    let family = sockopt::socket_domain(&fd)?;
+    let listening = sockopt::socket_acceptconn(&fd)?;
+    let socket = match (family, ty, listening) {
+        (AddressFamily::INET, SocketType::STREAM, true)
+        | (AddressFamily::INET6, SocketType::STREAM, true) => {
+            let listener = std::net::TcpListener::from(fd);
+            listener.set_nonblocking(true)?;
+            ListenSocket::Tcp(listener.into())
+        }
+        (AddressFamily::UNIX, SocketType::STREAM, true) => {
+            let listener = std::os::unix::net::UnixListener::from(fd);
+            listener.set_nonblocking(true)?;
+            ListenSocket::Unix(listener.into())
+        }
+        (AddressFamily::UNIX, SocketType::DGRAM, _) => {
+            let datagram = std::os::unix::net::UnixDatagram::from(fd);
+            datagram.set_nonblocking(true)?;
+            ListenSocket::UnixDatagram(datagram.into())
+        }
+        _ => {
+            return Err(invalid_data(format!(
+                "fd {} is none of the sockets supported",
+                raw
+            )))
+        }
+    };
+    Ok(socket)
+}
+
+/// Takes the ownership of an fd passed by the service manager.
+#[allow(unsafe_code)]
+#[inline]
+fn take_fd(fd: RawFd) -> OwnedFd {
+    // SAFETY: the fds passed are open and owned by nothing else in this process, and they
+    // are taken once, guarded by `CONSUMED` and the environment variables removed.
+    unsafe { OwnedFd::from_raw_fd(fd) }
+}
+
+#[inline]
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{listen_fds, stdio_socket, ListenSocket, StdioSocket};
+    use crate::net::uds::{UnixDatagram, UnixListener, UnixStream};
+    use crate::net::{TcpListener, TcpStream};
+    use crate::task::block_on;
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use std::io::{self, Read};
+    use std::os::unix::io::{AsFd, AsRawFd, RawFd};
+
+    /// Duplicates the fds from `start`, as the service manager passes them.
+    fn pass(start: RawFd, fds: &[RawFd]) {
+        for (i, &fd) in fds.iter().enumerate() {
+            assert_eq!(start + i as RawFd, unsafe {
+                libc::dup2(fd, start + i as RawFd)
+            });
+        }
+    }
+
+    #[test]
+    fn sockets() -> io::Result<()> {
+        block_on(async {
+            let dir = tempfile::tempdir()?;
+            let tcp = TcpListener::bind("127.0.0.1:0")?;
+            let unix = UnixListener::bind(dir.path().join("stream"))?;
+            let datagram = UnixDatagram::bind(dir.path().join("datagram"))?;
+            pass(
+                600,
+                &[tcp.as_raw_fd(), unix.as_raw_fd(), datagram.as_raw_fd()],
+            );
+
+            let mut sockets = listen_fds(600, "3", Some("http:control:log"))?;
+            let names = sockets
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(vec!["http", "control", "log"], names);
+            match sockets.remove(0).1 {
+                ListenSocket::Tcp(listener) => {
+                    let addr = tcp.local_addr()?;
+                    assert_eq!(addr, listener.local_addr()?);
+                    let _client = TcpStream::connect(addr).await?;
+                    listener.accept().await?;
+                }
+                socket => panic!("unexpected {:?}", socket),
+            }
+            match sockets.remove(0).1 {
+                ListenSocket::Unix(listener) => {
+                    let _client = UnixStream::connect(dir.path().join("stream")).await?;
+                    listener.accept().await?;
+                }
+                socket => panic!("unexpected {:?}", socket),
+            }
+            assert!(matches!(sockets.remove(0).1, ListenSocket::UnixDatagram(_)));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn invalid() -> io::Result<()> {
+        let (reader, _writer) = std::os::unix::net::UnixStream::pair()?;
+        let file = tempfile::tempfile()?;
+        pass(610, &[reader.as_raw_fd(), file.as_raw_fd()]);
+
+        // connected rather than listening
+        let err = listen_fds(610, "1", None).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        let err = listen_fds(611, "1", None).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().contains("not a socket"));
+
+        let err = listen_fds(612, "x", None).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert!(listen_fds(612, "0", None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn stdio() -> io::Result<()> {
+        block_on(async {
+            let (mut client, server) = std::os::unix::net::UnixStream::pair()?;
+            match stdio_socket(server.as_fd())? {
+                StdioSocket::Unix(mut stream) => {
+                    stream.write_all(b"ping").await?;
+                    let mut buf = [0; 4];
+                    client.read_exact(&mut buf)?;
+                    assert_eq!(b"ping", &buf);
+                }
+                socket => panic!("unexpected {:?}", socket),
+            }
+
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?;
+            let mut client = TcpStream::connect(addr).await?;
+            let (server, _) = listener.accept().await?;
+            match stdio_socket(server.as_fd())? {
+                StdioSocket::Tcp(mut stream) => {
+                    client.write_all(b"pong").await?;
+                    let mut buf = [0; 4];
+                    stream.read_exact(&mut buf).await?;
+                    assert_eq!(b"pong", &buf);
+                }
+                socket => panic!("unexpected {:?}", socket),
+            }
+
+            // listening, unconnected, or no socket
+            let err = stdio_socket(listener.as_fd()).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            let socket = std::net::UdpSocket::bind("127.0.0.1:0")?;
+            let err = stdio_socket(socket.as_fd()).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            let file = tempfile::tempfile()?;
+            let err = stdio_socket(file.as_fd()).unwrap_err();
+            assert!(err.to_string().contains("not a socket"));
+            Ok(())
+        })
+    }
+}
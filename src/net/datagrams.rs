@@ -0,0 +1,249 @@
+use futures::{Sink, Stream};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[cfg(feature = "udp")]
+use crate::net::UdpSocket;
+
+#[cfg(all(unix, feature = "uds"))]
+use crate::net::UnixDatagram;
+
+/// The default max length of the datagrams received, enough for any UDP datagram.
+const MAX_LEN: usize = 64 * 1024;
+
+/// A connected datagram socket, like a [`UdpSocket`] or a [`UnixDatagram`], whose datagrams
+/// can be received and sent by [`Datagrams`].
+///
+/// This trait is sealed, it cannot be implemented outside of tio.
+///
+/// [`UdpSocket`]: struct.UdpSocket.html
+/// [`UnixDatagram`]: struct.UnixDatagram.html
+/// [`Datagrams`]: struct.Datagrams.html
+#[cfg_attr(feature = "docs", doc(cfg(any(feature = "udp", feature = "uds"))))]
+pub trait DatagramSocket: sealed::Sealed {}
+
+mod sealed {
+    use std::io;
+    use std::task::{Context, Poll};
+
+    pub trait Sealed {
+        /// Polls to receive a datagram from the peer.
+        fn poll_recv(
+            &self,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>>;
+
+        /// Polls to send a datagram to the peer.
+        fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8])
+            -> Poll<io::Result<usize>>;
+    }
+}
+
+use sealed::Sealed;
+
+macro_rules! impl_datagram_socket {
+    ($($ty:ident: $cfg:meta),*) => {$(
+        #[cfg($cfg)]
+        impl DatagramSocket for $ty {}
+
+        #[cfg($cfg)]
+        impl Sealed for $ty {
+            #[inline]
+            fn poll_recv(
+                &self,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<io::Result<usize>> {
+                self.0.poll_read_with(cx, |inner| inner.recv(buf))
+            }
+
+            #[inline]
+            fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+                self.0.poll_write_with(cx, |inner| inner.send(buf))
+            }
+        }
+    )*};
+}
+
+impl_datagram_socket!(
+    UdpSocket: feature = "udp",
+    UnixDatagram: all(unix, feature = "uds")
+);
+
+/// A [`Stream`] and a [`Sink`] of the datagrams of a connected socket.
+///
+/// The stream yields every datagram received from the peer, truncated to the max length, and
+/// never ends; the errors are yielded as they come, the following datagrams can still be
+/// received. The sink sends every buffer as one datagram to the peer, on the next flush or
+/// the next send.
+///
+/// The socket must be [connected] before, it fails of `ENOTCONN` otherwise. It can be cloned
+/// before being wrapped, to receive and send the datagrams in different tasks.
+///
+/// [`Stream`]: https://docs.rs/futures/0.3/futures/stream/trait.Stream.html
+/// [`Sink`]: https://docs.rs/futures/0.3/futures/sink/trait.Sink.html
+/// [connected]: struct.UdpSocket.html#method.connect
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(not(feature = "udp"))]
+/// # fn main() {}
+/// # #[cfg(feature = "udp")]
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::{SinkExt, StreamExt};
+/// use tio::net::{Datagrams, UdpSocket};
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// socket.connect("127.0.0.1:8080")?;
+/// let (sink, stream) = Datagrams::new(socket).split();
+///
+/// // echoes the datagrams back to the peer
+/// stream.forward(sink).await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[cfg_attr(feature = "docs", doc(cfg(any(feature = "udp", feature = "uds"))))]
+#[derive(Debug)]
+pub struct Datagrams<S> {
+    socket: S,
+    buf: Vec<u8>,
+    pending: Option<Vec<u8>>,
+}
+
+impl<S: DatagramSocket> Datagrams<S> {
+    /// Wraps a connected socket, receiving the datagrams of at most 64 KiB.
+    #[inline]
+    pub fn new(socket: S) -> Self {
+        Self::with_max_len(MAX_LEN, socket)
+    }
+
+    /// Wraps a connected socket, receiving the datagrams of at most `max_len` bytes.
+    ///
+    /// The bytes of the longer datagrams are discarded.
+    #[inline]
+    pub fn with_max_len(max_len: usize, socket: S) -> Self {
+        Self {
+            socket,
+            buf: vec![0; max_len],
+            pending: None,
+        }
+    }
+
+    /// Gets a reference to the underlying socket.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.socket
+    }
+
+    /// Unwraps this `Datagrams`, returning the underlying socket.
+    ///
+    /// The datagram not sent yet is lost.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.socket
+    }
+}
+
+impl<S: DatagramSocket + Unpin> Stream for Datagrams<S> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let ret = futures::ready!(this.socket.poll_recv(cx, &mut this.buf));
+        Poll::Ready(Some(ret.map(|n| this.buf[..n].to_vec())))
+    }
+}
+
+impl<S: DatagramSocket + Unpin> Sink<Vec<u8>> for Datagrams<S> {
+    type Error = io::Error;
+
+    #[inline]
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+
+    #[inline]
+    fn start_send(mut self: Pin<&mut Self>, item: Vec<u8>) -> io::Result<()> {
+        self.pending = Some(item);
+        Ok(())
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if let Some(datagram) = &this.pending {
+            let ret = futures::ready!(this.socket.poll_send(cx, datagram));
+            this.pending = None;
+            ret?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Datagrams;
+    use crate::task::block_on;
+    use futures::{SinkExt, StreamExt};
+    use std::io;
+
+    #[cfg(feature = "udp")]
+    #[test]
+    fn udp() -> io::Result<()> {
+        use crate::net::UdpSocket;
+
+        block_on(async {
+            let a = UdpSocket::bind("127.0.0.1:0")?;
+            let b = UdpSocket::bind("127.0.0.1:0")?;
+            a.connect(b.local_addr()?)?;
+            b.connect(a.local_addr()?)?;
+            let mut a = Datagrams::new(a);
+            let mut b = Datagrams::with_max_len(4, b);
+            a.send(b"ping".to_vec()).await?;
+            a.send(b"truncated".to_vec()).await?;
+            assert_eq!(b"ping", b.next().await.unwrap()?.as_slice());
+            assert_eq!(b"trun", b.next().await.unwrap()?.as_slice());
+            b.send(b"pong".to_vec()).await?;
+            assert_eq!(b"pong", a.next().await.unwrap()?.as_slice());
+            Ok(())
+        })
+    }
+
+    #[cfg(all(unix, feature = "uds"))]
+    #[test]
+    fn uds() -> io::Result<()> {
+        use crate::net::UnixDatagram;
+        use futures::stream;
+
+        block_on(async {
+            let (a, b) = UnixDatagram::pair()?;
+            let (sink, stream) = Datagrams::new(a).split();
+            let mut b = Datagrams::new(b);
+            b.send_all(&mut stream::iter(vec![
+                Ok(b"hello".to_vec()),
+                Ok(Vec::new()),
+            ]))
+            .await?;
+            // echoed back by a forwarding pipeline
+            let echo = crate::task::spawn(stream.take(2).forward(sink));
+            assert_eq!(b"hello", b.next().await.unwrap()?.as_slice());
+            assert!(b.next().await.unwrap()?.is_empty());
+            echo.await.unwrap()?;
+            Ok(())
+        })
+    }
+}
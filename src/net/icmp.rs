@@ -0,0 +1,455 @@
+//! ICMP sockets for pinging hosts.
+//!
+//! An [`IcmpSocket`] sends ICMP echo requests and receives the replies, like `ping`. It is an
+//! unprivileged ping socket (`SOCK_DGRAM`) where the system permits one, like Linux when the
+//! group of the process is in `net.ipv4.ping_group_range`, and falls back to a raw socket,
+//! which takes `CAP_NET_RAW`, otherwise. Either way the socket sends and receives the ICMP
+//! messages without the IP headers.
+//!
+//! [`IcmpSocket`]: struct.IcmpSocket.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use std::time::Instant;
+//!
+//! use tio::net::icmp::IcmpSocket;
+//!
+//! let socket = IcmpSocket::new_v4()?;
+//! let start = Instant::now();
+//! socket.send_echo("127.0.0.1".parse().unwrap(), 1, b"ping").await?;
+//!
+//! let mut buf = [0u8; 64];
+//! let reply = socket.recv_echo(&mut buf).await?;
+//! println!("reply {} from {} in {:?}", reply.seq(), reply.addr(), start.elapsed());
+//! #
+//! # Ok(()) }) }
+//! ```
+
+use crate::net::poll::{Fd, Watcher};
+use crate::net::util;
+use futures::future;
+use rustix::net::{
+    ipproto, AddressFamily, RecvFlags, SendFlags, SocketFlags, SocketType,
+};
+use std::convert::TryFrom;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The length of the header of an echo message.
+const ECHO_HEADER_LEN: usize = 8;
+
+/// The maximum length of an IPv4 header, which a raw socket receives.
+const MAX_IPV4_HEADER_LEN: usize = 60;
+
+/// The identifiers of the raw sockets of this process, distinct from each other.
+static RAW_IDENT: AtomicU16 = AtomicU16::new(0);
+
+/// An ICMP socket of IPv4 or IPv6.
+///
+/// See the [module] for the kinds of sockets.
+///
+/// [module]: index.html
+#[derive(Debug, Clone)]
+pub struct IcmpSocket {
+    watcher: Arc<Watcher<Fd>>,
+    v6: bool,
+    raw: bool,
+    ident: u16,
+}
+
+/// An echo reply received by [`IcmpSocket::recv_echo`].
+///
+/// [`IcmpSocket::recv_echo`]: struct.IcmpSocket.html#method.recv_echo
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct EchoReply {
+    addr: IpAddr,
+    seq: u16,
+    len: usize,
+}
+
+impl EchoReply {
+    /// Returns the address which replies.
+    #[inline]
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the sequence number of the request replied.
+    #[inline]
+    pub fn seq(&self) -> u16 {
+        self.seq
+    }
+
+    /// Returns the length of the payload, which is at the start of the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the payload is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl IcmpSocket {
+    /// Creates an ICMP socket of IPv4.
+    ///
+    /// It must be called within a runtime.
+    #[inline]
+    pub fn new_v4() -> io::Result<Self> {
+        Self::new(false)
+    }
+
+    /// Creates an ICMP socket of IPv6.
+    ///
+    /// It must be called within a runtime.
+    #[inline]
+    pub fn new_v6() -> io::Result<Self> {
+        Self::new(true)
+    }
+
+    fn new(v6: bool) -> io::Result<Self> {
+        let (family, protocol, unspecified) = if v6 {
+            let addr = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+            (AddressFamily::INET6, ipproto::ICMPV6, addr)
+        } else {
+            let addr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+            (AddressFamily::INET, ipproto::ICMP, addr)
+        };
+        let flags = SocketFlags::NONBLOCK | SocketFlags::CLOEXEC;
+        let (fd, raw, ident) = match open(family, SocketType::DGRAM, protocol, flags) {
+            Ok(fd) => {
+                // the kernel takes the port as the identifier of the echo requests
+                rustix::net::bind(&fd, &SocketAddr::new(unspecified, 0))?;
+                let port = SocketAddr::try_from(rustix::net::getsockname(&fd)?)?.port();
+                (fd, false, port)
+            }
+            Err(err) if is_denied(&err) => {
+                let fd = open(family, SocketType::RAW, protocol, flags)?;
+                let pid = std::process::id() as u16;
+                let ident = pid.wrapping_add(RAW_IDENT.fetch_add(1, Ordering::Relaxed));
+                (fd, true, ident)
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            watcher: Arc::new(Watcher::new(Fd(fd))),
+            v6,
+            raw,
+            ident,
+        })
+    }
+
+    /// Returns `true` if this is a raw socket rather than a ping socket.
+    #[inline]
+    pub fn is_raw(&self) -> bool {
+        self.raw
+    }
+
+    /// Returns the identifier of the echo requests of this socket.
+    #[inline]
+    pub fn ident(&self) -> u16 {
+        self.ident
+    }
+
+    /// Sends an ICMP message to the given address.
+    ///
+    /// The checksum of the message is filled in, except for a raw socket of IPv4, where it is
+    /// left as it is. On success, returns the number of bytes written.
+    pub async fn send_to(&self, msg: &[u8], addr: IpAddr) -> io::Result<usize> {
+        let addr = SocketAddr::new(addr, 0);
+        future::poll_fn(|cx| {
+            self.watcher.poll_write_with(cx, |fd| {
+                Ok(rustix::net::sendto(fd, msg, SendFlags::empty(), &addr)?)
+            })
+        })
+        .await
+    }
+
+    /// Receives an ICMP message from the socket.
+    ///
+    /// On success, returns the number of bytes read and the address from whence the message
+    /// came. A ping socket only receives the replies to its own requests and the errors of
+    /// them, while a raw one receives every ICMP message of the host. The IPv4 header a raw
+    /// socket receives is stripped, but `buf` must have room for it too, or the message is
+    /// truncated.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, IpAddr)> {
+        let (n, addr) = future::poll_fn(|cx| {
+            self.watcher.poll_read_with(cx, |fd| {
+                let (n, _, addr) =
+                    rustix::net::recvfrom(fd, &mut *buf, RecvFlags::empty())?;
+                Ok((n, addr))
+            })
+        })
+        .await?;
+        let addr = match addr {
+            Some(addr) => SocketAddr::try_from(addr)?.ip(),
+            None => return Err(io::ErrorKind::InvalidData.into()),
+        };
+        if !self.raw || self.v6 {
+            return Ok((n, addr));
+        }
+        // a raw socket of IPv4 receives the IP header too
+        let header_len = match buf.first() {
+            Some(b) if n >= ((b & 0x0f) as usize) * 4 => ((b & 0x0f) as usize) * 4,
+            _ => return Err(io::ErrorKind::InvalidData.into()),
+        };
+        buf.copy_within(header_len..n, 0);
+        Ok((n - header_len, addr))
+    }
+
+    /// Sends an echo request with the sequence number and the payload to the given address.
+    pub async fn send_echo(
+        &self,
+        addr: IpAddr,
+        seq: u16,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        // type, code, checksum, identifier and sequence number
+        let ty = if self.v6 { 128 } else { 8 };
+        let mut msg = Vec::with_capacity(ECHO_HEADER_LEN + payload.len());
+        msg.extend_from_slice(&[ty, 0, 0, 0]);
+        msg.extend_from_slice(&self.ident.to_be_bytes());
+        msg.extend_from_slice(&seq.to_be_bytes());
+        msg.extend_from_slice(payload);
+        if !self.v6 {
+            let sum = checksum(&msg);
+            msg[2..4].copy_from_slice(&sum.to_be_bytes());
+        }
+        self.send_to(&msg, addr).await?;
+        Ok(())
+    }
+
+    /// Receives the next echo reply to this socket, skipping the other ICMP messages.
+    ///
+    /// The payload of the reply is copied to the start of `buf`, and truncated if `buf` is
+    /// shorter.
+    pub async fn recv_echo(&self, buf: &mut [u8]) -> io::Result<EchoReply> {
+        let ty = if self.v6 { 129 } else { 0 };
+        let mut msg = vec![0; MAX_IPV4_HEADER_LEN + ECHO_HEADER_LEN + buf.len()];
+        loop {
+            let (n, addr) = self.recv_from(&mut msg).await?;
+            if n < ECHO_HEADER_LEN || msg[0] != ty || msg[1] != 0 {
+                continue;
+            }
+            // a ping socket has the identifier rewritten by the kernel
+            let ident = u16::from_be_bytes([msg[4], msg[5]]);
+            if self.raw && ident != self.ident {
+                continue;
+            }
+            let seq = u16::from_be_bytes([msg[6], msg[7]]);
+            let len = (n - ECHO_HEADER_LEN).min(buf.len());
+            buf[..len].copy_from_slice(&msg[ECHO_HEADER_LEN..ECHO_HEADER_LEN + len]);
+            return Ok(EchoReply { addr, seq, len });
+        }
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks waiting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do. Its timeouts start unset.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self {
+            watcher: Arc::new(Watcher::new(Fd(fd))),
+            ..*self
+        })
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.watcher.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every receive which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`], which is how a lost echo is told. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.watcher.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.watcher.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every send which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.watcher.set_write_timeout(dur)
+    }
+}
+
+#[inline]
+fn open(
+    family: AddressFamily,
+    ty: SocketType,
+    protocol: rustix::net::Protocol,
+    flags: SocketFlags,
+) -> io::Result<OwnedFd> {
+    Ok(rustix::net::socket_with(family, ty, flags, Some(protocol))?)
+}
+
+/// Returns `true` if `err` tells a ping socket is not permitted or not supported.
+#[inline]
+fn is_denied(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::PermissionDenied
+        || err.raw_os_error() == Some(rustix::io::Errno::PROTONOSUPPORT.raw_os_error())
+}
+
+/// Computes the internet checksum of RFC 1071.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum = data
+        .chunks(2)
+        .map(|pair| match *pair {
+            [hi, lo] => u32::from(u16::from_be_bytes([hi, lo])),
+            [hi] => u32::from(hi) << 8,
+            _ => 0,
+        })
+        .sum::<u32>();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+impl AsFd for IcmpSocket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.watcher.as_fd()
+    }
+}
+
+impl AsRawFd for IcmpSocket {
+    /// Share raw fd of `IcmpSocket`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checksum, IcmpSocket};
+    use crate::task::block_on;
+    use std::io;
+    use std::net::IpAddr;
+    use std::time::Duration;
+
+    #[test]
+    fn internet_checksum() {
+        // the example of RFC 1071
+        let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq!(!0xddf2, checksum(&data));
+        // a checksum verifies to zero
+        let mut msg = vec![8, 0, 0, 0, 0x12, 0x34, 0, 1, b'a'];
+        let sum = checksum(&msg);
+        msg[2..4].copy_from_slice(&sum.to_be_bytes());
+        assert_eq!(0, checksum(&msg));
+    }
+
+    /// Pings `addr` twice, unless neither a ping socket nor a raw one is permitted.
+    fn ping(new: fn() -> io::Result<IcmpSocket>, addr: &str) -> io::Result<()> {
+        block_on(async {
+            let socket = match new() {
+                Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                    return Ok(())
+                }
+                ret => ret?,
+            };
+            socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+            let addr = addr.parse::<IpAddr>().unwrap();
+            for seq in 1..=2 {
+                socket.send_echo(addr, seq, b"hello").await?;
+                let mut buf = [0; 16];
+                let reply = socket.recv_echo(&mut buf).await?;
+                assert_eq!(addr, reply.addr());
+                assert_eq!(seq, reply.seq());
+                assert_eq!(b"hello", &buf[..reply.len()]);
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn ping_v4() -> io::Result<()> {
+        ping(IcmpSocket::new_v4, "127.0.0.1")
+    }
+
+    #[test]
+    fn ping_v6() -> io::Result<()> {
+        if std::net::UdpSocket::bind("[::1]:0").is_err() {
+            // no IPv6 on the loopback
+            return Ok(());
+        }
+        ping(IcmpSocket::new_v6, "::1")
+    }
+}
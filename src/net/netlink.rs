@@ -0,0 +1,435 @@
+//! Netlink sockets for the communication with the kernel of Linux.
+//!
+//! A [`NetlinkSocket`] sends requests to a subsystem of the kernel, like the routing one by
+//! [`NETLINK_ROUTE`], and receives its replies. By [joining] the multicast groups, it also
+//! receives the notifications of the subsystem, like the changes of links, addresses and
+//! routes, so a network daemon can watch them in a task rather than a blocking thread.
+//!
+//! The messages are the raw netlink messages, each starting with a `nlmsghdr`; parsing them
+//! is left to the protocol crates.
+//!
+//! [`NetlinkSocket`]: struct.NetlinkSocket.html
+//! [`NETLINK_ROUTE`]: constant.NETLINK_ROUTE.html
+//! [joining]: struct.NetlinkSocket.html#method.join_group
+//!
+//! # Examples
+//!
+//! Watching the changes of links:
+//!
+//! ```no_run
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use tio::net::netlink::{NetlinkAddr, NetlinkSocket, NETLINK_ROUTE, RTNLGRP_LINK};
+//!
+//! let socket = NetlinkSocket::bind(NETLINK_ROUTE, NetlinkAddr::new(0, 0))?;
+//! socket.join_group(RTNLGRP_LINK)?;
+//!
+//! let mut buf = vec![0u8; 8192];
+//! loop {
+//!     let n = socket.recv(&mut buf).await?;
+//!     println!("link changed: {:?}", &buf[..n]);
+//! }
+//! #
+//! # }) }
+//! ```
+
+use crate::net::poll::{Fd, Watcher};
+use crate::net::sys;
+use crate::net::util;
+use futures::future;
+use rustix::net::netlink::SocketAddrNetlink;
+use rustix::net::{
+    AddressFamily, Protocol, RecvFlags, SendFlags, SocketAddrAny, SocketFlags,
+    SocketType,
+};
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::num::NonZeroU32;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The protocol of the routing subsystem, for links, addresses, routes and neighbours.
+pub const NETLINK_ROUTE: i32 = libc::NETLINK_ROUTE;
+
+/// The protocol of the socket monitoring, like `ss`.
+pub const NETLINK_SOCK_DIAG: i32 = libc::NETLINK_SOCK_DIAG;
+
+/// The protocol of the audit subsystem.
+pub const NETLINK_AUDIT: i32 = libc::NETLINK_AUDIT;
+
+/// The protocol of the kernel events of devices, like hotplugging, for udev.
+pub const NETLINK_KOBJECT_UEVENT: i32 = libc::NETLINK_KOBJECT_UEVENT;
+
+/// The protocol of the generic netlink families, like nl80211.
+pub const NETLINK_GENERIC: i32 = libc::NETLINK_GENERIC;
+
+/// The multicast group of [`NETLINK_ROUTE`] notifying the changes of links.
+///
+/// [`NETLINK_ROUTE`]: constant.NETLINK_ROUTE.html
+pub const RTNLGRP_LINK: u32 = libc::RTNLGRP_LINK;
+
+/// The multicast group of [`NETLINK_ROUTE`] notifying the changes of IPv4 addresses.
+///
+/// [`NETLINK_ROUTE`]: constant.NETLINK_ROUTE.html
+pub const RTNLGRP_IPV4_IFADDR: u32 = libc::RTNLGRP_IPV4_IFADDR;
+
+/// The multicast group of [`NETLINK_ROUTE`] notifying the changes of IPv4 routes.
+///
+/// [`NETLINK_ROUTE`]: constant.NETLINK_ROUTE.html
+pub const RTNLGRP_IPV4_ROUTE: u32 = libc::RTNLGRP_IPV4_ROUTE;
+
+/// The multicast group of [`NETLINK_ROUTE`] notifying the changes of IPv6 addresses.
+///
+/// [`NETLINK_ROUTE`]: constant.NETLINK_ROUTE.html
+pub const RTNLGRP_IPV6_IFADDR: u32 = libc::RTNLGRP_IPV6_IFADDR;
+
+/// The multicast group of [`NETLINK_ROUTE`] notifying the changes of IPv6 routes.
+///
+/// [`NETLINK_ROUTE`]: constant.NETLINK_ROUTE.html
+pub const RTNLGRP_IPV6_ROUTE: u32 = libc::RTNLGRP_IPV6_ROUTE;
+
+/// The address of a netlink socket, as a port id and a mask of multicast groups.
+///
+/// The port id of the kernel is zero, and that of a socket bound with a zero port id is
+/// assigned by the kernel, usually as the pid of the process.
+///
+/// The mask only covers the first 32 groups; see [`NetlinkSocket::join_group`] for any of
+/// them.
+///
+/// [`NetlinkSocket::join_group`]: struct.NetlinkSocket.html#method.join_group
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct NetlinkAddr {
+    pid: u32,
+    groups: u32,
+}
+
+impl NetlinkAddr {
+    /// Creates an address from a port id and a mask of multicast groups.
+    #[inline]
+    pub const fn new(pid: u32, groups: u32) -> Self {
+        Self { pid, groups }
+    }
+
+    /// Returns the port id of this address.
+    #[inline]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the mask of multicast groups of this address.
+    #[inline]
+    pub const fn groups(&self) -> u32 {
+        self.groups
+    }
+
+    #[inline]
+    fn to_raw(self) -> SocketAddrNetlink {
+        SocketAddrNetlink::new(self.pid, self.groups)
+    }
+
+    #[inline]
+    fn from_any(addr: SocketAddrAny) -> io::Result<Self> {
+        let raw = SocketAddrNetlink::try_from(addr)?;
+        Ok(Self::new(raw.pid(), raw.groups()))
+    }
+}
+
+impl Display for NetlinkAddr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{:#x}", self.pid, self.groups)
+    }
+}
+
+/// A netlink socket.
+///
+/// After creating a `NetlinkSocket` by [`bind`]ing it to an address, messages can be [sent
+/// to] and [received from] the kernel or other netlink sockets of the same protocol.
+///
+/// A message longer than the buffer passed to [`recv`] is truncated, and a socket which does
+/// not keep up with the notifications loses some of them, which the next receive reports by
+/// an error of `ENOBUFS`.
+///
+/// [`bind`]: #method.bind
+/// [sent to]: #method.send_to
+/// [received from]: #method.recv_from
+/// [`recv`]: #method.recv
+#[derive(Debug, Clone)]
+pub struct NetlinkSocket(Arc<Watcher<Fd>>);
+
+impl NetlinkSocket {
+    /// Creates a netlink socket of the protocol, like [`NETLINK_ROUTE`], bound to the given
+    /// address.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`NETLINK_ROUTE`]: constant.NETLINK_ROUTE.html
+    pub fn bind(protocol: i32, addr: NetlinkAddr) -> io::Result<Self> {
+        let protocol = NonZeroU32::new(protocol as u32).map(Protocol::from_raw);
+        let flags = SocketFlags::NONBLOCK | SocketFlags::CLOEXEC;
+        let fd = rustix::net::socket_with(
+            AddressFamily::NETLINK,
+            SocketType::RAW,
+            flags,
+            protocol,
+        )?;
+        rustix::net::bind(&fd, &addr.to_raw())?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns the address of this socket, with the port id assigned by the kernel.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<NetlinkAddr> {
+        NetlinkAddr::from_any(rustix::net::getsockname(self)?)
+    }
+
+    /// Joins a multicast group, like [`RTNLGRP_LINK`], to receive its notifications.
+    ///
+    /// [`RTNLGRP_LINK`]: constant.RTNLGRP_LINK.html
+    #[inline]
+    pub fn join_group(&self, group: u32) -> io::Result<()> {
+        sys::setsockopt(
+            self.as_fd(),
+            libc::SOL_NETLINK,
+            libc::NETLINK_ADD_MEMBERSHIP,
+            &group,
+        )
+    }
+
+    /// Leaves a multicast group.
+    #[inline]
+    pub fn leave_group(&self, group: u32) -> io::Result<()> {
+        sys::setsockopt(
+            self.as_fd(),
+            libc::SOL_NETLINK,
+            libc::NETLINK_DROP_MEMBERSHIP,
+            &group,
+        )
+    }
+
+    /// Sends a message on the socket to the given address.
+    ///
+    /// On success, returns the number of bytes written.
+    pub async fn send_to(&self, buf: &[u8], addr: NetlinkAddr) -> io::Result<usize> {
+        let addr = addr.to_raw();
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |fd| {
+                Ok(rustix::net::sendto(fd, buf, SendFlags::empty(), &addr)?)
+            })
+        })
+        .await
+    }
+
+    /// Receives a message from the socket.
+    ///
+    /// On success, returns the number of bytes read and the address from whence the message
+    /// came.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, NetlinkAddr)> {
+        let (n, addr) = future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |fd| {
+                let (n, _, addr) =
+                    rustix::net::recvfrom(fd, &mut *buf, RecvFlags::empty())?;
+                Ok((n, addr))
+            })
+        })
+        .await?;
+        match addr {
+            Some(addr) => Ok((n, NetlinkAddr::from_any(addr)?)),
+            None => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    /// Sends a message on the socket to the kernel.
+    ///
+    /// On success, returns the number of bytes written.
+    #[inline]
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.send_to(buf, NetlinkAddr::new(0, 0)).await
+    }
+
+    /// Receives a message from the socket, from the kernel or any other socket.
+    ///
+    /// On success, returns the number of bytes read.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |fd| {
+                let (n, _) = rustix::net::recv(fd, &mut *buf, RecvFlags::empty())?;
+                Ok(n)
+            })
+        })
+        .await
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks waiting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do. Its timeouts start unset.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every receive which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every send which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+}
+
+impl AsFd for NetlinkSocket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for NetlinkSocket {
+    /// Share raw fd of `NetlinkSocket`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NetlinkAddr, NetlinkSocket, NETLINK_ROUTE, RTNLGRP_LINK};
+    use crate::task::block_on;
+    use std::convert::TryInto;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+    use std::time::Duration;
+
+    /// Encodes a `RTM_GETLINK` request dumping all the links.
+    fn get_links(seq: u32) -> Vec<u8> {
+        let mut msg = Vec::new();
+        // nlmsghdr, followed by a rtgenmsg padded to 4 bytes
+        msg.extend_from_slice(&20u32.to_ne_bytes());
+        msg.extend_from_slice(&libc::RTM_GETLINK.to_ne_bytes());
+        let flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+        msg.extend_from_slice(&flags.to_ne_bytes());
+        msg.extend_from_slice(&seq.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&[libc::AF_UNSPEC as u8, 0, 0, 0]);
+        msg
+    }
+
+    #[test]
+    fn route() -> io::Result<()> {
+        block_on(async {
+            let socket = NetlinkSocket::bind(NETLINK_ROUTE, NetlinkAddr::new(0, 0))?;
+            assert_ne!(0, socket.local_addr()?.pid());
+            socket.join_group(RTNLGRP_LINK)?;
+
+            assert_eq!(20, socket.send(&get_links(1)).await?);
+            let mut buf = vec![0; 1 << 16];
+            let (n, peer) = socket.recv_from(&mut buf).await?;
+            // the kernel replies with the loopback at least
+            assert_eq!(0, peer.pid());
+            assert!(n >= 16);
+            let ty = u16::from_ne_bytes(buf[4..6].try_into().unwrap());
+            let seq = u32::from_ne_bytes(buf[8..12].try_into().unwrap());
+            assert_eq!(libc::RTM_NEWLINK, ty);
+            assert_eq!(1, seq);
+            socket.leave_group(RTNLGRP_LINK)?;
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn try_clone() -> io::Result<()> {
+        block_on(async {
+            let socket = NetlinkSocket::bind(NETLINK_ROUTE, NetlinkAddr::new(0, 0))?;
+            let clone = socket.try_clone()?;
+            assert_eq!(socket.local_addr()?, clone.local_addr()?);
+            assert_ne!(socket.as_raw_fd(), clone.as_raw_fd());
+
+            // the handles have their own timeouts, for the same socket
+            clone.set_read_timeout(Some(Duration::from_secs(1)))?;
+            assert_eq!(None, socket.read_timeout()?);
+            clone.send(&get_links(2)).await?;
+            let mut buf = vec![0; 1 << 16];
+            let (n, _) = socket.recv_from(&mut buf).await?;
+            assert!(n >= 16);
+            assert_eq!(2, u32::from_ne_bytes(buf[8..12].try_into().unwrap()));
+            Ok(())
+        })
+    }
+}
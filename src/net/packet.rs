@@ -0,0 +1,579 @@
+//! Packet sockets for the frames of the link layer on Linux.
+//!
+//! A [`RawPacketSocket`] receives the frames of an interface, or of all of them, with their
+//! link-layer headers, and sends frames as they are, so it can capture packets or speak a
+//! custom protocol of the link layer. Opening one takes `CAP_NET_RAW`.
+//!
+//! A classic BPF filter [attached] to the socket drops the frames which are not wanted in the
+//! kernel, before they are copied to the socket.
+//!
+//! [`RawPacketSocket`]: struct.RawPacketSocket.html
+//! [attached]: struct.RawPacketSocket.html#method.attach_filter
+//!
+//! # Examples
+//!
+//! Capturing the ARP frames of `eth0`:
+//!
+//! ```no_run
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use tio::net::packet::{self, PacketAddr, RawPacketSocket, ETH_P_ARP};
+//!
+//! let eth0 = packet::interface_index("eth0")?;
+//! let socket = RawPacketSocket::bind(PacketAddr::new(eth0, ETH_P_ARP))?;
+//!
+//! let mut buf = vec![0u8; 65536];
+//! loop {
+//!     let (n, addr) = socket.recv_from(&mut buf).await?;
+//!     println!("{} bytes from {:?}", n, addr.hardware_addr());
+//! }
+//! #
+//! # }) }
+//! ```
+
+use crate::net::poll::{Fd, Watcher};
+use crate::net::sys;
+use crate::net::util;
+use futures::future;
+use rustix::net::addr::{SocketAddrArg, SocketAddrLen, SocketAddrOpaque};
+use rustix::net::{
+    AddressFamily, Protocol, RecvFlags, SendFlags, SocketAddrAny, SocketFlags,
+    SocketType,
+};
+use std::io;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The length of a `sockaddr_ll` without its hardware address.
+const HEADER_LEN: usize = size_of::<libc::sockaddr_ll>() - 8;
+
+/// The protocol of all the frames.
+pub const ETH_P_ALL: u16 = libc::ETH_P_ALL as u16;
+
+/// The protocol of the IPv4 frames.
+pub const ETH_P_IP: u16 = libc::ETH_P_IP as u16;
+
+/// The protocol of the IPv6 frames.
+pub const ETH_P_IPV6: u16 = libc::ETH_P_IPV6 as u16;
+
+/// The protocol of the ARP frames.
+pub const ETH_P_ARP: u16 = libc::ETH_P_ARP as u16;
+
+/// The type of a frame to this host.
+pub const PACKET_HOST: u8 = libc::PACKET_HOST;
+
+/// The type of a frame broadcast on the link.
+pub const PACKET_BROADCAST: u8 = libc::PACKET_BROADCAST;
+
+/// The type of a frame multicast on the link.
+pub const PACKET_MULTICAST: u8 = libc::PACKET_MULTICAST;
+
+/// The type of a frame to another host, captured in the promiscuous mode.
+pub const PACKET_OTHERHOST: u8 = libc::PACKET_OTHERHOST;
+
+/// The type of a frame sent by this host, looped back to the packet sockets.
+pub const PACKET_OUTGOING: u8 = libc::PACKET_OUTGOING;
+
+/// The address of a packet socket, as an interface and a protocol of the link layer.
+///
+/// The addresses of the frames received also carry the hardware address of the peer and the
+/// type of the frame, like [`PACKET_HOST`].
+///
+/// [`PACKET_HOST`]: constant.PACKET_HOST.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct PacketAddr {
+    ifindex: u32,
+    protocol: u16,
+    hatype: u16,
+    pkttype: u8,
+    halen: u8,
+    addr: [u8; 8],
+}
+
+impl PacketAddr {
+    /// Creates an address of the interface of `ifindex`, or of all the interfaces if it is
+    /// zero, and the protocol, like [`ETH_P_ALL`], in the order of the host.
+    ///
+    /// [`ETH_P_ALL`]: constant.ETH_P_ALL.html
+    #[inline]
+    pub const fn new(ifindex: u32, protocol: u16) -> Self {
+        Self {
+            ifindex,
+            protocol,
+            hatype: 0,
+            pkttype: 0,
+            halen: 0,
+            addr: [0; 8],
+        }
+    }
+
+    /// Sets the hardware address of the peer, the destination of the frames sent to this
+    /// address.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `addr` is longer than 8 bytes.
+    #[inline]
+    pub fn with_hardware_addr(mut self, addr: &[u8]) -> Self {
+        assert!(addr.len() <= 8, "a hardware address has at most 8 bytes");
+        self.addr = [0; 8];
+        self.addr[..addr.len()].copy_from_slice(addr);
+        self.halen = addr.len() as u8;
+        self
+    }
+
+    /// Returns the index of the interface of this address.
+    #[inline]
+    pub const fn ifindex(&self) -> u32 {
+        self.ifindex
+    }
+
+    /// Returns the protocol of this address, in the order of the host.
+    #[inline]
+    pub const fn protocol(&self) -> u16 {
+        self.protocol
+    }
+
+    /// Returns the type of the hardware of the interface, like `ARPHRD_ETHER`.
+    #[inline]
+    pub const fn hardware_type(&self) -> u16 {
+        self.hatype
+    }
+
+    /// Returns the type of the frame, like [`PACKET_HOST`].
+    ///
+    /// [`PACKET_HOST`]: constant.PACKET_HOST.html
+    #[inline]
+    pub const fn packet_type(&self) -> u8 {
+        self.pkttype
+    }
+
+    /// Returns the hardware address of the peer.
+    #[inline]
+    pub fn hardware_addr(&self) -> &[u8] {
+        &self.addr[..self.halen as usize]
+    }
+
+    #[inline]
+    fn to_raw(self) -> libc::sockaddr_ll {
+        libc::sockaddr_ll {
+            sll_family: libc::AF_PACKET as libc::c_ushort,
+            sll_protocol: self.protocol.to_be(),
+            sll_ifindex: self.ifindex as libc::c_int,
+            sll_hatype: self.hatype,
+            sll_pkttype: self.pkttype,
+            sll_halen: self.halen,
+            sll_addr: self.addr,
+        }
+    }
+
+    /// Decodes an address returned by the kernel.
+    #[allow(unsafe_code)]
+    fn from_any(addr: &SocketAddrAny) -> io::Result<Self> {
+        // the kernel leaves out the unused bytes of the hardware address
+        let len = addr.addr_len() as usize;
+        if addr.address_family() != AddressFamily::PACKET || len < HEADER_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the address is not of a packet socket",
+            ));
+        }
+        let mut raw = Self::new(0, 0).to_raw();
+        let len = len.min(size_of::<libc::sockaddr_ll>());
+        // SAFETY: the storage holds `len` bytes of a `sockaddr_ll`, copied into one.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                addr.as_ptr().cast::<u8>(),
+                (&mut raw as *mut libc::sockaddr_ll).cast::<u8>(),
+                len,
+            )
+        };
+        Ok(Self {
+            ifindex: raw.sll_ifindex as u32,
+            protocol: u16::from_be(raw.sll_protocol),
+            hatype: raw.sll_hatype,
+            pkttype: raw.sll_pkttype,
+            halen: raw.sll_halen.min(8),
+            addr: raw.sll_addr,
+        })
+    }
+}
+
+#[allow(unsafe_code)]
+// SAFETY: the pointer is to a `sockaddr_ll` alive during the call, of the length passed.
+unsafe impl SocketAddrArg for PacketAddr {
+    unsafe fn with_sockaddr<R>(
+        &self,
+        f: impl FnOnce(*const SocketAddrOpaque, SocketAddrLen) -> R,
+    ) -> R {
+        let raw = self.to_raw();
+        f(
+            (&raw as *const libc::sockaddr_ll).cast(),
+            size_of::<libc::sockaddr_ll>() as SocketAddrLen,
+        )
+    }
+}
+
+/// Returns the index of the interface of the given name, like `eth0`.
+pub fn interface_index(name: &str) -> io::Result<u32> {
+    // any socket can query the interfaces
+    let fd = rustix::net::socket_with(
+        AddressFamily::INET,
+        SocketType::DGRAM,
+        SocketFlags::CLOEXEC,
+        None,
+    )?;
+    Ok(rustix::net::netdevice::name_to_index(&fd, name)?)
+}
+
+/// An instruction of a classic BPF program, the same as `struct sock_filter`.
+///
+/// The programs are usually compiled by `tcpdump -dd`, which prints the instructions as
+/// the arguments of [`new`].
+///
+/// [`new`]: #method.new
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct BpfInstruction {
+    code: u16,
+    jt: u8,
+    jf: u8,
+    k: u32,
+}
+
+impl BpfInstruction {
+    /// Creates an instruction from its opcode, its jumps if true and false, and its operand.
+    #[inline]
+    pub const fn new(code: u16, jt: u8, jf: u8, k: u32) -> Self {
+        Self { code, jt, jf, k }
+    }
+}
+
+/// A raw packet socket, sending and receiving the frames with their link-layer headers.
+///
+/// A socket bound to a protocol other than zero receives the frames of that protocol, from
+/// the interface it is bound to or from all of them. The frames sent by [`send`] go to the
+/// interface it is bound to.
+///
+/// A frame longer than the buffer passed to [`recv`] is truncated.
+///
+/// [`send`]: #method.send
+/// [`recv`]: #method.recv
+#[derive(Debug, Clone)]
+pub struct RawPacketSocket(Arc<Watcher<Fd>>);
+
+impl RawPacketSocket {
+    /// Creates a raw packet socket bound to the given address.
+    ///
+    /// It must be called within a runtime.
+    pub fn bind(addr: PacketAddr) -> io::Result<Self> {
+        let protocol =
+            NonZeroU32::new(addr.protocol.to_be() as u32).map(Protocol::from_raw);
+        let flags = SocketFlags::NONBLOCK | SocketFlags::CLOEXEC;
+        let fd = rustix::net::socket_with(
+            AddressFamily::PACKET,
+            SocketType::RAW,
+            flags,
+            protocol,
+        )?;
+        rustix::net::bind(&fd, &addr)?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns the address of this socket.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<PacketAddr> {
+        PacketAddr::from_any(&rustix::net::getsockname(self)?)
+    }
+
+    /// Attaches a classic BPF program to this socket, replacing the one attached before.
+    ///
+    /// The program runs on each frame, which is dropped if it returns zero, and truncated to
+    /// the length returned otherwise.
+    pub fn attach_filter(&self, program: &[BpfInstruction]) -> io::Result<()> {
+        let mut filter = program
+            .iter()
+            .map(|inst| libc::sock_filter {
+                code: inst.code,
+                jt: inst.jt,
+                jf: inst.jf,
+                k: inst.k,
+            })
+            .collect::<Vec<_>>();
+        if filter.len() > u16::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the program is too long",
+            ));
+        }
+        let prog = libc::sock_fprog {
+            len: filter.len() as libc::c_ushort,
+            filter: filter.as_mut_ptr(),
+        };
+        sys::setsockopt(
+            self.as_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_ATTACH_FILTER,
+            &prog,
+        )
+    }
+
+    /// Detaches the BPF program attached to this socket.
+    #[inline]
+    pub fn detach_filter(&self) -> io::Result<()> {
+        sys::setsockopt(self.as_fd(), libc::SOL_SOCKET, libc::SO_DETACH_FILTER, &0)
+    }
+
+    /// Sends a frame on the socket to the given address.
+    ///
+    /// On success, returns the number of bytes written.
+    pub async fn send_to(&self, buf: &[u8], addr: PacketAddr) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |fd| {
+                Ok(rustix::net::sendto(fd, buf, SendFlags::empty(), &addr)?)
+            })
+        })
+        .await
+    }
+
+    /// Receives a frame from the socket.
+    ///
+    /// On success, returns the number of bytes read and the address from whence the frame
+    /// came.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, PacketAddr)> {
+        let (n, addr) = future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |fd| {
+                let (n, _, addr) =
+                    rustix::net::recvfrom(fd, &mut *buf, RecvFlags::empty())?;
+                Ok((n, addr))
+            })
+        })
+        .await?;
+        match addr {
+            Some(addr) => Ok((n, PacketAddr::from_any(&addr)?)),
+            None => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    /// Sends a frame on the socket to the interface it is bound to.
+    ///
+    /// On success, returns the number of bytes written.
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |fd| {
+                Ok(rustix::net::send(fd, buf, SendFlags::empty())?)
+            })
+        })
+        .await
+    }
+
+    /// Receives a frame from the socket.
+    ///
+    /// On success, returns the number of bytes read.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |fd| {
+                let (n, _) = rustix::net::recv(fd, &mut *buf, RecvFlags::empty())?;
+                Ok(n)
+            })
+        })
+        .await
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks waiting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do. Its timeouts start unset.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every receive which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every send which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+}
+
+impl AsFd for RawPacketSocket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for RawPacketSocket {
+    /// Share raw fd of `RawPacketSocket`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        interface_index, BpfInstruction, PacketAddr, RawPacketSocket, ETH_P_ALL,
+    };
+    use crate::task::block_on;
+    use std::io;
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    /// `ret #0`, which drops every frame.
+    const DROP_ALL: BpfInstruction = BpfInstruction::new(0x06, 0, 0, 0);
+
+    /// Binds a socket to the loopback, or returns `None` without `CAP_NET_RAW`.
+    fn loopback() -> io::Result<Option<RawPacketSocket>> {
+        let lo = interface_index("lo")?;
+        let socket = match RawPacketSocket::bind(PacketAddr::new(lo, ETH_P_ALL)) {
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(None)
+            }
+            ret => ret?,
+        };
+        assert_eq!(lo, socket.local_addr()?.ifindex());
+        socket.set_read_timeout(Some(Duration::from_millis(50)))?;
+        Ok(Some(socket))
+    }
+
+    async fn drain(socket: &RawPacketSocket) -> io::Result<()> {
+        let mut buf = vec![0; 1 << 16];
+        loop {
+            match socket.recv(&mut buf).await {
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => return Ok(()),
+                ret => ret?,
+            };
+        }
+    }
+
+    fn send_udp(payload: &[u8]) -> io::Result<()> {
+        let receiver = UdpSocket::bind("127.0.0.1:0")?;
+        let sender = UdpSocket::bind("127.0.0.1:0")?;
+        sender.send_to(payload, receiver.local_addr()?)?;
+        Ok(())
+    }
+
+    #[test]
+    fn capture() -> io::Result<()> {
+        block_on(async {
+            let socket = match loopback()? {
+                Some(socket) => socket,
+                None => return Ok(()),
+            };
+            let payload = b"captured by a packet socket";
+            send_udp(payload)?;
+            let mut buf = vec![0; 1 << 16];
+            // other frames may be on the loopback too
+            loop {
+                let (n, addr) = socket.recv_from(&mut buf).await?;
+                if buf[..n].ends_with(payload) {
+                    assert_eq!(libc::ETH_P_IP as u16, addr.protocol());
+                    return Ok(());
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn filter() -> io::Result<()> {
+        block_on(async {
+            let socket = match loopback()? {
+                Some(socket) => socket,
+                None => return Ok(()),
+            };
+            socket.attach_filter(&[DROP_ALL])?;
+            // the frames queued before the filter is attached
+            drain(&socket).await?;
+            send_udp(b"dropped by the filter")?;
+            let mut buf = vec![0; 1 << 16];
+            let err = socket.recv(&mut buf).await.unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+
+            socket.detach_filter()?;
+            send_udp(b"passed without the filter")?;
+            socket.recv(&mut buf).await?;
+            Ok(())
+        })
+    }
+}
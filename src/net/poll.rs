@@ -1,59 +1,152 @@
-use super::util::{may_block, ENTRIES_LOCK_POISONED};
+use super::util::{may_block, timed_out, ENTRIES_LOCK_POISONED, TIMEOUT_LOCK_POISONED};
+use crate::runtime::time::Delay;
+use crate::runtime::{context, Inner, ReactorHooks, Readiness};
+use crate::task::coop;
 use crossbeam_queue::SegQueue;
 use futures::future::poll_fn;
+use futures::task::{waker_ref, ArcWake};
 use mio::event;
 use mio::{Events, Interest, Poll, Registry, Token};
-use once_cell::sync::Lazy;
+use once_cell::sync::OnceCell;
 use slab::Slab;
 use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
 use std::io;
+use std::mem::{self, ManuallyDrop};
 use std::ops::Deref;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 use std::task::Waker;
 use std::task::{self, Context};
-use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(any(
+    all(
+        feature = "process",
+        any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "freebsd"
+        )
+    ),
+    all(
+        target_os = "linux",
+        any(
+            feature = "vsock",
+            feature = "netlink",
+            feature = "packet",
+            feature = "tun",
+            feature = "sctp"
+        )
+    ),
+    all(unix, feature = "icmp")
+))]
+mod fd;
+
+#[cfg(any(
+    all(
+        feature = "process",
+        any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "freebsd"
+        )
+    ),
+    all(
+        target_os = "linux",
+        any(
+            feature = "vsock",
+            feature = "netlink",
+            feature = "packet",
+            feature = "tun",
+            feature = "sctp"
+        )
+    ),
+    all(unix, feature = "icmp")
+))]
+pub(crate) use fd::Fd;
 
 const EVENTS: usize = 1 << 12;
-const THREAD_NAME: &str = "tio/poll";
 const ALL_INTEREST: Interest = Interest::READABLE.add(Interest::WRITABLE);
 
-static REACTOR: Lazy<Reactor> = Lazy::new(|| {
-    let mut poll = Poll::new()
-        .unwrap_or_else(|err| panic!("fail to construct a mio::Poll: {}", err));
-    let registry = poll
-        .registry()
-        .try_clone()
-        .map(Arc::new)
-        .unwrap_or_else(|err| panic!("fail to clone mio::Registry: {}", err));
-    let entries = Arc::new(RwLock::new(Slab::<Entry>::new()));
-    let ret = Reactor { registry, entries };
-    let reactor = ret.clone();
-    thread::Builder::new()
-        .name(THREAD_NAME.to_string())
-        .spawn(move || {
-            let mut events = Events::with_capacity(EVENTS);
-            reactor.poll(&mut poll, &mut events);
-        })
-        .unwrap_or_else(|err| panic!("fail to spawn thread {}: {}", THREAD_NAME, err));
-    ret
-});
+/// The token of the waker which interrupts the poll loop, never used by an entry.
+const WAKER_TOKEN: Token = Token(usize::MAX);
 
+/// The I/O driver of a runtime, a thread polling the registered sources.
 #[derive(Clone)]
-struct Reactor {
+pub(crate) struct Reactor {
     registry: Arc<Registry>,
     entries: Arc<RwLock<Slab<Entry>>>,
+    waker: Arc<mio::Waker>,
+    shutdown: Arc<AtomicBool>,
+    events: Arc<AtomicU64>,
+    hooks: Option<Arc<dyn ReactorHooks>>,
+    // how long the poll thread polls without blocking after an event
+    busy_poll: Option<Duration>,
 }
 
 impl Reactor {
+    /// Creates a reactor and starts its poll thread, the reactor of a NUMA node if the workers
+    /// are partitioned into nodes.
+    pub(crate) fn start(inner: &Arc<Inner>, node: Option<usize>) -> io::Result<Self> {
+        let mut poll = Poll::new()?;
+        let registry = Arc::new(poll.registry().try_clone()?);
+        let waker = Arc::new(mio::Waker::new(&registry, WAKER_TOKEN)?);
+        let reactor = Reactor {
+            registry,
+            entries: Arc::new(RwLock::new(Slab::with_capacity(
+                inner.config.io_capacity,
+            ))),
+            waker,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            events: Arc::new(AtomicU64::new(0)),
+            hooks: inner.config.reactor_hooks.clone(),
+            #[cfg(feature = "async-rt")]
+            busy_poll: inner.config.busy_poll,
+            #[cfg(not(feature = "async-rt"))]
+            busy_poll: None,
+        };
+        let kind = match node {
+            Some(node) => format!("poll{}", node),
+            None => "poll".to_string(),
+        };
+        inner.spawn_thread(&kind, {
+            let reactor = reactor.clone();
+            move |inner| {
+                #[cfg(feature = "async-rt")]
+                if let Some(node) = node {
+                    crate::runtime::pool::Pool::enter_poller(inner, node)
+                }
+                let mut events = Events::with_capacity(EVENTS);
+                reactor.poll(inner, &mut poll, &mut events);
+            }
+        })?;
+        Ok(reactor)
+    }
+
+    /// Returns the number of the registered sources.
     #[inline]
-    fn entry(&self, index: usize) -> Option<Entry> {
-        self.entries
-            .read()
-            .expect(ENTRIES_LOCK_POISONED)
-            .get(index)
-            .cloned()
+    pub(crate) fn registrations(&self) -> usize {
+        self.entries.read().expect(ENTRIES_LOCK_POISONED).len()
+    }
+
+    /// Returns how many events the poll thread has received.
+    #[inline]
+    pub(crate) fn event_count(&self) -> u64 {
+        self.events.load(Ordering::Relaxed)
+    }
+
+    /// Calls `f` with the entry of a registered source.
+    #[inline]
+    fn with<R>(&self, index: usize, f: impl FnOnce(&Entry) -> R) -> R {
+        let entries = self.entries.read().expect(ENTRIES_LOCK_POISONED);
+        f(&entries[index])
     }
 
     #[inline]
@@ -72,40 +165,257 @@ impl Reactor {
             .remove(index)
     }
 
-    fn poll(&self, poll: &mut Poll, events: &mut Events) {
-        loop {
-            if let Err(err) = poll.poll(events, None) {
+    fn poll(&self, inner: &Inner, poll: &mut Poll, events: &mut Events) {
+        // the last time events arrived, while the thread busy-polls
+        let mut busy: Option<Instant> = None;
+        // the events and the wakers of an iteration, handled out of the lock of the entries,
+        // since a hook or a woken task may register or drop a source
+        let mut ready = Vec::new();
+        let mut wakers = Vec::new();
+        while !self.shutdown.load(Ordering::Acquire) {
+            let timeout = match (busy, self.busy_poll) {
+                (Some(since), Some(dur)) if since.elapsed() < dur => {
+                    Some(Duration::ZERO)
+                }
+                _ => None,
+            };
+            let result = poll.poll(events, timeout);
+            inner.tick();
+            if self.busy_poll.is_some() && !events.is_empty() {
+                busy = Some(Instant::now());
+            }
+            if let Err(err) = result {
                 log::error!("poll error: {}", err)
             } else {
-                for event in events.iter() {
-                    let token = event.token();
-                    if let Some(entry) = self.entry(token.0) {
-                        if event.is_readable() {
-                            entry.reader.ready();
+                trace_event!(
+                    target: "tio::reactor",
+                    "wake up with {} events",
+                    events.iter().count()
+                );
+                #[cfg(feature = "async-rt")]
+                let _batch = crate::runtime::pool::Batch::start();
+                {
+                    let entries = self.entries.read().expect(ENTRIES_LOCK_POISONED);
+                    for event in events.iter() {
+                        if event.token() != WAKER_TOKEN {
+                            self.events.fetch_add(1, Ordering::Relaxed);
+                        }
+                        let entry = match entries.get(event.token().0) {
+                            Some(entry) => entry,
+                            None => continue,
+                        };
+                        if self.hooks.is_some() {
+                            ready.push((entry.fd, Readiness::new(event)));
                         }
-                        if event.is_writable() {
-                            entry.writer.ready();
+                        // a closed pipe is only reported as hung up, which the readers and
+                        // writers see by trying
+                        if event.is_readable()
+                            || event.is_read_closed()
+                            || event.is_error()
+                        {
+                            entry.reader.ready(&mut wakers);
                         }
+                        if event.is_writable()
+                            || event.is_write_closed()
+                            || event.is_error()
+                        {
+                            entry.writer.ready(&mut wakers);
+                        }
+                    }
+                }
+                if let Some(hooks) = &self.hooks {
+                    for (fd, readiness) in ready.drain(..) {
+                        hooks.on_event(fd, readiness)
                     }
                 }
+                wakers.drain(..).for_each(Waker::wake);
             }
         }
     }
+
+    /// Stops the poll thread.
+    ///
+    /// The registered sources stay valid, but they are never ready again.
+    pub(crate) fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Release);
+        if let Err(err) = self.waker.wake() {
+            log::error!("fail to wake the poll thread: {}", err)
+        }
+    }
 }
 
 pub struct Watcher<S>
 where
     S: event::Source,
 {
-    pub(crate) entry: Entry,
+    reactor: Reactor,
     pub(crate) index: usize,
     pub(crate) source: S,
+    read_timeout: Timeout,
+    write_timeout: Timeout,
+    // registered by `EPOLLEXCLUSIVE`, out of mio
+    #[cfg(all(target_os = "linux", feature = "tcp"))]
+    exclusive: bool,
 }
 
-#[derive(Clone)]
+/// A deadline shared by all pending operations in one direction.
+///
+/// The delay is armed when an operation first returns pending and disarmed once any
+/// operation becomes ready again. The delay wakes every operation waiting on it, so that
+/// each of the concurrent operations on the clones of a socket times out, not only the one
+/// polled last. The state is allocated once a timeout is set.
+struct Timeout {
+    enabled: AtomicBool,
+    shared: OnceCell<Arc<TimeoutShared>>,
+}
+
+struct TimeoutShared {
+    state: Mutex<TimeoutState>,
+    // the wakers of the pending operations, woken all by the delay
+    waiters: Mutex<Vec<Waker>>,
+}
+
+#[derive(Default)]
+struct TimeoutState {
+    dur: Option<Duration>,
+    delay: Option<Delay>,
+    // the wakers of the operations which timed out and have not been told yet
+    expired: Vec<Waker>,
+}
+
+impl ArcWake for TimeoutShared {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        // out of the lock, an operation woken may be polled meanwhile
+        let wakers = arc_self
+            .waiters
+            .lock()
+            .expect(TIMEOUT_LOCK_POISONED)
+            .clone();
+        wakers.into_iter().for_each(Waker::wake)
+    }
+}
+
+impl TimeoutShared {
+    #[inline]
+    fn clear(&self, state: &mut TimeoutState) {
+        state.delay = None;
+        state.expired.clear();
+        self.waiters.lock().expect(TIMEOUT_LOCK_POISONED).clear();
+    }
+}
+
+impl Timeout {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            shared: OnceCell::new(),
+        }
+    }
+
+    #[inline]
+    fn get(&self) -> Option<Duration> {
+        let shared = self.shared.get()?;
+        shared.state.lock().expect(TIMEOUT_LOCK_POISONED).dur
+    }
+
+    #[inline]
+    fn set(&self, dur: Option<Duration>) -> io::Result<()> {
+        if dur == Some(Duration::from_secs(0)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            ));
+        }
+        let shared = match (dur, self.shared.get()) {
+            (None, None) => return Ok(()),
+            (_, Some(shared)) => shared,
+            (Some(_), None) => self.shared.get_or_init(|| {
+                Arc::new(TimeoutShared {
+                    state: Mutex::new(TimeoutState::default()),
+                    waiters: Mutex::new(Vec::new()),
+                })
+            }),
+        };
+        let mut state = shared.state.lock().expect(TIMEOUT_LOCK_POISONED);
+        state.dur = dur;
+        shared.clear(&mut state);
+        self.enabled.store(dur.is_some(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Polls the deadline of an operation which is still pending.
+    ///
+    /// The operations are told apart by their wakers: every operation waiting when the delay
+    /// completes times out once, the later ones wait for a new delay.
+    #[inline]
+    fn poll_elapsed<R>(&self, cx: &mut Context<'_>) -> task::Poll<io::Result<R>> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return task::Poll::Pending;
+        }
+        let shared = match self.shared.get() {
+            Some(shared) => shared,
+            None => return task::Poll::Pending,
+        };
+        let mut state = shared.state.lock().expect(TIMEOUT_LOCK_POISONED);
+        let dur = match state.dur {
+            Some(dur) => dur,
+            None => return task::Poll::Pending,
+        };
+        if let Some(i) = state.expired.iter().position(|w| w.will_wake(cx.waker())) {
+            state.expired.swap_remove(i);
+            return task::Poll::Ready(Err(timed_out()));
+        }
+        {
+            let mut waiters = shared.waiters.lock().expect(TIMEOUT_LOCK_POISONED);
+            if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                waiters.push(cx.waker().clone())
+            }
+        }
+        let waker = waker_ref(shared);
+        let delay = state.delay.get_or_insert_with(|| Delay::new(dur));
+        match Pin::new(delay).poll(&mut Context::from_waker(&waker)) {
+            task::Poll::Ready(()) => {
+                state.delay = None;
+                let mut expired =
+                    mem::take(&mut *shared.waiters.lock().expect(TIMEOUT_LOCK_POISONED));
+                expired.retain(|w| !w.will_wake(cx.waker()));
+                state.expired.extend(expired.iter().cloned());
+                drop(state);
+                // the others are woken to time out in turn
+                expired.into_iter().for_each(Waker::wake);
+                task::Poll::Ready(Err(timed_out()))
+            }
+            task::Poll::Pending => task::Poll::Pending,
+        }
+    }
+
+    /// Disarms the deadline after an operation completed.
+    #[inline]
+    fn reset(&self) {
+        if self.enabled.load(Ordering::Relaxed) {
+            if let Some(shared) = self.shared.get() {
+                shared.clear(&mut shared.state.lock().expect(TIMEOUT_LOCK_POISONED));
+            }
+        }
+    }
+}
+
+impl Drop for Timeout {
+    #[inline]
+    fn drop(&mut self) {
+        // the delay holds a waker of the shared state in the timer, which would keep it alive
+        if let Some(shared) = self.shared.get() {
+            shared.clear(&mut shared.state.lock().expect(TIMEOUT_LOCK_POISONED));
+        }
+    }
+}
+
+/// The state of a registered source, in the slab of the reactor at the index of its token.
 pub struct Entry {
-    reader: Arc<Channel>,
-    writer: Arc<Channel>,
+    fd: RawFd,
+    reader: Channel,
+    writer: Channel,
 }
 
 struct Channel {
@@ -122,13 +432,14 @@ impl Channel {
         }
     }
 
+    /// Marks the channel ready, taking its wakers to wake.
     #[inline]
-    fn ready(&self) {
+    fn ready(&self, wakers: &mut Vec<Waker>) {
         if !self.is_ready() {
             self.ready.store(true, Ordering::Relaxed)
         }
         while let Ok(waker) = self.wakers.pop() {
-            waker.wake()
+            wakers.push(waker)
         }
     }
 
@@ -140,10 +451,11 @@ impl Channel {
 
 impl Entry {
     #[inline]
-    fn new() -> Self {
+    fn new(fd: RawFd) -> Self {
         Entry {
-            reader: Arc::new(Channel::new()),
-            writer: Arc::new(Channel::new()),
+            fd,
+            reader: Channel::new(),
+            writer: Channel::new(),
         }
     }
 
@@ -162,20 +474,152 @@ impl<S> Watcher<S>
 where
     S: event::Source,
 {
-    pub fn new(mut source: S) -> Self {
-        let entry = Entry::new();
-        let index = REACTOR.insert(entry.clone());
-        REACTOR
-            .registry
-            .register(&mut source, Token(index), ALL_INTEREST)
-            .expect("fail to register source");
+    pub fn new(source: S) -> Self
+    where
+        S: AsRawFd,
+    {
+        Self::register(source, |reactor, source, token| {
+            reactor
+                .registry
+                .register(source, token, ALL_INTEREST)
+                .expect("fail to register source");
+        })
+    }
+
+    /// Registers a source for its readable events by `EPOLLEXCLUSIVE`, so that an event wakes
+    /// a single one of the reactors watching the same file, instead of all of them.
+    ///
+    /// It falls back to the usual registration on the kernels before 4.5.
+    #[cfg(all(target_os = "linux", feature = "tcp"))]
+    pub fn new_exclusive(source: S) -> Self
+    where
+        S: AsRawFd,
+    {
+        use super::util::borrow_fd;
+        use rustix::event::epoll::{self, EventData, EventFlags};
+
+        let mut exclusive = false;
+        let mut watcher = Self::register(source, |reactor, source, token| {
+            // the kernel takes no `EPOLLRDHUP` along, a listener is never half closed anyway
+            let flags = EventFlags::EXCLUSIVE | EventFlags::ET | EventFlags::IN;
+            exclusive = epoll::add(
+                borrow_fd(&*reactor.registry),
+                borrow_fd(source),
+                EventData::new_u64(token.0 as u64),
+                flags,
+            )
+            .is_ok();
+            if !exclusive {
+                reactor
+                    .registry
+                    .register(source, token, ALL_INTEREST)
+                    .expect("fail to register source");
+            }
+        });
+        watcher.exclusive = exclusive;
+        watcher
+    }
+
+    /// Returns whether the source is registered by `EPOLLEXCLUSIVE`.
+    #[cfg(all(target_os = "linux", feature = "tcp"))]
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    #[inline]
+    fn register(mut source: S, register: impl FnOnce(&Reactor, &mut S, Token)) -> Self
+    where
+        S: AsRawFd,
+    {
+        let reactor = context::current().reactor();
+        let fd = source.as_raw_fd();
+        let index = reactor.insert(Entry::new(fd));
+        // before its first event
+        if let Some(hooks) = &reactor.hooks {
+            hooks.on_register(fd)
+        }
+        register(&reactor, &mut source, Token(index));
+        #[cfg(feature = "console")]
+        crate::console::emit(crate::console::Event::Register(
+            index,
+            std::any::type_name::<S>(),
+        ));
         Self {
-            entry,
+            reactor,
             index,
             source,
+            read_timeout: Timeout::new(),
+            write_timeout: Timeout::new(),
+            #[cfg(all(target_os = "linux", feature = "tcp"))]
+            exclusive: false,
+        }
+    }
+
+    /// Deregisters the source and takes it out of the watcher.
+    #[allow(unsafe_code)]
+    pub fn into_inner(self) -> S {
+        let mut watcher = ManuallyDrop::new(self);
+        watcher.deregister();
+        // Safety: every field is read exactly once, and the watcher is never dropped.
+        unsafe {
+            drop(ptr::read(&watcher.reactor));
+            drop(ptr::read(&watcher.read_timeout));
+            drop(ptr::read(&watcher.write_timeout));
+            ptr::read(&watcher.source)
         }
     }
 
+    /// Takes the source out of a watcher shared by handles, failing while another
+    /// handle is alive.
+    pub fn try_unwrap(watcher: Arc<Self>) -> io::Result<S> {
+        match Arc::try_unwrap(watcher) {
+            Ok(watcher) => Ok(watcher.into_inner()),
+            Err(_) => Err(io::Error::other("the socket is still shared by its clones")),
+        }
+    }
+
+    fn deregister(&mut self) {
+        let fd = self.reactor.with(self.index, |entry| entry.fd);
+        if let Some(hooks) = &self.reactor.hooks {
+            hooks.on_deregister(fd)
+        }
+        let registry = &self.reactor.registry;
+        // mio tells the sources it has not registered in the debug builds
+        #[cfg(all(target_os = "linux", feature = "tcp"))]
+        let result = if self.exclusive {
+            registry.deregister(&mut mio::unix::SourceFd(&fd))
+        } else {
+            registry.deregister(&mut self.source)
+        };
+        #[cfg(not(all(target_os = "linux", feature = "tcp")))]
+        let result = registry.deregister(&mut self.source);
+        result.expect("fail to deregister source");
+        self.reactor.remove(self.index);
+        #[cfg(feature = "console")]
+        crate::console::emit(crate::console::Event::Deregister(self.index));
+    }
+
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(self.read_timeout.get())
+    }
+
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.read_timeout.set(dur)
+    }
+
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(self.write_timeout.get())
+    }
+
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.write_timeout.set(dur)
+    }
+
     // ## Unused
     //
     // #[inline]
@@ -200,17 +644,22 @@ where
     // }
 
     #[inline]
+    #[cfg_attr(
+        not(any(feature = "tcp", feature = "vsock", feature = "sctp")),
+        allow(dead_code)
+    )]
     pub async fn write_ready(&self) {
         poll_fn(|cx| {
-            let channel = &*self.entry.writer;
-            if !channel.is_ready() {
-                self.entry.write(cx.waker().clone());
-            }
-            if channel.is_ready() {
-                task::Poll::Ready(())
-            } else {
-                task::Poll::Pending
-            }
+            self.reactor.with(self.index, |entry| {
+                if !entry.writer.is_ready() {
+                    entry.write(cx.waker().clone());
+                }
+                if entry.writer.is_ready() {
+                    task::Poll::Ready(())
+                } else {
+                    task::Poll::Pending
+                }
+            })
         })
         .await
     }
@@ -224,11 +673,18 @@ where
     where
         F: FnMut(&'a S) -> io::Result<R>,
     {
+        futures::ready!(coop::poll_proceed(cx));
         let mut poll = may_block(f(&self.source));
         if poll.is_pending() {
-            self.entry.read(cx.waker().clone());
+            self.reactor
+                .with(self.index, |entry| entry.read(cx.waker().clone()));
             poll = may_block(f(&self.source));
         }
+        if poll.is_pending() {
+            return self.read_timeout.poll_elapsed(cx);
+        }
+        self.read_timeout.reset();
+        coop::consume();
         poll
     }
 
@@ -241,11 +697,18 @@ where
     where
         F: FnMut(&'a S) -> io::Result<R>,
     {
+        futures::ready!(coop::poll_proceed(cx));
         let mut poll = may_block(f(&self.source));
         if poll.is_pending() {
-            self.entry.write(cx.waker().clone());
+            self.reactor
+                .with(self.index, |entry| entry.write(cx.waker().clone()));
             poll = may_block(f(&self.source));
         }
+        if poll.is_pending() {
+            return self.write_timeout.poll_elapsed(cx);
+        }
+        self.write_timeout.reset();
+        coop::consume();
         poll
     }
 }
@@ -255,11 +718,7 @@ where
     S: event::Source,
 {
     fn drop(&mut self) {
-        REACTOR
-            .registry
-            .deregister(&mut self.source)
-            .expect("fail to deregister source");
-        REACTOR.remove(self.index);
+        self.deregister()
     }
 }
 
@@ -284,3 +743,35 @@ where
             .finish()
     }
 }
+
+#[cfg(all(test, feature = "udp"))]
+mod tests {
+    use super::ENTRIES_LOCK_POISONED;
+    use crate::net::UdpSocket;
+    use crate::runtime::Builder;
+
+    #[test]
+    fn entries() {
+        let runtime = Builder::new().io_capacity(64).build().unwrap();
+        let reactor = runtime.handle().inner.reactor();
+        assert!(
+            reactor
+                .entries
+                .read()
+                .expect(ENTRIES_LOCK_POISONED)
+                .capacity()
+                >= 64
+        );
+        runtime.block_on(async {
+            let sockets = (0..8)
+                .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(8, reactor.registrations());
+            drop(sockets);
+            assert_eq!(0, reactor.registrations());
+            // the slots are reused, so the tokens stay small
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            assert!(socket.0.index < 8);
+        });
+    }
+}
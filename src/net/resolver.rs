@@ -0,0 +1,478 @@
+use super::util::resolve_none;
+use crate::task::spawn_blocking;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The default TTL of the records whose lookup tells none.
+const TTL: Duration = Duration::from_secs(30);
+
+/// The default TTL of the failures.
+const NEGATIVE_TTL: Duration = Duration::from_secs(5);
+
+/// The default max TTL.
+const MAX_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The default max number of hosts cached.
+const CAPACITY: usize = 1024;
+
+/// An exception
+const CACHE_LOCK_POISONED: &str = "resolver cache lock poisoned";
+
+/// A lookup of the addresses of a host and their TTL.
+type Lookup = dyn Fn(&str) -> io::Result<(Vec<IpAddr>, Option<Duration>)> + Send + Sync;
+
+/// An async resolver caching the addresses of the hosts, for the TTLs of their records.
+///
+/// Unlike a [`Resolver`], which calls `getaddrinfo` on the blocking pool every time, a
+/// `CachingResolver` looks a host up once and answers from its cache until the records
+/// expire. The failures are cached as well, for a shorter [negative TTL], so a host which
+/// does not resolve is not looked up again by every request.
+///
+/// A resolver is cheap to clone, the clones share the cache. Every runtime has one, returned
+/// by [`Handle::resolver`], which can be set by [`Builder::resolver`].
+///
+/// The default lookup is `getaddrinfo`, which tells no TTL, so the records expire after the
+/// [default TTL]. A [custom lookup], like a DNS client, can tell the TTLs of the records.
+///
+/// [`Resolver`]: trait.Resolver.html
+/// [negative TTL]: struct.CachingResolverBuilder.html#method.negative_ttl
+/// [`Handle::resolver`]: ../runtime/struct.Handle.html#method.resolver
+/// [`Builder::resolver`]: ../runtime/struct.Builder.html#method.resolver
+/// [default TTL]: struct.CachingResolverBuilder.html#method.ttl
+/// [custom lookup]: struct.CachingResolverBuilder.html#method.lookup
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(not(feature = "tcp"))]
+/// # fn main() {}
+/// # #[cfg(feature = "tcp")]
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use tio::net::TcpStream;
+/// use tio::runtime::Handle;
+///
+/// let resolver = Handle::current().resolver().clone();
+/// let addrs = resolver.resolve("github.com", 443).await?;
+/// let stream = TcpStream::connect(addrs.as_slice()).await?;
+/// // answered from the cache
+/// let addrs = resolver.resolve("github.com", 443).await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Clone)]
+pub struct CachingResolver {
+    shared: Arc<Shared>,
+}
+
+/// Builds a [`CachingResolver`] with custom configuration values.
+///
+/// [`CachingResolver`]: struct.CachingResolver.html
+///
+/// # Examples
+///
+/// ```
+/// use std::net::{IpAddr, Ipv4Addr};
+/// use std::time::Duration;
+///
+/// use tio::net::CachingResolverBuilder;
+///
+/// let resolver = CachingResolverBuilder::new()
+///     .negative_ttl(Duration::from_secs(1))
+///     .capacity(64)
+///     .lookup(|host| match host {
+///         "db.internal" => Ok((vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7))], None)),
+///         _ => Err(std::io::ErrorKind::NotFound.into()),
+///     })
+///     .build();
+/// ```
+pub struct CachingResolverBuilder {
+    ttl: Duration,
+    min_ttl: Duration,
+    max_ttl: Duration,
+    negative_ttl: Duration,
+    capacity: usize,
+    lookup: Option<Arc<Lookup>>,
+}
+
+struct Shared {
+    ttl: Duration,
+    min_ttl: Duration,
+    max_ttl: Duration,
+    negative_ttl: Duration,
+    capacity: usize,
+    lookup: Arc<Lookup>,
+    cache: Mutex<HashMap<String, Entry>>,
+}
+
+struct Entry {
+    answer: Answer,
+    expires: Instant,
+}
+
+enum Answer {
+    Addrs(Arc<[IpAddr]>),
+    Error(io::ErrorKind, String),
+}
+
+impl CachingResolver {
+    /// Creates a resolver with the default configuration.
+    #[inline]
+    pub fn new() -> Self {
+        CachingResolverBuilder::new().build()
+    }
+
+    /// Resolves the addresses of `host`, with `port`.
+    ///
+    /// The host is either an IP address, which is returned as it is, or a domain name, which is
+    /// answered from the cache while its records are alive, or looked up on the blocking pool.
+    pub async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        let with_port = |addrs: &[IpAddr]| {
+            addrs.iter().map(|&ip| SocketAddr::new(ip, port)).collect()
+        };
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![SocketAddr::new(ip, port)]);
+        }
+        let host = host.to_ascii_lowercase();
+        if let Some(answer) = self.cached(&host) {
+            return answer.map(|addrs| with_port(&addrs));
+        }
+
+        let lookup = self.shared.lookup.clone();
+        let name = host.clone();
+        let result = spawn_blocking(move || lookup(&name)).await?;
+        let now = Instant::now();
+        let shared = &self.shared;
+        let (answer, ttl) = match result {
+            Ok((addrs, ttl)) => {
+                let ttl = ttl.unwrap_or(shared.ttl).max(shared.min_ttl);
+                (Answer::Addrs(addrs.into()), ttl.min(shared.max_ttl))
+            }
+            Err(err) => {
+                let answer = Answer::Error(err.kind(), err.to_string());
+                (answer, shared.negative_ttl)
+            }
+        };
+        let ret = answer.to_result().map(|addrs| with_port(&addrs));
+        if ttl > Duration::from_secs(0) && shared.capacity > 0 {
+            let mut cache = shared.cache.lock().expect(CACHE_LOCK_POISONED);
+            if cache.len() >= shared.capacity && !cache.contains_key(&host) {
+                evict(&mut cache, now);
+            }
+            let expires = now + ttl;
+            cache.insert(host, Entry { answer, expires });
+        }
+        ret
+    }
+
+    /// Removes every host from the cache, so they are looked up again on the next resolution.
+    #[inline]
+    pub fn clear(&self) {
+        self.shared.cache.lock().expect(CACHE_LOCK_POISONED).clear()
+    }
+
+    /// Returns the answer cached for `host`, if it is alive.
+    fn cached(&self, host: &str) -> Option<io::Result<Arc<[IpAddr]>>> {
+        let mut cache = self.shared.cache.lock().expect(CACHE_LOCK_POISONED);
+        let entry = cache.get(host)?;
+        if entry.expires <= Instant::now() {
+            cache.remove(host);
+            return None;
+        }
+        Some(entry.answer.to_result())
+    }
+}
+
+/// Makes room for a host in a full cache, by removing the expired hosts, or the one expiring
+/// first if none is.
+fn evict(cache: &mut HashMap<String, Entry>, now: Instant) {
+    let len = cache.len();
+    cache.retain(|_, entry| entry.expires > now);
+    if cache.len() < len {
+        return;
+    }
+    let first = cache
+        .iter()
+        .min_by_key(|(_, entry)| entry.expires)
+        .map(|(host, _)| host.clone());
+    if let Some(host) = first {
+        cache.remove(&host);
+    }
+}
+
+impl Answer {
+    #[inline]
+    fn to_result(&self) -> io::Result<Arc<[IpAddr]>> {
+        match self {
+            Answer::Addrs(addrs) => Ok(addrs.clone()),
+            Answer::Error(kind, msg) => Err(io::Error::new(*kind, msg.as_str())),
+        }
+    }
+}
+
+impl Default for CachingResolver {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for CachingResolver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let shared = &self.shared;
+        f.debug_struct("CachingResolver")
+            .field("ttl", &shared.ttl)
+            .field("min_ttl", &shared.min_ttl)
+            .field("max_ttl", &shared.max_ttl)
+            .field("negative_ttl", &shared.negative_ttl)
+            .field("capacity", &shared.capacity)
+            .finish()
+    }
+}
+
+impl CachingResolverBuilder {
+    /// Creates a builder with the default configuration.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            ttl: TTL,
+            min_ttl: Duration::from_secs(0),
+            max_ttl: MAX_TTL,
+            negative_ttl: NEGATIVE_TTL,
+            capacity: CAPACITY,
+            lookup: None,
+        }
+    }
+
+    /// Sets how long the records are cached when the lookup tells no TTL, like
+    /// `getaddrinfo`.
+    ///
+    /// The default is 30 seconds.
+    #[inline]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the least time the records are cached, even if their TTL is shorter.
+    ///
+    /// The default is zero, with which the records of a zero TTL are not cached.
+    #[inline]
+    pub fn min_ttl(mut self, ttl: Duration) -> Self {
+        self.min_ttl = ttl;
+        self
+    }
+
+    /// Sets the most time the records are cached, even if their TTL is longer.
+    ///
+    /// The default is one hour.
+    #[inline]
+    pub fn max_ttl(mut self, ttl: Duration) -> Self {
+        self.max_ttl = ttl;
+        self
+    }
+
+    /// Sets how long the failures to look a host up are cached.
+    ///
+    /// The default is 5 seconds, and zero disables the negative caching.
+    #[inline]
+    pub fn negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Sets the max number of hosts cached.
+    ///
+    /// Once the cache is full, the expired hosts are removed to make room for a new one, or
+    /// the one expiring first if none is. The default is 1024.
+    #[inline]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the lookup of the addresses of a host, instead of `getaddrinfo`.
+    ///
+    /// The lookup runs on the blocking pool, and returns the addresses with the TTL of their
+    /// records, if it knows it.
+    #[inline]
+    pub fn lookup<F>(mut self, f: F) -> Self
+    where
+        F: 'static
+            + Send
+            + Sync
+            + Fn(&str) -> io::Result<(Vec<IpAddr>, Option<Duration>)>,
+    {
+        self.lookup = Some(Arc::new(f));
+        self
+    }
+
+    /// Creates the configured resolver, with an empty cache.
+    pub fn build(self) -> CachingResolver {
+        CachingResolver {
+            shared: Arc::new(Shared {
+                ttl: self.ttl,
+                min_ttl: self.min_ttl,
+                max_ttl: self.max_ttl,
+                negative_ttl: self.negative_ttl,
+                capacity: self.capacity,
+                lookup: self.lookup.unwrap_or_else(|| Arc::new(getaddrinfo)),
+                cache: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+}
+
+/// Looks a host up by `getaddrinfo`, which tells no TTL.
+fn getaddrinfo(host: &str) -> io::Result<(Vec<IpAddr>, Option<Duration>)> {
+    let addrs = (host, 0)
+        .to_socket_addrs()?
+        .map(|addr| addr.ip())
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        return Err(resolve_none());
+    }
+    Ok((addrs, None))
+}
+
+impl Default for CachingResolverBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for CachingResolverBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachingResolverBuilder")
+            .field("ttl", &self.ttl)
+            .field("min_ttl", &self.min_ttl)
+            .field("max_ttl", &self.max_ttl)
+            .field("negative_ttl", &self.negative_ttl)
+            .field("capacity", &self.capacity)
+            .field("lookup", &self.lookup.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachingResolverBuilder;
+    use crate::runtime::{Builder, Handle};
+    use crate::task::block_on;
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    /// Returns a builder whose lookup counts the calls, and resolves the hosts starting
+    /// with "ok" with the TTL of their length in milliseconds.
+    fn counting() -> (CachingResolverBuilder, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let builder = CachingResolverBuilder::new().lookup({
+            let calls = calls.clone();
+            move |host| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if host.starts_with("ok") {
+                    let ttl = Duration::from_millis(host.len() as u64 * 10);
+                    Ok((vec![IP], Some(ttl)))
+                } else {
+                    Err(io::Error::new(io::ErrorKind::NotFound, "no such host"))
+                }
+            }
+        });
+        (builder, calls)
+    }
+
+    #[test]
+    fn ttl() -> io::Result<()> {
+        block_on(async {
+            let (builder, calls) = counting();
+            let resolver = builder.build();
+            // TTL of 40ms
+            assert_eq!(
+                vec![SocketAddr::new(IP, 80)],
+                resolver.resolve("ok.a", 80).await?
+            );
+            assert_eq!(
+                vec![SocketAddr::new(IP, 443)],
+                resolver.resolve("OK.a", 443).await?
+            );
+            assert_eq!(1, calls.load(Ordering::SeqCst));
+            thread::sleep(Duration::from_millis(50));
+            resolver.resolve("ok.a", 80).await?;
+            assert_eq!(2, calls.load(Ordering::SeqCst));
+
+            // the literals are not looked up
+            let addrs = resolver.resolve("::1", 80).await?;
+            assert_eq!(vec!["[::1]:80".parse::<SocketAddr>().unwrap()], addrs);
+            assert_eq!(2, calls.load(Ordering::SeqCst));
+
+            // clamped
+            let (builder, calls) = counting();
+            let resolver = builder.max_ttl(Duration::from_millis(0)).build();
+            resolver.resolve("ok.a", 80).await?;
+            resolver.resolve("ok.a", 80).await?;
+            assert_eq!(2, calls.load(Ordering::SeqCst));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn negative() {
+        block_on(async {
+            let (builder, calls) = counting();
+            let resolver = builder.negative_ttl(Duration::from_secs(60)).build();
+            for _ in 0..2 {
+                let err = resolver.resolve("missing", 80).await.unwrap_err();
+                assert_eq!(io::ErrorKind::NotFound, err.kind());
+                assert_eq!("no such host", err.to_string());
+            }
+            assert_eq!(1, calls.load(Ordering::SeqCst));
+            resolver.clear();
+            resolver.resolve("missing", 80).await.unwrap_err();
+            assert_eq!(2, calls.load(Ordering::SeqCst));
+        })
+    }
+
+    #[test]
+    fn capacity() -> io::Result<()> {
+        block_on(async {
+            let (builder, calls) = counting();
+            let resolver = builder.capacity(2).build();
+            // expiring in 1s, 1.1s and 1.2s
+            let hosts = ["ok".repeat(50), "ok".repeat(55), "ok".repeat(60)];
+            for host in &hosts {
+                resolver.resolve(host, 80).await?;
+            }
+            // the first one is evicted
+            resolver.resolve(&hosts[2], 80).await?;
+            resolver.resolve(&hosts[1], 80).await?;
+            assert_eq!(3, calls.load(Ordering::SeqCst));
+            resolver.resolve(&hosts[0], 80).await?;
+            assert_eq!(4, calls.load(Ordering::SeqCst));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn runtime() -> io::Result<()> {
+        let (builder, calls) = counting();
+        let runtime = Builder::new().resolver(builder.build()).build()?;
+        runtime.block_on(async {
+            Handle::current().resolver().resolve("ok.a", 80).await?;
+            Ok::<_, io::Error>(())
+        })?;
+        runtime.block_on(runtime.handle().resolver().resolve("ok.a", 80))?;
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+//! SCTP sockets for the protocols of messages over multiple streams.
+//!
+//! SCTP carries messages rather than a stream of bytes, over many streams of an association,
+//! each with its own order, so a lost message only stalls its own stream. It comes in two
+//! styles of sockets: the one-to-one [`SctpListener`] and [`SctpStream`], which work like
+//! TCP, and the one-to-many [`SctpSocket`], which talks to many peers on one socket, like UDP.
+//! Every message is sent and received with a [`MessageInfo`], telling its stream and its
+//! payload protocol, like Diameter or M3UA.
+//!
+//! [`SctpListener`]: struct.SctpListener.html
+//! [`SctpStream`]: struct.SctpStream.html
+//! [`SctpSocket`]: struct.SctpSocket.html
+//! [`MessageInfo`]: struct.MessageInfo.html
+//!
+//! # Examples
+//!
+//! An echo server, which replies on the stream of each message:
+//!
+//! ```no_run
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use tio::net::sctp::SctpSocket;
+//!
+//! let socket = SctpSocket::bind("127.0.0.1:3868".parse().unwrap())?;
+//! let mut buf = vec![0u8; 4096];
+//!
+//! loop {
+//!     let (n, peer, info) = socket.recv_from(&mut buf).await?;
+//!     socket.send_to(&buf[..n], peer, info).await?;
+//! }
+//! #
+//! # Ok(()) }) }
+//! ```
+
+mod listener;
+mod socket;
+mod stream;
+
+pub use listener::SctpListener;
+pub use socket::SctpSocket;
+pub use stream::SctpStream;
+
+use crate::net::sys;
+use rustix::net::{ipproto, AddressFamily, SocketFlags, SocketType};
+use std::io;
+use std::net::SocketAddr;
+use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
+
+/// The stream and the payload protocol of an SCTP message.
+///
+/// The payload protocol identifier is opaque to SCTP, and sent in the network order.
+///
+/// # Examples
+///
+/// ```
+/// use tio::net::sctp::MessageInfo;
+///
+/// // a Diameter message on the stream 1
+/// let info = MessageInfo::new(1).with_ppid(46);
+/// assert_eq!(1, info.stream());
+/// assert_eq!(46, info.ppid());
+/// assert!(!info.is_unordered());
+/// ```
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct MessageInfo {
+    stream: u16,
+    ppid: u32,
+    unordered: bool,
+}
+
+impl MessageInfo {
+    /// Creates the information of a message on the stream, of the payload protocol 0.
+    #[inline]
+    pub fn new(stream: u16) -> Self {
+        Self {
+            stream,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the payload protocol identifier.
+    #[inline]
+    pub fn with_ppid(mut self, ppid: u32) -> Self {
+        self.ppid = ppid;
+        self
+    }
+
+    /// Sends the message out of the order of its stream, as soon as possible.
+    #[inline]
+    pub fn with_unordered(mut self, unordered: bool) -> Self {
+        self.unordered = unordered;
+        self
+    }
+
+    /// Returns the stream number.
+    #[inline]
+    pub fn stream(&self) -> u16 {
+        self.stream
+    }
+
+    /// Returns the payload protocol identifier.
+    #[inline]
+    pub fn ppid(&self) -> u32 {
+        self.ppid
+    }
+
+    /// Returns `true` if the message is out of the order of its stream.
+    #[inline]
+    pub fn is_unordered(&self) -> bool {
+        self.unordered
+    }
+
+    fn to_sndinfo(self) -> libc::sctp_sndinfo {
+        let flags = if self.unordered {
+            libc::SCTP_UNORDERED as u16
+        } else {
+            0
+        };
+        libc::sctp_sndinfo {
+            snd_sid: self.stream,
+            snd_flags: flags,
+            snd_ppid: self.ppid.to_be(),
+            snd_context: 0,
+            snd_assoc_id: 0,
+        }
+    }
+
+    fn from_rcvinfo(info: &libc::sctp_rcvinfo) -> Self {
+        Self {
+            stream: info.rcv_sid,
+            ppid: u32::from_be(info.rcv_ppid),
+            unordered: info.rcv_flags & libc::SCTP_UNORDERED as u16 != 0,
+        }
+    }
+}
+
+/// Creates a non-blocking SCTP socket of the given type, for the family of `addr`.
+fn socket(addr: &SocketAddr, ty: SocketType) -> io::Result<OwnedFd> {
+    let family = match addr {
+        SocketAddr::V4(_) => AddressFamily::INET,
+        SocketAddr::V6(_) => AddressFamily::INET6,
+    };
+    let flags = SocketFlags::NONBLOCK | SocketFlags::CLOEXEC;
+    let fd = rustix::net::socket_with(family, ty, flags, Some(ipproto::SCTP))?;
+    recv_info(fd.as_fd())?;
+    Ok(fd)
+}
+
+/// Asks for the information of every message received on `fd`.
+#[inline]
+fn recv_info(fd: BorrowedFd<'_>) -> io::Result<()> {
+    sys::setsockopt(
+        fd,
+        libc::IPPROTO_SCTP,
+        libc::SCTP_RECVRCVINFO,
+        &1 as &libc::c_int,
+    )
+}
+
+/// Sends a message of the information, to `addr` if any.
+#[inline]
+fn send(
+    fd: BorrowedFd<'_>,
+    buf: &[u8],
+    addr: Option<SocketAddr>,
+    info: MessageInfo,
+) -> io::Result<usize> {
+    let sndinfo = info.to_sndinfo();
+    sys::sendmsg(
+        fd,
+        buf,
+        addr,
+        libc::IPPROTO_SCTP,
+        libc::SCTP_SNDINFO,
+        &sndinfo,
+    )
+}
+
+/// Receives a message, skipping the notifications.
+fn recv(
+    fd: BorrowedFd<'_>,
+    buf: &mut [u8],
+) -> io::Result<(usize, Option<SocketAddr>, MessageInfo)> {
+    loop {
+        let msg = sys::recvmsg::<libc::sctp_rcvinfo>(
+            fd,
+            buf,
+            libc::IPPROTO_SCTP,
+            libc::SCTP_RCVINFO,
+            0,
+        )?;
+        if msg.flags & libc::MSG_NOTIFICATION != 0 {
+            continue;
+        }
+        let info = msg
+            .cmsg
+            .as_ref()
+            .map(MessageInfo::from_rcvinfo)
+            .unwrap_or_default();
+        return Ok((msg.len, msg.addr, info));
+    }
+}
+
+/// Returns `true` if `err` tells the kernel has no SCTP.
+#[cfg(test)]
+fn unsupported(err: &io::Error) -> bool {
+    let code = err.raw_os_error();
+    code == Some(libc::EPROTONOSUPPORT) || code == Some(libc::ESOCKTNOSUPPORT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageInfo;
+
+    #[test]
+    fn message_info() {
+        let info = MessageInfo::new(3).with_ppid(46).with_unordered(true);
+        let sndinfo = info.to_sndinfo();
+        assert_eq!(3, sndinfo.snd_sid);
+        assert_eq!(46u32.to_be(), sndinfo.snd_ppid);
+        assert_eq!(libc::SCTP_UNORDERED as u16, sndinfo.snd_flags);
+
+        let rcvinfo = libc::sctp_rcvinfo {
+            rcv_sid: sndinfo.snd_sid,
+            rcv_ssn: 0,
+            rcv_flags: sndinfo.snd_flags,
+            rcv_ppid: sndinfo.snd_ppid,
+            rcv_tsn: 0,
+            rcv_cumtsn: 0,
+            rcv_context: 0,
+            rcv_assoc_id: 0,
+        };
+        assert_eq!(info, MessageInfo::from_rcvinfo(&rcvinfo));
+    }
+}
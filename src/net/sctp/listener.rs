@@ -0,0 +1,202 @@
+use super::SctpStream;
+use crate::net::poll::{Fd, Watcher};
+use crate::net::util;
+use futures::task::{Context, Poll};
+use futures::{future, Stream};
+use rustix::net::{SocketFlags, SocketType};
+use std::convert::TryFrom;
+use std::io;
+use std::net::SocketAddr;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// The length of the queue of pending associations, the same as mio's.
+const BACKLOG: i32 = 1024;
+
+/// A one-to-one SCTP server, listening for associations.
+///
+/// After creating a `SctpListener` by [`bind`]ing it to an address, it listens for incoming
+/// associations. These can be accepted by [`accept`], or by awaiting elements from the
+/// listener as a stream.
+///
+/// The socket will be closed when the value is dropped.
+///
+/// [`bind`]: #method.bind
+/// [`accept`]: #method.accept
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::prelude::*;
+/// use tio::net::sctp::{MessageInfo, SctpListener};
+///
+/// let mut listener = SctpListener::bind("127.0.0.1:3868".parse().unwrap())?;
+///
+/// while let Some(stream) = listener.next().await {
+///     let stream = stream?;
+///     stream.send(b"hello world", MessageInfo::new(0)).await?;
+/// }
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SctpListener(Arc<Watcher<Fd>>);
+
+impl SctpListener {
+    /// Creates a new `SctpListener` bound to the given address.
+    ///
+    /// Binding with a port number of 0 picks a free port, which can be queried by
+    /// [`local_addr`].
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`local_addr`]: #method.local_addr
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let fd = super::socket(&addr, SocketType::STREAM)?;
+        rustix::net::bind(&fd, &addr)?;
+        rustix::net::listen(&fd, BACKLOG)?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Accepts a new incoming association to this listener.
+    ///
+    /// When an association is established, the corresponding stream and the address of the
+    /// peer will be returned.
+    pub async fn accept(&self) -> io::Result<(SctpStream, SocketAddr)> {
+        future::poll_fn(|cx| self.poll_accept(cx)).await
+    }
+
+    /// Polls to accept a new incoming association to this listener.
+    ///
+    /// The current task is woken once an association may be accepted, if there is none yet.
+    pub fn poll_accept(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<(SctpStream, SocketAddr)>> {
+        let flags = SocketFlags::NONBLOCK | SocketFlags::CLOEXEC;
+        let (fd, addr) = futures::ready!(self
+            .0
+            .poll_read_with(cx, |fd| Ok(rustix::net::acceptfrom_with(fd, flags)?)))?;
+        let addr = match addr {
+            Some(addr) => SocketAddr::try_from(addr)?,
+            None => return Poll::Ready(Err(io::ErrorKind::InvalidData.into())),
+        };
+        super::recv_info(fd.as_fd())?;
+        Poll::Ready(Ok((SctpStream::new(fd), addr)))
+    }
+
+    /// Returns the local address that this listener is bound to.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(SocketAddr::try_from(rustix::net::getsockname(self)?)?)
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks accepting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+}
+
+impl Stream for SctpListener {
+    type Item = io::Result<SctpStream>;
+
+    /// Returns a stream of incoming associations.
+    ///
+    /// Iterating over this stream is equivalent to calling [`accept`] in a loop. The stream
+    /// of associations is infinite, i.e awaiting the next association will never result in
+    /// [`None`].
+    ///
+    /// [`accept`]: #method.accept
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    #[inline]
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let (stream, _) = futures::ready!(self.poll_accept(cx))?;
+        Poll::Ready(Some(Ok(stream)))
+    }
+}
+
+impl AsFd for SctpListener {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for SctpListener {
+    /// Share raw fd of `SctpListener`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SctpListener;
+    use crate::net::sctp::{MessageInfo, SctpStream};
+    use crate::task::block_on;
+    use std::io;
+
+    #[test]
+    fn one_to_one() -> io::Result<()> {
+        block_on(async {
+            let listener = match SctpListener::bind("127.0.0.1:0".parse().unwrap()) {
+                Err(err) if super::super::unsupported(&err) => return Ok(()),
+                ret => ret?,
+            };
+            let addr = listener.local_addr()?;
+            let client = SctpStream::connect(addr).await?;
+            let (server, peer) = listener.accept().await?;
+            assert_eq!(client.local_addr()?, peer);
+            assert_eq!(addr, client.peer_addr()?);
+
+            let info = MessageInfo::new(1).with_ppid(46);
+            client.send(b"hello", info).await?;
+            let mut buf = [0; 16];
+            let (n, received) = server.recv(&mut buf).await?;
+            assert_eq!(b"hello", &buf[..n]);
+            assert_eq!(info, received);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,238 @@
+use super::MessageInfo;
+use crate::net::poll::{Fd, Watcher};
+use crate::net::util;
+use futures::future;
+use rustix::net::SocketType;
+use std::convert::TryFrom;
+use std::io;
+use std::net::SocketAddr;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The length of the queue of pending associations, the same as mio's.
+const BACKLOG: i32 = 1024;
+
+/// A one-to-many SCTP socket, of an association to every peer.
+///
+/// An association is set up by the first message sent to a peer, or by a peer sending to
+/// this socket, and every message received tells the address of its peer.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use tio::net::sctp::{MessageInfo, SctpSocket};
+///
+/// let socket = SctpSocket::bind("127.0.0.1:0".parse().unwrap())?;
+/// let peer = "127.0.0.1:2905".parse().unwrap();
+/// socket.send_to(b"hello world", peer, MessageInfo::new(1).with_ppid(3)).await?;
+///
+/// let mut buf = vec![0u8; 1024];
+/// let (n, peer, info) = socket.recv_from(&mut buf).await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SctpSocket(Arc<Watcher<Fd>>);
+
+impl SctpSocket {
+    /// Creates a new `SctpSocket` bound to the given address, accepting the associations of
+    /// the peers.
+    ///
+    /// Binding with a port number of 0 picks a free port, which can be queried by
+    /// [`local_addr`].
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`local_addr`]: #method.local_addr
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let fd = super::socket(&addr, SocketType::SEQPACKET)?;
+        rustix::net::bind(&fd, &addr)?;
+        rustix::net::listen(&fd, BACKLOG)?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns the local address that this socket is bound to.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(SocketAddr::try_from(rustix::net::getsockname(self)?)?)
+    }
+
+    /// Sends a message with the information to the given address, setting up an association
+    /// to it if there is none.
+    ///
+    /// On success, returns the number of bytes written.
+    pub async fn send_to(
+        &self,
+        buf: &[u8],
+        addr: SocketAddr,
+        info: MessageInfo,
+    ) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0
+                .poll_write_with(cx, |fd| super::send(fd.as_fd(), buf, Some(addr), info))
+        })
+        .await
+    }
+
+    /// Receives a message from any peer.
+    ///
+    /// On success, returns the number of bytes read, the address of the peer and the
+    /// information of the message. A message longer than `buf` is received across multiple
+    /// calls.
+    pub async fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr, MessageInfo)> {
+        future::poll_fn(|cx| {
+            self.0
+                .poll_read_with(cx, |fd| match super::recv(fd.as_fd(), &mut *buf)? {
+                    (n, Some(addr), info) => Ok((n, addr, info)),
+                    (_, None, _) => Err(io::ErrorKind::InvalidData.into()),
+                })
+        })
+        .await
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks waiting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do. Its timeouts start unset.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every receive which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every send which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+}
+
+impl AsFd for SctpSocket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for SctpSocket {
+    /// Share raw fd of `SctpSocket`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SctpSocket;
+    use crate::net::sctp::MessageInfo;
+    use crate::task::block_on;
+    use std::io;
+
+    #[test]
+    fn one_to_many() -> io::Result<()> {
+        block_on(async {
+            let server = match SctpSocket::bind("127.0.0.1:0".parse().unwrap()) {
+                Err(err) if super::super::unsupported(&err) => return Ok(()),
+                ret => ret?,
+            };
+            let client = SctpSocket::bind("127.0.0.1:0".parse().unwrap())?;
+            let info = MessageInfo::new(2).with_ppid(3);
+            client.send_to(b"hello", server.local_addr()?, info).await?;
+
+            let mut buf = [0; 16];
+            let (n, peer, received) = server.recv_from(&mut buf).await?;
+            assert_eq!(b"hello", &buf[..n]);
+            assert_eq!(client.local_addr()?, peer);
+            assert_eq!(info, received);
+
+            // reply on the same stream
+            server.send_to(b"world", peer, received).await?;
+            let (n, _, received) = client.recv_from(&mut buf).await?;
+            assert_eq!(b"world", &buf[..n]);
+            assert_eq!(info, received);
+            Ok(())
+        })
+    }
+}
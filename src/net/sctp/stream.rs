@@ -0,0 +1,229 @@
+use super::MessageInfo;
+use crate::net::poll::{Fd, Watcher};
+use crate::net::util;
+use futures::future;
+use rustix::io::Errno;
+use rustix::net::SocketType;
+use std::convert::TryFrom;
+use std::io;
+use std::net::{Shutdown, SocketAddr};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A one-to-one SCTP socket, of an association between a local and a remote socket.
+///
+/// A `SctpStream` can either be created by connecting to an endpoint, via the [`connect`]
+/// method, or by [accepting] an association from a [listener]. Messages are sent and received
+/// with their [`MessageInfo`].
+///
+/// [`connect`]: #method.connect
+/// [accepting]: struct.SctpListener.html#method.accept
+/// [listener]: struct.SctpListener.html
+/// [`MessageInfo`]: struct.MessageInfo.html
+///
+/// ## Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use tio::net::sctp::{MessageInfo, SctpStream};
+///
+/// let stream = SctpStream::connect("127.0.0.1:3868".parse().unwrap()).await?;
+/// stream.send(b"hello world", MessageInfo::new(0).with_ppid(46)).await?;
+///
+/// let mut buf = vec![0u8; 1024];
+/// let (n, info) = stream.recv(&mut buf).await?;
+/// println!("{} bytes on the stream {}", n, info.stream());
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SctpStream(Arc<Watcher<Fd>>);
+
+impl SctpStream {
+    #[inline]
+    pub(super) fn new(fd: OwnedFd) -> Self {
+        Self(Arc::new(Watcher::new(Fd(fd))))
+    }
+
+    /// Connects to the SCTP socket at the given address.
+    ///
+    /// The returned future is resolved once the association is established, or fails if the
+    /// peer aborts it or does not answer in time.
+    pub async fn connect(addr: SocketAddr) -> io::Result<Self> {
+        let fd = super::socket(&addr, SocketType::STREAM)?;
+        match rustix::net::connect(&fd, &addr) {
+            Ok(()) | Err(Errno::INPROGRESS) => (),
+            Err(err) => return Err(err.into()),
+        }
+        let stream = Self::new(fd);
+        // wait for the association established
+        stream.0.write_ready().await;
+        match rustix::net::sockopt::socket_error(&stream)? {
+            Ok(()) => Ok(stream),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the local address that this stream is bound to.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(SocketAddr::try_from(rustix::net::getsockname(self)?)?)
+    }
+
+    /// Returns the primary address of the peer of this stream.
+    #[inline]
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        match rustix::net::getpeername(self)? {
+            Some(addr) => Ok(SocketAddr::try_from(addr)?),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+
+    /// Sends a message with the information to the peer.
+    ///
+    /// On success, returns the number of bytes written.
+    pub async fn send(&self, buf: &[u8], info: MessageInfo) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0
+                .poll_write_with(cx, |fd| super::send(fd.as_fd(), buf, None, info))
+        })
+        .await
+    }
+
+    /// Receives a message from the peer.
+    ///
+    /// On success, returns the number of bytes read and the information of the message. A
+    /// message longer than `buf` is received across multiple calls, and 0 bytes are read
+    /// once the peer has shut the association down.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<(usize, MessageInfo)> {
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |fd| {
+                let (n, _, info) = super::recv(fd.as_fd(), &mut *buf)?;
+                Ok((n, info))
+            })
+        })
+        .await
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks waiting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do. Its timeouts start unset.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every receive which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every send which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Shuts down the read, write, or both halves of this association.
+    ///
+    /// Shutting down the write half, or both, shuts the association down gracefully, once
+    /// every message sent is acknowledged.
+    #[inline]
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        let how = match how {
+            Shutdown::Read => rustix::net::Shutdown::Read,
+            Shutdown::Write => rustix::net::Shutdown::Write,
+            Shutdown::Both => rustix::net::Shutdown::Both,
+        };
+        Ok(rustix::net::shutdown(self, how)?)
+    }
+}
+
+impl AsFd for SctpStream {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for SctpStream {
+    /// Share raw fd of `SctpStream`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
@@ -0,0 +1,231 @@
+use crate::sync::CancellationToken;
+use crate::task::{self, JoinSet};
+use futures::{future, pin_mut, Stream, StreamExt};
+use std::future::Future;
+use std::io;
+use std::task::Poll;
+use std::time::Duration;
+
+/// A server of the connections of a listener, shutting down gracefully.
+///
+/// The server spawns a task for every connection, and on shutdown it stops accepting, tells
+/// the tasks by their [`CancellationToken`] to finish, and waits for them to drain, aborting
+/// the ones left once the [grace period] is over.
+///
+/// The listener is any stream of connections, like a [`TcpListener`], a [`MultiListener`] or a
+/// [`UnixListener`].
+///
+/// [`CancellationToken`]: ../sync/struct.CancellationToken.html
+/// [grace period]: #method.grace_period
+/// [`TcpListener`]: struct.TcpListener.html
+/// [`MultiListener`]: struct.MultiListener.html
+/// [`UnixListener`]: struct.UnixListener.html
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(not(feature = "tcp"))]
+/// # fn main() {}
+/// # #[cfg(feature = "tcp")]
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use std::time::Duration;
+///
+/// use futures::prelude::*;
+/// use tio::net::{Server, TcpListener};
+/// use tio::sync::CancellationToken;
+///
+/// let listener = TcpListener::bind("127.0.0.1:8080")?;
+/// let shutdown = CancellationToken::new();
+/// // cancelled on a signal, for example
+/// let _guard = shutdown.clone().drop_guard();
+///
+/// let aborted = Server::new(listener)
+///     .grace_period(Duration::from_secs(30))
+///     .serve(shutdown, |mut stream, token| async move {
+///         while !token.is_cancelled() {
+///             if stream.write_all(b"hello world\n").await.is_err() {
+///                 break;
+///             }
+///         }
+///     })
+///     .await?;
+/// println!("aborted {} connections", aborted);
+/// #
+/// # Ok(()) }) }
+/// ```
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(feature = "async-rt", feature = "timer")))
+)]
+#[derive(Debug)]
+pub struct Server<L> {
+    listener: L,
+    grace_period: Option<Duration>,
+}
+
+impl<L> Server<L> {
+    /// Creates a new server of the connections of the listener.
+    #[inline]
+    pub fn new(listener: L) -> Self {
+        Self {
+            listener,
+            grace_period: None,
+        }
+    }
+
+    /// Sets the time the connections are given to finish after the shutdown, before they are
+    /// aborted.
+    ///
+    /// By default, the server waits for all of them, however long they take.
+    #[inline]
+    pub fn grace_period(mut self, dur: Duration) -> Self {
+        self.grace_period = Some(dur);
+        self
+    }
+
+    /// Serves the connections of the listener by `handler`, until `shutdown` is cancelled.
+    ///
+    /// `handler` is called with every connection and a token, which is cancelled once the
+    /// server shuts down, and the future it returns is spawned as the task of the connection.
+    /// The server shuts down as well when the listener ends or fails, and then returns the
+    /// error once the connections are drained.
+    ///
+    /// On success, returns the number of connections aborted at the end of the grace period.
+    ///
+    /// It must be called within a runtime.
+    pub async fn serve<C, F, Fut>(
+        self,
+        shutdown: CancellationToken,
+        mut handler: F,
+    ) -> io::Result<usize>
+    where
+        L: Stream<Item = io::Result<C>> + Unpin,
+        F: FnMut(C, CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let Self {
+            mut listener,
+            grace_period,
+        } = self;
+        let connections = shutdown.child_token();
+        let mut tasks = JoinSet::new();
+        let cancelled = shutdown.cancelled();
+        pin_mut!(cancelled);
+        let ret = loop {
+            let next = future::poll_fn(|cx| {
+                if cancelled.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(None);
+                }
+                // reap the tasks completed, so the set holds only the connections alive
+                while let Poll::Ready(Some(_)) = tasks.poll_join_next(cx) {}
+                listener.poll_next_unpin(cx).map(Some)
+            })
+            .await;
+            match next {
+                Some(Some(Ok(conn))) => tasks.spawn(handler(conn, connections.clone())),
+                Some(Some(Err(err))) => break Err(err),
+                Some(None) | None => break Ok(()),
+            }
+        };
+
+        // stop accepting, then drain the connections
+        drop(listener);
+        connections.cancel();
+        let drain = async { while tasks.join_next().await.is_some() {} };
+        let aborted = match grace_period {
+            None => {
+                drain.await;
+                0
+            }
+            Some(dur) => match task::timeout(dur, drain).await {
+                Ok(()) => 0,
+                Err(_) => {
+                    let aborted = tasks.len();
+                    tasks.shutdown().await;
+                    aborted
+                }
+            },
+        };
+        ret.map(|()| aborted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Server;
+    use crate::sync::CancellationToken;
+    use crate::task::{self, block_on};
+    use futures::channel::mpsc;
+    use futures::future;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn drain() -> io::Result<()> {
+        block_on(async {
+            let (sender, listener) = mpsc::unbounded::<io::Result<usize>>();
+            let shutdown = CancellationToken::new();
+            let finished = Arc::new(AtomicUsize::new(0));
+            let server = task::spawn({
+                let (shutdown, finished) = (shutdown.clone(), finished.clone());
+                Server::new(listener).serve(shutdown, move |conn, token| {
+                    let finished = finished.clone();
+                    async move {
+                        token.cancelled().await;
+                        finished.fetch_add(conn, Ordering::SeqCst);
+                    }
+                })
+            });
+            for conn in 1..=3 {
+                sender.unbounded_send(Ok(conn)).unwrap();
+            }
+            task::sleep(Duration::from_millis(50)).await;
+            shutdown.cancel();
+            assert_eq!(0, server.await.unwrap()?);
+            assert_eq!(6, finished.load(Ordering::SeqCst));
+            // the listener is dropped
+            assert!(sender.is_closed());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn grace_period() -> io::Result<()> {
+        block_on(async {
+            let (sender, listener) = mpsc::unbounded();
+            let shutdown = CancellationToken::new();
+            let server = task::spawn(
+                Server::new(listener)
+                    .grace_period(Duration::from_millis(10))
+                    .serve(shutdown.clone(), |slow: bool, _| async move {
+                        if slow {
+                            future::pending::<()>().await;
+                        }
+                    }),
+            );
+            sender.unbounded_send(Ok(true)).unwrap();
+            sender.unbounded_send(Ok(false)).unwrap();
+            task::sleep(Duration::from_millis(50)).await;
+            shutdown.cancel();
+            assert_eq!(1, server.await.unwrap()?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn listener_error() {
+        block_on(async {
+            let (sender, listener) = mpsc::unbounded::<io::Result<()>>();
+            sender
+                .unbounded_send(Err(io::ErrorKind::Other.into()))
+                .unwrap();
+            let server =
+                Server::new(listener).serve(CancellationToken::new(), |_, _| async {});
+            let err = server.await.unwrap_err();
+            assert_eq!(io::ErrorKind::Other, err.kind());
+        })
+    }
+}
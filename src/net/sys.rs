@@ -0,0 +1,304 @@
+//! The system calls which rustix has no safe wrapper for.
+
+#[cfg(any(feature = "sctp", feature = "tcp", feature = "udp"))]
+use std::convert::TryFrom;
+use std::io;
+#[cfg(any(
+    feature = "netlink",
+    feature = "packet",
+    feature = "sctp",
+    feature = "tcp",
+    feature = "udp"
+))]
+use std::mem::size_of;
+#[cfg(feature = "tcp")]
+use std::mem::MaybeUninit;
+#[cfg(any(feature = "sctp", feature = "tcp", feature = "udp"))]
+use std::net::SocketAddr;
+use std::os::unix::io::{AsRawFd, BorrowedFd};
+
+/// Sets a socket option to the C value of the option.
+///
+/// The kernel only reads `value`, and fails on the pointers it holds if they are invalid.
+#[cfg(any(
+    feature = "netlink",
+    feature = "packet",
+    feature = "sctp",
+    feature = "tcp",
+    feature = "udp"
+))]
+#[allow(unsafe_code)]
+pub(crate) fn setsockopt<T: Copy>(
+    fd: BorrowedFd<'_>,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: &T,
+) -> io::Result<()> {
+    let len = size_of::<T>() as libc::socklen_t;
+    // SAFETY: the option is read from a `T` alive during the call, of the length passed.
+    let ret = unsafe {
+        libc::setsockopt(fd.as_raw_fd(), level, name, (value as *const T).cast(), len)
+    };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Gets a socket option as the C value of the option.
+///
+/// `T` must be a C type of which every bit pattern is valid. The value is zeroed first, so
+/// the fields unknown to an older kernel, which writes a shorter option, are zero.
+#[cfg(feature = "tcp")]
+#[allow(unsafe_code)]
+pub(crate) fn getsockopt<T: Copy>(
+    fd: BorrowedFd<'_>,
+    level: libc::c_int,
+    name: libc::c_int,
+) -> io::Result<T> {
+    let mut value = MaybeUninit::<T>::zeroed();
+    let mut len = size_of::<T>() as libc::socklen_t;
+    // SAFETY: the kernel writes at most `len` bytes to the `T` alive during the call.
+    let ret = unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            level,
+            name,
+            value.as_mut_ptr().cast(),
+            &mut len,
+        )
+    };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the value is zeroed or written by the kernel, valid for `T` as required.
+    Ok(unsafe { value.assume_init() })
+}
+
+/// Returns `true` if the socket is at the urgent mark.
+#[cfg(feature = "tcp")]
+#[allow(unsafe_code)]
+pub(crate) fn at_mark(fd: BorrowedFd<'_>) -> io::Result<bool> {
+    // `SIOCATMARK` of the generic ABI, which libc does not define
+    const SIOCATMARK: libc::Ioctl = 0x8905;
+    let mut mark: libc::c_int = 0;
+    // SAFETY: the ioctl writes an `int` to `mark`, alive during the call.
+    let ret = unsafe { libc::ioctl(fd.as_raw_fd(), SIOCATMARK, &mut mark) };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(mark != 0)
+}
+
+/// An interface request, the argument of the `SIOC*IF*` and the `TUNSETIFF` ioctls.
+#[cfg(feature = "tun")]
+pub(crate) struct IfReq(libc::ifreq);
+
+#[cfg(feature = "tun")]
+#[allow(unsafe_code)]
+impl IfReq {
+    /// Creates a request on the interface of the name, which is empty for the kernel to pick
+    /// one.
+    pub(crate) fn new(name: &str) -> io::Result<Self> {
+        // SAFETY: the request is plain data, to which the zeros are valid.
+        let mut req: libc::ifreq = unsafe { std::mem::zeroed() };
+        if name.len() >= req.ifr_name.len() || name.contains('\0') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid interface name",
+            ));
+        }
+        for (dst, src) in req.ifr_name.iter_mut().zip(name.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        Ok(Self(req))
+    }
+
+    /// Returns the name of the interface.
+    pub(crate) fn name(&self) -> String {
+        let name = self
+            .0
+            .ifr_name
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8)
+            .collect::<Vec<_>>();
+        String::from_utf8_lossy(&name).into_owned()
+    }
+
+    pub(crate) fn flags(&self) -> libc::c_short {
+        // SAFETY: every field of the union is plain data, zeroed on creation.
+        unsafe { self.0.ifr_ifru.ifru_flags }
+    }
+
+    pub(crate) fn set_flags(&mut self, flags: libc::c_short) {
+        self.0.ifr_ifru.ifru_flags = flags;
+    }
+
+    pub(crate) fn mtu(&self) -> libc::c_int {
+        // SAFETY: every field of the union is plain data, zeroed on creation.
+        unsafe { self.0.ifr_ifru.ifru_mtu }
+    }
+
+    pub(crate) fn set_mtu(&mut self, mtu: libc::c_int) {
+        self.0.ifr_ifru.ifru_mtu = mtu;
+    }
+
+    /// Issues the ioctl of this request on `fd`.
+    ///
+    /// The request must be one which takes a pointer to an interface request.
+    pub(crate) fn ioctl(
+        &mut self,
+        fd: BorrowedFd<'_>,
+        request: libc::Ioctl,
+    ) -> io::Result<()> {
+        // SAFETY: the request reads and writes an interface request, which is alive during the
+        // call.
+        let ret = unsafe { libc::ioctl(fd.as_raw_fd(), request, &mut self.0 as *mut _) };
+        if ret == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// A message received by [`recvmsg`].
+#[cfg(any(feature = "sctp", feature = "tcp", feature = "udp"))]
+#[cfg_attr(not(feature = "sctp"), allow(dead_code))]
+pub(crate) struct RecvMsg<T> {
+    /// The number of bytes read.
+    pub(crate) len: usize,
+    /// The address from whence the message came, if the socket tells it.
+    pub(crate) addr: Option<SocketAddr>,
+    /// The control message of the level and the type asked for, if any.
+    pub(crate) cmsg: Option<T>,
+    /// The flags of the message, like `MSG_EOR`.
+    pub(crate) flags: libc::c_int,
+}
+
+/// Returns the length of a control message of a `T`, padding included.
+#[cfg(any(feature = "sctp", feature = "tcp", feature = "udp"))]
+#[allow(unsafe_code)]
+#[inline]
+fn cmsg_space<T>() -> usize {
+    // SAFETY: it is only arithmetic.
+    unsafe { libc::CMSG_SPACE(size_of::<T>() as libc::c_uint) as usize }
+}
+
+/// Sends a message along with a control message of the C value `cmsg`, to `addr` if any.
+#[cfg(feature = "sctp")]
+#[allow(unsafe_code)]
+pub(crate) fn sendmsg<T: Copy>(
+    fd: BorrowedFd<'_>,
+    buf: &[u8],
+    addr: Option<SocketAddr>,
+    level: libc::c_int,
+    ty: libc::c_int,
+    cmsg: &T,
+) -> io::Result<usize> {
+    let addr = addr.map(rustix::net::SocketAddrAny::from);
+    let mut iov = libc::iovec {
+        iov_base: buf.as_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let space = cmsg_space::<T>();
+    // a buffer of `u64` is aligned for the header of the control message
+    let mut control = vec![0u64; space.div_ceil(8)];
+    // SAFETY: the header is plain data, to which the zeros are valid.
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    if let Some(addr) = &addr {
+        msg.msg_name = addr.as_ptr() as *mut libc::c_void;
+        msg.msg_namelen = addr.addr_len();
+    }
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    // SAFETY: the control buffer has room for a header and a `T`, so the first header is not
+    // null and its data is writable; every buffer the header points to outlives the call, and
+    // the kernel only reads them.
+    let ret = unsafe {
+        let header = libc::CMSG_FIRSTHDR(&msg);
+        (*header).cmsg_level = level;
+        (*header).cmsg_type = ty;
+        (*header).cmsg_len = libc::CMSG_LEN(size_of::<T>() as libc::c_uint) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(header).cast::<T>(), *cmsg);
+        libc::sendmsg(fd.as_raw_fd(), &msg, libc::MSG_NOSIGNAL)
+    };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret as usize)
+}
+
+/// Receives a message by the `flags`, like `MSG_ERRQUEUE`, along with the control message of
+/// the level and the type, of the C value `T`.
+#[cfg(any(feature = "sctp", feature = "tcp", feature = "udp"))]
+#[allow(unsafe_code)]
+pub(crate) fn recvmsg<T: Copy>(
+    fd: BorrowedFd<'_>,
+    buf: &mut [u8],
+    level: libc::c_int,
+    ty: libc::c_int,
+    flags: libc::c_int,
+) -> io::Result<RecvMsg<T>> {
+    // SAFETY: the storage is plain data, to which the zeros are valid.
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    // room for a few other control messages, which are skipped
+    let space = 4 * cmsg_space::<T>();
+    let mut control = vec![0u64; space.div_ceil(8)];
+    // SAFETY: the header is plain data, to which the zeros are valid.
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_name = (&mut storage as *mut libc::sockaddr_storage).cast();
+    msg.msg_namelen = size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    // SAFETY: every buffer the header points to is alive during the call and of the length
+    // passed.
+    let ret = unsafe { libc::recvmsg(fd.as_raw_fd(), &mut msg, flags) };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut cmsg = None;
+    // SAFETY: the kernel has filled in the control buffer up to the length it set, which the
+    // macros walk within; a `T` is only read from a control message long enough for it.
+    unsafe {
+        let mut header = libc::CMSG_FIRSTHDR(&msg);
+        while !header.is_null() {
+            if (*header).cmsg_level == level
+                && (*header).cmsg_type == ty
+                && (*header).cmsg_len as usize
+                    >= libc::CMSG_LEN(size_of::<T>() as libc::c_uint) as usize
+            {
+                cmsg = Some(std::ptr::read_unaligned(
+                    libc::CMSG_DATA(header).cast::<T>(),
+                ));
+            }
+            header = libc::CMSG_NXTHDR(&msg, header);
+        }
+    }
+    let addr = if msg.msg_namelen == 0 {
+        None
+    } else {
+        // SAFETY: the kernel has written an address of the length it set to the storage.
+        let addr = unsafe {
+            rustix::net::SocketAddrAny::read(
+                (&storage as *const libc::sockaddr_storage).cast(),
+                msg.msg_namelen,
+            )
+        };
+        Some(SocketAddr::try_from(addr)?)
+    };
+    Ok(RecvMsg {
+        len: ret as usize,
+        addr,
+        cmsg,
+        flags: msg.msg_flags,
+    })
+}
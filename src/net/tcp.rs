@@ -1,5 +1,13 @@
 mod listener;
+mod multi_listener;
 mod stream;
 
-pub use listener::TcpListener;
-pub use stream::TcpStream;
+#[cfg(feature = "socks5")]
+mod socks5;
+
+pub use listener::{Incoming, IntoIncoming, TcpListener, TcpListenerBuilder};
+pub use multi_listener::MultiListener;
+pub use stream::{OwnedTcpStream, TcpStream};
+
+#[cfg(feature = "socks5")]
+pub use socks5::Socks5Target;
@@ -1,14 +1,23 @@
-use super::TcpStream;
+use super::{MultiListener, OwnedTcpStream, TcpStream};
+use crate::net::accept::Retry;
 use crate::net::poll::Watcher;
-use crate::net::util::resolve_none;
+#[cfg(target_os = "linux")]
+use crate::net::sys;
+use crate::net::util::{self, resolve_none};
+use crate::net::AcceptPolicy;
 use futures::task::{Context, Poll};
 use futures::{future, Stream};
 use mio::net;
 use std::io;
 use std::net::{SocketAddr, TcpListener as StdListener, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
 use std::pin::Pin;
 use std::sync::Arc;
 
+/// The length of the queue of pending connections, the same as mio's.
+const BACKLOG: u32 = 1024;
+
 /// A TCP socket server, listening for connections.
 ///
 /// After creating a `TcpListener` by [`bind`]ing it to a socket address, it listens for incoming
@@ -48,15 +57,49 @@ use std::sync::Arc;
 /// ```
 #[cfg_attr(feature = "docs", doc(cfg(feature = "tcp")))]
 #[derive(Debug, Clone)]
-pub struct TcpListener(Arc<Watcher<net::TcpListener>>);
+pub struct TcpListener {
+    watcher: Arc<Watcher<net::TcpListener>>,
+    policy: Option<AcceptPolicy>,
+    // for polling the listener as a stream
+    retry: Retry,
+}
 
 impl TcpListener {
+    #[inline]
+    fn new(watcher: Arc<Watcher<net::TcpListener>>) -> Self {
+        Self {
+            watcher,
+            policy: None,
+            retry: Retry::default(),
+        }
+    }
+
     /// Bind a socket addr
-    fn bind_once(addr: SocketAddr) -> io::Result<Self> {
-        let watcher = Watcher::new(net::TcpListener::bind(addr)?);
+    fn bind_once(addr: SocketAddr, builder: &TcpListenerBuilder) -> io::Result<Self> {
+        let socket = match addr {
+            SocketAddr::V4(_) => net::TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => net::TcpSocket::new_v6()?,
+        };
+        // rebinding the address of a listener just closed, as mio does
+        #[cfg(not(windows))]
+        socket.set_reuseaddr(true)?;
+        #[cfg(unix)]
+        if let (SocketAddr::V6(_), Some(only_v6)) = (addr, builder.only_v6) {
+            rustix::net::sockopt::set_ipv6_v6only(util::borrow_fd(&socket), only_v6)?;
+        }
+        socket.bind(addr)?;
+        let listener = socket.listen(builder.backlog)?;
+        #[cfg(target_os = "linux")]
+        let watcher = if builder.exclusive {
+            Watcher::new_exclusive(listener)
+        } else {
+            Watcher::new(listener)
+        };
+        #[cfg(not(target_os = "linux"))]
+        let watcher = Watcher::new(listener);
         let inner = Arc::new(watcher);
         match inner.take_error() {
-            Ok(None) => Ok(Self(inner)),
+            Ok(None) => Ok(Self::new(inner)),
             Ok(Some(err)) | Err(err) => Err(err),
         }
     }
@@ -89,17 +132,44 @@ impl TcpListener {
     /// You can resolve addrs asynchronously by [`Resolver`].
     ///
     /// [`Resolver`]: trait.Resolver.html
+    #[inline]
     pub fn bind<A: ToSocketAddrs>(addrs: A) -> io::Result<TcpListener> {
-        let mut error = None;
-
-        for addr in addrs.to_socket_addrs()? {
-            match Self::bind_once(addr) {
-                Err(err) => error = Some(err),
-                ok => return ok,
-            }
-        }
+        TcpListenerBuilder::new().bind(addrs)
+    }
 
-        Err(error.unwrap_or_else(resolve_none))
+    /// Creates a new `MultiListener` which will be bound to every specified address.
+    ///
+    /// Unlike [`bind`], which binds to the first address it can, this fails unless every
+    /// address is bound. The IPv6 addresses are bound to IPv6 only, so a server can listen on
+    /// both `0.0.0.0` and `[::]` of a port.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::net::SocketAddr;
+    ///
+    /// use futures::prelude::*;
+    /// use tio::net::TcpListener;
+    ///
+    /// let addrs: [SocketAddr; 2] =
+    ///     ["0.0.0.0:8080".parse().unwrap(), "[::]:8080".parse().unwrap()];
+    /// let mut listener = TcpListener::bind_all(&addrs)?;
+    ///
+    /// while let Some(stream) = listener.next().await {
+    ///     stream?.write_all(b"hello world").await?;
+    /// }
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`bind`]: #method.bind
+    #[inline]
+    pub fn bind_all(addrs: &[SocketAddr]) -> io::Result<MultiListener> {
+        TcpListenerBuilder::new().bind_all(addrs)
     }
 
     /// Accepts a new incoming connection to this listener.
@@ -119,12 +189,118 @@ impl TcpListener {
     /// # Ok(()) }) }
     /// ```
     pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
-        let (io, addr) =
-            future::poll_fn(|cx| self.0.poll_read_with(cx, |inner| inner.accept()))
-                .await?;
+        let mut retry = Retry::default();
+        future::poll_fn(|cx| {
+            retry.poll(self.policy.as_ref(), cx, |cx| self.poll_accept(cx))
+        })
+        .await
+    }
+
+    /// Polls to accept a new incoming connection to this listener.
+    ///
+    /// The current task is woken once a connection may be accepted, if there is none yet. The
+    /// errors are returned as they are, regardless of the [`AcceptPolicy`].
+    ///
+    /// [`AcceptPolicy`]: struct.AcceptPolicy.html
+    #[inline]
+    pub fn poll_accept(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        poll_accept(&self.watcher, cx)
+            .map_ok(|(stream, addr)| (stream.into_shared(), addr))
+    }
+
+    /// Accepts a new incoming connection to this listener, as an [`OwnedTcpStream`].
+    ///
+    /// This is [`accept`] for the connections which are never cloned.
+    ///
+    /// [`OwnedTcpStream`]: struct.OwnedTcpStream.html
+    /// [`accept`]: #method.accept
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpListener;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0")?;
+    /// let (stream, addr) = listener.accept_owned().await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn accept_owned(&self) -> io::Result<(OwnedTcpStream, SocketAddr)> {
+        let mut retry = Retry::default();
+        future::poll_fn(|cx| {
+            retry.poll(self.policy.as_ref(), cx, |cx| {
+                poll_accept(&self.watcher, cx)
+            })
+        })
+        .await
+    }
+
+    /// Returns a stream of the incoming connections, borrowing this listener.
+    ///
+    /// The stream is the same as the listener itself, so it works with the combinators of
+    /// streams without taking the listener.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use futures::prelude::*;
+    /// use tio::net::TcpListener;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0")?;
+    /// listener
+    ///     .incoming()
+    ///     .try_for_each_concurrent(None, |mut stream| async move {
+    ///         stream.write_all(b"hello world").await
+    ///     })
+    ///     .await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming {
+            listener: self,
+            retry: Retry::default(),
+        }
+    }
 
-        let stream = TcpStream(Arc::new(Watcher::new(io)));
-        Ok((stream, addr))
+    /// Turns this listener into a stream of the incoming connections.
+    ///
+    /// Unlike [`incoming`], the stream owns the listener, so it can be moved into a task.
+    ///
+    /// [`incoming`]: #method.incoming
+    #[inline]
+    pub fn into_incoming(self) -> IntoIncoming {
+        IntoIncoming { listener: self }
+    }
+
+    /// Sets the policy for the transient errors of accepting connections, which are returned
+    /// if it is `None`, the default.
+    ///
+    /// The policy applies to [`accept`], to the streams of the connections, and to the
+    /// clones of this listener made after.
+    ///
+    /// [`accept`]: #method.accept
+    #[inline]
+    pub fn set_accept_policy(&mut self, policy: Option<AcceptPolicy>) {
+        self.policy = policy;
+    }
+
+    /// Returns the policy for the transient errors of accepting connections.
+    ///
+    /// See [`AcceptPolicy`].
+    ///
+    /// [`AcceptPolicy`]: struct.AcceptPolicy.html
+    #[inline]
+    pub fn accept_policy(&self) -> Option<&AcceptPolicy> {
+        self.policy.as_ref()
     }
 
     /// Returns the local address that this listener is bound to.
@@ -145,7 +321,299 @@ impl TcpListener {
     /// # Ok(()) }) }
     /// ```
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
-        self.0.local_addr()
+        self.watcher.local_addr()
+    }
+
+    /// Deregisters this listener from the reactor, converting it into its `std` counterpart.
+    ///
+    /// The returned listener stays in non-blocking mode, so it can be configured with the
+    /// socket options tio doesn't expose and converted back by `From`. This fails if clones
+    /// of this listener are still alive, as they share its registration.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpListener;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:8080")?;
+    /// let std_listener = listener.into_std()?;
+    /// let listener = TcpListener::from(std_listener);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[allow(unsafe_code)]
+    pub fn into_std(self) -> io::Result<StdListener> {
+        let listener = Watcher::try_unwrap(self.watcher)?;
+        // Safety: the mio listener gives up the descriptor it owns.
+        Ok(unsafe { StdListener::from_raw_fd(listener.into_raw_fd()) })
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    ///
+    /// The returned `TcpListener` is a reference to the same socket that this object references,
+    /// by a duplicated file descriptor registered to the reactor on its own, so tasks
+    /// accepting on the two handles do not contend for one registration as those of
+    /// [`clone`] do. The accept policy is copied, and so is the exclusive registration of
+    /// [`TcpListenerBuilder::exclusive`].
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpListener;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:8080")?;
+    /// let listener_clone = listener.try_clone()?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`clone`]: #method.clone
+    /// [`TcpListenerBuilder::exclusive`]: struct.TcpListenerBuilder.html#method.exclusive
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let listener = util::dup::<_, StdListener>(&**self.watcher)?;
+        #[cfg(target_os = "linux")]
+        let mut listener = if self.watcher.is_exclusive() {
+            let watcher = Watcher::new_exclusive(net::TcpListener::from_std(listener));
+            Self::new(Arc::new(watcher))
+        } else {
+            Self::from(listener)
+        };
+        #[cfg(not(target_os = "linux"))]
+        let mut listener = Self::from(listener);
+        listener.policy = self.policy.clone();
+        Ok(listener)
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../process/struct.Command.html#method.pass_fd
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the number of connections established but not accepted yet, and the length of
+    /// the queue of them.
+    ///
+    /// Once the queue is full, the kernel drops the connections completing their handshakes,
+    /// so a count staying at the length tells the listener is accepting too slowly. The
+    /// length is that of the [backlog], capped by the kernel.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpListener;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:8080")?;
+    /// let (pending, backlog) = listener.pending_connections()?;
+    /// if pending >= backlog {
+    ///     eprintln!("the accept queue overflows");
+    /// }
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [backlog]: struct.TcpListenerBuilder.html#method.backlog
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "docs", doc(cfg(target_os = "linux")))]
+    #[inline]
+    pub fn pending_connections(&self) -> io::Result<(usize, usize)> {
+        // the kernel tells the queue of a listener in the fields of the unacknowledged and
+        // the selectively acknowledged segments
+        let info = sys::getsockopt::<libc::tcp_info>(
+            self.as_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+        )?;
+        Ok((info.tcpi_unacked as usize, info.tcpi_sacked as usize))
+    }
+}
+
+/// Listener factory, which can be used in order to configure the properties of a new listener.
+///
+/// By default, it binds a listener of a backlog of 1024 connections, like [`TcpListener::bind`].
+///
+/// [`TcpListener::bind`]: struct.TcpListener.html#method.bind
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use tio::net::TcpListenerBuilder;
+///
+/// let listener = TcpListenerBuilder::new().backlog(128).bind("127.0.0.1:8080")?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[cfg_attr(feature = "docs", doc(cfg(feature = "tcp")))]
+#[derive(Debug, Clone)]
+pub struct TcpListenerBuilder {
+    backlog: u32,
+    #[cfg(unix)]
+    only_v6: Option<bool>,
+    #[cfg(target_os = "linux")]
+    exclusive: bool,
+}
+
+impl TcpListenerBuilder {
+    /// Creates a new builder, from which the properties of a new listener can be configured.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the length of the queue of the connections established but not accepted yet.
+    ///
+    /// The kernel caps the backlog at its own limit, `net.core.somaxconn` on Linux, and
+    /// drops the connections beyond it, as reported by
+    /// [`TcpListener::pending_connections`].
+    ///
+    /// [`TcpListener::pending_connections`]: struct.TcpListener.html#method.pending_connections
+    #[inline]
+    pub fn backlog(mut self, backlog: u32) -> Self {
+        self.backlog = backlog;
+        self
+    }
+
+    /// Sets whether the listeners bound to IPv6 addresses accept the connections of IPv6 only,
+    /// rather than those of IPv4 as well, by the IPv4-mapped addresses.
+    ///
+    /// It is left to the system by default, except by [`bind_all`].
+    ///
+    /// [`bind_all`]: #method.bind_all
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[inline]
+    pub fn only_v6(mut self, only_v6: bool) -> Self {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Sets whether an incoming connection wakes a single one of the runtimes accepting on the
+    /// listener, by `EPOLLEXCLUSIVE`.
+    ///
+    /// A server may spread its connections over several runtimes, each accepting on a
+    /// [`try_clone`] of one listener registered to its own I/O driver. Every connection wakes
+    /// all of them by default, and all but one find nothing to accept. An exclusive listener
+    /// wakes one, or a few, instead. It takes every handle to keep accepting, since a
+    /// connection waiting on a runtime which stopped is never reported to the others.
+    ///
+    /// It is disabled by default, and has no effect on the kernels before 4.5.
+    ///
+    /// [`try_clone`]: struct.TcpListener.html#method.try_clone
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// use tio::net::TcpListenerBuilder;
+    /// use tio::runtime::Runtime;
+    ///
+    /// let (first, second) = (Runtime::new()?, Runtime::new()?);
+    /// let listener = first.block_on(async {
+    ///     TcpListenerBuilder::new().exclusive(true).bind("127.0.0.1:8080")
+    /// })?;
+    /// let clone = second.block_on(async { listener.try_clone() })?;
+    /// # Ok(()) }
+    /// ```
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "docs", doc(cfg(target_os = "linux")))]
+    #[inline]
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Creates a new `TcpListener` which will be bound to the specified address, with the
+    /// configured properties.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// # Blocking
+    ///
+    /// This method may be blocked by resolving.
+    /// You can resolve addrs asynchronously by [`Resolver`].
+    ///
+    /// [`Resolver`]: trait.Resolver.html
+    pub fn bind<A: ToSocketAddrs>(self, addrs: A) -> io::Result<TcpListener> {
+        let mut error = None;
+
+        for addr in addrs.to_socket_addrs()? {
+            match TcpListener::bind_once(addr, &self) {
+                Err(err) => error = Some(err),
+                ok => return ok,
+            }
+        }
+
+        Err(error.unwrap_or_else(resolve_none))
+    }
+
+    /// Creates a new `MultiListener` which will be bound to every specified address, with the
+    /// configured properties.
+    ///
+    /// The IPv6 addresses are bound to IPv6 only, unless [`only_v6`] says otherwise.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`only_v6`]: #method.only_v6
+    #[cfg_attr(not(unix), allow(unused_mut))]
+    pub fn bind_all(mut self, addrs: &[SocketAddr]) -> io::Result<MultiListener> {
+        if addrs.is_empty() {
+            return Err(resolve_none());
+        }
+        #[cfg(unix)]
+        {
+            self.only_v6 = self.only_v6.or(Some(true));
+        }
+        let listeners = addrs
+            .iter()
+            .map(|&addr| TcpListener::bind_once(addr, &self))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(MultiListener::from(listeners))
+    }
+}
+
+impl Default for TcpListenerBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            backlog: BACKLOG,
+            #[cfg(unix)]
+            only_v6: None,
+            #[cfg(target_os = "linux")]
+            exclusive: false,
+        }
     }
 }
 
@@ -176,28 +644,120 @@ impl Stream for TcpListener {
     /// # Ok(()) }) }
     /// ```
     fn poll_next(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        let (io, _) =
-            futures::ready!(self.0.poll_read_with(cx, |inner| inner.accept()))?;
-        let stream = TcpStream(Arc::new(Watcher::new(io)));
+        let this = &mut *self;
+        let (watcher, policy) = (&this.watcher, this.policy.as_ref());
+        let poll = this.retry.poll(policy, cx, |cx| {
+            poll_accept(watcher, cx)
+                .map_ok(|(stream, addr)| (stream.into_shared(), addr))
+        });
+        let (stream, _) = futures::ready!(poll)?;
         Poll::Ready(Some(Ok(stream)))
     }
 }
 
+#[inline]
+fn poll_accept(
+    watcher: &Watcher<net::TcpListener>,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<(OwnedTcpStream, SocketAddr)>> {
+    let (io, addr) =
+        futures::ready!(watcher.poll_read_with(cx, |inner| inner.accept()))?;
+    Poll::Ready(Ok((OwnedTcpStream(Watcher::new(io)), addr)))
+}
+
+/// A stream of the incoming connections of a [`TcpListener`], created by [`TcpListener::incoming`].
+///
+/// [`TcpListener`]: struct.TcpListener.html
+/// [`TcpListener::incoming`]: struct.TcpListener.html#method.incoming
+#[derive(Debug)]
+pub struct Incoming<'a> {
+    listener: &'a TcpListener,
+    retry: Retry,
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<TcpStream>;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let listener = this.listener;
+        let policy = listener.accept_policy();
+        let (stream, _) =
+            futures::ready!(this.retry.poll(policy, cx, |cx| listener.poll_accept(cx)))?;
+        Poll::Ready(Some(Ok(stream)))
+    }
+}
+
+/// A stream of the incoming connections owning a [`TcpListener`], created by
+/// [`TcpListener::into_incoming`].
+///
+/// [`TcpListener`]: struct.TcpListener.html
+/// [`TcpListener::into_incoming`]: struct.TcpListener.html#method.into_incoming
+#[derive(Debug)]
+pub struct IntoIncoming {
+    listener: TcpListener,
+}
+
+impl IntoIncoming {
+    /// Returns the listener.
+    #[inline]
+    pub fn into_inner(self) -> TcpListener {
+        self.listener
+    }
+}
+
+impl Stream for IntoIncoming {
+    type Item = io::Result<TcpStream>;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.listener).poll_next(cx)
+    }
+}
+
 impl From<StdListener> for TcpListener {
     fn from(listener: StdListener) -> Self {
         let watcher = Watcher::new(net::TcpListener::from_std(listener));
-        Self(Arc::new(watcher))
+        Self::new(Arc::new(watcher))
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for TcpListener {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        util::borrow_fd(&**self.watcher)
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for TcpListener {
+    /// Share raw fd of `TcpListener`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.watcher.as_raw_fd()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{TcpListener, TcpStream};
+    use super::{TcpListener, TcpListenerBuilder, TcpStream};
+    use crate::net::AcceptPolicy;
     use crate::task::{block_on, sleep, spawn};
-    use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
+    use futures::{future, AsyncReadExt, AsyncWriteExt, StreamExt, TryStreamExt};
     use std::io;
     use std::net::SocketAddr;
     use std::time::Duration;
@@ -255,6 +815,29 @@ mod tests {
         })
     }
 
+    #[test]
+    fn into_std() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let clone = listener.clone();
+            assert!(clone.into_std().is_err());
+            let std_listener = listener.into_std()?;
+            std_listener.set_ttl(42)?;
+            let listener = TcpListener::from(std_listener);
+            assert_eq!(42, listener.watcher.ttl()?);
+            let server_addr = listener.local_addr()?;
+            spawn(async move {
+                let (mut stream, addr) = listener.accept().await?;
+                stream.write_all(addr.to_string().as_bytes()).await?;
+                let mut data = [0; DATA.len()];
+                stream.read_exact(&mut data).await?;
+                assert_eq!(DATA, data.as_ref());
+                Ok::<_, io::Error>(())
+            });
+            connect(server_addr).await
+        })
+    }
+
     #[test]
     fn stream() -> io::Result<()> {
         block_on(async {
@@ -272,4 +855,110 @@ mod tests {
             connect(server_addr).await
         })
     }
+
+    #[test]
+    fn accept_policy() -> io::Result<()> {
+        block_on(async {
+            let mut listener = TcpListener::bind("127.0.0.1:0")?;
+            let server_addr = listener.local_addr()?;
+            assert!(listener.accept_policy().is_none());
+            listener.set_accept_policy(Some(AcceptPolicy::new()));
+            // the clones keep the policy
+            let mut listener = listener.clone();
+            assert!(listener.accept_policy().is_some());
+            spawn(async move {
+                let mut stream = listener.next().await.unwrap()?;
+                let addr = stream.peer_addr()?;
+                stream.write_all(addr.to_string().as_bytes()).await?;
+                let mut data = [0; DATA.len()];
+                stream.read_exact(&mut data).await?;
+                Ok::<_, io::Error>(())
+            });
+            connect(server_addr).await
+        })
+    }
+
+    #[test]
+    fn incoming() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let server_addr = listener.local_addr()?;
+            spawn(async move {
+                listener
+                    .into_incoming()
+                    .try_for_each_concurrent(None, |mut stream| async move {
+                        let addr = stream.peer_addr()?;
+                        stream.write_all(addr.to_string().as_bytes()).await?;
+                        let mut data = [0; DATA.len()];
+                        stream.read_exact(&mut data).await?;
+                        assert_eq!(DATA, data.as_ref());
+                        Ok(())
+                    })
+                    .await
+            });
+            future::try_join(connect(server_addr), connect(server_addr)).await?;
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn pending_connections() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListenerBuilder::new().backlog(4).bind("127.0.0.1:0")?;
+            let server_addr = listener.local_addr()?;
+            assert_eq!((0, 4), listener.pending_connections()?);
+            let _clients = future::try_join(
+                TcpStream::connect(server_addr),
+                TcpStream::connect(server_addr),
+            )
+            .await?;
+            assert_eq!((2, 4), listener.pending_connections()?);
+            listener.accept().await?;
+            assert_eq!((1, 4), listener.pending_connections()?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn exclusive() -> io::Result<()> {
+        use crate::runtime::{Builder, ReactorHooks, Readiness};
+        use std::os::unix::io::{AsRawFd, RawFd};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Readable(Mutex<Vec<RawFd>>);
+
+        impl ReactorHooks for Readable {
+            fn on_event(&self, fd: RawFd, readiness: Readiness) {
+                if readiness.is_readable() {
+                    self.0.lock().unwrap().push(fd)
+                }
+            }
+        }
+
+        // how many of the two runtimes watching one listener a connection wakes
+        let woken = |exclusive| -> io::Result<usize> {
+            let readable = Arc::new(Readable::default());
+            let runtime = || Builder::new().reactor_hooks(readable.clone()).build();
+            let (first, second) = (runtime()?, runtime()?);
+            let listener = first.block_on(async {
+                TcpListenerBuilder::new()
+                    .exclusive(exclusive)
+                    .bind("127.0.0.1:0")
+            })?;
+            let clone = second.block_on(async { listener.try_clone() })?;
+            let fds = [listener.as_raw_fd(), clone.as_raw_fd()];
+            // an event is reported by every reactor not blocked in its poll yet
+            std::thread::sleep(Duration::from_millis(50));
+            let _client = std::net::TcpStream::connect(listener.local_addr()?)?;
+            std::thread::sleep(Duration::from_millis(100));
+            let events = readable.0.lock().unwrap();
+            Ok(events.iter().filter(|fd| fds.contains(fd)).count())
+        };
+        assert_eq!(2, woken(false)?);
+        assert_eq!(1, woken(true)?);
+        Ok(())
+    }
 }
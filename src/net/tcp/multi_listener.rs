@@ -0,0 +1,231 @@
+use super::{TcpListener, TcpStream};
+use crate::net::accept::Retry;
+use crate::net::AcceptPolicy;
+use futures::task::{Context, Poll};
+use futures::{future, Stream};
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A TCP server listening on several addresses, accepting the connections of them all.
+///
+/// A `MultiListener` is created by [`TcpListener::bind_all`], or from the listeners bound
+/// already. Its connections can be accepted by [`accept`], or by awaiting elements from it as
+/// a single stream. The listeners are polled in turn, so none of them starves the others.
+///
+/// [`TcpListener::bind_all`]: struct.TcpListener.html#method.bind_all
+/// [`accept`]: #method.accept
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::prelude::*;
+/// use tio::net::{MultiListener, TcpListener};
+///
+/// let listeners = vec![
+///     TcpListener::bind("127.0.0.1:8080")?,
+///     TcpListener::bind("127.0.0.1:8443")?,
+/// ];
+/// let listener = MultiListener::from(listeners);
+///
+/// loop {
+///     let (mut stream, _) = listener.accept().await?;
+///     stream.write_all(b"hello world").await?;
+/// }
+/// #
+/// # Ok(()) }) }
+/// ```
+#[cfg_attr(feature = "docs", doc(cfg(feature = "tcp")))]
+#[derive(Debug)]
+pub struct MultiListener {
+    listeners: Vec<TcpListener>,
+    // the listener polled first, next time
+    next: AtomicUsize,
+}
+
+impl MultiListener {
+    /// Accepts a new incoming connection to any of the listeners.
+    ///
+    /// When a connection is established, the corresponding stream and address will be returned.
+    /// The errors are handled under the [`AcceptPolicy`] of their listener.
+    ///
+    /// [`AcceptPolicy`]: struct.AcceptPolicy.html
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let mut retries = vec![Retry::default(); self.listeners.len()];
+        future::poll_fn(|cx| {
+            self.poll_each(cx, |i, listener, cx| {
+                let policy = listener.accept_policy();
+                retries[i].poll(policy, cx, |cx| listener.poll_accept(cx))
+            })
+        })
+        .await
+    }
+
+    /// Polls to accept a new incoming connection to any of the listeners.
+    ///
+    /// The current task is woken once a connection may be accepted, if there is none yet. The
+    /// errors are returned as they are, regardless of the [`AcceptPolicy`].
+    ///
+    /// [`AcceptPolicy`]: struct.AcceptPolicy.html
+    #[inline]
+    pub fn poll_accept(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        self.poll_each(cx, |_, listener, cx| listener.poll_accept(cx))
+    }
+
+    /// Sets the accept policy of every listener.
+    ///
+    /// For more information about the policy, see [`TcpListener::set_accept_policy`].
+    ///
+    /// [`TcpListener::set_accept_policy`]: struct.TcpListener.html#method.set_accept_policy
+    pub fn set_accept_policy(&mut self, policy: Option<AcceptPolicy>) {
+        for listener in &mut self.listeners {
+            listener.set_accept_policy(policy.clone());
+        }
+    }
+
+    /// Returns the local addresses that the listeners are bound to, in their order.
+    pub fn local_addrs(&self) -> io::Result<Vec<SocketAddr>> {
+        self.listeners.iter().map(TcpListener::local_addr).collect()
+    }
+
+    /// Returns the listeners.
+    #[inline]
+    pub fn listeners(&self) -> &[TcpListener] {
+        &self.listeners
+    }
+
+    /// Unwraps this `MultiListener`, returning the listeners.
+    #[inline]
+    pub fn into_listeners(self) -> Vec<TcpListener> {
+        self.listeners
+    }
+
+    /// Polls every listener in turn, from the one after the last ready, until one is ready.
+    fn poll_each<T, F>(&self, cx: &mut Context<'_>, mut poll: F) -> Poll<T>
+    where
+        F: FnMut(usize, &TcpListener, &mut Context<'_>) -> Poll<T>,
+    {
+        let len = self.listeners.len();
+        let start = self.next.load(Ordering::Relaxed);
+        for i in (0..len).map(|offset| (start + offset) % len) {
+            if let Poll::Ready(ret) = poll(i, &self.listeners[i], cx) {
+                self.next.store((i + 1) % len, Ordering::Relaxed);
+                return Poll::Ready(ret);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl From<Vec<TcpListener>> for MultiListener {
+    /// Gathers the listeners. Of no listener, it never accepts a connection.
+    #[inline]
+    fn from(listeners: Vec<TcpListener>) -> Self {
+        Self {
+            listeners,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Stream for MultiListener {
+    type Item = io::Result<TcpStream>;
+
+    /// Iterating over this stream is equivalent to calling [`accept`] in a loop. The stream of
+    /// connections is infinite, i.e awaiting the next connection will never result in [`None`].
+    ///
+    /// [`accept`]: #method.accept
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let len = this.listeners.len();
+        let start = *this.next.get_mut();
+        for i in (0..len).map(|offset| (start + offset) % len) {
+            if let Poll::Ready(item) = Pin::new(&mut this.listeners[i]).poll_next(cx) {
+                *this.next.get_mut() = (i + 1) % len;
+                return Poll::Ready(item);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiListener;
+    use crate::net::{TcpListener, TcpStream};
+    use crate::task::block_on;
+    use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
+    use std::io;
+    use std::net::SocketAddr;
+
+    #[test]
+    fn bind_all() -> io::Result<()> {
+        block_on(async {
+            let addrs: [SocketAddr; 2] = [
+                "127.0.0.1:0".parse().unwrap(),
+                "127.0.0.1:0".parse().unwrap(),
+            ];
+            let mut listener = TcpListener::bind_all(&addrs)?;
+            let local_addrs = listener.local_addrs()?;
+            assert_eq!(2, local_addrs.len());
+            assert_ne!(local_addrs[0], local_addrs[1]);
+
+            for (i, &addr) in local_addrs.iter().enumerate().rev() {
+                let mut client = TcpStream::connect(addr).await?;
+                let mut stream = listener.next().await.unwrap()?;
+                assert_eq!(addr, stream.local_addr()?);
+                stream.write_all(&[i as u8]).await?;
+                let mut buf = [0; 1];
+                client.read_exact(&mut buf).await?;
+                assert_eq!([i as u8], buf);
+            }
+
+            let client = TcpStream::connect(local_addrs[1]).await?;
+            let (_, peer) = listener.accept().await?;
+            assert_eq!(client.local_addr()?, peer);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn dual_stack() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListener::bind("[::1]:0")?;
+            let port = listener.local_addr()?.port();
+            drop(listener);
+            // the same port on both families
+            let addrs: [SocketAddr; 2] = [
+                format!("[::]:{}", port).parse().unwrap(),
+                format!("0.0.0.0:{}", port).parse().unwrap(),
+            ];
+            let listener = TcpListener::bind_all(&addrs)?;
+            TcpStream::connect(("::1", port)).await?;
+            TcpStream::connect(("127.0.0.1", port)).await?;
+            let (first, _) = listener.accept().await?;
+            let (second, _) = listener.accept().await?;
+            assert!(first.local_addr()?.is_ipv6());
+            assert!(second.local_addr()?.is_ipv4());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn empty() -> io::Result<()> {
+        block_on(async {
+            assert!(MultiListener::from(vec![]).local_addrs()?.is_empty());
+            let err = TcpListener::bind_all(&[]).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,380 @@
+use super::TcpStream;
+use futures::{AsyncReadExt, AsyncWriteExt};
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+
+const VERSION: u8 = 5;
+const NO_AUTH: u8 = 0;
+const USER_PASS: u8 = 2;
+const NO_ACCEPTABLE: u8 = 0xff;
+const USER_PASS_VERSION: u8 = 1;
+const CONNECT: u8 = 1;
+const ATYP_V4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+const ATYP_V6: u8 = 4;
+
+/// The destination of a connection through a SOCKS5 proxy.
+///
+/// A domain name is resolved by the proxy, which may see the hosts the client cannot, like
+/// the ones of a corporate network.
+#[cfg_attr(feature = "docs", doc(cfg(feature = "socks5")))]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Socks5Target {
+    /// A socket address.
+    Addr(SocketAddr),
+    /// A domain name of 1 to 255 bytes, and a port.
+    Domain(String, u16),
+}
+
+impl From<SocketAddr> for Socks5Target {
+    #[inline]
+    fn from(addr: SocketAddr) -> Self {
+        Socks5Target::Addr(addr)
+    }
+}
+
+impl From<(IpAddr, u16)> for Socks5Target {
+    #[inline]
+    fn from((ip, port): (IpAddr, u16)) -> Self {
+        Socks5Target::Addr(SocketAddr::new(ip, port))
+    }
+}
+
+impl From<(&str, u16)> for Socks5Target {
+    /// Parses the host as an IP address, or takes it as a domain name.
+    #[inline]
+    fn from((host, port): (&str, u16)) -> Self {
+        match host.parse::<IpAddr>() {
+            Ok(ip) => Socks5Target::Addr(SocketAddr::new(ip, port)),
+            Err(_) => Socks5Target::Domain(host.to_string(), port),
+        }
+    }
+}
+
+impl From<(String, u16)> for Socks5Target {
+    #[inline]
+    fn from((host, port): (String, u16)) -> Self {
+        match host.parse::<IpAddr>() {
+            Ok(ip) => Socks5Target::Addr(SocketAddr::new(ip, port)),
+            Err(_) => Socks5Target::Domain(host, port),
+        }
+    }
+}
+
+impl TcpStream {
+    /// Creates a new TCP stream connected to `target` through the SOCKS5 proxy at `proxy`.
+    ///
+    /// The stream is connected to the proxy, which is asked to connect to the target, and once
+    /// the proxy has, the stream carries the bytes to and from the target as if it were
+    /// connected to it directly. `auth` is the username and the password to authenticate to the
+    /// proxy with, otherwise only the proxies requiring no authentication are accepted.
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors of connecting to the proxy, this method fails of:
+    ///
+    /// * `PermissionDenied` if the proxy accepts none of the authentication methods, rejects
+    ///   the credentials, or does not allow the connection
+    /// * `ConnectionRefused` or `TimedOut` if the target refuses the connection or the proxy
+    ///   times out connecting to it
+    /// * `InvalidInput` if a domain name, a username or a password is empty or longer than 255
+    ///   bytes
+    /// * `InvalidData` if the proxy does not speak SOCKS5
+    /// * `Other` of the other failures the proxy replies
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use futures::prelude::*;
+    /// use tio::net::TcpStream;
+    ///
+    /// let auth = Some(("user", "secret"));
+    /// let mut stream =
+    ///     TcpStream::connect_via_socks5("10.0.0.1:1080", ("example.com", 80), auth).await?;
+    /// stream.write_all(b"GET / HTTP/1.0\r\nHost: example.com\r\n\r\n").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// # Blocking
+    ///
+    /// This method may be blocked by resolving the proxy.
+    /// You can resolve addrs asynchronously by [`Resolver`].
+    ///
+    /// [`Resolver`]: trait.Resolver.html
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "socks5")))]
+    pub async fn connect_via_socks5(
+        proxy: impl ToSocketAddrs,
+        target: impl Into<Socks5Target>,
+        auth: Option<(&str, &str)>,
+    ) -> io::Result<Self> {
+        let target = target.into();
+        // checked before connecting
+        let request = request(&target)?;
+        let credentials = auth.map(credentials).transpose()?;
+        let mut stream = Self::connect(proxy).await?;
+
+        let greeting: &[u8] = match credentials {
+            Some(_) => &[VERSION, 2, NO_AUTH, USER_PASS],
+            None => &[VERSION, 1, NO_AUTH],
+        };
+        stream.write_all(greeting).await?;
+        let mut reply = [0; 2];
+        stream.read_exact(&mut reply).await?;
+        check_version(reply[0], VERSION)?;
+        match (reply[1], credentials) {
+            (NO_AUTH, _) => (),
+            (USER_PASS, Some(credentials)) => {
+                stream.write_all(&credentials).await?;
+                stream.read_exact(&mut reply).await?;
+                check_version(reply[0], USER_PASS_VERSION)?;
+                if reply[1] != 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "SOCKS5 proxy rejected the credentials",
+                    ));
+                }
+            }
+            (NO_ACCEPTABLE, _) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "SOCKS5 proxy accepted no authentication method",
+                ))
+            }
+            (method, _) => {
+                return Err(invalid_data(&format!(
+                    "SOCKS5 proxy chose the unoffered method {}",
+                    method
+                )))
+            }
+        }
+
+        stream.write_all(&request).await?;
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply).await?;
+        check_version(reply[0], VERSION)?;
+        if reply[1] != 0 {
+            return Err(reply_error(reply[1]));
+        }
+        // skip the address the proxy bound
+        let len = match reply[3] {
+            ATYP_V4 => 4,
+            ATYP_V6 => 16,
+            ATYP_DOMAIN => {
+                let mut len = [0; 1];
+                stream.read_exact(&mut len).await?;
+                len[0] as usize
+            }
+            atyp => {
+                return Err(invalid_data(&format!(
+                    "SOCKS5 proxy replied the unknown address type {}",
+                    atyp
+                )))
+            }
+        };
+        let mut bound = [0; 255 + 2];
+        stream.read_exact(&mut bound[..len + 2]).await?;
+        Ok(stream)
+    }
+}
+
+/// Encodes the request to connect to the target.
+fn request(target: &Socks5Target) -> io::Result<Vec<u8>> {
+    let mut request = vec![VERSION, CONNECT, 0];
+    let port = match target {
+        Socks5Target::Addr(SocketAddr::V4(addr)) => {
+            request.push(ATYP_V4);
+            request.extend_from_slice(&addr.ip().octets());
+            addr.port()
+        }
+        Socks5Target::Addr(SocketAddr::V6(addr)) => {
+            request.push(ATYP_V6);
+            request.extend_from_slice(&addr.ip().octets());
+            addr.port()
+        }
+        Socks5Target::Domain(domain, port) => {
+            request.extend_from_slice(&[ATYP_DOMAIN, short_len(domain, "domain name")?]);
+            request.extend_from_slice(domain.as_bytes());
+            *port
+        }
+    };
+    request.extend_from_slice(&port.to_be_bytes());
+    Ok(request)
+}
+
+/// Encodes the username and the password, by RFC 1929.
+fn credentials((username, password): (&str, &str)) -> io::Result<Vec<u8>> {
+    let mut credentials = vec![USER_PASS_VERSION, short_len(username, "username")?];
+    credentials.extend_from_slice(username.as_bytes());
+    credentials.push(short_len(password, "password")?);
+    credentials.extend_from_slice(password.as_bytes());
+    Ok(credentials)
+}
+
+/// Returns the length of a field of no more than 255 bytes.
+#[inline]
+fn short_len(field: &str, name: &str) -> io::Result<u8> {
+    if field.is_empty() || field.len() > u8::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} must be of 1 to 255 bytes", name),
+        ));
+    }
+    Ok(field.len() as u8)
+}
+
+#[inline]
+fn check_version(version: u8, expected: u8) -> io::Result<()> {
+    if version != expected {
+        return Err(invalid_data(&format!(
+            "SOCKS5 proxy replied the version {}, not {}",
+            version, expected
+        )));
+    }
+    Ok(())
+}
+
+#[inline]
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Converts the failure replied by the proxy into an error.
+fn reply_error(rep: u8) -> io::Error {
+    let (kind, msg) = match rep {
+        1 => (io::ErrorKind::Other, "general SOCKS server failure"),
+        2 => (
+            io::ErrorKind::PermissionDenied,
+            "connection not allowed by ruleset",
+        ),
+        3 => (io::ErrorKind::Other, "network unreachable"),
+        4 => (io::ErrorKind::Other, "host unreachable"),
+        5 => (io::ErrorKind::ConnectionRefused, "connection refused"),
+        6 => (io::ErrorKind::TimedOut, "TTL expired"),
+        7 => (io::ErrorKind::Other, "command not supported"),
+        8 => (io::ErrorKind::Other, "address type not supported"),
+        _ => (io::ErrorKind::Other, "unknown SOCKS server failure"),
+    };
+    io::Error::new(kind, format!("SOCKS5 proxy failed: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Socks5Target;
+    use crate::net::{TcpListener, TcpStream};
+    use crate::task::{self, block_on};
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use std::io;
+    use std::net::SocketAddr;
+
+    /// Serves a SOCKS5 handshake, expecting the messages of the client and replying
+    /// `replies` to them in turn, then echoes.
+    async fn proxy(
+        listener: TcpListener,
+        expected: Vec<Vec<u8>>,
+        replies: Vec<Vec<u8>>,
+    ) -> io::Result<()> {
+        let (mut stream, _) = listener.accept().await?;
+        for (expected, reply) in expected.iter().zip(&replies) {
+            let mut buf = vec![0; expected.len()];
+            stream.read_exact(&mut buf).await?;
+            assert_eq!(expected, &buf);
+            stream.write_all(reply).await?;
+        }
+        let mut buf = [0; 4];
+        if stream.read_exact(&mut buf).await.is_ok() {
+            stream.write_all(&buf).await?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn domain() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?;
+            let server = task::spawn(proxy(
+                listener,
+                vec![
+                    vec![5, 1, 0],
+                    [&[5, 1, 0, 3, 11][..], b"example.com", &[0, 80]].concat(),
+                ],
+                vec![
+                    vec![5, 0],
+                    vec![5, 0, 0, 3, 4, b'p', b'r', b'o', b'x', 0, 1],
+                ],
+            ));
+            let target = ("example.com", 80);
+            let mut stream = TcpStream::connect_via_socks5(addr, target, None).await?;
+            stream.write_all(b"ping").await?;
+            let mut buf = [0; 4];
+            stream.read_exact(&mut buf).await?;
+            assert_eq!(b"ping", &buf);
+            server.await.unwrap()
+        })
+    }
+
+    #[test]
+    fn auth() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?;
+            let target: SocketAddr = "[::1]:443".parse().unwrap();
+            let mut request = vec![5, 1, 0, 4];
+            request.extend_from_slice(&[0; 15]);
+            request.extend_from_slice(&[1, 1, 0xbb]);
+            let server = task::spawn(proxy(
+                listener,
+                vec![
+                    vec![5, 2, 0, 2],
+                    b"\x01\x04user\x06secret".to_vec(),
+                    request,
+                ],
+                vec![vec![5, 2], vec![1, 0], vec![5, 0, 0, 1, 127, 0, 0, 1, 0, 1]],
+            ));
+            let auth = Some(("user", "secret"));
+            TcpStream::connect_via_socks5(addr, target, auth).await?;
+            server.await.unwrap()
+        })
+    }
+
+    #[test]
+    fn failures() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?;
+            let target: SocketAddr = "10.0.0.1:80".parse().unwrap();
+            let server = task::spawn(proxy(
+                listener,
+                vec![vec![5, 1, 0], vec![5, 1, 0, 1, 10, 0, 0, 1, 0, 80]],
+                vec![vec![5, 0], vec![5, 5, 0, 1, 0, 0, 0, 0, 0, 0]],
+            ));
+            let err = TcpStream::connect_via_socks5(addr, target, None)
+                .await
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::ConnectionRefused, err.kind());
+            server.await.unwrap()?;
+
+            // authentication required
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?;
+            let server =
+                task::spawn(proxy(listener, vec![vec![5, 1, 0]], vec![vec![5, 0xff]]));
+            let err = TcpStream::connect_via_socks5(addr, target, None)
+                .await
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::PermissionDenied, err.kind());
+            server.await.unwrap()?;
+
+            // checked before connecting
+            let target = Socks5Target::Domain("a".repeat(256), 80);
+            let err = TcpStream::connect_via_socks5(addr, target, None)
+                .await
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+            Ok(())
+        })
+    }
+}
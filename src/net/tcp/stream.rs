@@ -1,12 +1,19 @@
 use crate::net::poll::Watcher;
-use crate::net::util::resolve_none;
+use crate::net::util::{self, resolve_none};
+#[cfg(target_os = "linux")]
+use crate::net::{sys, zerocopy, ZeroCopySend};
 use futures::task::{Context, Poll};
 use futures::{future, AsyncRead, AsyncWrite};
 use mio::net;
+#[cfg(unix)]
+use rustix::net::{RecvFlags, SendFlags};
 use std::io::{self, IoSlice, IoSliceMut, Read, Write};
 use std::net::{SocketAddr, TcpStream as StdStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// A TCP stream between a local and a remote socket.
 ///
@@ -46,19 +53,19 @@ use std::sync::Arc;
 /// ```
 #[cfg_attr(feature = "docs", doc(cfg(feature = "tcp")))]
 #[derive(Debug, Clone)]
-pub struct TcpStream(pub(crate) Arc<Watcher<net::TcpStream>>);
+pub struct TcpStream(
+    pub(crate) Arc<Watcher<net::TcpStream>>,
+    #[cfg(target_os = "linux")] Arc<zerocopy::Tracker>,
+);
 
 impl TcpStream {
-    /// Connect to a socket addr
-    async fn connect_once(addr: SocketAddr) -> io::Result<Self> {
-        let watcher = Watcher::new(net::TcpStream::connect(addr)?);
-        // wait for connection established
-        watcher.write_ready().await;
-        let inner = Arc::new(watcher);
-        match inner.take_error() {
-            Ok(None) => Ok(Self(inner)),
-            Ok(Some(err)) | Err(err) => Err(err),
-        }
+    #[inline]
+    pub(super) fn new(watcher: Arc<Watcher<net::TcpStream>>) -> Self {
+        Self(
+            watcher,
+            #[cfg(target_os = "linux")]
+            Arc::default(),
+        )
     }
 
     /// Creates a new TCP stream connected to the specified address.
@@ -86,16 +93,9 @@ impl TcpStream {
     ///
     /// [`Resolver`]: trait.Resolver.html
     pub async fn connect(addrs: impl ToSocketAddrs) -> io::Result<Self> {
-        let mut error = None;
-        for addr in addrs.to_socket_addrs()? {
-            match Self::connect_once(addr).await {
-                Err(err) => {
-                    error = Some(err);
-                }
-                ok => return ok,
-            }
-        }
-        Err(error.unwrap_or_else(resolve_none))
+        OwnedTcpStream::connect(addrs)
+            .await
+            .map(OwnedTcpStream::into_shared)
     }
     /// Returns the local address that this stream is connected to.
     ///
@@ -263,6 +263,288 @@ impl TcpStream {
         self.0.set_nodelay(nodelay)
     }
 
+    /// Writes all of `buf` without copying it into the kernel.
+    ///
+    /// The kernel sends the bytes from the pages of `buf` in place, which saves the copy of
+    /// large transfers, and notifies once it is done with them. On success, returns a
+    /// [`ZeroCopySend`] of the number of bytes written, which resolves to `buf` after the
+    /// notification. Since the kernel pins the pages until the peer acknowledges them, it
+    /// pays off for transfers of at least some kilobytes.
+    ///
+    /// The sends are matched to the notifications by the count of the sends of this stream and
+    /// its clones, so the zero-copy sends must not be mixed with those of a handle of
+    /// [`try_clone`].
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:8080").await?;
+    /// let sent = stream.send_zc(vec![0; 1 << 20]).await?;
+    /// assert_eq!(1 << 20, sent.sent());
+    /// // the buffer can be reused once the kernel is done with it
+    /// let buf = sent.await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`ZeroCopySend`]: struct.ZeroCopySend.html
+    /// [`try_clone`]: #method.try_clone
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "docs", doc(cfg(target_os = "linux")))]
+    pub async fn send_zc<B>(&self, buf: B) -> io::Result<ZeroCopySend<B>>
+    where
+        B: AsRef<[u8]> + Send + 'static,
+    {
+        zerocopy::send(&self.0, &self.1, buf, true).await
+    }
+
+    /// Sends `buf` as urgent data, which the peer receives out of band.
+    ///
+    /// TCP has room for only one byte of urgent data, so only the last byte of `buf` is
+    /// urgent, while the bytes before it go in band. On success, returns the number of bytes
+    /// written.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:23").await?;
+    /// // the data mark of telnet, after an interrupt
+    /// stream.send_oob(&[255, 242]).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub async fn send_oob(&self, buf: &[u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |inner| {
+                let flags = SendFlags::OOB | SendFlags::NOSIGNAL;
+                Ok(rustix::net::send(util::borrow_fd(inner), buf, flags)?)
+            })
+        })
+        .await
+    }
+
+    /// Receives the byte of urgent data sent out of band by the peer.
+    ///
+    /// The peer signals the urgent data ahead of the byte, which is waited for once it is
+    /// signalled. An error of kind [`InvalidInput`] is returned if there is no urgent data,
+    /// or it has been received; use [`at_mark`] to find where it was in the stream. On
+    /// success, returns the number of bytes read, which is 0 once the stream is closed.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:21").await?;
+    /// let mut buf = [0; 1];
+    /// stream.recv_oob(&mut buf).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`InvalidInput`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+    /// [`at_mark`]: #method.at_mark
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub async fn recv_oob(&self, buf: &mut [u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |inner| {
+                let fd = util::borrow_fd(inner);
+                Ok(rustix::net::recv(fd, &mut *buf, RecvFlags::OOB)?.0)
+            })
+        })
+        .await
+    }
+
+    /// Returns `true` if the stream is at the urgent mark, where the byte of urgent data was
+    /// taken out of the stream.
+    ///
+    /// The bytes read before the mark were sent before the urgent data, so a reader can
+    /// discard them until the mark, as telnet does after an interrupt.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use futures::prelude::*;
+    /// use tio::net::TcpStream;
+    ///
+    /// let mut stream = TcpStream::connect("127.0.0.1:23").await?;
+    /// let mut buf = [0; 1];
+    /// // flush the data sent before the urgent data
+    /// while !stream.at_mark()? {
+    ///     stream.read(&mut buf).await?;
+    /// }
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "docs", doc(cfg(target_os = "linux")))]
+    #[inline]
+    pub fn at_mark(&self) -> io::Result<bool> {
+        sys::at_mark(self.as_fd())
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    ///
+    /// The returned `TcpStream` is a reference to the same socket that this object references,
+    /// by a duplicated file descriptor registered to the reactor on its own. The handles of
+    /// [`clone`] share one registration, so tasks waiting on them contend for the same
+    /// readiness and timeouts; those of `try_clone` are waited on apart, and start without
+    /// timeouts.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:8080").await?;
+    /// let stream_clone = stream.try_clone()?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`clone`]: #method.clone
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self::from(util::dup::<_, StdStream>(&**self.0)?))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../process/struct.Command.html#method.pass_fd
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// If the timeout is [`None`], then pending reads will never time out.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every read, peek which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. The timeout is driven by the timer rather than `SO_RCVTIMEO`, so the
+    /// socket itself stays non-blocking. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:8080").await?;
+    /// stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    /// assert_eq!(stream.read_timeout()?, Some(Duration::from_secs(1)));
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// If the timeout is [`None`], then pending writes will never time out.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every write which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:8080").await?;
+    /// stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+    /// assert_eq!(stream.write_timeout()?, Some(Duration::from_secs(1)));
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
     /// Shuts down the read, write, or both halves of this connection.
     ///
     /// This method will cause all pending and future I/O on the specified portions to return
@@ -288,12 +570,60 @@ impl TcpStream {
     pub fn shutdown(&self, how: std::net::Shutdown) -> std::io::Result<()> {
         self.0.shutdown(how)
     }
+
+    /// Deregisters this stream from the reactor, converting it into its `std` counterpart.
+    ///
+    /// The returned stream stays in non-blocking mode, so it can be configured with the
+    /// socket options tio doesn't expose and converted back by `From`. This fails if clones
+    /// of this stream are still alive, as they share its registration.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:8080").await?;
+    /// let std_stream = stream.into_std()?;
+    /// let stream = TcpStream::from(std_stream);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[allow(unsafe_code)]
+    pub fn into_std(self) -> io::Result<StdStream> {
+        let stream = Watcher::try_unwrap(self.0)?;
+        // Safety: the mio stream gives up the descriptor it owns.
+        Ok(unsafe { StdStream::from_raw_fd(stream.into_raw_fd()) })
+    }
 }
 
 impl From<StdStream> for TcpStream {
     fn from(stream: StdStream) -> Self {
         let watcher = Watcher::new(net::TcpStream::from_std(stream));
-        Self(Arc::new(watcher))
+        Self::new(Arc::new(watcher))
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for TcpStream {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        util::borrow_fd(&**self.0)
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for TcpStream {
+    /// Share raw fd of `TcpStream`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
     }
 }
 
@@ -348,6 +678,194 @@ impl AsyncWrite for TcpStream {
     }
 }
 
+/// A TCP stream owned by a single handle.
+///
+/// Unlike a [`TcpStream`], which shares its registration behind an [`Arc`] so that it can be
+/// cloned, an `OwnedTcpStream` holds it inline, which saves an indirection on every read and
+/// write. It covers the hot path only: configure the stream as a [`std::net::TcpStream`]
+/// before converting it, or turn it [`into_shared`] for the full API.
+///
+/// An owned stream is connected by [`connect`], or accepted by [`TcpListener::accept_owned`].
+///
+/// [`TcpStream`]: struct.TcpStream.html
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`std::net::TcpStream`]: https://doc.rust-lang.org/std/net/struct.TcpStream.html
+/// [`into_shared`]: #method.into_shared
+/// [`connect`]: #method.connect
+/// [`TcpListener::accept_owned`]: struct.TcpListener.html#method.accept_owned
+///
+/// ## Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::prelude::*;
+/// use tio::net::OwnedTcpStream;
+///
+/// let mut stream = OwnedTcpStream::connect("127.0.0.1:8080").await?;
+/// stream.write_all(b"hello world").await?;
+///
+/// let mut buf = vec![0u8; 1024];
+/// let n = stream.read(&mut buf).await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[cfg_attr(feature = "docs", doc(cfg(feature = "tcp")))]
+#[derive(Debug)]
+pub struct OwnedTcpStream(pub(super) Watcher<net::TcpStream>);
+
+impl OwnedTcpStream {
+    /// Connect to a socket addr
+    async fn connect_once(addr: SocketAddr) -> io::Result<Self> {
+        let watcher = Watcher::new(net::TcpStream::connect(addr)?);
+        // wait for connection established
+        watcher.write_ready().await;
+        match watcher.take_error() {
+            Ok(None) => Ok(Self(watcher)),
+            Ok(Some(err)) | Err(err) => Err(err),
+        }
+    }
+
+    /// Creates a new TCP stream connected to the specified address, like
+    /// [`TcpStream::connect`].
+    ///
+    /// [`TcpStream::connect`]: struct.TcpStream.html#method.connect
+    ///
+    /// # Blocking
+    ///
+    /// This method may be blocked by resolving.
+    /// You can resolve addrs asynchronously by [`Resolver`].
+    ///
+    /// [`Resolver`]: trait.Resolver.html
+    pub async fn connect(addrs: impl ToSocketAddrs) -> io::Result<Self> {
+        let mut error = None;
+        for addr in addrs.to_socket_addrs()? {
+            match Self::connect_once(addr).await {
+                Err(err) => error = Some(err),
+                ok => return ok,
+            }
+        }
+        Err(error.unwrap_or_else(resolve_none))
+    }
+
+    /// Returns the local address that this stream is connected to.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Returns the remote address that this stream is connected to.
+    #[inline]
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.0.peer_addr()
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    #[inline]
+    pub fn shutdown(&self, how: std::net::Shutdown) -> std::io::Result<()> {
+        self.0.shutdown(how)
+    }
+
+    /// Converts this stream into a [`TcpStream`], which can be cloned, keeping its
+    /// registration.
+    ///
+    /// [`TcpStream`]: struct.TcpStream.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::OwnedTcpStream;
+    ///
+    /// let stream = OwnedTcpStream::connect("127.0.0.1:8080").await?.into_shared();
+    /// let writer = stream.clone();
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn into_shared(self) -> TcpStream {
+        TcpStream::new(Arc::new(self.0))
+    }
+}
+
+impl From<StdStream> for OwnedTcpStream {
+    fn from(stream: StdStream) -> Self {
+        Self(Watcher::new(net::TcpStream::from_std(stream)))
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for OwnedTcpStream {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        util::borrow_fd(&*self.0)
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for OwnedTcpStream {
+    /// Share raw fd of `OwnedTcpStream`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsyncRead for OwnedTcpStream {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_read_with(cx, |mut i| i.read(buf))
+    }
+
+    #[inline]
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_read_with(cx, |mut i| i.read_vectored(bufs))
+    }
+}
+
+impl AsyncWrite for OwnedTcpStream {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_write_with(cx, |mut o| o.write(buf))
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_write_with(cx, |mut o| o.write_vectored(bufs))
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.poll_write_with(cx, |mut o| o.flush())
+    }
+
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.shutdown(std::net::Shutdown::Both)?;
+        Poll::Ready(Ok(()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::net::TcpStream;
@@ -412,6 +930,34 @@ mod tests {
         })
     }
 
+    #[test]
+    fn owned() -> io::Result<()> {
+        use crate::net::{OwnedTcpStream, TcpListener};
+
+        block_on(async {
+            let addr = start_server()?;
+            let mut stream = OwnedTcpStream::connect(addr).await?;
+            stream.write_all(DATA).await?;
+            let mut recv_data = String::new();
+            stream.read_to_string(&mut recv_data).await?;
+            let local_addr: SocketAddr = recv_data.parse().unwrap();
+            assert_eq!(local_addr, stream.local_addr()?);
+            assert_eq!(addr, stream.peer_addr()?);
+
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let client = TcpStream::connect(listener.local_addr()?).await?;
+            let (stream, peer) = listener.accept_owned().await?;
+            assert_eq!(client.local_addr()?, peer);
+            // the shared stream reads what the client writes
+            let mut stream = stream.into_shared();
+            client.clone().write_all(DATA).await?;
+            let mut data = [0; DATA.len()];
+            stream.read_exact(&mut data).await?;
+            assert_eq!(DATA, data);
+            Ok(())
+        })
+    }
+
     #[test]
     fn peek() -> io::Result<()> {
         async fn peek_to_string(stream: TcpStream) -> io::Result<String> {
@@ -476,4 +1022,110 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn read_timeout() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let mut stream = TcpStream::connect(listener.local_addr()?).await?;
+            assert!(stream
+                .set_read_timeout(Some(Duration::from_secs(0)))
+                .is_err());
+            stream.set_read_timeout(Some(Duration::from_millis(100)))?;
+            assert_eq!(Some(Duration::from_millis(100)), stream.read_timeout()?);
+            let mut data = [0; 1024];
+            let err = stream.read(&mut data).await.unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+            stream.set_read_timeout(None)?;
+            assert_eq!(None, stream.read_timeout()?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn try_clone() -> io::Result<()> {
+        block_on(async {
+            let addr = start_server()?;
+            let mut stream = TcpStream::connect(addr).await?;
+            let mut clone = stream.try_clone()?;
+            clone.set_read_timeout(Some(Duration::from_millis(100)))?;
+            assert_eq!(None, stream.read_timeout()?);
+
+            // one handle writes while the other reads
+            clone.write_all(DATA).await?;
+            let mut recv_data = String::new();
+            stream.read_to_string(&mut recv_data).await?;
+            let local_addr: SocketAddr = recv_data.parse().unwrap();
+            assert_eq!(local_addr, clone.local_addr()?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn close_on_exec() -> io::Result<()> {
+        block_on(async {
+            let addr = start_server()?;
+            let stream = TcpStream::connect(addr).await?;
+            assert!(stream.close_on_exec()?);
+            stream.set_close_on_exec(false)?;
+            assert!(!stream.close_on_exec()?);
+            // the duplicate of `try_clone` is closed on exec anyway
+            assert!(stream.try_clone()?.close_on_exec()?);
+            stream.set_close_on_exec(true)?;
+            assert!(stream.close_on_exec()?);
+            Ok(())
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn send_zc() -> io::Result<()> {
+        use std::io::Read;
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?;
+            let server = thread::spawn(move || -> io::Result<Vec<u8>> {
+                let mut data = Vec::new();
+                listener.accept()?.0.read_to_end(&mut data)?;
+                Ok(data)
+            });
+            let stream = TcpStream::connect(addr).await?;
+            let buf: Vec<u8> = (0..1 << 20).map(|i| i as u8).collect();
+            let sent = stream.send_zc(buf).await?;
+            assert_eq!(1 << 20, sent.sent());
+            let buf = sent.await?;
+            assert!(stream.send_zc(Vec::new()).await?.await?.is_empty());
+            stream.shutdown(Shutdown::Write)?;
+            assert_eq!(buf, server.join().unwrap()?);
+            Ok(())
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn out_of_band() -> io::Result<()> {
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let mut client = TcpStream::connect(listener.local_addr()?).await?;
+            let mut server = TcpStream::from(listener.accept()?.0);
+            client.write_all(b"ab").await?;
+            // only the last byte is urgent
+            assert_eq!(2, client.send_oob(b"c!").await?);
+
+            let mut data = Vec::new();
+            let mut buf = [0; 16];
+            while data.len() < 3 {
+                assert!(!server.at_mark()?);
+                let n = server.read(&mut buf).await?;
+                data.extend_from_slice(&buf[..n]);
+            }
+            assert_eq!(b"abc", &data[..]);
+            assert!(server.at_mark()?);
+            assert_eq!(1, server.recv_oob(&mut buf).await?);
+            assert_eq!(b'!', buf[0]);
+            let err = server.recv_oob(&mut buf).await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+            Ok(())
+        })
+    }
 }
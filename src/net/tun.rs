@@ -0,0 +1,445 @@
+//! TUN/TAP devices for networking in userspace.
+//!
+//! A TUN device is a virtual network interface whose IP packets are read and written by a
+//! process instead of a network card, and a TAP device is one of Ethernet frames. A
+//! [`Device`] is opened by a [`Builder`], then read and written one packet at a time with the
+//! [`AsyncRead`] and [`AsyncWrite`] traits, as a VPN does. The interface only lives as long
+//! as its device is open, and it takes `CAP_NET_ADMIN` to be created.
+//!
+//! [`Device`]: struct.Device.html
+//! [`Builder`]: struct.Builder.html
+//! [`AsyncRead`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncRead.html
+//! [`AsyncWrite`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncWrite.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use futures::prelude::*;
+//! use tio::net::tun::Builder;
+//!
+//! let mut device = Builder::new().name("tun0").mtu(1400).open()?;
+//! device.set_up(true)?;
+//!
+//! let mut packet = vec![0u8; 1400];
+//! loop {
+//!     let n = device.read(&mut packet).await?;
+//!     println!("{} bytes from {}", n, device.name());
+//! }
+//! #
+//! # Ok(()) }) }
+//! ```
+
+use crate::net::poll::{Fd, Watcher};
+use crate::net::sys::IfReq;
+use crate::net::util;
+use futures::task::{Context, Poll};
+use futures::{AsyncRead, AsyncWrite};
+use rustix::net::{AddressFamily, SocketFlags, SocketType};
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::pin::Pin;
+use std::time::Duration;
+
+/// The clone device, from which every device is opened.
+const CLONE_DEVICE: &str = "/dev/net/tun";
+
+/// Device factory, which can be used in order to configure the properties of a new device.
+///
+/// By default, it opens a TUN device without the packet information, named by the kernel
+/// like `tun0`.
+#[derive(Debug, Default, Clone)]
+pub struct Builder {
+    name: Option<String>,
+    tap: bool,
+    packet_info: bool,
+    multi_queue: bool,
+    mtu: Option<u32>,
+}
+
+/// A queue of a TUN/TAP device.
+///
+/// Every read takes a packet from the interface, and every write puts one into it. A packet
+/// which does not fit into the buffer of a read is truncated.
+#[derive(Debug)]
+pub struct Device {
+    watcher: Watcher<Fd>,
+    name: String,
+    flags: libc::c_short,
+}
+
+impl Builder {
+    /// Creates a new builder, from which the properties of a new device can be configured.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the interface, which is attached to if it exists already.
+    ///
+    /// The name can contain a `%d`, which the kernel replaces with the first free number.
+    #[inline]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Opens a TAP device of Ethernet frames rather than a TUN device of IP packets.
+    #[inline]
+    pub fn tap(mut self, tap: bool) -> Self {
+        self.tap = tap;
+        self
+    }
+
+    /// Prefixes every packet with the packet information.
+    ///
+    /// The information is four bytes: some flags, and the protocol of the packet as an
+    /// EtherType, both of them big-endian.
+    #[inline]
+    pub fn packet_info(mut self, packet_info: bool) -> Self {
+        self.packet_info = packet_info;
+        self
+    }
+
+    /// Opens a device of multiple queues, across which the kernel spreads the packets.
+    ///
+    /// Another queue is opened by [`Device::open_queue`].
+    ///
+    /// [`Device::open_queue`]: struct.Device.html#method.open_queue
+    #[inline]
+    pub fn multi_queue(mut self, multi_queue: bool) -> Self {
+        self.multi_queue = multi_queue;
+        self
+    }
+
+    /// Sets the MTU of the interface once it is opened.
+    #[inline]
+    pub fn mtu(mut self, mtu: u32) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
+    /// Opens the device with the configured properties.
+    ///
+    /// It must be opened within a runtime.
+    pub fn open(self) -> io::Result<Device> {
+        let mut flags = if self.tap {
+            libc::IFF_TAP
+        } else {
+            libc::IFF_TUN
+        };
+        if !self.packet_info {
+            flags |= libc::IFF_NO_PI;
+        }
+        if self.multi_queue {
+            flags |= libc::IFF_MULTI_QUEUE;
+        }
+        let name = self.name.as_deref().unwrap_or("");
+        let device = Device::attach(name, flags as libc::c_short)?;
+        if let Some(mtu) = self.mtu {
+            device.set_mtu(mtu)?;
+        }
+        Ok(device)
+    }
+}
+
+impl Device {
+    fn attach(name: &str, flags: libc::c_short) -> io::Result<Self> {
+        let mut req = IfReq::new(name)?;
+        let fd: OwnedFd = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(CLONE_DEVICE)?
+            .into();
+        req.set_flags(flags);
+        req.ioctl(fd.as_fd(), libc::TUNSETIFF)?;
+        Ok(Self {
+            watcher: Watcher::new(Fd(fd)),
+            name: req.name(),
+            flags,
+        })
+    }
+
+    /// Returns the name of the interface.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true` if this is a TAP device.
+    #[inline]
+    pub fn is_tap(&self) -> bool {
+        self.flags & libc::IFF_TAP as libc::c_short != 0
+    }
+
+    /// Opens another queue of this device, which must have been opened with
+    /// [`Builder::multi_queue`].
+    ///
+    /// It must be opened within a runtime.
+    ///
+    /// [`Builder::multi_queue`]: struct.Builder.html#method.multi_queue
+    #[inline]
+    pub fn open_queue(&self) -> io::Result<Self> {
+        Self::attach(&self.name, self.flags)
+    }
+
+    /// Returns whether this device is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this device is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The device is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the device, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the MTU of the interface.
+    pub fn mtu(&self) -> io::Result<u32> {
+        let mut req = IfReq::new(&self.name)?;
+        req.ioctl(control()?.as_fd(), libc::SIOCGIFMTU as libc::Ioctl)?;
+        Ok(req.mtu() as u32)
+    }
+
+    /// Sets the MTU of the interface.
+    pub fn set_mtu(&self, mtu: u32) -> io::Result<()> {
+        let mut req = IfReq::new(&self.name)?;
+        req.set_mtu(mtu as libc::c_int);
+        req.ioctl(control()?.as_fd(), libc::SIOCSIFMTU as libc::Ioctl)
+    }
+
+    /// Returns `true` if the interface is up.
+    pub fn is_up(&self) -> io::Result<bool> {
+        let mut req = IfReq::new(&self.name)?;
+        req.ioctl(control()?.as_fd(), libc::SIOCGIFFLAGS as libc::Ioctl)?;
+        Ok(req.flags() & libc::IFF_UP as libc::c_short != 0)
+    }
+
+    /// Brings the interface up or down.
+    ///
+    /// No packet is read from the interface until it is up.
+    pub fn set_up(&self, up: bool) -> io::Result<()> {
+        let control = control()?;
+        let mut req = IfReq::new(&self.name)?;
+        req.ioctl(control.as_fd(), libc::SIOCGIFFLAGS as libc::Ioctl)?;
+        let flags = if up {
+            req.flags() | libc::IFF_UP as libc::c_short
+        } else {
+            req.flags() & !(libc::IFF_UP as libc::c_short)
+        };
+        req.set_flags(flags);
+        req.ioctl(control.as_fd(), libc::SIOCSIFFLAGS as libc::Ioctl)
+    }
+
+    /// Returns the read timeout of this device.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.watcher.read_timeout()
+    }
+
+    /// Sets the read timeout of this device.
+    ///
+    /// Every read which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.watcher.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this device.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.watcher.write_timeout()
+    }
+
+    /// Sets the write timeout of this device.
+    ///
+    /// Every write which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.watcher.set_write_timeout(dur)
+    }
+}
+
+/// Opens a socket to configure the interfaces with.
+#[inline]
+fn control() -> io::Result<OwnedFd> {
+    Ok(rustix::net::socket_with(
+        AddressFamily::INET,
+        SocketType::DGRAM,
+        SocketFlags::CLOEXEC,
+        None,
+    )?)
+}
+
+impl AsyncRead for Device {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.watcher
+            .poll_read_with(cx, |fd| Ok(rustix::io::read(fd, &mut *buf)?))
+    }
+}
+
+impl AsyncWrite for Device {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.watcher
+            .poll_write_with(cx, |fd| Ok(rustix::io::write(fd, buf)?))
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// The device is not closed until this is dropped.
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsFd for Device {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.watcher.as_fd()
+    }
+}
+
+impl AsRawFd for Device {
+    /// Share raw fd of `Device`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Builder, Device};
+    use crate::task::block_on;
+    use futures::AsyncReadExt;
+    use std::io;
+    use std::time::Duration;
+
+    /// Opens the device, unless TUN/TAP devices are not permitted or not supported.
+    fn open(builder: Builder) -> io::Result<Option<Device>> {
+        match builder.open() {
+            Err(err)
+                if err.kind() == io::ErrorKind::PermissionDenied
+                    || err.kind() == io::ErrorKind::NotFound =>
+            {
+                Ok(None)
+            }
+            ret => ret.map(Some),
+        }
+    }
+
+    #[test]
+    fn configure() -> io::Result<()> {
+        block_on(async {
+            let device = match open(Builder::new().name("tio-tun%d").mtu(1400))? {
+                Some(device) => device,
+                None => return Ok(()),
+            };
+            assert!(device.name().starts_with("tio-tun"));
+            assert!(!device.is_tap());
+            assert_eq!(1400, device.mtu()?);
+            device.set_mtu(1280)?;
+            assert_eq!(1280, device.mtu()?);
+            assert!(!device.is_up()?);
+            device.set_up(true)?;
+            assert!(device.is_up()?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn multi_queue() -> io::Result<()> {
+        block_on(async {
+            let builder = Builder::new().name("tio-tap%d").tap(true).multi_queue(true);
+            let device = match open(builder)? {
+                Some(device) => device,
+                None => return Ok(()),
+            };
+            let queue = device.open_queue()?;
+            assert_eq!(device.name(), queue.name());
+            assert!(queue.is_tap());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn packet_info() -> io::Result<()> {
+        let ipv6 =
+            std::fs::read_to_string("/proc/sys/net/ipv6/conf/default/disable_ipv6");
+        if ipv6.map_or(true, |disabled| disabled.trim() != "0") {
+            // no packet is sent without IPv6
+            return Ok(());
+        }
+        block_on(async {
+            let mut device = match open(Builder::new().packet_info(true))? {
+                Some(device) => device,
+                None => return Ok(()),
+            };
+            // the kernel solicits routers and reports multicast listeners once it is up
+            device.set_up(true)?;
+            device.set_read_timeout(Some(Duration::from_secs(5)))?;
+            let mut packet = [0; 1500];
+            let n = device.read(&mut packet).await?;
+            assert!(n > 4 + 40);
+            assert_eq!([0, 0, 0x86, 0xdd], packet[..4]);
+            assert_eq!(6, packet[4] >> 4);
+            Ok(())
+        })
+    }
+}
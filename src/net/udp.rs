@@ -1,10 +1,20 @@
 use crate::net::poll::Watcher;
-use crate::net::util::resolve_none;
+use crate::net::util::{self, resolve_none};
+#[cfg(target_os = "linux")]
+use crate::net::{zerocopy, ZeroCopySend};
 use futures::future;
 use mio::net;
+#[cfg(unix)]
+use rustix::net::{RecvFlags, SendFlags};
+#[cfg(unix)]
+use std::convert::TryFrom;
 use std::io;
+use std::mem::MaybeUninit;
 use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket as StdSocket};
+#[cfg(unix)]
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// A UDP socket.
 ///
@@ -44,17 +54,19 @@ use std::sync::Arc;
 /// ```
 #[cfg_attr(feature = "docs", doc(cfg(feature = "udp")))]
 #[derive(Debug, Clone)]
-pub struct UdpSocket(Arc<Watcher<net::UdpSocket>>);
+pub struct UdpSocket(
+    pub(crate) Arc<Watcher<net::UdpSocket>>,
+    #[cfg(target_os = "linux")] Arc<zerocopy::Tracker>,
+);
 
 impl UdpSocket {
-    /// Bind a socket addr
-    fn bind_once(addr: SocketAddr) -> io::Result<Self> {
-        let watcher = Watcher::new(net::UdpSocket::bind(addr)?);
-        let inner = Arc::new(watcher);
-        match inner.take_error() {
-            Ok(None) => Ok(Self(inner)),
-            Ok(Some(err)) | Err(err) => Err(err),
-        }
+    #[inline]
+    fn new(watcher: Arc<Watcher<net::UdpSocket>>) -> Self {
+        Self(
+            watcher,
+            #[cfg(target_os = "linux")]
+            Arc::default(),
+        )
     }
 
     /// Creates a UDP socket from the given address.
@@ -83,15 +95,7 @@ impl UdpSocket {
     ///
     /// [`Resolver`]: trait.Resolver.html
     pub fn bind<A: ToSocketAddrs>(addrs: A) -> io::Result<UdpSocket> {
-        let mut error = None;
-        for addr in addrs.to_socket_addrs()? {
-            match Self::bind_once(addr) {
-                Err(err) => error = Some(err),
-                ok => return ok,
-            }
-        }
-
-        Err(error.unwrap_or_else(resolve_none))
+        OwnedUdpSocket::bind(addrs).map(OwnedUdpSocket::into_shared)
     }
 
     /// Returns the local address that this listener is bound to.
@@ -193,6 +197,95 @@ impl UdpSocket {
             .await
     }
 
+    /// Receives data from the socket into a buffer which may be uninitialized.
+    ///
+    /// On success, returns the part of `buf` the datagram is read into and the origin. A large
+    /// buffer used by [`recv_from`] is initialized by the caller first, which this spares.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::mem::MaybeUninit;
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    ///
+    /// let mut buf = vec![MaybeUninit::uninit(); 64 * 1024];
+    /// let (data, peer) = socket.recv_from_uninit(&mut buf).await?;
+    /// println!("Received {:?} from {}", data, peer);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`recv_from`]: #method.recv_from
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub async fn recv_from_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+    ) -> io::Result<(&'a mut [u8], SocketAddr)> {
+        util::recv_uninit(buf, |cx, buf| {
+            self.0.poll_read_with(cx, |inner| {
+                let fd = util::borrow_fd(inner);
+                match rustix::net::recvfrom(fd, &mut *buf, RecvFlags::empty())? {
+                    ((init, _), _, Some(addr)) => {
+                        Ok((init.len(), SocketAddr::try_from(addr)?))
+                    }
+                    (_, _, None) => Err(io::ErrorKind::InvalidData.into()),
+                }
+            })
+        })
+        .await
+    }
+
+    /// Receives data from the socket, telling the length of the whole datagram.
+    ///
+    /// On success, returns the length of the datagram and the origin. A datagram longer than
+    /// `buf` is truncated to fit, the same as by [`recv_from`], but its length is still
+    /// returned, so a length greater than that of `buf` tells the bytes lost.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    ///
+    /// let mut buf = vec![0; 1024];
+    /// let (n, peer) = socket.recv_from_full(&mut buf).await?;
+    /// if n > buf.len() {
+    ///     println!("Lost {} bytes from {}", n - buf.len(), peer);
+    /// }
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`recv_from`]: #method.recv_from
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[cfg_attr(
+        feature = "docs",
+        doc(cfg(any(target_os = "linux", target_os = "android")))
+    )]
+    pub async fn recv_from_full(
+        &self,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr)> {
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |inner| {
+                let fd = util::borrow_fd(inner);
+                match rustix::net::recvfrom(fd, &mut *buf, RecvFlags::TRUNC)? {
+                    (_, len, Some(addr)) => Ok((len, SocketAddr::try_from(addr)?)),
+                    (_, _, None) => Err(io::ErrorKind::InvalidData.into()),
+                }
+            })
+        })
+        .await
+    }
+
     /// Connects the UDP socket to a remote address.
     ///
     /// When connected, methods [`send`] and [`recv`] will use the specified address for sending
@@ -260,6 +353,116 @@ impl UdpSocket {
         future::poll_fn(|cx| self.0.poll_write_with(cx, |inner| inner.send(buf))).await
     }
 
+    /// Sends data on the socket to the remote address to which it is connected, with the
+    /// flags of `send(2)`.
+    ///
+    /// The flags are those of the `MSG_*` constants of the C library, like `MSG_CONFIRM`, as an
+    /// escape hatch for the flags tio has no method for.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:34254")?;
+    /// socket.connect("127.0.0.1:8080")?;
+    /// // the neighbour is alive, as the peer has just replied
+    /// socket.send_with_flags(b"Hi there!", libc::MSG_CONFIRM).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub async fn send_with_flags(&self, buf: &[u8], flags: i32) -> io::Result<usize> {
+        let flags = SendFlags::from_bits_retain(flags as u32);
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |inner| {
+                Ok(rustix::net::send(util::borrow_fd(inner), buf, flags)?)
+            })
+        })
+        .await
+    }
+
+    /// Sends data on the socket to the given address, with the flags of `sendto(2)`.
+    ///
+    /// The flags are those of the `MSG_*` constants of the C library, like `MSG_CONFIRM`, as an
+    /// escape hatch for the flags tio has no method for.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let addr = "192.168.1.2:8080".parse().unwrap();
+    /// // straight to the host on the local network, ignoring the routes
+    /// socket.send_to_with_flags(b"Hi there!", addr, libc::MSG_DONTROUTE).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub async fn send_to_with_flags(
+        &self,
+        buf: &[u8],
+        addr: SocketAddr,
+        flags: i32,
+    ) -> io::Result<usize> {
+        let flags = SendFlags::from_bits_retain(flags as u32);
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |inner| {
+                Ok(rustix::net::sendto(
+                    util::borrow_fd(inner),
+                    buf,
+                    flags,
+                    &addr,
+                )?)
+            })
+        })
+        .await
+    }
+
+    /// Sends `buf` as a datagram to the remote address to which the socket is connected,
+    /// without copying it into the kernel.
+    ///
+    /// The kernel sends the bytes from the pages of `buf` in place, and notifies once it is
+    /// done with them. On success, returns a [`ZeroCopySend`] of the number of bytes sent,
+    /// which resolves to `buf` after the notification. It pays off for datagrams of some
+    /// kilobytes, as those of segmentation offload.
+    ///
+    /// The sends are matched to the notifications by the count of the sends of this socket
+    /// and its clones, so the zero-copy sends must not be mixed with those of a handle of
+    /// [`try_clone`]. This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:34254")?;
+    /// socket.connect("127.0.0.1:8080")?;
+    /// let buf = socket.send_zc(vec![0; 8192]).await?.await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`ZeroCopySend`]: struct.ZeroCopySend.html
+    /// [`try_clone`]: #method.try_clone
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "docs", doc(cfg(target_os = "linux")))]
+    pub async fn send_zc<B>(&self, buf: B) -> io::Result<ZeroCopySend<B>>
+    where
+        B: AsRef<[u8]> + Send + 'static,
+    {
+        zerocopy::send(&self.0, &self.1, buf, false).await
+    }
+
     /// Receives data from the socket.
     ///
     /// On success, returns the number of bytes read.
@@ -285,6 +488,271 @@ impl UdpSocket {
         future::poll_fn(|cx| self.0.poll_read_with(cx, |inner| inner.recv(buf))).await
     }
 
+    /// Receives data from the socket into a buffer which may be uninitialized.
+    ///
+    /// On success, returns the part of `buf` the datagram is read into. See
+    /// [`recv_from_uninit`] for more details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::mem::MaybeUninit;
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// socket.connect("127.0.0.1:8080")?;
+    ///
+    /// let mut buf = [MaybeUninit::uninit(); 1500];
+    /// let data = socket.recv_uninit(&mut buf).await?;
+    /// println!("Received {} bytes", data.len());
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`recv_from_uninit`]: #method.recv_from_uninit
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub async fn recv_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+    ) -> io::Result<&'a mut [u8]> {
+        let (data, ()) = util::recv_uninit(buf, |cx, buf| {
+            self.0.poll_read_with(cx, |inner| {
+                let fd = util::borrow_fd(inner);
+                let ((init, _), _) =
+                    rustix::net::recv(fd, &mut *buf, RecvFlags::empty())?;
+                Ok((init.len(), ()))
+            })
+        })
+        .await?;
+        Ok(data)
+    }
+
+    /// Receives data from the socket, from the remote address to which it is connected, with
+    /// the flags of `recv(2)`.
+    ///
+    /// The flags are those of the `MSG_*` constants of the C library, like `MSG_CONFIRM`, as an
+    /// escape hatch for the flags tio has no method for. On success, returns what the call
+    /// does, which is the length of the whole datagram with `MSG_TRUNC`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// socket.connect("127.0.0.1:8080")?;
+    /// let mut buf = vec![0; 1024];
+    /// // the datagram stays queued for the next receive
+    /// let n = socket.recv_with_flags(&mut buf, libc::MSG_PEEK).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub async fn recv_with_flags(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<usize> {
+        let flags = RecvFlags::from_bits_retain(flags as u32);
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |inner| {
+                Ok(rustix::net::recv(util::borrow_fd(inner), &mut *buf, flags)?.1)
+            })
+        })
+        .await
+    }
+
+    /// Receives data from the socket, with the flags of `recvfrom(2)`.
+    ///
+    /// The flags are those of the `MSG_*` constants of the C library, like `MSG_CONFIRM`, as an
+    /// escape hatch for the flags tio has no method for. On success, returns what the call
+    /// does along with the origin.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let mut buf = vec![0; 1024];
+    /// let (n, peer) = socket.recv_from_with_flags(&mut buf, libc::MSG_PEEK).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub async fn recv_from_with_flags(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<(usize, SocketAddr)> {
+        let flags = RecvFlags::from_bits_retain(flags as u32);
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |inner| {
+                match rustix::net::recvfrom(util::borrow_fd(inner), &mut *buf, flags)? {
+                    (_, len, Some(addr)) => Ok((len, SocketAddr::try_from(addr)?)),
+                    (_, _, None) => Err(io::ErrorKind::InvalidData.into()),
+                }
+            })
+        })
+        .await
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    ///
+    /// The returned `UdpSocket` is a reference to the same socket that this object references,
+    /// by a duplicated file descriptor registered to the reactor on its own. The handles of
+    /// [`clone`] share one registration, so tasks waiting on them contend for the same
+    /// readiness and timeouts; those of `try_clone` are waited on apart, and start without
+    /// timeouts.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let socket_clone = socket.try_clone()?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`clone`]: #method.clone
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self::from(util::dup::<_, StdSocket>(&**self.0)?))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../process/struct.Command.html#method.pass_fd
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// If the timeout is [`None`], then pending reads will never time out.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every receive which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. The timeout is driven by the timer rather than `SO_RCVTIMEO`, so the
+    /// socket itself stays non-blocking. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+    /// assert_eq!(socket.read_timeout()?, Some(Duration::from_secs(1)));
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// If the timeout is [`None`], then pending writes will never time out.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every send which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// socket.set_write_timeout(Some(Duration::from_secs(1)))?;
+    /// assert_eq!(socket.write_timeout()?, Some(Duration::from_secs(1)));
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
     /// Gets the value of the `SO_BROADCAST` option for this socket.
     ///
     /// For more information about this option, see [`set_broadcast`].
@@ -482,12 +950,242 @@ impl UdpSocket {
     ) -> io::Result<()> {
         self.0.leave_multicast_v6(multiaddr, interface)
     }
+
+    /// Deregisters this socket from the reactor, converting it into its `std` counterpart.
+    ///
+    /// The returned socket stays in non-blocking mode, so it can be configured with the
+    /// socket options tio doesn't expose and converted back by `From`. This fails if clones
+    /// of this socket are still alive, as they share its registration.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let std_socket = socket.into_std()?;
+    /// let socket = UdpSocket::from(std_socket);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docs", doc(cfg(unix)))]
+    #[allow(unsafe_code)]
+    pub fn into_std(self) -> io::Result<StdSocket> {
+        let socket = Watcher::try_unwrap(self.0)?;
+        // Safety: the mio socket gives up the descriptor it owns.
+        Ok(unsafe { StdSocket::from_raw_fd(socket.into_raw_fd()) })
+    }
 }
 
 impl From<StdSocket> for UdpSocket {
     fn from(socket: StdSocket) -> Self {
         let watcher = Watcher::new(net::UdpSocket::from_std(socket));
-        Self(Arc::new(watcher))
+        Self::new(Arc::new(watcher))
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for UdpSocket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        util::borrow_fd(&**self.0)
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for UdpSocket {
+    /// Share raw fd of `UdpSocket`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+/// A UDP socket owned by a single handle.
+///
+/// Unlike a [`UdpSocket`], which shares its registration behind an [`Arc`] so that it can be
+/// cloned, an `OwnedUdpSocket` holds it inline, which saves an indirection on every send and
+/// receive. It covers the hot path only: configure the socket as a [`std::net::UdpSocket`]
+/// before converting it, or turn it [`into_shared`] for the full API.
+///
+/// [`UdpSocket`]: struct.UdpSocket.html
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`std::net::UdpSocket`]: https://doc.rust-lang.org/std/net/struct.UdpSocket.html
+/// [`into_shared`]: #method.into_shared
+///
+/// ## Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use tio::net::OwnedUdpSocket;
+///
+/// let socket = OwnedUdpSocket::bind("127.0.0.1:8080")?;
+/// let mut buf = vec![0u8; 1024];
+///
+/// loop {
+///     let (n, peer) = socket.recv_from(&mut buf).await?;
+///     socket.send_to(&buf[..n], &peer).await?;
+/// }
+/// #
+/// # }) }
+/// ```
+#[cfg_attr(feature = "docs", doc(cfg(feature = "udp")))]
+#[derive(Debug)]
+pub struct OwnedUdpSocket(Watcher<net::UdpSocket>);
+
+impl OwnedUdpSocket {
+    /// Bind a socket addr
+    fn bind_once(addr: SocketAddr) -> io::Result<Self> {
+        let watcher = Watcher::new(net::UdpSocket::bind(addr)?);
+        match watcher.take_error() {
+            Ok(None) => Ok(Self(watcher)),
+            Ok(Some(err)) | Err(err) => Err(err),
+        }
+    }
+
+    /// Creates a UDP socket from the given address, like [`UdpSocket::bind`].
+    ///
+    /// [`UdpSocket::bind`]: struct.UdpSocket.html#method.bind
+    ///
+    /// # Blocking
+    ///
+    /// This method may be blocked by resolving.
+    /// You can resolve addrs asynchronously by [`Resolver`].
+    ///
+    /// [`Resolver`]: trait.Resolver.html
+    pub fn bind<A: ToSocketAddrs>(addrs: A) -> io::Result<Self> {
+        let mut error = None;
+        for addr in addrs.to_socket_addrs()? {
+            match Self::bind_once(addr) {
+                Err(err) => error = Some(err),
+                ok => return ok,
+            }
+        }
+        Err(error.unwrap_or_else(resolve_none))
+    }
+
+    /// Returns the local address that this socket is bound to.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Connects this socket to a remote address, like [`UdpSocket::connect`].
+    ///
+    /// [`UdpSocket::connect`]: struct.UdpSocket.html#method.connect
+    ///
+    /// # Blocking
+    ///
+    /// This method may be blocked by resolving.
+    /// You can resolve addrs asynchronously by [`Resolver`].
+    ///
+    /// [`Resolver`]: trait.Resolver.html
+    pub fn connect<A: ToSocketAddrs>(&self, addrs: A) -> io::Result<()> {
+        let mut error = None;
+        for addr in addrs.to_socket_addrs()? {
+            match self.0.connect(addr) {
+                Err(err) => error = Some(err),
+                ok => return ok,
+            }
+        }
+        Err(error.unwrap_or_else(resolve_none))
+    }
+
+    /// Sends data on the socket to the given address, returning the number of bytes written.
+    ///
+    /// # Blocking
+    ///
+    /// This method may be blocked by resolving.
+    /// You can resolve addrs asynchronously by [`Resolver`].
+    ///
+    /// [`Resolver`]: trait.Resolver.html
+    #[inline]
+    pub async fn send_to<A: ToSocketAddrs>(
+        &self,
+        buf: &[u8],
+        addrs: A,
+    ) -> io::Result<usize> {
+        let addr = addrs.to_socket_addrs()?.next().ok_or_else(resolve_none)?;
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |inner| inner.send_to(buf, addr))
+        })
+        .await
+    }
+
+    /// Receives data from the socket, returning the number of bytes read and the origin.
+    #[inline]
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        future::poll_fn(|cx| self.0.poll_read_with(cx, |inner| inner.recv_from(buf)))
+            .await
+    }
+
+    /// Sends data on the socket to the remote address to which it is connected, returning the
+    /// number of bytes written.
+    #[inline]
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| self.0.poll_write_with(cx, |inner| inner.send(buf))).await
+    }
+
+    /// Receives data from the remote address to which the socket is connected, returning the
+    /// number of bytes read.
+    #[inline]
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| self.0.poll_read_with(cx, |inner| inner.recv(buf))).await
+    }
+
+    /// Converts this socket into a [`UdpSocket`], which can be cloned, keeping its
+    /// registration.
+    ///
+    /// [`UdpSocket`]: struct.UdpSocket.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::OwnedUdpSocket;
+    ///
+    /// let socket = OwnedUdpSocket::bind("127.0.0.1:0")?.into_shared();
+    /// let sender = socket.clone();
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn into_shared(self) -> UdpSocket {
+        UdpSocket::new(Arc::new(self.0))
+    }
+}
+
+impl From<StdSocket> for OwnedUdpSocket {
+    fn from(socket: StdSocket) -> Self {
+        Self(Watcher::new(net::UdpSocket::from_std(socket)))
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for OwnedUdpSocket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        util::borrow_fd(&*self.0)
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for OwnedUdpSocket {
+    /// Share raw fd of `OwnedUdpSocket`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
     }
 }
 
@@ -497,6 +1195,7 @@ mod tests {
     use crate::task::{block_on, spawn};
     use std::io;
     use std::net::SocketAddr;
+    use std::time::Duration;
 
     const DATA: &[u8] = b"
     If you prick us, do we not bleed?
@@ -551,6 +1250,48 @@ mod tests {
         })
     }
 
+    #[test]
+    fn into_std() -> io::Result<()> {
+        block_on(async {
+            let mut data = [0; 1024];
+            let server_addr = server()?;
+            let socket = one()?;
+            let clone = socket.clone();
+            assert!(clone.into_std().is_err());
+            let std_socket = socket.into_std()?;
+            std_socket.connect(server_addr)?;
+            let socket = UdpSocket::from(std_socket);
+            socket.send(DATA).await?;
+            let size = socket.recv(&mut data).await?;
+            assert_eq!(DATA, &data[..size]);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn owned() -> io::Result<()> {
+        use super::OwnedUdpSocket;
+
+        block_on(async {
+            let mut data = [0; 1024];
+            let server_addr = server()?;
+            let socket = OwnedUdpSocket::bind("127.0.0.1:0")?;
+            socket.send_to(DATA, server_addr).await?;
+            let (size, addr) = socket.recv_from(&mut data).await?;
+            assert_eq!(DATA, &data[..size]);
+            assert_eq!(server_addr, addr);
+            // the registration is kept by the shared socket
+            let local_addr = socket.local_addr()?;
+            let socket = socket.into_shared();
+            assert_eq!(local_addr, socket.local_addr()?);
+            socket.connect(server_addr)?;
+            socket.clone().send(DATA).await?;
+            let size = socket.recv(&mut data).await?;
+            assert_eq!(DATA, &data[..size]);
+            Ok(())
+        })
+    }
+
     #[test]
     fn broadcast() -> io::Result<()> {
         let socket = one()?;
@@ -593,4 +1334,136 @@ mod tests {
         assert_eq!(100, socket.ttl()?);
         Ok(())
     }
+
+    #[test]
+    fn read_timeout() -> io::Result<()> {
+        block_on(async {
+            let socket = one()?;
+            socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+            let mut data = [0; 1024];
+            let err = socket.recv_from(&mut data).await.unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn concurrent_read_timeout() -> io::Result<()> {
+        block_on(async {
+            // every pending receive on the clones times out, not only the one polled last
+            let socket = one()?;
+            socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+            let tasks = (0..3)
+                .map(|_| {
+                    let socket = socket.clone();
+                    spawn(async move { socket.recv(&mut [0; 16]).await })
+                })
+                .collect::<Vec<_>>();
+            for task in tasks {
+                let err = task.await.unwrap().unwrap_err();
+                assert_eq!(io::ErrorKind::TimedOut, err.kind());
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn try_clone() -> io::Result<()> {
+        block_on(async {
+            let mut data = [0; 1024];
+            let socket = one()?;
+            socket.connect(server()?)?;
+            let clone = socket.try_clone()?;
+            assert_eq!(socket.local_addr()?, clone.local_addr()?);
+            clone.set_read_timeout(Some(Duration::from_secs(1)))?;
+            assert_eq!(None, socket.read_timeout()?);
+
+            // the echo to one handle is received by the other
+            socket.send(DATA).await?;
+            let size = clone.recv(&mut data).await?;
+            assert_eq!(DATA, &data[..size]);
+            Ok(())
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn send_zc() -> io::Result<()> {
+        block_on(async {
+            let mut data = [0; 1024];
+            let socket = one()?;
+            socket.connect(server()?)?;
+            // a dropped send keeps its buffer until the later ones are notified
+            drop(socket.send_zc(DATA).await?);
+            let sent = socket.send_zc(DATA.to_vec()).await?;
+            assert_eq!(DATA.len(), sent.sent());
+            assert_eq!(DATA, &sent.await?[..]);
+            for _ in 0..2 {
+                let size = socket.recv(&mut data).await?;
+                assert_eq!(DATA, &data[..size]);
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn recv_uninit() -> io::Result<()> {
+        use std::mem::MaybeUninit;
+
+        block_on(async {
+            let (socket, peer) = (one()?, one()?);
+            peer.send_to(DATA, socket.local_addr()?).await?;
+            let mut buf = vec![MaybeUninit::uninit(); 64 * 1024];
+            let (data, addr) = socket.recv_from_uninit(&mut buf).await?;
+            assert_eq!(DATA, data);
+            assert_eq!(peer.local_addr()?, addr);
+            socket.connect(peer.local_addr()?)?;
+            peer.send_to(b"short", socket.local_addr()?).await?;
+            let mut buf = [MaybeUninit::uninit(); 4];
+            // truncated to fit
+            assert_eq!(b"shor", socket.recv_uninit(&mut buf).await?);
+            Ok(())
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn recv_from_full() -> io::Result<()> {
+        block_on(async {
+            let (socket, peer) = (one()?, one()?);
+            peer.send_to(DATA, socket.local_addr()?).await?;
+            let mut data = [0; 16];
+            let (size, addr) = socket.recv_from_full(&mut data).await?;
+            assert_eq!(DATA.len(), size);
+            assert_eq!(&DATA[..16], &data);
+            assert_eq!(peer.local_addr()?, addr);
+
+            // an empty datagram is not truncated
+            peer.send_to(&[], socket.local_addr()?).await?;
+            assert_eq!(0, socket.recv_from_full(&mut data).await?.0);
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn with_flags() -> io::Result<()> {
+        block_on(async {
+            let (socket, peer) = (one()?, one()?);
+            socket.connect(peer.local_addr()?)?;
+            socket.send_with_flags(DATA, libc::MSG_DONTROUTE).await?;
+            let mut data = [0; 1024];
+            // a peek leaves the datagram queued
+            let (size, addr) =
+                peer.recv_from_with_flags(&mut data, libc::MSG_PEEK).await?;
+            assert_eq!(DATA, &data[..size]);
+            assert_eq!(socket.local_addr()?, addr);
+            assert_eq!(DATA.len(), peer.recv(&mut data).await?);
+
+            peer.send_to_with_flags(DATA, addr, 0).await?;
+            let size = socket.recv_with_flags(&mut data, 0).await?;
+            assert_eq!(DATA, &data[..size]);
+            Ok(())
+        })
+    }
 }
@@ -5,6 +5,6 @@ mod listener;
 mod stream;
 
 pub use datagram::UnixDatagram;
-pub use listener::UnixListener;
+pub use listener::{Incoming, IntoIncoming, UnixListener};
 pub use mio::net::SocketAddr;
 pub use stream::UnixStream;
@@ -1,13 +1,17 @@
 use super::SocketAddr;
 use crate::net::poll::Watcher;
+use crate::net::util;
 use futures::future;
 use mio::net;
+use rustix::net::{RecvFlags, SendFlags};
 use std::io;
+use std::mem::MaybeUninit;
 use std::net::Shutdown;
-use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
 use std::os::unix::net::UnixDatagram as StdDatagram;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// A Unix datagram socket.
 ///
@@ -38,7 +42,7 @@ use std::sync::Arc;
 /// # Ok(()) }) }
 /// ```
 #[derive(Debug, Clone)]
-pub struct UnixDatagram(Arc<Watcher<net::UnixDatagram>>);
+pub struct UnixDatagram(pub(crate) Arc<Watcher<net::UnixDatagram>>);
 
 impl UnixDatagram {
     fn new(datagram: net::UnixDatagram) -> UnixDatagram {
@@ -192,6 +196,55 @@ impl UnixDatagram {
             .await
     }
 
+    /// Receives data from the socket, telling the length of the whole datagram.
+    ///
+    /// On success, returns the length of the datagram and the address from whence it came. A
+    /// datagram longer than `buf` is truncated to fit, the same as by [`recv_from`], but its
+    /// length is still returned, so a length greater than that of `buf` tells the bytes lost.
+    ///
+    /// The length is peeked before the datagram is received, so it may be of another datagram
+    /// if a receive on another thread takes this one in between.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::bind("/tmp/socket")?;
+    /// let mut buf = vec![0; 1024];
+    /// let (n, peer) = socket.recv_from_full(&mut buf).await?;
+    /// if n > buf.len() {
+    ///     println!("Lost {} bytes from {:?}", n - buf.len(), peer);
+    /// }
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`recv_from`]: #method.recv_from
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[cfg_attr(
+        feature = "docs",
+        doc(cfg(any(target_os = "linux", target_os = "android")))
+    )]
+    pub async fn recv_from_full(
+        &self,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr)> {
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |inner| {
+                // the length of the next datagram, which stays queued
+                let flags = RecvFlags::PEEK | RecvFlags::TRUNC;
+                let (_, len) =
+                    rustix::net::recv(util::borrow_fd(inner), &mut [0; 0], flags)?;
+                let (n, addr) = inner.recv_from(buf)?;
+                Ok((len.max(n), addr))
+            })
+        })
+        .await
+    }
+
     /// Receives data from the socket.
     ///
     /// On success, returns the number of bytes read.
@@ -213,6 +266,77 @@ impl UnixDatagram {
         future::poll_fn(|cx| self.0.poll_read_with(cx, |inner| inner.recv(buf))).await
     }
 
+    /// Receives data from the socket into a buffer which may be uninitialized.
+    ///
+    /// On success, returns the part of `buf` the datagram is read into. A large buffer used
+    /// by [`recv`] is initialized by the caller first, which this spares.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::mem::MaybeUninit;
+    /// use tio::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::bind("/tmp/socket")?;
+    /// let mut buf = vec![MaybeUninit::uninit(); 64 * 1024];
+    /// let data = socket.recv_uninit(&mut buf).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`recv`]: #method.recv
+    pub async fn recv_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+    ) -> io::Result<&'a mut [u8]> {
+        let (data, ()) = util::recv_uninit(buf, |cx, buf| {
+            self.0.poll_read_with(cx, |inner| {
+                let fd = util::borrow_fd(inner);
+                let ((init, _), _) =
+                    rustix::net::recv(fd, &mut *buf, RecvFlags::empty())?;
+                Ok((init.len(), ()))
+            })
+        })
+        .await?;
+        Ok(data)
+    }
+
+    /// Receives data from the socket, with the flags of `recv(2)`.
+    ///
+    /// The flags are those of the `MSG_*` constants of the C library, like `MSG_CONFIRM`, as an
+    /// escape hatch for the flags tio has no method for. On success, returns what the call
+    /// does, which is the length of the whole datagram with `MSG_TRUNC` on Linux.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::bind("/tmp/socket")?;
+    /// let mut buf = vec![0; 1024];
+    /// // the datagram stays queued for the next receive
+    /// let n = socket.recv_with_flags(&mut buf, libc::MSG_PEEK).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn recv_with_flags(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<usize> {
+        let flags = RecvFlags::from_bits_retain(flags as u32);
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |inner| {
+                Ok(rustix::net::recv(util::borrow_fd(inner), &mut *buf, flags)?.1)
+            })
+        })
+        .await
+    }
+
     /// Sends data on the socket to the specified address.
     ///
     /// On success, returns the number of bytes written.
@@ -262,6 +386,175 @@ impl UnixDatagram {
         future::poll_fn(|cx| self.0.poll_write_with(cx, |inner| inner.send(buf))).await
     }
 
+    /// Sends data on the socket to the socket's peer, with the flags of `send(2)`.
+    ///
+    /// The flags are those of the `MSG_*` constants of the C library, like `MSG_CONFIRM`, as an
+    /// escape hatch for the flags tio has no method for.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::unbound()?;
+    /// socket.connect("/tmp/socket")?;
+    /// socket.send_with_flags(b"hello world", libc::MSG_EOR).await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn send_with_flags(&self, buf: &[u8], flags: i32) -> io::Result<usize> {
+        let flags = SendFlags::from_bits_retain(flags as u32);
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |inner| {
+                Ok(rustix::net::send(util::borrow_fd(inner), buf, flags)?)
+            })
+        })
+        .await
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    ///
+    /// The returned `UnixDatagram` is a reference to the same socket that this object references,
+    /// by a duplicated file descriptor registered to the reactor on its own. The handles of
+    /// [`clone`] share one registration, so tasks waiting on them contend for the same
+    /// readiness and timeouts; those of `try_clone` are waited on apart, and start without
+    /// timeouts.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::unbound()?;
+    /// let socket_clone = socket.try_clone()?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self::from(util::dup::<_, StdDatagram>(&**self.0)?))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// If the timeout is [`None`], then pending reads will never time out.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every receive which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. The timeout is driven by the timer rather than `SO_RCVTIMEO`, so the
+    /// socket itself stays non-blocking. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::unbound()?;
+    /// socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+    /// assert_eq!(socket.read_timeout()?, Some(Duration::from_secs(1)));
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// If the timeout is [`None`], then pending writes will never time out.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every send which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::unbound()?;
+    /// socket.set_write_timeout(Some(Duration::from_secs(1)))?;
+    /// assert_eq!(socket.write_timeout()?, Some(Duration::from_secs(1)));
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
     /// Shut down the read, write, or both halves of this connection.
     ///
     /// This function will cause all pending and future I/O calls on the specified portions to
@@ -285,6 +578,32 @@ impl UnixDatagram {
     pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
         self.0.shutdown(how)
     }
+
+    /// Deregisters this socket from the reactor, converting it into its `std` counterpart.
+    ///
+    /// The returned socket stays in non-blocking mode, so it can be configured with the
+    /// socket options tio doesn't expose and converted back by `From`. This fails if clones
+    /// of this socket are still alive, as they share its registration.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::unbound()?;
+    /// let std_socket = socket.into_std()?;
+    /// let socket = UnixDatagram::from(std_socket);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[allow(unsafe_code)]
+    pub fn into_std(self) -> io::Result<StdDatagram> {
+        let socket = Watcher::try_unwrap(self.0)?;
+        // Safety: the mio socket gives up the descriptor it owns.
+        Ok(unsafe { StdDatagram::from_raw_fd(socket.into_raw_fd()) })
+    }
 }
 
 impl From<StdDatagram> for UnixDatagram {
@@ -300,6 +619,13 @@ impl From<StdDatagram> for UnixDatagram {
     }
 }
 
+impl AsFd for UnixDatagram {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        util::borrow_fd(&**self.0)
+    }
+}
+
 impl AsRawFd for UnixDatagram {
     /// Share raw fd of `UnixDatagram`.
     ///
@@ -319,6 +645,7 @@ mod tests {
     use std::net::Shutdown;
     use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
     use std::path::PathBuf;
+    use std::time::Duration;
     use tempfile::NamedTempFile;
 
     fn random_path() -> io::Result<PathBuf> {
@@ -395,7 +722,7 @@ mod tests {
             raw_socket.send(DATA)?;
             let size = raw_socket.recv(&mut data)?;
             assert_eq!(DATA, &data[..size]);
-            raw_socket.into_raw_fd(); // avoid fd closed when raw_socket is dropped
+            let _ = raw_socket.into_raw_fd(); // avoid fd closed when raw_socket is dropped
             Ok(())
         })
     }
@@ -446,4 +773,61 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn read_timeout() -> io::Result<()> {
+        block_on(async {
+            let (s1, _s2) = UnixDatagram::pair()?;
+            s1.set_read_timeout(Some(Duration::from_millis(100)))?;
+            let mut data = [0; 1024];
+            let err = s1.recv(&mut data).await.unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+            Ok(())
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn recv_from_full() -> io::Result<()> {
+        block_on(async {
+            let (socket, peer) = UnixDatagram::pair()?;
+            peer.send(DATA).await?;
+            peer.send(b"short").await?;
+            let mut data = [0; 16];
+            let (size, addr) = socket.recv_from_full(&mut data).await?;
+            assert_eq!(DATA.len(), size);
+            assert_eq!(&DATA[..16], &data);
+            assert!(addr.is_unnamed());
+            assert_eq!(5, socket.recv_from_full(&mut data).await?.0);
+            assert_eq!(b"short", &data[..5]);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn recv_uninit() -> io::Result<()> {
+        use std::mem::MaybeUninit;
+
+        block_on(async {
+            let (socket, peer) = UnixDatagram::pair()?;
+            peer.send(DATA).await?;
+            let mut buf = vec![MaybeUninit::uninit(); 64 * 1024];
+            assert_eq!(DATA, socket.recv_uninit(&mut buf).await?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn with_flags() -> io::Result<()> {
+        block_on(async {
+            let (socket, peer) = UnixDatagram::pair()?;
+            peer.send_with_flags(DATA, 0).await?;
+            let mut data = [0; 1024];
+            // a peek leaves the datagram queued
+            let size = socket.recv_with_flags(&mut data, libc::MSG_PEEK).await?;
+            assert_eq!(DATA, &data[..size]);
+            assert_eq!(DATA.len(), socket.recv(&mut data).await?);
+            Ok(())
+        })
+    }
 }
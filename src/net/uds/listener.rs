@@ -1,10 +1,13 @@
 use super::{SocketAddr, UnixStream};
+use crate::net::accept::Retry;
 use crate::net::poll::Watcher;
+use crate::net::util;
+use crate::net::AcceptPolicy;
 use futures::task::{Context, Poll};
 use futures::{future, Stream};
 use mio::net;
 use std::io;
-use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
 use std::os::unix::net::UnixListener as StdListener;
 use std::path::Path;
 use std::pin::Pin;
@@ -43,9 +46,23 @@ use std::sync::Arc;
 /// # Ok(()) }) }
 /// ```
 #[derive(Debug, Clone)]
-pub struct UnixListener(Arc<Watcher<net::UnixListener>>);
+pub struct UnixListener {
+    watcher: Arc<Watcher<net::UnixListener>>,
+    policy: Option<AcceptPolicy>,
+    // for polling the listener as a stream
+    retry: Retry,
+}
 
 impl UnixListener {
+    #[inline]
+    fn new(watcher: Arc<Watcher<net::UnixListener>>) -> Self {
+        Self {
+            watcher,
+            policy: None,
+            retry: Retry::default(),
+        }
+    }
+
     /// Creates a Unix datagram listener bound to the given path.
     ///
     /// # Examples
@@ -61,7 +78,7 @@ impl UnixListener {
     /// ```
     pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixListener> {
         let listener = net::UnixListener::bind(path)?;
-        Ok(UnixListener(Arc::new(Watcher::new(listener))))
+        Ok(Self::new(Arc::new(Watcher::new(listener))))
     }
 
     /// Accepts a new incoming connection to this listener.
@@ -81,11 +98,88 @@ impl UnixListener {
     /// # Ok(()) }) }
     /// ```
     pub async fn accept(&self) -> io::Result<(UnixStream, SocketAddr)> {
-        let (io, addr) =
-            future::poll_fn(|cx| self.0.poll_read_with(cx, |inner| inner.accept()))
-                .await?;
-        let stream = UnixStream(Arc::new(Watcher::new(io)));
-        Ok((stream, addr))
+        let mut retry = Retry::default();
+        future::poll_fn(|cx| {
+            retry.poll(self.policy.as_ref(), cx, |cx| self.poll_accept(cx))
+        })
+        .await
+    }
+
+    /// Polls to accept a new incoming connection to this listener.
+    ///
+    /// The current task is woken once a connection may be accepted, if there is none yet. The
+    /// errors are returned as they are, regardless of the [`AcceptPolicy`].
+    ///
+    /// [`AcceptPolicy`]: ../struct.AcceptPolicy.html
+    #[inline]
+    pub fn poll_accept(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<(UnixStream, SocketAddr)>> {
+        poll_accept(&self.watcher, cx)
+    }
+
+    /// Returns a stream of the incoming connections, borrowing this listener.
+    ///
+    /// The stream is the same as the listener itself, so it works with the combinators of
+    /// streams without taking the listener.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use futures::prelude::*;
+    /// use tio::net::UnixListener;
+    ///
+    /// let listener = UnixListener::bind("/tmp/socket")?;
+    /// listener
+    ///     .incoming()
+    ///     .try_for_each_concurrent(None, |mut stream| async move {
+    ///         stream.write_all(b"hello world").await
+    ///     })
+    ///     .await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming {
+            listener: self,
+            retry: Retry::default(),
+        }
+    }
+
+    /// Turns this listener into a stream of the incoming connections.
+    ///
+    /// Unlike [`incoming`], the stream owns the listener, so it can be moved into a task.
+    ///
+    /// [`incoming`]: #method.incoming
+    #[inline]
+    pub fn into_incoming(self) -> IntoIncoming {
+        IntoIncoming { listener: self }
+    }
+
+    /// Sets the policy for the transient errors of accepting connections, which are returned
+    /// if it is `None`, the default.
+    ///
+    /// The policy applies to [`accept`], to the streams of the connections, and to the
+    /// clones of this listener made after.
+    ///
+    /// [`accept`]: #method.accept
+    #[inline]
+    pub fn set_accept_policy(&mut self, policy: Option<AcceptPolicy>) {
+        self.policy = policy;
+    }
+
+    /// Returns the policy for the transient errors of accepting connections.
+    ///
+    /// See [`AcceptPolicy`].
+    ///
+    /// [`AcceptPolicy`]: ../struct.AcceptPolicy.html
+    #[inline]
+    pub fn accept_policy(&self) -> Option<&AcceptPolicy> {
+        self.policy.as_ref()
     }
 
     /// Returns the local socket address of this listener.
@@ -103,7 +197,84 @@ impl UnixListener {
     /// # Ok(()) }) }
     /// ```
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
-        self.0.local_addr()
+        self.watcher.local_addr()
+    }
+
+    /// Deregisters this listener from the reactor, converting it into its `std` counterpart.
+    ///
+    /// The returned listener stays in non-blocking mode, so it can be configured with the
+    /// socket options tio doesn't expose and converted back by `From`. This fails if clones
+    /// of this listener are still alive, as they share its registration.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixListener;
+    ///
+    /// let listener = UnixListener::bind("/tmp/socket")?;
+    /// let std_listener = listener.into_std()?;
+    /// let listener = UnixListener::from(std_listener);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[allow(unsafe_code)]
+    pub fn into_std(self) -> io::Result<StdListener> {
+        let listener = Watcher::try_unwrap(self.watcher)?;
+        // Safety: the mio listener gives up the descriptor it owns.
+        Ok(unsafe { StdListener::from_raw_fd(listener.into_raw_fd()) })
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    ///
+    /// The returned `UnixListener` is a reference to the same socket that this object references,
+    /// by a duplicated file descriptor registered to the reactor on its own, so tasks
+    /// accepting on the two handles do not contend for one registration as those of
+    /// [`clone`] do. The accept policy is copied.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixListener;
+    ///
+    /// let listener = UnixListener::bind("/tmp/socket")?;
+    /// let listener_clone = listener.try_clone()?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let mut listener = Self::from(util::dup::<_, StdListener>(&**self.watcher)?);
+        listener.policy = self.policy.clone();
+        Ok(listener)
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
     }
 }
 
@@ -136,16 +307,86 @@ impl Stream for UnixListener {
     /// # Ok(()) }) }
     /// ```
     fn poll_next(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        let (io, _) =
-            futures::ready!(self.0.poll_read_with(cx, |inner| inner.accept()))?;
-        let stream = UnixStream(Arc::new(Watcher::new(io)));
+        let this = &mut *self;
+        let (watcher, policy) = (&this.watcher, this.policy.as_ref());
+        let poll = this.retry.poll(policy, cx, |cx| poll_accept(watcher, cx));
+        let (stream, _) = futures::ready!(poll)?;
         Poll::Ready(Some(Ok(stream)))
     }
 }
 
+#[inline]
+fn poll_accept(
+    watcher: &Watcher<net::UnixListener>,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<(UnixStream, SocketAddr)>> {
+    let (io, addr) =
+        futures::ready!(watcher.poll_read_with(cx, |inner| inner.accept()))?;
+    let stream = UnixStream(Arc::new(Watcher::new(io)));
+    Poll::Ready(Ok((stream, addr)))
+}
+
+/// A stream of the incoming connections of a [`UnixListener`], created by
+/// [`UnixListener::incoming`].
+///
+/// [`UnixListener`]: struct.UnixListener.html
+/// [`UnixListener::incoming`]: struct.UnixListener.html#method.incoming
+#[derive(Debug)]
+pub struct Incoming<'a> {
+    listener: &'a UnixListener,
+    retry: Retry,
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<UnixStream>;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let listener = this.listener;
+        let policy = listener.accept_policy();
+        let (stream, _) =
+            futures::ready!(this.retry.poll(policy, cx, |cx| listener.poll_accept(cx)))?;
+        Poll::Ready(Some(Ok(stream)))
+    }
+}
+
+/// A stream of the incoming connections owning a [`UnixListener`], created by
+/// [`UnixListener::into_incoming`].
+///
+/// [`UnixListener`]: struct.UnixListener.html
+/// [`UnixListener::into_incoming`]: struct.UnixListener.html#method.into_incoming
+#[derive(Debug)]
+pub struct IntoIncoming {
+    listener: UnixListener,
+}
+
+impl IntoIncoming {
+    /// Returns the listener.
+    #[inline]
+    pub fn into_inner(self) -> UnixListener {
+        self.listener
+    }
+}
+
+impl Stream for IntoIncoming {
+    type Item = io::Result<UnixStream>;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.listener).poll_next(cx)
+    }
+}
+
 impl From<StdListener> for UnixListener {
     /// Converts a `std::os::unix::net::UnixListener` into its asynchronous equivalent.
     ///
@@ -155,7 +396,14 @@ impl From<StdListener> for UnixListener {
     /// non-blocking mode.
     fn from(listener: StdListener) -> UnixListener {
         let mio_listener = net::UnixListener::from_std(listener);
-        UnixListener(Arc::new(Watcher::new(mio_listener)))
+        Self::new(Arc::new(Watcher::new(mio_listener)))
+    }
+}
+
+impl AsFd for UnixListener {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        util::borrow_fd(&**self.watcher)
     }
 }
 
@@ -166,7 +414,7 @@ impl AsRawFd for UnixListener {
     ///
     /// The caller is responsible for never closing this fd.
     fn as_raw_fd(&self) -> RawFd {
-        self.0.as_raw_fd()
+        self.watcher.as_raw_fd()
     }
 }
 
@@ -249,7 +497,7 @@ mod tests {
             let path_buf = random_path()?;
             let listener = UnixListener::bind(path_buf.as_path())?;
             let raw_fd = listener.as_raw_fd();
-            spawn(async move {
+            std::thread::spawn(move || {
                 let raw_listener =
                     unsafe { std::os::unix::net::UnixListener::from_raw_fd(raw_fd) };
                 raw_listener.set_nonblocking(false).unwrap();
@@ -260,7 +508,7 @@ mod tests {
                     assert_eq!(DATA, data.as_ref());
                     stream.write_all(&data).unwrap();
                 }
-                raw_listener.into_raw_fd(); // avoid fd closed when raw_listener is dropped
+                let _ = raw_listener.into_raw_fd(); // avoid fd closed when raw_listener is dropped
             });
             connect(path_buf).await
         })
@@ -283,6 +531,23 @@ mod tests {
         })
     }
 
+    #[test]
+    fn incoming() -> io::Result<()> {
+        block_on(async {
+            let path_buf = random_path()?;
+            let listener = UnixListener::bind(path_buf.as_path())?;
+            spawn(async move {
+                let mut incoming = listener.incoming();
+                let mut data = [0; DATA.len()];
+                while let Some(Ok(mut stream)) = incoming.next().await {
+                    stream.read_exact(&mut data).await.unwrap();
+                    stream.write_all(&data).await.unwrap();
+                }
+            });
+            connect(path_buf).await
+        })
+    }
+
     #[test]
     fn local_addr() -> io::Result<()> {
         let path_buf = random_path()?;
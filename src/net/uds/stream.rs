@@ -1,15 +1,17 @@
 use super::SocketAddr;
 use crate::net::poll::Watcher;
+use crate::net::util;
 use futures::task::{Context, Poll};
 use futures::{AsyncRead, AsyncWrite};
 use mio::net;
 use std::io::{self, IoSlice, IoSliceMut, Read, Write};
 use std::net::Shutdown;
-use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
 use std::os::unix::net::UnixStream as StdStream;
 use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// A Unix stream socket.
 ///
@@ -119,6 +121,147 @@ impl UnixStream {
         self.0.peer_addr()
     }
 
+    /// Creates a new independently owned handle to the underlying socket.
+    ///
+    /// The returned `UnixStream` is a reference to the same socket that this object references,
+    /// by a duplicated file descriptor registered to the reactor on its own. The handles of
+    /// [`clone`] share one registration, so tasks waiting on them contend for the same
+    /// readiness and timeouts; those of `try_clone` are waited on apart, and start without
+    /// timeouts.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixStream;
+    ///
+    /// let stream = UnixStream::connect("/tmp/socket").await?;
+    /// let stream_clone = stream.try_clone()?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self::from(util::dup::<_, StdStream>(&**self.0)?))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// If the timeout is [`None`], then pending reads will never time out.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every read which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. The timeout is driven by the timer rather than `SO_RCVTIMEO`, so the
+    /// socket itself stays non-blocking. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::UnixStream;
+    ///
+    /// let stream = UnixStream::connect("/tmp/socket").await?;
+    /// stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    /// assert_eq!(stream.read_timeout()?, Some(Duration::from_secs(1)));
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// If the timeout is [`None`], then pending writes will never time out.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every write which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::UnixStream;
+    ///
+    /// let stream = UnixStream::connect("/tmp/socket").await?;
+    /// stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+    /// assert_eq!(stream.write_timeout()?, Some(Duration::from_secs(1)));
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
     /// Shuts down the read, write, or both halves of this connection.
     ///
     /// This function will cause all pending and future I/O calls on the specified portions to
@@ -140,6 +283,32 @@ impl UnixStream {
     pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
         self.0.shutdown(how)
     }
+
+    /// Deregisters this stream from the reactor, converting it into its `std` counterpart.
+    ///
+    /// The returned stream stays in non-blocking mode, so it can be configured with the
+    /// socket options tio doesn't expose and converted back by `From`. This fails if clones
+    /// of this stream are still alive, as they share its registration.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::net::UnixStream;
+    ///
+    /// let stream = UnixStream::connect("/tmp/socket").await?;
+    /// let std_stream = stream.into_std()?;
+    /// let stream = UnixStream::from(std_stream);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[allow(unsafe_code)]
+    pub fn into_std(self) -> io::Result<StdStream> {
+        let stream = Watcher::try_unwrap(self.0)?;
+        // Safety: the mio stream gives up the descriptor it owns.
+        Ok(unsafe { StdStream::from_raw_fd(stream.into_raw_fd()) })
+    }
 }
 
 impl From<StdStream> for UnixStream {
@@ -206,6 +375,13 @@ impl AsyncWrite for UnixStream {
     }
 }
 
+impl AsFd for UnixStream {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        util::borrow_fd(&**self.0)
+    }
+}
+
 impl AsRawFd for UnixStream {
     /// Share raw fd of `UnixStream`.
     ///
@@ -308,7 +484,7 @@ mod tests {
             raw_stream.read_to_end(&mut data)?;
 
             assert_eq!(DATA, data.as_slice());
-            raw_stream.into_raw_fd(); // avoid fd closed when raw_stream is dropped
+            let _ = raw_stream.into_raw_fd(); // avoid fd closed when raw_stream is dropped
             Ok(())
         })
     }
@@ -331,7 +507,7 @@ mod tests {
         block_on(async {
             let path_buf = random_path()?;
             let path = path_buf.as_path();
-            let _listener = std::os::unix::net::UnixListener::bind(path.to_path_buf())?;
+            let _listener = std::os::unix::net::UnixListener::bind(path)?;
             let mut stream = UnixStream::connect(path).await?;
             stream.shutdown(Shutdown::Write)?;
             assert!(stream.write_all(DATA).await.is_err());
@@ -357,4 +533,16 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn read_timeout() -> io::Result<()> {
+        block_on(async {
+            let (mut s1, _s2) = UnixStream::pair()?;
+            s1.set_read_timeout(Some(Duration::from_millis(100)))?;
+            let mut data = [0; DATA.len()];
+            let err = s1.read_exact(&mut data).await.unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+            Ok(())
+        })
+    }
 }
@@ -1,6 +1,7 @@
 #![allow(dead_code, unused_imports)]
 
-use crate::task::{spawn_blocking, JoinHandle};
+use crate::task::{spawn_blocking, JoinError, JoinHandle};
+use futures::future::{FutureExt, Map};
 use std::future::Future;
 use std::io::{self, ErrorKind::*, Result};
 use std::net::{SocketAddr, ToSocketAddrs};
@@ -9,6 +10,12 @@ use std::task::Poll;
 /// An exception
 pub const ENTRIES_LOCK_POISONED: &str = "entries lock poisoned";
 
+/// An exception
+pub const TIMEOUT_LOCK_POISONED: &str = "timeout lock poisoned";
+
+/// An exception
+pub const ZERO_COPY_LOCK_POISONED: &str = "zero-copy lock poisoned";
+
 /// Async address resolver
 pub trait Resolver: ToSocketAddrs {
     /// Future to resolve address
@@ -18,6 +25,9 @@ pub trait Resolver: ToSocketAddrs {
     /// # Example
     ///
     /// ```
+    /// # #[cfg(not(feature = "tcp"))]
+    /// # fn main() {}
+    /// # #[cfg(feature = "tcp")]
     /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
     /// #
     /// use tio::net::{TcpStream, Resolver};
@@ -35,13 +45,23 @@ where
     A: 'static + Send + ToSocketAddrs,
     A::Iter: 'static + Send,
 {
-    type ResolveFuture = JoinHandle<io::Result<Vec<SocketAddr>>>;
+    type ResolveFuture =
+        Map<JoinHandle<io::Result<Vec<SocketAddr>>>, Flatten<Vec<SocketAddr>>>;
     #[inline]
     fn resolve(self) -> Self::ResolveFuture {
         spawn_blocking(move || self.to_socket_addrs().map(Iterator::collect))
+            .map(flatten as Flatten<_>)
     }
 }
 
+type Flatten<T> = fn(std::result::Result<io::Result<T>, JoinError>) -> io::Result<T>;
+
+/// Merge the error of a blocking task into its io result
+#[inline]
+fn flatten<T>(result: std::result::Result<io::Result<T>, JoinError>) -> io::Result<T> {
+    result?
+}
+
 /// Convert io result to poll
 #[inline]
 pub fn may_block<T>(result: Result<T>) -> Poll<Result<T>> {
@@ -59,3 +79,76 @@ pub fn resolve_none() -> io::Error {
         "could not resolve to any valid addresses",
     )
 }
+
+/// Construct timed out io error
+#[inline]
+pub fn timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "operation timed out")
+}
+
+/// Duplicates the socket of a mio type as a std one, which can be registered to the reactor
+/// apart from the original.
+#[cfg(unix)]
+#[allow(unsafe_code)]
+#[inline]
+pub(crate) fn dup<T, S>(io: &T) -> io::Result<S>
+where
+    T: std::os::unix::io::AsRawFd,
+    S: From<std::os::unix::io::OwnedFd>,
+{
+    Ok(S::from(borrow_fd(io).try_clone_to_owned()?))
+}
+
+/// Borrows the socket of a mio type, which implements no `AsFd`.
+#[cfg(unix)]
+#[allow(unsafe_code)]
+#[inline]
+pub(crate) fn borrow_fd<T>(io: &T) -> std::os::unix::io::BorrowedFd<'_>
+where
+    T: std::os::unix::io::AsRawFd,
+{
+    // SAFETY: the fd is owned by `io`, which outlives the borrow.
+    unsafe { std::os::unix::io::BorrowedFd::borrow_raw(io.as_raw_fd()) }
+}
+
+/// Polls a receive into an uninitialized buffer until it completes, returning the prefix of
+/// the buffer it initialized along with the rest of its output.
+///
+/// `recv` returns the length of the slice rustix tells initialized, first.
+#[cfg(unix)]
+#[allow(unsafe_code)]
+pub(crate) async fn recv_uninit<T, F>(
+    buf: &mut [std::mem::MaybeUninit<u8>],
+    mut recv: F,
+) -> io::Result<(&mut [u8], T)>
+where
+    F: FnMut(
+        &mut std::task::Context<'_>,
+        &mut [std::mem::MaybeUninit<u8>],
+    ) -> Poll<io::Result<(usize, T)>>,
+{
+    let (len, output) = futures::future::poll_fn(|cx| recv(cx, &mut *buf)).await?;
+    let init = &mut buf[..len];
+    // SAFETY: the receive initialized the first `len` bytes, and a `MaybeUninit<u8>` has the
+    // layout of an `u8`.
+    let init = unsafe { &mut *(init as *mut [std::mem::MaybeUninit<u8>] as *mut [u8]) };
+    Ok((init, output))
+}
+
+/// Returns whether `fd` is closed on exec.
+#[cfg(all(unix, feature = "rustix"))]
+#[inline]
+pub(crate) fn close_on_exec(fd: std::os::unix::io::BorrowedFd<'_>) -> io::Result<bool> {
+    Ok(rustix::io::fcntl_getfd(fd)?.contains(rustix::io::FdFlags::CLOEXEC))
+}
+
+/// Sets whether `fd` is closed on exec, keeping its other flags.
+#[cfg(all(unix, feature = "rustix"))]
+pub(crate) fn set_close_on_exec(
+    fd: std::os::unix::io::BorrowedFd<'_>,
+    on: bool,
+) -> io::Result<()> {
+    let mut flags = rustix::io::fcntl_getfd(fd)?;
+    flags.set(rustix::io::FdFlags::CLOEXEC, on);
+    Ok(rustix::io::fcntl_setfd(fd, flags)?)
+}
@@ -0,0 +1,56 @@
+//! VM sockets for the communication between a host and its guests.
+//!
+//! VM sockets (`AF_VSOCK`) connect the processes of a virtual machine to the processes of its
+//! host, without a network between them: an agent in a guest serves the host on a port, the
+//! same as on TCP, while each machine is addressed by a context identifier rather than an IP
+//! address. See [`VsockAddr`] for the addresses.
+//!
+//! [`VsockAddr`]: struct.VsockAddr.html
+//!
+//! # Examples
+//!
+//! An echo server in a guest, which the host dials by the CID of the guest:
+//!
+//! ```no_run
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use futures::io;
+//! use futures::prelude::*;
+//! use tio::net::vsock::{VsockAddr, VsockListener};
+//!
+//! let mut listener = VsockListener::bind(VsockAddr::new(VsockAddr::CID_ANY, 1024))?;
+//!
+//! while let Some(stream) = listener.next().await {
+//!     let stream = stream?;
+//!     let (reader, writer) = &mut (stream.clone(), stream);
+//!     io::copy(reader, writer).await?;
+//! }
+//! #
+//! # Ok(()) }) }
+//! ```
+
+mod addr;
+mod datagram;
+mod listener;
+mod stream;
+
+pub use addr::VsockAddr;
+pub use datagram::VsockDatagram;
+pub use listener::VsockListener;
+pub use stream::VsockStream;
+
+use rustix::net::{AddressFamily, SocketFlags, SocketType};
+use std::io;
+use std::os::unix::io::OwnedFd;
+
+/// Creates a non-blocking VM socket of the given type.
+#[inline]
+fn socket(ty: SocketType) -> io::Result<OwnedFd> {
+    let flags = SocketFlags::NONBLOCK | SocketFlags::CLOEXEC;
+    Ok(rustix::net::socket_with(
+        AddressFamily::VSOCK,
+        ty,
+        flags,
+        None,
+    )?)
+}
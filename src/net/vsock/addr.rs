@@ -0,0 +1,126 @@
+use rustix::net::addr::{SocketAddrArg, SocketAddrLen, SocketAddrOpaque};
+use rustix::net::{AddressFamily, SocketAddrAny};
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::mem::size_of;
+
+/// The address of a VM socket, as a context identifier and a port.
+///
+/// The context identifier (CID) names a machine: the host, or one of its guests. Each guest
+/// is given its own CID by the hypervisor.
+///
+/// # Examples
+///
+/// ```
+/// use tio::net::vsock::VsockAddr;
+///
+/// let addr = VsockAddr::new(VsockAddr::CID_HOST, 1024);
+/// assert_eq!(2, addr.cid());
+/// assert_eq!(1024, addr.port());
+/// assert_eq!("2:1024", addr.to_string());
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct VsockAddr {
+    cid: u32,
+    port: u32,
+}
+
+impl VsockAddr {
+    /// Any context identifier, to bind a socket to all of them.
+    pub const CID_ANY: u32 = libc::VMADDR_CID_ANY;
+
+    /// The context identifier of the hypervisor.
+    pub const CID_HYPERVISOR: u32 = libc::VMADDR_CID_HYPERVISOR;
+
+    /// The context identifier of the local machine, for the loopback transport.
+    pub const CID_LOCAL: u32 = libc::VMADDR_CID_LOCAL;
+
+    /// The context identifier of the host, to be dialed from the guests.
+    pub const CID_HOST: u32 = libc::VMADDR_CID_HOST;
+
+    /// Any port, to bind a socket to a free port chosen by the kernel.
+    pub const PORT_ANY: u32 = libc::VMADDR_PORT_ANY;
+
+    /// Creates an address from a context identifier and a port.
+    #[inline]
+    pub const fn new(cid: u32, port: u32) -> Self {
+        Self { cid, port }
+    }
+
+    /// Returns the context identifier of this address.
+    #[inline]
+    pub const fn cid(&self) -> u32 {
+        self.cid
+    }
+
+    /// Returns the port of this address.
+    #[inline]
+    pub const fn port(&self) -> u32 {
+        self.port
+    }
+
+    #[inline]
+    fn to_raw(self) -> libc::sockaddr_vm {
+        libc::sockaddr_vm {
+            svm_family: libc::AF_VSOCK as libc::sa_family_t,
+            svm_reserved1: 0,
+            svm_port: self.port,
+            svm_cid: self.cid,
+            svm_zero: [0; 4],
+        }
+    }
+
+    /// Decodes an address returned by the kernel.
+    #[allow(unsafe_code)]
+    pub(super) fn from_any(addr: &SocketAddrAny) -> io::Result<Self> {
+        if addr.address_family() != AddressFamily::VSOCK
+            || (addr.addr_len() as usize) < size_of::<libc::sockaddr_vm>()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the address is not of a VM socket",
+            ));
+        }
+        // SAFETY: the storage holds a `sockaddr_vm`, as checked above.
+        let raw = unsafe { addr.as_ptr().cast::<libc::sockaddr_vm>().read_unaligned() };
+        Ok(Self::new(raw.svm_cid, raw.svm_port))
+    }
+}
+
+#[allow(unsafe_code)]
+// SAFETY: the pointer is to a `sockaddr_vm` alive during the call, of the length passed.
+unsafe impl SocketAddrArg for VsockAddr {
+    unsafe fn with_sockaddr<R>(
+        &self,
+        f: impl FnOnce(*const SocketAddrOpaque, SocketAddrLen) -> R,
+    ) -> R {
+        let raw = self.to_raw();
+        f(
+            (&raw as *const libc::sockaddr_vm).cast(),
+            size_of::<libc::sockaddr_vm>() as SocketAddrLen,
+        )
+    }
+}
+
+impl Display for VsockAddr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.cid, self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VsockAddr;
+    use rustix::net::addr::SocketAddrArg;
+
+    #[test]
+    fn roundtrip() {
+        let addr = VsockAddr::new(VsockAddr::CID_LOCAL, 4096);
+        assert_eq!(addr, VsockAddr::from_any(&addr.as_any()).unwrap());
+
+        let inet = rustix::net::SocketAddrAny::from(
+            "127.0.0.1:80".parse::<std::net::SocketAddr>().unwrap(),
+        );
+        assert!(VsockAddr::from_any(&inet).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use super::VsockAddr;
+use crate::net::poll::{Fd, Watcher};
+use crate::net::util;
+use futures::future;
+use rustix::net::{RecvFlags, SendFlags, SocketType};
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::Arc;
+
+/// A VM datagram socket.
+///
+/// After creating a `VsockDatagram` by [`bind`]ing it to an address, data can be [sent to]
+/// and [received from] any other VM socket address.
+///
+/// Only some transports support datagrams, like the VMCI one of VMware; the others fail to
+/// bind with `ENODEV`.
+///
+/// [`bind`]: #method.bind
+/// [received from]: #method.recv_from
+/// [sent to]: #method.send_to
+///
+/// ## Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use tio::net::vsock::{VsockAddr, VsockDatagram};
+///
+/// let socket = VsockDatagram::bind(VsockAddr::new(VsockAddr::CID_ANY, 1024))?;
+/// socket.send_to(b"hello world", VsockAddr::new(VsockAddr::CID_HOST, 1024)).await?;
+///
+/// let mut buf = vec![0u8; 1024];
+/// let (n, peer) = socket.recv_from(&mut buf).await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VsockDatagram(Arc<Watcher<Fd>>);
+
+impl VsockDatagram {
+    /// Creates a VM datagram socket bound to the given address.
+    ///
+    /// It must be called within a runtime.
+    pub fn bind(addr: VsockAddr) -> io::Result<Self> {
+        let fd = super::socket(SocketType::DGRAM)?;
+        rustix::net::bind(&fd, &addr)?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Connects the socket to the specified address.
+    ///
+    /// The [`send`] method may be used to send data to the specified address. [`recv`] will
+    /// only receive data from that address.
+    ///
+    /// [`send`]: #method.send
+    /// [`recv`]: #method.recv
+    #[inline]
+    pub fn connect(&self, addr: VsockAddr) -> io::Result<()> {
+        Ok(rustix::net::connect(self, &addr)?)
+    }
+
+    /// Returns the address of this socket.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        VsockAddr::from_any(&rustix::net::getsockname(self)?)
+    }
+
+    /// Returns the address of the connected peer, if the socket is connected.
+    #[inline]
+    pub fn peer_addr(&self) -> io::Result<VsockAddr> {
+        match rustix::net::getpeername(self)? {
+            Some(addr) => VsockAddr::from_any(&addr),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks waiting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Sends data on the socket to the given address.
+    ///
+    /// On success, returns the number of bytes written.
+    pub async fn send_to(&self, buf: &[u8], addr: VsockAddr) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |fd| {
+                Ok(rustix::net::sendto(fd, buf, SendFlags::empty(), &addr)?)
+            })
+        })
+        .await
+    }
+
+    /// Receives data from the socket.
+    ///
+    /// On success, returns the number of bytes read and the address from whence the data
+    /// came.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, VsockAddr)> {
+        let (n, addr) = future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |fd| {
+                let (n, _, addr) =
+                    rustix::net::recvfrom(fd, &mut *buf, RecvFlags::empty())?;
+                Ok((n, addr))
+            })
+        })
+        .await?;
+        match addr {
+            Some(addr) => Ok((n, VsockAddr::from_any(&addr)?)),
+            None => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    /// Sends data on the socket to the socket's peer.
+    ///
+    /// The peer address may be set by the [`connect`] method, and this method will return an
+    /// error if the socket has not already been connected.
+    ///
+    /// On success, returns the number of bytes written.
+    ///
+    /// [`connect`]: #method.connect
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_write_with(cx, |fd| {
+                Ok(rustix::net::send(fd, buf, SendFlags::empty())?)
+            })
+        })
+        .await
+    }
+
+    /// Receives data from the socket's peer.
+    ///
+    /// The peer address may be set by the [`connect`] method, and this method will return an
+    /// error if the socket has not already been connected.
+    ///
+    /// On success, returns the number of bytes read.
+    ///
+    /// [`connect`]: #method.connect
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        future::poll_fn(|cx| {
+            self.0.poll_read_with(cx, |fd| {
+                let (n, _) = rustix::net::recv(fd, &mut *buf, RecvFlags::empty())?;
+                Ok(n)
+            })
+        })
+        .await
+    }
+}
+
+impl AsFd for VsockDatagram {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for VsockDatagram {
+    /// Share raw fd of `VsockDatagram`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
@@ -0,0 +1,199 @@
+use super::{VsockAddr, VsockStream};
+use crate::net::poll::{Fd, Watcher};
+use crate::net::util;
+use futures::task::{Context, Poll};
+use futures::{future, Stream};
+use rustix::net::{SocketFlags, SocketType};
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// The length of the queue of pending connections, the same as mio's.
+const BACKLOG: i32 = 1024;
+
+/// A VM socket server, listening for connections.
+///
+/// After creating a `VsockListener` by [`bind`]ing it to an address, it listens for incoming
+/// connections. These can be accepted by [`accept`], or by awaiting elements from the
+/// listener as a stream.
+///
+/// The socket will be closed when the value is dropped.
+///
+/// [`bind`]: #method.bind
+/// [`accept`]: #method.accept
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::prelude::*;
+/// use tio::net::vsock::{VsockAddr, VsockListener};
+///
+/// let mut listener = VsockListener::bind(VsockAddr::new(VsockAddr::CID_ANY, 1024))?;
+///
+/// while let Some(stream) = listener.next().await {
+///     let mut stream = stream?;
+///     stream.write_all(b"hello world").await?;
+/// }
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VsockListener(Arc<Watcher<Fd>>);
+
+impl VsockListener {
+    /// Creates a new `VsockListener` bound to the given address.
+    ///
+    /// Binding with a port of [`VsockAddr::PORT_ANY`] picks a free port, which can be
+    /// queried by [`local_addr`].
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`VsockAddr::PORT_ANY`]: struct.VsockAddr.html#associatedconstant.PORT_ANY
+    /// [`local_addr`]: #method.local_addr
+    pub fn bind(addr: VsockAddr) -> io::Result<Self> {
+        let fd = super::socket(SocketType::STREAM)?;
+        rustix::net::bind(&fd, &addr)?;
+        rustix::net::listen(&fd, BACKLOG)?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Accepts a new incoming connection to this listener.
+    ///
+    /// When a connection is established, the corresponding stream and address will be
+    /// returned.
+    pub async fn accept(&self) -> io::Result<(VsockStream, VsockAddr)> {
+        future::poll_fn(|cx| self.poll_accept(cx)).await
+    }
+
+    /// Polls to accept a new incoming connection to this listener.
+    ///
+    /// The current task is woken once a connection may be accepted, if there is none yet.
+    pub fn poll_accept(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<(VsockStream, VsockAddr)>> {
+        let flags = SocketFlags::NONBLOCK | SocketFlags::CLOEXEC;
+        let (fd, addr) = futures::ready!(self
+            .0
+            .poll_read_with(cx, |fd| Ok(rustix::net::acceptfrom_with(fd, flags)?)))?;
+        let addr = match addr {
+            Some(addr) => VsockAddr::from_any(&addr)?,
+            None => return Poll::Ready(Err(io::ErrorKind::InvalidData.into())),
+        };
+        Poll::Ready(Ok((VsockStream::new(fd), addr)))
+    }
+
+    /// Returns the local address that this listener is bound to.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        VsockAddr::from_any(&rustix::net::getsockname(self)?)
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks accepting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+}
+
+impl Stream for VsockListener {
+    type Item = io::Result<VsockStream>;
+
+    /// Returns a stream of incoming connections.
+    ///
+    /// Iterating over this stream is equivalent to calling [`accept`] in a loop. The stream
+    /// of connections is infinite, i.e awaiting the next connection will never result in
+    /// [`None`].
+    ///
+    /// [`accept`]: #method.accept
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    #[inline]
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let (stream, _) = futures::ready!(self.poll_accept(cx))?;
+        Poll::Ready(Some(Ok(stream)))
+    }
+}
+
+impl AsFd for VsockListener {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for VsockListener {
+    /// Share raw fd of `VsockListener`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VsockListener;
+    use crate::net::vsock::VsockAddr;
+    use crate::task::block_on;
+    use std::io;
+
+    #[test]
+    fn bind() -> io::Result<()> {
+        block_on(async {
+            let addr = VsockAddr::new(VsockAddr::CID_ANY, VsockAddr::PORT_ANY);
+            let listener = match VsockListener::bind(addr) {
+                // the kernel has no transport of VM sockets
+                Err(err) if err.raw_os_error() == Some(libc::EAFNOSUPPORT) => {
+                    return Ok(())
+                }
+                ret => ret?,
+            };
+            let local = listener.local_addr()?;
+            assert_eq!(VsockAddr::CID_ANY, local.cid());
+            assert_ne!(VsockAddr::PORT_ANY, local.port());
+
+            // the port is taken
+            let err = VsockListener::bind(local).unwrap_err();
+            assert_eq!(io::ErrorKind::AddrInUse, err.kind());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,247 @@
+use super::VsockAddr;
+use crate::net::poll::{Fd, Watcher};
+use crate::net::util;
+use futures::task::{Context, Poll};
+use futures::{AsyncRead, AsyncWrite};
+use rustix::io::Errno;
+use rustix::net::{RecvFlags, SendFlags, SocketType};
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A VM socket stream between a local and a remote socket.
+///
+/// A `VsockStream` can either be created by connecting to an endpoint, via the [`connect`]
+/// method, or by [accepting] a connection from a [listener]. It can be read or written to
+/// using the [`AsyncRead`], [`AsyncWrite`], and related extension traits in [`futures::io`].
+///
+/// [`connect`]: #method.connect
+/// [accepting]: struct.VsockListener.html#method.accept
+/// [listener]: struct.VsockListener.html
+/// [`AsyncRead`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncRead.html
+/// [`AsyncWrite`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncWrite.html
+/// [`futures::io`]: https://docs.rs/futures/0.3/futures/io/index.html
+///
+/// ## Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use futures::prelude::*;
+/// use tio::net::vsock::{VsockAddr, VsockStream};
+///
+/// let mut stream = VsockStream::connect(VsockAddr::new(VsockAddr::CID_HOST, 1024)).await?;
+/// stream.write_all(b"hello world").await?;
+///
+/// let mut buf = vec![0u8; 1024];
+/// let n = stream.read(&mut buf).await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VsockStream(Arc<Watcher<Fd>>);
+
+impl VsockStream {
+    #[inline]
+    pub(super) fn new(fd: OwnedFd) -> Self {
+        Self(Arc::new(Watcher::new(Fd(fd))))
+    }
+
+    /// Connects to the VM socket at the given address.
+    ///
+    /// The returned future is resolved once the stream has connected, or fails if the peer
+    /// refuses or does not answer in time.
+    pub async fn connect(addr: VsockAddr) -> io::Result<Self> {
+        let fd = super::socket(SocketType::STREAM)?;
+        match rustix::net::connect(&fd, &addr) {
+            Ok(()) | Err(Errno::INPROGRESS) => (),
+            Err(err) => return Err(err.into()),
+        }
+        let stream = Self::new(fd);
+        // wait for connection established
+        stream.0.write_ready().await;
+        match rustix::net::sockopt::socket_error(&stream)? {
+            Ok(()) => Ok(stream),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the local address that this stream is connected to.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        VsockAddr::from_any(&rustix::net::getsockname(self)?)
+    }
+
+    /// Returns the remote address that this stream is connected to.
+    #[inline]
+    pub fn peer_addr(&self) -> io::Result<VsockAddr> {
+        match rustix::net::getpeername(self)? {
+            Some(addr) => VsockAddr::from_any(&addr),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+
+    /// Creates a new independently owned handle to this socket.
+    ///
+    /// The handle has a duplicated file descriptor, registered to the reactor apart from this
+    /// one, so tasks waiting on the two do not contend for one registration, as those on the
+    /// handles of [`clone`] do. Its timeouts start unset.
+    ///
+    /// It must be called within a runtime.
+    ///
+    /// [`clone`]: #method.clone
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = self.as_fd().try_clone_to_owned()?;
+        Ok(Self(Arc::new(Watcher::new(Fd(fd)))))
+    }
+
+    /// Returns whether this socket is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this socket is closed on exec, i.e. when a child process executes a program.
+    ///
+    /// The socket is opened closed on exec, so no child inherits it by accident. Once this is
+    /// cleared, every child spawned afterwards inherits the socket, including those spawned by
+    /// other threads in the meantime; [`Command::pass_fd`] hands it to one child only.
+    ///
+    /// [`Command::pass_fd`]: ../../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        util::set_close_on_exec(self.as_fd(), on)
+    }
+
+    /// Returns the read timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_read_timeout`].
+    ///
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    #[inline]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout of this socket.
+    ///
+    /// Every read which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    ///
+    /// For more information about this option, see [`set_write_timeout`].
+    ///
+    /// [`set_write_timeout`]: #method.set_write_timeout
+    #[inline]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout of this socket.
+    ///
+    /// Every write which stays pending for longer than `dur` fails with an error of kind
+    /// [`TimedOut`]. Passing [`None`] disables the timeout.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    #[inline]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// This method will cause all pending and future I/O on the specified portions to
+    /// return immediately with an appropriate value (see the documentation of
+    /// [`Shutdown`]).
+    ///
+    /// [`Shutdown`]: https://doc.rust-lang.org/std/net/enum.Shutdown.html
+    #[inline]
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        let how = match how {
+            Shutdown::Read => rustix::net::Shutdown::Read,
+            Shutdown::Write => rustix::net::Shutdown::Write,
+            Shutdown::Both => rustix::net::Shutdown::Both,
+        };
+        Ok(rustix::net::shutdown(self, how)?)
+    }
+}
+
+impl AsyncRead for VsockStream {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_read_with(cx, |fd| {
+            let (n, _) = rustix::net::recv(fd, &mut *buf, RecvFlags::empty())?;
+            Ok(n)
+        })
+    }
+}
+
+impl AsyncWrite for VsockStream {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_write_with(cx, |fd| {
+            Ok(rustix::net::send(fd, buf, SendFlags::NOSIGNAL)?)
+        })
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.shutdown(Shutdown::Both)?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsFd for VsockStream {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for VsockStream {
+    /// Share raw fd of `VsockStream`.
+    ///
+    /// # Notes
+    ///
+    /// The caller is responsible for never closing this fd.
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
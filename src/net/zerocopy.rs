@@ -0,0 +1,278 @@
+use crate::net::poll::Watcher;
+use crate::net::sys;
+use crate::net::util::{self, ZERO_COPY_LOCK_POISONED};
+use futures::future::{self, Future};
+use futures::task::{Context, Poll};
+use mio::event;
+use rustix::net::{AddressFamily, SendFlags};
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::os::unix::io::{AsRawFd, BorrowedFd};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// The `SO_ZEROCOPY` option of the generic ABI, which libc does not define.
+const SO_ZEROCOPY: libc::c_int = 60;
+
+/// The origin of the notifications of zero-copy sends, which libc does not define.
+const SO_EE_ORIGIN_ZEROCOPY: u8 = 5;
+
+/// The zero-copy sends of one socket, shared by its clones.
+///
+/// The kernel numbers every zero-copy send of the socket in turn, and notifies the ranges of
+/// the numbers it is done with on the error queue.
+#[derive(Default)]
+pub(crate) struct Tracker(Mutex<State>);
+
+#[derive(Default)]
+struct State {
+    // the level of the notifications, once `SO_ZEROCOPY` is set
+    level: Option<(libc::c_int, libc::c_int)>,
+    // the number of the next send
+    next: u32,
+    // the numbers notified but not yet awaited
+    completed: HashSet<u32>,
+    // the buffers of the sends whose futures are dropped before the notifications
+    abandoned: Vec<(Vec<u32>, Box<dyn Send>)>,
+}
+
+impl Tracker {
+    /// Sends `buf` without copying it, returning the number of bytes sent and the number of
+    /// the send, if it is a zero-copy one.
+    fn send(&self, fd: BorrowedFd<'_>, buf: &[u8]) -> io::Result<(usize, Option<u32>)> {
+        // the kernel numbers no empty send
+        if buf.is_empty() {
+            return Ok((rustix::net::send(fd, buf, SendFlags::NOSIGNAL)?, None));
+        }
+        let mut state = self.0.lock().expect(ZERO_COPY_LOCK_POISONED);
+        if state.level.is_none() {
+            sys::setsockopt(fd, libc::SOL_SOCKET, SO_ZEROCOPY, &1 as &libc::c_int)?;
+            state.level = Some(match rustix::net::sockopt::socket_domain(fd)? {
+                AddressFamily::INET6 => (libc::IPPROTO_IPV6, libc::IPV6_RECVERR),
+                _ => (libc::IPPROTO_IP, libc::IP_RECVERR),
+            });
+        }
+        let flags =
+            SendFlags::NOSIGNAL | SendFlags::from_bits_retain(libc::MSG_ZEROCOPY as u32);
+        let n = rustix::net::send(fd, buf, flags)?;
+        let id = state.next;
+        state.next = id.wrapping_add(1);
+        Ok((n, Some(id)))
+    }
+
+    /// Reads the notifications on the error queue of `fd`, and takes the sends of `ids` if
+    /// they are all notified.
+    fn complete(&self, fd: BorrowedFd<'_>, ids: &[u32]) -> io::Result<()> {
+        let mut state = self.0.lock().expect(ZERO_COPY_LOCK_POISONED);
+        let (level, ty) = match state.level {
+            Some(level) => level,
+            None => return Ok(()),
+        };
+        let mut notified = false;
+        loop {
+            let msg = match sys::recvmsg::<libc::sock_extended_err>(
+                fd,
+                &mut [],
+                level,
+                ty,
+                libc::MSG_ERRQUEUE,
+            ) {
+                Ok(msg) => msg,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            };
+            if let Some(err) = msg
+                .cmsg
+                .filter(|err| err.ee_origin == SO_EE_ORIGIN_ZEROCOPY)
+            {
+                // the range from `ee_info` to `ee_data` inclusive
+                let count = err.ee_data.wrapping_sub(err.ee_info);
+                for i in 0..=count {
+                    state.completed.insert(err.ee_info.wrapping_add(i));
+                }
+                notified = true;
+            }
+        }
+        if notified {
+            let State {
+                completed,
+                abandoned,
+                ..
+            } = &mut *state;
+            abandoned.retain(|(ids, _)| !take(completed, ids));
+        }
+        if take(&mut state.completed, ids) {
+            Ok(())
+        } else {
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+
+    /// Keeps `buf` until the sends of `ids` are notified.
+    fn abandon(&self, ids: &[u32], buf: Box<dyn Send>) {
+        let mut state = self.0.lock().expect(ZERO_COPY_LOCK_POISONED);
+        if !take(&mut state.completed, ids) {
+            state.abandoned.push((ids.to_vec(), buf));
+        }
+    }
+}
+
+/// Removes `ids` from `completed` if they are all in it.
+fn take(completed: &mut HashSet<u32>, ids: &[u32]) -> bool {
+    if !ids.iter().all(|id| completed.contains(id)) {
+        return false;
+    }
+    for id in ids {
+        completed.remove(id);
+    }
+    true
+}
+
+impl Debug for Tracker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.0.lock().expect(ZERO_COPY_LOCK_POISONED);
+        f.debug_struct("Tracker")
+            .field("next", &state.next)
+            .field("abandoned", &state.abandoned.len())
+            .finish()
+    }
+}
+
+/// A socket of zero-copy sends, whose error queue is read once it is writable.
+trait ErrorQueue: Send + Sync {
+    fn poll_complete(
+        &self,
+        cx: &mut Context<'_>,
+        tracker: &Tracker,
+        ids: &[u32],
+    ) -> Poll<io::Result<()>>;
+}
+
+impl<S> ErrorQueue for Watcher<S>
+where
+    S: event::Source + AsRawFd + Send + Sync,
+{
+    #[inline]
+    fn poll_complete(
+        &self,
+        cx: &mut Context<'_>,
+        tracker: &Tracker,
+        ids: &[u32],
+    ) -> Poll<io::Result<()>> {
+        // the notifications are reported as errors, which wake the writers
+        self.poll_write_with(cx, |source| tracker.complete(util::borrow_fd(source), ids))
+    }
+}
+
+/// Sends `buf` on `watcher` without copying it, all of it if `all` is set, or once.
+pub(crate) async fn send<S, B>(
+    watcher: &Arc<Watcher<S>>,
+    tracker: &Arc<Tracker>,
+    buf: B,
+    all: bool,
+) -> io::Result<ZeroCopySend<B>>
+where
+    S: event::Source + AsRawFd + Send + Sync + 'static,
+    B: AsRef<[u8]> + Send + 'static,
+{
+    // the kernel reads the bytes in place, so they must not move with the buffer, which is
+    // kept until the kernel is done with them even if this is cancelled
+    let mut send = ZeroCopySend {
+        socket: watcher.clone(),
+        tracker: tracker.clone(),
+        buf: Some(Box::new(buf)),
+        ids: Vec::new(),
+        sent: 0,
+    };
+    loop {
+        let (n, id) = {
+            let bytes = send.bytes();
+            let rest = &bytes[send.sent..];
+            future::poll_fn(|cx| {
+                watcher.poll_write_with(cx, |source| {
+                    tracker.send(util::borrow_fd(source), rest)
+                })
+            })
+            .await?
+        };
+        send.sent += n;
+        send.ids.extend(id);
+        if !all || n == 0 || send.sent == send.bytes().len() {
+            return Ok(send);
+        }
+    }
+}
+
+/// A future which resolves to the buffer of a zero-copy send, once the kernel is done with it.
+///
+/// The kernel sends the bytes of the buffer in place, so it holds the buffer until every byte
+/// is acknowledged by the peer, or copied on a route which cannot send in place, like the
+/// loopback. The future waits for the notifications of the kernel within the write timeout of
+/// the socket.
+///
+/// If the future is dropped before it resolves, the socket keeps the buffer until the
+/// notification, which is read by the futures of the later zero-copy sends on the socket.
+///
+/// This future is created by the `send_zc` methods of [`TcpStream`] and [`UdpSocket`].
+///
+/// [`TcpStream`]: struct.TcpStream.html
+/// [`UdpSocket`]: struct.UdpSocket.html
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ZeroCopySend<B: Send + 'static> {
+    socket: Arc<dyn ErrorQueue>,
+    tracker: Arc<Tracker>,
+    buf: Option<Box<B>>,
+    ids: Vec<u32>,
+    sent: usize,
+}
+
+impl<B: Send + 'static> ZeroCopySend<B> {
+    /// Returns the number of bytes sent.
+    #[inline]
+    pub fn sent(&self) -> usize {
+        self.sent
+    }
+}
+
+impl<B: AsRef<[u8]> + Send + 'static> ZeroCopySend<B> {
+    #[inline]
+    fn bytes(&self) -> &[u8] {
+        (**self.buf.as_ref().expect("polled after completion")).as_ref()
+    }
+}
+
+impl<B: Send + 'static> Unpin for ZeroCopySend<B> {}
+
+impl<B: Send + 'static> Future for ZeroCopySend<B> {
+    type Output = io::Result<B>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if !this.ids.is_empty() {
+            futures::ready!(this.socket.poll_complete(cx, &this.tracker, &this.ids))?;
+            this.ids.clear();
+        }
+        let buf = this.buf.take().expect("polled after completion");
+        Poll::Ready(Ok(*buf))
+    }
+}
+
+impl<B: Send + 'static> Drop for ZeroCopySend<B> {
+    fn drop(&mut self) {
+        if let Some(buf) = self.buf.take() {
+            if !self.ids.is_empty() {
+                self.tracker.abandon(&self.ids, buf);
+            }
+        }
+    }
+}
+
+impl<B: Send + 'static> Debug for ZeroCopySend<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZeroCopySend")
+            .field("sent", &self.sent)
+            .field("pending", &!self.ids.is_empty())
+            .finish()
+    }
+}
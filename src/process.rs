@@ -0,0 +1,567 @@
+//! Asynchronous child processes.
+//!
+//! This module is an async version of [`std::process`]. A [`Command`] spawns a [`Child`] like
+//! [`std::process::Command`] does, but waiting for the child parks the task instead of the
+//! thread: the runtime watches the exit of the child by a pidfd registered to the reactor on
+//! Linux, and by polling it with a backoff elsewhere.
+//!
+//! The piped standard streams of a child are asynchronous as well, and the [`pty`] module runs
+//! interactive children in pseudoterminals.
+//!
+//! [`pty`]: pty/index.html
+//! [`std::process`]: https://doc.rust-lang.org/std/process/index.html
+//! [`std::process::Command`]: https://doc.rust-lang.org/std/process/struct.Command.html
+//! [`Command`]: struct.Command.html
+//! [`Child`]: struct.Child.html
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use tio::process::Command;
+//!
+//! let status = Command::new("true").status().await?;
+//! assert!(status.success());
+//! #
+//! # Ok(()) }) }
+//! ```
+
+mod child;
+mod stdio;
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "freebsd"
+))]
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "freebsd"
+    )))
+)]
+pub mod pty;
+
+pub use child::Child;
+pub use std::process::{ExitStatus, Output, Stdio};
+pub use stdio::{ChildStderr, ChildStdin, ChildStdout};
+
+use std::ffi::OsStr;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+
+/// A builder of child processes.
+///
+/// It takes the same options as [`std::process::Command`], but [`spawn`] returns a [`Child`]
+/// to be waited asynchronously. It must be spawned within a runtime.
+///
+/// [`std::process::Command`]: https://doc.rust-lang.org/std/process/struct.Command.html
+/// [`spawn`]: #method.spawn
+/// [`Child`]: struct.Child.html
+pub struct Command {
+    std: std::process::Command,
+    // whether the standard input, output and error are configured, for `output`
+    configured: [bool; 3],
+    kill_on_drop: bool,
+    setsid: bool,
+    // above every target of `pass_fd`, where the passed fds are duplicated
+    fd_floor: RawFd,
+}
+
+impl Command {
+    /// Creates a command for launching the program at `program`.
+    ///
+    /// See [`std::process::Command::new`] for the defaults.
+    ///
+    /// [`std::process::Command::new`]:
+    /// https://doc.rust-lang.org/std/process/struct.Command.html#method.new
+    #[inline]
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
+        Self {
+            std: std::process::Command::new(program),
+            configured: [false; 3],
+            kill_on_drop: false,
+            setsid: false,
+            fd_floor: 0,
+        }
+    }
+
+    /// Adds an argument to pass to the program.
+    #[inline]
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.std.arg(arg);
+        self
+    }
+
+    /// Adds multiple arguments to pass to the program.
+    #[inline]
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.std.args(args);
+        self
+    }
+
+    /// Sets an environment variable for the child.
+    #[inline]
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.std.env(key, val);
+        self
+    }
+
+    /// Sets multiple environment variables for the child.
+    #[inline]
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.std.envs(vars);
+        self
+    }
+
+    /// Removes an environment variable for the child.
+    #[inline]
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+        self.std.env_remove(key);
+        self
+    }
+
+    /// Clears all environment variables for the child.
+    #[inline]
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.std.env_clear();
+        self
+    }
+
+    /// Sets the working directory of the child.
+    #[inline]
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.std.current_dir(dir);
+        self
+    }
+
+    /// Sets the configuration of the standard input of the child.
+    #[inline]
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.std.stdin(cfg);
+        self.configured[0] = true;
+        self
+    }
+
+    /// Sets the configuration of the standard output of the child.
+    #[inline]
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.std.stdout(cfg);
+        self.configured[1] = true;
+        self
+    }
+
+    /// Sets the configuration of the standard error of the child.
+    #[inline]
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.std.stderr(cfg);
+        self.configured[2] = true;
+        self
+    }
+
+    /// Sets whether to kill the child if it is dropped before it exits, `false` by default.
+    ///
+    /// The killed child is reaped on the blocking pool, so it never lingers as a zombie. This
+    /// makes a task which is cancelled while waiting for its child leave nothing behind, but
+    /// the grandchildren still run on.
+    #[inline]
+    pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    /// Puts the child into the process group `pgroup`, or a new group led by itself if
+    /// `pgroup` is 0.
+    ///
+    /// A supervisor can then signal the child and all of its descendants at once, by sending
+    /// the signal to the negated pid of the group leader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::process::Command;
+    ///
+    /// let mut child = Command::new("true").process_group(0).spawn()?;
+    /// assert!(child.wait().await?.success());
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn process_group(&mut self, pgroup: i32) -> &mut Self {
+        self.std.process_group(pgroup);
+        self
+    }
+
+    /// Makes the child the leader of a new session, detached from the controlling terminal.
+    ///
+    /// The child also leads a new process group, so this cannot be combined with
+    /// [`process_group`], which fails the spawn.
+    ///
+    /// [`process_group`]: #method.process_group
+    pub fn setsid(&mut self) -> &mut Self {
+        if !self.setsid {
+            self.setsid = true;
+            // SAFETY: `setsid` is a bare syscall, which is async-signal-safe and allocates
+            // nothing
+            #[allow(unsafe_code)]
+            unsafe {
+                self.std.pre_exec(|| {
+                    rustix::process::setsid()?;
+                    Ok(())
+                });
+            }
+        }
+        self
+    }
+
+    /// Runs the child in the terminal `pts`, as its standard streams and its controlling
+    /// terminal.
+    ///
+    /// The child leads a new session as by [`setsid`], which the terminal belongs to.
+    ///
+    /// [`setsid`]: #method.setsid
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "freebsd"
+    ))]
+    #[cfg_attr(
+        feature = "docs",
+        doc(cfg(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "freebsd"
+        )))
+    )]
+    pub fn pty(&mut self, pts: &pty::Pts) -> io::Result<&mut Self> {
+        self.stdin(pts.stdio()?)
+            .stdout(pts.stdio()?)
+            .stderr(pts.stdio()?);
+        let fd = pts.try_clone_fd()?;
+        let session = !self.setsid;
+        self.setsid = true;
+        // SAFETY: `setsid` and `ioctl` are bare syscalls, which are async-signal-safe and
+        // allocate nothing
+        #[allow(unsafe_code)]
+        unsafe {
+            self.std.pre_exec(move || {
+                if session {
+                    rustix::process::setsid()?;
+                }
+                rustix::process::ioctl_tiocsctty(&fd)?;
+                Ok(())
+            });
+        }
+        Ok(self)
+    }
+
+    /// Passes `fd` to the child as its file descriptor `target`, which stays open when the
+    /// child executes the program.
+    ///
+    /// Only the child gets `fd` at `target`, duplicated after the fork, so `fd` itself stays
+    /// closed on exec and leaks into no other child spawned in the meantime. Whatever the
+    /// child has at `target` is replaced, even a standard stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use std::io::Read;
+    /// use std::os::unix::net::UnixStream;
+    /// use tio::process::Command;
+    ///
+    /// let (mut supervisor, worker) = UnixStream::pair()?;
+    /// let mut command = Command::new("sh");
+    /// command.args(["-c", "echo hello >&3"]).pass_fd(&worker, 3)?;
+    /// assert!(command.status().await?.success());
+    ///
+    /// drop((command, worker));
+    /// let mut message = String::new();
+    /// supervisor.read_to_string(&mut message)?;
+    /// assert_eq!("hello\n", message);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn pass_fd(&mut self, fd: impl AsFd, target: RawFd) -> io::Result<&mut Self> {
+        if target < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "negative file descriptor",
+            ));
+        }
+        // the duplicate is above the targets passed before, which are overwritten before it
+        // is read
+        self.fd_floor = self.fd_floor.max(target + 1);
+        let fd = rustix::io::fcntl_dupfd_cloexec(fd, self.fd_floor)?;
+        // SAFETY: `dup2` is a bare syscall, which is async-signal-safe and allocates nothing
+        #[allow(unsafe_code)]
+        unsafe {
+            self.std.pre_exec(move || {
+                if libc::dup2(fd.as_raw_fd(), target) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(self)
+    }
+
+    /// Schedules a closure to run in the child after it is forked, just before it executes
+    /// the program, like [`CommandExt::pre_exec`].
+    ///
+    /// The closures run in the order they are added, and the spawn fails with the first
+    /// error returned by them.
+    ///
+    /// # Safety
+    ///
+    /// The closure runs in a forked copy of this process, in which only the forking thread
+    /// exists: any lock held by another thread at the fork is never released. It must only
+    /// make async-signal-safe calls, so in particular no allocation, locking or logging.
+    ///
+    /// [`CommandExt::pre_exec`]:
+    /// https://doc.rust-lang.org/std/os/unix/process/trait.CommandExt.html#tymethod.pre_exec
+    #[allow(unsafe_code)]
+    #[inline]
+    pub unsafe fn pre_exec<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnMut() -> io::Result<()> + Send + Sync + 'static,
+    {
+        self.std.pre_exec(f);
+        self
+    }
+
+    /// Spawns the command as a child process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::process::Command;
+    ///
+    /// let mut child = Command::new("sh").args(["-c", "exit 3"]).spawn()?;
+    /// assert_eq!(Some(3), child.wait().await?.code());
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    #[inline]
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        Child::new(self.std.spawn()?, self.kill_on_drop)
+    }
+
+    /// Spawns the command and waits for it to exit, returning its status.
+    ///
+    /// The child inherits the standard input and output of this process unless they are
+    /// configured otherwise.
+    #[inline]
+    pub async fn status(&mut self) -> io::Result<ExitStatus> {
+        self.spawn()?.wait().await
+    }
+
+    /// Spawns the command and collects its status and output.
+    ///
+    /// The standard output and error are piped and drained concurrently, and the standard
+    /// input is [`Stdio::null`], unless they are configured otherwise by this builder.
+    ///
+    /// [`Stdio::null`]: https://doc.rust-lang.org/std/process/struct.Stdio.html#method.null
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::process::Command;
+    ///
+    /// let output = Command::new("sh").args(["-c", "echo out; echo err >&2"]).output().await?;
+    /// assert!(output.status.success());
+    /// assert_eq!(b"out\n", &output.stdout[..]);
+    /// assert_eq!(b"err\n", &output.stderr[..]);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn output(&mut self) -> io::Result<Output> {
+        let [stdin, stdout, stderr] = self.configured;
+        if !stdin {
+            self.std.stdin(Stdio::null());
+        }
+        if !stdout {
+            self.std.stdout(Stdio::piped());
+        }
+        if !stderr {
+            self.std.stderr(Stdio::piped());
+        }
+        let child = self.std.spawn();
+        // restore the defaults of `spawn`
+        if !stdin {
+            self.std.stdin(Stdio::inherit());
+        }
+        if !stdout {
+            self.std.stdout(Stdio::inherit());
+        }
+        if !stderr {
+            self.std.stderr(Stdio::inherit());
+        }
+        Child::new(child?, self.kill_on_drop)?
+            .wait_with_output()
+            .await
+    }
+
+    /// Returns the underlying [`std::process::Command`], to set the platform-specific options.
+    ///
+    /// [`std::process::Command`]: https://doc.rust-lang.org/std/process/struct.Command.html
+    #[inline]
+    pub fn as_std_mut(&mut self) -> &mut std::process::Command {
+        &mut self.std
+    }
+}
+
+impl From<std::process::Command> for Command {
+    #[inline]
+    fn from(std: std::process::Command) -> Self {
+        Self {
+            std,
+            configured: [false; 3],
+            kill_on_drop: false,
+            setsid: false,
+            fd_floor: 0,
+        }
+    }
+}
+
+impl Debug for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.std, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Stdio};
+    use crate::task;
+    use rustix::io::{fcntl_getfd, FdFlags};
+    use rustix::process::{getpgid, getsid, Pid};
+    use std::io::{self, Read};
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn output_large() -> io::Result<()> {
+        task::block_on(async {
+            // more than a pipe holds on both sides, which blocks a sequential reader
+            let script = "head -c 200000 /dev/zero; head -c 200000 /dev/zero >&2";
+            let output = Command::new("sh").args(["-c", script]).output().await?;
+            assert!(output.status.success());
+            assert_eq!(200000, output.stdout.len());
+            assert_eq!(200000, output.stderr.len());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn output_configured() -> io::Result<()> {
+        task::block_on(async {
+            let mut command = Command::new("sh");
+            command.args(["-c", "printf \"$OUT\"; printf err >&2"]);
+            command.env("OUT", "out").stderr(Stdio::null());
+            let output = command.output().await?;
+            assert_eq!(b"out", &output.stdout[..]);
+            assert!(output.stderr.is_empty());
+            // the pipes of `output` are not kept for `spawn`
+            let mut child = command.env_remove("OUT").spawn()?;
+            assert!(child.stdout.is_none());
+            child.wait().await?;
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn process_group() -> io::Result<()> {
+        task::block_on(async {
+            let mut child = Command::new("sleep").arg("10").process_group(0).spawn()?;
+            let pid = Pid::from_raw(child.id() as i32);
+            assert_eq!(pid, getpgid(pid).ok());
+            child.kill().await
+        })
+    }
+
+    #[test]
+    fn setsid() -> io::Result<()> {
+        task::block_on(async {
+            let mut command = Command::new("sleep");
+            // set twice, which is the same as once
+            let mut child = command.arg("10").setsid().setsid().spawn()?;
+            let pid = Pid::from_raw(child.id() as i32);
+            assert_eq!(pid, getsid(pid).ok());
+            child.kill().await
+        })
+    }
+
+    #[test]
+    fn pass_fd() -> io::Result<()> {
+        task::block_on(async {
+            let (mut first, first_end) = UnixStream::pair()?;
+            let (mut second, second_end) = UnixStream::pair()?;
+            let mut command = Command::new("sh");
+            command.args(["-c", "echo one >&4; echo two >&3"]);
+            // the second is duplicated above the first target
+            command.pass_fd(&first_end, 4)?.pass_fd(&second_end, 3)?;
+            assert!(command.status().await?.success());
+            assert!(fcntl_getfd(&first_end)?.contains(FdFlags::CLOEXEC));
+
+            drop((command, first_end, second_end));
+            let mut message = String::new();
+            first.read_to_string(&mut message)?;
+            assert_eq!("one\n", message);
+            message.clear();
+            second.read_to_string(&mut message)?;
+            assert_eq!("two\n", message);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn pass_fd_negative() -> io::Result<()> {
+        let (socket, _) = UnixStream::pair()?;
+        let err = Command::new("true").pass_fd(&socket, -1).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        Ok(())
+    }
+
+    #[test]
+    fn pre_exec_error() {
+        task::block_on(async {
+            let mut command = Command::new("true");
+            // SAFETY: constructing an error does not allocate for a raw OS error
+            unsafe {
+                command.pre_exec(|| Err(io::Error::from_raw_os_error(1)));
+            }
+            let err = command.spawn().unwrap_err();
+            assert_eq!(Some(1), err.raw_os_error());
+        })
+    }
+}
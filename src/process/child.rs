@@ -0,0 +1,344 @@
+use super::{ChildStderr, ChildStdin, ChildStdout};
+#[cfg(target_os = "linux")]
+use crate::net::poll::Fd;
+use crate::net::poll::Watcher;
+use crate::runtime::time::Delay;
+use futures::future::{self, poll_fn};
+use futures::{AsyncRead, AsyncReadExt};
+use rustix::process::WaitOptions;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::process::{ExitStatus, Output};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The first interval to poll a child which has no pidfd.
+const MIN_BACKOFF: Duration = Duration::from_millis(1);
+
+/// The max interval to poll a child which has no pidfd.
+const MAX_BACKOFF: Duration = Duration::from_millis(100);
+
+/// A child process spawned by [`Command::spawn`].
+///
+/// Unlike [`std::process::Child`], it is waited asynchronously by [`wait`].
+///
+/// Dropping a child neither kills nor waits for it, the same as [`std::process::Child`],
+/// unless it is spawned with [`Command::kill_on_drop`]. A task which has a running child
+/// should kill it before it gives up waiting:
+///
+/// ```
+/// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+/// #
+/// use std::time::Duration;
+///
+/// use tio::process::Command;
+/// use tio::task;
+///
+/// let mut child = Command::new("sleep").arg("10").spawn()?;
+/// if task::timeout(Duration::from_millis(10), child.wait()).await.is_err() {
+///     child.kill().await?;
+/// }
+/// #
+/// # Ok(()) }) }
+/// ```
+///
+/// [`Command::spawn`]: struct.Command.html#method.spawn
+/// [`std::process::Child`]: https://doc.rust-lang.org/std/process/struct.Child.html
+/// [`wait`]: #method.wait
+/// [`Command::kill_on_drop`]: struct.Command.html#method.kill_on_drop
+pub struct Child {
+    /// The standard input of the child, if it is piped.
+    pub stdin: Option<ChildStdin>,
+
+    /// The standard output of the child, if it is piped.
+    pub stdout: Option<ChildStdout>,
+
+    /// The standard error of the child, if it is piped.
+    pub stderr: Option<ChildStderr>,
+
+    child: std::process::Child,
+    exit: Exit,
+    kill_on_drop: bool,
+}
+
+/// The way to learn the exit of a child.
+enum Exit {
+    /// The pidfd of the child, readable once it exits.
+    #[cfg(target_os = "linux")]
+    PidFd(Watcher<Fd>),
+
+    /// Polling the child with a growing interval.
+    Backoff {
+        interval: Duration,
+        delay: Option<Delay>,
+    },
+}
+
+impl Exit {
+    #[cfg(target_os = "linux")]
+    fn new(child: &std::process::Child) -> Self {
+        use rustix::process::{pidfd_open, Pid, PidfdFlags};
+        match pidfd_open(Pid::from_child(child), PidfdFlags::empty()) {
+            Ok(fd) => Exit::PidFd(Watcher::new(Fd(fd))),
+            // the kernel is older than 5.3, or the syscall is filtered
+            Err(err) => {
+                log::debug!("fall back to polling the child for pidfd_open: {}", err);
+                Self::backoff()
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    #[inline]
+    fn new(_child: &std::process::Child) -> Self {
+        Self::backoff()
+    }
+
+    #[inline]
+    fn backoff() -> Self {
+        Exit::Backoff {
+            interval: MIN_BACKOFF,
+            delay: None,
+        }
+    }
+}
+
+impl Child {
+    #[inline]
+    pub(super) fn new(
+        mut child: std::process::Child,
+        kill_on_drop: bool,
+    ) -> io::Result<Self> {
+        let stdin = child.stdin.take().map(ChildStdin::new).transpose()?;
+        let stdout = child.stdout.take().map(ChildStdout::new).transpose()?;
+        let stderr = child.stderr.take().map(ChildStderr::new).transpose()?;
+        let exit = Exit::new(&child);
+        Ok(Self {
+            stdin,
+            stdout,
+            stderr,
+            child,
+            exit,
+            kill_on_drop,
+        })
+    }
+
+    /// Returns the OS-assigned process identifier of the child.
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Returns the exit status of the child if it has exited, without waiting.
+    #[inline]
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+
+    /// Waits for the child to exit, returning its status.
+    ///
+    /// The standard input of the child is not closed before waiting, so a child reading it
+    /// may never exit. Cancelling the wait leaves the child running.
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        poll_fn(|cx| self.poll_wait(cx)).await
+    }
+
+    /// Sends `SIGKILL` to the child, without waiting for it to exit.
+    ///
+    /// Nothing is sent if the child has been waited, as its pid may be taken by another
+    /// process.
+    #[inline]
+    pub fn start_kill(&mut self) -> io::Result<()> {
+        self.child.kill()
+    }
+
+    /// Kills the child and waits for it to exit.
+    pub async fn kill(&mut self) -> io::Result<()> {
+        self.start_kill()?;
+        self.wait().await?;
+        Ok(())
+    }
+
+    /// Waits for the child to exit, collecting its status and all of its piped output.
+    ///
+    /// The standard input is closed first. The standard output and error are read
+    /// concurrently with the wait, so the child never blocks on a full pipe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+    /// #
+    /// use tio::process::{Command, Stdio};
+    ///
+    /// let child = Command::new("echo")
+    ///     .arg("hello")
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?;
+    /// let output = child.wait_with_output().await?;
+    /// assert_eq!(b"hello\n", &output.stdout[..]);
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn wait_with_output(mut self) -> io::Result<Output> {
+        drop(self.stdin.take());
+        let stdout = self.stdout.take();
+        let stderr = self.stderr.take();
+        let (status, stdout, stderr) =
+            future::try_join3(self.wait(), read_to_end(stdout), read_to_end(stderr))
+                .await?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+
+    fn poll_wait(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<ExitStatus>> {
+        let child = &mut self.child;
+        match &mut self.exit {
+            #[cfg(target_os = "linux")]
+            Exit::PidFd(watcher) => {
+                watcher.poll_read_with(cx, |_| match child.try_wait()? {
+                    Some(status) => Ok(status),
+                    None => Err(io::ErrorKind::WouldBlock.into()),
+                })
+            }
+            Exit::Backoff { interval, delay } => loop {
+                if let Some(status) = child.try_wait()? {
+                    *delay = None;
+                    return Poll::Ready(Ok(status));
+                }
+                let dur = *interval;
+                let timer = delay.get_or_insert_with(|| Delay::new(dur));
+                futures::ready!(Pin::new(timer).poll(cx));
+                *delay = None;
+                *interval = (dur * 2).min(MAX_BACKOFF);
+            },
+        }
+    }
+}
+
+/// Reads a pipe to its end, if it exists.
+async fn read_to_end<R: AsyncRead + Unpin>(pipe: Option<R>) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        pipe.read_to_end(&mut buf).await?;
+    }
+    Ok(buf)
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if !self.kill_on_drop || !matches!(self.child.try_wait(), Ok(None)) {
+            return;
+        }
+        if let Err(err) = self.child.kill() {
+            log::debug!("fail to kill child {} on drop: {}", self.id(), err);
+            return;
+        }
+        // reap the child, which is killed and exits soon
+        let pid = rustix::process::Pid::from_child(&self.child);
+        let reap = move || rustix::process::waitpid(Some(pid), WaitOptions::empty());
+        if let Err(err) = crate::task::try_spawn_blocking(reap) {
+            log::debug!("fail to reap child {} on drop: {}", pid, err);
+        }
+    }
+}
+
+impl Debug for Child {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Child").field("id", &self.id()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Child, Exit};
+    use crate::process::Command;
+    use crate::task;
+    use rustix::process::{Pid, Signal};
+    use std::io;
+    use std::os::unix::process::ExitStatusExt;
+    use std::time::Duration;
+
+    #[test]
+    fn wait_twice() -> io::Result<()> {
+        task::block_on(async {
+            let mut child = Command::new("sh").args(["-c", "exit 2"]).spawn()?;
+            assert_eq!(Some(2), child.wait().await?.code());
+            // the status is kept once the child is reaped
+            assert_eq!(Some(2), child.wait().await?.code());
+            assert_eq!(Some(2), child.try_wait()?.and_then(|status| status.code()));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn backoff() -> io::Result<()> {
+        task::block_on(async {
+            let std = std::process::Command::new("sleep").arg("0.05").spawn()?;
+            let mut child = Child {
+                stdin: None,
+                stdout: None,
+                stderr: None,
+                child: std,
+                exit: Exit::backoff(),
+                kill_on_drop: false,
+            };
+            assert!(child.try_wait()?.is_none());
+            assert!(child.wait().await?.success());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn kill() -> io::Result<()> {
+        task::block_on(async {
+            let mut child = Command::new("sleep").arg("10").spawn()?;
+            let dur = Duration::from_millis(10);
+            assert!(task::timeout(dur, child.wait()).await.is_err());
+            child.kill().await?;
+            let status = child.try_wait()?.unwrap();
+            assert_eq!(Some(Signal::KILL.as_raw()), status.signal());
+            // killing a waited child sends nothing
+            child.kill().await?;
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn kill_on_drop() -> io::Result<()> {
+        task::block_on(async {
+            let child = Command::new("sleep").arg("10").kill_on_drop(true).spawn()?;
+            let pid = Pid::from_raw(child.id() as i32).unwrap();
+            drop(child);
+            let dur = Duration::from_millis(1);
+            // the child is killed and reaped, so it is gone
+            while rustix::process::test_kill_process(pid).is_ok() {
+                task::sleep(dur).await;
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn concurrent_children() -> io::Result<()> {
+        task::block_on(async {
+            let handles = (0..4)
+                .map(|code| {
+                    task::spawn(async move {
+                        let script = format!("sleep 0.01; exit {}", code);
+                        Command::new("sh").args(["-c", &script]).status().await
+                    })
+                })
+                .collect::<Vec<_>>();
+            for (code, handle) in handles.into_iter().enumerate() {
+                assert_eq!(Some(code as i32), handle.await.unwrap()?.code());
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,316 @@
+//! Pseudoterminals for interactive children.
+//!
+//! A [`Pty`] is the master side of a pseudoterminal, read and written asynchronously like a
+//! pipe. Its [`Pts`] is the terminal side, given to a child by [`Command::pty`], so the child
+//! sees a real terminal: it can query the window size, and its line discipline handles the
+//! echoing, the line editing and the signals like `^C`, as when it runs in a terminal
+//! emulator.
+//!
+//! [`Pty`]: struct.Pty.html
+//! [`Pts`]: struct.Pts.html
+//! [`Command::pty`]: ../struct.Command.html#method.pty
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use futures::AsyncReadExt;
+//! use tio::process::pty::Pty;
+//! use tio::process::Command;
+//!
+//! let mut pty = Pty::open()?;
+//! pty.resize(24, 80)?;
+//! let mut child = Command::new("stty").arg("size").pty(&pty.pts()?)?.spawn()?;
+//! let mut output = String::new();
+//! pty.read_to_string(&mut output).await?;
+//! assert_eq!("24 80\r\n", output);
+//! assert!(child.wait().await?.success());
+//! #
+//! # Ok(()) }) }
+//! ```
+
+use crate::net::poll::{Fd, Watcher};
+use futures::task::{Context, Poll};
+use futures::{AsyncRead, AsyncWrite};
+use rustix::pty::{grantpt, openpt, ptsname, unlockpt, OpenptFlags};
+use rustix::termios::{tcgetwinsize, tcsetwinsize, Winsize};
+use std::fmt::{self, Debug, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Stdio;
+
+/// The master side of a pseudoterminal.
+///
+/// What is written to it is the input of the terminal, and what the children write to the
+/// terminal is read from it. Reading reaches the end once no child holds the terminal.
+pub struct Pty {
+    watcher: Watcher<Fd>,
+    path: PathBuf,
+}
+
+/// The terminal side of a [`Pty`], to be given to the children.
+///
+/// [`Pty`]: struct.Pty.html
+pub struct Pts {
+    file: File,
+    path: PathBuf,
+}
+
+impl Pty {
+    /// Opens a new pseudoterminal.
+    ///
+    /// It must be opened within a runtime.
+    pub fn open() -> io::Result<Self> {
+        let fd = open_master()?;
+        grantpt(&fd)?;
+        unlockpt(&fd)?;
+        let path = ptsname(&fd, Vec::new())?.into_string().map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, err.utf8_error())
+        })?;
+        rustix::io::ioctl_fionbio(&fd, true)?;
+        Ok(Self {
+            watcher: Watcher::new(Fd(fd)),
+            path: path.into(),
+        })
+    }
+
+    /// Opens the terminal side of this pseudoterminal.
+    ///
+    /// It can be opened multiple times, for multiple children.
+    pub fn pts(&self) -> io::Result<Pts> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(rustix::fs::OFlags::NOCTTY.bits() as i32)
+            .open(&self.path)?;
+        Ok(Pts {
+            file,
+            path: self.path.clone(),
+        })
+    }
+
+    /// Returns the path of the terminal side, like `/dev/pts/0`.
+    #[inline]
+    pub fn pts_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the window size of the terminal, as the rows and the columns.
+    #[inline]
+    pub fn size(&self) -> io::Result<(u16, u16)> {
+        let size = tcgetwinsize(self)?;
+        Ok((size.ws_row, size.ws_col))
+    }
+
+    /// Sets the window size of the terminal, which sends `SIGWINCH` to its foreground
+    /// children.
+    #[inline]
+    pub fn resize(&self, rows: u16, cols: u16) -> io::Result<()> {
+        let size = Winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        tcsetwinsize(self, size)?;
+        Ok(())
+    }
+
+    /// Returns whether this pseudoterminal is closed on exec.
+    ///
+    /// For more information about this option, see [`set_close_on_exec`].
+    ///
+    /// [`set_close_on_exec`]: #method.set_close_on_exec
+    #[inline]
+    pub fn close_on_exec(&self) -> io::Result<bool> {
+        crate::net::util::close_on_exec(self.as_fd())
+    }
+
+    /// Sets whether this pseudoterminal is closed on exec, i.e. when a child process executes a
+    /// program.
+    ///
+    /// The pseudoterminal is opened closed on exec, so no child inherits it by accident. Once
+    /// this is cleared, every child spawned afterwards inherits the pseudoterminal, including
+    /// those spawned by other threads in the meantime; [`Command::pass_fd`] hands it to one
+    /// child only.
+    ///
+    /// [`Command::pass_fd`]: ../process/struct.Command.html#method.pass_fd
+    #[inline]
+    pub fn set_close_on_exec(&self, on: bool) -> io::Result<()> {
+        crate::net::util::set_close_on_exec(self.as_fd(), on)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+#[inline]
+fn open_master() -> io::Result<OwnedFd> {
+    let flags = OpenptFlags::RDWR | OpenptFlags::NOCTTY | OpenptFlags::CLOEXEC;
+    Ok(openpt(flags)?)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+#[inline]
+fn open_master() -> io::Result<OwnedFd> {
+    use rustix::io::{fcntl_setfd, FdFlags};
+    let fd = openpt(OpenptFlags::RDWR | OpenptFlags::NOCTTY)?;
+    fcntl_setfd(&fd, FdFlags::CLOEXEC)?;
+    Ok(fd)
+}
+
+impl Pts {
+    /// Returns the path of this terminal, like `/dev/pts/0`.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Creates a handle to this terminal, to be a standard stream of a child.
+    ///
+    /// See [`Command::pty`] for a child which takes this terminal as its controlling one.
+    ///
+    /// [`Command::pty`]: ../struct.Command.html#method.pty
+    #[inline]
+    pub fn stdio(&self) -> io::Result<Stdio> {
+        Ok(self.file.try_clone()?.into())
+    }
+
+    #[inline]
+    pub(super) fn try_clone_fd(&self) -> io::Result<OwnedFd> {
+        Ok(self.file.try_clone()?.into())
+    }
+}
+
+impl AsyncRead for Pty {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.watcher
+            .poll_read_with(cx, |fd| match rustix::io::read(fd, &mut *buf) {
+                // Linux fails the read once no one holds the terminal side
+                Err(rustix::io::Errno::IO) => Ok(0),
+                ret => Ok(ret?),
+            })
+    }
+}
+
+impl AsyncWrite for Pty {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.watcher
+            .poll_write_with(cx, |fd| Ok(rustix::io::write(fd, buf)?))
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// The pseudoterminal is not closed until this is dropped.
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsFd for Pty {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.watcher.as_fd()
+    }
+}
+
+impl AsRawFd for Pty {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+impl AsFd for Pts {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl AsRawFd for Pts {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl Debug for Pty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pty")
+            .field("fd", &self.as_raw_fd())
+            .field("pts", &self.path)
+            .finish()
+    }
+}
+
+impl Debug for Pts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pts")
+            .field("fd", &self.as_raw_fd())
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pty;
+    use crate::process::Command;
+    use crate::task;
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use std::io;
+
+    #[test]
+    fn interactive() -> io::Result<()> {
+        task::block_on(async {
+            let mut pty = Pty::open()?;
+            let script = "test -t 0 && read line && echo \"got $line\"";
+            let mut child = Command::new("sh")
+                .args(["-c", script])
+                .pty(&pty.pts()?)?
+                .spawn()?;
+            pty.write_all(b"hello\n").await?;
+            let mut output = String::new();
+            pty.read_to_string(&mut output).await?;
+            // the terminal echoes the input
+            assert_eq!("hello\r\ngot hello\r\n", output);
+            assert!(child.wait().await?.success());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn controlling_terminal() -> io::Result<()> {
+        task::block_on(async {
+            let mut pty = Pty::open()?;
+            pty.resize(30, 100)?;
+            assert_eq!((30, 100), pty.size()?);
+            let mut child = Command::new("sh")
+                .args(["-c", "stty size < /dev/tty"])
+                .pty(&pty.pts()?)?
+                .spawn()?;
+            let mut output = String::new();
+            pty.read_to_string(&mut output).await?;
+            assert_eq!("30 100\r\n", output);
+            assert!(child.wait().await?.success());
+            Ok(())
+        })
+    }
+}
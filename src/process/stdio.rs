@@ -0,0 +1,168 @@
+use crate::net::poll::Watcher;
+use futures::task::{Context, Poll};
+use futures::{AsyncRead, AsyncWrite};
+use mio::unix::pipe::{Receiver, Sender};
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::pin::Pin;
+
+/// The standard input of a child, configured by [`Stdio::piped`].
+///
+/// Writing to it parks the task while the pipe is full. The pipe is closed once this is
+/// dropped, which the child may take as the end of its input.
+///
+/// [`Stdio::piped`]: https://doc.rust-lang.org/std/process/struct.Stdio.html#method.piped
+pub struct ChildStdin(Watcher<Sender>);
+
+/// The standard output of a child, configured by [`Stdio::piped`].
+///
+/// Reading from it parks the task while the pipe is empty.
+///
+/// [`Stdio::piped`]: https://doc.rust-lang.org/std/process/struct.Stdio.html#method.piped
+pub struct ChildStdout(Watcher<Receiver>);
+
+/// The standard error of a child, configured by [`Stdio::piped`].
+///
+/// Reading from it parks the task while the pipe is empty.
+///
+/// [`Stdio::piped`]: https://doc.rust-lang.org/std/process/struct.Stdio.html#method.piped
+pub struct ChildStderr(Watcher<Receiver>);
+
+impl ChildStdin {
+    #[inline]
+    pub(super) fn new(stdin: std::process::ChildStdin) -> io::Result<Self> {
+        let sender = Sender::from(stdin);
+        sender.set_nonblocking(true)?;
+        Ok(Self(Watcher::new(sender)))
+    }
+}
+
+impl ChildStdout {
+    #[inline]
+    pub(super) fn new(stdout: std::process::ChildStdout) -> io::Result<Self> {
+        let receiver = Receiver::from(stdout);
+        receiver.set_nonblocking(true)?;
+        Ok(Self(Watcher::new(receiver)))
+    }
+}
+
+impl ChildStderr {
+    #[inline]
+    pub(super) fn new(stderr: std::process::ChildStderr) -> io::Result<Self> {
+        let receiver = Receiver::from(stderr);
+        receiver.set_nonblocking(true)?;
+        Ok(Self(Watcher::new(receiver)))
+    }
+}
+
+impl AsyncWrite for ChildStdin {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_write_with(cx, |mut o| o.write(buf))
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_write_with(cx, |mut o| o.write_vectored(bufs))
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.poll_write_with(cx, |mut o| o.flush())
+    }
+
+    /// The pipe is not closed until this is dropped.
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+macro_rules! impl_read {
+    ($ty:ty) => {
+        impl AsyncRead for $ty {
+            #[inline]
+            fn poll_read(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<io::Result<usize>> {
+                self.0.poll_read_with(cx, |mut i| i.read(buf))
+            }
+
+            #[inline]
+            fn poll_read_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &mut [IoSliceMut<'_>],
+            ) -> Poll<io::Result<usize>> {
+                self.0.poll_read_with(cx, |mut i| i.read_vectored(bufs))
+            }
+        }
+    };
+}
+
+impl_read!(ChildStdout);
+impl_read!(ChildStderr);
+
+macro_rules! impl_fd {
+    ($($ty:ident),*) => {
+        $(
+            impl AsRawFd for $ty {
+                #[inline]
+                fn as_raw_fd(&self) -> RawFd {
+                    self.0.as_raw_fd()
+                }
+            }
+
+            impl Debug for $ty {
+                fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                    f.debug_tuple(stringify!($ty)).field(&self.as_raw_fd()).finish()
+                }
+            }
+        )*
+    };
+}
+
+impl_fd!(ChildStdin, ChildStdout, ChildStderr);
+
+#[cfg(test)]
+mod tests {
+    use crate::process::{Command, Stdio};
+    use crate::task;
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use std::io;
+
+    #[test]
+    fn echo() -> io::Result<()> {
+        task::block_on(async {
+            let mut child = Command::new("cat")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let mut stdin = child.stdin.take().unwrap();
+            let mut stdout = child.stdout.take().unwrap();
+            stdin.write_all(b"hello").await?;
+            let mut buf = [0; 5];
+            stdout.read_exact(&mut buf).await?;
+            assert_eq!(b"hello", &buf);
+
+            // cat exits at the end of its input
+            drop(stdin);
+            let mut rest = Vec::new();
+            stdout.read_to_end(&mut rest).await?;
+            assert!(rest.is_empty());
+            assert!(child.wait().await?.success());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,360 @@
+//! Retrying fallible operations with exponential backoff.
+//!
+//! A network client retries the operations failing of transient errors, like a connection
+//! refused while a server restarts, waiting longer after each failure so as not to flood the
+//! peer. The [`Backoff`] policy tells the delays, growing exponentially up to a maximum, and
+//! spread by a [`Jitter`] so the clients failing together do not retry together. [`retry`] and
+//! [`retry_if`] drive an async closure under a policy, sleeping on the timers of the runtime.
+//!
+//! [`Backoff`]: struct.Backoff.html
+//! [`Jitter`]: enum.Jitter.html
+//! [`retry`]: fn.retry.html
+//! [`retry_if`]: fn.retry_if.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[cfg(not(feature = "tcp"))]
+//! # fn main() {}
+//! # #[cfg(feature = "tcp")]
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use std::io;
+//! use std::time::Duration;
+//!
+//! use tio::net::TcpStream;
+//! use tio::retry::{self, Backoff};
+//!
+//! let policy = Backoff::new(Duration::from_millis(100)).max_retries(5);
+//! let stream = retry::retry_if(
+//!     &policy,
+//!     || TcpStream::connect("127.0.0.1:8080"),
+//!     |err: &io::Error| err.kind() == io::ErrorKind::ConnectionRefused,
+//! )
+//! .await?;
+//! #
+//! # Ok(()) }) }
+//! ```
+
+use crate::task;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// The policy of the delays between the attempts of an operation, growing exponentially.
+///
+/// The first retry waits for the initial delay, and every later one for `factor` times the
+/// delay before it, up to the maximum delay. A [`Jitter`] then spreads each delay randomly.
+///
+/// By default, the delays start at 100 milliseconds, double up to 30 seconds, and are fully
+/// jittered, for at most 10 retries.
+///
+/// [`Jitter`]: enum.Jitter.html
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use tio::retry::{Backoff, Jitter};
+///
+/// let policy = Backoff::new(Duration::from_millis(10))
+///     .factor(3)
+///     .max_delay(Duration::from_millis(50))
+///     .max_retries(4)
+///     .jitter(Jitter::None);
+/// let delays = policy.delays().map(|delay| delay.as_millis()).collect::<Vec<_>>();
+/// assert_eq!(vec![10, 30, 50, 50], delays);
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    factor: u32,
+    max_delay: Duration,
+    max_retries: Option<usize>,
+    jitter: Jitter,
+}
+
+/// The way a delay of [`Backoff`] is spread.
+///
+/// [`Backoff`]: struct.Backoff.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Jitter {
+    /// Waits for the delay exactly.
+    None,
+    /// Waits for a random time between zero and the delay, which spreads the retries most.
+    Full,
+    /// Waits for half of the delay, then a random time up to the other half, which keeps
+    /// the delays from being short.
+    Equal,
+}
+
+impl Backoff {
+    /// Creates a policy whose first delay is `initial`, with the other properties of the
+    /// default.
+    #[inline]
+    pub fn new(initial: Duration) -> Self {
+        Self {
+            initial,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the factor by which each delay grows, 2 by default.
+    ///
+    /// A factor of 1 keeps the delays constant.
+    #[inline]
+    pub fn factor(mut self, factor: u32) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    /// Sets the delay the growth stops at, before the jitter.
+    #[inline]
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the number of retries after the first attempt, after which the last error is
+    /// returned.
+    #[inline]
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Retries for as long as the operation fails.
+    #[inline]
+    pub fn unlimited_retries(mut self) -> Self {
+        self.max_retries = None;
+        self
+    }
+
+    /// Sets how the delays are spread.
+    #[inline]
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the delays before each retry, one for every retry allowed.
+    ///
+    /// Every call picks new random jitters.
+    #[inline]
+    pub fn delays(&self) -> Delays {
+        Delays {
+            policy: *self,
+            next: self.initial.min(self.max_delay),
+            retries: 0,
+            rng: RandomState::new().build_hasher().finish(),
+        }
+    }
+}
+
+impl Default for Backoff {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            factor: 2,
+            max_delay: Duration::from_secs(30),
+            max_retries: Some(10),
+            jitter: Jitter::Full,
+        }
+    }
+}
+
+/// An iterator over the delays of a [`Backoff`], created by [`Backoff::delays`].
+///
+/// [`Backoff`]: struct.Backoff.html
+/// [`Backoff::delays`]: struct.Backoff.html#method.delays
+#[derive(Debug, Clone)]
+pub struct Delays {
+    policy: Backoff,
+    // the delay of the next retry, before the jitter
+    next: Duration,
+    retries: usize,
+    rng: u64,
+}
+
+impl Delays {
+    /// Returns a random number up to `max` inclusive, by splitmix64.
+    #[inline]
+    fn random(&mut self, max: u64) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        z % max.saturating_add(1)
+    }
+}
+
+impl Iterator for Delays {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        if Some(self.retries) == self.policy.max_retries {
+            return None;
+        }
+        self.retries += 1;
+        let delay = self.next;
+        self.next = delay
+            .checked_mul(self.policy.factor)
+            .map_or(self.policy.max_delay, |next| {
+                next.min(self.policy.max_delay)
+            });
+        let nanos = delay.as_nanos().min(u64::MAX as u128) as u64;
+        let delay = match self.policy.jitter {
+            Jitter::None => delay,
+            Jitter::Full => Duration::from_nanos(self.random(nanos)),
+            Jitter::Equal => {
+                Duration::from_nanos(nanos / 2 + self.random(nanos - nanos / 2))
+            }
+        };
+        Some(delay)
+    }
+}
+
+/// Calls `op` until it succeeds, sleeping for the delays of `policy` between the attempts.
+///
+/// Once the retries of the policy run out, the error of the last attempt is returned.
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use std::time::Duration;
+///
+/// use tio::retry::{self, Backoff};
+///
+/// let policy = Backoff::new(Duration::from_millis(1));
+/// let mut attempts = 0;
+/// let ret = retry::retry(&policy, || {
+///     attempts += 1;
+///     let attempt = attempts;
+///     async move { if attempt < 3 { Err(attempt) } else { Ok(attempt) } }
+/// })
+/// .await;
+/// assert_eq!(Ok(3), ret);
+/// #
+/// # })
+/// ```
+#[inline]
+pub async fn retry<F, Fut, T, E>(policy: &Backoff, op: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    retry_if(policy, op, |_| true).await
+}
+
+/// Calls `op` until it succeeds or fails of an error `retryable` rejects, sleeping for the
+/// delays of `policy` between the attempts.
+///
+/// The error rejected, or that of the last attempt once the retries of the policy run out, is
+/// returned.
+pub async fn retry_if<F, Fut, T, E, P>(
+    policy: &Backoff,
+    mut op: F,
+    mut retryable: P,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: FnMut(&E) -> bool,
+{
+    let mut delays = policy.delays();
+    loop {
+        let err = match op().await {
+            Ok(output) => return Ok(output),
+            Err(err) => err,
+        };
+        if !retryable(&err) {
+            return Err(err);
+        }
+        match delays.next() {
+            Some(delay) => task::sleep(delay).await,
+            None => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backoff, Jitter};
+    use crate::task::block_on;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn delays() {
+        let ms = Duration::from_millis;
+        let policy = Backoff::new(ms(100))
+            .max_delay(ms(1000))
+            .jitter(Jitter::None);
+        let delays = policy.delays().collect::<Vec<_>>();
+        assert_eq!(10, delays.len());
+        assert_eq!(
+            &[ms(100), ms(200), ms(400), ms(800), ms(1000)],
+            &delays[..5]
+        );
+        assert!(delays[5..].iter().all(|&delay| delay == ms(1000)));
+
+        // saturated rather than overflowed
+        let policy = Backoff::new(Duration::from_secs(u64::MAX / 2))
+            .factor(u32::MAX)
+            .max_delay(Duration::from_secs(u64::MAX));
+        assert!(policy
+            .delays()
+            .all(|delay| delay <= Duration::from_secs(u64::MAX)));
+        assert!(policy.unlimited_retries().delays().nth(100).is_some());
+    }
+
+    #[test]
+    fn jitter() {
+        let ms = Duration::from_millis;
+        let policy = Backoff::new(ms(100)).factor(1).max_retries(1000);
+        assert!(policy.delays().all(|delay| delay <= ms(100)));
+        assert!(policy.delays().any(|delay| delay < ms(50)));
+        let policy = policy.jitter(Jitter::Equal);
+        assert!(policy
+            .delays()
+            .all(|delay| ms(50) <= delay && delay <= ms(100)));
+        assert!(policy.delays().any(|delay| delay != ms(100)));
+    }
+
+    #[test]
+    fn retry_if() {
+        block_on(async {
+            let policy = Backoff::new(Duration::from_millis(10)).jitter(Jitter::None);
+            let mut attempts = 0;
+            let start = Instant::now();
+            let ret = super::retry_if(
+                &policy,
+                || {
+                    attempts += 1;
+                    let attempt = attempts;
+                    async move { Err::<(), _>(attempt) }
+                },
+                |&attempt| attempt < 3,
+            )
+            .await;
+            assert_eq!(Err(3), ret);
+            assert!(start.elapsed() >= Duration::from_millis(30));
+
+            // the retries run out
+            let policy = policy.max_retries(2);
+            let mut attempts = 0;
+            let ret = super::retry(&policy, || {
+                attempts += 1;
+                let attempt = attempts;
+                async move { Err::<(), _>(attempt) }
+            })
+            .await;
+            assert_eq!(Err(3), ret);
+        })
+    }
+}
@@ -0,0 +1,938 @@
+//! The tio runtime.
+//!
+//! A runtime consists of a work-stealing thread pool running async tasks, a thread pool for
+//! blocking tasks and the drivers of I/O and timers. The free functions like [`task::spawn`]
+//! use the runtime of the current context: the one which is running the current task or
+//! [`block_on`], or a default runtime created on the first use.
+//!
+//! A [`Runtime`] can be configured explicitly by the [`Builder`]:
+//!
+//! ```
+//! use tio::runtime::Builder;
+//! use tio::task;
+//!
+//! let runtime = Builder::new().worker_threads(4).build().unwrap();
+//! let val = runtime.block_on(async {
+//!     // spawned onto `runtime`
+//!     task::spawn(async { 1 }).await.unwrap()
+//! });
+//! assert_eq!(1, val);
+//! ```
+//!
+//! A runtime of the [`CurrentThread`] flavor spawns no worker threads, its tasks run on the
+//! thread calling [`block_on`] instead. Such a runtime can also be driven by [`turn`] from a
+//! loop owned by the application, like the main loop of a GUI or a game engine.
+//!
+//! [`task::spawn`]: ../task/fn.spawn.html
+//! [`CurrentThread`]: enum.Flavor.html#variant.CurrentThread
+//! [`block_on`]: struct.Runtime.html#method.block_on
+//! [`turn`]: struct.Runtime.html#method.turn
+//! [`Runtime`]: struct.Runtime.html
+//! [`Builder`]: struct.Builder.html
+//!
+//! # Tracing
+//!
+//! With the `trace-log` feature, the runtime emits [`log`] records of the trace level when a
+//! task is spawned, polled and completed, when the I/O driver wakes up and when timers fire.
+//! The targets are `tio::task`, `tio::reactor` and `tio::timer`, and a poll record tells how
+//! long the poll took. A `tracing-subscriber` collects them as events through the
+//! `tracing-log` bridge.
+//!
+//! The records are plain log messages: there is no span around the poll of a task, and the
+//! ids, names and durations are formatted into the message rather than recorded as fields.
+//!
+//! [`log`]: https://docs.rs/log
+
+mod blocking;
+mod builder;
+pub(crate) mod context;
+mod handle;
+mod metrics;
+mod owned;
+mod threads;
+
+#[cfg(feature = "timer")]
+pub(crate) mod time;
+
+#[cfg(feature = "async-rt")]
+pub(crate) mod pool;
+
+#[cfg(feature = "async-rt")]
+mod watchdog;
+
+#[cfg(all(target_os = "linux", feature = "affinity"))]
+mod affinity;
+
+#[cfg(feature = "prometheus")]
+mod prometheus;
+
+#[cfg(feature = "event-loop")]
+mod reactor_hooks;
+
+#[cfg(all(target_os = "linux", feature = "affinity"))]
+pub use affinity::numa_nodes;
+
+pub use blocking::SpawnError;
+pub use builder::Builder;
+pub use handle::{EnterGuard, Handle};
+pub use metrics::RuntimeMetrics;
+
+#[cfg(feature = "prometheus")]
+pub use prometheus::{Metric, MetricFamily, MetricType};
+
+#[cfg(feature = "event-loop")]
+pub use reactor_hooks::{ReactorHooks, Readiness};
+
+#[cfg(feature = "async-rt")]
+pub use builder::Flavor;
+
+#[cfg(feature = "console")]
+pub(crate) use owned::live_tasks;
+
+use crate::task::{JoinHandle, Tag};
+use blocking::BlockingPool;
+use builder::Config;
+use owned::OwnedTasks;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use threads::Threads;
+
+#[cfg(feature = "async-rt")]
+use crate::task::{tag::Output, AbortHandle};
+
+#[cfg(feature = "async-rt")]
+use pool::Pool;
+
+#[cfg(feature = "async-rt")]
+use watchdog::Watchdog;
+
+#[cfg(all(target_os = "linux", feature = "affinity"))]
+use affinity::Affinity;
+
+#[cfg(feature = "event-loop")]
+use crate::net::poll::Reactor;
+
+#[cfg(feature = "event-loop")]
+use once_cell::sync::OnceCell;
+
+#[cfg(feature = "timer")]
+use time::Timer;
+
+/// The shared state of a runtime.
+pub(crate) struct Inner {
+    pub(crate) config: Config,
+    #[cfg(feature = "async-rt")]
+    pool: Pool,
+    #[cfg(feature = "async-rt")]
+    watchdog: Option<Watchdog>,
+    blocking: BlockingPool,
+    // a reactor per NUMA node of the workers, or a single one
+    #[cfg(feature = "event-loop")]
+    reactors: Vec<OnceCell<Reactor>>,
+    #[cfg(feature = "timer")]
+    timer: Timer,
+    tasks: OwnedTasks,
+    pub(crate) threads: Threads,
+}
+
+impl Inner {
+    fn start(config: Config) -> io::Result<Arc<Self>> {
+        #[cfg(all(target_os = "linux", feature = "affinity"))]
+        let nodes = config.affinity.as_ref().map_or(0, Affinity::nodes);
+        #[cfg(all(
+            feature = "async-rt",
+            not(all(target_os = "linux", feature = "affinity"))
+        ))]
+        let nodes = 0;
+        #[cfg(feature = "async-rt")]
+        let (pool, queues) = Pool::new(config.worker_threads, nodes, config.seed());
+        #[cfg(all(feature = "event-loop", feature = "async-rt"))]
+        let reactors = pool.nodes().max(1);
+        #[cfg(all(feature = "event-loop", not(feature = "async-rt")))]
+        let reactors = 1;
+        let inner = Arc::new(Self {
+            blocking: BlockingPool::new(
+                config.max_blocking_threads,
+                config.blocking_queue_limit,
+                config.thread_keep_alive,
+            ),
+            #[cfg(feature = "async-rt")]
+            pool,
+            #[cfg(feature = "async-rt")]
+            watchdog: config
+                .long_poll_threshold
+                .map(|threshold| Watchdog::new(threshold, config.worker_threads)),
+            #[cfg(feature = "event-loop")]
+            reactors: std::iter::repeat_with(OnceCell::new)
+                .take(reactors)
+                .collect(),
+            #[cfg(feature = "timer")]
+            timer: Timer::new(
+                config.seed().is_some(),
+                config.timer_granularity,
+                config.coarse_clock,
+            ),
+            tasks: OwnedTasks::new(),
+            threads: Threads::new(),
+            config,
+        });
+        #[cfg(feature = "console")]
+        owned::register(&inner);
+        #[cfg(feature = "async-rt")]
+        for (index, (queue, parker)) in queues.into_iter().enumerate() {
+            if let Err(err) = pool::start_worker(&inner, index, queue, parker) {
+                inner.shutdown();
+                return Err(err);
+            }
+        }
+        #[cfg(feature = "async-rt")]
+        if inner.watchdog.is_some() {
+            if let Err(err) = watchdog::start_thread(&inner) {
+                inner.shutdown();
+                return Err(err);
+            }
+        }
+        Ok(inner)
+    }
+
+    #[cfg(feature = "async-rt")]
+    #[track_caller]
+    pub(crate) fn spawn<F, R>(
+        self: &Arc<Self>,
+        name: Option<String>,
+        fut: F,
+    ) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + Future<Output = R>,
+    {
+        let (tag, abort, fut) = Tag::wrap(name, fut);
+        self.spawn_tagged(tag, abort, fut)
+    }
+
+    /// Spawns a task hinted to run on a NUMA node.
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    #[track_caller]
+    pub(crate) fn spawn_on_node<F, R>(
+        self: &Arc<Self>,
+        name: Option<String>,
+        node: usize,
+        fut: F,
+    ) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + Future<Output = R>,
+    {
+        let (mut tag, abort, fut) = Tag::wrap(name, fut);
+        tag.set_node(node);
+        self.spawn_tagged(tag, abort, fut)
+    }
+
+    #[cfg(feature = "async-rt")]
+    fn spawn_tagged<F, R>(
+        self: &Arc<Self>,
+        tag: Tag,
+        abort: AbortHandle,
+        fut: F,
+    ) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + Future<Output = Output<R>>,
+    {
+        let fut = OwnedTasks::bind(self, tag.info(), &abort, fut);
+        let inner = self.clone();
+        let (task, handle) =
+            async_task::spawn(fut, move |t| Pool::schedule(&inner, t), tag);
+        task.schedule();
+        JoinHandle(handle, abort)
+    }
+
+    /// Spawns a blocking task, returning its handle even if it fails to be queued.
+    #[track_caller]
+    fn spawn_blocking_inner<F, R>(
+        self: &Arc<Self>,
+        name: Option<String>,
+        f: F,
+    ) -> (JoinHandle<R>, Result<(), SpawnError>)
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnOnce() -> R,
+    {
+        let (tag, abort, fut) = Tag::wrap(name, async move { f() });
+        let fut = OwnedTasks::bind(self, tag.info(), &abort, fut);
+        let inner = self.clone();
+        let (task, handle) =
+            async_task::spawn(fut, move |t| BlockingPool::schedule(&inner, t), tag);
+        let ret = BlockingPool::push(self, task);
+        (JoinHandle(handle, abort), ret)
+    }
+
+    /// Spawns a blocking task, panicking if the blocking pool is saturated.
+    #[track_caller]
+    pub(crate) fn spawn_blocking<F, R>(
+        self: &Arc<Self>,
+        name: Option<String>,
+        f: F,
+    ) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnOnce() -> R,
+    {
+        match self.spawn_blocking_inner(name, f) {
+            // the task is cancelled already
+            (handle, Ok(()) | Err(SpawnError::Shutdown)) => handle,
+            (_, Err(err)) => panic!("cannot spawn a blocking task: {}", err),
+        }
+    }
+
+    #[track_caller]
+    pub(crate) fn try_spawn_blocking<F, R>(
+        self: &Arc<Self>,
+        name: Option<String>,
+        f: F,
+    ) -> Result<JoinHandle<R>, SpawnError>
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnOnce() -> R,
+    {
+        let (handle, ret) = self.spawn_blocking_inner(name, f);
+        ret.map(|()| handle)
+    }
+
+    /// Spawns a thread of this runtime, named `{thread_name}/{kind}`.
+    ///
+    /// The thread runs `f` within the context of the runtime, between the start and stop hooks,
+    /// and it is counted until it stops.
+    pub(crate) fn spawn_thread<F>(self: &Arc<Self>, kind: &str, f: F) -> io::Result<()>
+    where
+        F: 'static + Send + FnOnce(&Arc<Self>),
+    {
+        self.threads.start();
+        let inner = self.clone();
+        #[cfg(all(target_os = "linux", feature = "affinity"))]
+        let kind_owned = kind.to_string();
+        let ret = self.config.thread(kind).spawn(move || {
+            let _stopped = inner.threads.guard();
+            #[cfg(all(target_os = "linux", feature = "affinity"))]
+            if let Some(affinity) = &inner.config.affinity {
+                affinity.apply(&kind_owned)
+            }
+            let _enter = context::enter(inner.clone());
+            let _hooks = inner.config.hooks.start();
+            f(&inner)
+        });
+        if ret.is_err() {
+            drop(self.threads.guard());
+        }
+        ret.map(drop)
+    }
+
+    /// Returns the reactor of the NUMA node of the current worker, or the first one out of the
+    /// workers, starting it on the first use.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the I/O driver is disabled, or the reactor fails to start.
+    #[cfg(feature = "event-loop")]
+    pub(crate) fn reactor(self: &Arc<Self>) -> Reactor {
+        assert!(
+            self.config.enable_io,
+            "the I/O driver is disabled, enable it by `runtime::Builder::enable_io`"
+        );
+        #[cfg(feature = "async-rt")]
+        let node = Pool::current_node(self).unwrap_or(0);
+        #[cfg(not(feature = "async-rt"))]
+        let node = 0;
+        self.reactors[node]
+            .get_or_try_init(|| {
+                Reactor::start(self, Some(node).filter(|_| self.reactors.len() > 1))
+            })
+            .unwrap_or_else(|err| panic!("fail to start the reactor: {}", err))
+            .clone()
+    }
+
+    /// Returns the started reactors.
+    #[cfg(feature = "event-loop")]
+    #[inline]
+    pub(crate) fn reactors(&self) -> impl Iterator<Item = &Reactor> {
+        self.reactors.iter().filter_map(OnceCell::get)
+    }
+
+    /// Panics if the time driver is disabled.
+    #[cfg(feature = "timer")]
+    #[inline]
+    pub(crate) fn ensure_time(&self) {
+        assert!(
+            self.config.enable_time,
+            "the time driver is disabled, enable it by `runtime::Builder::enable_time`"
+        )
+    }
+
+    /// Returns the instant cached by the coarse clock, if it is enabled.
+    #[cfg(feature = "timer")]
+    #[inline]
+    pub(crate) fn coarse_now(&self) -> Option<std::time::Instant> {
+        self.timer.coarse_now()
+    }
+
+    /// Refreshes the coarse clock, if it is enabled.
+    #[cfg(feature = "timer")]
+    #[inline]
+    pub(crate) fn tick(&self) {
+        self.timer.tick();
+    }
+
+    /// Stops the threads and cancels the tasks, without waiting.
+    fn shutdown(&self) {
+        // before the queued tasks are dropped by the pool
+        #[cfg(feature = "task-dump")]
+        if let Some(report) = &self.config.on_leaked_task {
+            self.tasks.pending().iter().for_each(|task| report(task));
+        }
+        #[cfg(feature = "async-rt")]
+        self.pool.shutdown();
+        #[cfg(feature = "async-rt")]
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.shutdown()
+        }
+        self.blocking.shutdown();
+        self.tasks.close();
+        #[cfg(feature = "event-loop")]
+        for reactor in self.reactors() {
+            reactor.shutdown()
+        }
+        #[cfg(feature = "timer")]
+        self.timer.shutdown();
+    }
+}
+
+/// The tio runtime.
+///
+/// The runtime owns its worker threads and its blocking pool. When it is dropped, it stops
+/// accepting new tasks and cancels the pending ones, their [`JoinHandle`]s complete with a
+/// cancelled [`JoinError`]. The threads stop once their running tasks yield, which is not
+/// waited for; use [`shutdown_timeout`] to wait for them.
+///
+/// [`JoinHandle`]: ../task/struct.JoinHandle.html
+/// [`JoinError`]: ../task/struct.JoinError.html
+/// [`shutdown_timeout`]: #method.shutdown_timeout
+///
+/// # Examples
+///
+/// ```
+/// use tio::runtime::Runtime;
+///
+/// let runtime = Runtime::new().unwrap();
+/// let handle = runtime.spawn(async { 1 + 2 });
+/// assert_eq!(3, runtime.block_on(handle).unwrap());
+/// ```
+pub struct Runtime {
+    handle: Handle,
+}
+
+impl Runtime {
+    /// Creates a runtime with the default configuration.
+    ///
+    /// See also: [`Builder`].
+    ///
+    /// [`Builder`]: struct.Builder.html
+    #[inline]
+    pub fn new() -> io::Result<Self> {
+        Builder::new().build()
+    }
+
+    /// Returns a handle to this runtime.
+    #[inline]
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Returns the metrics of this runtime.
+    ///
+    /// See also: [`Handle::metrics`].
+    ///
+    /// [`Handle::metrics`]: struct.Handle.html#method.metrics
+    #[inline]
+    pub fn metrics(&self) -> RuntimeMetrics {
+        self.handle.metrics()
+    }
+
+    /// Captures the state of the live tasks of this runtime, for debugging hangs.
+    ///
+    /// See also: [`Handle::dump`].
+    ///
+    /// [`Handle::dump`]: struct.Handle.html#method.dump
+    #[cfg(feature = "task-dump")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "task-dump")))]
+    #[inline]
+    pub fn dump(&self) -> crate::task::Dump {
+        self.handle.dump()
+    }
+
+    /// Makes this runtime the context of the current thread until the guard is dropped.
+    ///
+    /// See also: [`Handle::enter`].
+    ///
+    /// [`Handle::enter`]: struct.Handle.html#method.enter
+    #[inline]
+    pub fn enter(&self) -> EnterGuard<'_> {
+        self.handle.enter()
+    }
+
+    /// Runs a future to completion on the current thread, within the context of this runtime.
+    ///
+    /// Tasks spawned by the future go to this runtime. On a runtime of the [`CurrentThread`]
+    /// flavor, this is where they run.
+    ///
+    /// See also: [`task::block_on`].
+    ///
+    /// [`CurrentThread`]: enum.Flavor.html#variant.CurrentThread
+    /// [`task::block_on`]: ../task/fn.block_on.html
+    #[inline]
+    #[track_caller]
+    pub fn block_on<F>(&self, fut: F) -> F::Output
+    where
+        F: Future,
+    {
+        self.handle.block_on(fut)
+    }
+
+    /// Runs the tasks of this runtime which are ready, without owning the current thread.
+    ///
+    /// If no task is ready, this method waits for one to be woken for at most `max_wait`, or
+    /// without limit if `max_wait` is `None`. It returns whether any task ran, which can be
+    /// `false` once the wait is over.
+    ///
+    /// This lets an application embed the runtime into a loop it already runs, calling `turn`
+    /// on each iteration instead of handing the thread over to [`block_on`]. The I/O and the
+    /// time drivers keep running on their own threads, waking the tasks for the next turn.
+    ///
+    /// [`block_on`]: #method.block_on
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the runtime is not of the [`CurrentThread`] flavor, whose tasks
+    /// run on the worker threads instead.
+    ///
+    /// [`CurrentThread`]: enum.Flavor.html#variant.CurrentThread
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use tio::runtime::Builder;
+    ///
+    /// let runtime = Builder::new_current_thread().build().unwrap();
+    /// let done = Arc::new(AtomicBool::new(false));
+    /// let flag = done.clone();
+    /// runtime.spawn(async move { flag.store(true, Ordering::SeqCst) });
+    /// while !done.load(Ordering::SeqCst) {
+    ///     // handle the events of the application
+    ///     runtime.turn(Some(Duration::from_millis(16)));
+    /// }
+    /// ```
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    pub fn turn(&self, max_wait: Option<Duration>) -> bool {
+        let inner = &self.handle.inner;
+        assert_eq!(
+            Flavor::CurrentThread,
+            inner.config.flavor,
+            "only a current-thread runtime can be turned"
+        );
+        let _enter = self.enter();
+        Pool::turn(inner, max_wait)
+    }
+
+    /// Returns the flavor of this runtime.
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn flavor(&self) -> Flavor {
+        self.handle.flavor()
+    }
+
+    /// Spawns a task onto this runtime.
+    ///
+    /// See also: [`task::spawn`].
+    ///
+    /// [`task::spawn`]: ../task/fn.spawn.html
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[track_caller]
+    #[inline]
+    pub fn spawn<F, R>(&self, fut: F) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + Future<Output = R>,
+    {
+        self.handle.spawn(fut)
+    }
+
+    /// Spawns a blocking task onto the blocking pool of this runtime.
+    ///
+    /// See also: [`task::spawn_blocking`].
+    ///
+    /// [`task::spawn_blocking`]: ../task/fn.spawn_blocking.html
+    #[track_caller]
+    #[inline]
+    pub fn spawn_blocking<F, R>(&self, f: F) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnOnce() -> R,
+    {
+        self.handle.spawn_blocking(f)
+    }
+
+    /// Shuts the runtime down, waiting at most `timeout` for its threads to stop.
+    ///
+    /// New tasks are cancelled at once, and so are the pending tasks. A task which is running
+    /// stops at its next await point, but a blocking task cannot be interrupted, so a thread
+    /// which is still busy when the timeout elapses is left running in the background.
+    ///
+    /// Calling this method on a thread of the runtime always waits for the whole timeout, as
+    /// that thread cannot stop meanwhile.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use tio::runtime::Runtime;
+    ///
+    /// let runtime = Runtime::new().unwrap();
+    /// let handle = runtime.spawn(futures::future::pending::<()>());
+    /// runtime.shutdown_timeout(Duration::from_secs(1));
+    /// assert!(tio::task::block_on(handle).unwrap_err().is_cancelled());
+    /// ```
+    pub fn shutdown_timeout(self, timeout: Duration) {
+        self.handle.inner.shutdown();
+        if !self.handle.inner.threads.join(timeout) {
+            log::warn!(
+                "runtime threads are still running after the shutdown timeout of {:?}",
+                timeout
+            )
+        }
+    }
+
+    /// Shuts the runtime down without waiting for its threads to stop.
+    ///
+    /// This is the same as dropping the runtime, see [`shutdown_timeout`].
+    ///
+    /// [`shutdown_timeout`]: #method.shutdown_timeout
+    #[inline]
+    pub fn shutdown_background(self) {
+        drop(self)
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        self.handle.inner.shutdown()
+    }
+}
+
+impl Debug for Runtime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad("Runtime { .. }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Builder, SpawnError};
+    use crate::task;
+    use futures::future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc, Barrier};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn spawn_in_context() {
+        let runtime = Builder::new()
+            .worker_threads(2)
+            .thread_name("rt-test")
+            .build()
+            .unwrap();
+        let name = runtime.block_on(async {
+            task::spawn(async { thread::current().name().map(ToString::to_string) })
+                .await
+                .unwrap()
+        });
+        assert!(name.unwrap().starts_with("rt-test/async"));
+    }
+
+    #[test]
+    fn spawn_blocking() {
+        let runtime = Builder::new().thread_name("rt-blocking").build().unwrap();
+        let handle = runtime.spawn_blocking(|| {
+            let name = thread::current().name().map(ToString::to_string);
+            // nested tasks go to the same runtime
+            let nested = task::spawn_blocking(|| {
+                thread::current().name().map(ToString::to_string)
+            });
+            (name, task::block_on(nested).unwrap())
+        });
+        let (name, nested) = runtime.block_on(handle).unwrap();
+        assert_eq!(Some("rt-blocking/blocking"), name.as_deref());
+        assert_eq!(name, nested);
+    }
+
+    #[test]
+    fn current_thread() {
+        let runtime = Builder::new_current_thread()
+            .thread_name("rt-current")
+            .build()
+            .unwrap();
+        let caller = thread::current().id();
+        let ids = runtime.block_on(async {
+            let handles = (0..10)
+                .map(|_| {
+                    task::spawn(async {
+                        task::yield_now().await;
+                        thread::current().id()
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut ids = Vec::new();
+            for handle in handles {
+                ids.push(handle.await.unwrap());
+            }
+            ids
+        });
+        assert!(ids.into_iter().all(|id| id == caller));
+        // tasks spawned outside of `block_on` wait for the next call
+        let handle = runtime.spawn(async { 1 });
+        assert_eq!(1, runtime.block_on(handle).unwrap());
+    }
+
+    #[test]
+    fn turn() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        assert!(!runtime.turn(Some(Duration::from_millis(1))));
+        let (sender, receiver) = mpsc::channel();
+        let handle = runtime.spawn(async move {
+            task::yield_now().await;
+            sender.send(1).unwrap();
+        });
+        assert!(runtime.turn(None));
+        assert_eq!(1, receiver.try_recv().unwrap());
+        assert!(runtime.block_on(handle).is_ok());
+        // a task woken from another thread ends the wait
+        let (wake, woken) = futures::channel::oneshot::channel::<()>();
+        let handle = runtime.spawn(woken);
+        assert!(runtime.turn(None));
+        thread::spawn(move || wake.send(()).unwrap());
+        while !handle.is_finished() {
+            runtime.turn(None);
+        }
+    }
+
+    #[cfg(feature = "task-dump")]
+    #[test]
+    fn leaked_tasks() {
+        let leaked = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let runtime = {
+            let leaked = leaked.clone();
+            Builder::new()
+                .on_leaked_task(move |task| {
+                    leaked.lock().unwrap().push(task.name().map(String::from))
+                })
+                .build()
+                .unwrap()
+        };
+        let finished = runtime.spawn(async {});
+        while !finished.is_finished() {
+            thread::yield_now();
+        }
+        task::Builder::new()
+            .name("pending")
+            .spawn_on_handle(runtime.handle(), future::pending::<()>())
+            .detach();
+        runtime.shutdown_timeout(Duration::from_secs(1));
+        assert_eq!(vec![Some("pending".to_string())], *leaked.lock().unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn turn_multi_thread() {
+        Builder::new().build().unwrap().turn(None);
+    }
+
+    #[test]
+    fn shutdown_timeout() {
+        let runtime = Builder::new().worker_threads(2).build().unwrap();
+        let pending = runtime.spawn(future::pending::<()>());
+        let (sender, receiver) = mpsc::channel();
+        let blocking = runtime.spawn_blocking(move || {
+            sender.send(()).unwrap();
+            thread::sleep(Duration::from_millis(50))
+        });
+        receiver.recv().unwrap();
+        let runtime_handle = runtime.handle.inner.clone();
+        runtime.shutdown_timeout(Duration::from_secs(5));
+        assert!(task::block_on(pending).unwrap_err().is_cancelled());
+        assert!(task::block_on(blocking).is_ok());
+        assert!(runtime_handle.threads.join(Duration::from_secs(0)));
+        // new tasks are cancelled
+        let handle = runtime_handle.spawn(None, async {});
+        assert!(task::block_on(handle).unwrap_err().is_cancelled());
+    }
+
+    #[test]
+    fn shutdown_background() {
+        let runtime = Builder::new().build().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let blocking = runtime.spawn_blocking(move || {
+            sender.send(()).unwrap();
+            thread::sleep(Duration::from_millis(200))
+        });
+        receiver.recv().unwrap();
+        let inner = runtime.handle.inner.clone();
+        runtime.shutdown_background();
+        assert!(!inner.threads.join(Duration::from_secs(0)));
+        drop(blocking);
+    }
+
+    #[test]
+    fn blocking_keep_alive() {
+        let runtime = Builder::new()
+            .thread_keep_alive(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let metrics = runtime.metrics();
+        let barrier = Arc::new(Barrier::new(5));
+        let tasks = (0..4)
+            .map(|_| {
+                let barrier = barrier.clone();
+                runtime.spawn_blocking(move || {
+                    barrier.wait();
+                    barrier.wait();
+                })
+            })
+            .collect::<Vec<_>>();
+        barrier.wait();
+        assert!(metrics.num_blocking_threads() >= 4);
+        barrier.wait();
+        for task in tasks {
+            runtime.block_on(task).unwrap();
+        }
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(1, metrics.num_blocking_threads());
+    }
+
+    #[test]
+    fn zero_keep_alive() {
+        let runtime = Builder::new()
+            .thread_name("keep-alive")
+            .thread_keep_alive(Duration::ZERO)
+            .build()
+            .unwrap();
+        runtime.block_on(runtime.spawn_blocking(|| ())).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(1, runtime.metrics().num_blocking_threads());
+        // the last thread waits for a task instead of spinning
+        #[cfg(target_os = "linux")]
+        {
+            let cpu_time = || {
+                std::fs::read_dir("/proc/self/task")
+                    .unwrap()
+                    .filter_map(|entry| {
+                        let path = entry.ok()?.path();
+                        let comm = std::fs::read_to_string(path.join("comm")).ok()?;
+                        if comm.trim_end() != "keep-alive/bloc" {
+                            return None;
+                        }
+                        let stat = std::fs::read_to_string(path.join("stat")).ok()?;
+                        // utime and stime, after the state following the name
+                        let fields = stat.rsplit(')').next()?.split_whitespace();
+                        Some(
+                            fields
+                                .skip(11)
+                                .take(2)
+                                .map(|ticks| ticks.parse::<u64>().unwrap())
+                                .sum::<u64>(),
+                        )
+                    })
+                    .sum::<u64>()
+            };
+            let start = cpu_time();
+            thread::sleep(Duration::from_millis(200));
+            assert!(cpu_time() - start < 5);
+        }
+        assert_eq!(1, runtime.block_on(runtime.spawn_blocking(|| 1)).unwrap());
+    }
+
+    #[test]
+    fn try_spawn_blocking_after_shutdown() {
+        let runtime = Builder::new().build().unwrap();
+        let handle = runtime.handle().clone();
+        drop(runtime);
+        assert_eq!(
+            SpawnError::Shutdown,
+            handle.try_spawn_blocking(|| ()).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn park_hooks() {
+        let parks = Arc::new(AtomicUsize::new(0));
+        let unparks = Arc::new(AtomicUsize::new(0));
+        let runtime = Builder::new()
+            .worker_threads(1)
+            .on_thread_park({
+                let parks = parks.clone();
+                move || {
+                    parks.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+            .on_thread_unpark({
+                let unparks = unparks.clone();
+                move || {
+                    unparks.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+            .build()
+            .unwrap();
+        for _ in 0..3 {
+            runtime.block_on(runtime.spawn(async {})).unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+        runtime.shutdown_timeout(Duration::from_secs(5));
+        assert!(parks.load(Ordering::SeqCst) >= 1);
+        assert_eq!(parks.load(Ordering::SeqCst), unparks.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    #[cfg(feature = "tcp")]
+    fn isolated_reactor() {
+        use crate::net::{TcpListener, TcpStream};
+        let first = Builder::new().thread_name("first").build().unwrap();
+        let listener = first.block_on(async { TcpListener::bind("127.0.0.1:0") });
+        let listener = listener.unwrap();
+        first.shutdown_timeout(Duration::from_secs(5));
+        // the reactor of the first runtime is stopped, but not the one of the second
+        let second = Builder::new().thread_name("second").build().unwrap();
+        second.block_on(async {
+            let server = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = server.local_addr().unwrap();
+            let client = task::spawn(TcpStream::connect(addr));
+            server.accept().await.unwrap();
+            client.await.unwrap().unwrap();
+        });
+        drop(listener);
+    }
+
+    #[test]
+    #[cfg(feature = "timer")]
+    #[should_panic]
+    fn time_disabled() {
+        let runtime = Builder::new().enable_time(false).build().unwrap();
+        runtime.block_on(task::sleep(std::time::Duration::from_millis(1)));
+    }
+}
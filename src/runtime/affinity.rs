@@ -0,0 +1,332 @@
+use rustix::thread::{self, CpuSet};
+use std::fs;
+use std::io;
+
+/// The directory of the NUMA nodes in sysfs.
+const NODES_DIR: &str = "/sys/devices/system/node";
+
+/// Returns the CPUs of each NUMA node of the machine, for [`Builder::numa_nodes`].
+///
+/// The nodes without CPUs, which only have memory, are skipped. The others are in the order of
+/// their ids, so the index of a node is its id unless such a node precedes it.
+///
+/// [`Builder::numa_nodes`]: struct.Builder.html#method.numa_nodes
+///
+/// # Errors
+///
+/// This function fails if the topology cannot be read from sysfs.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tio::runtime::{self, Builder};
+///
+/// let runtime = Builder::new()
+///     .numa_nodes(runtime::numa_nodes().unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+#[cfg_attr(
+    feature = "docs",
+    doc(cfg(all(target_os = "linux", feature = "affinity")))
+)]
+pub fn numa_nodes() -> io::Result<Vec<Vec<usize>>> {
+    let mut nodes = Vec::new();
+    for entry in fs::read_dir(NODES_DIR)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let id = match name.to_str().and_then(|name| name.strip_prefix("node")) {
+            Some(id) => match id.parse::<usize>() {
+                Ok(id) => id,
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+        let cpus = parse_cpu_list(&fs::read_to_string(entry.path().join("cpulist"))?)?;
+        if !cpus.is_empty() {
+            nodes.push((id, cpus));
+        }
+    }
+    nodes.sort_unstable();
+    Ok(nodes.into_iter().map(|(_, cpus)| cpus).collect())
+}
+
+/// Parses a CPU list of the kernel, like `0-3,8-11`.
+fn parse_cpu_list(list: &str) -> io::Result<Vec<usize>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid CPU list");
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start, end),
+            None => (range, range),
+        };
+        let start = start.parse::<usize>().map_err(|_| invalid())?;
+        let end = end.parse::<usize>().map_err(|_| invalid())?;
+        if start > end {
+            return Err(invalid());
+        }
+        cpus.extend(start..=end);
+    }
+    Ok(cpus)
+}
+
+/// The CPU affinity of the threads of a runtime.
+///
+/// A thread inherits the affinity of the thread spawning it, so the threads which are not
+/// pinned are reset to the CPUs of the process, as they were when the runtime was built.
+///
+/// A worker thread pinned to no core runs on the CPUs of its NUMA node, if any, and so does the
+/// poll thread of the reactor of a node.
+pub(crate) struct Affinity {
+    workers: Vec<usize>,
+    reactor: Option<usize>,
+    nodes: Vec<CpuSet>,
+    default: CpuSet,
+}
+
+impl Affinity {
+    pub(crate) fn new(
+        workers: Vec<usize>,
+        reactor: Option<usize>,
+        nodes: Vec<Vec<usize>>,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            workers,
+            reactor,
+            nodes: nodes.iter().map(|cpus| cpu_set(cpus)).collect(),
+            default: thread::sched_getaffinity(None)?,
+        })
+    }
+
+    /// Returns the number of NUMA nodes the workers are partitioned into.
+    #[inline]
+    pub(crate) fn nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the CPUs a worker thread is pinned to.
+    fn worker(&self, index: usize) -> Option<CpuSet> {
+        match (self.workers.len(), self.nodes.len()) {
+            (0, 0) => None,
+            (0, nodes) => Some(self.nodes[index % nodes]),
+            (len, _) => Some(cpu_set(&[self.workers[index % len]])),
+        }
+    }
+
+    /// Sets the affinity of the current thread, a thread of the kind given to
+    /// `Inner::spawn_thread`.
+    pub(crate) fn apply(&self, kind: &str) {
+        let cpus = match kind {
+            "poll" => self.reactor.map(|core| cpu_set(&[core])),
+            // the reactor of a node, the first one may be pinned
+            _ if kind.starts_with("poll") => kind
+                .strip_prefix("poll")
+                .and_then(|node| node.parse::<usize>().ok())
+                .and_then(|node| match self.reactor {
+                    Some(core) if node == 0 => Some(cpu_set(&[core])),
+                    _ => self.nodes.get(node).copied(),
+                }),
+            _ => kind
+                .strip_prefix("async")
+                .and_then(|index| index.parse().ok())
+                .and_then(|index| self.worker(index)),
+        };
+        let ret =
+            thread::sched_setaffinity(None, cpus.as_ref().unwrap_or(&self.default));
+        // a core may be offline or out of the cgroup, the thread runs unpinned then
+        if let Err(_err) = ret {
+            trace_event!(target: "tio::task", "cannot pin the {} thread: {}", kind, _err);
+        }
+    }
+}
+
+#[inline]
+fn cpu_set(cpus: &[usize]) -> CpuSet {
+    let mut set = CpuSet::new();
+    cpus.iter().for_each(|&cpu| set.set(cpu));
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_cpu_list;
+    use crate::runtime::Builder;
+    use crate::task;
+    use rustix::thread::{self, CpuSet};
+    use std::io;
+
+    /// Returns the first CPU the current thread can run on.
+    fn first_cpu() -> usize {
+        let cpus = thread::sched_getaffinity(None).unwrap();
+        (0..CpuSet::MAX_CPU).find(|&cpu| cpus.is_set(cpu)).unwrap()
+    }
+
+    #[test]
+    fn workers() {
+        let core = first_cpu();
+        let before = thread::sched_getaffinity(None).unwrap().count();
+        let runtime = Builder::new()
+            .worker_threads(2)
+            .pin_workers([core])
+            .build()
+            .unwrap();
+        let (worker, blocking) = runtime.block_on(async {
+            task::spawn(async {
+                let worker = thread::sched_getaffinity(None).unwrap();
+                // spawned from a pinned worker, but not pinned
+                let blocking = task::spawn_blocking(|| {
+                    thread::sched_getaffinity(None).unwrap().count()
+                })
+                .await
+                .unwrap();
+                (worker, blocking)
+            })
+            .await
+            .unwrap()
+        });
+        assert_eq!(1, worker.count());
+        assert!(worker.is_set(core));
+        assert_eq!(before, blocking);
+    }
+
+    #[test]
+    fn cpu_list() -> io::Result<()> {
+        assert_eq!(
+            vec![0, 1, 2, 3, 8, 10, 11],
+            parse_cpu_list("0-3,8,10-11\n")?
+        );
+        assert!(parse_cpu_list("\n")?.is_empty());
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn numa_nodes() {
+        let cpus = thread::sched_getaffinity(None).unwrap();
+        let cpus = (0..CpuSet::MAX_CPU)
+            .filter(|&cpu| cpus.is_set(cpu))
+            .collect::<Vec<_>>();
+        let nodes = vec![vec![cpus[0]], vec![cpus[cpus.len() - 1]]];
+        let runtime = Builder::new()
+            .worker_threads(4)
+            .numa_nodes(nodes.clone())
+            .build()
+            .unwrap();
+        let ran = runtime.block_on(async {
+            let handles = (0..8)
+                .map(|i| {
+                    task::Builder::new().numa_node(i).spawn(async {
+                        task::yield_now().await;
+                        let name = std::thread::current().name().unwrap().to_string();
+                        let cpus = thread::sched_getaffinity(None).unwrap();
+                        (name, cpus)
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut ran = Vec::new();
+            for handle in handles {
+                ran.push(handle.await.unwrap());
+            }
+            ran
+        });
+        for (name, cpus) in ran {
+            // the worker `i` runs on the CPUs of the node `i % 2`
+            let index = name
+                .strip_prefix("tio/async")
+                .unwrap()
+                .parse::<usize>()
+                .unwrap();
+            assert_eq!(1, cpus.count());
+            assert!(cpus.is_set(nodes[index % 2][0]));
+        }
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn node_reactors() {
+        use crate::net::TcpListener;
+        use crate::runtime::pool::Pool;
+
+        let cpus = thread::sched_getaffinity(None).unwrap();
+        let cpus = (0..CpuSet::MAX_CPU)
+            .filter(|&cpu| cpus.is_set(cpu))
+            .collect::<Vec<_>>();
+        let nodes = vec![vec![cpus[0]], vec![cpus[cpus.len() - 1]]];
+        let runtime = Builder::new()
+            .worker_threads(2)
+            .thread_name("numa-io")
+            .numa_nodes(nodes.clone())
+            .build()
+            .unwrap();
+        let inner = runtime.handle().inner.clone();
+        let registered = runtime.block_on(async {
+            let mut registered = Vec::new();
+            for node in 0..2 {
+                let inner = inner.clone();
+                let handle = task::Builder::new().numa_node(node).spawn(async move {
+                    task::yield_now().await;
+                    // an idle worker may have stolen the task from the other node
+                    let node = Pool::current_node(&inner).unwrap();
+                    (node, TcpListener::bind("127.0.0.1:0").unwrap())
+                });
+                registered.push(handle.await.unwrap());
+            }
+            registered
+        });
+        // each listener is registered to the reactor of its node, polled on the node
+        let mut used = registered.iter().map(|(node, _)| *node).collect::<Vec<_>>();
+        used.sort_unstable();
+        used.dedup();
+        assert_eq!(used.len(), inner.reactors().count());
+        assert_eq!(2, runtime.metrics().num_io_registrations());
+        // the poll threads name and pin themselves once they start
+        let pinned = |node: usize, cpus: &CpuSet| {
+            cpus.count() == 1 && cpus.is_set(nodes[node][0])
+        };
+        let poll_threads = || {
+            let mut threads = Vec::new();
+            for entry in std::fs::read_dir("/proc/self/task").unwrap() {
+                let path = entry.unwrap().path();
+                let comm =
+                    std::fs::read_to_string(path.join("comm")).unwrap_or_default();
+                let node =
+                    match comm.trim_end().strip_prefix("numa-io/poll").map(str::parse) {
+                        Some(Ok::<usize, _>(node)) => node,
+                        _ => continue,
+                    };
+                let tid = path.file_name().unwrap().to_str().unwrap().parse().unwrap();
+                if let Ok(cpus) = thread::sched_getaffinity(thread::Pid::from_raw(tid)) {
+                    threads.push((node, cpus));
+                }
+            }
+            threads.sort_unstable_by_key(|(node, _)| *node);
+            threads
+        };
+        let mut threads = poll_threads();
+        for _ in 0..500 {
+            if threads
+                .iter()
+                .map(|(node, _)| *node)
+                .eq(used.iter().copied())
+                && threads.iter().all(|(node, cpus)| pinned(*node, cpus))
+            {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            threads = poll_threads();
+        }
+        assert_eq!(
+            used,
+            threads.iter().map(|(node, _)| *node).collect::<Vec<_>>()
+        );
+        assert!(threads.iter().all(|(node, cpus)| pinned(*node, cpus)));
+        drop(registered);
+    }
+
+    #[test]
+    #[should_panic(expected = "no core to pin the workers to")]
+    fn no_core() {
+        let _ = Builder::new().pin_workers(Vec::new());
+    }
+}
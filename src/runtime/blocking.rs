@@ -0,0 +1,211 @@
+use super::Inner;
+use crate::task::{tag, Task};
+use crossbeam_channel::{bounded, select, unbounded, Receiver, Sender, TrySendError};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// An error returned by [`try_spawn_blocking`] when a blocking task cannot be spawned.
+///
+/// [`try_spawn_blocking`]: ../task/fn.try_spawn_blocking.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SpawnError {
+    /// Every thread of the blocking pool is busy and its queue is full.
+    ///
+    /// See [`Builder::max_blocking_threads`] and [`Builder::blocking_queue_limit`].
+    ///
+    /// [`Builder::max_blocking_threads`]: struct.Builder.html#method.max_blocking_threads
+    /// [`Builder::blocking_queue_limit`]: struct.Builder.html#method.blocking_queue_limit
+    Saturated,
+
+    /// The runtime is shut down.
+    Shutdown,
+}
+
+impl Display for SpawnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SpawnError::Saturated => "the blocking pool is saturated",
+            SpawnError::Shutdown => "the runtime is shut down",
+        })
+    }
+}
+
+impl Error for SpawnError {}
+
+impl From<SpawnError> for io::Error {
+    #[inline]
+    fn from(err: SpawnError) -> Self {
+        let kind = match err {
+            SpawnError::Saturated => io::ErrorKind::WouldBlock,
+            SpawnError::Shutdown => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+/// A thread pool for blocking tasks, which grows on demand up to a limit.
+pub(crate) struct BlockingPool {
+    sender: Sender<Task>,
+    receiver: Receiver<Task>,
+    // dropped on shutdown, to wake the idle threads by disconnecting `stopped`
+    stop: Mutex<Option<Sender<()>>>,
+    stopped: Receiver<()>,
+    free_threads: AtomicUsize,
+    threads: AtomicUsize,
+    max_threads: usize,
+    keep_alive: Duration,
+    started: AtomicBool,
+    shutdown: AtomicBool,
+}
+
+impl BlockingPool {
+    pub(crate) fn new(
+        max_threads: usize,
+        queue_limit: Option<usize>,
+        keep_alive: Duration,
+    ) -> Self {
+        let (sender, receiver) = match queue_limit {
+            Some(limit) => bounded(limit),
+            None => unbounded(),
+        };
+        let (stop, stopped) = unbounded();
+        Self {
+            sender,
+            receiver,
+            stop: Mutex::new(Some(stop)),
+            stopped,
+            free_threads: AtomicUsize::new(0),
+            threads: AtomicUsize::new(0),
+            max_threads,
+            keep_alive,
+            started: AtomicBool::new(false),
+            shutdown: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the number of live threads.
+    #[inline]
+    pub(crate) fn threads(&self) -> usize {
+        self.threads.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of threads waiting for a task.
+    #[inline]
+    pub(crate) fn idle_threads(&self) -> usize {
+        self.free_threads.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of tasks waiting for a thread.
+    #[inline]
+    pub(crate) fn queue_depth(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// Queues a task, starting the first thread of the pool if necessary.
+    ///
+    /// The task is dropped if it cannot be queued.
+    #[inline]
+    pub(crate) fn push(inner: &Arc<Inner>, task: Task) -> Result<(), SpawnError> {
+        let pool = &inner.blocking;
+        if pool.shutdown.load(Ordering::Acquire) {
+            return Err(SpawnError::Shutdown);
+        }
+        if !pool.started.swap(true, Ordering::SeqCst) {
+            start_thread(inner);
+        }
+        match pool.sender.try_send(task) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(SpawnError::Saturated),
+            Err(TrySendError::Disconnected(_)) => {
+                unreachable!("blocking queue should not be disconnected")
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn schedule(inner: &Arc<Inner>, task: Task) {
+        let _ = Self::push(inner, task);
+    }
+
+    /// Stops the idle threads and drops the queued tasks.
+    pub(crate) fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Release);
+        self.stop.lock().expect("stop lock poisoned").take();
+        while let Ok(task) = self.receiver.try_recv() {
+            drop(task);
+        }
+    }
+}
+
+fn start_thread(inner: &Arc<Inner>) {
+    let pool = &inner.blocking;
+    // no more threads, tasks will wait in the queue until a thread is free
+    if pool.threads.fetch_add(1, Ordering::SeqCst) >= pool.max_threads {
+        pool.threads.fetch_sub(1, Ordering::SeqCst);
+        return;
+    }
+
+    let ret = inner.spawn_thread("blocking", |inner| {
+        let pool = &inner.blocking;
+        pool.free_threads.fetch_add(1, Ordering::SeqCst);
+        // the last free thread waits without a timeout, it never stops anyway
+        let mut last = false;
+        loop {
+            let result = if last {
+                select! {
+                    recv(pool.receiver) -> task => task.ok(),
+                    recv(pool.stopped) -> _ => None,
+                }
+            } else {
+                select! {
+                    recv(pool.receiver) -> task => task.ok(),
+                    recv(pool.stopped) -> _ => None,
+                    default(pool.keep_alive) => None,
+                }
+            };
+            if pool.shutdown.load(Ordering::Acquire) {
+                break;
+            }
+            let mut task = match result {
+                Some(task) => task,
+                None => {
+                    if pool.free_threads.fetch_sub(1, Ordering::SeqCst) == 1 {
+                        pool.free_threads.fetch_add(1, Ordering::SeqCst);
+                        last = true;
+                        continue;
+                    }
+                    // stop thread
+                    break;
+                }
+            };
+
+            last = false;
+            if pool.free_threads.fetch_sub(1, Ordering::SeqCst) == 1 {
+                start_thread(inner)
+            }
+
+            loop {
+                tag::run(task);
+                task = match pool.receiver.try_recv() {
+                    Ok(t) => t,
+                    Err(_) => break,
+                }
+            }
+
+            if pool.free_threads.load(Ordering::SeqCst) > 0 {
+                break;
+            }
+
+            pool.free_threads.fetch_add(1, Ordering::SeqCst);
+        }
+        pool.threads.fetch_sub(1, Ordering::SeqCst);
+    });
+    if ret.is_err() {
+        inner.blocking.threads.fetch_sub(1, Ordering::SeqCst);
+        panic!("cannot start a blocking thread");
+    }
+}
@@ -0,0 +1,1032 @@
+#[cfg(all(target_os = "linux", feature = "affinity"))]
+use super::affinity::Affinity;
+use super::{Handle, Inner, Runtime};
+use crate::net::CachingResolver;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "event-loop")]
+use super::ReactorHooks;
+#[cfg(feature = "task-dump")]
+use crate::task::TaskInfo;
+
+/// The default prefix of thread names.
+const THREAD_NAME: &str = "tio";
+
+/// The default max number of blocking threads.
+const MAX_BLOCKING_THREADS: usize = 512;
+
+/// The default time a blocking thread stays idle before it stops.
+const THREAD_KEEP_ALIVE: Duration = Duration::from_secs(1);
+
+/// The flavor of a runtime, how it runs async tasks.
+#[cfg(feature = "async-rt")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Flavor {
+    /// All tasks run on the thread calling [`Runtime::block_on`], no worker thread is spawned.
+    ///
+    /// Tasks make progress only while some thread is blocked on the runtime, so they are
+    /// never run in parallel.
+    ///
+    /// [`Runtime::block_on`]: struct.Runtime.html#method.block_on
+    CurrentThread,
+
+    /// Tasks run on a pool of work-stealing worker threads.
+    MultiThread,
+}
+
+/// A callback run on a thread of the runtime.
+type Callback = Arc<dyn Fn() + Send + Sync>;
+
+#[cfg(feature = "task-dump")]
+pub(crate) type LeakCallback = Arc<dyn Fn(&TaskInfo) + Send + Sync>;
+
+/// The callbacks run on the threads of a runtime.
+#[derive(Default, Clone)]
+pub(crate) struct Hooks {
+    on_thread_start: Option<Callback>,
+    on_thread_stop: Option<Callback>,
+    on_thread_park: Option<Callback>,
+    on_thread_unpark: Option<Callback>,
+}
+
+/// Runs the stop hook when dropped.
+pub(crate) struct StopGuard<'a>(&'a Hooks);
+
+impl Drop for StopGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(f) = &self.0.on_thread_stop {
+            f()
+        }
+    }
+}
+
+impl Hooks {
+    /// Runs the start hook, returning a guard which runs the stop hook.
+    #[inline]
+    pub(crate) fn start(&self) -> StopGuard<'_> {
+        if let Some(f) = &self.on_thread_start {
+            f()
+        }
+        StopGuard(self)
+    }
+
+    #[cfg(feature = "async-rt")]
+    #[inline]
+    pub(crate) fn park(&self) {
+        if let Some(f) = &self.on_thread_park {
+            f()
+        }
+    }
+
+    #[cfg(feature = "async-rt")]
+    #[inline]
+    pub(crate) fn unpark(&self) {
+        if let Some(f) = &self.on_thread_unpark {
+            f()
+        }
+    }
+}
+
+impl Debug for Hooks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("on_thread_start", &self.on_thread_start.is_some())
+            .field("on_thread_stop", &self.on_thread_stop.is_some())
+            .field("on_thread_park", &self.on_thread_park.is_some())
+            .field("on_thread_unpark", &self.on_thread_unpark.is_some())
+            .finish()
+    }
+}
+
+/// The resolved configuration of a runtime.
+pub(crate) struct Config {
+    #[cfg(feature = "async-rt")]
+    pub(crate) flavor: Flavor,
+    #[cfg(feature = "async-rt")]
+    pub(crate) worker_threads: usize,
+    #[cfg(feature = "async-rt")]
+    pub(crate) lifo_slot: bool,
+    #[cfg(feature = "async-rt")]
+    pub(crate) long_poll_threshold: Option<Duration>,
+    #[cfg(feature = "async-rt")]
+    pub(crate) busy_poll: Option<Duration>,
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    pub(crate) affinity: Option<Affinity>,
+    #[cfg(feature = "test-util")]
+    pub(crate) seed: Option<u64>,
+    pub(crate) thread_name: String,
+    pub(crate) thread_stack_size: Option<usize>,
+    #[cfg(feature = "event-loop")]
+    pub(crate) enable_io: bool,
+    #[cfg(feature = "event-loop")]
+    pub(crate) reactor_hooks: Option<Arc<dyn ReactorHooks>>,
+    #[cfg(feature = "event-loop")]
+    pub(crate) io_capacity: usize,
+    #[cfg(feature = "timer")]
+    pub(crate) enable_time: bool,
+    #[cfg(feature = "timer")]
+    pub(crate) timer_granularity: Duration,
+    #[cfg(feature = "timer")]
+    pub(crate) coarse_clock: bool,
+    pub(crate) max_blocking_threads: usize,
+    pub(crate) blocking_queue_limit: Option<usize>,
+    pub(crate) thread_keep_alive: Duration,
+    pub(crate) hooks: Hooks,
+    #[cfg(feature = "task-dump")]
+    pub(crate) on_leaked_task: Option<LeakCallback>,
+    pub(crate) resolver: CachingResolver,
+}
+
+impl Config {
+    /// Returns the seed of a deterministic runtime.
+    #[cfg(any(feature = "async-rt", feature = "timer"))]
+    #[inline]
+    pub(crate) fn seed(&self) -> Option<u64> {
+        #[cfg(feature = "test-util")]
+        return self.seed;
+        #[cfg(not(feature = "test-util"))]
+        None
+    }
+
+    /// Returns a builder for a thread of this runtime, named `{thread_name}/{kind}`.
+    #[inline]
+    pub(crate) fn thread(&self, kind: &str) -> std::thread::Builder {
+        let builder =
+            std::thread::Builder::new().name(format!("{}/{}", self.thread_name, kind));
+        match self.thread_stack_size {
+            Some(size) => builder.stack_size(size),
+            None => builder,
+        }
+    }
+}
+
+/// Builds a [`Runtime`] with custom configuration values.
+///
+/// Methods can be chained on it in order to configure it, then [`build`] creates the runtime.
+///
+/// [`Runtime`]: struct.Runtime.html
+/// [`build`]: #method.build
+///
+/// # Examples
+///
+/// ```
+/// use tio::runtime::Builder;
+///
+/// let runtime = Builder::new()
+///     .worker_threads(2)
+///     .thread_name("my-app")
+///     .thread_stack_size(4 * 1024 * 1024)
+///     .max_blocking_threads(16)
+///     .build()
+///     .unwrap();
+/// assert_eq!(1, runtime.block_on(async { 1 }));
+/// ```
+pub struct Builder {
+    #[cfg(feature = "async-rt")]
+    flavor: Flavor,
+    #[cfg(feature = "async-rt")]
+    worker_threads: Option<usize>,
+    #[cfg(feature = "async-rt")]
+    lifo_slot: bool,
+    #[cfg(feature = "async-rt")]
+    long_poll_threshold: Option<Duration>,
+    #[cfg(feature = "async-rt")]
+    busy_poll: Option<Duration>,
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    worker_cores: Vec<usize>,
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    reactor_core: Option<usize>,
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    numa_nodes: Vec<Vec<usize>>,
+    #[cfg(feature = "test-util")]
+    seed: Option<u64>,
+    thread_name: String,
+    thread_stack_size: Option<usize>,
+    #[cfg(feature = "event-loop")]
+    enable_io: bool,
+    #[cfg(feature = "event-loop")]
+    reactor_hooks: Option<Arc<dyn ReactorHooks>>,
+    #[cfg(feature = "event-loop")]
+    io_capacity: usize,
+    #[cfg(feature = "timer")]
+    enable_time: bool,
+    #[cfg(feature = "timer")]
+    timer_granularity: Duration,
+    #[cfg(feature = "timer")]
+    coarse_clock: bool,
+    max_blocking_threads: usize,
+    blocking_queue_limit: Option<usize>,
+    thread_keep_alive: Duration,
+    hooks: Hooks,
+    #[cfg(feature = "task-dump")]
+    on_leaked_task: Option<LeakCallback>,
+    resolver: Option<CachingResolver>,
+}
+
+impl Builder {
+    /// Creates a builder of a multi-threaded runtime with the default configuration.
+    ///
+    /// Both the I/O and the time drivers are enabled by default.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "async-rt")]
+            flavor: Flavor::MultiThread,
+            #[cfg(feature = "async-rt")]
+            worker_threads: None,
+            #[cfg(feature = "async-rt")]
+            lifo_slot: true,
+            #[cfg(feature = "async-rt")]
+            long_poll_threshold: None,
+            #[cfg(feature = "async-rt")]
+            busy_poll: None,
+            #[cfg(all(target_os = "linux", feature = "affinity"))]
+            worker_cores: Vec::new(),
+            #[cfg(all(target_os = "linux", feature = "affinity"))]
+            reactor_core: None,
+            #[cfg(all(target_os = "linux", feature = "affinity"))]
+            numa_nodes: Vec::new(),
+            #[cfg(feature = "test-util")]
+            seed: None,
+            thread_name: THREAD_NAME.to_string(),
+            thread_stack_size: None,
+            #[cfg(feature = "event-loop")]
+            enable_io: true,
+            #[cfg(feature = "event-loop")]
+            reactor_hooks: None,
+            #[cfg(feature = "event-loop")]
+            io_capacity: 0,
+            #[cfg(feature = "timer")]
+            enable_time: true,
+            #[cfg(feature = "timer")]
+            timer_granularity: Duration::ZERO,
+            #[cfg(feature = "timer")]
+            coarse_clock: false,
+            max_blocking_threads: MAX_BLOCKING_THREADS,
+            blocking_queue_limit: None,
+            thread_keep_alive: THREAD_KEEP_ALIVE,
+            hooks: Hooks::default(),
+            #[cfg(feature = "task-dump")]
+            on_leaked_task: None,
+            resolver: None,
+        }
+    }
+
+    /// Creates a builder of a runtime of the [`CurrentThread`] flavor.
+    ///
+    /// [`CurrentThread`]: enum.Flavor.html#variant.CurrentThread
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use tio::runtime::Builder;
+    /// use tio::task;
+    ///
+    /// let runtime = Builder::new_current_thread().build().unwrap();
+    /// let id = runtime.block_on(async {
+    ///     task::spawn(async { thread::current().id() }).await.unwrap()
+    /// });
+    /// assert_eq!(thread::current().id(), id);
+    /// ```
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn new_current_thread() -> Self {
+        Self {
+            flavor: Flavor::CurrentThread,
+            ..Self::new()
+        }
+    }
+
+    /// Sets the number of worker threads running async tasks.
+    ///
+    /// The default is the number of CPUs, unless it is overridden by the `TIO_WORKER_THREADS`
+    /// environment variable. It is ignored by the [`CurrentThread`] flavor.
+    ///
+    /// [`CurrentThread`]: enum.Flavor.html#variant.CurrentThread
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `nums` is zero.
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn worker_threads(mut self, nums: usize) -> Self {
+        assert!(nums > 0, "worker threads cannot be zero");
+        self.worker_threads = Some(nums);
+        self
+    }
+
+    /// Disables the LIFO slot of the workers.
+    ///
+    /// A task woken by the running task of a worker usually runs next on the same worker, before
+    /// the other queued tasks, which cuts the latency of tasks passing messages back and forth.
+    /// Disabling the slot schedules every task in FIFO order, trading the latency for fairness.
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn disable_lifo_slot(mut self) -> Self {
+        self.lifo_slot = false;
+        self
+    }
+
+    /// Enables a watchdog, warning of the polls of the async tasks which take longer than
+    /// `threshold`.
+    ///
+    /// A poll blocking its thread, like an accidental blocking call in an async task, stalls
+    /// every task queued on the worker. The watchdog logs a warning with the name and the
+    /// spawning location of the task once a poll runs for longer than the threshold, while it
+    /// still runs, and another one with the elapsed time once it returns. The watchdog is
+    /// disabled by default.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `threshold` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use tio::runtime::Builder;
+    ///
+    /// let runtime = Builder::new()
+    ///     .long_poll_threshold(Duration::from_millis(100))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn long_poll_threshold(mut self, threshold: Duration) -> Self {
+        assert!(!threshold.is_zero(), "long poll threshold cannot be zero");
+        self.long_poll_threshold = Some(threshold);
+        self
+    }
+
+    /// Enables the busy-poll mode, where an idle worker spins for `dur` looking for a task
+    /// before it parks, and the I/O driver polls the OS without blocking for `dur` after its
+    /// last event.
+    ///
+    /// Parking and unparking a thread takes a few microseconds, which adds up to the latency
+    /// of every message arriving at an idle runtime. Busy-polling skips them for the messages
+    /// arriving within `dur` of the last one, at the cost of keeping the CPUs busy meanwhile,
+    /// so it suits the latency-critical deployments with dedicated cores, like the ones pinned
+    /// by [`pin_workers`]. A runtime without workers only busy-polls its I/O driver. The mode
+    /// is disabled by default.
+    ///
+    /// [`pin_workers`]: #method.pin_workers
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `dur` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use tio::runtime::Builder;
+    ///
+    /// let runtime = Builder::new()
+    ///     .worker_threads(2)
+    ///     .busy_poll(Duration::from_micros(50))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(1, runtime.block_on(runtime.spawn(async { 1 })).unwrap());
+    /// ```
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn busy_poll(mut self, dur: Duration) -> Self {
+        assert!(!dur.is_zero(), "busy poll duration cannot be zero");
+        self.busy_poll = Some(dur);
+        self
+    }
+
+    /// Pins the worker threads to the CPU cores, the worker of index `i` to the core
+    /// `cores[i % cores.len()]`.
+    ///
+    /// Latency-critical deployments may pin the workers to isolated cores, to keep them off the
+    /// cores of the other processes. The threads of the blocking pool and of the timer are not
+    /// pinned, they run on the CPUs the process runs on when the runtime is built. A core the
+    /// thread cannot run on, like an offline one, leaves the thread unpinned.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `cores` is empty, or a core is out of the CPUs supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::runtime::Builder;
+    ///
+    /// let runtime = Builder::new()
+    ///     .worker_threads(2)
+    ///     .pin_workers([0, 1])
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    #[cfg_attr(
+        feature = "docs",
+        doc(cfg(all(target_os = "linux", feature = "affinity")))
+    )]
+    pub fn pin_workers(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        let cores = cores.into_iter().collect::<Vec<_>>();
+        assert!(!cores.is_empty(), "no core to pin the workers to");
+        assert!(
+            cores
+                .iter()
+                .all(|&core| core < rustix::thread::CpuSet::MAX_CPU),
+            "the core is out of the CPUs supported"
+        );
+        self.worker_cores = cores;
+        self
+    }
+
+    /// Pins the thread of the I/O driver to a CPU core.
+    ///
+    /// With [`numa_nodes`], this pins the reactor of the first node, the reactors of the
+    /// others run on the CPUs of their nodes.
+    ///
+    /// [`numa_nodes`]: #method.numa_nodes
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the core is out of the CPUs supported.
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    #[cfg_attr(
+        feature = "docs",
+        doc(cfg(all(target_os = "linux", feature = "affinity")))
+    )]
+    #[inline]
+    pub fn pin_reactor(mut self, core: usize) -> Self {
+        assert!(
+            core < rustix::thread::CpuSet::MAX_CPU,
+            "the core is out of the CPUs supported"
+        );
+        self.reactor_core = Some(core);
+        self
+    }
+
+    /// Partitions the worker threads into NUMA nodes, given the CPUs of each node, like the
+    /// ones returned by [`numa_nodes`].
+    ///
+    /// The worker of index `i` runs on the CPUs of the node `i % nodes.len()`, unless it is
+    /// pinned to a core by [`pin_workers`]. A task spawned with a node hint by
+    /// [`task::Builder::numa_node`] is queued on its node, and the workers look for a task on
+    /// their node before stealing one from the others, so the memory of a connection stays
+    /// local whenever the node is not idle. There are at most as many nodes as workers.
+    ///
+    /// Each node also has a reactor of its own, whose poll thread runs on the CPUs of the node.
+    /// An I/O source is registered to the reactor of the node of the worker creating it, or to
+    /// the reactor of the first node out of the workers, and the tasks its events wake are
+    /// queued on that node unless they are hinted to another one.
+    ///
+    /// [`numa_nodes`]: fn.numa_nodes.html
+    /// [`pin_workers`]: #method.pin_workers
+    /// [`task::Builder::numa_node`]: ../task/struct.Builder.html#method.numa_node
+    ///
+    /// # Panics
+    ///
+    /// This method panics if there is no node, a node has no CPU, or a CPU is out of the ones
+    /// supported.
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    #[cfg_attr(
+        feature = "docs",
+        doc(cfg(all(target_os = "linux", feature = "affinity")))
+    )]
+    pub fn numa_nodes<I>(mut self, nodes: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: IntoIterator<Item = usize>,
+    {
+        let nodes = nodes
+            .into_iter()
+            .map(|cpus| cpus.into_iter().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert!(
+            !nodes.is_empty(),
+            "no NUMA node to partition the workers into"
+        );
+        assert!(
+            nodes.iter().all(|cpus| !cpus.is_empty()),
+            "a NUMA node has no CPU"
+        );
+        assert!(
+            nodes
+                .iter()
+                .flatten()
+                .all(|&cpu| cpu < rustix::thread::CpuSet::MAX_CPU),
+            "the core is out of the CPUs supported"
+        );
+        self.numa_nodes = nodes;
+        self
+    }
+
+    /// Makes the runtime deterministic, for tests.
+    ///
+    /// The ready tasks run in a pseudo-random order determined by `seed`, instead of the order
+    /// they are woken in, and the clock of the timers is paused: once no task can run, it jumps
+    /// to the next deadline at once. A test which fails on some interleaving of its tasks can
+    /// then be reproduced by running it with the same seed, and it does not wait for its
+    /// timers in real time.
+    ///
+    /// Only the interleaving of the tasks is deterministic, the tasks woken by other threads,
+    /// like the I/O driver or the blocking pool, are still scheduled whenever they are woken.
+    ///
+    /// # Panics
+    ///
+    /// This method panics unless the builder is of the [`CurrentThread`] flavor.
+    ///
+    /// [`CurrentThread`]: enum.Flavor.html#variant.CurrentThread
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::{Duration, Instant};
+    /// use tio::runtime::Builder;
+    /// use tio::task;
+    ///
+    /// fn interleave(seed: u64) -> Vec<usize> {
+    ///     let runtime = Builder::new_current_thread().seed(seed).build().unwrap();
+    ///     let order = Arc::new(Mutex::new(Vec::new()));
+    ///     runtime.block_on(async {
+    ///         let handles = (0..8)
+    ///             .map(|i| {
+    ///                 let order = order.clone();
+    ///                 task::spawn(async move { order.lock().unwrap().push(i) })
+    ///             })
+    ///             .collect::<Vec<_>>();
+    ///         for handle in handles {
+    ///             handle.await.unwrap();
+    ///         }
+    ///     });
+    ///     let order = order.lock().unwrap().clone();
+    ///     order
+    /// }
+    ///
+    /// assert_eq!(interleave(42), interleave(42));
+    ///
+    /// // the clock jumps over the sleep
+    /// let start = Instant::now();
+    /// let runtime = Builder::new_current_thread().seed(42).build().unwrap();
+    /// runtime.block_on(task::sleep(Duration::from_secs(3600)));
+    /// assert!(start.elapsed() < Duration::from_secs(60));
+    /// ```
+    #[cfg(feature = "test-util")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "test-util")))]
+    #[inline]
+    pub fn seed(mut self, seed: u64) -> Self {
+        assert_eq!(
+            Flavor::CurrentThread,
+            self.flavor,
+            "only a current-thread runtime can be seeded"
+        );
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the name prefix of the threads of the runtime.
+    ///
+    /// Worker threads are named `{name}/async{index}` and blocking threads `{name}/blocking`.
+    /// The default prefix is `tio`.
+    #[inline]
+    pub fn thread_name(mut self, name: impl Into<String>) -> Self {
+        self.thread_name = name.into();
+        self
+    }
+
+    /// Sets the stack size in bytes of the threads of the runtime.
+    ///
+    /// The default is the stack size of [`std::thread::Builder`].
+    ///
+    /// [`std::thread::Builder`]: https://doc.rust-lang.org/std/thread/struct.Builder.html
+    #[inline]
+    pub fn thread_stack_size(mut self, size: usize) -> Self {
+        self.thread_stack_size = Some(size);
+        self
+    }
+
+    /// Enables or disables the I/O driver.
+    ///
+    /// Creating a socket on a runtime without the I/O driver panics.
+    #[cfg(feature = "event-loop")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "event-loop")))]
+    #[inline]
+    pub fn enable_io(mut self, enabled: bool) -> Self {
+        self.enable_io = enabled;
+        self
+    }
+
+    /// Installs hooks into the I/O driver, called on its events and on the registrations of
+    /// its sources.
+    ///
+    /// See [`ReactorHooks`] for an example.
+    ///
+    /// [`ReactorHooks`]: trait.ReactorHooks.html
+    #[cfg(feature = "event-loop")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "event-loop")))]
+    #[inline]
+    pub fn reactor_hooks(mut self, hooks: impl ReactorHooks) -> Self {
+        self.reactor_hooks = Some(Arc::new(hooks));
+        self
+    }
+
+    /// Sets the number of I/O sources the driver has room for before its table grows.
+    ///
+    /// The driver keeps the state of every socket, pipe or other source registered to it in a
+    /// table indexed by the tokens of their events. The table grows by reallocating, which
+    /// holds the events of every source of the runtime back meanwhile; a server expecting
+    /// many connections may keep its latency flat by sizing it up front. It is empty by
+    /// default. With NUMA nodes, every node has a table of this capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::runtime::Builder;
+    ///
+    /// let runtime = Builder::new().io_capacity(10_000).build().unwrap();
+    /// ```
+    #[cfg(feature = "event-loop")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "event-loop")))]
+    #[inline]
+    pub fn io_capacity(mut self, nums: usize) -> Self {
+        self.io_capacity = nums;
+        self
+    }
+
+    /// Enables or disables the time driver.
+    ///
+    /// Creating a timer on a runtime without the time driver panics.
+    #[cfg(feature = "timer")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+    #[inline]
+    pub fn enable_time(mut self, enabled: bool) -> Self {
+        self.enable_time = enabled;
+        self
+    }
+
+    /// Sets the granularity of the timers, rounding their deadlines up to its multiples.
+    ///
+    /// The timers of a bucket elapse together, by one wakeup of the time driver, so a server of
+    /// many timers trades a little precision for far fewer wakeups. A timer never elapses
+    /// early, but up to a granularity late. The default is zero, the timers are exact.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use tio::runtime::Builder;
+    ///
+    /// let runtime = Builder::new()
+    ///     .timer_granularity(Duration::from_millis(10))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "timer")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+    #[inline]
+    pub fn timer_granularity(mut self, granularity: Duration) -> Self {
+        self.timer_granularity = granularity;
+        self
+    }
+
+    /// Enables the coarse clock of the runtime, which is disabled by default.
+    ///
+    /// The timers, the socket timeouts and [`task::now`] then read an instant cached by the
+    /// threads of the runtime, refreshed whenever a worker polls a task, the reactor or the time
+    /// driver wakes up, or a thread enters the runtime. This saves a `clock_gettime` call per
+    /// timer on a server of many timeouts, at the cost of a lag up to the poll of a task.
+    ///
+    /// [`task::now`]: ../task/fn.now.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::runtime::Builder;
+    ///
+    /// let runtime = Builder::new().coarse_clock(true).build().unwrap();
+    /// runtime.block_on(async {
+    ///     tio::task::sleep(std::time::Duration::from_millis(10)).await;
+    /// });
+    /// ```
+    #[cfg(feature = "timer")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+    #[inline]
+    pub fn coarse_clock(mut self, enabled: bool) -> Self {
+        self.coarse_clock = enabled;
+        self
+    }
+
+    /// Sets the max number of threads in the blocking pool.
+    ///
+    /// Once the limit is reached, blocking tasks wait in a queue until a thread becomes free.
+    /// The default is 512.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `nums` is zero.
+    #[inline]
+    pub fn max_blocking_threads(mut self, nums: usize) -> Self {
+        assert!(nums > 0, "max blocking threads cannot be zero");
+        self.max_blocking_threads = nums;
+        self
+    }
+
+    /// Sets the max number of blocking tasks waiting for a thread.
+    ///
+    /// Once every blocking thread is busy and the queue is full, [`task::try_spawn_blocking`]
+    /// fails with [`SpawnError::Saturated`], and [`task::spawn_blocking`] panics. The queue is
+    /// unbounded by default.
+    ///
+    /// [`task::try_spawn_blocking`]: ../task/fn.try_spawn_blocking.html
+    /// [`SpawnError::Saturated`]: enum.SpawnError.html#variant.Saturated
+    /// [`task::spawn_blocking`]: ../task/fn.spawn_blocking.html
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `limit` is zero.
+    #[inline]
+    pub fn blocking_queue_limit(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "blocking queue limit cannot be zero");
+        self.blocking_queue_limit = Some(limit);
+        self
+    }
+
+    /// Sets how long a blocking thread stays idle before it stops.
+    ///
+    /// The last thread of the blocking pool never stops until the runtime is shut down, it
+    /// waits for a task without any timeout. The default is one second.
+    #[inline]
+    pub fn thread_keep_alive(mut self, dur: Duration) -> Self {
+        self.thread_keep_alive = dur;
+        self
+    }
+
+    /// Sets a callback run on every thread of the runtime once it starts.
+    ///
+    /// The callback runs within the context of the runtime, before the thread runs any task.
+    /// It may initialize the thread-local state, like allocator arenas or the CPU affinity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use tio::runtime::Builder;
+    ///
+    /// let live = Arc::new(AtomicUsize::new(0));
+    /// let started = Arc::new(AtomicUsize::new(0));
+    /// let runtime = Builder::new()
+    ///     .worker_threads(2)
+    ///     .on_thread_start({
+    ///         let (live, started) = (live.clone(), started.clone());
+    ///         move || {
+    ///             live.fetch_add(1, Ordering::SeqCst);
+    ///             started.fetch_add(1, Ordering::SeqCst);
+    ///         }
+    ///     })
+    ///     .on_thread_stop({
+    ///         let live = live.clone();
+    ///         move || {
+    ///             live.fetch_sub(1, Ordering::SeqCst);
+    ///         }
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// runtime.shutdown_timeout(Duration::from_secs(1));
+    /// assert_eq!(2, started.load(Ordering::SeqCst));
+    /// assert_eq!(0, live.load(Ordering::SeqCst));
+    /// ```
+    #[inline]
+    pub fn on_thread_start<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(),
+    {
+        self.hooks.on_thread_start = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets a callback run on every thread of the runtime right before it stops.
+    #[inline]
+    pub fn on_thread_stop<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(),
+    {
+        self.hooks.on_thread_stop = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets a callback run on a worker thread before it parks for lack of tasks.
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn on_thread_park<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(),
+    {
+        self.hooks.on_thread_park = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets a callback run on a worker thread after it is unparked.
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn on_thread_unpark<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(),
+    {
+        self.hooks.on_thread_unpark = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets a callback run on every task which is still pending when the runtime shuts down.
+    ///
+    /// The pending tasks are cancelled on shutdown, which is rarely expected but for the tasks
+    /// running forever, like the accept loop of a server. This callback reports the others, by
+    /// their names and spawn locations, to track down the futures which never complete.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::future;
+    /// use std::sync::{Arc, Mutex};
+    /// use tio::runtime::Builder;
+    /// use tio::task;
+    ///
+    /// let leaked = Arc::new(Mutex::new(Vec::new()));
+    /// let runtime = {
+    ///     let leaked = leaked.clone();
+    ///     Builder::new()
+    ///         .on_leaked_task(move |task| {
+    ///             log::warn!("{} never completes", task);
+    ///             leaked.lock().unwrap().push(task.name().map(String::from));
+    ///         })
+    ///         .build()
+    ///         .unwrap()
+    /// };
+    /// runtime.block_on(async {
+    ///     task::Builder::new()
+    ///         .name("stuck")
+    ///         .spawn(future::pending::<()>())
+    ///         .detach();
+    /// });
+    /// drop(runtime);
+    /// assert_eq!(vec![Some("stuck".to_string())], *leaked.lock().unwrap());
+    /// ```
+    #[cfg(feature = "task-dump")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "task-dump")))]
+    #[inline]
+    pub fn on_leaked_task<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(&TaskInfo),
+    {
+        self.on_leaked_task = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets the caching resolver shared by the runtime, returned by [`Handle::resolver`].
+    ///
+    /// By default, the runtime has a resolver of the default configuration.
+    ///
+    /// [`Handle::resolver`]: struct.Handle.html#method.resolver
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use tio::net::CachingResolverBuilder;
+    /// use tio::runtime::Builder;
+    ///
+    /// let resolver = CachingResolverBuilder::new()
+    ///     .max_ttl(Duration::from_secs(60))
+    ///     .build();
+    /// let runtime = Builder::new().resolver(resolver).build().unwrap();
+    /// ```
+    #[inline]
+    pub fn resolver(mut self, resolver: CachingResolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Creates the configured runtime and starts its worker threads, if any.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if a thread cannot be spawned.
+    pub fn build(self) -> io::Result<Runtime> {
+        self.build_inner().map(|inner| Runtime {
+            handle: Handle { inner },
+        })
+    }
+
+    pub(crate) fn build_inner(self) -> io::Result<Arc<Inner>> {
+        #[cfg(all(target_os = "linux", feature = "affinity"))]
+        let affinity = if self.worker_cores.is_empty()
+            && self.reactor_core.is_none()
+            && self.numa_nodes.is_empty()
+        {
+            None
+        } else {
+            Some(Affinity::new(
+                self.worker_cores,
+                self.reactor_core,
+                self.numa_nodes,
+            )?)
+        };
+        Inner::start(Config {
+            #[cfg(feature = "async-rt")]
+            flavor: self.flavor,
+            #[cfg(feature = "async-rt")]
+            worker_threads: match self.flavor {
+                Flavor::CurrentThread => 0,
+                Flavor::MultiThread => self
+                    .worker_threads
+                    .unwrap_or_else(super::pool::default_worker_threads),
+            },
+            #[cfg(feature = "async-rt")]
+            lifo_slot: self.lifo_slot,
+            #[cfg(feature = "async-rt")]
+            long_poll_threshold: self.long_poll_threshold,
+            #[cfg(feature = "async-rt")]
+            busy_poll: self.busy_poll,
+            #[cfg(all(target_os = "linux", feature = "affinity"))]
+            affinity,
+            #[cfg(feature = "test-util")]
+            seed: self.seed,
+            thread_name: self.thread_name,
+            thread_stack_size: self.thread_stack_size,
+            #[cfg(feature = "event-loop")]
+            enable_io: self.enable_io,
+            #[cfg(feature = "event-loop")]
+            reactor_hooks: self.reactor_hooks,
+            #[cfg(feature = "event-loop")]
+            io_capacity: self.io_capacity,
+            #[cfg(feature = "timer")]
+            enable_time: self.enable_time,
+            #[cfg(feature = "timer")]
+            timer_granularity: self.timer_granularity,
+            #[cfg(feature = "timer")]
+            coarse_clock: self.coarse_clock,
+            max_blocking_threads: self.max_blocking_threads,
+            blocking_queue_limit: self.blocking_queue_limit,
+            thread_keep_alive: self.thread_keep_alive,
+            hooks: self.hooks,
+            #[cfg(feature = "task-dump")]
+            on_leaked_task: self.on_leaked_task,
+            resolver: self.resolver.unwrap_or_default(),
+        })
+    }
+}
+
+impl Default for Builder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Builder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Builder");
+        #[cfg(feature = "async-rt")]
+        f.field("flavor", &self.flavor)
+            .field("worker_threads", &self.worker_threads)
+            .field("lifo_slot", &self.lifo_slot)
+            .field("long_poll_threshold", &self.long_poll_threshold)
+            .field("busy_poll", &self.busy_poll);
+        #[cfg(all(target_os = "linux", feature = "affinity"))]
+        f.field("worker_cores", &self.worker_cores)
+            .field("reactor_core", &self.reactor_core)
+            .field("numa_nodes", &self.numa_nodes);
+        #[cfg(feature = "test-util")]
+        f.field("seed", &self.seed);
+        f.field("thread_name", &self.thread_name)
+            .field("thread_stack_size", &self.thread_stack_size);
+        #[cfg(feature = "event-loop")]
+        f.field("enable_io", &self.enable_io)
+            .field("reactor_hooks", &self.reactor_hooks.is_some())
+            .field("io_capacity", &self.io_capacity);
+        #[cfg(feature = "timer")]
+        f.field("enable_time", &self.enable_time)
+            .field("timer_granularity", &self.timer_granularity)
+            .field("coarse_clock", &self.coarse_clock);
+        f.field("max_blocking_threads", &self.max_blocking_threads)
+            .field("blocking_queue_limit", &self.blocking_queue_limit)
+            .field("thread_keep_alive", &self.thread_keep_alive)
+            .field("hooks", &self.hooks);
+        #[cfg(feature = "task-dump")]
+        f.field("on_leaked_task", &self.on_leaked_task.is_some());
+        f.field("resolver", &self.resolver).finish()
+    }
+}
@@ -0,0 +1,48 @@
+use super::{Builder, Inner};
+use once_cell::sync::Lazy;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<Inner>>> = const { RefCell::new(None) };
+}
+
+/// The runtime used outside of any runtime context.
+static DEFAULT: Lazy<Arc<Inner>> = Lazy::new(|| {
+    Builder::new()
+        .build_inner()
+        .unwrap_or_else(|err| panic!("fail to start the default runtime: {}", err))
+});
+
+/// Restores the previous runtime context when dropped.
+pub(crate) struct EnterGuard(Option<Arc<Inner>>);
+
+impl Drop for EnterGuard {
+    #[inline]
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+    }
+}
+
+/// Makes `inner` the runtime of the current thread until the guard is dropped.
+#[inline]
+pub(crate) fn enter(inner: Arc<Inner>) -> EnterGuard {
+    #[cfg(feature = "timer")]
+    inner.tick();
+    EnterGuard(CURRENT.with(|current| current.replace(Some(inner))))
+}
+
+/// Calls `f` with the runtime of the current thread, if any.
+#[cfg(feature = "timer")]
+#[inline]
+pub(crate) fn with_current<R>(f: impl FnOnce(&Inner) -> R) -> Option<R> {
+    CURRENT.with(|current| current.borrow().as_deref().map(f))
+}
+
+/// Returns the runtime of the current thread, or the default runtime.
+#[inline]
+pub(crate) fn current() -> Arc<Inner> {
+    CURRENT
+        .with(|current| current.borrow().clone())
+        .unwrap_or_else(|| DEFAULT.clone())
+}
@@ -0,0 +1,285 @@
+use super::{context, Inner, RuntimeMetrics, SpawnError};
+use crate::net::CachingResolver;
+use crate::task::{self, JoinHandle};
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[cfg(feature = "async-rt")]
+use super::{pool::Pool, Flavor};
+
+/// A handle to a runtime, to spawn tasks onto it from anywhere.
+///
+/// A handle is cheap to clone and can be sent to other threads, like the callbacks of a C
+/// library, which are not in any runtime context. It does not keep the runtime running: once
+/// the [`Runtime`] is shut down, the tasks spawned by the handle are cancelled at once.
+///
+/// [`Runtime`]: struct.Runtime.html
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use tio::runtime::{Handle, Runtime};
+///
+/// let runtime = Runtime::new().unwrap();
+/// let handle = runtime.handle().clone();
+/// let val = thread::spawn(move || {
+///     let task = handle.spawn(async { 1 + 2 });
+///     handle.block_on(task).unwrap()
+/// })
+/// .join()
+/// .unwrap();
+/// assert_eq!(3, val);
+/// ```
+#[derive(Clone)]
+pub struct Handle {
+    pub(crate) inner: Arc<Inner>,
+}
+
+/// A guard which keeps a runtime as the context of the current thread, returned by
+/// [`Handle::enter`].
+///
+/// The previous context is restored when it is dropped.
+///
+/// [`Handle::enter`]: struct.Handle.html#method.enter
+pub struct EnterGuard<'a> {
+    _guard: context::EnterGuard,
+    // the context is thread-local
+    _marker: PhantomData<(&'a Handle, *const ())>,
+}
+
+impl Handle {
+    /// Returns a handle to the runtime of the current context.
+    ///
+    /// Outside of any runtime context, this is the default runtime, see the [module docs].
+    ///
+    /// [module docs]: index.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::runtime::{Handle, Runtime};
+    ///
+    /// let runtime = Runtime::new().unwrap();
+    /// let handle = runtime.block_on(async { Handle::current() });
+    /// assert_eq!(3, handle.block_on(handle.spawn(async { 1 + 2 })).unwrap());
+    /// ```
+    #[inline]
+    pub fn current() -> Self {
+        Self {
+            inner: context::current(),
+        }
+    }
+
+    /// Makes the runtime the context of the current thread until the guard is dropped.
+    ///
+    /// The free functions like [`task::spawn`] spawn tasks onto the runtime meanwhile.
+    ///
+    /// [`task::spawn`]: ../task/fn.spawn.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::runtime::Runtime;
+    /// use tio::task;
+    ///
+    /// let runtime = Runtime::new().unwrap();
+    /// let task = {
+    ///     let _guard = runtime.handle().enter();
+    ///     task::spawn(async { 1 + 2 })
+    /// };
+    /// assert_eq!(3, runtime.block_on(task).unwrap());
+    /// ```
+    #[inline]
+    pub fn enter(&self) -> EnterGuard<'_> {
+        EnterGuard {
+            _guard: context::enter(self.inner.clone()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the metrics of the runtime, like the queue depths of the scheduler.
+    ///
+    /// See [`RuntimeMetrics`] for the available metrics.
+    ///
+    /// [`RuntimeMetrics`]: struct.RuntimeMetrics.html
+    #[inline]
+    pub fn metrics(&self) -> RuntimeMetrics {
+        RuntimeMetrics::new(self.inner.clone())
+    }
+
+    /// Returns the caching resolver shared by the runtime.
+    ///
+    /// It is set by [`Builder::resolver`], or of the default configuration.
+    ///
+    /// [`Builder::resolver`]: struct.Builder.html#method.resolver
+    #[inline]
+    pub fn resolver(&self) -> &CachingResolver {
+        &self.inner.config.resolver
+    }
+
+    /// Runs a future to completion on the current thread, within the context of the runtime.
+    ///
+    /// See also: [`Runtime::block_on`].
+    ///
+    /// [`Runtime::block_on`]: struct.Runtime.html#method.block_on
+    #[inline]
+    #[track_caller]
+    pub fn block_on<F>(&self, fut: F) -> F::Output
+    where
+        F: Future,
+    {
+        let _enter = self.enter();
+        #[cfg(feature = "async-rt")]
+        if self.inner.config.flavor == Flavor::CurrentThread {
+            return task::block_on(Pool::run_until(&self.inner, fut));
+        }
+        task::block_on(fut)
+    }
+
+    /// Returns the flavor of the runtime.
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn flavor(&self) -> Flavor {
+        self.inner.config.flavor
+    }
+
+    /// Spawns a task onto the runtime.
+    ///
+    /// See also: [`task::spawn`].
+    ///
+    /// [`task::spawn`]: ../task/fn.spawn.html
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[track_caller]
+    #[inline]
+    pub fn spawn<F, R>(&self, fut: F) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + Future<Output = R>,
+    {
+        self.inner.spawn(None, fut)
+    }
+
+    /// Spawns a blocking task onto the blocking pool of the runtime.
+    ///
+    /// See also: [`task::spawn_blocking`].
+    ///
+    /// [`task::spawn_blocking`]: ../task/fn.spawn_blocking.html
+    #[track_caller]
+    #[inline]
+    pub fn spawn_blocking<F, R>(&self, f: F) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnOnce() -> R,
+    {
+        self.inner.spawn_blocking(None, f)
+    }
+
+    /// Spawns a blocking task onto the blocking pool of the runtime, returning an error if the
+    /// pool is saturated.
+    ///
+    /// See also: [`task::try_spawn_blocking`].
+    ///
+    /// [`task::try_spawn_blocking`]: ../task/fn.try_spawn_blocking.html
+    #[track_caller]
+    #[inline]
+    pub fn try_spawn_blocking<F, R>(&self, f: F) -> Result<JoinHandle<R>, SpawnError>
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnOnce() -> R,
+    {
+        self.inner.try_spawn_blocking(None, f)
+    }
+
+    /// Captures the state of the live tasks of the runtime, for debugging hangs.
+    ///
+    /// A task is live from its spawn until its body is dropped, once it completes or is
+    /// aborted. The tasks of a [`LocalSet`] don't belong to any runtime and are not
+    /// captured. This is a best-effort trace: the states are read without stopping the
+    /// tasks, and the await point a task is parked on is not known, but its name, id,
+    /// spawn location and poll count usually tell which task is stuck or spinning.
+    ///
+    /// [`LocalSet`]: ../task/struct.LocalSet.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::future;
+    /// use tio::runtime::Runtime;
+    /// use tio::task;
+    ///
+    /// let runtime = Runtime::new().unwrap();
+    /// let handle = task::Builder::new()
+    ///     .name("stuck")
+    ///     .spawn_on_handle(runtime.handle(), future::pending::<()>());
+    /// let dump = runtime.handle().dump();
+    /// assert!(dump.tasks().iter().any(|task| task.name() == Some("stuck")));
+    /// println!("{}", dump);
+    /// # handle.abort();
+    /// ```
+    #[cfg(feature = "task-dump")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "task-dump")))]
+    #[inline]
+    pub fn dump(&self) -> task::Dump {
+        task::Dump::new(self.inner.tasks.dump())
+    }
+}
+
+impl Debug for Handle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad("Handle { .. }")
+    }
+}
+
+impl Debug for EnterGuard<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad("EnterGuard { .. }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Handle;
+    use crate::runtime::Builder;
+    use crate::task;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn current() {
+        let runtime = Builder::new().build().unwrap();
+        let handle = runtime.block_on(async { Handle::current() });
+        assert!(Arc::ptr_eq(&runtime.handle().inner, &handle.inner));
+        assert!(!Arc::ptr_eq(&Handle::current().inner, &handle.inner));
+    }
+
+    #[test]
+    fn enter() {
+        let runtime = Builder::new().thread_name("rt-enter").build().unwrap();
+        let task = {
+            let _guard = runtime.handle().enter();
+            task::spawn_blocking(|| thread::current().name().map(ToString::to_string))
+        };
+        assert!(!Arc::ptr_eq(
+            &Handle::current().inner,
+            &runtime.handle().inner
+        ));
+        assert_eq!(
+            Some("rt-enter/blocking"),
+            runtime.block_on(task).unwrap().as_deref()
+        );
+    }
+
+    #[test]
+    fn spawn_after_shutdown() {
+        let runtime = Builder::new().build().unwrap();
+        let handle = runtime.handle().clone();
+        drop(runtime);
+        let task = handle.spawn(async {});
+        assert!(task::block_on(task).unwrap_err().is_cancelled());
+    }
+}
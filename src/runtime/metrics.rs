@@ -0,0 +1,274 @@
+use super::Inner;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+#[cfg(feature = "async-rt")]
+use std::sync::atomic::Ordering;
+
+/// A handle to the metrics of a runtime, returned by [`Handle::metrics`].
+///
+/// Every method reads the current value of a counter or a gauge, without stopping the
+/// runtime, so the values are only a best-effort snapshot.
+///
+/// [`Handle::metrics`]: struct.Handle.html#method.metrics
+///
+/// # Examples
+///
+/// ```
+/// use tio::runtime::Builder;
+///
+/// let runtime = Builder::new().worker_threads(2).build().unwrap();
+/// runtime.block_on(runtime.spawn(async {})).unwrap();
+///
+/// let metrics = runtime.metrics();
+/// assert_eq!(2, metrics.num_workers());
+/// let polls: u64 = (0..metrics.num_workers())
+///     .map(|worker| metrics.worker_poll_count(worker))
+///     .sum();
+/// assert!(polls >= 1);
+/// ```
+#[derive(Clone)]
+pub struct RuntimeMetrics {
+    pub(super) inner: Arc<Inner>,
+}
+
+impl RuntimeMetrics {
+    #[inline]
+    pub(crate) fn new(inner: Arc<Inner>) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the number of tasks which are spawned onto the runtime and not dropped yet,
+    /// including the blocking tasks.
+    #[inline]
+    pub fn num_alive_tasks(&self) -> usize {
+        self.inner.tasks.len()
+    }
+
+    /// Returns the number of worker threads, which is zero for the [`CurrentThread`] flavor.
+    ///
+    /// [`CurrentThread`]: enum.Flavor.html#variant.CurrentThread
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn num_workers(&self) -> usize {
+        self.inner.pool.stats.len()
+    }
+
+    /// Returns the number of tasks in the global queue, which takes the tasks scheduled from
+    /// outside of the workers.
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn global_queue_depth(&self) -> usize {
+        self.inner.pool.injected.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of tasks in the local queue of a worker.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `worker` is not less than [`num_workers`].
+    ///
+    /// [`num_workers`]: #method.num_workers
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn worker_local_queue_depth(&self, worker: usize) -> usize {
+        self.inner.pool.stats[worker].queued.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many times a worker has polled a task.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `worker` is not less than [`num_workers`].
+    ///
+    /// [`num_workers`]: #method.num_workers
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn worker_poll_count(&self, worker: usize) -> u64 {
+        self.inner.pool.stats[worker].polls.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many tasks a worker has stolen from the other workers.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `worker` is not less than [`num_workers`].
+    ///
+    /// [`num_workers`]: #method.num_workers
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn worker_steal_count(&self, worker: usize) -> u64 {
+        self.inner.pool.stats[worker].steals.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many tasks overflowed from the full local queue of a worker into the
+    /// injector.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `worker` is not less than [`num_workers`].
+    ///
+    /// [`num_workers`]: #method.num_workers
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    pub fn worker_overflow_count(&self, worker: usize) -> u64 {
+        self.inner.pool.stats[worker]
+            .overflows
+            .load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of threads in the blocking pool.
+    #[inline]
+    pub fn num_blocking_threads(&self) -> usize {
+        self.inner.blocking.threads()
+    }
+
+    /// Returns the number of threads in the blocking pool which are waiting for a task.
+    #[inline]
+    pub fn num_idle_blocking_threads(&self) -> usize {
+        self.inner.blocking.idle_threads()
+    }
+
+    /// Returns the number of blocking tasks waiting for a thread.
+    #[inline]
+    pub fn blocking_queue_depth(&self) -> usize {
+        self.inner.blocking.queue_depth()
+    }
+
+    /// Returns the number of the timers registered to the time driver, which are waiting for
+    /// their deadlines.
+    #[cfg(feature = "timer")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+    #[inline]
+    pub fn num_timers(&self) -> usize {
+        self.inner.timer.counts().0
+    }
+
+    /// Returns how many timers the time driver has fired.
+    #[cfg(feature = "timer")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+    #[inline]
+    pub fn timer_fire_count(&self) -> u64 {
+        self.inner.timer.counts().1
+    }
+
+    /// Returns the number of the I/O sources registered to the I/O driver, like the sockets.
+    ///
+    /// It is zero until the I/O driver is started by the first source. With NUMA nodes, this
+    /// counts the sources of the reactors of every node.
+    #[cfg(feature = "event-loop")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "event-loop")))]
+    #[inline]
+    pub fn num_io_registrations(&self) -> usize {
+        self.inner
+            .reactors()
+            .map(|reactor| reactor.registrations())
+            .sum()
+    }
+
+    /// Returns how many readiness events the I/O driver has received from the OS.
+    #[cfg(feature = "event-loop")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "event-loop")))]
+    #[inline]
+    pub fn io_event_count(&self) -> u64 {
+        self.inner
+            .reactors()
+            .map(|reactor| reactor.event_count())
+            .sum()
+    }
+}
+
+impl Debug for RuntimeMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("RuntimeMetrics");
+        f.field("num_alive_tasks", &self.num_alive_tasks());
+        #[cfg(feature = "async-rt")]
+        f.field("num_workers", &self.num_workers())
+            .field("global_queue_depth", &self.global_queue_depth());
+        f.field("num_blocking_threads", &self.num_blocking_threads())
+            .field(
+                "num_idle_blocking_threads",
+                &self.num_idle_blocking_threads(),
+            )
+            .field("blocking_queue_depth", &self.blocking_queue_depth());
+        #[cfg(feature = "timer")]
+        f.field("num_timers", &self.num_timers());
+        #[cfg(feature = "event-loop")]
+        f.field("num_io_registrations", &self.num_io_registrations());
+        f.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::Builder;
+    use futures::future;
+    use std::sync::mpsc;
+
+    #[test]
+    fn alive_tasks() {
+        let runtime = Builder::new().build().unwrap();
+        let metrics = runtime.metrics();
+        let pending = runtime.spawn(future::pending::<()>());
+        let (sender, receiver) = mpsc::channel();
+        let blocking = runtime.spawn_blocking(move || receiver.recv().unwrap());
+        assert_eq!(2, metrics.num_alive_tasks());
+        pending.abort();
+        sender.send(()).unwrap();
+        runtime.block_on(blocking).unwrap();
+        assert!(runtime.block_on(pending).unwrap_err().is_cancelled());
+        assert_eq!(0, metrics.num_alive_tasks());
+        assert!(metrics.num_blocking_threads() >= 1);
+        assert_eq!(0, metrics.blocking_queue_depth());
+    }
+
+    #[test]
+    fn queue_depth() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let metrics = runtime.metrics();
+        let handles = (0..3).map(|_| runtime.spawn(async {})).collect::<Vec<_>>();
+        // nothing runs the tasks until `block_on`
+        assert_eq!(0, metrics.num_workers());
+        assert_eq!(3, metrics.global_queue_depth());
+        for handle in handles {
+            runtime.block_on(handle).unwrap();
+        }
+        assert_eq!(0, metrics.global_queue_depth());
+    }
+
+    #[cfg(all(feature = "async-rt", feature = "udp"))]
+    #[test]
+    fn drivers() -> std::io::Result<()> {
+        use crate::net::UdpSocket;
+        use crate::task;
+        use std::time::Duration;
+
+        let runtime = Builder::new().build().unwrap();
+        let metrics = runtime.metrics();
+        assert_eq!(0, metrics.num_io_registrations());
+        runtime.block_on(async {
+            let socket = UdpSocket::bind("127.0.0.1:0")?;
+            assert_eq!(1, metrics.num_io_registrations());
+            let addr = socket.local_addr()?;
+            let sender = task::spawn(async move {
+                task::sleep(Duration::from_millis(10)).await;
+                UdpSocket::bind("127.0.0.1:0")?.send_to(b"ping", addr).await
+            });
+            // readable by an event
+            socket.recv_from(&mut [0; 4]).await?;
+            assert!(metrics.io_event_count() >= 1);
+            sender.await.unwrap()?;
+            assert_eq!(0, metrics.num_timers());
+            assert_eq!(1, metrics.timer_fire_count());
+            drop(socket);
+            assert_eq!(0, metrics.num_io_registrations());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,164 @@
+use super::Inner;
+use crate::task::tag::Info;
+use crate::task::{AbortHandle, TaskId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "task-dump")]
+use crate::task::{TaskInfo, TaskState};
+
+#[cfg(feature = "console")]
+use once_cell::sync::Lazy;
+
+#[cfg(feature = "console")]
+use std::sync::Weak;
+
+const TASKS_LOCK_POISONED: &str = "owned tasks lock poisoned";
+
+#[cfg(feature = "console")]
+const RUNTIMES_LOCK_POISONED: &str = "runtime registry lock poisoned";
+
+/// The runtimes of the process, whose tasks are described to a connecting console.
+#[cfg(feature = "console")]
+static RUNTIMES: Lazy<Mutex<Vec<Weak<Inner>>>> = Lazy::new(Default::default);
+
+/// Registers a runtime, so its tasks are in [`live_tasks`].
+///
+/// [`live_tasks`]: fn.live_tasks.html
+#[cfg(feature = "console")]
+pub(crate) fn register(inner: &Arc<Inner>) {
+    let mut runtimes = RUNTIMES.lock().expect(RUNTIMES_LOCK_POISONED);
+    runtimes.retain(|runtime| runtime.strong_count() > 0);
+    runtimes.push(Arc::downgrade(inner));
+}
+
+/// Takes a snapshot of the live tasks of every runtime, ordered by id.
+#[cfg(feature = "console")]
+pub(crate) fn live_tasks() -> Vec<TaskInfo> {
+    // dropping the last reference to a runtime takes the lock again, release it first
+    let runtimes = RUNTIMES
+        .lock()
+        .expect(RUNTIMES_LOCK_POISONED)
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect::<Vec<_>>();
+    let mut tasks = runtimes
+        .iter()
+        .flat_map(|runtime| runtime.tasks.dump())
+        .collect::<Vec<_>>();
+    tasks.sort_by_key(TaskInfo::id);
+    tasks
+}
+
+/// The live tasks of a runtime, to cancel them on shutdown.
+pub(crate) struct OwnedTasks {
+    // `None` once the runtime is shut down
+    tasks: Mutex<Option<HashMap<TaskId, Entry>>>,
+}
+
+struct Entry {
+    abort: AbortHandle,
+    #[cfg(feature = "task-dump")]
+    info: Arc<Info>,
+}
+
+/// Removes a task from its runtime once the task body is dropped.
+struct Owned {
+    inner: Arc<Inner>,
+    id: TaskId,
+}
+
+impl Drop for Owned {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(tasks) =
+            &mut *self.inner.tasks.tasks.lock().expect(TASKS_LOCK_POISONED)
+        {
+            tasks.remove(&self.id);
+        }
+    }
+}
+
+impl OwnedTasks {
+    pub(crate) fn new() -> Self {
+        Self {
+            tasks: Mutex::new(Some(HashMap::new())),
+        }
+    }
+
+    /// Registers a task of `inner`, returning its body which unregisters it when dropped.
+    ///
+    /// The task is not registered if the runtime is shut down, it is going to be dropped
+    /// as soon as it is scheduled.
+    #[inline]
+    pub(crate) fn bind<F>(
+        inner: &Arc<Inner>,
+        info: &Arc<Info>,
+        abort: &AbortHandle,
+        fut: F,
+    ) -> impl Future<Output = F::Output>
+    where
+        F: Future,
+    {
+        let id = info.id();
+        if let Some(tasks) = &mut *inner.tasks.tasks.lock().expect(TASKS_LOCK_POISONED) {
+            let entry = Entry {
+                abort: abort.clone(),
+                #[cfg(feature = "task-dump")]
+                info: info.clone(),
+            };
+            tasks.insert(id, entry);
+        }
+        let owned = Owned {
+            inner: inner.clone(),
+            id,
+        };
+        async move {
+            let _owned = owned;
+            fut.await
+        }
+    }
+
+    /// Returns the number of live tasks.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.tasks
+            .lock()
+            .expect(TASKS_LOCK_POISONED)
+            .as_ref()
+            .map_or(0, HashMap::len)
+    }
+
+    /// Takes a snapshot of the live tasks, ordered by id.
+    #[cfg(feature = "task-dump")]
+    pub(crate) fn dump(&self) -> Vec<TaskInfo> {
+        let mut tasks = self
+            .tasks
+            .lock()
+            .expect(TASKS_LOCK_POISONED)
+            .iter()
+            .flat_map(HashMap::values)
+            .map(|entry| TaskInfo::new(&entry.info))
+            .collect::<Vec<_>>();
+        tasks.sort_by_key(TaskInfo::id);
+        tasks
+    }
+
+    /// Takes a snapshot of the pending tasks, ordered by id.
+    #[cfg(feature = "task-dump")]
+    pub(crate) fn pending(&self) -> Vec<TaskInfo> {
+        let mut tasks = self.dump();
+        tasks.retain(|task| task.state() != TaskState::Finished);
+        tasks
+    }
+
+    /// Stops accepting tasks and aborts the live ones.
+    pub(crate) fn close(&self) {
+        // aborting a task may drop it, which takes the lock again
+        let tasks = self.tasks.lock().expect(TASKS_LOCK_POISONED).take();
+        for entry in tasks.into_iter().flat_map(HashMap::into_values) {
+            entry.abort.abort()
+        }
+    }
+}
@@ -0,0 +1,896 @@
+use super::Inner;
+use crate::task::{tag, Task};
+use async_task::waker_fn;
+use crossbeam_deque::{Injector, Steal, Stealer, Worker};
+use crossbeam_queue::ArrayQueue;
+use crossbeam_utils::sync::{Parker, Unparker};
+use futures::future::poll_fn;
+use futures::pin_mut;
+use futures::task::AtomicWaker;
+use std::cell::{Cell, RefCell};
+use std::env;
+use std::future::Future;
+use std::hint;
+use std::io;
+use std::iter;
+use std::sync::atomic::{self, AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "test-util")]
+use std::sync::Mutex;
+use std::task::{Poll, Waker};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "test-util")]
+const SEEDED_LOCK_POISONED: &str = "seeded lock poisoned";
+
+/// The environment variable to override the number of worker threads.
+const WORKER_THREADS_ENV: &str = "TIO_WORKER_THREADS";
+
+/// The max number of tasks run by `run_until` before polling the main future again.
+const DRIVE_BATCH: usize = 64;
+
+/// The max number of tasks a worker takes from its LIFO slot in a row, so that two tasks
+/// waking each other cannot starve the local queue.
+const MAX_LIFO_POLLS: usize = 3;
+
+/// The max number of tasks in the local queue of a worker, beyond which half of them
+/// overflow into the injector, where the idle workers find them without stealing.
+const LOCAL_QUEUE_CAPACITY: usize = 256;
+
+/// The wakes deferred by a batch, by runtime.
+type Deferred = Vec<(Arc<Inner>, usize)>;
+
+thread_local! {
+    /// The worker running on this thread.
+    static WORKER: RefCell<Option<Local>> = const { RefCell::new(None) };
+
+    /// The parker of the thread driving a pool by `turn`, unparked by newly scheduled tasks.
+    static TURN: (Parker, Waker) = {
+        let parker = Parker::new();
+        let unparker = parker.unparker().clone();
+        (parker, waker_fn(move || unparker.unpark()))
+    };
+
+    /// The wakes deferred by the batch of this thread, or `None` out of a batch.
+    static DEFERRED: RefCell<Option<Deferred>> = const { RefCell::new(None) };
+
+    /// The runtime, by address, and the NUMA node of the reactor polling on this thread.
+    static POLLER: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// A worker and the runtime it belongs to.
+struct Local {
+    inner: Arc<Inner>,
+    index: usize,
+    queue: Worker<Task>,
+    parker: Parker,
+    // the task woken last by the running task, which runs next with hot caches
+    lifo: Cell<Option<Task>>,
+    lifo_polls: Cell<usize>,
+    // the sleepers to wake once the running task yields, for the tasks it pushed
+    deferred: Cell<usize>,
+}
+
+impl Local {
+    #[inline]
+    fn new(
+        inner: Arc<Inner>,
+        index: usize,
+        queue: Worker<Task>,
+        parker: Parker,
+    ) -> Self {
+        Self {
+            inner,
+            index,
+            queue,
+            parker,
+            lifo: Cell::new(None),
+            lifo_polls: Cell::new(0),
+            deferred: Cell::new(0),
+        }
+    }
+}
+
+/// The counters of a worker, read by the metrics.
+#[derive(Default)]
+pub(crate) struct WorkerStats {
+    pub(crate) queued: AtomicUsize,
+    pub(crate) polls: AtomicU64,
+    pub(crate) steals: AtomicU64,
+    pub(crate) overflows: AtomicU64,
+}
+
+/// A work-stealing thread pool.
+///
+/// A pool without workers is driven by the thread calling `run_until` instead.
+pub(crate) struct Pool {
+    injector: Injector<Task>,
+    // the queues of the tasks hinted to run on a NUMA node, the worker `i` is on the node
+    // `i % nodes.len()`
+    nodes: Vec<Injector<Task>>,
+    pub(crate) injected: AtomicUsize,
+    stealers: Vec<Stealer<Task>>,
+    pub(crate) stats: Vec<WorkerStats>,
+    unparkers: Vec<Unparker>,
+    // the indices of the sleeping workers, a worker is in it at most once
+    idle: ArrayQueue<usize>,
+    // whether a worker is in `idle` or about to be pushed to it
+    sleeping: Vec<AtomicBool>,
+    driver: AtomicWaker,
+    // whether the driver is woken and has not run the queue since
+    notified: AtomicBool,
+    #[cfg(feature = "test-util")]
+    seeded: Option<Mutex<Seeded>>,
+    shutdown: AtomicBool,
+}
+
+/// The queue of a seeded pool, which runs its tasks in a pseudo-random order.
+#[cfg(feature = "test-util")]
+struct Seeded {
+    rng: u64,
+    tasks: Vec<Task>,
+}
+
+#[cfg(feature = "test-util")]
+impl Seeded {
+    /// Removes a random task, by splitmix64.
+    #[inline]
+    fn pop(&mut self) -> Option<Task> {
+        if self.tasks.is_empty() {
+            return None;
+        }
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        let index = (z % self.tasks.len() as u64) as usize;
+        Some(self.tasks.swap_remove(index))
+    }
+}
+
+impl Pool {
+    /// Creates a pool and the local queues and parkers of its workers.
+    ///
+    /// The workers are partitioned into at most `nodes` NUMA nodes. A seeded pool must have no
+    /// workers.
+    pub(crate) fn new(
+        nums: usize,
+        nodes: usize,
+        seed: Option<u64>,
+    ) -> (Self, Vec<(Worker<Task>, Parker)>) {
+        #[cfg(not(feature = "test-util"))]
+        let _ = seed;
+        let queues = (0..nums)
+            .map(|_| (Worker::new_fifo(), Parker::new()))
+            .collect::<Vec<_>>();
+        let stealers = queues.iter().map(|(queue, _)| queue.stealer()).collect();
+        let unparkers = queues
+            .iter()
+            .map(|(_, parker)| parker.unparker().clone())
+            .collect();
+        let pool = Self {
+            injector: Injector::new(),
+            nodes: iter::repeat_with(Injector::new)
+                .take(nodes.min(nums))
+                .collect(),
+            injected: AtomicUsize::new(0),
+            stealers,
+            stats: iter::repeat_with(WorkerStats::default).take(nums).collect(),
+            unparkers,
+            idle: ArrayQueue::new(nums.max(1)),
+            sleeping: iter::repeat_with(|| AtomicBool::new(false))
+                .take(nums)
+                .collect(),
+            driver: AtomicWaker::new(),
+            notified: AtomicBool::new(false),
+            #[cfg(feature = "test-util")]
+            seeded: seed.map(|rng| {
+                Mutex::new(Seeded {
+                    rng,
+                    tasks: Vec::new(),
+                })
+            }),
+            shutdown: AtomicBool::new(false),
+        };
+        (pool, queues)
+    }
+
+    /// Returns the NUMA node of a worker.
+    #[inline]
+    fn node_of(&self, worker: usize) -> Option<usize> {
+        match self.nodes.len() {
+            0 => None,
+            len => Some(worker % len),
+        }
+    }
+
+    /// Returns the number of NUMA nodes the workers are partitioned into.
+    #[cfg(feature = "event-loop")]
+    #[inline]
+    pub(crate) fn nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the NUMA node of the current thread, if it is a worker of `inner` on a node.
+    #[cfg(feature = "event-loop")]
+    #[inline]
+    pub(crate) fn current_node(inner: &Inner) -> Option<usize> {
+        WORKER.with(|current| match &*current.borrow() {
+            Some(local) if std::ptr::eq(&*local.inner, inner) => {
+                inner.pool.node_of(local.index)
+            }
+            _ => None,
+        })
+    }
+
+    /// Marks the current thread as the poll thread of the reactor of a NUMA node, whose
+    /// wakes are queued on the node.
+    #[cfg(feature = "event-loop")]
+    #[inline]
+    pub(crate) fn enter_poller(inner: &Inner, node: usize) {
+        POLLER.with(|poller| poller.set(Some((inner as *const Inner as usize, node))))
+    }
+
+    /// Returns the NUMA node of the reactor polling on the current thread, if it is one of
+    /// `inner`.
+    #[inline]
+    fn poller_node(inner: &Inner) -> Option<usize> {
+        match POLLER.with(Cell::get) {
+            Some((runtime, node)) if runtime == inner as *const Inner as usize => {
+                Some(node)
+            }
+            _ => None,
+        }
+    }
+
+    /// Registers a worker as sleeping, unless some task arrived in the meantime.
+    ///
+    /// The injectors are checked after a fence pairing with the one of `wake`, so that a
+    /// concurrent `schedule` either sees the worker or the worker sees the task. A worker left
+    /// registered by a failed attempt is woken spuriously at worst.
+    #[inline]
+    fn sleep(&self, index: usize) -> bool {
+        if !self.sleeping[index].swap(true, Ordering::AcqRel) {
+            // never full, since it has room for every worker
+            let _ = self.idle.push(index);
+        }
+        atomic::fence(Ordering::SeqCst);
+        self.injector.is_empty()
+            && self.nodes.iter().all(Injector::is_empty)
+            && !self.shutdown.load(Ordering::Acquire)
+    }
+
+    /// Wakes at most `nums` sleeping workers, or the driver of a pool without workers.
+    ///
+    /// The driver is woken once until it runs the queue again, however many tasks are
+    /// scheduled meanwhile.
+    #[inline]
+    fn wake(&self, nums: usize) {
+        if self.stealers.is_empty() {
+            if !self.notified.swap(true, Ordering::AcqRel) {
+                self.driver.wake()
+            }
+            return;
+        }
+        atomic::fence(Ordering::SeqCst);
+        for index in iter::from_fn(|| self.idle.pop().ok()).take(nums) {
+            self.sleeping[index].store(false, Ordering::Release);
+            self.unparkers[index].unpark()
+        }
+    }
+
+    /// Wakes a sleeping worker or the driver, unless a batch of this thread defers it.
+    #[inline]
+    fn wake_one(inner: &Arc<Inner>) {
+        let deferred = DEFERRED.with(|deferred| match &mut *deferred.borrow_mut() {
+            Some(deferred) => {
+                match deferred.iter_mut().find(|(i, _)| Arc::ptr_eq(i, inner)) {
+                    Some((_, nums)) => *nums += 1,
+                    None => deferred.push((inner.clone(), 1)),
+                }
+                true
+            }
+            None => false,
+        });
+        if !deferred {
+            inner.pool.wake(1)
+        }
+    }
+
+    /// Clears the notification of the driver, before it runs the queue.
+    #[inline]
+    fn unnotify(&self) {
+        self.notified.swap(false, Ordering::AcqRel);
+    }
+
+    /// Pushes a task to the local queue if it is scheduled by a worker of this pool, otherwise
+    /// to the injector. A task hinted to run on another NUMA node, or woken by the reactor of a
+    /// node out of the workers, goes to the queue of the node instead.
+    ///
+    /// A task woken by the running task of a worker goes to the LIFO slot of the worker
+    /// instead, unless it is the running task itself, and the task it replaces goes to the
+    /// local queue. A full local queue overflows half of its tasks into the injector. A
+    /// sleeping worker is woken whenever a task is pushed to a queue, so it may steal the task
+    /// from a busy worker. The wakes for the tasks pushed by a worker are deferred until its
+    /// running task yields.
+    ///
+    /// A task woken again before it runs is scheduled once, since it is already queued.
+    #[inline]
+    pub(crate) fn schedule(inner: &Arc<Inner>, task: Task) {
+        let pool = &inner.pool;
+        if pool.shutdown.load(Ordering::Acquire) {
+            // the task is dropped instead of leaking into a queue nobody pops
+            return;
+        }
+        #[cfg(feature = "console")]
+        crate::console::emit(crate::console::Event::Wake(task.tag().info().id()));
+        // a task woken by the reactor of a node stays on the node, unless it is hinted
+        let node = match pool.nodes.len() {
+            0 => None,
+            len => task
+                .tag()
+                .node()
+                .or_else(|| Pool::poller_node(inner))
+                .map(|node| node % len),
+        };
+        let task = WORKER.with(|current| match &*current.borrow() {
+            Some(local)
+                if Arc::ptr_eq(&local.inner, inner)
+                    && (node.is_none() || node == pool.node_of(local.index)) =>
+            {
+                pool.stats[local.index]
+                    .queued
+                    .fetch_add(1, Ordering::Relaxed);
+                let task = if local.inner.config.lifo_slot
+                    && tag::id() != Some(task.tag().info().id())
+                {
+                    local.lifo.replace(Some(task))
+                } else {
+                    Some(task)
+                };
+                if let Some(task) = task {
+                    // the queued tasks of the worker count the LIFO slot and this task
+                    let queued = pool.stats[local.index].queued.load(Ordering::Relaxed);
+                    if queued > LOCAL_QUEUE_CAPACITY {
+                        pool.overflow(local);
+                    }
+                    local.queue.push(task);
+                    local.deferred.set(local.deferred.get() + 1)
+                }
+                None
+            }
+            _ => Some(task),
+        });
+        if let Some(task) = task {
+            pool.injected.fetch_add(1, Ordering::Relaxed);
+            #[cfg(feature = "test-util")]
+            if let Some(seeded) = &pool.seeded {
+                seeded.lock().expect(SEEDED_LOCK_POISONED).tasks.push(task);
+                return Pool::wake_one(inner);
+            }
+            match node {
+                Some(node) => pool.nodes[node].push(task),
+                None => pool.injector.push(task),
+            }
+            Pool::wake_one(inner)
+        }
+    }
+
+    /// Moves the older half of a full local queue to the queue of the node of its worker, or
+    /// to the global queue.
+    #[inline]
+    fn overflow(&self, local: &Local) {
+        let injector = match self.node_of(local.index) {
+            Some(node) => &self.nodes[node],
+            None => &self.injector,
+        };
+        let moved = iter::from_fn(|| local.queue.pop())
+            .take(LOCAL_QUEUE_CAPACITY / 2)
+            .map(|task| injector.push(task))
+            .count();
+        let stats = &self.stats[local.index];
+        stats.queued.fetch_sub(moved, Ordering::Relaxed);
+        stats.overflows.fetch_add(moved as u64, Ordering::Relaxed);
+        self.injected.fetch_add(moved, Ordering::Relaxed);
+    }
+
+    /// Stops all workers once they finish their running tasks, dropping the queued tasks.
+    pub(crate) fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Release);
+        self.unparkers.iter().for_each(Unparker::unpark);
+        for injector in iter::once(&self.injector).chain(&self.nodes) {
+            while let Some(task) = injector.steal().success() {
+                self.injected.fetch_sub(1, Ordering::Relaxed);
+                drop(task);
+            }
+        }
+        #[cfg(feature = "test-util")]
+        if let Some(seeded) = &self.seeded {
+            let tasks =
+                std::mem::take(&mut seeded.lock().expect(SEEDED_LOCK_POISONED).tasks);
+            self.injected.fetch_sub(tasks.len(), Ordering::Relaxed);
+            drop(tasks);
+        }
+    }
+
+    /// Finds a task for a worker: from its LIFO slot first, then its local queue, then the
+    /// queue of its NUMA node and the global queue, then the other workers of its node, then the
+    /// other nodes.
+    #[inline]
+    fn find_task(&self, local: &Local) -> Option<Task> {
+        let index = local.index;
+        if let Some(task) = local.lifo.take() {
+            if local.lifo_polls.get() < MAX_LIFO_POLLS {
+                local.lifo_polls.set(local.lifo_polls.get() + 1);
+                self.stats[index].queued.fetch_sub(1, Ordering::Relaxed);
+                return Some(task);
+            }
+            // give the local queue a turn
+            local.queue.push(task);
+        }
+        local.lifo_polls.set(0);
+        if let Some(task) = local.queue.pop() {
+            self.stats[index].queued.fetch_sub(1, Ordering::Relaxed);
+            return Some(task);
+        }
+        let node = self.node_of(index);
+        let local_node =
+            |&(victim, _): &(usize, &Stealer<Task>)| self.node_of(victim) == node;
+        loop {
+            let mut retry = false;
+            let injectors = node.map(|node| &self.nodes[node]).into_iter();
+            for injector in injectors.chain(iter::once(&self.injector)) {
+                if let Some(task) = self.steal_injected(injector, &mut retry) {
+                    return Some(task);
+                }
+            }
+            let victims = self.stealers.iter().enumerate();
+            let (near, far) = (victims.clone().filter(local_node), victims);
+            for (victim, stealer) in near.chain(far.filter(|v| !local_node(v))) {
+                match stealer.steal() {
+                    Steal::Success(task) => {
+                        self.stats[victim].queued.fetch_sub(1, Ordering::Relaxed);
+                        self.stats[index].steals.fetch_add(1, Ordering::Relaxed);
+                        return Some(task);
+                    }
+                    Steal::Retry => retry = true,
+                    Steal::Empty => (),
+                }
+            }
+            let others = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| Some(i) != node);
+            for (_, injector) in others {
+                if let Some(task) = self.steal_injected(injector, &mut retry) {
+                    return Some(task);
+                }
+            }
+            // loop while no task was stolen and any steal operation needs to be retried
+            if !retry {
+                return None;
+            }
+        }
+    }
+
+    #[inline]
+    fn steal_injected(
+        &self,
+        injector: &Injector<Task>,
+        retry: &mut bool,
+    ) -> Option<Task> {
+        match injector.steal() {
+            Steal::Success(task) => {
+                self.injected.fetch_sub(1, Ordering::Relaxed);
+                Some(task)
+            }
+            Steal::Retry => {
+                *retry = true;
+                None
+            }
+            Steal::Empty => None,
+        }
+    }
+
+    /// Runs a future to completion, running the queued tasks on the current thread meanwhile.
+    ///
+    /// This is how a pool without workers makes progress. Only the latest caller is woken by
+    /// newly scheduled tasks, so concurrent callers take turns to drive the pool.
+    pub(crate) async fn run_until<F>(inner: &Inner, fut: F) -> F::Output
+    where
+        F: Future,
+    {
+        let pool = &inner.pool;
+        pin_mut!(fut);
+        poll_fn(|cx| {
+            pool.driver.register(cx.waker());
+            pool.unnotify();
+            if let Poll::Ready(output) = fut.as_mut().poll(cx) {
+                return Poll::Ready(output);
+            }
+            match Pool::run_batch(inner) {
+                #[cfg(feature = "test-util")]
+                0 if inner.timer.advance() => (),
+                #[cfg(feature = "test-util")]
+                // the clock may only advance once nothing can run, which takes another poll
+                ran if ran > 0 && inner.timer.is_paused() => (),
+                ran if ran < DRIVE_BATCH => return Poll::Pending,
+                // yield to the caller before running the rest
+                _ => (),
+            }
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Runs the queued tasks on the current thread, waiting at most `max_wait` for a task to be
+    /// scheduled if there is none. Returns whether any task ran.
+    ///
+    /// Like `run_until`, this takes over the pool from any concurrent caller.
+    pub(crate) fn turn(inner: &Inner, max_wait: Option<Duration>) -> bool {
+        let pool = &inner.pool;
+        TURN.with(|(parker, waker)| {
+            pool.driver.register(waker);
+            pool.unnotify();
+            if Pool::run_batch(inner) > 0 {
+                return true;
+            }
+            #[cfg(feature = "test-util")]
+            if inner.timer.advance() {
+                return Pool::run_batch(inner) > 0;
+            }
+            match max_wait {
+                Some(dur) => parker.park_timeout(dur),
+                None => parker.park(),
+            }
+            pool.unnotify();
+            Pool::run_batch(inner) > 0
+        })
+    }
+
+    /// Runs at most `DRIVE_BATCH` queued tasks, returning how many ran.
+    #[inline]
+    fn run_batch(inner: &Inner) -> usize {
+        let pool = &inner.pool;
+        for ran in 0..DRIVE_BATCH {
+            #[cfg(feature = "timer")]
+            inner.tick();
+            match pool.pop_injected() {
+                Some(task) => {
+                    pool.injected.fetch_sub(1, Ordering::Relaxed);
+                    let _poll = inner
+                        .watchdog
+                        .as_ref()
+                        .map(|watchdog| watchdog.start(None, task.tag().info()));
+                    tag::run(task)
+                }
+                None => return ran,
+            }
+        }
+        DRIVE_BATCH
+    }
+
+    #[inline]
+    fn pop_injected(&self) -> Option<Task> {
+        #[cfg(feature = "test-util")]
+        if let Some(seeded) = &self.seeded {
+            return seeded.lock().expect(SEEDED_LOCK_POISONED).pop();
+        }
+        iter::repeat_with(|| self.injector.steal())
+            .find(|s| !s.is_retry())
+            .and_then(Steal::success)
+    }
+}
+
+/// Defers the wakes of the sleeping workers and of the drivers issued by this thread until it
+/// is dropped, so that a thread waking many tasks at once, like the poll thread, wakes the
+/// sleepers of a runtime in one pass and its driver once.
+///
+/// A batch started in another one does nothing.
+#[cfg(feature = "timer")]
+pub(crate) struct Batch(bool);
+
+#[cfg(feature = "timer")]
+impl Batch {
+    #[inline]
+    pub(crate) fn start() -> Self {
+        Self(DEFERRED.with(|deferred| {
+            let mut deferred = deferred.borrow_mut();
+            deferred.is_none() && deferred.replace(Vec::new()).is_none()
+        }))
+    }
+}
+
+#[cfg(feature = "timer")]
+impl Drop for Batch {
+    #[inline]
+    fn drop(&mut self) {
+        if self.0 {
+            // out of the borrow, waking a driver may schedule another task
+            let deferred = DEFERRED.with(|deferred| deferred.borrow_mut().take());
+            for (inner, nums) in deferred.into_iter().flatten() {
+                inner.pool.wake(nums)
+            }
+        }
+    }
+}
+
+/// Returns the default number of worker threads, which is the number of CPUs unless it is
+/// overridden by `TIO_WORKER_THREADS`.
+pub(crate) fn default_worker_threads() -> usize {
+    env::var(WORKER_THREADS_ENV)
+        .ok()
+        .and_then(|nums| nums.trim().parse().ok())
+        .filter(|&nums| nums > 0)
+        .unwrap_or_else(num_cpus::get)
+}
+
+/// Starts a worker thread which runs the tasks of `inner`.
+pub(crate) fn start_worker(
+    inner: &Arc<Inner>,
+    index: usize,
+    queue: Worker<Task>,
+    parker: Parker,
+) -> io::Result<()> {
+    inner.spawn_thread(&format!("async{}", index), move |inner| {
+        let pool = &inner.pool;
+        WORKER.with(|current| {
+            *current.borrow_mut() = Some(Local::new(inner.clone(), index, queue, parker))
+        });
+        // when the worker ran out of tasks, while it busy-polls
+        let mut idle = None;
+        while !pool.shutdown.load(Ordering::Acquire) {
+            #[cfg(feature = "timer")]
+            inner.tick();
+            let task = WORKER.with(|current| {
+                current.borrow().as_ref().map(|local| pool.find_task(local))
+            });
+            match task {
+                // the queue is handed off by `block_in_place`, this thread is done
+                None => return,
+                Some(Some(task)) => {
+                    idle = None;
+                    pool.stats[index].polls.fetch_add(1, Ordering::Relaxed);
+                    let _poll = inner
+                        .watchdog
+                        .as_ref()
+                        .map(|watchdog| watchdog.start(Some(index), task.tag().info()));
+                    tag::run(task);
+                    let deferred = WORKER.with(|current| {
+                        current.borrow().as_ref().map(|local| local.deferred.take())
+                    });
+                    if let Some(nums @ 1..) = deferred {
+                        pool.wake(nums)
+                    }
+                }
+                Some(None) => {
+                    if let Some(dur) = inner.config.busy_poll {
+                        let since = *idle.get_or_insert_with(Instant::now);
+                        if since.elapsed() < dur {
+                            hint::spin_loop();
+                            continue;
+                        }
+                        idle = None;
+                    }
+                    if pool.sleep(index) {
+                        inner.config.hooks.park();
+                        WORKER.with(|current| {
+                            current.borrow().as_ref().map(|local| local.parker.park())
+                        });
+                        inner.config.hooks.unpark();
+                    }
+                }
+            }
+        }
+        // drop the tasks out of the borrow, dropping a task may schedule another one
+        let local = WORKER.with(|current| current.borrow_mut().take());
+        if let Some(local) = local {
+            if local.lifo.take().is_some() {
+                pool.stats[index].queued.fetch_sub(1, Ordering::Relaxed);
+            }
+            while let Some(task) = local.queue.pop() {
+                pool.stats[index].queued.fetch_sub(1, Ordering::Relaxed);
+                drop(task);
+            }
+        }
+    })
+}
+
+/// Returns whether the current thread is a worker, which has not handed its queue off.
+#[cfg(all(debug_assertions, feature = "detect-blocking"))]
+#[inline]
+pub(crate) fn is_worker() -> bool {
+    WORKER.with(|current| current.try_borrow().map_or(true, |local| local.is_some()))
+}
+
+/// Hands the local queue of the current worker off to a new worker thread.
+///
+/// The current thread keeps running its task but stops being a worker once the task yields.
+/// Returns `false` if the current thread is not a worker.
+pub(crate) fn hand_off() -> bool {
+    match WORKER.with(|current| current.borrow_mut().take()) {
+        Some(local) => {
+            if let Some(task) = local.lifo.take() {
+                local.queue.push(task);
+            }
+            start_worker(&local.inner, local.index, local.queue, local.parker)
+                .expect("fail to start a worker thread");
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::Builder;
+    use crate::task;
+    use std::sync::{Arc, Mutex};
+
+    fn spawn_order(builder: Builder, nums: usize) -> Vec<usize> {
+        let runtime = builder.build().unwrap();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let spawner = {
+            let order = order.clone();
+            runtime.spawn(async move {
+                (0..nums)
+                    .map(|i| {
+                        let order = order.clone();
+                        task::spawn(async move { order.lock().unwrap().push(i) })
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+        runtime.block_on(async {
+            for handle in spawner.await.unwrap() {
+                handle.await.unwrap()
+            }
+        });
+        let order = order.lock().unwrap().clone();
+        order
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn seeded() {
+        let seeded = |seed| spawn_order(Builder::new_current_thread().seed(seed), 16);
+        assert_eq!(seeded(7), seeded(7));
+        assert_ne!(seeded(7), seeded(8));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn paused_idle() {
+        use futures::FutureExt;
+
+        // waiting for a blocking task on a paused clock doesn't spin the driver
+        let runtime = Builder::new_current_thread().seed(0).build().unwrap();
+        let polls = runtime.block_on(async {
+            let mut polls = 0;
+            let mut handle = task::spawn_blocking(|| {
+                std::thread::sleep(std::time::Duration::from_millis(50))
+            });
+            futures::future::poll_fn(|cx| {
+                polls += 1;
+                handle.poll_unpin(cx)
+            })
+            .await
+            .unwrap();
+            polls
+        });
+        assert!(polls < 10, "polled {} times", polls);
+    }
+
+    #[test]
+    fn lifo_slot() {
+        // the task scheduled last runs first, then the others in order
+        let builder = Builder::new().worker_threads(1);
+        assert_eq!(vec![2, 0, 1], spawn_order(builder, 3));
+        let builder = Builder::new().worker_threads(1).disable_lifo_slot();
+        assert_eq!(vec![0, 1, 2], spawn_order(builder, 3));
+    }
+
+    #[test]
+    fn deferred_wakes() {
+        // the sleeping worker is woken once the spawner yields, and steals
+        let runtime = Builder::new().worker_threads(2).build().unwrap();
+        let threads = runtime.block_on(runtime.spawn(async {
+            let handles = (0..16)
+                .map(|_| {
+                    task::spawn(async {
+                        std::thread::sleep(std::time::Duration::from_millis(5));
+                        std::thread::current().id()
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut threads = Vec::new();
+            for handle in handles {
+                threads.push(handle.await.unwrap())
+            }
+            threads
+        }));
+        let mut threads = threads.unwrap();
+        threads.dedup();
+        assert!(threads.len() > 1);
+    }
+
+    #[test]
+    fn overflow() {
+        // a task spawning more than a local queue holds overflows into the injector
+        let runtime = Builder::new().worker_threads(1).build().unwrap();
+        let sum = runtime.block_on(runtime.spawn(async {
+            let handles = (0..1000)
+                .map(|i| task::spawn(async move { i }))
+                .collect::<Vec<_>>();
+            let mut sum = 0;
+            for handle in handles {
+                sum += handle.await.unwrap()
+            }
+            sum
+        }));
+        assert_eq!((0..1000).sum::<usize>(), sum.unwrap());
+        let metrics = runtime.metrics();
+        assert!(metrics.worker_overflow_count(0) > 0);
+        assert_eq!(0, metrics.worker_local_queue_depth(0));
+        assert_eq!(0, metrics.global_queue_depth());
+    }
+
+    #[test]
+    fn busy_poll() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let parked = |dur| {
+            let parks = Arc::new(AtomicUsize::new(0));
+            let runtime = Builder::new()
+                .worker_threads(1)
+                .busy_poll(dur)
+                .on_thread_park({
+                    let parks = parks.clone();
+                    move || {
+                        parks.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+                .build()
+                .unwrap();
+            assert_eq!(1, runtime.block_on(runtime.spawn(async { 1 })).unwrap());
+            std::thread::sleep(Duration::from_millis(100));
+            parks.load(Ordering::Relaxed) > 0
+        };
+        // the worker spins instead of parking, then parks once the duration elapses
+        assert!(!parked(Duration::from_secs(60)));
+        assert!(parked(Duration::from_millis(1)));
+    }
+
+    #[cfg(feature = "timer")]
+    #[test]
+    fn batch() {
+        use super::Batch;
+        use std::sync::atomic::Ordering;
+
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let pool = &runtime.handle().inner.pool;
+        let handles = {
+            let _batch = Batch::start();
+            let handles = (0..16)
+                .map(|i| runtime.spawn(async move { i }))
+                .collect::<Vec<_>>();
+            // the driver is woken once the batch ends
+            assert!(!pool.notified.load(Ordering::Acquire));
+            handles
+        };
+        assert!(pool.notified.load(Ordering::Acquire));
+        let outputs = runtime.block_on(async {
+            let mut outputs = Vec::new();
+            for handle in handles {
+                outputs.push(handle.await.unwrap())
+            }
+            outputs
+        });
+        assert_eq!((0..16).collect::<Vec<_>>(), outputs);
+        assert!(!pool.notified.load(Ordering::Acquire));
+    }
+}
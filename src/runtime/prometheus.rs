@@ -0,0 +1,329 @@
+use super::RuntimeMetrics;
+use std::fmt::{self, Display, Formatter, Write};
+
+/// The type of a [`MetricFamily`].
+///
+/// [`MetricFamily`]: struct.MetricFamily.html
+#[cfg_attr(feature = "docs", doc(cfg(feature = "prometheus")))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MetricType {
+    /// A value which only goes up, like a number of polls.
+    Counter,
+
+    /// A value which goes up and down, like the depth of a queue.
+    Gauge,
+}
+
+impl MetricType {
+    #[inline]
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        }
+    }
+}
+
+/// A sample of a [`MetricFamily`], identified by its labels.
+///
+/// [`MetricFamily`]: struct.MetricFamily.html
+#[cfg_attr(feature = "docs", doc(cfg(feature = "prometheus")))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    labels: Vec<(&'static str, String)>,
+    value: f64,
+}
+
+impl Metric {
+    /// Returns the labels of this sample, by name.
+    #[inline]
+    pub fn labels(&self) -> &[(&'static str, String)] {
+        &self.labels
+    }
+
+    /// Returns the value of this sample.
+    #[inline]
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+/// A family of metrics of a runtime, returned by [`RuntimeMetrics::gather`].
+///
+/// A family displays in the text exposition format of Prometheus, with its `HELP` and `TYPE`
+/// lines, so the families of a gathering concatenated are the body of a scrape.
+///
+/// [`RuntimeMetrics::gather`]: struct.RuntimeMetrics.html#method.gather
+#[cfg_attr(feature = "docs", doc(cfg(feature = "prometheus")))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricFamily {
+    name: &'static str,
+    help: &'static str,
+    metric_type: MetricType,
+    metrics: Vec<Metric>,
+}
+
+impl MetricFamily {
+    #[inline]
+    fn new(name: &'static str, help: &'static str, metric_type: MetricType) -> Self {
+        Self {
+            name,
+            help,
+            metric_type,
+            metrics: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn with(mut self, labels: Vec<(&'static str, String)>, value: f64) -> Self {
+        self.metrics.push(Metric { labels, value });
+        self
+    }
+
+    /// Returns the name of this family, prefixed by `tio_`.
+    #[inline]
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Returns the description of this family.
+    #[inline]
+    pub fn help(&self) -> &str {
+        self.help
+    }
+
+    /// Returns the type of this family.
+    #[inline]
+    pub fn metric_type(&self) -> MetricType {
+        self.metric_type
+    }
+
+    /// Returns the samples of this family.
+    #[inline]
+    pub fn metrics(&self) -> &[Metric] {
+        &self.metrics
+    }
+}
+
+impl Display for MetricFamily {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("# HELP ")?;
+        f.write_str(self.name)?;
+        f.write_char(' ')?;
+        escape(f, self.help, false)?;
+        writeln!(f, "\n# TYPE {} {}", self.name, self.metric_type.as_str())?;
+        for metric in &self.metrics {
+            f.write_str(self.name)?;
+            if !metric.labels.is_empty() {
+                f.write_char('{')?;
+                for (i, (name, value)) in metric.labels.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}=\"", name)?;
+                    escape(f, value, true)?;
+                    f.write_char('"')?;
+                }
+                f.write_char('}')?;
+            }
+            writeln!(f, " {}", metric.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes a text of the exposition format, a label value if `quoted`.
+fn escape(f: &mut Formatter<'_>, text: &str, quoted: bool) -> fmt::Result {
+    for c in text.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '"' if quoted => f.write_str("\\\"")?,
+            c => f.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+impl RuntimeMetrics {
+    /// Gathers the metrics of the runtime and of its drivers as metric families of
+    /// Prometheus.
+    ///
+    /// The per-worker metrics are labeled by the `worker` index. The metrics of a disabled
+    /// driver are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::runtime::{Builder, MetricType};
+    ///
+    /// let runtime = Builder::new().worker_threads(2).build().unwrap();
+    /// runtime.block_on(runtime.spawn(async {})).unwrap();
+    ///
+    /// let families = runtime.metrics().gather();
+    /// let polls = families
+    ///     .iter()
+    ///     .find(|family| family.name() == "tio_worker_polls_total")
+    ///     .unwrap();
+    /// assert_eq!(MetricType::Counter, polls.metric_type());
+    /// assert_eq!(2, polls.metrics().len());
+    ///
+    /// // the body of a scrape
+    /// let body = families.iter().map(ToString::to_string).collect::<String>();
+    /// assert!(body.contains("# TYPE tio_alive_tasks gauge\ntio_alive_tasks 0\n"));
+    /// ```
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "prometheus")))]
+    pub fn gather(&self) -> Vec<MetricFamily> {
+        use MetricType::Gauge;
+
+        let mut families = vec![MetricFamily::new(
+            "tio_alive_tasks",
+            "Number of the tasks spawned and not dropped yet.",
+            Gauge,
+        )
+        .with(Vec::new(), self.num_alive_tasks() as f64)];
+        #[cfg(feature = "async-rt")]
+        {
+            let workers = self.num_workers();
+            let per_worker = |name, help, metric_type, value: &dyn Fn(usize) -> f64| {
+                (0..workers).fold(
+                    MetricFamily::new(name, help, metric_type),
+                    |family, worker| {
+                        family.with(vec![("worker", worker.to_string())], value(worker))
+                    },
+                )
+            };
+            families.extend(vec![
+                MetricFamily::new("tio_workers", "Number of the worker threads.", Gauge)
+                    .with(Vec::new(), workers as f64),
+                MetricFamily::new(
+                    "tio_global_queue_depth",
+                    "Number of the tasks in the global queue.",
+                    Gauge,
+                )
+                .with(Vec::new(), self.global_queue_depth() as f64),
+                per_worker(
+                    "tio_worker_local_queue_depth",
+                    "Number of the tasks in the local queue of a worker.",
+                    Gauge,
+                    &|worker| self.worker_local_queue_depth(worker) as f64,
+                ),
+                per_worker(
+                    "tio_worker_polls_total",
+                    "Number of the polls of the tasks by a worker.",
+                    MetricType::Counter,
+                    &|worker| self.worker_poll_count(worker) as f64,
+                ),
+                per_worker(
+                    "tio_worker_steals_total",
+                    "Number of the tasks stolen by a worker.",
+                    MetricType::Counter,
+                    &|worker| self.worker_steal_count(worker) as f64,
+                ),
+                per_worker(
+                    "tio_worker_overflows_total",
+                    "Number of the tasks overflowed from the local queue of a worker.",
+                    MetricType::Counter,
+                    &|worker| self.worker_overflow_count(worker) as f64,
+                ),
+            ]);
+        }
+        families.extend(vec![
+            MetricFamily::new(
+                "tio_blocking_threads",
+                "Number of the threads of the blocking pool.",
+                Gauge,
+            )
+            .with(Vec::new(), self.num_blocking_threads() as f64),
+            MetricFamily::new(
+                "tio_idle_blocking_threads",
+                "Number of the idle threads of the blocking pool.",
+                Gauge,
+            )
+            .with(Vec::new(), self.num_idle_blocking_threads() as f64),
+            MetricFamily::new(
+                "tio_blocking_queue_depth",
+                "Number of the blocking tasks waiting for a thread.",
+                Gauge,
+            )
+            .with(Vec::new(), self.blocking_queue_depth() as f64),
+        ]);
+        #[cfg(feature = "timer")]
+        if self.inner.config.enable_time {
+            families.extend(vec![
+                MetricFamily::new("tio_timers", "Number of the pending timers.", Gauge)
+                    .with(Vec::new(), self.num_timers() as f64),
+                MetricFamily::new(
+                    "tio_timer_fires_total",
+                    "Number of the timers fired.",
+                    MetricType::Counter,
+                )
+                .with(Vec::new(), self.timer_fire_count() as f64),
+            ]);
+        }
+        #[cfg(feature = "event-loop")]
+        if self.inner.config.enable_io {
+            families.extend(vec![
+                MetricFamily::new(
+                    "tio_io_registrations",
+                    "Number of the I/O sources registered.",
+                    Gauge,
+                )
+                .with(Vec::new(), self.num_io_registrations() as f64),
+                MetricFamily::new(
+                    "tio_io_events_total",
+                    "Number of the readiness events received.",
+                    MetricType::Counter,
+                )
+                .with(Vec::new(), self.io_event_count() as f64),
+            ]);
+        }
+        families
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MetricFamily, MetricType};
+
+    #[test]
+    fn text() {
+        let family = MetricFamily::new("tio_test", "A test\\metric.", MetricType::Gauge)
+            .with(
+                vec![("name", "a\"b\nc".to_string()), ("worker", "1".to_string())],
+                1.5,
+            )
+            .with(Vec::new(), 2.);
+        assert_eq!(
+            "# HELP tio_test A test\\\\metric.\n\
+             # TYPE tio_test gauge\n\
+             tio_test{name=\"a\\\"b\\nc\",worker=\"1\"} 1.5\n\
+             tio_test 2\n",
+            family.to_string()
+        );
+    }
+
+    #[cfg(all(feature = "async-rt", feature = "timer"))]
+    #[test]
+    fn gather() {
+        use crate::runtime::Builder;
+
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let metrics = runtime.metrics();
+        let _handle = runtime.spawn(async {});
+        let families = metrics.gather();
+        let value = |name| {
+            families
+                .iter()
+                .find(|family| family.name() == name)
+                .map(|family| family.metrics()[0].value())
+        };
+        assert_eq!(Some(1.), value("tio_alive_tasks"));
+        assert_eq!(Some(1.), value("tio_global_queue_depth"));
+        assert_eq!(Some(0.), value("tio_workers"));
+        assert_eq!(Some(0.), value("tio_timers"));
+        let runtime = Builder::new().enable_time(false).build().unwrap();
+        let families = runtime.metrics().gather();
+        assert!(families.iter().all(|family| family.name() != "tio_timers"));
+    }
+}
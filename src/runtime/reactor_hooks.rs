@@ -0,0 +1,212 @@
+use std::fmt::{self, Debug, Formatter};
+use std::os::unix::io::RawFd;
+
+/// Hooks into the I/O driver, installed by [`Builder::reactor_hooks`].
+///
+/// The hooks are called synchronously: [`on_event`] by the poll thread for every event,
+/// before the tasks are woken, and the others by the thread registering or dropping the
+/// source. They are meant for cheap telemetry, like counting the events by descriptor, where
+/// the `trace-log` records are too heavy; a slow hook delays every source of the runtime.
+///
+/// Every method does nothing by default.
+///
+/// [`Builder::reactor_hooks`]: struct.Builder.html#method.reactor_hooks
+/// [`on_event`]: #method.on_event
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// use std::os::unix::io::RawFd;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use tio::net::UdpSocket;
+/// use tio::runtime::{Builder, ReactorHooks, Readiness};
+///
+/// #[derive(Default)]
+/// struct Counter(AtomicUsize);
+///
+/// impl ReactorHooks for Counter {
+///     fn on_event(&self, _fd: RawFd, readiness: Readiness) {
+///         if readiness.is_readable() {
+///             self.0.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// }
+///
+/// let counter = Arc::new(Counter::default());
+/// let runtime = Builder::new().reactor_hooks(counter.clone()).build()?;
+/// runtime.block_on(async {
+///     let socket = UdpSocket::bind("127.0.0.1:0")?;
+///     let addr = socket.local_addr()?;
+///     let sender = tio::task::spawn(async move {
+///         tio::task::sleep(std::time::Duration::from_millis(10)).await;
+///         UdpSocket::bind("127.0.0.1:0")?.send_to(b"ping", addr).await
+///     });
+///     socket.recv_from(&mut [0; 4]).await?;
+///     sender.await.unwrap().map(drop)
+/// })?;
+/// assert!(counter.0.load(Ordering::Relaxed) >= 1);
+/// # Ok(()) }
+/// ```
+#[cfg_attr(feature = "docs", doc(cfg(feature = "event-loop")))]
+pub trait ReactorHooks: Send + Sync + 'static {
+    /// Called when the source of `fd` is reported ready by the OS.
+    #[inline]
+    fn on_event(&self, fd: RawFd, readiness: Readiness) {
+        let _ = (fd, readiness);
+    }
+
+    /// Called when the source of `fd` is registered to the driver, before any of its events.
+    #[inline]
+    fn on_register(&self, fd: RawFd) {
+        let _ = fd;
+    }
+
+    /// Called when the source of `fd` is deregistered from the driver, before it is closed.
+    #[inline]
+    fn on_deregister(&self, fd: RawFd) {
+        let _ = fd;
+    }
+}
+
+impl<H: ReactorHooks> ReactorHooks for std::sync::Arc<H> {
+    #[inline]
+    fn on_event(&self, fd: RawFd, readiness: Readiness) {
+        (**self).on_event(fd, readiness)
+    }
+
+    #[inline]
+    fn on_register(&self, fd: RawFd) {
+        (**self).on_register(fd)
+    }
+
+    #[inline]
+    fn on_deregister(&self, fd: RawFd) {
+        (**self).on_deregister(fd)
+    }
+}
+
+/// The readiness of an event of the I/O driver, passed to [`ReactorHooks::on_event`].
+///
+/// [`ReactorHooks::on_event`]: trait.ReactorHooks.html#method.on_event
+#[cfg_attr(feature = "docs", doc(cfg(feature = "event-loop")))]
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct Readiness(u8);
+
+const READABLE: u8 = 1;
+const WRITABLE: u8 = 1 << 1;
+const READ_CLOSED: u8 = 1 << 2;
+const WRITE_CLOSED: u8 = 1 << 3;
+const ERROR: u8 = 1 << 4;
+
+impl Readiness {
+    #[inline]
+    pub(crate) fn new(event: &mio::event::Event) -> Self {
+        let flags = [
+            (event.is_readable(), READABLE),
+            (event.is_writable(), WRITABLE),
+            (event.is_read_closed(), READ_CLOSED),
+            (event.is_write_closed(), WRITE_CLOSED),
+            (event.is_error(), ERROR),
+        ];
+        Self(
+            flags
+                .iter()
+                .filter(|(set, _)| *set)
+                .fold(0, |bits, (_, flag)| bits | flag),
+        )
+    }
+
+    /// Returns whether the source is readable.
+    #[inline]
+    pub fn is_readable(self) -> bool {
+        self.0 & READABLE != 0
+    }
+
+    /// Returns whether the source is writable.
+    #[inline]
+    pub fn is_writable(self) -> bool {
+        self.0 & WRITABLE != 0
+    }
+
+    /// Returns whether the read half of the source is closed, like by a FIN of the peer.
+    #[inline]
+    pub fn is_read_closed(self) -> bool {
+        self.0 & READ_CLOSED != 0
+    }
+
+    /// Returns whether the write half of the source is closed.
+    #[inline]
+    pub fn is_write_closed(self) -> bool {
+        self.0 & WRITE_CLOSED != 0
+    }
+
+    /// Returns whether the source has a pending error.
+    #[inline]
+    pub fn is_error(self) -> bool {
+        self.0 & ERROR != 0
+    }
+}
+
+impl Debug for Readiness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Readiness")
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .field("read_closed", &self.is_read_closed())
+            .field("write_closed", &self.is_write_closed())
+            .field("error", &self.is_error())
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "udp"))]
+mod tests {
+    use super::{ReactorHooks, Readiness};
+    use crate::net::UdpSocket;
+    use crate::runtime::Builder;
+    use std::os::unix::io::{AsRawFd, RawFd};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(&'static str, RawFd)>>);
+
+    impl ReactorHooks for Recorder {
+        fn on_event(&self, fd: RawFd, readiness: Readiness) {
+            if readiness.is_writable() {
+                self.0.lock().unwrap().push(("event", fd))
+            }
+        }
+
+        fn on_register(&self, fd: RawFd) {
+            self.0.lock().unwrap().push(("register", fd))
+        }
+
+        fn on_deregister(&self, fd: RawFd) {
+            self.0.lock().unwrap().push(("deregister", fd))
+        }
+    }
+
+    #[test]
+    fn hooks() {
+        let recorder = Arc::new(Recorder::default());
+        let runtime = Builder::new()
+            .reactor_hooks(recorder.clone())
+            .build()
+            .unwrap();
+        let fd = runtime.block_on(async {
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let fd = socket.as_raw_fd();
+            // the first writable event
+            while !recorder.0.lock().unwrap().contains(&("event", fd)) {
+                crate::task::yield_now().await;
+            }
+            fd
+        });
+        let records = recorder.0.lock().unwrap();
+        assert_eq!(("register", fd), records[0]);
+        // an event may be reported after the deregistration, by a poll preceding it
+        assert!(records[1..].contains(&("deregister", fd)));
+    }
+}
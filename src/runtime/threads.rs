@@ -0,0 +1,65 @@
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+const THREADS_LOCK_POISONED: &str = "threads lock poisoned";
+
+/// Counts the live threads of a runtime, to join them on shutdown.
+pub(crate) struct Threads {
+    live: Mutex<usize>,
+    stopped: Condvar,
+}
+
+/// Marks a thread as stopped when dropped.
+pub(crate) struct ThreadGuard<'a>(&'a Threads);
+
+impl Drop for ThreadGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        let mut live = self.0.live.lock().expect(THREADS_LOCK_POISONED);
+        *live -= 1;
+        if *live == 0 {
+            self.0.stopped.notify_all()
+        }
+    }
+}
+
+impl Threads {
+    pub(crate) fn new() -> Self {
+        Self {
+            live: Mutex::new(0),
+            stopped: Condvar::new(),
+        }
+    }
+
+    /// Counts a thread which is about to be spawned.
+    #[inline]
+    pub(crate) fn start(&self) {
+        *self.live.lock().expect(THREADS_LOCK_POISONED) += 1;
+    }
+
+    /// Returns a guard which marks a thread counted by `start` as stopped.
+    ///
+    /// It should be held by the thread, or dropped at once if the thread fails to spawn.
+    #[inline]
+    pub(crate) fn guard(&self) -> ThreadGuard<'_> {
+        ThreadGuard(self)
+    }
+
+    /// Waits until every thread stops, returning `false` on timeout.
+    pub(crate) fn join(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut live = self.live.lock().expect(THREADS_LOCK_POISONED);
+        while *live > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            live = self
+                .stopped
+                .wait_timeout(live, deadline - now)
+                .expect(THREADS_LOCK_POISONED)
+                .0;
+        }
+        true
+    }
+}
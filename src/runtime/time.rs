@@ -0,0 +1,444 @@
+use super::{context, Inner};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+const TIMER_LOCK_POISONED: &str = "timer lock poisoned";
+
+/// The time driver of a runtime, a thread waking the delays once they elapse.
+///
+/// The thread is started on the first delay. A paused timer has no thread, its virtual clock
+/// is advanced by the scheduler instead. With a granularity, the deadlines are rounded up to
+/// its multiples, so the delays of a bucket are registered at the same instant and fired by
+/// one wakeup.
+pub(crate) struct Timer {
+    state: Mutex<State>,
+    changed: Condvar,
+    granularity: Duration,
+    // the origin of the buckets
+    origin: Instant,
+    // the coarse clock, out of a paused timer
+    clock: Option<Clock>,
+}
+
+#[derive(Default)]
+struct State {
+    // keyed by the deadline first, so the first entry is the next to fire
+    delays: BTreeMap<(Instant, u64), Waker>,
+    next_id: u64,
+    fired: u64,
+    // the virtual clock of a paused timer
+    now: Option<Instant>,
+    started: bool,
+    shutdown: bool,
+}
+
+impl Timer {
+    pub(crate) fn new(paused: bool, granularity: Duration, coarse: bool) -> Self {
+        let origin = Instant::now();
+        Self {
+            state: Mutex::new(State {
+                now: if paused { Some(origin) } else { None },
+                ..State::default()
+            }),
+            changed: Condvar::new(),
+            granularity,
+            origin,
+            clock: if coarse && !paused {
+                Some(Clock::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Returns the current instant of the clock of this timer.
+    #[inline]
+    fn now(&self) -> Instant {
+        if let Some(clock) = &self.clock {
+            return clock.now();
+        }
+        let now = self.state.lock().expect(TIMER_LOCK_POISONED).now;
+        now.unwrap_or_else(Instant::now)
+    }
+
+    /// Returns the deadline of a delay of `dur` from now, rounded up to the granularity.
+    fn deadline(&self, dur: Duration) -> Instant {
+        let deadline = self.now() + dur;
+        if self.granularity.is_zero() {
+            return deadline;
+        }
+        let rem = (deadline - self.origin).as_nanos() % self.granularity.as_nanos();
+        if rem == 0 {
+            deadline
+        } else {
+            deadline + (self.granularity - Duration::from_nanos(rem as u64))
+        }
+    }
+
+    /// Returns the instant cached by the coarse clock, if it is enabled.
+    #[inline]
+    pub(crate) fn coarse_now(&self) -> Option<Instant> {
+        self.clock.as_ref().map(Clock::now)
+    }
+
+    /// Refreshes the coarse clock if it is enabled, returning the real instant.
+    #[inline]
+    pub(crate) fn tick(&self) -> Instant {
+        match &self.clock {
+            Some(clock) => clock.tick(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Returns the number of the pending delays, and how many delays have fired.
+    #[inline]
+    pub(crate) fn counts(&self) -> (usize, u64) {
+        let state = self.state.lock().expect(TIMER_LOCK_POISONED);
+        (state.delays.len(), state.fired)
+    }
+
+    #[cfg(feature = "test-util")]
+    #[inline]
+    pub(crate) fn is_paused(&self) -> bool {
+        self.state.lock().expect(TIMER_LOCK_POISONED).now.is_some()
+    }
+
+    /// Advances a paused clock to the next deadline, waking the delays which elapse.
+    ///
+    /// Returns `false` if the timer is not paused or there is no delay.
+    #[cfg(feature = "test-util")]
+    pub(crate) fn advance(&self) -> bool {
+        let mut state = self.state.lock().expect(TIMER_LOCK_POISONED);
+        let deadline = match (state.now, state.delays.keys().next()) {
+            (Some(now), Some(&(deadline, _))) => now.max(deadline),
+            _ => return false,
+        };
+        state.now = Some(deadline);
+        let mut wakers = Vec::new();
+        while let Some(entry) = state.delays.first_entry() {
+            if entry.key().0 > deadline {
+                break;
+            }
+            wakers.push(entry.remove());
+        }
+        state.fired += wakers.len() as u64;
+        drop(state);
+        trace_event!(target: "tio::timer", "advance to fire {} delays", wakers.len());
+        let _batch = super::pool::Batch::start();
+        wakers.into_iter().for_each(Waker::wake);
+        true
+    }
+
+    /// Registers the waker of a delay, returning its id.
+    fn register(
+        inner: &Arc<Inner>,
+        id: Option<u64>,
+        deadline: Instant,
+        waker: &Waker,
+    ) -> u64 {
+        let timer = &inner.timer;
+        let mut state = timer.state.lock().expect(TIMER_LOCK_POISONED);
+        assert!(!state.shutdown, "the runtime is shut down");
+        let id = id.unwrap_or_else(|| {
+            state.next_id += 1;
+            state.next_id
+        });
+        let earliest = state.delays.keys().next().map(|&(deadline, _)| deadline);
+        state.delays.insert((deadline, id), waker.clone());
+        if state.now.is_some() {
+            // paused, the delay is woken by `advance` instead of the thread
+            return id;
+        }
+        if !state.started {
+            state.started = true;
+            drop(state);
+            start_thread(inner);
+        } else if earliest.is_none_or(|earliest| deadline < earliest) {
+            timer.changed.notify_one()
+        }
+        id
+    }
+
+    #[inline]
+    fn cancel(&self, id: u64, deadline: Instant) {
+        let waker = self
+            .state
+            .lock()
+            .expect(TIMER_LOCK_POISONED)
+            .delays
+            .remove(&(deadline, id));
+        // dropping a waker may drop a task, which may cancel another delay
+        drop(waker)
+    }
+
+    /// Stops the thread and drops the pending delays.
+    pub(crate) fn shutdown(&self) {
+        let delays = {
+            let mut state = self.state.lock().expect(TIMER_LOCK_POISONED);
+            state.shutdown = true;
+            mem::take(&mut state.delays)
+        };
+        self.changed.notify_all();
+        drop(delays)
+    }
+}
+
+fn start_thread(inner: &Arc<Inner>) {
+    let ret = inner.spawn_thread("timer", |inner| {
+        let timer = &inner.timer;
+        let mut state = timer.state.lock().expect(TIMER_LOCK_POISONED);
+        let mut wakers = Vec::new();
+        while !state.shutdown {
+            let now = timer.tick();
+            while let Some(entry) = state.delays.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+                wakers.push(entry.remove());
+            }
+            if !wakers.is_empty() {
+                state.fired += wakers.len() as u64;
+                drop(state);
+                trace_event!(target: "tio::timer", "fire {} delays", wakers.len());
+                #[cfg(feature = "async-rt")]
+                let batch = super::pool::Batch::start();
+                wakers.drain(..).for_each(Waker::wake);
+                #[cfg(feature = "async-rt")]
+                drop(batch);
+                state = timer.state.lock().expect(TIMER_LOCK_POISONED);
+                continue;
+            }
+            state = match state.delays.keys().next() {
+                Some(&(deadline, _)) => {
+                    timer
+                        .changed
+                        .wait_timeout(state, deadline - now)
+                        .expect(TIMER_LOCK_POISONED)
+                        .0
+                }
+                None => timer.changed.wait(state).expect(TIMER_LOCK_POISONED),
+            }
+        }
+    });
+    if let Err(err) = ret {
+        panic!("cannot start a timer thread: {}", err);
+    }
+}
+
+/// An instant cached by the threads of a runtime, refreshed by `tick`.
+struct Clock {
+    base: Instant,
+    elapsed: AtomicU64,
+}
+
+impl Clock {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            elapsed: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the cached instant.
+    #[inline]
+    fn now(&self) -> Instant {
+        self.base + Duration::from_nanos(self.elapsed.load(Ordering::Relaxed))
+    }
+
+    /// Refreshes the cached instant, returning the real one.
+    #[inline]
+    fn tick(&self) -> Instant {
+        let now = Instant::now();
+        let elapsed = (now - self.base).as_nanos().try_into().unwrap_or(u64::MAX);
+        self.elapsed.fetch_max(elapsed, Ordering::Relaxed);
+        now
+    }
+}
+
+/// A future which completes after a duration, driven by the timer of the runtime of the
+/// current context.
+pub(crate) struct Delay {
+    inner: Arc<Inner>,
+    deadline: Instant,
+    id: Option<u64>,
+}
+
+impl Delay {
+    /// Creates a delay on the runtime of the current context.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the time driver of the runtime is disabled.
+    #[inline]
+    pub(crate) fn new(dur: Duration) -> Self {
+        let inner = context::current();
+        inner.ensure_time();
+        Self {
+            deadline: inner.timer.deadline(dur),
+            inner,
+            id: None,
+        }
+    }
+
+    /// Resets the delay to complete after `dur` from now.
+    #[inline]
+    pub(crate) fn reset(&mut self, dur: Duration) {
+        self.cancel();
+        self.deadline = self.inner.timer.deadline(dur);
+    }
+
+    #[inline]
+    fn cancel(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.inner.timer.cancel(id, self.deadline)
+        }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.inner.timer.now() >= self.deadline {
+            self.cancel();
+            return Poll::Ready(());
+        }
+        let id = Timer::register(&self.inner, self.id, self.deadline, cx.waker());
+        self.id = Some(id);
+        Poll::Pending
+    }
+}
+
+impl Drop for Delay {
+    #[inline]
+    fn drop(&mut self) {
+        self.cancel()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Delay, Timer};
+    use crate::runtime::Builder;
+    use crate::task;
+    use std::time::{Duration, Instant};
+
+    #[cfg(feature = "test-util")]
+    use futures::StreamExt;
+
+    #[test]
+    fn earlier_deadline() {
+        let runtime = Builder::new().build().unwrap();
+        let elapsed = runtime.block_on(async {
+            let late = task::spawn(Delay::new(Duration::from_secs(10)));
+            // the timer thread is waiting for the late delay by now
+            task::sleep(Duration::from_millis(10)).await;
+            let start = Instant::now();
+            Delay::new(Duration::from_millis(10)).await;
+            late.abort();
+            start.elapsed()
+        });
+        assert!(elapsed >= Duration::from_millis(10));
+        assert!(elapsed < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn isolated() {
+        let first = Builder::new().thread_name("first").build().unwrap();
+        let second = Builder::new().thread_name("second").build().unwrap();
+        let delay = {
+            let _enter = first.enter();
+            Delay::new(Duration::from_millis(10))
+        };
+        // the delay is driven by the first runtime wherever it is polled
+        second.block_on(delay);
+        drop(first);
+        second.block_on(task::sleep(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn granularity() {
+        let timer = Timer::new(false, Duration::from_secs(1), false);
+        let start = Instant::now();
+        let early = timer.deadline(Duration::from_millis(1));
+        // in the same bucket
+        assert_eq!(early, timer.deadline(Duration::from_millis(30)));
+        assert!(early >= start + Duration::from_millis(30));
+        assert_eq!(Duration::from_secs(1), early - timer.origin);
+        assert!(
+            timer.deadline(Duration::from_millis(1500)) - timer.origin
+                >= Duration::from_secs(2)
+        );
+
+        let exact = Timer::new(false, Duration::ZERO, false);
+        let deadline = exact.deadline(Duration::from_millis(1));
+        assert!(deadline - Instant::now() <= Duration::from_millis(1));
+
+        let runtime = Builder::new()
+            .timer_granularity(Duration::from_millis(20))
+            .build()
+            .unwrap();
+        let elapsed = runtime.block_on(async {
+            let start = Instant::now();
+            Delay::new(Duration::from_millis(5)).await;
+            start.elapsed()
+        });
+        assert!(elapsed >= Duration::from_millis(5));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn paused() {
+        let runtime = Builder::new_current_thread().seed(0).build().unwrap();
+        let start = Instant::now();
+        let order = runtime.block_on(async {
+            let late = task::spawn(async {
+                Delay::new(Duration::from_secs(20)).await;
+                2
+            });
+            let early = task::spawn(async {
+                Delay::new(Duration::from_secs(10)).await;
+                1
+            });
+            let mut interval = task::interval(Duration::from_secs(3));
+            for _ in 0..5 {
+                interval.next().await;
+            }
+            vec![early.await.unwrap(), late.await.unwrap()]
+        });
+        assert_eq!(vec![1, 2], order);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn coarse_clock() {
+        let timer = Timer::new(false, Duration::ZERO, true);
+        let cached = timer.now();
+        std::thread::sleep(Duration::from_millis(10));
+        // the clock keeps the cached instant until it is refreshed
+        assert_eq!(cached, timer.now());
+        let now = timer.tick();
+        assert_eq!(Some(now), timer.coarse_now());
+        assert!(now >= cached + Duration::from_millis(10));
+        assert!(Timer::new(true, Duration::ZERO, true)
+            .coarse_now()
+            .is_none());
+
+        let runtime = Builder::new().coarse_clock(true).build().unwrap();
+        let elapsed = runtime.block_on(async {
+            let start = Instant::now();
+            task::sleep(Duration::from_millis(10)).await;
+            assert!(task::now() >= start);
+            start.elapsed()
+        });
+        assert!(elapsed >= Duration::from_millis(10));
+    }
+}
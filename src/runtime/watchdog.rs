@@ -0,0 +1,175 @@
+use super::Inner;
+use crate::task::tag::Info;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+const WATCHDOG_LOCK_POISONED: &str = "watchdog lock poisoned";
+
+/// The poll of a task running on a thread of the pool.
+struct Running {
+    info: Arc<Info>,
+    start: Instant,
+    warned: bool,
+}
+
+/// A watchdog warning of the polls which take longer than a threshold.
+///
+/// Every worker records its running poll in its slot, the last slot is shared by the threads
+/// driving the pool. A thread scans the slots twice per threshold, so a poll which never
+/// returns is reported while it blocks the worker.
+pub(crate) struct Watchdog {
+    threshold: Duration,
+    slots: Vec<Mutex<Option<Running>>>,
+    shutdown: Mutex<bool>,
+    changed: Condvar,
+}
+
+/// Clears the slot of a poll once it returns, warning if it took too long.
+pub(crate) struct PollGuard<'a> {
+    watchdog: &'a Watchdog,
+    slot: usize,
+}
+
+impl Drop for PollGuard<'_> {
+    fn drop(&mut self) {
+        let running = self.watchdog.slots[self.slot]
+            .lock()
+            .expect(WATCHDOG_LOCK_POISONED)
+            .take();
+        if let Some(running) = running {
+            let elapsed = running.start.elapsed();
+            if elapsed >= self.watchdog.threshold {
+                log::warn!(
+                    "{} spawned at {} was polled for {:?}, longer than {:?}",
+                    running.info,
+                    running.info.location(),
+                    elapsed,
+                    self.watchdog.threshold
+                )
+            }
+        }
+    }
+}
+
+impl Watchdog {
+    /// Creates a watchdog of `workers` workers and the threads driving the pool.
+    pub(crate) fn new(threshold: Duration, workers: usize) -> Self {
+        Self {
+            threshold,
+            slots: (0..=workers).map(|_| Mutex::new(None)).collect(),
+            shutdown: Mutex::new(false),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Records a poll of a task by a worker, or by a thread driving the pool if `worker` is
+    /// `None`.
+    #[inline]
+    pub(crate) fn start(
+        &self,
+        worker: Option<usize>,
+        info: &Arc<Info>,
+    ) -> PollGuard<'_> {
+        let slot = worker.unwrap_or(self.slots.len() - 1);
+        *self.slots[slot].lock().expect(WATCHDOG_LOCK_POISONED) = Some(Running {
+            info: info.clone(),
+            start: Instant::now(),
+            warned: false,
+        });
+        PollGuard {
+            watchdog: self,
+            slot,
+        }
+    }
+
+    /// Returns the polls running for longer than the threshold, which are not reported yet.
+    fn scan(&self) -> Vec<(Arc<Info>, Duration)> {
+        let mut stalled = Vec::new();
+        for slot in &self.slots {
+            if let Some(running) = &mut *slot.lock().expect(WATCHDOG_LOCK_POISONED) {
+                let elapsed = running.start.elapsed();
+                if !running.warned && elapsed >= self.threshold {
+                    running.warned = true;
+                    stalled.push((running.info.clone(), elapsed));
+                }
+            }
+        }
+        stalled
+    }
+
+    /// Stops the thread of the watchdog.
+    pub(crate) fn shutdown(&self) {
+        *self.shutdown.lock().expect(WATCHDOG_LOCK_POISONED) = true;
+        self.changed.notify_all()
+    }
+}
+
+/// Starts the thread of the watchdog of `inner`.
+pub(crate) fn start_thread(inner: &Arc<Inner>) -> std::io::Result<()> {
+    inner.spawn_thread("watchdog", |inner| {
+        let watchdog = match &inner.watchdog {
+            Some(watchdog) => watchdog,
+            None => return,
+        };
+        let mut shutdown = watchdog.shutdown.lock().expect(WATCHDOG_LOCK_POISONED);
+        while !*shutdown {
+            shutdown = watchdog
+                .changed
+                .wait_timeout(shutdown, watchdog.threshold / 2)
+                .expect(WATCHDOG_LOCK_POISONED)
+                .0;
+            for (info, elapsed) in watchdog.scan() {
+                log::warn!(
+                    "{} spawned at {} has been polled for {:?}, it may be blocking the worker",
+                    info,
+                    info.location(),
+                    elapsed
+                )
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Watchdog;
+    use crate::runtime::Builder;
+    use crate::task::{self, Tag};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn scan() {
+        let watchdog = Watchdog::new(Duration::from_millis(20), 1);
+        let (tag, _, _) = Tag::wrap(Some("stalled".to_string()), async {});
+        let fast = watchdog.start(Some(0), tag.info());
+        assert!(watchdog.scan().is_empty());
+        drop(fast);
+        let _slow = watchdog.start(None, tag.info());
+        thread::sleep(Duration::from_millis(30));
+        let stalled = watchdog.scan();
+        assert_eq!(1, stalled.len());
+        assert_eq!(Some("stalled"), stalled[0].0.name());
+        assert!(stalled[0].1 >= Duration::from_millis(20));
+        // reported once
+        assert!(watchdog.scan().is_empty());
+    }
+
+    #[test]
+    fn runtime() {
+        let runtime = Builder::new()
+            .worker_threads(1)
+            .long_poll_threshold(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let val = runtime.block_on(async {
+            task::spawn(async {
+                thread::sleep(Duration::from_millis(30));
+                1
+            })
+            .await
+            .unwrap()
+        });
+        assert_eq!(1, val);
+    }
+}
@@ -0,0 +1,105 @@
+//! Stream adapters which need the timers of the runtime.
+//!
+//! The combinators of [`futures::StreamExt`] work on any stream, but the ones in [`StreamExt`]
+//! measure time, like [`timeout`] and [`chunks_timeout`]. Each item then pays for a timer of
+//! the current runtime, which is only registered while the stream is pending.
+//!
+//! Some types of tio are streams themselves, such as [`task::Interval`], and the [`incoming`]
+//! connections of the listeners.
+//!
+//! [`futures::StreamExt`]: https://docs.rs/futures/0.3/futures/stream/trait.StreamExt.html
+//! [`StreamExt`]: trait.StreamExt.html
+//! [`timeout`]: trait.StreamExt.html#method.timeout
+//! [`chunks_timeout`]: trait.StreamExt.html#method.chunks_timeout
+//! [`task::Interval`]: ../task/struct.Interval.html
+//! [`incoming`]: ../net/struct.TcpListener.html#method.incoming
+//!
+//! # Examples
+//!
+//! ```
+//! # tio::task::block_on(async {
+//! #
+//! use std::time::Duration;
+//!
+//! use futures::stream::{self, StreamExt as _};
+//! use tio::stream::StreamExt;
+//!
+//! let numbers = stream::iter(0..5).chunks_timeout(2, Duration::from_secs(1));
+//! assert_eq!(vec![vec![0, 1], vec![2, 3], vec![4]], numbers.collect::<Vec<_>>().await);
+//! #
+//! # })
+//! ```
+
+mod chunks_timeout;
+mod merge;
+mod timeout;
+
+pub use chunks_timeout::ChunksTimeout;
+pub use merge::Merge;
+pub use timeout::Timeout;
+
+use futures::Stream;
+use std::time::Duration;
+
+/// An extension trait for the streams, adding the adapters of tio.
+pub trait StreamExt: Stream {
+    /// Fails the items which take longer than `dur` to arrive.
+    ///
+    /// The stream yields a [`TimeoutError`] each time it waits for `dur` without an item, and
+    /// then waits again, so the consumer decides whether to go on.
+    ///
+    /// [`TimeoutError`]: ../task/struct.TimeoutError.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use futures::stream::{self, StreamExt as _};
+    /// use tio::stream::StreamExt;
+    ///
+    /// let mut items = stream::pending::<()>().timeout(Duration::from_millis(10));
+    /// assert!(items.next().await.unwrap().is_err());
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    fn timeout(self, dur: Duration) -> Timeout<Self>
+    where
+        Self: Sized,
+    {
+        Timeout::new(self, dur)
+    }
+
+    /// Groups the items into chunks of at most `capacity` items, yielding a chunk once it is
+    /// full, or `dur` after its first item arrived.
+    ///
+    /// The last chunk may be partial, but no chunk is empty.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is 0.
+    #[inline]
+    fn chunks_timeout(self, capacity: usize, dur: Duration) -> ChunksTimeout<Self>
+    where
+        Self: Sized,
+    {
+        ChunksTimeout::new(self, capacity, dur)
+    }
+
+    /// Merges the items of two streams as they arrive, ending once both streams end.
+    ///
+    /// The streams are polled in turn, so neither starves the other.
+    #[inline]
+    fn merge<U>(self, other: U) -> Merge<Self, U>
+    where
+        U: Stream<Item = Self::Item>,
+        Self: Sized,
+    {
+        Merge::new(self, other)
+    }
+}
+
+impl<S: Stream + ?Sized> StreamExt for S {}
@@ -0,0 +1,125 @@
+use crate::runtime::time::Delay;
+use futures::Stream;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// A stream grouping items into chunks by their number and time, created by
+/// [`StreamExt::chunks_timeout`].
+///
+/// [`StreamExt::chunks_timeout`]: trait.StreamExt.html#method.chunks_timeout
+#[must_use = "streams do nothing unless polled"]
+pub struct ChunksTimeout<S: Stream> {
+    stream: Pin<Box<S>>,
+    capacity: usize,
+    dur: Duration,
+    chunk: Vec<S::Item>,
+    // started by the first item of the chunk
+    delay: Option<Delay>,
+    done: bool,
+}
+
+impl<S: Stream> ChunksTimeout<S> {
+    #[inline]
+    pub(super) fn new(stream: S, capacity: usize, dur: Duration) -> Self {
+        assert!(capacity > 0, "the capacity of chunks must be positive");
+        Self {
+            stream: Box::pin(stream),
+            capacity,
+            dur,
+            chunk: Vec::with_capacity(capacity),
+            delay: None,
+            done: false,
+        }
+    }
+
+    #[inline]
+    fn take(&mut self) -> Vec<S::Item> {
+        self.delay = None;
+        mem::replace(&mut self.chunk, Vec::with_capacity(self.capacity))
+    }
+}
+
+// the items are never pinned
+impl<S: Stream> Unpin for ChunksTimeout<S> {}
+
+impl<S: Stream> Stream for ChunksTimeout<S> {
+    type Item = Vec<S::Item>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while !this.done {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    if this.chunk.is_empty() {
+                        this.delay = Some(Delay::new(this.dur));
+                    }
+                    this.chunk.push(item);
+                    if this.chunk.len() >= this.capacity {
+                        return Poll::Ready(Some(this.take()));
+                    }
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => {
+                    let delay = match &mut this.delay {
+                        Some(delay) => delay,
+                        None => return Poll::Pending,
+                    };
+                    futures::ready!(Pin::new(delay).poll(cx));
+                    return Poll::Ready(Some(this.take()));
+                }
+            }
+        }
+        if this.chunk.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Ready(Some(this.take()))
+        }
+    }
+}
+
+impl<S: Stream> Debug for ChunksTimeout<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunksTimeout")
+            .field("capacity", &self.capacity)
+            .field("dur", &self.dur)
+            .field("len", &self.chunk.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stream::StreamExt;
+    use crate::task;
+    use futures::stream::{self, StreamExt as _};
+    use std::time::Duration;
+
+    #[test]
+    fn by_time() {
+        task::block_on(async {
+            let dur = Duration::from_millis(20);
+            let late = stream::once(async move {
+                task::sleep(dur * 5).await;
+                2
+            });
+            let items = stream::iter(vec![0, 1])
+                .chain(late)
+                .chain(stream::iter(vec![3]));
+            let chunks = items.chunks_timeout(10, dur).collect::<Vec<_>>().await;
+            assert_eq!(vec![vec![0, 1], vec![2, 3]], chunks);
+        })
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_capacity() {
+        let _ = stream::empty::<()>().chunks_timeout(0, Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,124 @@
+use futures::Stream;
+use std::fmt::{self, Debug, Formatter};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A stream merging the items of two streams, created by [`StreamExt::merge`].
+///
+/// [`StreamExt::merge`]: trait.StreamExt.html#method.merge
+#[must_use = "streams do nothing unless polled"]
+pub struct Merge<S, U> {
+    first: Option<Pin<Box<S>>>,
+    second: Option<Pin<Box<U>>>,
+    // whether to poll `second` first this time
+    flip: bool,
+}
+
+impl<S, U> Merge<S, U> {
+    #[inline]
+    pub(super) fn new(first: S, second: U) -> Self {
+        Self {
+            first: Some(Box::pin(first)),
+            second: Some(Box::pin(second)),
+            flip: false,
+        }
+    }
+}
+
+/// Polls a stream which has not ended, dropping it once it ends.
+#[inline]
+fn poll_side<S: Stream>(
+    side: &mut Option<Pin<Box<S>>>,
+    cx: &mut Context<'_>,
+) -> Poll<Option<S::Item>> {
+    let stream = match side {
+        Some(stream) => stream,
+        None => return Poll::Ready(None),
+    };
+    let item = futures::ready!(stream.as_mut().poll_next(cx));
+    if item.is_none() {
+        *side = None;
+    }
+    Poll::Ready(item)
+}
+
+/// Polls `second` only if `first` has no item.
+#[inline]
+fn poll_in_turn<S, U>(
+    first: &mut Option<Pin<Box<S>>>,
+    second: &mut Option<Pin<Box<U>>>,
+    cx: &mut Context<'_>,
+) -> Poll<Option<S::Item>>
+where
+    S: Stream,
+    U: Stream<Item = S::Item>,
+{
+    let first = poll_side(first, cx);
+    if let Poll::Ready(Some(item)) = first {
+        return Poll::Ready(Some(item));
+    }
+    match poll_side(second, cx) {
+        Poll::Ready(None) if first.is_ready() => Poll::Ready(None),
+        Poll::Ready(None) => Poll::Pending,
+        poll => poll,
+    }
+}
+
+impl<S, U> Stream for Merge<S, U>
+where
+    S: Stream,
+    U: Stream<Item = S::Item>,
+{
+    type Item = S::Item;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        this.flip = !this.flip;
+        if this.flip {
+            poll_in_turn(&mut this.first, &mut this.second, cx)
+        } else {
+            poll_in_turn(&mut this.second, &mut this.first, cx)
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let hint = |side: Option<(usize, Option<usize>)>| side.unwrap_or((0, Some(0)));
+        let (first_low, first_high) = hint(self.first.as_ref().map(|s| s.size_hint()));
+        let (second_low, second_high) =
+            hint(self.second.as_ref().map(|s| s.size_hint()));
+        let high = match (first_high, second_high) {
+            (Some(first), Some(second)) => first.checked_add(second),
+            _ => None,
+        };
+        (first_low.saturating_add(second_low), high)
+    }
+}
+
+impl<S, U> Debug for Merge<S, U> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Merge")
+            .field("first_done", &self.first.is_none())
+            .field("second_done", &self.second.is_none())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stream::StreamExt;
+    use crate::task;
+    use futures::stream::{self, Stream, StreamExt as _};
+
+    #[test]
+    fn in_turn() {
+        task::block_on(async {
+            let merged = stream::iter(vec![0, 2, 4]).merge(stream::iter(vec![1, 3]));
+            assert_eq!((5, Some(5)), merged.size_hint());
+            assert_eq!(vec![0, 1, 2, 3, 4], merged.collect::<Vec<_>>().await);
+        })
+    }
+}
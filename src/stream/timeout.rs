@@ -0,0 +1,86 @@
+use crate::runtime::time::Delay;
+use crate::task::TimeoutError;
+use futures::Stream;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// A stream failing the items which take too long, created by [`StreamExt::timeout`].
+///
+/// [`StreamExt::timeout`]: trait.StreamExt.html#method.timeout
+#[must_use = "streams do nothing unless polled"]
+pub struct Timeout<S> {
+    stream: Pin<Box<S>>,
+    dur: Duration,
+    // started once the stream is pending
+    delay: Option<Delay>,
+}
+
+impl<S> Timeout<S> {
+    #[inline]
+    pub(super) fn new(stream: S, dur: Duration) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            dur,
+            delay: None,
+        }
+    }
+}
+
+impl<S: Stream> Stream for Timeout<S> {
+    type Item = Result<S::Item, TimeoutError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if let Poll::Ready(item) = this.stream.as_mut().poll_next(cx) {
+            this.delay = None;
+            return Poll::Ready(item.map(Ok));
+        }
+        let dur = this.dur;
+        let delay = this.delay.get_or_insert_with(|| Delay::new(dur));
+        futures::ready!(Pin::new(delay).poll(cx));
+        this.delay = None;
+        Poll::Ready(Some(Err(TimeoutError)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // any number of errors may come between the items
+        (self.stream.size_hint().0, None)
+    }
+}
+
+impl<S> Debug for Timeout<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timeout").field("dur", &self.dur).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stream::StreamExt;
+    use crate::task::{self, TimeoutError};
+    use futures::stream::{self, StreamExt as _};
+    use std::time::Duration;
+
+    #[test]
+    fn recover() {
+        task::block_on(async {
+            let dur = Duration::from_millis(10);
+            let slow =
+                stream::once(task::sleep(dur * 5)).chain(stream::iter(vec![(), ()]));
+            let items = slow.timeout(dur).collect::<Vec<_>>().await;
+            // the slow item times out but still arrives
+            assert!(items.len() >= 4);
+            assert!(items[..items.len() - 3]
+                .iter()
+                .all(|item| *item == Err(TimeoutError)));
+            assert_eq!(&[Ok(()); 3], &items[items.len() - 3..]);
+        })
+    }
+}
@@ -0,0 +1,63 @@
+//! Synchronization primitives for asynchronous tasks.
+//!
+//! The primitives of this module park the task instead of the thread while waiting, so they
+//! can be used within tasks without blocking the worker threads, and their guards can be held
+//! across `.await` points.
+//!
+//! - [`Mutex`], a fair mutual exclusion lock.
+//! - [`RwLock`], a reader-writer lock, fair by default.
+//! - [`Semaphore`], a fair counting semaphore, to bound the concurrency of some work.
+//! - [`Notify`], to wake tasks up without sending any data.
+//! - [`Condvar`], a condition variable working with [`Mutex`].
+//! - [`Barrier`], to make a number of tasks wait for each other.
+//! - [`OnceCell`], to initialize a value once, by the first of many tasks.
+//! - [`CancellationToken`], to cancel a tree of tasks for a graceful shutdown.
+//!
+//! The channels between tasks are in their own modules:
+//!
+//! - [`oneshot`], sending a single value.
+//! - [`broadcast`], sending every value to all receivers.
+//! - [`watch`], holding only the latest value for all receivers.
+//! - [`mpmc`], distributing values among receivers, like jobs among workers.
+//!
+//! [`Mutex`]: struct.Mutex.html
+//! [`RwLock`]: struct.RwLock.html
+//! [`Semaphore`]: struct.Semaphore.html
+//! [`Notify`]: struct.Notify.html
+//! [`Condvar`]: struct.Condvar.html
+//! [`Barrier`]: struct.Barrier.html
+//! [`OnceCell`]: struct.OnceCell.html
+//! [`CancellationToken`]: struct.CancellationToken.html
+//! [`oneshot`]: oneshot/index.html
+//! [`broadcast`]: broadcast/index.html
+//! [`watch`]: watch/index.html
+//! [`mpmc`]: mpmc/index.html
+
+mod barrier;
+mod batch;
+mod cancellation_token;
+mod condvar;
+mod mutex;
+mod notify;
+mod once_cell;
+mod rwlock;
+mod semaphore;
+
+pub mod broadcast;
+pub mod mpmc;
+pub mod oneshot;
+pub mod watch;
+
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use cancellation_token::{CancellationToken, DropGuard};
+pub use condvar::Condvar;
+pub use mutex::{Mutex, MutexGuard, OwnedMutexGuard, TryLockError};
+pub use notify::{Notified, Notify};
+pub use once_cell::OnceCell;
+pub use rwlock::{
+    OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock, RwLockPolicy, RwLockReadGuard,
+    RwLockWriteGuard,
+};
+pub use semaphore::{
+    AcquireError, OwnedSemaphorePermit, Semaphore, SemaphorePermit, TryAcquireError,
+};
@@ -0,0 +1,158 @@
+use futures::future::poll_fn;
+use std::fmt::{self, Debug, Formatter};
+use std::mem;
+use std::sync::Mutex;
+use std::task::{Poll, Waker};
+
+const BARRIER_LOCK_POISONED: &str = "barrier lock poisoned";
+
+/// A barrier which makes a number of tasks wait until all of them arrive.
+///
+/// Unlike [`std::sync::Barrier`], waiting parks the task instead of the thread. The barrier is
+/// reusable: once the last task arrives, every waiting task is released and the next `n` tasks
+/// wait for each other again.
+///
+/// [`std::sync::Barrier`]: https://doc.rust-lang.org/std/sync/struct.Barrier.html
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use std::sync::Arc;
+/// use tio::sync::Barrier;
+/// use tio::task;
+///
+/// let barrier = Arc::new(Barrier::new(5));
+/// let handles = (0..5)
+///     .map(|_| {
+///         let barrier = barrier.clone();
+///         task::spawn(async move {
+///             // start up
+///             barrier.wait().await.is_leader()
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// let mut leaders = 0;
+/// for handle in handles {
+///     if handle.await.unwrap() {
+///         leaders += 1;
+///     }
+/// }
+/// assert_eq!(1, leaders);
+/// #
+/// # })
+/// ```
+pub struct Barrier {
+    n: usize,
+    state: Mutex<State>,
+}
+
+struct State {
+    arrived: usize,
+    generation: u64,
+    wakers: Vec<Waker>,
+}
+
+/// The result of [`Barrier::wait`].
+///
+/// [`Barrier::wait`]: struct.Barrier.html#method.wait
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Returns `true` for exactly one task of each round, the last one to arrive.
+    #[inline]
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// Creates a barrier for `n` tasks.
+    ///
+    /// A barrier for zero task behaves like one for a single task: waiting completes at once.
+    #[inline]
+    pub fn new(n: usize) -> Self {
+        Self {
+            n: n.max(1),
+            state: Mutex::new(State {
+                arrived: 0,
+                generation: 0,
+                wakers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Waits until `n` tasks wait on this barrier.
+    ///
+    /// A task arrives once the future is first polled, and it still counts as arrived for the
+    /// current round if it cancels its wait afterwards.
+    pub async fn wait(&self) -> BarrierWaitResult {
+        let generation = {
+            let mut state = self.state.lock().expect(BARRIER_LOCK_POISONED);
+            state.arrived += 1;
+            if state.arrived == self.n {
+                state.arrived = 0;
+                state.generation += 1;
+                let wakers = mem::take(&mut state.wakers);
+                drop(state);
+                wakers.into_iter().for_each(Waker::wake);
+                return BarrierWaitResult(true);
+            }
+            state.generation
+        };
+        let mut registered: Option<usize> = None;
+        poll_fn(|cx| {
+            let mut state = self.state.lock().expect(BARRIER_LOCK_POISONED);
+            if state.generation != generation {
+                return Poll::Ready(BarrierWaitResult(false));
+            }
+            match registered {
+                Some(index) if state.wakers[index].will_wake(cx.waker()) => (),
+                Some(index) => state.wakers[index] = cx.waker().clone(),
+                None => {
+                    registered = Some(state.wakers.len());
+                    state.wakers.push(cx.waker().clone())
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl Debug for Barrier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Barrier").field("n", &self.n).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Barrier;
+    use crate::task;
+    use futures::FutureExt;
+    use std::sync::Arc;
+
+    #[test]
+    fn reuse() {
+        task::block_on(async {
+            let barrier = Arc::new(Barrier::new(2));
+            for _ in 0..3 {
+                let mut waiting = Box::pin(barrier.wait());
+                assert!((&mut waiting).now_or_never().is_none());
+                assert!(barrier.wait().await.is_leader());
+                assert!(!waiting.await.is_leader());
+            }
+        })
+    }
+
+    #[test]
+    fn single() {
+        task::block_on(async {
+            assert!(Barrier::new(0).wait().await.is_leader());
+            assert!(Barrier::new(1).wait().await.is_leader());
+        })
+    }
+}
@@ -0,0 +1,313 @@
+use super::{AcquireError, TryAcquireError};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+const SEMAPHORE_LOCK_POISONED: &str = "semaphore lock poisoned";
+
+/// A fair semaphore, the building block of the locks.
+///
+/// Waiters are served in the order they start waiting: a waiter which needs more permits than
+/// available blocks the ones queued after it, even if they need fewer.
+///
+/// A waiter may also have a priority, lower ones served first. It then waits only behind the
+/// waiters of the same or lower priorities, which lets a lock prefer its readers or writers.
+pub(crate) struct Semaphore {
+    state: Mutex<State>,
+}
+
+struct State {
+    permits: usize,
+    // keyed by the priority and the arrival order, a waiter which got its permits stays
+    // until it is polled
+    waiters: BTreeMap<(u8, u64), Waiter>,
+    next_id: u64,
+    closed: bool,
+}
+
+struct Waiter {
+    remaining: usize,
+    waker: Waker,
+}
+
+impl State {
+    /// Hands the available permits to the waiters in order, returning the wakers to wake
+    /// once the lock is released.
+    fn grant(&mut self) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        for waiter in self.waiters.values_mut() {
+            if waiter.remaining == 0 {
+                continue;
+            }
+            if waiter.remaining > self.permits {
+                break;
+            }
+            self.permits -= mem::take(&mut waiter.remaining);
+            wakers.push(waiter.waker.clone());
+        }
+        wakers
+    }
+
+    /// Returns `true` if a waiter of `priority` or lower is not granted yet.
+    #[inline]
+    fn has_waiters(&self, priority: u8) -> bool {
+        self.waiters
+            .range(..=(priority, u64::MAX))
+            .any(|(_, waiter)| waiter.remaining > 0)
+    }
+}
+
+impl Semaphore {
+    /// The max number of permits, so that adding permits never overflows.
+    pub(crate) const MAX_PERMITS: usize = usize::MAX >> 3;
+
+    #[inline]
+    pub(crate) fn new(permits: usize) -> Self {
+        assert!(
+            permits <= Self::MAX_PERMITS,
+            "a semaphore cannot have more than {} permits",
+            Self::MAX_PERMITS
+        );
+        Self {
+            state: Mutex::new(State {
+                permits,
+                waiters: BTreeMap::new(),
+                next_id: 0,
+                closed: false,
+            }),
+        }
+    }
+
+    #[inline]
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect(SEMAPHORE_LOCK_POISONED)
+    }
+
+    /// Returns the number of available permits.
+    #[inline]
+    pub(crate) fn available_permits(&self) -> usize {
+        self.state().permits
+    }
+
+    /// Waits until `n` permits are acquired.
+    #[inline]
+    pub(crate) fn acquire(&self, n: usize) -> Acquire<'_> {
+        self.acquire_with_priority(n, 0)
+    }
+
+    /// Waits until `n` permits are acquired, behind the waiters of `priority` or lower.
+    #[inline]
+    pub(crate) fn acquire_with_priority(&self, n: usize, priority: u8) -> Acquire<'_> {
+        Acquire {
+            semaphore: self,
+            needed: n,
+            priority,
+            id: None,
+            done: false,
+        }
+    }
+
+    /// Acquires `n` permits if they are available and nobody is waiting.
+    #[inline]
+    pub(crate) fn try_acquire(&self, n: usize) -> Result<(), TryAcquireError> {
+        self.try_acquire_with_priority(n, 0)
+    }
+
+    /// Acquires `n` permits if they are available and no waiter of `priority` or lower is
+    /// waiting.
+    pub(crate) fn try_acquire_with_priority(
+        &self,
+        n: usize,
+        priority: u8,
+    ) -> Result<(), TryAcquireError> {
+        let mut state = self.state();
+        if state.closed {
+            return Err(TryAcquireError::Closed);
+        }
+        if state.permits < n || state.has_waiters(priority) {
+            return Err(TryAcquireError::NoPermits);
+        }
+        state.permits -= n;
+        Ok(())
+    }
+
+    /// Returns `n` permits, handing them to the waiters.
+    pub(crate) fn release(&self, n: usize) {
+        let wakers = {
+            let mut state = self.state();
+            assert!(
+                state.permits + n <= Self::MAX_PERMITS,
+                "a semaphore cannot have more than {} permits",
+                Self::MAX_PERMITS
+            );
+            state.permits += n;
+            state.grant()
+        };
+        wakers.into_iter().for_each(Waker::wake)
+    }
+
+    /// Closes the semaphore, failing the pending and the future acquisitions.
+    pub(crate) fn close(&self) {
+        let wakers = {
+            let mut state = self.state();
+            state.closed = true;
+            let waiters = mem::take(&mut state.waiters);
+            let (granted, pending) = waiters
+                .into_iter()
+                .partition::<BTreeMap<_, _>, _>(|(_, waiter)| waiter.remaining == 0);
+            state.waiters = granted;
+            pending
+        };
+        wakers.into_values().for_each(|waiter| waiter.waker.wake())
+    }
+
+    #[inline]
+    pub(crate) fn is_closed(&self) -> bool {
+        self.state().closed
+    }
+}
+
+/// A future acquiring permits, which returns them if it is dropped after they are granted.
+pub(crate) struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    needed: usize,
+    priority: u8,
+    id: Option<(u8, u64)>,
+    done: bool,
+}
+
+impl Future for Acquire<'_> {
+    type Output = Result<(), AcquireError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        assert!(!self.done, "`Acquire` polled after completion");
+        let mut state = self.semaphore.state();
+        let ret = match self.id {
+            None if state.closed => Err(AcquireError(())),
+            None if state.permits >= self.needed
+                && !state.has_waiters(self.priority) =>
+            {
+                state.permits -= self.needed;
+                Ok(())
+            }
+            None => {
+                let id = (self.priority, state.next_id);
+                state.next_id += 1;
+                state.waiters.insert(
+                    id,
+                    Waiter {
+                        remaining: self.needed,
+                        waker: cx.waker().clone(),
+                    },
+                );
+                drop(state);
+                self.id = Some(id);
+                return Poll::Pending;
+            }
+            Some(id) => match state.waiters.get_mut(&id) {
+                // removed by `close`
+                None => Err(AcquireError(())),
+                Some(waiter) if waiter.remaining == 0 => {
+                    state.waiters.remove(&id);
+                    Ok(())
+                }
+                Some(waiter) => {
+                    if !waiter.waker.will_wake(cx.waker()) {
+                        waiter.waker = cx.waker().clone();
+                    }
+                    return Poll::Pending;
+                }
+            },
+        };
+        drop(state);
+        self.id = None;
+        self.done = true;
+        Poll::Ready(ret)
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        let id = match self.id {
+            Some(id) => id,
+            None => return,
+        };
+        let wakers = {
+            let mut state = self.semaphore.state();
+            match state.waiters.remove(&id) {
+                // the permits are granted but never taken
+                Some(waiter) if waiter.remaining == 0 => state.permits += self.needed,
+                // a waiter blocking the ones behind it leaves
+                Some(_) => (),
+                None => return,
+            }
+            state.grant()
+        };
+        wakers.into_iter().for_each(Waker::wake)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AcquireError, Semaphore, TryAcquireError};
+    use crate::task;
+    use futures::FutureExt;
+
+    #[test]
+    fn fifo() {
+        task::block_on(async {
+            let semaphore = Semaphore::new(2);
+            semaphore.acquire(2).await.unwrap();
+            let mut large = semaphore.acquire(2);
+            let mut small = semaphore.acquire(1);
+            assert!((&mut large).now_or_never().is_none());
+            assert!((&mut small).now_or_never().is_none());
+            // the small one waits behind the large one
+            semaphore.release(1);
+            assert!((&mut small).now_or_never().is_none());
+            assert_eq!(Err(TryAcquireError::NoPermits), semaphore.try_acquire(1));
+            semaphore.release(1);
+            assert_eq!(Some(Ok(())), (&mut large).now_or_never());
+            semaphore.release(2);
+            assert_eq!(Some(Ok(())), small.now_or_never());
+            assert_eq!(1, semaphore.available_permits());
+        })
+    }
+
+    #[test]
+    fn cancel() {
+        task::block_on(async {
+            let semaphore = Semaphore::new(1);
+            semaphore.acquire(1).await.unwrap();
+            let mut granted = semaphore.acquire(1);
+            let mut blocking = semaphore.acquire(2);
+            let mut blocked = semaphore.acquire(1);
+            assert!((&mut granted).now_or_never().is_none());
+            assert!((&mut blocking).now_or_never().is_none());
+            assert!((&mut blocked).now_or_never().is_none());
+            semaphore.release(1);
+            // the permit granted to a dropped waiter goes to the next one
+            drop(granted);
+            assert!((&mut blocked).now_or_never().is_none());
+            drop(blocking);
+            assert_eq!(Some(Ok(())), blocked.now_or_never());
+        })
+    }
+
+    #[test]
+    fn close() {
+        task::block_on(async {
+            let semaphore = Semaphore::new(0);
+            let mut waiter = semaphore.acquire(1);
+            assert!((&mut waiter).now_or_never().is_none());
+            semaphore.close();
+            assert_eq!(Some(Err(AcquireError(()))), waiter.now_or_never());
+            assert_eq!(Err(AcquireError(())), semaphore.acquire(1).await);
+            assert_eq!(Err(TryAcquireError::Closed), semaphore.try_acquire(0));
+            assert!(semaphore.is_closed());
+        })
+    }
+}
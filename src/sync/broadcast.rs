@@ -0,0 +1,438 @@
+//! A multi-producer, multi-consumer channel in which every receiver sees every value.
+//!
+//! [`channel`] creates a [`Sender`] and a [`Receiver`] sharing a ring buffer of a fixed
+//! capacity. Every value sent is cloned to each receiver subscribed at the time, which fits
+//! fanning a shutdown signal or a configuration update out to all connection tasks. More
+//! senders are created by cloning one, and more receivers by [`Sender::subscribe`].
+//!
+//! Sending never waits: once the buffer is full, the oldest value is overwritten. A receiver
+//! which falls behind so misses some values, and its next receive returns
+//! [`RecvError::Lagged`] with the number of values missed, before it goes on receiving from
+//! the oldest value still buffered.
+//!
+//! [`channel`]: fn.channel.html
+//! [`Sender`]: struct.Sender.html
+//! [`Receiver`]: struct.Receiver.html
+//! [`Sender::subscribe`]: struct.Sender.html#method.subscribe
+//! [`RecvError::Lagged`]: enum.RecvError.html#variant.Lagged
+//!
+//! # Examples
+//!
+//! ```
+//! # tio::task::block_on(async {
+//! #
+//! use tio::sync::broadcast::{self, RecvError};
+//! use tio::task;
+//!
+//! let (sender, mut receiver) = broadcast::channel(16);
+//! let mut other = sender.subscribe();
+//! let handle = task::spawn(async move {
+//!     assert_eq!(Ok(1), other.recv().await);
+//!     assert_eq!(Ok(2), other.recv().await);
+//! });
+//! sender.send(1).unwrap();
+//! sender.send(2).unwrap();
+//! assert_eq!(Ok(1), receiver.recv().await);
+//! assert_eq!(Ok(2), receiver.recv().await);
+//! handle.await.unwrap();
+//! drop(sender);
+//! assert_eq!(Err(RecvError::Closed), receiver.recv().await);
+//! #
+//! # })
+//! ```
+
+use futures::future::poll_fn;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Poll, Waker};
+
+const CHANNEL_LOCK_POISONED: &str = "broadcast channel lock poisoned";
+
+/// Creates a broadcast channel buffering up to `capacity` values, returning a sender and a
+/// receiver.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(
+        capacity > 0,
+        "the capacity of a broadcast channel must be positive"
+    );
+    let shared = Arc::new(Shared {
+        capacity,
+        state: Mutex::new(State {
+            buffer: VecDeque::with_capacity(capacity),
+            head: 0,
+            senders: 1,
+            receivers: 0,
+            next_id: 0,
+            waiters: BTreeMap::new(),
+        }),
+    });
+    let receiver = Receiver::subscribe(&shared);
+    (Sender { shared }, receiver)
+}
+
+struct Shared<T> {
+    capacity: usize,
+    state: Mutex<State<T>>,
+}
+
+struct State<T> {
+    buffer: VecDeque<T>,
+    // the position of the oldest value buffered
+    head: u64,
+    senders: usize,
+    receivers: usize,
+    next_id: u64,
+    // the wakers of the waiting receivers, keyed by their ids
+    waiters: BTreeMap<u64, Waker>,
+}
+
+impl<T> Shared<T> {
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().expect(CHANNEL_LOCK_POISONED)
+    }
+}
+
+impl<T> State<T> {
+    /// The position of the next value to send.
+    #[inline]
+    fn tail(&self) -> u64 {
+        self.head + self.buffer.len() as u64
+    }
+
+    /// Takes a clone of the value at `next`, moving it on.
+    fn take(&self, next: &mut u64) -> Result<T, TryRecvError>
+    where
+        T: Clone,
+    {
+        if *next < self.head {
+            let missed = self.head - *next;
+            *next = self.head;
+            return Err(TryRecvError::Lagged(missed));
+        }
+        match self.buffer.get((*next - self.head) as usize) {
+            Some(value) => {
+                *next += 1;
+                Ok(value.clone())
+            }
+            None if self.senders == 0 => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+}
+
+/// The sending half of a broadcast channel, created by [`channel`].
+///
+/// [`channel`]: fn.channel.html
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving half of a broadcast channel, created by [`channel`] or
+/// [`Sender::subscribe`].
+///
+/// [`channel`]: fn.channel.html
+/// [`Sender::subscribe`]: struct.Sender.html#method.subscribe
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    id: u64,
+    // the position of the next value to receive
+    next: u64,
+}
+
+/// An error returned by [`Sender::send`] if there is no receiver, holding the value back.
+///
+/// [`Sender::send`]: struct.Sender.html#method.send
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct SendError<T>(pub T);
+
+impl<T> Debug for SendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SendError").finish()
+    }
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("there is no receiver")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// An error returned by [`Receiver::recv`].
+///
+/// [`Receiver::recv`]: struct.Receiver.html#method.recv
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RecvError {
+    /// All senders are dropped, and every value sent is received.
+    Closed,
+
+    /// The receiver falls behind, missing this number of values which are overwritten.
+    Lagged(u64),
+}
+
+impl Display for RecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Closed => f.write_str("the channel is closed"),
+            RecvError::Lagged(missed) => {
+                write!(f, "the receiver lags by {} values", missed)
+            }
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+/// An error returned by [`Receiver::try_recv`].
+///
+/// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TryRecvError {
+    /// No value is sent since the last one received.
+    Empty,
+
+    /// All senders are dropped, and every value sent is received.
+    Closed,
+
+    /// The receiver falls behind, missing this number of values which are overwritten.
+    Lagged(u64),
+}
+
+impl Display for TryRecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("the channel is empty"),
+            TryRecvError::Closed => f.write_str("the channel is closed"),
+            TryRecvError::Lagged(missed) => {
+                write!(f, "the receiver lags by {} values", missed)
+            }
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+impl<T> Sender<T> {
+    /// Sends a value to all receivers, returning the number of them.
+    ///
+    /// The oldest value is overwritten if the buffer is full. Returns the value back if there
+    /// is no receiver.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let (receivers, waiters) = {
+            let mut state = self.shared.state();
+            if state.receivers == 0 {
+                return Err(SendError(value));
+            }
+            if state.buffer.len() == self.shared.capacity {
+                state.buffer.pop_front();
+                state.head += 1;
+            }
+            state.buffer.push_back(value);
+            let waiters = std::mem::take(&mut state.waiters);
+            (state.receivers, waiters)
+        };
+        waiters.into_iter().for_each(|(_, waker)| waker.wake());
+        Ok(receivers)
+    }
+
+    /// Creates a receiver which receives the values sent after this call.
+    #[inline]
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver::subscribe(&self.shared)
+    }
+
+    /// Returns the number of receivers.
+    #[inline]
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state().receivers
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared.state().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let waiters = {
+            let mut state = self.shared.state();
+            state.senders -= 1;
+            if state.senders > 0 {
+                return;
+            }
+            std::mem::take(&mut state.waiters)
+        };
+        waiters.into_iter().for_each(|(_, waker)| waker.wake())
+    }
+}
+
+impl<T> Receiver<T> {
+    fn subscribe(shared: &Arc<Shared<T>>) -> Self {
+        let mut state = shared.state();
+        state.receivers += 1;
+        let id = state.next_id;
+        state.next_id += 1;
+        let next = state.tail();
+        drop(state);
+        Self {
+            shared: shared.clone(),
+            id,
+            next,
+        }
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receives the next value without waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::sync::broadcast::{self, TryRecvError};
+    ///
+    /// let (sender, mut receiver) = broadcast::channel(2);
+    /// assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+    /// for i in 0..3 {
+    ///     sender.send(i).unwrap();
+    /// }
+    /// assert_eq!(Err(TryRecvError::Lagged(1)), receiver.try_recv());
+    /// assert_eq!(Ok(1), receiver.try_recv());
+    /// assert_eq!(Ok(2), receiver.try_recv());
+    /// ```
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let state = self.shared.state();
+        state.take(&mut self.next)
+    }
+
+    /// Receives the next value, waiting until one is sent.
+    ///
+    /// Returns [`RecvError::Lagged`] once if some values are overwritten before this
+    /// receiver gets them, and [`RecvError::Closed`] after all senders are dropped and every
+    /// value buffered is received. Cancelling the wait loses no value.
+    ///
+    /// [`RecvError::Lagged`]: enum.RecvError.html#variant.Lagged
+    /// [`RecvError::Closed`]: enum.RecvError.html#variant.Closed
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        poll_fn(|cx| {
+            let mut state = self.shared.state();
+            match state.take(&mut self.next) {
+                Ok(value) => Poll::Ready(Ok(value)),
+                Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError::Closed)),
+                Err(TryRecvError::Lagged(missed)) => {
+                    Poll::Ready(Err(RecvError::Lagged(missed)))
+                }
+                Err(TryRecvError::Empty) => {
+                    match state.waiters.get(&self.id) {
+                        Some(waker) if waker.will_wake(cx.waker()) => (),
+                        _ => {
+                            state.waiters.insert(self.id, cx.waker().clone());
+                        }
+                    }
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state();
+        state.receivers -= 1;
+        state.waiters.remove(&self.id);
+    }
+}
+
+impl<T> Debug for Sender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("capacity", &self.shared.capacity)
+            .finish()
+    }
+}
+
+impl<T> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("capacity", &self.shared.capacity)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channel, RecvError, SendError, TryRecvError};
+    use crate::task;
+    use futures::FutureExt;
+
+    #[test]
+    fn fan_out() {
+        task::block_on(async {
+            let (sender, receiver) = channel(4);
+            let mut receivers = vec![receiver, sender.subscribe(), sender.subscribe()];
+            let handles = receivers
+                .drain(..)
+                .map(|mut receiver| task::spawn(async move { receiver.recv().await }))
+                .collect::<Vec<_>>();
+            task::yield_now().await;
+            assert_eq!(Ok(3), sender.send("shutdown"));
+            for handle in handles {
+                assert_eq!(Ok("shutdown"), handle.await.unwrap());
+            }
+            assert_eq!(0, sender.receiver_count());
+            assert_eq!(Err(SendError("again")), sender.send("again"));
+        })
+    }
+
+    #[test]
+    fn lagged() {
+        let (sender, mut receiver) = channel(2);
+        for i in 0..5 {
+            sender.send(i).unwrap();
+        }
+        assert_eq!(
+            Some(Err(RecvError::Lagged(3))),
+            receiver.recv().now_or_never()
+        );
+        assert_eq!(Ok(3), receiver.try_recv());
+        assert_eq!(Ok(4), receiver.try_recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+    }
+
+    #[test]
+    fn closed_after_drain() {
+        let (sender, mut receiver) = channel(2);
+        let other = sender.clone();
+        sender.send(1).unwrap();
+        drop(sender);
+        assert_eq!(Ok(1), receiver.try_recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+        drop(other);
+        assert_eq!(Err(TryRecvError::Closed), receiver.try_recv());
+    }
+
+    #[test]
+    fn subscribe_late() {
+        let (sender, _receiver) = channel(2);
+        sender.send(1).unwrap();
+        let mut late = sender.subscribe();
+        assert_eq!(Err(TryRecvError::Empty), late.try_recv());
+        sender.send(2).unwrap();
+        assert_eq!(Ok(2), late.try_recv());
+    }
+}
@@ -0,0 +1,241 @@
+use super::Notify;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+const CHILDREN_LOCK_POISONED: &str = "cancellation token children lock poisoned";
+
+/// A token signaling tasks to cancel their work.
+///
+/// Cancelling a token by [`cancel`] completes the [`cancelled`] futures of it and of all its
+/// clones. A token may have child tokens created by [`child_token`], which are cancelled with
+/// their parent, but cancelling a child leaves its parent alone. This expresses a tree of
+/// graceful shutdown, like a runtime cancelling its listeners, each of which cancels its
+/// connections.
+///
+/// [`cancel`]: #method.cancel
+/// [`cancelled`]: #method.cancelled
+/// [`child_token`]: #method.child_token
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::sync::CancellationToken;
+/// use tio::task;
+///
+/// let shutdown = CancellationToken::new();
+/// let connection = shutdown.child_token();
+/// let handle = task::spawn(async move {
+///     // serve the connection until the shutdown
+///     connection.cancelled().await;
+/// });
+/// shutdown.cancel();
+/// handle.await.unwrap();
+/// #
+/// # })
+/// ```
+#[derive(Clone)]
+pub struct CancellationToken {
+    node: Arc<Node>,
+}
+
+struct Node {
+    cancelled: AtomicBool,
+    notify: Notify,
+    // collected and cancelled once this is cancelled
+    children: Mutex<Vec<Weak<Node>>>,
+}
+
+/// A guard which cancels a token when dropped, returned by
+/// [`CancellationToken::drop_guard`].
+///
+/// [`CancellationToken::drop_guard`]: struct.CancellationToken.html#method.drop_guard
+#[must_use = "the token is cancelled at once if the guard is not held"]
+pub struct DropGuard {
+    token: Option<CancellationToken>,
+}
+
+impl Node {
+    #[inline]
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            cancelled: AtomicBool::new(false),
+            notify: Notify::new(),
+            children: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn cancel(&self) {
+        if self.cancelled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.notify.notify_waiters();
+        let children =
+            std::mem::take(&mut *self.children.lock().expect(CHILDREN_LOCK_POISONED));
+        children
+            .iter()
+            .filter_map(Weak::upgrade)
+            .for_each(|child| child.cancel())
+    }
+}
+
+impl CancellationToken {
+    /// Creates a token which is not cancelled.
+    #[inline]
+    pub fn new() -> Self {
+        Self { node: Node::new() }
+    }
+
+    /// Creates a child token, which is cancelled once this token is.
+    ///
+    /// The child is cancelled at once if this token is cancelled already.
+    pub fn child_token(&self) -> Self {
+        let child = Node::new();
+        let mut children = self.node.children.lock().expect(CHILDREN_LOCK_POISONED);
+        // checked under the lock, so a concurrent `cancel` either sees the child or is seen
+        if self.is_cancelled() {
+            drop(children);
+            child.cancel();
+        } else {
+            children.retain(|node| node.strong_count() > 0);
+            children.push(Arc::downgrade(&child));
+        }
+        Self { node: child }
+    }
+
+    /// Cancels this token and all its descendants.
+    #[inline]
+    pub fn cancel(&self) {
+        self.node.cancel()
+    }
+
+    /// Returns `true` if this token is cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.node.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Waits until this token is cancelled.
+    ///
+    /// It completes at once if the token is cancelled already.
+    pub async fn cancelled(&self) {
+        loop {
+            // created before checking, so a cancellation in between is not lost
+            let notified = self.node.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await
+        }
+    }
+
+    /// Returns a guard which cancels this token when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::sync::CancellationToken;
+    ///
+    /// let token = CancellationToken::new();
+    /// let guard = token.clone().drop_guard();
+    /// assert!(!token.is_cancelled());
+    /// drop(guard);
+    /// assert!(token.is_cancelled());
+    /// ```
+    #[inline]
+    pub fn drop_guard(self) -> DropGuard {
+        DropGuard { token: Some(self) }
+    }
+}
+
+impl Default for CancellationToken {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for CancellationToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl DropGuard {
+    /// Returns the token without cancelling it.
+    #[inline]
+    pub fn disarm(mut self) -> CancellationToken {
+        self.token.take().expect("the guard is dropped")
+    }
+}
+
+impl Drop for DropGuard {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            token.cancel()
+        }
+    }
+}
+
+impl Debug for DropGuard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropGuard")
+            .field("token", &self.token)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancellationToken;
+    use crate::task;
+    use futures::FutureExt;
+
+    #[test]
+    fn hierarchy() {
+        task::block_on(async {
+            let root = CancellationToken::new();
+            let listener = root.child_token();
+            let connection = listener.child_token();
+            let other = root.child_token();
+            let mut cancelled = Box::pin(connection.cancelled());
+            assert!((&mut cancelled).now_or_never().is_none());
+
+            listener.cancel();
+            cancelled.await;
+            assert!(connection.is_cancelled());
+            assert!(!root.is_cancelled());
+            assert!(!other.is_cancelled());
+
+            root.cancel();
+            other.cancelled().await;
+            assert!(root.child_token().is_cancelled());
+        })
+    }
+
+    #[test]
+    fn disarm() {
+        let token = CancellationToken::new();
+        let guard = token.clone().drop_guard();
+        drop(guard.disarm());
+        assert!(!token.is_cancelled());
+    }
+
+    #[test]
+    fn dropped_child() {
+        let root = CancellationToken::new();
+        for _ in 0..8 {
+            drop(root.child_token());
+        }
+        let child = root.child_token();
+        // the dropped children are pruned
+        assert_eq!(1, root.node.children.lock().unwrap().len());
+        root.cancel();
+        assert!(child.is_cancelled());
+    }
+}
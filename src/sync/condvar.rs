@@ -0,0 +1,326 @@
+use super::notify::Notification;
+use super::{Mutex, MutexGuard, OwnedMutexGuard};
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{self, Arc, MutexGuard as StateGuard};
+use std::task::{Context, Poll, Waker};
+
+const CONDVAR_LOCK_POISONED: &str = "condvar lock poisoned";
+
+/// A condition variable, working with the guards of [`Mutex`].
+///
+/// Unlike [`std::sync::Condvar`], waiting parks the task instead of the thread. A task waits by
+/// [`wait`], which unlocks the mutex and locks it again once the task is notified. The wait
+/// may complete spuriously, so the condition should be checked in a loop, or by
+/// [`wait_while`].
+///
+/// [`Mutex`]: struct.Mutex.html
+/// [`std::sync::Condvar`]: https://doc.rust-lang.org/std/sync/struct.Condvar.html
+/// [`wait`]: #method.wait
+/// [`wait_while`]: #method.wait_while
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use std::sync::Arc;
+/// use tio::sync::{Condvar, Mutex};
+/// use tio::task;
+///
+/// let pair = Arc::new((Mutex::new(false), Condvar::new()));
+/// task::spawn({
+///     let pair = pair.clone();
+///     async move {
+///         let (started, condvar) = &*pair;
+///         *started.lock().await = true;
+///         condvar.notify_one();
+///     }
+/// });
+/// let (started, condvar) = &*pair;
+/// let mut guard = started.lock().await;
+/// while !*guard {
+///     guard = condvar.wait(guard).await;
+/// }
+/// #
+/// # })
+/// ```
+pub struct Condvar {
+    state: sync::Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    // keyed by the arrival order
+    waiters: BTreeMap<u64, Waiter>,
+    next_id: u64,
+}
+
+struct Waiter {
+    waker: Option<Waker>,
+    notified: Option<Notification>,
+}
+
+impl State {
+    /// Notifies the first waiter which is not notified yet.
+    fn notify_one(&mut self) -> Option<Waker> {
+        self.waiters
+            .values_mut()
+            .find(|waiter| waiter.notified.is_none())
+            .and_then(|waiter| {
+                waiter.notified = Some(Notification::One);
+                waiter.waker.take()
+            })
+    }
+}
+
+/// A future which completes once the task waiting on a condition variable is notified.
+struct Waiting<'a> {
+    condvar: &'a Condvar,
+    id: Option<u64>,
+}
+
+impl Condvar {
+    /// Creates a condition variable without any waiting task.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            state: sync::Mutex::new(State::default()),
+        }
+    }
+
+    #[inline]
+    fn state(&self) -> StateGuard<'_, State> {
+        self.state.lock().expect(CONDVAR_LOCK_POISONED)
+    }
+
+    /// Starts waiting, before the mutex is unlocked, so no notification is lost in between.
+    fn register(&self) -> Waiting<'_> {
+        let mut state = self.state();
+        let id = state.next_id;
+        state.next_id += 1;
+        let waiter = Waiter {
+            waker: None,
+            notified: None,
+        };
+        state.waiters.insert(id, waiter);
+        Waiting {
+            condvar: self,
+            id: Some(id),
+        }
+    }
+
+    /// Unlocks the mutex of `guard` and waits until this is notified, then locks the mutex
+    /// again.
+    ///
+    /// A notification for a task which cancels this wait goes to the next waiting task.
+    pub async fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex: &'a Mutex<T> = MutexGuard::mutex(&guard);
+        let waiting = self.register();
+        drop(guard);
+        waiting.await;
+        mutex.lock().await
+    }
+
+    /// Like [`wait`], but for an owned guard.
+    ///
+    /// [`wait`]: #method.wait
+    pub async fn wait_owned<T>(&self, guard: OwnedMutexGuard<T>) -> OwnedMutexGuard<T> {
+        let mutex: Arc<Mutex<T>> = OwnedMutexGuard::mutex(&guard).clone();
+        let waiting = self.register();
+        drop(guard);
+        waiting.await;
+        mutex.lock_owned().await
+    }
+
+    /// Waits until `condition` returns `false`, checking it each time this is notified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use std::sync::Arc;
+    /// use tio::sync::{Condvar, Mutex};
+    /// use tio::task;
+    ///
+    /// let pair = Arc::new((Mutex::new(0), Condvar::new()));
+    /// for _ in 0..3 {
+    ///     let pair = pair.clone();
+    ///     task::spawn(async move {
+    ///         let (done, condvar) = &*pair;
+    ///         *done.lock().await += 1;
+    ///         condvar.notify_all();
+    ///     });
+    /// }
+    /// let (done, condvar) = &*pair;
+    /// let guard = condvar.wait_while(done.lock().await, |done| *done < 3).await;
+    /// assert_eq!(3, *guard);
+    /// #
+    /// # })
+    /// ```
+    pub async fn wait_while<'a, T, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: F,
+    ) -> MutexGuard<'a, T>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard).await;
+        }
+        guard
+    }
+
+    /// Wakes the task which has been waiting the longest, if any.
+    ///
+    /// Unlike [`Notify::notify_one`], nothing is stored if no task is waiting.
+    ///
+    /// [`Notify::notify_one`]: struct.Notify.html#method.notify_one
+    #[inline]
+    pub fn notify_one(&self) {
+        let waker = self.state().notify_one();
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+
+    /// Wakes all waiting tasks.
+    pub fn notify_all(&self) {
+        let wakers = {
+            let mut state = self.state();
+            state
+                .waiters
+                .values_mut()
+                .filter(|waiter| waiter.notified.is_none())
+                .filter_map(|waiter| {
+                    waiter.notified = Some(Notification::All);
+                    waiter.waker.take()
+                })
+                .collect::<Vec<_>>()
+        };
+        wakers.into_iter().for_each(Waker::wake)
+    }
+}
+
+impl Default for Condvar {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Condvar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Condvar")
+            .field("waiters", &self.state().waiters.len())
+            .finish()
+    }
+}
+
+impl Future for Waiting<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let id = match self.id {
+            Some(id) => id,
+            None => return Poll::Ready(()),
+        };
+        let mut state = self.condvar.state();
+        let waiter = state
+            .waiters
+            .get_mut(&id)
+            .expect("a waiter leaves only by its future");
+        if waiter.notified.is_none() {
+            match &waiter.waker {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => waiter.waker = Some(cx.waker().clone()),
+            }
+            return Poll::Pending;
+        }
+        state.waiters.remove(&id);
+        drop(state);
+        self.id = None;
+        Poll::Ready(())
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let id = match self.id {
+            Some(id) => id,
+            None => return,
+        };
+        let waker = {
+            let mut state = self.condvar.state();
+            match state.waiters.remove(&id) {
+                // pass the notification on, which would be lost otherwise
+                Some(Waiter {
+                    notified: Some(Notification::One),
+                    ..
+                }) => state.notify_one(),
+                _ => None,
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Condvar;
+    use crate::sync::Mutex;
+    use crate::task;
+    use futures::FutureExt;
+
+    #[test]
+    fn notify_one_in_order() {
+        task::block_on(async {
+            let mutex = Mutex::new(0);
+            let condvar = Condvar::new();
+            let mut first = Box::pin(condvar.wait(mutex.lock().await));
+            assert!((&mut first).now_or_never().is_none());
+            let mut second = Box::pin(condvar.wait(mutex.lock().await));
+            assert!((&mut second).now_or_never().is_none());
+            // the lock is released while waiting
+            *mutex.lock().await += 1;
+
+            condvar.notify_one();
+            assert!((&mut second).now_or_never().is_none());
+            assert_eq!(1, *first.await);
+            condvar.notify_one();
+            assert_eq!(1, *second.await);
+        })
+    }
+
+    #[test]
+    fn not_stored() {
+        task::block_on(async {
+            let mutex = Mutex::new(());
+            let condvar = Condvar::new();
+            condvar.notify_one();
+            condvar.notify_all();
+            assert!(condvar.wait(mutex.lock().await).now_or_never().is_none());
+        })
+    }
+
+    #[test]
+    fn forward_on_drop() {
+        task::block_on(async {
+            let mutex = Mutex::new(());
+            let condvar = Condvar::new();
+            let mut first = Box::pin(condvar.wait(mutex.lock().await));
+            assert!((&mut first).now_or_never().is_none());
+            let mut second = Box::pin(condvar.wait(mutex.lock().await));
+            assert!((&mut second).now_or_never().is_none());
+            condvar.notify_one();
+            drop(first);
+            assert!(second.now_or_never().is_some());
+        })
+    }
+}
@@ -0,0 +1,669 @@
+//! A multi-producer, multi-consumer channel distributing values among receivers.
+//!
+//! [`bounded`] and [`unbounded`] create a [`Sender`] and a [`Receiver`] sharing a queue. Both
+//! halves can be cloned, and each value sent is received by exactly one receiver, waking the
+//! one which has been waiting the longest. A pool of worker tasks so pulls jobs from one queue
+//! without wrapping a receiver in a mutex.
+//!
+//! Sending to a bounded channel waits while the queue is full, which applies back pressure
+//! to the producers.
+//!
+//! [`bounded`]: fn.bounded.html
+//! [`unbounded`]: fn.unbounded.html
+//! [`Sender`]: struct.Sender.html
+//! [`Receiver`]: struct.Receiver.html
+//!
+//! # Examples
+//!
+//! ```
+//! # tio::task::block_on(async {
+//! #
+//! use tio::sync::mpmc;
+//! use tio::task;
+//!
+//! let (jobs, receiver) = mpmc::bounded(8);
+//! let workers = (0..4)
+//!     .map(|_| {
+//!         let receiver = receiver.clone();
+//!         task::spawn(async move {
+//!             let mut done = 0;
+//!             while let Some(job) = receiver.recv().await {
+//!                 done += job;
+//!             }
+//!             done
+//!         })
+//!     })
+//!     .collect::<Vec<_>>();
+//! for job in 1..=10 {
+//!     jobs.send(job).await.unwrap();
+//! }
+//! drop(jobs);
+//! let mut total = 0;
+//! for worker in workers {
+//!     total += worker.await.unwrap();
+//! }
+//! assert_eq!(55, total);
+//! #
+//! # })
+//! ```
+
+use futures::future::poll_fn;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+const CHANNEL_LOCK_POISONED: &str = "mpmc channel lock poisoned";
+
+/// Creates a channel buffering up to `capacity` values, returning a sender and a receiver.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(
+        capacity > 0,
+        "the capacity of a mpmc channel must be positive"
+    );
+    channel(Some(capacity))
+}
+
+/// Creates a channel buffering any number of values, returning a sender and a receiver.
+#[inline]
+pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+    channel(None)
+}
+
+fn channel<T>(capacity: Option<usize>) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        capacity,
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            senders: 1,
+            receivers: 1,
+            sending: Waiters::default(),
+            receiving: Waiters::default(),
+        }),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    capacity: Option<usize>,
+    state: Mutex<State<T>>,
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receivers: usize,
+    // the senders waiting for room, and the receivers waiting for values
+    sending: Waiters,
+    receiving: Waiters,
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Sending,
+    Receiving,
+}
+
+/// The tasks waiting on one side of a channel.
+#[derive(Default)]
+struct Waiters {
+    // keyed by the arrival order
+    waiters: BTreeMap<u64, Waiter>,
+    next_id: u64,
+}
+
+struct Waiter {
+    waker: Waker,
+    notified: bool,
+}
+
+impl Waiters {
+    /// Registers the waiter of `id`, or a new one if it is `None`.
+    fn register(&mut self, id: &mut Option<u64>, cx: &mut Context<'_>) {
+        match id.and_then(|id| self.waiters.get_mut(&id)) {
+            Some(waiter) => {
+                waiter.notified = false;
+                if !waiter.waker.will_wake(cx.waker()) {
+                    waiter.waker = cx.waker().clone();
+                }
+            }
+            None => {
+                let waiter = Waiter {
+                    waker: cx.waker().clone(),
+                    notified: false,
+                };
+                self.waiters.insert(self.next_id, waiter);
+                *id = Some(self.next_id);
+                self.next_id += 1;
+            }
+        }
+    }
+
+    /// Notifies the first waiter which is not notified yet.
+    fn notify_one(&mut self) -> Option<Waker> {
+        let waiter = self.waiters.values_mut().find(|waiter| !waiter.notified)?;
+        waiter.notified = true;
+        Some(waiter.waker.clone())
+    }
+
+    fn notify_all(&mut self) -> Vec<Waker> {
+        self.waiters
+            .values_mut()
+            .map(|waiter| {
+                waiter.notified = true;
+                waiter.waker.clone()
+            })
+            .collect()
+    }
+}
+
+impl<T> Shared<T> {
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().expect(CHANNEL_LOCK_POISONED)
+    }
+
+    #[inline]
+    fn is_full(&self, state: &State<T>) -> bool {
+        matches!(self.capacity, Some(capacity) if state.queue.len() >= capacity)
+    }
+
+    /// Returns `true` if there is room for a value once `reserved` more values are sent.
+    #[inline]
+    fn has_room(&self, state: &State<T>, reserved: usize) -> bool {
+        !matches!(self.capacity, Some(capacity) if state.queue.len() + reserved >= capacity)
+    }
+}
+
+impl<T> State<T> {
+    #[inline]
+    fn waiters(&mut self, side: Side) -> &mut Waiters {
+        match side {
+            Side::Sending => &mut self.sending,
+            Side::Receiving => &mut self.receiving,
+        }
+    }
+}
+
+/// Removes a waiter when its future is done or cancelled.
+struct Waiting<'a, T> {
+    shared: &'a Shared<T>,
+    side: Side,
+    id: Option<u64>,
+}
+
+impl<'a, T> Waiting<'a, T> {
+    #[inline]
+    fn new(shared: &'a Shared<T>, side: Side) -> Self {
+        Self {
+            shared,
+            side,
+            id: None,
+        }
+    }
+
+    #[inline]
+    fn register(&mut self, state: &mut State<T>, cx: &mut Context<'_>) {
+        state.waiters(self.side).register(&mut self.id, cx)
+    }
+
+    /// Returns the number of waiters which started waiting before this one.
+    #[inline]
+    fn ahead(&self, state: &State<T>) -> usize {
+        let waiters = match self.side {
+            Side::Sending => &state.sending.waiters,
+            Side::Receiving => &state.receiving.waiters,
+        };
+        match self.id {
+            Some(id) => waiters.range(..id).count(),
+            None => waiters.len(),
+        }
+    }
+
+    #[inline]
+    fn complete(&mut self, state: &mut State<T>) {
+        if let Some(id) = self.id.take() {
+            state.waiters(self.side).waiters.remove(&id);
+        }
+    }
+}
+
+impl<T> Drop for Waiting<'_, T> {
+    fn drop(&mut self) {
+        let id = match self.id {
+            Some(id) => id,
+            None => return,
+        };
+        let waker = {
+            let mut state = self.shared.state();
+            let waiters = state.waiters(self.side);
+            match waiters.waiters.remove(&id) {
+                // pass the notification on, which would be lost otherwise
+                Some(Waiter { notified: true, .. }) => waiters.notify_one(),
+                _ => None,
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+}
+
+/// The sending half of a mpmc channel, created by [`bounded`] or [`unbounded`].
+///
+/// [`bounded`]: fn.bounded.html
+/// [`unbounded`]: fn.unbounded.html
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving half of a mpmc channel, created by [`bounded`] or [`unbounded`].
+///
+/// [`bounded`]: fn.bounded.html
+/// [`unbounded`]: fn.unbounded.html
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// An error returned by [`Sender::send`] if all receivers are dropped, holding the value back.
+///
+/// [`Sender::send`]: struct.Sender.html#method.send
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct SendError<T>(pub T);
+
+impl<T> Debug for SendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SendError").finish()
+    }
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("there is no receiver")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// An error returned by [`Sender::try_send`], holding the value back.
+///
+/// [`Sender::try_send`]: struct.Sender.html#method.try_send
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum TrySendError<T> {
+    /// The channel is full.
+    Full(T),
+
+    /// All receivers are dropped.
+    Closed(T),
+}
+
+impl<T> TrySendError<T> {
+    /// Returns the value which is not sent.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) | TrySendError::Closed(value) => value,
+        }
+    }
+}
+
+impl<T> Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Closed(_) => f.write_str("Closed(..)"),
+        }
+    }
+}
+
+impl<T> Display for TrySendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TrySendError::Full(_) => "the channel is full",
+            TrySendError::Closed(_) => "there is no receiver",
+        })
+    }
+}
+
+impl<T> Error for TrySendError<T> {}
+
+/// An error returned by [`Receiver::try_recv`].
+///
+/// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TryRecvError {
+    /// The channel is empty.
+    Empty,
+
+    /// All senders are dropped, and the channel is empty.
+    Closed,
+}
+
+impl Display for TryRecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TryRecvError::Empty => "the channel is empty",
+            TryRecvError::Closed => "the channel is closed",
+        })
+    }
+}
+
+impl Error for TryRecvError {}
+
+impl<T> Sender<T> {
+    /// Sends a value, waiting while the channel is full.
+    ///
+    /// Returns the value back if all receivers are dropped. Senders get room in the order
+    /// they start waiting for it, and cancelling the wait sends nothing.
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut value = Some(value);
+        let mut waiting = Waiting::new(&self.shared, Side::Sending);
+        poll_fn(|cx| {
+            let mut state = self.shared.state();
+            let item = value.take().expect("the value is sent");
+            if state.receivers == 0 {
+                waiting.complete(&mut state);
+                return Poll::Ready(Err(SendError(item)));
+            }
+            // the senders waiting longer come first, each taking one of the free slots
+            let ahead = waiting.ahead(&state);
+            if !self.shared.has_room(&state, ahead) {
+                waiting.register(&mut state, cx);
+                drop(state);
+                value = Some(item);
+                return Poll::Pending;
+            }
+            waiting.complete(&mut state);
+            state.queue.push_back(item);
+            let waker = state.receiving.notify_one();
+            drop(state);
+            if let Some(waker) = waker {
+                waker.wake()
+            }
+            Poll::Ready(Ok(()))
+        })
+        .await
+    }
+
+    /// Sends a value if the channel is not full, without waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::sync::mpmc::{self, TrySendError};
+    ///
+    /// let (sender, receiver) = mpmc::bounded(1);
+    /// sender.try_send(1).unwrap();
+    /// assert_eq!(TrySendError::Full(2), sender.try_send(2).unwrap_err());
+    /// drop(receiver);
+    /// assert_eq!(TrySendError::Closed(2), sender.try_send(2).unwrap_err());
+    /// ```
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut state = self.shared.state();
+        if state.receivers == 0 {
+            return Err(TrySendError::Closed(value));
+        }
+        // a waiting sender comes first
+        if self.shared.is_full(&state) || !state.sending.waiters.is_empty() {
+            return Err(TrySendError::Full(value));
+        }
+        state.queue.push_back(value);
+        let waker = state.receiving.notify_one();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if all receivers are dropped.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.shared.state().receivers == 0
+    }
+
+    /// Returns the number of values in the channel.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shared.state().queue.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receives a value, waiting until one is sent.
+    ///
+    /// Returns `None` once all senders are dropped and the channel is empty. Receivers get
+    /// values in the order they start waiting for them, and cancelling the wait loses no
+    /// value.
+    pub async fn recv(&self) -> Option<T> {
+        let mut waiting = Waiting::new(&self.shared, Side::Receiving);
+        poll_fn(|cx| {
+            let mut state = self.shared.state();
+            match state.queue.pop_front() {
+                Some(value) => {
+                    waiting.complete(&mut state);
+                    let waker = state.sending.notify_one();
+                    drop(state);
+                    if let Some(waker) = waker {
+                        waker.wake()
+                    }
+                    Poll::Ready(Some(value))
+                }
+                None if state.senders == 0 => {
+                    waiting.complete(&mut state);
+                    Poll::Ready(None)
+                }
+                None => {
+                    waiting.register(&mut state, cx);
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Receives a value if the channel is not empty, without waiting.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.state();
+        match state.queue.pop_front() {
+            Some(value) => {
+                let waker = state.sending.notify_one();
+                drop(state);
+                if let Some(waker) = waker {
+                    waker.wake()
+                }
+                Ok(value)
+            }
+            None if state.senders == 0 => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Returns `true` if all senders are dropped.
+    ///
+    /// Values may still be received from a closed channel until it is empty.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.shared.state().senders == 0
+    }
+
+    /// Returns the number of values in the channel.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shared.state().queue.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared.state().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared.state().receivers += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.state();
+            state.senders -= 1;
+            if state.senders > 0 {
+                return;
+            }
+            state.receiving.notify_all()
+        };
+        wakers.into_iter().for_each(Waker::wake)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.state();
+            state.receivers -= 1;
+            if state.receivers > 0 {
+                return;
+            }
+            state.sending.notify_all()
+        };
+        wakers.into_iter().for_each(Waker::wake)
+    }
+}
+
+impl<T> Debug for Sender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("capacity", &self.shared.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<T> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("capacity", &self.shared.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bounded, unbounded, TryRecvError};
+    use futures::FutureExt;
+
+    #[test]
+    fn each_value_once() {
+        let (sender, receiver) = unbounded();
+        let other = receiver.clone();
+        let mut first = Box::pin(receiver.recv());
+        let mut second = Box::pin(other.recv());
+        assert!((&mut first).now_or_never().is_none());
+        assert!((&mut second).now_or_never().is_none());
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+        assert_eq!(Some(1), first.now_or_never().unwrap());
+        assert_eq!(Some(2), second.now_or_never().unwrap());
+        assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+    }
+
+    #[test]
+    fn cancelled_receiver() {
+        let (sender, receiver) = unbounded();
+        let other = receiver.clone();
+        let mut first = Box::pin(receiver.recv());
+        let mut second = Box::pin(other.recv());
+        assert!((&mut first).now_or_never().is_none());
+        assert!((&mut second).now_or_never().is_none());
+        sender.try_send(1).unwrap();
+        // the value stays for the next receiver
+        drop(first);
+        assert_eq!(Some(1), second.now_or_never().unwrap());
+    }
+
+    #[test]
+    fn back_pressure() {
+        let (sender, receiver) = bounded(1);
+        sender.try_send(1).unwrap();
+        let mut waiting = Box::pin(sender.send(2));
+        assert!((&mut waiting).now_or_never().is_none());
+        // the waiting sender comes first
+        assert!(sender.try_send(3).is_err());
+        assert_eq!(Ok(1), receiver.try_recv());
+        assert_eq!(Some(Ok(())), waiting.now_or_never());
+        assert_eq!(Ok(2), receiver.try_recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+    }
+
+    #[test]
+    fn waiting_senders_first() {
+        let (sender, receiver) = bounded(1);
+        sender.try_send(1).unwrap();
+        let mut waiting = Box::pin(sender.send(2));
+        assert!((&mut waiting).now_or_never().is_none());
+        assert_eq!(Ok(1), receiver.try_recv());
+        // the free slot is the waiting sender's
+        assert!(sender.send(3).now_or_never().is_none());
+        assert_eq!(Some(Ok(())), waiting.now_or_never());
+        assert_eq!(Ok(2), receiver.try_recv());
+
+        // each notified sender takes one of the free slots, in any order
+        let (sender, receiver) = bounded(2);
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+        let mut first = Box::pin(sender.send(3));
+        let mut second = Box::pin(sender.send(4));
+        assert!((&mut first).now_or_never().is_none());
+        assert!((&mut second).now_or_never().is_none());
+        assert_eq!(Ok(1), receiver.try_recv());
+        assert_eq!(Ok(2), receiver.try_recv());
+        assert_eq!(Some(Ok(())), second.now_or_never());
+        assert_eq!(Some(Ok(())), first.now_or_never());
+        assert_eq!(2, receiver.len());
+    }
+
+    #[test]
+    fn closed() {
+        let (sender, receiver) = bounded(2);
+        sender.try_send(1).unwrap();
+        drop(sender);
+        assert!(receiver.is_closed());
+        assert_eq!(Some(Some(1)), receiver.recv().now_or_never());
+        assert_eq!(Some(None), receiver.recv().now_or_never());
+    }
+}
@@ -0,0 +1,352 @@
+use super::batch::Semaphore;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, PoisonError};
+
+/// An asynchronous mutual exclusion lock.
+///
+/// Unlike [`std::sync::Mutex`], locking it parks the task instead of the thread, and its guard
+/// can be held across `.await` points. The lock is fair: the tasks get it in the order they
+/// start waiting for it.
+///
+/// [`std::sync::Mutex`]: https://doc.rust-lang.org/std/sync/struct.Mutex.html
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use std::sync::Arc;
+/// use tio::sync::Mutex;
+/// use tio::task;
+///
+/// let counter = Arc::new(Mutex::new(0));
+/// let handles = (0..10)
+///     .map(|_| {
+///         let counter = counter.clone();
+///         task::spawn(async move {
+///             let mut counter = counter.lock().await;
+///             task::yield_now().await;
+///             *counter += 1;
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for handle in handles {
+///     handle.await.unwrap();
+/// }
+/// assert_eq!(10, *counter.lock().await);
+/// #
+/// # })
+/// ```
+pub struct Mutex<T> {
+    semaphore: Semaphore,
+    // taken by the guard which holds the lock
+    value: std::sync::Mutex<Option<T>>,
+}
+
+/// A guard which releases the lock of a [`Mutex`] when dropped, returned by [`Mutex::lock`].
+///
+/// [`Mutex`]: struct.Mutex.html
+/// [`Mutex::lock`]: struct.Mutex.html#method.lock
+pub struct MutexGuard<'a, T> {
+    lock: &'a Mutex<T>,
+    value: Option<T>,
+}
+
+/// An owned guard which releases the lock of a [`Mutex`] when dropped, returned by
+/// [`Mutex::lock_owned`].
+///
+/// It keeps the mutex alive by an `Arc`, so it can be moved into a spawned task.
+///
+/// [`Mutex`]: struct.Mutex.html
+/// [`Mutex::lock_owned`]: struct.Mutex.html#method.lock_owned
+pub struct OwnedMutexGuard<T> {
+    lock: Arc<Mutex<T>>,
+    value: Option<T>,
+}
+
+/// An error returned by [`Mutex::try_lock`], [`RwLock::try_read`] and [`RwLock::try_write`]
+/// when the lock is held.
+///
+/// [`Mutex::try_lock`]: struct.Mutex.html#method.try_lock
+/// [`RwLock::try_read`]: struct.RwLock.html#method.try_read
+/// [`RwLock::try_write`]: struct.RwLock.html#method.try_write
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TryLockError(pub(super) ());
+
+impl Display for TryLockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("the lock is held")
+    }
+}
+
+impl Error for TryLockError {}
+
+impl<T> Mutex<T> {
+    /// Creates an unlocked mutex holding `value`.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            semaphore: Semaphore::new(1),
+            value: std::sync::Mutex::new(Some(value)),
+        }
+    }
+
+    /// Takes the value out once the lock is acquired.
+    #[inline]
+    fn take(&self) -> T {
+        self.value
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .expect("the value of a mutex is taken twice")
+    }
+
+    /// Puts the value back and releases the lock.
+    #[inline]
+    fn unlock(&self, value: T) {
+        *self.value.lock().unwrap_or_else(PoisonError::into_inner) = Some(value);
+        self.semaphore.release(1)
+    }
+
+    /// Locks the mutex, waiting until it is unlocked.
+    ///
+    /// The lock is released when the guard is dropped. Cancelling the wait gives the turn
+    /// to the next task.
+    #[inline]
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        let acquired = self.semaphore.acquire(1).await;
+        debug_assert!(acquired.is_ok(), "the semaphore of a mutex is never closed");
+        MutexGuard {
+            lock: self,
+            value: Some(self.take()),
+        }
+    }
+
+    /// Locks the mutex if it is unlocked and no task is waiting for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::sync::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// let guard = mutex.try_lock().unwrap();
+    /// assert!(mutex.try_lock().is_err());
+    /// drop(guard);
+    /// assert_eq!(1, *mutex.try_lock().unwrap());
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn try_lock(&self) -> Result<MutexGuard<'_, T>, TryLockError> {
+        match self.semaphore.try_acquire(1) {
+            Ok(()) => Ok(MutexGuard {
+                lock: self,
+                value: Some(self.take()),
+            }),
+            Err(_) => Err(TryLockError(())),
+        }
+    }
+
+    /// Locks the mutex behind an `Arc`, waiting until it is unlocked.
+    ///
+    /// The guard owns a clone of the `Arc` instead of borrowing the mutex, so it is `'static`
+    /// and can be moved into a spawned task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use std::sync::Arc;
+    /// use tio::sync::Mutex;
+    /// use tio::task;
+    ///
+    /// let mutex = Arc::new(Mutex::new(Vec::new()));
+    /// let mut guard = mutex.clone().lock_owned().await;
+    /// let handle = task::spawn(async move { guard.push(1) });
+    /// handle.await.unwrap();
+    /// assert_eq!(vec![1], *mutex.lock().await);
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub async fn lock_owned(self: Arc<Self>) -> OwnedMutexGuard<T> {
+        let acquired = self.semaphore.acquire(1).await;
+        debug_assert!(acquired.is_ok(), "the semaphore of a mutex is never closed");
+        let value = Some(self.take());
+        OwnedMutexGuard { lock: self, value }
+    }
+
+    /// Locks the mutex behind an `Arc` if it is unlocked and no task is waiting for it.
+    #[inline]
+    pub fn try_lock_owned(self: Arc<Self>) -> Result<OwnedMutexGuard<T>, TryLockError> {
+        match self.semaphore.try_acquire(1) {
+            Ok(()) => {
+                let value = Some(self.take());
+                Ok(OwnedMutexGuard { lock: self, value })
+            }
+            Err(_) => Err(TryLockError(())),
+        }
+    }
+
+    /// Returns a mutable reference to the value, which needs no locking.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .expect("a mutex borrowed mutably cannot be locked")
+    }
+
+    /// Consumes the mutex, returning the value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .expect("a mutex moved cannot be locked")
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for Mutex<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Debug> Debug for Mutex<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Mutex");
+        match self.try_lock() {
+            Ok(guard) => f.field("value", &*guard),
+            Err(_) => f.field("value", &format_args!("<locked>")),
+        };
+        f.finish()
+    }
+}
+
+macro_rules! impl_guard {
+    ($guard:ident $(<$lt:lifetime>)?) => {
+        impl<$($lt,)? T> Deref for $guard<$($lt,)? T> {
+            type Target = T;
+
+            #[inline]
+            fn deref(&self) -> &T {
+                self.value.as_ref().expect("the guard is dropped")
+            }
+        }
+
+        impl<$($lt,)? T> DerefMut for $guard<$($lt,)? T> {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut T {
+                self.value.as_mut().expect("the guard is dropped")
+            }
+        }
+
+        impl<$($lt,)? T> Drop for $guard<$($lt,)? T> {
+            #[inline]
+            fn drop(&mut self) {
+                if let Some(value) = self.value.take() {
+                    self.lock.unlock(value)
+                }
+            }
+        }
+
+        impl<$($lt,)? T: Debug> Debug for $guard<$($lt,)? T> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&**self, f)
+            }
+        }
+
+        impl<$($lt,)? T: Display> Display for $guard<$($lt,)? T> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                Display::fmt(&**self, f)
+            }
+        }
+    };
+}
+
+impl_guard!(MutexGuard<'a>);
+impl_guard!(OwnedMutexGuard);
+
+impl<'a, T> MutexGuard<'a, T> {
+    /// Returns the mutex this guard locks.
+    #[inline]
+    pub fn mutex(this: &Self) -> &'a Mutex<T> {
+        this.lock
+    }
+}
+
+impl<T> OwnedMutexGuard<T> {
+    /// Returns the mutex this guard locks.
+    #[inline]
+    pub fn mutex(this: &Self) -> &Arc<Mutex<T>> {
+        &this.lock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mutex;
+    use crate::task;
+    use futures::FutureExt;
+    use std::sync::Arc;
+
+    #[test]
+    fn fair() {
+        task::block_on(async {
+            let mutex = Mutex::new(0);
+            let guard = mutex.lock().await;
+            let mut first = Box::pin(mutex.lock());
+            let mut second = Box::pin(mutex.lock());
+            assert!((&mut first).now_or_never().is_none());
+            assert!((&mut second).now_or_never().is_none());
+            drop(guard);
+            // the lock goes to the first waiter, even if the second one is polled first
+            assert!((&mut second).now_or_never().is_none());
+            assert!(mutex.try_lock().is_err());
+            let mut guard = first.now_or_never().unwrap();
+            *guard += 1;
+            drop(guard);
+            assert_eq!(1, *second.now_or_never().unwrap());
+        })
+    }
+
+    #[test]
+    fn cancel_lock() {
+        task::block_on(async {
+            let mutex = Mutex::new(1);
+            let guard = mutex.lock().await;
+            let mut waiting = Box::pin(mutex.lock());
+            assert!((&mut waiting).now_or_never().is_none());
+            drop(guard);
+            // the lock is handed to the cancelled waiter, which gives it back
+            drop(waiting);
+            assert_eq!(1, *mutex.try_lock().unwrap());
+        })
+    }
+
+    #[test]
+    fn owned() {
+        let mutex = Arc::new(Mutex::new(1));
+        let mut guard = mutex.clone().try_lock_owned().unwrap();
+        assert!(mutex.clone().try_lock_owned().is_err());
+        *guard += 1;
+        drop(guard);
+        assert_eq!(2, Arc::try_unwrap(mutex).ok().unwrap().into_inner());
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+const NOTIFY_LOCK_POISONED: &str = "notify lock poisoned";
+
+/// Notifies tasks to wake up, without sending any data.
+///
+/// A task waits by awaiting [`notified`], and another one wakes it by [`notify_one`] or
+/// [`notify_waiters`].
+///
+/// [`notify_one`] stores a permit if no task is waiting, so the next call to [`notified`]
+/// completes at once: a notification is never lost between checking for some work and waiting
+/// for it. Multiple calls store one permit at most.
+///
+/// [`notified`]: #method.notified
+/// [`notify_one`]: #method.notify_one
+/// [`notify_waiters`]: #method.notify_waiters
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use std::sync::Arc;
+/// use tio::sync::Notify;
+/// use tio::task;
+///
+/// let notify = Arc::new(Notify::new());
+/// let flusher = task::spawn({
+///     let notify = notify.clone();
+///     async move {
+///         // there is new work to flush
+///         notify.notified().await;
+///     }
+/// });
+/// notify.notify_one();
+/// flusher.await.unwrap();
+/// #
+/// # })
+/// ```
+pub struct Notify {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    permit: bool,
+    // keyed by the arrival order
+    waiters: BTreeMap<u64, Waiter>,
+    next_id: u64,
+    // bumped by `notify_waiters`, to complete the futures created before it
+    generation: u64,
+}
+
+struct Waiter {
+    waker: Waker,
+    notified: Option<Notification>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub(super) enum Notification {
+    One,
+    All,
+}
+
+impl State {
+    /// Wakes the first waiter which is not notified yet, or stores a permit.
+    fn notify_one(&mut self) -> Option<Waker> {
+        match self
+            .waiters
+            .values_mut()
+            .find(|waiter| waiter.notified.is_none())
+        {
+            Some(waiter) => {
+                waiter.notified = Some(Notification::One);
+                Some(waiter.waker.clone())
+            }
+            None => {
+                self.permit = true;
+                None
+            }
+        }
+    }
+}
+
+impl Notify {
+    /// Creates a `Notify` without any permit.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect(NOTIFY_LOCK_POISONED)
+    }
+
+    /// Returns a future which completes once this is notified.
+    ///
+    /// The future starts waiting when it is first polled, but it also completes by the
+    /// [`notify_waiters`] calls after it is created.
+    ///
+    /// [`notify_waiters`]: #method.notify_waiters
+    #[inline]
+    pub fn notified(&self) -> Notified<'_> {
+        Notified {
+            notify: self,
+            generation: self.state().generation,
+            id: None,
+            done: false,
+        }
+    }
+
+    /// Wakes the task which has been waiting the longest, or stores a permit for the next
+    /// task to wait if none is waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::sync::Notify;
+    ///
+    /// let notify = Notify::new();
+    /// notify.notify_one();
+    /// // completes at once by the stored permit
+    /// notify.notified().await;
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn notify_one(&self) {
+        let waker = self.state().notify_one();
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+
+    /// Wakes all waiting tasks, without storing a permit.
+    ///
+    /// The futures returned by [`notified`] before this call complete, even if they are not
+    /// polled yet.
+    ///
+    /// [`notified`]: #method.notified
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::sync::Notify;
+    ///
+    /// let notify = Notify::new();
+    /// let first = notify.notified();
+    /// let second = notify.notified();
+    /// notify.notify_waiters();
+    /// first.await;
+    /// second.await;
+    /// #
+    /// # })
+    /// ```
+    pub fn notify_waiters(&self) {
+        let wakers = {
+            let mut state = self.state();
+            state.generation += 1;
+            state
+                .waiters
+                .values_mut()
+                .filter(|waiter| waiter.notified.is_none())
+                .map(|waiter| {
+                    waiter.notified = Some(Notification::All);
+                    waiter.waker.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        wakers.into_iter().for_each(Waker::wake)
+    }
+}
+
+impl Default for Notify {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Notify {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("Notify")
+            .field("permit", &state.permit)
+            .field("waiters", &state.waiters.len())
+            .finish()
+    }
+}
+
+/// A future which completes once a [`Notify`] is notified, returned by [`Notify::notified`].
+///
+/// [`Notify`]: struct.Notify.html
+/// [`Notify::notified`]: struct.Notify.html#method.notified
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Notified<'a> {
+    notify: &'a Notify,
+    generation: u64,
+    id: Option<u64>,
+    done: bool,
+}
+
+impl Future for Notified<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.done {
+            return Poll::Ready(());
+        }
+        let mut state = self.notify.state();
+        match self.id {
+            None if state.generation != self.generation => (),
+            None if state.permit => state.permit = false,
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                let waiter = Waiter {
+                    waker: cx.waker().clone(),
+                    notified: None,
+                };
+                state.waiters.insert(id, waiter);
+                drop(state);
+                self.id = Some(id);
+                return Poll::Pending;
+            }
+            Some(id) => {
+                let waiter = state
+                    .waiters
+                    .get_mut(&id)
+                    .expect("a waiter leaves only by its future");
+                if waiter.notified.is_none() {
+                    if !waiter.waker.will_wake(cx.waker()) {
+                        waiter.waker = cx.waker().clone();
+                    }
+                    return Poll::Pending;
+                }
+                state.waiters.remove(&id);
+            }
+        }
+        drop(state);
+        self.id = None;
+        self.done = true;
+        Poll::Ready(())
+    }
+}
+
+impl Drop for Notified<'_> {
+    fn drop(&mut self) {
+        let id = match self.id {
+            Some(id) => id,
+            None => return,
+        };
+        let waker = {
+            let mut state = self.notify.state();
+            match state.waiters.remove(&id) {
+                // pass the notification on, which would be lost otherwise
+                Some(Waiter {
+                    notified: Some(Notification::One),
+                    ..
+                }) => state.notify_one(),
+                _ => None,
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+}
+
+impl Debug for Notified<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Notified")
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Notify;
+    use crate::task;
+    use futures::FutureExt;
+
+    #[test]
+    fn single_permit() {
+        task::block_on(async {
+            let notify = Notify::new();
+            notify.notify_one();
+            notify.notify_one();
+            notify.notified().await;
+            assert!(notify.notified().now_or_never().is_none());
+        })
+    }
+
+    #[test]
+    fn notify_one_in_order() {
+        let notify = Notify::new();
+        let mut first = Box::pin(notify.notified());
+        let mut second = Box::pin(notify.notified());
+        assert!((&mut first).now_or_never().is_none());
+        assert!((&mut second).now_or_never().is_none());
+        notify.notify_one();
+        assert!((&mut second).now_or_never().is_none());
+        assert!(first.now_or_never().is_some());
+    }
+
+    #[test]
+    fn forward_on_drop() {
+        let notify = Notify::new();
+        let mut first = Box::pin(notify.notified());
+        let mut second = Box::pin(notify.notified());
+        assert!((&mut first).now_or_never().is_none());
+        assert!((&mut second).now_or_never().is_none());
+        notify.notify_one();
+        drop(first);
+        assert!(second.now_or_never().is_some());
+    }
+
+    #[test]
+    fn notify_waiters() {
+        let notify = Notify::new();
+        let mut polled = Box::pin(notify.notified());
+        assert!((&mut polled).now_or_never().is_none());
+        let unpolled = notify.notified();
+        notify.notify_waiters();
+        let late = notify.notified();
+        assert!(polled.now_or_never().is_some());
+        assert!(unpolled.now_or_never().is_some());
+        // no permit is stored
+        assert!(late.now_or_never().is_none());
+    }
+}
@@ -0,0 +1,259 @@
+use super::batch::Semaphore;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+
+/// A cell which is written once, by an asynchronous initializer.
+///
+/// Unlike [`once_cell::sync::OnceCell`], initializing it parks the task instead of the thread.
+/// Only one initializer runs at a time: the tasks calling [`get_or_init`] concurrently wait for
+/// the first one, and get the value it initializes. This fits establishing a shared resource
+/// lazily, like a database connection used by many tasks.
+///
+/// [`once_cell::sync::OnceCell`]: https://docs.rs/once_cell/1/once_cell/sync/struct.OnceCell.html
+/// [`get_or_init`]: #method.get_or_init
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use tio::sync::OnceCell;
+/// use tio::task;
+///
+/// static CONNECTS: AtomicUsize = AtomicUsize::new(0);
+///
+/// let cell = Arc::new(OnceCell::new());
+/// let handles = (0..5)
+///     .map(|_| {
+///         let cell = cell.clone();
+///         task::spawn(async move {
+///             *cell
+///                 .get_or_init(|| async {
+///                     // connect to the database
+///                     task::yield_now().await;
+///                     CONNECTS.fetch_add(1, Ordering::SeqCst)
+///                 })
+///                 .await
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for handle in handles {
+///     assert_eq!(0, handle.await.unwrap());
+/// }
+/// assert_eq!(1, CONNECTS.load(Ordering::SeqCst));
+/// #
+/// # })
+/// ```
+pub struct OnceCell<T> {
+    value: ::once_cell::sync::OnceCell<T>,
+    // held by the running initializer
+    semaphore: Semaphore,
+}
+
+/// Releases the turn to initialize when dropped, so a cancelled initializer passes it on.
+struct Initializing<'a>(&'a Semaphore);
+
+impl Drop for Initializing<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.release(1)
+    }
+}
+
+impl<T> OnceCell<T> {
+    /// Creates an empty cell.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            value: ::once_cell::sync::OnceCell::new(),
+            semaphore: Semaphore::new(1),
+        }
+    }
+
+    /// Returns the value, or `None` if the cell is not initialized yet.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    /// Returns a mutable reference to the value, or `None` if the cell is not initialized yet.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.value.get_mut()
+    }
+
+    /// Returns `true` if the cell is initialized.
+    #[inline]
+    pub fn initialized(&self) -> bool {
+        self.get().is_some()
+    }
+
+    /// Initializes the cell with `value`.
+    ///
+    /// Returns the value back if the cell is initialized, or some initializer is running.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::sync::OnceCell;
+    ///
+    /// let cell = OnceCell::new();
+    /// assert_eq!(Ok(()), cell.set(1));
+    /// assert_eq!(Err(2), cell.set(2));
+    /// assert_eq!(Some(&1), cell.get());
+    /// ```
+    pub fn set(&self, value: T) -> Result<(), T> {
+        if self.semaphore.try_acquire(1).is_err() {
+            return Err(value);
+        }
+        let _initializing = Initializing(&self.semaphore);
+        self.value.set(value)
+    }
+
+    /// Returns the value, initializing the cell by `f` if it is not initialized yet.
+    ///
+    /// If some initializer is running, this waits for it instead of running `f`. If that
+    /// initializer is cancelled, the next waiting task runs its own.
+    pub async fn get_or_init<F, Fut>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        let _initializing = self.initializing().await;
+        match self.get() {
+            Some(value) => value,
+            None => self.init(f().await),
+        }
+    }
+
+    /// Returns the value, initializing the cell by `f` if it is not initialized yet.
+    ///
+    /// The cell is left empty if `f` fails, and the error is returned. The next waiting task
+    /// then runs its own initializer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::sync::OnceCell;
+    ///
+    /// let cell = OnceCell::new();
+    /// assert_eq!(Err(()), cell.get_or_try_init(|| async { Err(()) }).await);
+    /// assert_eq!(Ok(&1), cell.get_or_try_init(|| async { Ok::<_, ()>(1) }).await);
+    /// #
+    /// # })
+    /// ```
+    pub async fn get_or_try_init<E, F, Fut>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+        let _initializing = self.initializing().await;
+        match self.get() {
+            Some(value) => Ok(value),
+            None => Ok(self.init(f().await?)),
+        }
+    }
+
+    /// Waits for the turn to initialize the cell.
+    async fn initializing(&self) -> Initializing<'_> {
+        let acquired = self.semaphore.acquire(1).await;
+        debug_assert!(
+            acquired.is_ok(),
+            "the semaphore of a once cell is never closed"
+        );
+        Initializing(&self.semaphore)
+    }
+
+    #[inline]
+    fn init(&self, value: T) -> &T {
+        if self.value.set(value).is_err() {
+            unreachable!("a once cell is initialized only by the turn to initialize it");
+        }
+        self.get().expect("the once cell is initialized")
+    }
+
+    /// Takes the value out, leaving the cell empty.
+    #[inline]
+    pub fn take(&mut self) -> Option<T> {
+        self.value.take()
+    }
+
+    /// Consumes the cell, returning the value if it is initialized.
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.value.into_inner()
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<T> for OnceCell<T> {
+    /// Creates a cell initialized with `value`.
+    #[inline]
+    fn from(value: T) -> Self {
+        let cell = Self::new();
+        if cell.set(value).is_err() {
+            unreachable!("a new once cell is empty");
+        }
+        cell
+    }
+}
+
+impl<T: Debug> Debug for OnceCell<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("OnceCell");
+        match self.get() {
+            Some(value) => f.field("value", value),
+            None => f.field("value", &format_args!("<uninit>")),
+        };
+        f.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OnceCell;
+    use crate::task;
+    use futures::future::{self, FutureExt};
+
+    #[test]
+    fn cancelled_initializer() {
+        task::block_on(async {
+            let cell = OnceCell::new();
+            let mut first = Box::pin(cell.get_or_init(future::pending));
+            let mut second = Box::pin(cell.get_or_init(|| async { 2 }));
+            assert!((&mut first).now_or_never().is_none());
+            assert!((&mut second).now_or_never().is_none());
+            // the waiting initializer takes over
+            drop(first);
+            assert_eq!(&2, second.await);
+            assert_eq!(Err(3), cell.set(3));
+        })
+    }
+
+    #[test]
+    fn set_while_initializing() {
+        let cell = OnceCell::new();
+        let mut initializing = Box::pin(cell.get_or_init(future::pending));
+        assert!((&mut initializing).now_or_never().is_none());
+        assert_eq!(Err(1), cell.set(1));
+        drop(initializing);
+        assert_eq!(Ok(()), cell.set(1));
+        assert_eq!(Some(1), cell.into_inner());
+    }
+}
@@ -0,0 +1,327 @@
+//! A channel sending a single value between tasks.
+//!
+//! [`channel`] creates a [`Sender`] and a [`Receiver`]. The sender sends one value, which the
+//! receiver awaits, or the receiver gets an error if the sender is dropped without sending.
+//! This signals the completion of some work, like the response to a request sent to an actor.
+//!
+//! [`channel`]: fn.channel.html
+//! [`Sender`]: struct.Sender.html
+//! [`Receiver`]: struct.Receiver.html
+//!
+//! # Examples
+//!
+//! ```
+//! # tio::task::block_on(async {
+//! #
+//! use tio::sync::oneshot;
+//! use tio::task;
+//!
+//! let (sender, receiver) = oneshot::channel();
+//! task::spawn(async move {
+//!     sender.send(1 + 2).unwrap();
+//! });
+//! assert_eq!(Ok(3), receiver.await);
+//! #
+//! # })
+//! ```
+
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+const CHANNEL_LOCK_POISONED: &str = "oneshot channel lock poisoned";
+
+/// Creates a oneshot channel, returning its two halves.
+#[inline]
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            value: None,
+            sent: false,
+            sender_dropped: false,
+            receiver_closed: false,
+            receiver: None,
+            sender: None,
+        }),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+}
+
+struct State<T> {
+    value: Option<T>,
+    sent: bool,
+    sender_dropped: bool,
+    receiver_closed: bool,
+    // the wakers of the tasks waiting on each half
+    receiver: Option<Waker>,
+    sender: Option<Waker>,
+}
+
+impl<T> Shared<T> {
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().expect(CHANNEL_LOCK_POISONED)
+    }
+}
+
+/// The sending half of a oneshot channel, created by [`channel`].
+///
+/// [`channel`]: fn.channel.html
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving half of a oneshot channel, created by [`channel`].
+///
+/// It is a future which completes with the value, or with an error if the sender is dropped
+/// without sending it.
+///
+/// [`channel`]: fn.channel.html
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// An error returned by a [`Receiver`] when the sender is dropped without sending.
+///
+/// [`Receiver`]: struct.Receiver.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RecvError(());
+
+impl Display for RecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("the sender is dropped")
+    }
+}
+
+impl Error for RecvError {}
+
+/// An error returned by [`Receiver::try_recv`].
+///
+/// [`Receiver::try_recv`]: struct.Receiver.html#method.try_recv
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TryRecvError {
+    /// The value is not sent yet.
+    Empty,
+
+    /// The sender is dropped without sending, or the value is received already.
+    Closed,
+}
+
+impl Display for TryRecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TryRecvError::Empty => "the channel is empty",
+            TryRecvError::Closed => "the channel is closed",
+        })
+    }
+}
+
+impl Error for TryRecvError {}
+
+impl<T> Sender<T> {
+    /// Sends the value, consuming the sender.
+    ///
+    /// Returns the value back if the receiver is dropped or closed.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let waker = {
+            let mut state = self.shared.state();
+            if state.receiver_closed {
+                return Err(value);
+            }
+            state.value = Some(value);
+            state.sent = true;
+            state.receiver.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the receiver is dropped or closed, so a value would never be received.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.shared.state().receiver_closed
+    }
+
+    /// Polls whether the receiver is dropped or closed, registering the task to be woken
+    /// once it is.
+    pub fn poll_closed(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.shared.state();
+        if state.receiver_closed {
+            return Poll::Ready(());
+        }
+        match &state.sender {
+            Some(waker) if waker.will_wake(cx.waker()) => (),
+            _ => state.sender = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+
+    /// Waits until the receiver is dropped or closed.
+    ///
+    /// This lets the task computing the value give up once nobody is waiting for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::sync::oneshot;
+    /// use tio::task;
+    ///
+    /// let (mut sender, receiver) = oneshot::channel::<()>();
+    /// let worker = task::spawn(async move {
+    ///     sender.closed().await;
+    ///     sender.is_closed()
+    /// });
+    /// drop(receiver);
+    /// assert!(worker.await.unwrap());
+    /// #
+    /// # })
+    /// ```
+    pub async fn closed(&mut self) {
+        futures::future::poll_fn(|cx| self.poll_closed(cx)).await
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.shared.state();
+            state.sender_dropped = true;
+            state.receiver.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receives the value if it is sent, without waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::sync::oneshot::{self, TryRecvError};
+    ///
+    /// let (sender, mut receiver) = oneshot::channel();
+    /// assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+    /// sender.send(1).unwrap();
+    /// assert_eq!(Ok(1), receiver.try_recv());
+    /// assert_eq!(Err(TryRecvError::Closed), receiver.try_recv());
+    /// ```
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.state();
+        match state.value.take() {
+            Some(value) => Ok(value),
+            None if state.sent || state.sender_dropped => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Closes the channel without dropping the receiver.
+    ///
+    /// The sender fails to send afterwards, but a value sent before can still be received.
+    pub fn close(&mut self) {
+        let waker = {
+            let mut state = self.shared.state();
+            state.receiver_closed = true;
+            state.sender.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state();
+        if let Some(value) = state.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+        if state.sent || state.sender_dropped {
+            return Poll::Ready(Err(RecvError(())));
+        }
+        match &state.receiver {
+            Some(waker) if waker.will_wake(cx.waker()) => (),
+            _ => state.receiver = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.close()
+    }
+}
+
+impl<T> Debug for Sender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+impl<T> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channel, RecvError, TryRecvError};
+    use crate::task;
+    use futures::FutureExt;
+
+    #[test]
+    fn sender_dropped() {
+        task::block_on(async {
+            let (sender, receiver) = channel::<()>();
+            let waiter = task::spawn(receiver);
+            task::yield_now().await;
+            drop(sender);
+            assert_eq!(Err(RecvError(())), waiter.await.unwrap());
+        })
+    }
+
+    #[test]
+    fn receiver_closed() {
+        let (mut sender, mut receiver) = channel();
+        assert!(!sender.is_closed());
+        receiver.close();
+        assert!(sender.is_closed());
+        assert!(sender.closed().now_or_never().is_some());
+        assert_eq!(Err(1), sender.send(1));
+        assert_eq!(Err(TryRecvError::Closed), receiver.try_recv());
+    }
+
+    #[test]
+    fn sent_before_close() {
+        let (sender, mut receiver) = channel();
+        sender.send(1).unwrap();
+        receiver.close();
+        assert_eq!(Some(Ok(1)), receiver.now_or_never());
+    }
+}
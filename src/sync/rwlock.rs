@@ -0,0 +1,512 @@
+use super::batch::Semaphore;
+use super::TryLockError;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, PoisonError};
+
+/// The permits of a write lock, one for every reader.
+const MAX_READS: usize = Semaphore::MAX_PERMITS;
+
+/// An asynchronous reader-writer lock.
+///
+/// Any number of readers or a single writer may hold the lock at once. Unlike
+/// [`std::sync::RwLock`], waiting for it parks the task instead of the thread, and its guards
+/// can be held across `.await` points.
+///
+/// The lock is fair by default, the tasks get it in the order they start waiting. In particular,
+/// a writer waiting for the readers to leave blocks the readers arriving after it, so the
+/// writers never starve under a steady stream of readers. Other trade-offs are chosen by
+/// [`with_policy`].
+///
+/// [`std::sync::RwLock`]: https://doc.rust-lang.org/std/sync/struct.RwLock.html
+/// [`with_policy`]: #method.with_policy
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::sync::RwLock;
+///
+/// let lock = RwLock::new(5);
+/// {
+///     let first = lock.read().await;
+///     let second = lock.read().await;
+///     assert_eq!(10, *first + *second);
+/// }
+/// *lock.write().await += 1;
+/// assert_eq!(6, *lock.read().await);
+/// #
+/// # })
+/// ```
+pub struct RwLock<T> {
+    policy: RwLockPolicy,
+    semaphore: Semaphore,
+    // shared by the readers, taken by the writer
+    value: std::sync::Mutex<Option<Arc<T>>>,
+}
+
+/// A guard which releases the shared access to a [`RwLock`] when dropped, returned by
+/// [`RwLock::read`].
+///
+/// [`RwLock`]: struct.RwLock.html
+/// [`RwLock::read`]: struct.RwLock.html#method.read
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    value: Option<Arc<T>>,
+}
+
+/// A guard which releases the exclusive access to a [`RwLock`] when dropped, returned by
+/// [`RwLock::write`].
+///
+/// [`RwLock`]: struct.RwLock.html
+/// [`RwLock::write`]: struct.RwLock.html#method.write
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    value: Option<Arc<T>>,
+}
+
+/// An owned guard of the shared access to a [`RwLock`], returned by [`RwLock::read_owned`].
+///
+/// [`RwLock`]: struct.RwLock.html
+/// [`RwLock::read_owned`]: struct.RwLock.html#method.read_owned
+pub struct OwnedRwLockReadGuard<T> {
+    lock: Arc<RwLock<T>>,
+    value: Option<Arc<T>>,
+}
+
+/// An owned guard of the exclusive access to a [`RwLock`], returned by
+/// [`RwLock::write_owned`].
+///
+/// [`RwLock`]: struct.RwLock.html
+/// [`RwLock::write_owned`]: struct.RwLock.html#method.write_owned
+pub struct OwnedRwLockWriteGuard<T> {
+    lock: Arc<RwLock<T>>,
+    value: Option<Arc<T>>,
+}
+
+/// The order in which the readers and writers of a [`RwLock`] get it.
+///
+/// [`RwLock`]: struct.RwLock.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RwLockPolicy {
+    /// The tasks get the lock in the order they start waiting, so neither readers nor writers
+    /// starve.
+    Fair,
+
+    /// A reader gets the lock whenever no writer holds it, even if some writers are waiting.
+    ///
+    /// This gives the readers the lowest latency, but the writers may starve under a steady
+    /// stream of readers.
+    ReadPreferring,
+
+    /// A waiting writer gets the lock before every waiting reader, even the ones which start
+    /// waiting before it.
+    ///
+    /// This keeps the value fresh for the readers, but the readers may starve under a steady
+    /// stream of writers.
+    WritePreferring,
+}
+
+impl RwLockPolicy {
+    /// The priority of the readers in the semaphore, lower ones served first.
+    #[inline]
+    fn read(self) -> u8 {
+        match self {
+            RwLockPolicy::WritePreferring => 1,
+            _ => 0,
+        }
+    }
+
+    /// The priority of the writers in the semaphore, lower ones served first.
+    #[inline]
+    fn write(self) -> u8 {
+        match self {
+            RwLockPolicy::ReadPreferring => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl Default for RwLockPolicy {
+    #[inline]
+    fn default() -> Self {
+        RwLockPolicy::Fair
+    }
+}
+
+impl<T> RwLock<T> {
+    /// Creates an unlocked fair lock holding `value`.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self::with_policy(value, RwLockPolicy::Fair)
+    }
+
+    /// Creates an unlocked lock holding `value`, ordering its readers and writers by
+    /// `policy`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use futures::FutureExt;
+    /// use tio::sync::{RwLock, RwLockPolicy};
+    ///
+    /// let lock = RwLock::with_policy(0, RwLockPolicy::ReadPreferring);
+    /// let reader = lock.read().await;
+    /// let mut writer = Box::pin(lock.write());
+    /// assert!((&mut writer).now_or_never().is_none());
+    /// // a waiting writer does not block the readers
+    /// assert!(lock.try_read().is_ok());
+    /// drop(reader);
+    /// assert!(writer.now_or_never().is_some());
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn with_policy(value: T, policy: RwLockPolicy) -> Self {
+        Self {
+            policy,
+            semaphore: Semaphore::new(MAX_READS),
+            value: std::sync::Mutex::new(Some(Arc::new(value))),
+        }
+    }
+
+    /// Returns the policy of this lock.
+    #[inline]
+    pub fn policy(&self) -> RwLockPolicy {
+        self.policy
+    }
+
+    /// Shares the value once a read permit is acquired.
+    #[inline]
+    fn share(&self) -> Arc<T> {
+        self.value
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .expect("the value of a read lock is taken")
+    }
+
+    /// Takes the value out once every permit is acquired.
+    #[inline]
+    fn take(&self) -> Arc<T> {
+        self.value
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .expect("the value of a write lock is taken twice")
+    }
+
+    /// Releases a read permit, after the value is no longer shared by the reader.
+    #[inline]
+    fn unlock_read(&self, value: Arc<T>) {
+        drop(value);
+        self.semaphore.release(1)
+    }
+
+    /// Puts the value back and releases the write permits.
+    #[inline]
+    fn unlock_write(&self, value: Arc<T>) {
+        *self.value.lock().unwrap_or_else(PoisonError::into_inner) = Some(value);
+        self.semaphore.release(MAX_READS)
+    }
+
+    /// Locks this lock with shared read access, waiting until no writer holds it or waits
+    /// before this call.
+    #[inline]
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        let acquired = self
+            .semaphore
+            .acquire_with_priority(1, self.policy.read())
+            .await;
+        debug_assert!(acquired.is_ok(), "the semaphore of a lock is never closed");
+        RwLockReadGuard {
+            lock: self,
+            value: Some(self.share()),
+        }
+    }
+
+    /// Locks this lock with shared read access if no writer holds it or waits for it.
+    #[inline]
+    pub fn try_read(&self) -> Result<RwLockReadGuard<'_, T>, TryLockError> {
+        self.semaphore
+            .try_acquire_with_priority(1, self.policy.read())
+            .map_err(|_| TryLockError(()))?;
+        Ok(RwLockReadGuard {
+            lock: self,
+            value: Some(self.share()),
+        })
+    }
+
+    /// Locks this lock with exclusive write access, waiting until every reader and writer
+    /// before this call leaves.
+    #[inline]
+    pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
+        let acquired = self
+            .semaphore
+            .acquire_with_priority(MAX_READS, self.policy.write())
+            .await;
+        debug_assert!(acquired.is_ok(), "the semaphore of a lock is never closed");
+        RwLockWriteGuard {
+            lock: self,
+            value: Some(self.take()),
+        }
+    }
+
+    /// Locks this lock with exclusive write access if nobody holds it or waits for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::sync::RwLock;
+    ///
+    /// let lock = RwLock::new(1);
+    /// let reader = lock.read().await;
+    /// assert!(lock.try_write().is_err());
+    /// drop(reader);
+    /// *lock.try_write().unwrap() += 1;
+    /// assert_eq!(2, *lock.read().await);
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn try_write(&self) -> Result<RwLockWriteGuard<'_, T>, TryLockError> {
+        self.semaphore
+            .try_acquire_with_priority(MAX_READS, self.policy.write())
+            .map_err(|_| TryLockError(()))?;
+        Ok(RwLockWriteGuard {
+            lock: self,
+            value: Some(self.take()),
+        })
+    }
+
+    /// Locks this lock behind an `Arc` with shared read access.
+    ///
+    /// The guard owns a clone of the `Arc` instead of borrowing the lock, so it is `'static`
+    /// and can be moved into a spawned task.
+    #[inline]
+    pub async fn read_owned(self: Arc<Self>) -> OwnedRwLockReadGuard<T> {
+        let acquired = self
+            .semaphore
+            .acquire_with_priority(1, self.policy.read())
+            .await;
+        debug_assert!(acquired.is_ok(), "the semaphore of a lock is never closed");
+        let value = Some(self.share());
+        OwnedRwLockReadGuard { lock: self, value }
+    }
+
+    /// Locks this lock behind an `Arc` with exclusive write access.
+    ///
+    /// The guard owns a clone of the `Arc` instead of borrowing the lock, so it is `'static`
+    /// and can be moved into a spawned task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use std::sync::Arc;
+    /// use tio::sync::RwLock;
+    /// use tio::task;
+    ///
+    /// let lock = Arc::new(RwLock::new(Vec::new()));
+    /// let mut guard = lock.clone().write_owned().await;
+    /// let handle = task::spawn(async move { guard.push(1) });
+    /// handle.await.unwrap();
+    /// assert_eq!(vec![1], *lock.read().await);
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub async fn write_owned(self: Arc<Self>) -> OwnedRwLockWriteGuard<T> {
+        let acquired = self
+            .semaphore
+            .acquire_with_priority(MAX_READS, self.policy.write())
+            .await;
+        debug_assert!(acquired.is_ok(), "the semaphore of a lock is never closed");
+        let value = Some(self.take());
+        OwnedRwLockWriteGuard { lock: self, value }
+    }
+
+    /// Returns a mutable reference to the value, which needs no locking.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        let value = self
+            .value
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .expect("a lock borrowed mutably cannot be locked");
+        Arc::get_mut(value).expect("a lock borrowed mutably cannot be shared")
+    }
+
+    /// Consumes the lock, returning the value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        let value = self
+            .value
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .expect("a lock moved cannot be locked");
+        Arc::try_unwrap(value)
+            .ok()
+            .expect("a lock moved cannot be shared")
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for RwLock<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Debug> Debug for RwLock<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("RwLock");
+        match self.try_read() {
+            Ok(guard) => f.field("value", &*guard),
+            Err(_) => f.field("value", &format_args!("<locked>")),
+        };
+        f.finish()
+    }
+}
+
+macro_rules! impl_guard {
+    ($guard:ident $(<$lt:lifetime>)?, $unlock:ident) => {
+        impl<$($lt,)? T> Deref for $guard<$($lt,)? T> {
+            type Target = T;
+
+            #[inline]
+            fn deref(&self) -> &T {
+                self.value.as_deref().expect("the guard is dropped")
+            }
+        }
+
+        impl<$($lt,)? T> Drop for $guard<$($lt,)? T> {
+            #[inline]
+            fn drop(&mut self) {
+                if let Some(value) = self.value.take() {
+                    self.lock.$unlock(value)
+                }
+            }
+        }
+
+        impl<$($lt,)? T: Debug> Debug for $guard<$($lt,)? T> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&**self, f)
+            }
+        }
+
+        impl<$($lt,)? T: Display> Display for $guard<$($lt,)? T> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                Display::fmt(&**self, f)
+            }
+        }
+    };
+}
+
+macro_rules! impl_deref_mut {
+    ($guard:ident $(<$lt:lifetime>)?) => {
+        impl<$($lt,)? T> DerefMut for $guard<$($lt,)? T> {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut T {
+                let value = self.value.as_mut().expect("the guard is dropped");
+                Arc::get_mut(value).expect("the value of a write lock is shared")
+            }
+        }
+    };
+}
+
+impl_guard!(RwLockReadGuard<'a>, unlock_read);
+impl_guard!(RwLockWriteGuard<'a>, unlock_write);
+impl_guard!(OwnedRwLockReadGuard, unlock_read);
+impl_guard!(OwnedRwLockWriteGuard, unlock_write);
+impl_deref_mut!(RwLockWriteGuard<'a>);
+impl_deref_mut!(OwnedRwLockWriteGuard);
+
+#[cfg(test)]
+mod tests {
+    use super::{RwLock, RwLockPolicy};
+    use crate::task;
+    use futures::FutureExt;
+    use std::sync::Arc;
+
+    #[test]
+    fn write_preferring() {
+        task::block_on(async {
+            let lock = RwLock::new(0);
+            let reader = lock.read().await;
+            let mut writer = Box::pin(lock.write());
+            assert!((&mut writer).now_or_never().is_none());
+            // a pending writer blocks the readers arriving after it
+            let mut late_reader = Box::pin(lock.read());
+            assert!((&mut late_reader).now_or_never().is_none());
+            assert!(lock.try_read().is_err());
+            drop(reader);
+            assert!((&mut late_reader).now_or_never().is_none());
+            *writer.now_or_never().unwrap() += 1;
+            assert_eq!(1, *late_reader.now_or_never().unwrap());
+        })
+    }
+
+    #[test]
+    fn concurrent_readers() {
+        task::block_on(async {
+            let lock = Arc::new(RwLock::new(1));
+            let mut readers = Vec::new();
+            for _ in 0..3 {
+                readers.push(lock.clone().read_owned().await);
+            }
+            assert_eq!(3, readers.iter().map(|reader| **reader).sum::<i32>());
+            assert!(lock.try_write().is_err());
+            drop(readers);
+            *lock.try_write().unwrap() += 1;
+            assert_eq!(2, Arc::try_unwrap(lock).ok().unwrap().into_inner());
+        })
+    }
+
+    #[test]
+    fn read_preferring() {
+        task::block_on(async {
+            let lock = RwLock::with_policy(0, RwLockPolicy::ReadPreferring);
+            let reader = lock.read().await;
+            let mut writer = Box::pin(lock.write());
+            assert!((&mut writer).now_or_never().is_none());
+            // the readers arriving after a waiting writer go first
+            let late_reader = lock.read().now_or_never().unwrap();
+            drop(reader);
+            assert!((&mut writer).now_or_never().is_none());
+            drop(late_reader);
+            assert!(writer.now_or_never().is_some());
+        })
+    }
+
+    #[test]
+    fn write_preferring_policy() {
+        task::block_on(async {
+            let lock = RwLock::with_policy(0, RwLockPolicy::WritePreferring);
+            let writer = lock.write().await;
+            let mut reader = Box::pin(lock.read());
+            assert!((&mut reader).now_or_never().is_none());
+            let mut late_writer = Box::pin(lock.write());
+            assert!((&mut late_writer).now_or_never().is_none());
+            drop(writer);
+            // the writer arriving after a waiting reader goes first
+            assert!((&mut reader).now_or_never().is_none());
+            *late_writer.now_or_never().unwrap() += 1;
+            assert_eq!(1, *reader.now_or_never().unwrap());
+        })
+    }
+}
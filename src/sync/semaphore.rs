@@ -0,0 +1,395 @@
+use super::batch;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::mem;
+use std::sync::Arc;
+
+/// An asynchronous counting semaphore.
+///
+/// A semaphore holds a number of permits, which tasks acquire and release. Acquiring a permit
+/// parks the task until one is available, which makes it the usual tool to bound the
+/// concurrency of some work, like the number of outbound connections. The semaphore is fair:
+/// the tasks get their permits in the order they start waiting for them.
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use std::sync::Arc;
+/// use tio::sync::Semaphore;
+/// use tio::task;
+///
+/// // at most 3 connections at once
+/// let semaphore = Arc::new(Semaphore::new(3));
+/// let handles = (0..10)
+///     .map(|_| {
+///         let semaphore = semaphore.clone();
+///         task::spawn(async move {
+///             let _permit = semaphore.acquire_owned().await.unwrap();
+///             // connect and talk to the server
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for handle in handles {
+///     handle.await.unwrap();
+/// }
+/// assert_eq!(3, semaphore.available_permits());
+/// #
+/// # })
+/// ```
+pub struct Semaphore {
+    inner: batch::Semaphore,
+}
+
+/// Permits acquired from a [`Semaphore`], which are released when dropped.
+///
+/// [`Semaphore`]: struct.Semaphore.html
+#[must_use = "the permits are released at once if unused"]
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+}
+
+/// Owned permits acquired from a [`Semaphore`], which are released when dropped.
+///
+/// They keep the semaphore alive by an `Arc`, so they can be moved into a spawned task.
+///
+/// [`Semaphore`]: struct.Semaphore.html
+#[must_use = "the permits are released at once if unused"]
+pub struct OwnedSemaphorePermit {
+    semaphore: Arc<Semaphore>,
+    permits: usize,
+}
+
+/// An error returned by the acquiring methods of a [`Semaphore`] once it is closed.
+///
+/// [`Semaphore`]: struct.Semaphore.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct AcquireError(pub(super) ());
+
+impl Display for AcquireError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("the semaphore is closed")
+    }
+}
+
+impl Error for AcquireError {}
+
+/// An error returned by the trying methods of a [`Semaphore`].
+///
+/// [`Semaphore`]: struct.Semaphore.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TryAcquireError {
+    /// The semaphore is closed.
+    Closed,
+
+    /// There are not enough permits available, or other tasks are waiting for them first.
+    NoPermits,
+}
+
+impl Display for TryAcquireError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TryAcquireError::Closed => "the semaphore is closed",
+            TryAcquireError::NoPermits => "no permits available",
+        })
+    }
+}
+
+impl Error for TryAcquireError {}
+
+impl Semaphore {
+    /// The max number of permits of a semaphore.
+    pub const MAX_PERMITS: usize = batch::Semaphore::MAX_PERMITS;
+
+    /// Creates a semaphore with `permits` available permits.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `permits` exceeds [`MAX_PERMITS`].
+    ///
+    /// [`MAX_PERMITS`]: #associatedconstant.MAX_PERMITS
+    #[inline]
+    pub fn new(permits: usize) -> Self {
+        Self {
+            inner: batch::Semaphore::new(permits),
+        }
+    }
+
+    /// Returns the number of available permits.
+    #[inline]
+    pub fn available_permits(&self) -> usize {
+        self.inner.available_permits()
+    }
+
+    /// Adds `n` permits, handing them to the waiting tasks first.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the permits exceed [`MAX_PERMITS`].
+    ///
+    /// [`MAX_PERMITS`]: #associatedconstant.MAX_PERMITS
+    #[inline]
+    pub fn add_permits(&self, n: usize) {
+        self.inner.release(n)
+    }
+
+    /// Acquires a permit, waiting until one is available.
+    ///
+    /// Returns an error if the semaphore is closed meanwhile.
+    #[inline]
+    pub async fn acquire(&self) -> Result<SemaphorePermit<'_>, AcquireError> {
+        self.acquire_many(1).await
+    }
+
+    /// Acquires `n` permits at once, waiting until they are available.
+    ///
+    /// The task waits in line with the others: until it gets its permits, the tasks arriving
+    /// after it wait too, even if they need fewer permits than available.
+    ///
+    /// Returns an error if the semaphore is closed meanwhile.
+    #[inline]
+    pub async fn acquire_many(
+        &self,
+        n: usize,
+    ) -> Result<SemaphorePermit<'_>, AcquireError> {
+        self.inner.acquire(n).await?;
+        Ok(SemaphorePermit {
+            semaphore: self,
+            permits: n,
+        })
+    }
+
+    /// Acquires a permit if one is available and no task is waiting before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::sync::{Semaphore, TryAcquireError};
+    ///
+    /// let semaphore = Semaphore::new(1);
+    /// let permit = semaphore.try_acquire().unwrap();
+    /// assert_eq!(Err(TryAcquireError::NoPermits), semaphore.try_acquire().map(drop));
+    /// drop(permit);
+    /// assert!(semaphore.try_acquire().is_ok());
+    /// ```
+    #[inline]
+    pub fn try_acquire(&self) -> Result<SemaphorePermit<'_>, TryAcquireError> {
+        self.try_acquire_many(1)
+    }
+
+    /// Acquires `n` permits if they are available and no task is waiting before.
+    #[inline]
+    pub fn try_acquire_many(
+        &self,
+        n: usize,
+    ) -> Result<SemaphorePermit<'_>, TryAcquireError> {
+        self.inner.try_acquire(n)?;
+        Ok(SemaphorePermit {
+            semaphore: self,
+            permits: n,
+        })
+    }
+
+    /// Acquires a permit of a semaphore behind an `Arc`, waiting until one is available.
+    ///
+    /// The permit owns a clone of the `Arc` instead of borrowing the semaphore, so it is
+    /// `'static` and can be moved into a spawned task.
+    #[inline]
+    pub async fn acquire_owned(
+        self: Arc<Self>,
+    ) -> Result<OwnedSemaphorePermit, AcquireError> {
+        self.acquire_many_owned(1).await
+    }
+
+    /// Acquires `n` permits of a semaphore behind an `Arc`, waiting until they are available.
+    #[inline]
+    pub async fn acquire_many_owned(
+        self: Arc<Self>,
+        n: usize,
+    ) -> Result<OwnedSemaphorePermit, AcquireError> {
+        self.inner.acquire(n).await?;
+        Ok(OwnedSemaphorePermit {
+            semaphore: self,
+            permits: n,
+        })
+    }
+
+    /// Acquires a permit of a semaphore behind an `Arc` if one is available and no task is
+    /// waiting before.
+    #[inline]
+    pub fn try_acquire_owned(
+        self: Arc<Self>,
+    ) -> Result<OwnedSemaphorePermit, TryAcquireError> {
+        self.try_acquire_many_owned(1)
+    }
+
+    /// Acquires `n` permits of a semaphore behind an `Arc` if they are available and no task
+    /// is waiting before.
+    #[inline]
+    pub fn try_acquire_many_owned(
+        self: Arc<Self>,
+        n: usize,
+    ) -> Result<OwnedSemaphorePermit, TryAcquireError> {
+        self.inner.try_acquire(n)?;
+        Ok(OwnedSemaphorePermit {
+            semaphore: self,
+            permits: n,
+        })
+    }
+
+    /// Closes the semaphore.
+    ///
+    /// The waiting tasks and every later acquisition fail, but the permits acquired so far
+    /// stay valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::sync::{Semaphore, TryAcquireError};
+    ///
+    /// let semaphore = Semaphore::new(1);
+    /// semaphore.close();
+    /// assert!(semaphore.acquire().await.is_err());
+    /// assert_eq!(Err(TryAcquireError::Closed), semaphore.try_acquire().map(drop));
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn close(&self) {
+        self.inner.close()
+    }
+
+    /// Returns `true` if the semaphore is closed.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+impl Debug for Semaphore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Semaphore")
+            .field("available_permits", &self.available_permits())
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+impl SemaphorePermit<'_> {
+    /// Returns the number of permits held.
+    #[inline]
+    pub fn num_permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Forgets the permits without releasing them, which removes them from the semaphore.
+    #[inline]
+    pub fn forget(mut self) {
+        self.permits = 0;
+    }
+}
+
+impl OwnedSemaphorePermit {
+    /// Returns the number of permits held.
+    #[inline]
+    pub fn num_permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Forgets the permits without releasing them, which removes them from the semaphore.
+    #[inline]
+    pub fn forget(mut self) {
+        self.permits = 0;
+    }
+
+    /// Returns the semaphore these permits are acquired from.
+    #[inline]
+    pub fn semaphore(&self) -> &Arc<Semaphore> {
+        &self.semaphore
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        match mem::take(&mut self.permits) {
+            0 => (),
+            permits => self.semaphore.add_permits(permits),
+        }
+    }
+}
+
+impl Drop for OwnedSemaphorePermit {
+    #[inline]
+    fn drop(&mut self) {
+        match mem::take(&mut self.permits) {
+            0 => (),
+            permits => self.semaphore.add_permits(permits),
+        }
+    }
+}
+
+impl Debug for SemaphorePermit<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SemaphorePermit")
+            .field("permits", &self.permits)
+            .finish()
+    }
+}
+
+impl Debug for OwnedSemaphorePermit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OwnedSemaphorePermit")
+            .field("permits", &self.permits)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Semaphore;
+    use crate::task;
+    use futures::FutureExt;
+    use std::sync::Arc;
+
+    #[test]
+    fn acquire_many() {
+        task::block_on(async {
+            let semaphore = Semaphore::new(3);
+            let permit = semaphore.acquire_many(2).await.unwrap();
+            assert_eq!(2, permit.num_permits());
+            assert!(semaphore.try_acquire_many(2).is_err());
+            let mut waiting = Box::pin(semaphore.acquire_many(3));
+            assert!((&mut waiting).now_or_never().is_none());
+            // the waiting task is served first
+            assert!(semaphore.try_acquire().is_err());
+            drop(permit);
+            assert_eq!(3, waiting.now_or_never().unwrap().unwrap().num_permits());
+            assert_eq!(3, semaphore.available_permits());
+        })
+    }
+
+    #[test]
+    fn forget() {
+        let semaphore = Arc::new(Semaphore::new(2));
+        semaphore.clone().try_acquire_owned().unwrap().forget();
+        assert_eq!(1, semaphore.available_permits());
+        semaphore.add_permits(2);
+        assert_eq!(3, semaphore.available_permits());
+    }
+
+    #[test]
+    fn close_waiting() {
+        task::block_on(async {
+            let semaphore = Arc::new(Semaphore::new(0));
+            let mut waiting = Box::pin(semaphore.clone().acquire_owned());
+            assert!((&mut waiting).now_or_never().is_none());
+            semaphore.close();
+            assert!(waiting.await.is_err());
+        })
+    }
+}
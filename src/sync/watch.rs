@@ -0,0 +1,424 @@
+//! A channel holding only the latest value, watched by many receivers.
+//!
+//! [`channel`] creates a [`Sender`] and a [`Receiver`] sharing a single value. Sending replaces
+//! the value and marks it changed for every receiver, which borrows the latest value by
+//! [`Receiver::borrow`] or waits for the next change by [`Receiver::changed`]. Values sent in
+//! between are never seen, so this fits propagating snapshots like a configuration or a health
+//! state cheaply to many tasks. More receivers are created by cloning one.
+//!
+//! [`channel`]: fn.channel.html
+//! [`Sender`]: struct.Sender.html
+//! [`Receiver`]: struct.Receiver.html
+//! [`Receiver::borrow`]: struct.Receiver.html#method.borrow
+//! [`Receiver::changed`]: struct.Receiver.html#method.changed
+//!
+//! # Examples
+//!
+//! ```
+//! # tio::task::block_on(async {
+//! #
+//! use tio::sync::watch;
+//! use tio::task;
+//!
+//! let (sender, mut receiver) = watch::channel("starting");
+//! let handle = task::spawn(async move {
+//!     while receiver.changed().await.is_ok() {
+//!         if *receiver.borrow() == "ready" {
+//!             return true;
+//!         }
+//!     }
+//!     false
+//! });
+//! sender.send("ready").unwrap();
+//! assert!(handle.await.unwrap());
+//! #
+//! # })
+//! ```
+
+use futures::future::poll_fn;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard};
+use std::task::{Poll, Waker};
+
+const CHANNEL_LOCK_POISONED: &str = "watch channel lock poisoned";
+
+/// Creates a watch channel holding `init`, returning its sender and a receiver.
+///
+/// The initial value counts as seen by the receiver.
+pub fn channel<T>(init: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        value: RwLock::new(init),
+        state: Mutex::new(State {
+            version: 0,
+            closed: false,
+            receivers: 0,
+            next_id: 0,
+            waiters: BTreeMap::new(),
+            sender: None,
+        }),
+    });
+    let receiver = Receiver::subscribe(&shared);
+    (Sender { shared }, receiver)
+}
+
+struct Shared<T> {
+    value: RwLock<T>,
+    state: Mutex<State>,
+}
+
+struct State {
+    // bumped by each value sent, while the value is locked for writing
+    version: u64,
+    // set once the sender is dropped
+    closed: bool,
+    receivers: usize,
+    next_id: u64,
+    // the wakers of the waiting receivers, keyed by their ids
+    waiters: BTreeMap<u64, Waker>,
+    // the waker of the sender waiting for all receivers to be dropped
+    sender: Option<Waker>,
+}
+
+impl<T> Shared<T> {
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect(CHANNEL_LOCK_POISONED)
+    }
+
+    #[inline]
+    fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.value.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The sending half of a watch channel, created by [`channel`].
+///
+/// [`channel`]: fn.channel.html
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving half of a watch channel, created by [`channel`] or [`Sender::subscribe`].
+///
+/// [`channel`]: fn.channel.html
+/// [`Sender::subscribe`]: struct.Sender.html#method.subscribe
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    id: u64,
+    // the version of the value seen last
+    version: u64,
+}
+
+/// A borrow of the value in a watch channel, returned by [`Receiver::borrow`] and
+/// [`Sender::borrow`].
+///
+/// Sending is blocked while it is held, so it should not be held across `.await` points.
+///
+/// [`Receiver::borrow`]: struct.Receiver.html#method.borrow
+/// [`Sender::borrow`]: struct.Sender.html#method.borrow
+pub struct Ref<'a, T> {
+    guard: RwLockReadGuard<'a, T>,
+}
+
+/// An error returned by [`Sender::send`] if all receivers are dropped, holding the value back.
+///
+/// [`Sender::send`]: struct.Sender.html#method.send
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct SendError<T>(pub T);
+
+impl<T> Debug for SendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SendError").finish()
+    }
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("there is no receiver")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// An error returned by [`Receiver::changed`] if the sender is dropped.
+///
+/// [`Receiver::changed`]: struct.Receiver.html#method.changed
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RecvError(());
+
+impl Display for RecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("the sender is dropped")
+    }
+}
+
+impl Error for RecvError {}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: Debug> Debug for Ref<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> Sender<T> {
+    /// Replaces the value, notifying all receivers.
+    ///
+    /// Returns the value back if all receivers are dropped.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut guard = self
+            .shared
+            .value
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let waiters = {
+            let mut state = self.shared.state();
+            if state.receivers == 0 {
+                return Err(SendError(value));
+            }
+            state.version += 1;
+            std::mem::take(&mut state.waiters)
+        };
+        *guard = value;
+        drop(guard);
+        waiters.into_iter().for_each(|(_, waker)| waker.wake());
+        Ok(())
+    }
+
+    /// Borrows the value sent last.
+    #[inline]
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.read(),
+        }
+    }
+
+    /// Creates a receiver, which sees the current value.
+    #[inline]
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver::subscribe(&self.shared)
+    }
+
+    /// Returns the number of receivers.
+    #[inline]
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state().receivers
+    }
+
+    /// Returns `true` if all receivers are dropped.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.receiver_count() == 0
+    }
+
+    /// Waits until all receivers are dropped.
+    pub async fn closed(&self) {
+        poll_fn(|cx| {
+            let mut state = self.shared.state();
+            if state.receivers == 0 {
+                return Poll::Ready(());
+            }
+            match &state.sender {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => state.sender = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let waiters = {
+            let mut state = self.shared.state();
+            state.closed = true;
+            std::mem::take(&mut state.waiters)
+        };
+        waiters.into_iter().for_each(|(_, waker)| waker.wake())
+    }
+}
+
+impl<T> Receiver<T> {
+    fn subscribe(shared: &Arc<Shared<T>>) -> Self {
+        let mut state = shared.state();
+        state.receivers += 1;
+        let id = state.next_id;
+        state.next_id += 1;
+        let version = state.version;
+        drop(state);
+        Self {
+            shared: shared.clone(),
+            id,
+            version,
+        }
+    }
+
+    /// Borrows the latest value, without marking it seen.
+    #[inline]
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.read(),
+        }
+    }
+
+    /// Borrows the latest value, marking it seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::sync::watch;
+    ///
+    /// let (sender, mut receiver) = watch::channel(1);
+    /// sender.send(2).unwrap();
+    /// assert!(receiver.has_changed().unwrap());
+    /// assert_eq!(2, *receiver.borrow_and_update());
+    /// assert!(!receiver.has_changed().unwrap());
+    /// ```
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        let guard = self.shared.read();
+        // the version cannot change while the value is locked
+        self.version = self.shared.state().version;
+        Ref { guard }
+    }
+
+    /// Returns `true` if a value is sent since the one seen last.
+    ///
+    /// Returns an error if the sender is dropped.
+    pub fn has_changed(&self) -> Result<bool, RecvError> {
+        let state = self.shared.state();
+        if state.closed {
+            return Err(RecvError(()));
+        }
+        Ok(state.version != self.version)
+    }
+
+    /// Waits until a value is sent since the one seen last, marking it seen.
+    ///
+    /// It completes at once if such a value is sent already. Returns an error if the sender
+    /// is dropped without sending a new value.
+    pub async fn changed(&mut self) -> Result<(), RecvError> {
+        poll_fn(|cx| {
+            let mut state = self.shared.state();
+            if state.version != self.version {
+                self.version = state.version;
+                return Poll::Ready(Ok(()));
+            }
+            if state.closed {
+                return Poll::Ready(Err(RecvError(())));
+            }
+            match state.waiters.get(&self.id) {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => {
+                    state.waiters.insert(self.id, cx.waker().clone());
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    /// Creates another receiver, which has seen the same version as this one.
+    fn clone(&self) -> Self {
+        let mut receiver = Self::subscribe(&self.shared);
+        receiver.version = self.version;
+        receiver
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.shared.state();
+            state.receivers -= 1;
+            state.waiters.remove(&self.id);
+            match state.receivers {
+                0 => state.sender.take(),
+                _ => None,
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+}
+
+impl<T: Debug> Debug for Sender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("value", &*self.borrow())
+            .finish()
+    }
+}
+
+impl<T: Debug> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("value", &*self.borrow())
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::channel;
+    use crate::task;
+    use futures::FutureExt;
+
+    #[test]
+    fn latest_only() {
+        let (sender, mut receiver) = channel(0);
+        assert!(receiver.changed().now_or_never().is_none());
+        for i in 1..=3 {
+            sender.send(i).unwrap();
+        }
+        assert_eq!(Some(Ok(())), receiver.changed().now_or_never());
+        assert_eq!(3, *receiver.borrow());
+        // the values in between are never seen
+        assert!(receiver.changed().now_or_never().is_none());
+    }
+
+    #[test]
+    fn sender_dropped() {
+        task::block_on(async {
+            let (sender, mut receiver) = channel(0);
+            let mut other = receiver.clone();
+            let waiter = task::spawn(async move { other.changed().await });
+            task::yield_now().await;
+            drop(sender);
+            assert!(waiter.await.unwrap().is_err());
+            assert!(receiver.changed().await.is_err());
+            // the last value is still there
+            assert_eq!(0, *receiver.borrow());
+        })
+    }
+
+    #[test]
+    fn receivers_dropped() {
+        task::block_on(async {
+            let (sender, receiver) = channel(0);
+            let other = sender.subscribe();
+            assert_eq!(2, sender.receiver_count());
+            drop(receiver);
+            assert!(sender.closed().now_or_never().is_none());
+            drop(other);
+            sender.closed().await;
+            assert!(sender.is_closed());
+            assert!(sender.send(1).is_err());
+        })
+    }
+}
@@ -13,7 +13,9 @@
 //!
 //! Fatal logic errors in Rust cause *thread panic*, during which a thread will unwind the stack,
 //! running destructors and freeing owned resources. If a panic occurs inside a task, there is no
-//! meaningful way of recovering, so the panic will propagate through task which is awaiting [`JoinHandle`].
+//! meaningful way of recovering, so the panic is caught and handed over to the task which is
+//! awaiting the [`JoinHandle`], as a [`JoinError`]. See [`PanicPolicy`] for other ways to
+//! handle it.
 //!
 //! ## Spawning a task
 //!
@@ -47,14 +49,30 @@
 //! # })
 //! ```
 //!
-//! The `await` operator returns the final value produced by the child task.
+//! The `await` operator returns the final value produced by the child task, or a [`JoinError`]
+//! if the child task panicked or was aborted.
 //!
 //! [`spawn`]: fn.spawn.html
 //! [`JoinHandle`]: struct.JoinHandle.html
+//! [`JoinError`]: struct.JoinError.html
+//! [`PanicPolicy`]: enum.PanicPolicy.html
 //! [`panic!`]: https://doc.rust-lang.org/std/macro.panic.html
 
+mod abort;
 mod block;
+mod block_in_place;
 mod blocking;
+mod builder;
+pub(crate) mod clock;
+pub(crate) mod coop;
+mod detect;
+mod join;
+mod join_set;
+mod local;
+mod panic;
+mod scope;
+pub(crate) mod tag;
+mod task_local;
 mod yield_now;
 
 #[cfg(feature = "async-rt")]
@@ -63,44 +81,38 @@ mod spawn;
 #[cfg(feature = "async-rt")]
 pub use spawn::spawn;
 
+#[cfg(feature = "task-dump")]
+mod dump;
+
+#[cfg(feature = "task-dump")]
+pub use dump::{Dump, TaskInfo, TaskState};
+
+#[cfg(feature = "detect-blocking")]
+pub use detect::{blocking_policy, set_blocking_policy, BlockingPolicy};
+
 #[cfg(feature = "timer")]
 mod timer;
 
 #[cfg(feature = "timer")]
-pub use timer::{interval, sleep, Interval};
+pub use timer::{interval, sleep, timeout, Interval, TimeoutError};
 
+pub use abort::{abortable, AbortHandle, AbortRegistration, Abortable, Aborted};
 pub use block::block_on;
-pub use blocking::spawn_blocking;
+pub use block_in_place::block_in_place;
+pub use blocking::{spawn_blocking, try_spawn_blocking};
+pub use builder::Builder;
+pub use clock::now;
+pub use coop::consume_budget;
+pub use detect::check_blocking;
+pub use join::{JoinError, JoinHandle};
+pub use join_set::JoinSet;
+pub use local::{spawn_local, LocalSet};
+pub use panic::{panic_policy, set_panic_policy, PanicPolicy};
+pub use scope::{scope, Scope};
+pub use tag::{id, name, TaskId};
+pub use task_local::{AccessError, LocalKey};
 pub use yield_now::yield_now;
 
-use std::future::Future;
-use std::panic::resume_unwind;
-use std::pin::Pin;
-use std::task::{Context, Poll};
-use std::thread::Result;
-
-type Task = async_task::Task<()>;
-
-/// A handle that awaits the result of a task.
-///
-/// Dropping a [`JoinHandle`] will detach the task, meaning that there is no longer
-/// a handle to the task and no way to `join` on it.
-///
-/// Created when a task is [spawned].
-///
-/// [spawned]: fn.spawn.html
-pub struct JoinHandle<T>(async_task::JoinHandle<Result<T>, ()>);
-
-impl<T> Future for JoinHandle<T> {
-    type Output = T;
+pub(crate) use tag::Tag;
 
-    #[inline]
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        Pin::new(&mut self.0).poll(cx).map(|opt| {
-            match opt.expect("task should not be canceled") {
-                Ok(ret) => ret,
-                Err(err) => resume_unwind(err),
-            }
-        })
-    }
-}
+pub(crate) type Task = async_task::Task<Tag>;
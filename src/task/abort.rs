@@ -0,0 +1,246 @@
+use futures::task::AtomicWaker;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+
+/// A handle to abort an [`Abortable`] future or a task remotely.
+///
+/// It is created by [`AbortHandle::new_pair`], [`abortable`] or [`JoinHandle::abort_handle`],
+/// and can be cloned and shared freely. Once every handle is dropped, the future can no longer
+/// be aborted.
+///
+/// [`Abortable`]: struct.Abortable.html
+/// [`AbortHandle::new_pair`]: #method.new_pair
+/// [`abortable`]: fn.abortable.html
+/// [`JoinHandle::abort_handle`]: struct.JoinHandle.html#method.abort_handle
+#[derive(Clone)]
+pub struct AbortHandle {
+    aborted: Arc<AtomicBool>,
+    waker: Arc<AtomicWaker>,
+}
+
+/// A registration to make a future [`Abortable`], paired with an [`AbortHandle`].
+///
+/// [`Abortable`]: struct.Abortable.html
+/// [`AbortHandle`]: struct.AbortHandle.html
+pub struct AbortRegistration {
+    aborted: Arc<AtomicBool>,
+    // the future only holds a weak reference to the waker, so the waker of a task never keeps
+    // the task itself alive
+    waker: Weak<AtomicWaker>,
+}
+
+impl AbortHandle {
+    /// Creates an abort handle and its registration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use futures::future;
+    /// use tio::task::{AbortHandle, Abortable, Aborted};
+    ///
+    /// let (handle, registration) = AbortHandle::new_pair();
+    /// let fut = Abortable::new(future::pending::<()>(), registration);
+    /// handle.abort();
+    /// assert_eq!(Err(Aborted), fut.await);
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn new_pair() -> (Self, AbortRegistration) {
+        let aborted = Arc::new(AtomicBool::new(false));
+        let waker = Arc::new(AtomicWaker::new());
+        let registration = AbortRegistration {
+            aborted: aborted.clone(),
+            waker: Arc::downgrade(&waker),
+        };
+        (Self { aborted, waker }, registration)
+    }
+
+    /// Aborts the future.
+    ///
+    /// The future is not polled anymore, it completes with [`Aborted`] instead. A future which
+    /// has completed already is not affected.
+    ///
+    /// [`Aborted`]: struct.Aborted.html
+    #[inline]
+    pub fn abort(&self) {
+        self.aborted.store(true, Ordering::Release);
+        self.waker.wake();
+    }
+
+    /// Returns `true` if [`abort`] has been called.
+    ///
+    /// [`abort`]: #method.abort
+    #[inline]
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Acquire)
+    }
+}
+
+impl Debug for AbortHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbortHandle")
+            .field("aborted", &self.is_aborted())
+            .finish()
+    }
+}
+
+impl Debug for AbortRegistration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad("AbortRegistration { .. }")
+    }
+}
+
+/// Polls `fut` unless it is aborted through `registration`.
+#[inline]
+pub(crate) fn poll_abortable<F>(
+    registration: &AbortRegistration,
+    fut: Pin<&mut F>,
+    cx: &mut Context<'_>,
+) -> Poll<Result<F::Output, Aborted>>
+where
+    F: Future,
+{
+    if registration.aborted.load(Ordering::Acquire) {
+        return Poll::Ready(Err(Aborted));
+    }
+    if let Poll::Ready(output) = fut.poll(cx) {
+        return Poll::Ready(Ok(output));
+    }
+    // no handle is left if the waker is gone, nobody is able to abort it anymore
+    if let Some(waker) = registration.waker.upgrade() {
+        waker.register(cx.waker());
+        // check again in case `abort` is called before the waker is registered
+        if registration.aborted.load(Ordering::Acquire) {
+            return Poll::Ready(Err(Aborted));
+        }
+    }
+    Poll::Pending
+}
+
+/// A future which can be aborted by an [`AbortHandle`].
+///
+/// It resolves to the output of the inner future, or to [`Aborted`] once aborted.
+///
+/// [`AbortHandle`]: struct.AbortHandle.html
+/// [`Aborted`]: struct.Aborted.html
+pub struct Abortable<F> {
+    fut: Pin<Box<F>>,
+    registration: AbortRegistration,
+}
+
+impl<F> Abortable<F>
+where
+    F: Future,
+{
+    /// Makes a future abortable by the [`AbortHandle`] paired with `registration`.
+    ///
+    /// [`AbortHandle`]: struct.AbortHandle.html
+    #[inline]
+    pub fn new(fut: F, registration: AbortRegistration) -> Self {
+        Self {
+            fut: Box::pin(fut),
+            registration,
+        }
+    }
+}
+
+impl<F> Future for Abortable<F>
+where
+    F: Future,
+{
+    type Output = Result<F::Output, Aborted>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        poll_abortable(&this.registration, this.fut.as_mut(), cx)
+    }
+}
+
+impl<F> Debug for Abortable<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad("Abortable { .. }")
+    }
+}
+
+/// Makes a future abortable, returning it along with its [`AbortHandle`].
+///
+/// This is useful for request timeouts: hand the [`AbortHandle`] over to a watchdog, which
+/// aborts the request once the deadline passes.
+///
+/// [`AbortHandle`]: struct.AbortHandle.html
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use futures::future;
+/// use tio::task;
+///
+/// let (fut, handle) = task::abortable(future::pending::<()>());
+/// task::spawn(async move { handle.abort() });
+/// assert!(fut.await.is_err());
+/// #
+/// # })
+/// ```
+#[inline]
+pub fn abortable<F>(fut: F) -> (Abortable<F>, AbortHandle)
+where
+    F: Future,
+{
+    let (handle, registration) = AbortHandle::new_pair();
+    (Abortable::new(fut, registration), handle)
+}
+
+/// An error returned by an [`Abortable`] future which is aborted.
+///
+/// [`Abortable`]: struct.Abortable.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Aborted;
+
+impl Display for Aborted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("future was aborted")
+    }
+}
+
+impl Error for Aborted {}
+
+#[cfg(test)]
+mod tests {
+    use super::{abortable, AbortHandle, Abortable, Aborted};
+    use crate::task::block_on;
+    use futures::future;
+
+    #[test]
+    fn aborted() {
+        let (handle, registration) = AbortHandle::new_pair();
+        handle.abort();
+        let output = block_on(Abortable::new(future::pending::<()>(), registration));
+        assert_eq!(Err(Aborted), output);
+        assert!(handle.is_aborted());
+    }
+
+    #[test]
+    fn not_aborted() {
+        let (fut, handle) = abortable(async { 1 });
+        assert_eq!(Ok(1), block_on(fut));
+        assert!(!handle.is_aborted());
+    }
+
+    #[test]
+    fn handle_dropped() {
+        let (fut, handle) = abortable(async { 1 });
+        drop(handle);
+        assert_eq!(Ok(1), block_on(fut));
+    }
+}
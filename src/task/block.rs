@@ -1,3 +1,4 @@
+use super::coop;
 use async_task::waker_fn;
 use crossbeam_utils::sync::Parker;
 use futures::pin_mut;
@@ -13,7 +14,7 @@ thread_local! {
         (parker, waker)
     };
 
-    static IS_RUNNING: Cell<bool> = Cell::new(false);
+    static IS_RUNNING: Cell<bool> = const { Cell::new(false) };
 }
 
 struct RunningGuard;
@@ -60,16 +61,18 @@ impl Drop for RunningGuard {
 /// });
 /// assert_eq!(1, val);
 /// ```
+#[track_caller]
 pub fn block_on<F>(fut: F) -> F::Output
 where
     F: Future,
 {
+    super::check_blocking("block_on");
     let _guard = RunningGuard::new();
     pin_mut!(fut);
     SCHEDULE.with(|(parker, waker)| {
         let mut ctx = Context::from_waker(waker);
         loop {
-            match fut.as_mut().poll(&mut ctx) {
+            match coop::budget(|| fut.as_mut().poll(&mut ctx)) {
                 Poll::Pending => parker.park(),
                 Poll::Ready(output) => break output,
             }
@@ -0,0 +1,56 @@
+/// Runs a blocking closure on the current thread without stalling the other tasks.
+///
+/// When called from a task spawned by [`task::spawn`], the tasks queued on the current worker
+/// are handed off to a new worker thread first, then the closure runs in place. Unlike
+/// [`task::spawn_blocking`], nothing needs to be moved to another thread, so the closure may
+/// borrow from the task.
+///
+/// Outside of the thread pool, e.g. in [`task::block_on`], the closure simply runs; other
+/// futures polled by the same thread are blocked meanwhile.
+///
+/// [`task::spawn`]: fn.spawn.html
+/// [`task::spawn_blocking`]: fn.spawn_blocking.html
+/// [`task::block_on`]: fn.block_on.html
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::task;
+///
+/// let data = vec![1, 2, 3];
+/// let sum = task::block_in_place(|| {
+///     // expensive computation on borrowed data
+///     data.iter().sum::<i32>()
+/// });
+/// assert_eq!(6, sum);
+/// #
+/// # })
+/// ```
+pub fn block_in_place<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    #[cfg(feature = "async-rt")]
+    crate::runtime::pool::hand_off();
+    f()
+}
+
+#[cfg(all(test, feature = "async-rt"))]
+mod tests {
+    use super::block_in_place;
+    use crate::task::{block_on, spawn};
+    use std::time::Duration;
+
+    #[test]
+    fn hand_off() {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let waiting = spawn(async move {
+            block_in_place(|| receiver.recv_timeout(Duration::from_secs(5)))
+        });
+        // queued behind the blocked task on a single worker
+        spawn(async move { sender.send(1).unwrap() }).detach();
+        assert_eq!(Ok(1), block_on(waiting).unwrap());
+    }
+}
@@ -1,60 +1,5 @@
-use super::{JoinHandle, Task};
-use crossbeam_channel::{unbounded, Receiver, Sender};
-use once_cell::sync::Lazy;
-use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::thread;
-use std::time::Duration;
-
-static FREE_THREADS: AtomicUsize = AtomicUsize::new(0);
-const TIMEOUT: Duration = Duration::from_secs(1);
-
-static POOL: Lazy<Sender<Task>> = Lazy::new(|| {
-    let (sender, recv) = unbounded();
-    start_thread(recv);
-    sender
-});
-
-fn start_thread(recv: Receiver<Task>) {
-    thread::Builder::new()
-        .name("tio/blocking".to_string())
-        .spawn(move || {
-            FREE_THREADS.fetch_add(1, Ordering::SeqCst);
-            loop {
-                let result = recv.recv_timeout(TIMEOUT);
-                let mut task = match result {
-                    Ok(task) => task,
-                    Err(_) => {
-                        if FREE_THREADS.fetch_sub(1, Ordering::SeqCst) == 1 {
-                            FREE_THREADS.fetch_add(1, Ordering::SeqCst);
-                            continue;
-                        }
-                        // stop thread
-                        break;
-                    }
-                };
-
-                if FREE_THREADS.fetch_sub(1, Ordering::SeqCst) == 1 {
-                    start_thread(recv.clone())
-                }
-
-                loop {
-                    task.run();
-                    task = match recv.try_recv() {
-                        Ok(t) => t,
-                        Err(_) => break,
-                    }
-                }
-
-                if FREE_THREADS.load(Ordering::SeqCst) > 0 {
-                    break;
-                }
-
-                FREE_THREADS.fetch_add(1, Ordering::SeqCst);
-            }
-        })
-        .expect("cannot start a blocking thread");
-}
+use super::JoinHandle;
+use crate::runtime::{context, SpawnError};
 
 /// Spawns a blocking task.
 ///
@@ -62,10 +7,25 @@ fn start_thread(recv: Receiver<Task>) {
 /// is useful to prevent long-running synchronous operations from blocking the main futures
 /// executor.
 ///
-/// See also: [`task::block_on`], [`task::spawn`].
+/// The pool starts a new thread whenever all of its threads are busy, up to 512 threads unless
+/// [`Builder::max_blocking_threads`] sets the limit. Once the limit is reached, new tasks wait in
+/// a queue until a thread becomes free. Threads which stay idle for a second, or the duration set
+/// by [`Builder::thread_keep_alive`], are stopped, except the last one.
 ///
+/// See also: [`task::block_on`], [`task::spawn`], [`task::try_spawn_blocking`].
+///
+/// [`Builder::max_blocking_threads`]: ../runtime/struct.Builder.html#method.max_blocking_threads
+/// [`Builder::thread_keep_alive`]: ../runtime/struct.Builder.html#method.thread_keep_alive
 /// [`task::block_on`]: fn.block_on.html
 /// [`task::spawn`]: fn.spawn.html
+/// [`task::try_spawn_blocking`]: fn.try_spawn_blocking.html
+///
+/// # Panics
+///
+/// This function panics if the queue of the blocking pool is full, see
+/// [`Builder::blocking_queue_limit`].
+///
+/// [`Builder::blocking_queue_limit`]: ../runtime/struct.Builder.html#method.blocking_queue_limit
 ///
 /// # Examples
 ///
@@ -79,33 +39,87 @@ fn start_thread(recv: Receiver<Task>) {
 /// let val = task::spawn_blocking(|| {
 ///     println!("long-running task here");
 ///     1
-/// }).await;
+/// }).await.unwrap();
 /// assert_eq!(1, val);
 /// #
 /// # })
 /// ```
+#[track_caller]
 pub fn spawn_blocking<F, R>(f: F) -> JoinHandle<R>
 where
     R: 'static + Send,
     F: 'static + Send + FnOnce() -> R,
 {
-    let (task, handler) = async_task::spawn(
-        async move { catch_unwind(AssertUnwindSafe(f)) },
-        |t| POOL.send(t).expect("No blocking thread started"),
-        (),
-    );
-    task.schedule();
-    JoinHandle(handler)
+    spawn_with(None, f)
+}
+
+/// Spawns a blocking task, returning an error if the blocking pool is saturated.
+///
+/// Unlike [`spawn_blocking`], this function fails with [`SpawnError::Saturated`] instead of
+/// panicking once every thread of the pool is busy and its queue is full, so the caller can
+/// shed load. It fails with [`SpawnError::Shutdown`] if the runtime is shut down.
+///
+/// [`spawn_blocking`]: fn.spawn_blocking.html
+/// [`SpawnError::Saturated`]: ../runtime/enum.SpawnError.html#variant.Saturated
+/// [`SpawnError::Shutdown`]: ../runtime/enum.SpawnError.html#variant.Shutdown
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::mpsc;
+/// use tio::runtime::{Builder, SpawnError};
+/// use tio::task;
+///
+/// let runtime = Builder::new()
+///     .max_blocking_threads(1)
+///     .blocking_queue_limit(1)
+///     .build()
+///     .unwrap();
+/// runtime.block_on(async {
+///     let (sender, receiver) = mpsc::channel();
+///     let busy = task::spawn_blocking(move || receiver.recv().unwrap());
+///     // wait until the only thread takes the first task
+///     while tio::runtime::Handle::current().metrics().blocking_queue_depth() > 0 {
+///         task::yield_now().await;
+///     }
+///     let queued = task::try_spawn_blocking(|| ()).unwrap();
+///     let err = task::try_spawn_blocking(|| ()).unwrap_err();
+///     assert_eq!(SpawnError::Saturated, err);
+///     sender.send(()).unwrap();
+///     busy.await.unwrap();
+///     queued.await.unwrap();
+/// });
+/// ```
+#[track_caller]
+pub fn try_spawn_blocking<F, R>(f: F) -> Result<JoinHandle<R>, SpawnError>
+where
+    R: 'static + Send,
+    F: 'static + Send + FnOnce() -> R,
+{
+    context::current().try_spawn_blocking(None, f)
+}
+
+#[track_caller]
+pub(crate) fn spawn_with<F, R>(name: Option<String>, f: F) -> JoinHandle<R>
+where
+    R: 'static + Send,
+    F: 'static + Send + FnOnce() -> R,
+{
+    context::current().spawn_blocking(name, f)
 }
 
 #[cfg(test)]
 mod tests {
     use super::spawn_blocking;
+    use crate::runtime::Handle;
     use crate::task::block_on;
+    use futures::future::join_all;
+    use std::thread;
+    use std::time::Duration;
 
     #[test]
     fn basic() {
-        assert_eq!(1, block_on(spawn_blocking(|| 1)));
+        assert_eq!(1, block_on(spawn_blocking(|| 1)).unwrap());
     }
 
     #[test]
@@ -120,6 +134,22 @@ mod tests {
     #[test]
     #[should_panic]
     fn unwind_caught() {
-        block_on(spawn_blocking(|| panic!("task panic")))
+        block_on(spawn_blocking(|| panic!("task panic"))).unwrap()
+    }
+
+    #[test]
+    fn many() {
+        let tasks = (0..64).map(|i| {
+            spawn_blocking(move || {
+                thread::sleep(Duration::from_millis(10));
+                i
+            })
+        });
+        let sum: usize = block_on(join_all(tasks))
+            .into_iter()
+            .map(Result::unwrap)
+            .sum();
+        assert_eq!((0..64).sum::<usize>(), sum);
+        assert!(Handle::current().metrics().num_blocking_threads() <= 512);
     }
 }
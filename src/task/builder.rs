@@ -0,0 +1,276 @@
+use super::{blocking, local, JoinHandle, LocalSet};
+use crate::runtime::Handle;
+
+#[cfg(all(target_os = "linux", feature = "affinity"))]
+use crate::runtime::context;
+use std::future::Future;
+
+/// Task factory, which can be used in order to configure the properties of a new task.
+///
+/// Methods can be chained on it in order to configure it, then one of the `spawn` methods
+/// starts the task. Every kind of task can be spawned from a builder, so there is a single place
+/// to configure them.
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::task;
+///
+/// let handle = task::Builder::new()
+///     .name("worker")
+///     .spawn(async { task::name() });
+/// assert_eq!(Some("worker".into()), handle.await.unwrap());
+/// #
+/// # })
+/// ```
+#[derive(Debug, Default)]
+pub struct Builder {
+    name: Option<String>,
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    node: Option<usize>,
+}
+
+impl Builder {
+    /// Creates a new builder, from which the properties of a new task can be configured.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the task-to-be.
+    ///
+    /// The name shows up in the [`Debug`] output of the [`JoinHandle`], in the log of a panic
+    /// and can be read by the task itself with [`task::name`].
+    ///
+    /// [`Debug`]: https://doc.rust-lang.org/std/fmt/trait.Debug.html
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    /// [`task::name`]: fn.name.html
+    #[inline]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Hints the task-to-be to run on a NUMA node of the runtime, the index of the node given
+    /// to [`runtime::Builder::numa_nodes`].
+    ///
+    /// The task is queued on the node whenever it is woken, so the workers of the node run it,
+    /// unless another node is idle and steals it. The hint is taken modulo the number of nodes,
+    /// and ignored by a runtime without nodes, or by the `spawn_local` and `spawn_blocking`
+    /// methods.
+    ///
+    /// [`runtime::Builder::numa_nodes`]: ../runtime/struct.Builder.html#method.numa_nodes
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    #[cfg_attr(
+        feature = "docs",
+        doc(cfg(all(target_os = "linux", feature = "affinity")))
+    )]
+    #[inline]
+    pub fn numa_node(mut self, node: usize) -> Self {
+        self.node = Some(node);
+        self
+    }
+
+    /// Spawns a task with the configured properties.
+    ///
+    /// See also: [`task::spawn`].
+    ///
+    /// [`task::spawn`]: fn.spawn.html
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[track_caller]
+    pub fn spawn<F, R>(self, fut: F) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + Future<Output = R>,
+    {
+        #[cfg(all(target_os = "linux", feature = "affinity"))]
+        if let Some(node) = self.node {
+            return context::current().spawn_on_node(self.name, node, fut);
+        }
+        super::spawn::spawn_with(self.name, fut)
+    }
+
+    /// Spawns a `!Send` task with the configured properties onto the [`LocalSet`] running on the
+    /// current thread.
+    ///
+    /// See also: [`task::spawn_local`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if it is not called within a [`LocalSet`].
+    ///
+    /// [`LocalSet`]: struct.LocalSet.html
+    /// [`task::spawn_local`]: fn.spawn_local.html
+    #[track_caller]
+    pub fn spawn_local<F, R>(self, fut: F) -> JoinHandle<R>
+    where
+        R: 'static,
+        F: 'static + Future<Output = R>,
+    {
+        local::spawn_local_named(self.name, fut)
+    }
+
+    /// Spawns a `!Send` task with the configured properties onto the given [`LocalSet`].
+    ///
+    /// See also: [`LocalSet::spawn_local`].
+    ///
+    /// [`LocalSet`]: struct.LocalSet.html
+    /// [`LocalSet::spawn_local`]: struct.LocalSet.html#method.spawn_local
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::task::{self, LocalSet};
+    ///
+    /// let local = LocalSet::new();
+    /// let handle = task::Builder::new()
+    ///     .name("local")
+    ///     .spawn_on(&local, async { task::name() });
+    /// assert_eq!(Some("local".into()), local.block_on(handle).unwrap());
+    /// ```
+    #[track_caller]
+    pub fn spawn_on<F, R>(self, local: &LocalSet, fut: F) -> JoinHandle<R>
+    where
+        R: 'static,
+        F: 'static + Future<Output = R>,
+    {
+        local.spawn_named(self.name, fut)
+    }
+
+    /// Spawns a task with the configured properties onto the runtime of the given [`Handle`].
+    ///
+    /// See also: [`Handle::spawn`].
+    ///
+    /// [`Handle`]: ../runtime/struct.Handle.html
+    /// [`Handle::spawn`]: ../runtime/struct.Handle.html#method.spawn
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::runtime::Runtime;
+    /// use tio::task;
+    ///
+    /// let runtime = Runtime::new().unwrap();
+    /// let handle = task::Builder::new()
+    ///     .name("worker")
+    ///     .spawn_on_handle(runtime.handle(), async { task::name() });
+    /// assert_eq!(Some("worker".into()), runtime.block_on(handle).unwrap());
+    /// ```
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[track_caller]
+    pub fn spawn_on_handle<F, R>(self, handle: &Handle, fut: F) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + Future<Output = R>,
+    {
+        #[cfg(all(target_os = "linux", feature = "affinity"))]
+        if let Some(node) = self.node {
+            return handle.inner.spawn_on_node(self.name, node, fut);
+        }
+        handle.inner.spawn(self.name, fut)
+    }
+
+    /// Spawns a blocking task with the configured properties onto the blocking pool of the
+    /// runtime of the given [`Handle`].
+    ///
+    /// See also: [`Handle::spawn_blocking`].
+    ///
+    /// [`Handle`]: ../runtime/struct.Handle.html
+    /// [`Handle::spawn_blocking`]: ../runtime/struct.Handle.html#method.spawn_blocking
+    #[track_caller]
+    pub fn spawn_blocking_on_handle<F, R>(self, handle: &Handle, f: F) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnOnce() -> R,
+    {
+        handle.inner.spawn_blocking(self.name, f)
+    }
+
+    /// Spawns a blocking task with the configured properties.
+    ///
+    /// See also: [`task::spawn_blocking`].
+    ///
+    /// [`task::spawn_blocking`]: fn.spawn_blocking.html
+    #[track_caller]
+    pub fn spawn_blocking<F, R>(self, f: F) -> JoinHandle<R>
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnOnce() -> R,
+    {
+        blocking::spawn_with(self.name, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Builder;
+    use crate::task::{self, block_on, LocalSet};
+
+    #[test]
+    fn blocking() {
+        let handle = Builder::new().name("blocking").spawn_blocking(task::name);
+        assert_eq!(Some("blocking"), handle.name());
+        assert_eq!(Some("blocking".into()), block_on(handle).unwrap());
+    }
+
+    #[test]
+    fn blocking_on_handle() {
+        use crate::runtime;
+        use std::thread;
+        let runtime = runtime::Builder::new()
+            .thread_name("builder")
+            .build()
+            .unwrap();
+        let handle = Builder::new().name("blocking").spawn_blocking_on_handle(
+            runtime.handle(),
+            || {
+                (
+                    task::name(),
+                    thread::current().name().map(ToString::to_string),
+                )
+            },
+        );
+        let (name, thread) = block_on(handle).unwrap();
+        assert_eq!(Some("blocking".into()), name);
+        assert_eq!(Some("builder/blocking"), thread.as_deref());
+    }
+
+    #[test]
+    fn local() {
+        let local = LocalSet::new();
+        let name = local.block_on(async {
+            Builder::new()
+                .name("local")
+                .spawn_local(async { task::name() })
+                .await
+                .unwrap()
+        });
+        assert_eq!(Some("local".into()), name);
+    }
+
+    #[test]
+    #[cfg(feature = "async-rt")]
+    fn name() {
+        use crate::task::spawn;
+        let handle = Builder::new().name("worker").spawn(async { task::name() });
+        assert_eq!(Some("worker"), handle.name());
+        assert_eq!(Some("worker".into()), block_on(handle).unwrap());
+        assert_eq!(None, block_on(spawn(async { task::name() })).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "async-rt")]
+    fn unique_id() {
+        use crate::task::spawn;
+        let first = spawn(async { task::id() });
+        let second = spawn(async { task::id() });
+        assert_ne!(first.id(), second.id());
+        let id = first.id();
+        assert_eq!(Some(id), block_on(first).unwrap());
+        assert_eq!(None, task::id());
+    }
+}
@@ -0,0 +1,31 @@
+use std::time::Instant;
+
+/// Returns an instant corresponding to "now".
+///
+/// On a runtime with the coarse clock enabled by [`Builder::coarse_clock`], this is the
+/// instant cached by the runtime, which saves a `clock_gettime` call at the cost of some
+/// precision. Otherwise, and out of any runtime, this is exactly [`Instant::now`].
+///
+/// [`Builder::coarse_clock`]: ../runtime/struct.Builder.html#method.coarse_clock
+/// [`Instant::now`]: https://doc.rust-lang.org/std/time/struct.Instant.html#method.now
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use tio::task;
+///
+/// let deadline = task::now() + Duration::from_secs(1);
+/// assert!(task::now() < deadline);
+/// ```
+#[inline]
+pub fn now() -> Instant {
+    #[cfg(feature = "timer")]
+    if let Some(now) =
+        crate::runtime::context::with_current(crate::runtime::Inner::coarse_now)
+            .flatten()
+    {
+        return now;
+    }
+    Instant::now()
+}
@@ -0,0 +1,120 @@
+use futures::future::poll_fn;
+use std::cell::Cell;
+use std::task::{Context, Poll};
+
+/// Number of operations a task may perform in one poll before it is forced to yield.
+const BUDGET: u8 = 128;
+
+thread_local! {
+    /// The remaining budget of the running task, `None` means unconstrained.
+    static CURRENT: Cell<Option<u8>> = const { Cell::new(None) };
+}
+
+/// Restores the previous budget when dropped.
+struct ResetGuard(Option<u8>);
+
+impl Drop for ResetGuard {
+    #[inline]
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.0));
+    }
+}
+
+/// Runs `f` with a fresh budget, e.g. for one poll of a task.
+#[inline]
+pub(crate) fn budget<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _guard = ResetGuard(CURRENT.with(|current| current.replace(Some(BUDGET))));
+    f()
+}
+
+/// Returns `Poll::Pending` and schedules the task again if its budget is exhausted.
+///
+/// Call [`consume`] once the operation made progress.
+///
+/// [`consume`]: fn.consume.html
+#[inline]
+pub(crate) fn poll_proceed(cx: &mut Context<'_>) -> Poll<()> {
+    if CURRENT.with(Cell::get) == Some(0) {
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    } else {
+        Poll::Ready(())
+    }
+}
+
+/// Consumes one unit of the budget of the running task.
+#[inline]
+pub(crate) fn consume() {
+    CURRENT.with(|current| {
+        if let Some(budget) = current.get() {
+            current.set(Some(budget.saturating_sub(1)))
+        }
+    })
+}
+
+/// Consumes one unit of the cooperative budget of the current task, yielding if it runs out.
+///
+/// Every task gets a budget of operations each time it is polled. I/O resources consume it when
+/// they are ready, so a task looping over an always-ready resource still yields once in a
+/// while instead of starving the other tasks on the same thread. Call this function in loops
+/// which otherwise never hit a pending await point.
+///
+/// See also: [`task::yield_now`].
+///
+/// [`task::yield_now`]: fn.yield_now.html
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::task;
+///
+/// let mut sum = 0u64;
+/// for i in 0..1000 {
+///     task::consume_budget().await;
+///     sum += i;
+/// }
+/// assert_eq!(499500, sum);
+/// #
+/// # })
+/// ```
+pub async fn consume_budget() {
+    poll_fn(|cx| {
+        futures::ready!(poll_proceed(cx));
+        consume();
+        Poll::Ready(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::consume_budget;
+    use crate::task::{spawn_local, LocalSet};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn no_starvation() {
+        let stop = Rc::new(Cell::new(false));
+        LocalSet::new().block_on(async {
+            let busy = {
+                let stop = stop.clone();
+                spawn_local(async move {
+                    let mut iterations = 0;
+                    while !stop.get() {
+                        consume_budget().await;
+                        iterations += 1;
+                    }
+                    iterations
+                })
+            };
+            spawn_local(async move { stop.set(true) }).detach();
+            assert!(busy.await.unwrap() >= 1);
+        })
+    }
+}
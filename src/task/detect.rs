@@ -0,0 +1,159 @@
+#[cfg(all(debug_assertions, feature = "detect-blocking"))]
+use std::panic::Location;
+#[cfg(feature = "detect-blocking")]
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(feature = "detect-blocking")]
+static POLICY: AtomicU8 = AtomicU8::new(BlockingPolicy::Panic as u8);
+
+/// How a blocking call on a worker thread is handled, with the `detect-blocking` feature in
+/// debug builds.
+///
+/// The policy is set with [`set_blocking_policy`] and applies to every thread.
+///
+/// [`set_blocking_policy`]: fn.set_blocking_policy.html
+#[cfg(feature = "detect-blocking")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "detect-blocking")))]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum BlockingPolicy {
+    /// Panics, failing the task. This is the default.
+    #[default]
+    Panic,
+
+    /// Logs a warning, so every blocking call of a run can be collected.
+    Log,
+}
+
+/// Sets the [`BlockingPolicy`] of the blocking calls.
+///
+/// [`BlockingPolicy`]: enum.BlockingPolicy.html
+///
+/// # Examples
+///
+/// ```
+/// use tio::task::{self, BlockingPolicy};
+///
+/// task::set_blocking_policy(BlockingPolicy::Log);
+/// assert_eq!(BlockingPolicy::Log, task::blocking_policy());
+/// ```
+#[cfg(feature = "detect-blocking")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "detect-blocking")))]
+#[inline]
+pub fn set_blocking_policy(policy: BlockingPolicy) {
+    POLICY.store(policy as u8, Ordering::Relaxed)
+}
+
+/// Returns the current [`BlockingPolicy`] of the blocking calls.
+///
+/// [`BlockingPolicy`]: enum.BlockingPolicy.html
+#[cfg(feature = "detect-blocking")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "detect-blocking")))]
+#[inline]
+pub fn blocking_policy() -> BlockingPolicy {
+    match POLICY.load(Ordering::Relaxed) {
+        0 => BlockingPolicy::Panic,
+        _ => BlockingPolicy::Log,
+    }
+}
+
+/// Checks that the current thread may block, before a blocking call named `call`.
+///
+/// A blocking call in an async task, like a synchronous read or a `std::thread::sleep`, stalls
+/// the worker thread and every task queued on it. With the `detect-blocking` feature in a debug
+/// build, calling this function on a worker thread of a runtime, outside of
+/// [`block_in_place`], is reported by the [`BlockingPolicy`]: it panics or logs a warning with
+/// the call and its location. It does nothing otherwise, so the wrappers of the synchronous
+/// code migrated onto tio can call it unconditionally. [`block_on`] calls it too.
+///
+/// The blocking calls which do not call this function, like the ones of the standard library,
+/// are caught by the watchdog of [`Builder::long_poll_threshold`] instead, once they take too
+/// long.
+///
+/// [`block_in_place`]: fn.block_in_place.html
+/// [`BlockingPolicy`]: enum.BlockingPolicy.html
+/// [`block_on`]: fn.block_on.html
+/// [`Builder::long_poll_threshold`]: ../runtime/struct.Builder.html#method.long_poll_threshold
+///
+/// # Examples
+///
+/// ```
+/// use std::fs;
+/// use std::io;
+/// use tio::task;
+///
+/// fn read_config() -> io::Result<String> {
+///     task::check_blocking("read_config");
+///     fs::read_to_string("/etc/hostname")
+/// }
+///
+/// # task::block_on(async {
+/// #
+/// // fine on a blocking thread
+/// let config = task::spawn_blocking(read_config).await.unwrap();
+/// #
+/// # })
+/// ```
+#[inline]
+#[track_caller]
+pub fn check_blocking(call: &str) {
+    #[cfg(all(debug_assertions, feature = "detect-blocking"))]
+    if crate::runtime::pool::is_worker() {
+        report(call, Location::caller())
+    }
+    #[cfg(not(all(debug_assertions, feature = "detect-blocking")))]
+    let _ = call;
+}
+
+#[cfg(all(debug_assertions, feature = "detect-blocking"))]
+#[cold]
+fn report(call: &str, location: &Location<'_>) {
+    let task = super::tag::current()
+        .map(|info| format!("{} spawned at {}", info, info.location()))
+        .unwrap_or_else(|| "a task".to_string());
+    match blocking_policy() {
+        BlockingPolicy::Panic => panic!(
+            "blocking call `{}` at {} on a worker thread, in {}; run it by \
+             `task::spawn_blocking` or `task::block_in_place` instead",
+            call, location, task
+        ),
+        BlockingPolicy::Log => log::warn!(
+            "blocking call `{}` at {} on a worker thread, in {}",
+            call,
+            location,
+            task
+        ),
+    }
+}
+
+#[cfg(all(test, debug_assertions, feature = "detect-blocking"))]
+mod tests {
+    use super::check_blocking;
+    use crate::task::{self, block_in_place, block_on};
+
+    #[test]
+    fn worker() {
+        block_on(async {
+            // outside of any worker
+            check_blocking("block_on");
+            let err = task::spawn(async { check_blocking("test") })
+                .await
+                .unwrap_err();
+            assert!(err.is_panic());
+            let err = task::spawn(async { block_on(async {}) }).await.unwrap_err();
+            assert!(err.is_panic());
+        })
+    }
+
+    #[test]
+    fn allowed() {
+        block_on(async {
+            task::spawn_blocking(|| check_blocking("test"))
+                .await
+                .unwrap();
+            task::spawn(async { block_in_place(|| check_blocking("test")) })
+                .await
+                .unwrap();
+        })
+    }
+}
@@ -0,0 +1,208 @@
+use super::tag::Info;
+use super::TaskId;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::panic::Location;
+use std::sync::Arc;
+
+/// The state of a task in a [`Dump`].
+///
+/// [`Dump`]: struct.Dump.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TaskState {
+    /// The task is waiting to be polled, either queued or parked on an await point.
+    Idle,
+
+    /// The task is being polled.
+    Running,
+
+    /// The task has completed, but it is not removed from its runtime yet.
+    Finished,
+}
+
+impl Display for TaskState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            TaskState::Idle => "idle",
+            TaskState::Running => "running",
+            TaskState::Finished => "finished",
+        })
+    }
+}
+
+/// A snapshot of a task in a [`Dump`].
+///
+/// [`Dump`]: struct.Dump.html
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    id: TaskId,
+    name: Option<Arc<str>>,
+    location: &'static Location<'static>,
+    state: TaskState,
+    polls: u64,
+}
+
+impl TaskInfo {
+    /// Takes a snapshot of a task.
+    #[inline]
+    pub(crate) fn new(info: &Info) -> Self {
+        Self {
+            id: info.id(),
+            name: info.name().map(Into::into),
+            location: info.location(),
+            state: if info.is_finished() {
+                TaskState::Finished
+            } else if info.is_running() {
+                TaskState::Running
+            } else {
+                TaskState::Idle
+            },
+            polls: info.polls(),
+        }
+    }
+
+    /// Returns the id of the task.
+    #[inline]
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Returns the name of the task.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the location where the task is spawned.
+    #[inline]
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Returns the state of the task.
+    #[inline]
+    pub fn state(&self) -> TaskState {
+        self.state
+    }
+
+    /// Returns how many times the task has been polled.
+    ///
+    /// A task which is polled a lot more than others is likely to be spinning.
+    #[inline]
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+}
+
+impl Display for TaskInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "task {}", self.id)?;
+        if let Some(name) = &self.name {
+            write!(f, " '{}'", name)?;
+        }
+        write!(
+            f,
+            ": {}, polled {} times, spawned at {}",
+            self.state, self.polls, self.location
+        )
+    }
+}
+
+/// A snapshot of the live tasks of a runtime, returned by [`Handle::dump`].
+///
+/// The [`Display`] implementation prints one task per line.
+///
+/// [`Handle::dump`]: ../runtime/struct.Handle.html#method.dump
+/// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+pub struct Dump {
+    tasks: Vec<TaskInfo>,
+}
+
+impl Dump {
+    #[inline]
+    pub(crate) fn new(tasks: Vec<TaskInfo>) -> Self {
+        Self { tasks }
+    }
+
+    /// Returns the tasks in the snapshot, ordered by id.
+    #[inline]
+    pub fn tasks(&self) -> &[TaskInfo] {
+        &self.tasks
+    }
+}
+
+impl Debug for Dump {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.tasks).finish()
+    }
+}
+
+impl Display for Dump {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for task in &self.tasks {
+            writeln!(f, "{}", task)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskState;
+    use crate::runtime;
+    use crate::task::{yield_now, Builder};
+    use futures::future;
+
+    #[cfg(feature = "async-rt")]
+    #[test]
+    fn live_tasks() {
+        let runtime = runtime::Builder::new_current_thread().build().unwrap();
+        let handle = runtime.handle().clone();
+        let pending = Builder::new()
+            .name("pending")
+            .spawn_on_handle(&handle, future::pending::<()>());
+        let finished = runtime.spawn(async {});
+        runtime.block_on(async {
+            yield_now().await;
+            let dump = handle.clone();
+            let running = Builder::new()
+                .name("running")
+                .spawn_on_handle(&handle, async move { dump.dump() })
+                .await
+                .unwrap();
+            let info = running
+                .tasks()
+                .iter()
+                .find(|task| task.id() == pending.id())
+                .cloned()
+                .unwrap();
+            assert_eq!(Some("pending"), info.name());
+            assert_eq!(TaskState::Idle, info.state());
+            assert_eq!(1, info.polls());
+            assert_eq!(file!(), info.location().file());
+            // a completed task is removed from its runtime
+            assert!(finished.is_finished());
+            assert!(running
+                .tasks()
+                .iter()
+                .all(|task| task.id() != finished.id()));
+            assert!(running
+                .tasks()
+                .iter()
+                .any(|task| task.name() == Some("running")
+                    && task.state() == TaskState::Running));
+        });
+        let id = pending.id();
+        pending.abort();
+        runtime.block_on(async {
+            while !pending.is_finished() {
+                yield_now().await;
+            }
+        });
+        assert!(runtime
+            .handle()
+            .dump()
+            .tasks()
+            .iter()
+            .all(|task| task.id() != id));
+    }
+}
@@ -0,0 +1,415 @@
+use super::tag::{panic_message, Info, Output};
+use super::{AbortHandle, Tag, TaskId};
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::io;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// A handle that awaits the result of a task.
+///
+/// Dropping a [`JoinHandle`] will detach the task, meaning that there is no longer
+/// a handle to the task and no way to `join` on it.
+///
+/// Awaiting a handle resolves to the output of the task, or to a [`JoinError`] if the task
+/// panicked or was [aborted].
+///
+/// Created when a task is [spawned].
+///
+/// [`JoinHandle`]: struct.JoinHandle.html
+/// [`JoinError`]: struct.JoinError.html
+/// [aborted]: struct.JoinHandle.html#method.abort
+/// [spawned]: fn.spawn.html
+pub struct JoinHandle<T>(
+    pub(crate) async_task::JoinHandle<Output<T>, Tag>,
+    pub(crate) AbortHandle,
+);
+
+impl<T> JoinHandle<T> {
+    /// Detaches the task to let it keep running in the background.
+    ///
+    /// This is equivalent to dropping the handle, but spells out the intention. The output of a
+    /// detached task is dropped once it completes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::task;
+    ///
+    /// task::spawn(async {
+    ///     // some work here
+    /// })
+    /// .detach();
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn detach(self) {}
+
+    /// Aborts the task.
+    ///
+    /// The task stops at its next await point and its future is dropped without being polled
+    /// again. Awaiting the handle then resolves to a [`JoinError`] which [is cancelled].
+    ///
+    /// Aborting a task which has already completed has no effect.
+    ///
+    /// [`JoinError`]: struct.JoinError.html
+    /// [is cancelled]: struct.JoinError.html#method.is_cancelled
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use futures::future;
+    /// use tio::task;
+    ///
+    /// let handle = task::spawn(future::pending::<()>());
+    /// handle.abort();
+    /// assert!(handle.await.unwrap_err().is_cancelled());
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn abort(&self) {
+        self.0.cancel()
+    }
+
+    /// Returns an [`AbortHandle`] which aborts the task remotely.
+    ///
+    /// Unlike the `JoinHandle`, the abort handle can be cloned and shared, so the task may be
+    /// cancelled from a place which does not own the `JoinHandle`, e.g. a timeout watchdog.
+    /// Awaiting the `JoinHandle` of the aborted task resolves to a cancelled [`JoinError`].
+    ///
+    /// [`AbortHandle`]: struct.AbortHandle.html
+    /// [`JoinError`]: struct.JoinError.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use futures::future;
+    /// use tio::task;
+    ///
+    /// let handle = task::spawn(future::pending::<()>());
+    /// let abort = handle.abort_handle();
+    /// task::spawn(async move { abort.abort() });
+    /// assert!(handle.await.unwrap_err().is_cancelled());
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.1.clone()
+    }
+
+    /// Returns `true` if the task has finished: it ran to completion, panicked, or was
+    /// aborted.
+    ///
+    /// An aborted task is finished once it is dropped, at its next await point if it is
+    /// running.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::task;
+    ///
+    /// let handle = task::spawn_blocking(|| 1);
+    /// while !handle.is_finished() {
+    ///     task::yield_now().await;
+    /// }
+    /// assert_eq!(1, handle.await.unwrap());
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.0.tag().info().is_finished()
+    }
+
+    /// Returns the [`TaskId`] of the task.
+    ///
+    /// [`TaskId`]: struct.TaskId.html
+    #[inline]
+    pub fn id(&self) -> TaskId {
+        self.0.tag().info().id()
+    }
+
+    /// Returns the name of the task, if it is named by a [`Builder`].
+    ///
+    /// [`Builder`]: struct.Builder.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::task;
+    ///
+    /// let handle = task::Builder::new().name("worker").spawn(async {});
+    /// assert_eq!(Some("worker"), handle.name());
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.0.tag().info().name()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = std::result::Result<T, JoinError>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let info = self.0.tag().info().clone();
+        Pin::new(&mut self.0).poll(cx).map(|opt| match opt {
+            Some(Ok(Ok(ret))) => Ok(ret),
+            Some(Err(payload)) => Err(JoinError::panic(info, payload)),
+            Some(Ok(Err(_))) | None => Err(JoinError::cancelled(info)),
+        })
+    }
+}
+
+impl<T> Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinHandle")
+            .field("id", &self.id())
+            .field("name", &self.name())
+            .field("location", &self.0.tag().info().location())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+/// An error returned when awaiting a task which did not complete.
+///
+/// See also: [`JoinHandle::abort`], [`PanicPolicy`].
+///
+/// [`JoinHandle::abort`]: struct.JoinHandle.html#method.abort
+/// [`PanicPolicy`]: enum.PanicPolicy.html
+pub struct JoinError {
+    info: Arc<Info>,
+    repr: Repr,
+}
+
+enum Repr {
+    Cancelled,
+    // the payload is only `Send`, the lock makes the error `Sync`
+    Panic(Mutex<Box<dyn Any + Send + 'static>>),
+}
+
+impl JoinError {
+    #[inline]
+    fn cancelled(info: Arc<Info>) -> Self {
+        Self {
+            info,
+            repr: Repr::Cancelled,
+        }
+    }
+
+    #[inline]
+    fn panic(info: Arc<Info>, payload: Box<dyn Any + Send + 'static>) -> Self {
+        Self {
+            info,
+            repr: Repr::Panic(Mutex::new(payload)),
+        }
+    }
+
+    /// Returns the [`TaskId`] of the task which did not complete.
+    ///
+    /// [`TaskId`]: struct.TaskId.html
+    #[inline]
+    pub fn id(&self) -> TaskId {
+        self.info.id()
+    }
+
+    /// Returns `true` if the task was aborted.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.repr, Repr::Cancelled)
+    }
+
+    /// Returns `true` if the task panicked.
+    #[inline]
+    pub fn is_panic(&self) -> bool {
+        matches!(self.repr, Repr::Panic(_))
+    }
+
+    /// Consumes the error, returning the object with which the task panicked.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the task did not panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use std::panic;
+    /// use tio::task;
+    ///
+    /// let err = task::spawn(async { panic!("boom") }).await.unwrap_err();
+    /// assert!(err.is_panic());
+    /// // resume the panic on the current task
+    /// # let _ = panic::catch_unwind(move || {
+    /// panic::resume_unwind(err.into_panic());
+    /// # });
+    /// #
+    /// # })
+    /// ```
+    #[inline]
+    pub fn into_panic(self) -> Box<dyn Any + Send + 'static> {
+        self.try_into_panic()
+            .expect("`JoinError` reason is not a panic")
+    }
+
+    /// Consumes the error, returning the object with which the task panicked, or the error
+    /// itself if the task did not panic.
+    #[inline]
+    pub fn try_into_panic(
+        self,
+    ) -> std::result::Result<Box<dyn Any + Send + 'static>, Self> {
+        match self.repr {
+            Repr::Panic(payload) => {
+                Ok(payload.into_inner().unwrap_or_else(|err| err.into_inner()))
+            }
+            _ => Err(self),
+        }
+    }
+}
+
+impl Debug for JoinError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.repr {
+            Repr::Cancelled => write!(f, "JoinError::Cancelled({:?})", self.id()),
+            Repr::Panic(_) => write!(f, "JoinError::Panic({:?}, ...)", self.id()),
+        }
+    }
+}
+
+impl Display for JoinError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Cancelled => write!(f, "{} was cancelled", self.info),
+            Repr::Panic(payload) => match payload.lock() {
+                Ok(payload) => {
+                    write!(f, "{} panicked: {}", self.info, panic_message(&**payload))
+                }
+                Err(_) => write!(f, "{} panicked", self.info),
+            },
+        }
+    }
+}
+
+impl Error for JoinError {}
+
+// the payload is only reachable by value and the task info is never mutated through the error
+impl UnwindSafe for JoinError {}
+impl RefUnwindSafe for JoinError {}
+
+impl From<JoinError> for io::Error {
+    #[inline]
+    fn from(err: JoinError) -> Self {
+        io::Error::other(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::task::{block_on, spawn_local, yield_now, LocalSet};
+    use futures::future;
+    use std::sync::Arc;
+
+    #[test]
+    fn abort() {
+        let local = LocalSet::new();
+        local.block_on(async {
+            let handle = spawn_local(future::pending::<()>());
+            assert!(!handle.is_finished());
+            handle.abort();
+            // an aborted task is finished too
+            while !handle.is_finished() {
+                yield_now().await;
+            }
+            assert!(handle.await.unwrap_err().is_cancelled());
+        })
+    }
+
+    #[test]
+    fn abort_finished() {
+        let local = LocalSet::new();
+        let handle = local.spawn_local(async { 1 });
+        block_on(local.run_until(async {
+            while !handle.is_finished() {
+                yield_now().await;
+            }
+        }));
+        handle.abort();
+        assert_eq!(1, block_on(handle).unwrap());
+    }
+
+    #[test]
+    fn abort_handle() {
+        let local = LocalSet::new();
+        local.block_on(async {
+            let handle = spawn_local(future::pending::<()>());
+            let abort = handle.abort_handle();
+            spawn_local(async move { abort.abort() }).detach();
+            assert!(handle.await.unwrap_err().is_cancelled());
+        })
+    }
+
+    #[test]
+    fn abort_handle_no_leak() {
+        let data = Arc::new(());
+        let local = LocalSet::new();
+        let handle = {
+            let data = data.clone();
+            local.spawn_local(async move {
+                let _data = data;
+                future::pending::<()>().await
+            })
+        };
+        let abort = handle.abort_handle();
+        local.block_on(yield_now());
+        drop(handle);
+        drop(abort);
+        drop(local);
+        assert_eq!(1, Arc::strong_count(&data));
+    }
+
+    #[test]
+    fn panic() {
+        let local = LocalSet::new();
+        let handle = local.spawn_local(async { panic!("task panic") });
+        let id = handle.id();
+        let err = local.block_on(handle).unwrap_err();
+        assert!(err.is_panic());
+        assert!(!err.is_cancelled());
+        assert_eq!(id, err.id());
+        assert_eq!(format!("task {} panicked: task panic", id), err.to_string());
+        assert_eq!("task panic", *err.into_panic().downcast::<&str>().unwrap());
+    }
+
+    #[test]
+    fn try_into_panic() {
+        let local = LocalSet::new();
+        local.block_on(async {
+            let handle = spawn_local(future::pending::<()>());
+            handle.abort();
+            let err = handle.await.unwrap_err().try_into_panic().unwrap_err();
+            assert!(err.is_cancelled());
+        })
+    }
+}
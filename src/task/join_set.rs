@@ -0,0 +1,246 @@
+use super::{spawn_blocking, spawn_local, JoinError, JoinHandle};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::task::{Context, Poll};
+
+/// A collection of tasks which can be awaited in the order they complete.
+///
+/// All of the tasks must have the same return type `T`. When the set is dropped, every task in
+/// it is [aborted].
+///
+/// [aborted]: struct.JoinHandle.html#method.abort
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::task::JoinSet;
+///
+/// let mut set = JoinSet::new();
+/// for i in 0..10 {
+///     set.spawn(async move { i });
+/// }
+///
+/// let mut sum = 0;
+/// while let Some(res) = set.join_next().await {
+///     sum += res.unwrap();
+/// }
+/// assert_eq!(45, sum);
+/// #
+/// # })
+/// ```
+pub struct JoinSet<T> {
+    handles: FuturesUnordered<JoinHandle<T>>,
+}
+
+impl<T> JoinSet<T> {
+    /// Creates an empty `JoinSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            handles: FuturesUnordered::new(),
+        }
+    }
+
+    /// Returns the number of tasks in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns `true` if there is no task in the set.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Adds a task to the set by its [`JoinHandle`].
+    ///
+    /// This is useful for tasks spawned by a [`Builder`].
+    ///
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    /// [`Builder`]: struct.Builder.html
+    #[inline]
+    pub fn insert(&mut self, handle: JoinHandle<T>) {
+        self.handles.push(handle)
+    }
+
+    /// Spawns a task onto the set.
+    ///
+    /// See also: [`task::spawn`].
+    ///
+    /// [`task::spawn`]: fn.spawn.html
+    #[cfg(feature = "async-rt")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+    #[inline]
+    #[track_caller]
+    pub fn spawn<F>(&mut self, fut: F)
+    where
+        T: 'static + Send,
+        F: 'static + Send + Future<Output = T>,
+    {
+        self.insert(super::spawn(fut))
+    }
+
+    /// Spawns a `!Send` task onto the set.
+    ///
+    /// See also: [`task::spawn_local`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if it is not called within a [`LocalSet`].
+    ///
+    /// [`task::spawn_local`]: fn.spawn_local.html
+    /// [`LocalSet`]: struct.LocalSet.html
+    #[inline]
+    #[track_caller]
+    pub fn spawn_local<F>(&mut self, fut: F)
+    where
+        T: 'static,
+        F: 'static + Future<Output = T>,
+    {
+        self.insert(spawn_local(fut))
+    }
+
+    /// Spawns a blocking task onto the set.
+    ///
+    /// See also: [`task::spawn_blocking`].
+    ///
+    /// [`task::spawn_blocking`]: fn.spawn_blocking.html
+    #[inline]
+    #[track_caller]
+    pub fn spawn_blocking<F>(&mut self, f: F)
+    where
+        T: 'static + Send,
+        F: 'static + Send + FnOnce() -> T,
+    {
+        self.insert(spawn_blocking(f))
+    }
+
+    /// Waits until one of the tasks in the set completes and returns its output.
+    ///
+    /// Returns [`None`] if the set is empty.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    #[inline]
+    pub async fn join_next(&mut self) -> Option<Result<T, JoinError>> {
+        self.handles.next().await
+    }
+
+    /// Polls for one of the tasks in the set to complete, returning its output.
+    ///
+    /// Returns `Poll::Ready(None)` if the set is empty. Otherwise, the current task is woken
+    /// once a task completes, if none has yet.
+    #[inline]
+    pub fn poll_join_next(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<T, JoinError>>> {
+        self.handles.poll_next_unpin(cx)
+    }
+
+    /// Aborts all tasks in the set.
+    ///
+    /// The tasks stay in the set, awaiting them with [`join_next`] resolves to a cancelled
+    /// [`JoinError`] unless they have completed already.
+    ///
+    /// [`join_next`]: #method.join_next
+    /// [`JoinError`]: struct.JoinError.html
+    #[inline]
+    pub fn abort_all(&mut self) {
+        self.handles.iter().for_each(JoinHandle::abort)
+    }
+
+    /// Removes all tasks from the set without aborting them.
+    ///
+    /// The tasks keep running in the background.
+    #[inline]
+    pub fn detach_all(&mut self) {
+        self.handles.clear()
+    }
+
+    /// Aborts all tasks and waits for them to stop.
+    pub async fn shutdown(&mut self) {
+        self.abort_all();
+        while self.join_next().await.is_some() {}
+    }
+}
+
+impl<T> Default for JoinSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for JoinSet<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.abort_all()
+    }
+}
+
+impl<T> Debug for JoinSet<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinSet").field("len", &self.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JoinSet;
+    use crate::task::{yield_now, LocalSet};
+    use futures::future;
+
+    #[test]
+    fn join_next() {
+        LocalSet::new().block_on(async {
+            let mut set = JoinSet::new();
+            for i in 0..10 {
+                set.spawn_local(async move {
+                    for _ in 0..(10 - i) {
+                        yield_now().await;
+                    }
+                    i
+                });
+            }
+            assert_eq!(10, set.len());
+            let mut outputs = Vec::new();
+            while let Some(output) = set.join_next().await {
+                outputs.push(output.unwrap());
+            }
+            assert_eq!((0..10).rev().collect::<Vec<_>>(), outputs);
+            assert!(set.is_empty());
+        })
+    }
+
+    #[test]
+    fn abort_on_drop() {
+        LocalSet::new().block_on(async {
+            let (sender, receiver) = crossbeam_channel::unbounded::<()>();
+            let mut set = JoinSet::new();
+            set.spawn_local(async move {
+                let _sender = sender;
+                future::pending::<()>().await
+            });
+            yield_now().await;
+            drop(set);
+            yield_now().await;
+            // the sender is dropped along with the aborted task
+            assert!(receiver.recv().is_err());
+        })
+    }
+
+    #[test]
+    fn shutdown() {
+        LocalSet::new().block_on(async {
+            let mut set = JoinSet::new();
+            set.spawn_local(future::pending::<()>());
+            set.spawn_blocking(|| ());
+            set.shutdown().await;
+            assert!(set.is_empty());
+        })
+    }
+}
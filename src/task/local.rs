@@ -0,0 +1,314 @@
+use super::{tag, JoinHandle, Tag, Task};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use futures::future::poll_fn;
+use futures::pin_mut;
+use futures::task::AtomicWaker;
+use std::cell::RefCell;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::Poll;
+
+/// Max number of local tasks run before the driving future is polled again.
+const BUDGET: usize = 64;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<Shared>>> = const { RefCell::new(None) };
+}
+
+struct Shared {
+    sender: Sender<Task>,
+    receiver: Receiver<Task>,
+    waker: AtomicWaker,
+}
+
+impl Shared {
+    #[inline]
+    fn schedule(&self, task: Task) {
+        #[cfg(feature = "console")]
+        crate::console::emit(crate::console::Event::Wake(task.tag().info().id()));
+        self.sender
+            .send(task)
+            .expect("local queue should not be disconnected");
+        self.waker.wake();
+    }
+}
+
+/// Restores the previous running set when dropped.
+struct CurrentGuard(Option<Arc<Shared>>);
+
+impl CurrentGuard {
+    #[inline]
+    fn new(shared: &Arc<Shared>) -> Self {
+        Self(CURRENT.with(|current| current.replace(Some(shared.clone()))))
+    }
+}
+
+impl Drop for CurrentGuard {
+    #[inline]
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+    }
+}
+
+/// A set of tasks which are executed on the same thread.
+///
+/// Futures spawned onto a `LocalSet` do not need to be [`Send`], so they may hold `Rc`s and other
+/// thread-bound resources. They only make progress while the set is driven by
+/// [`run_until`] or [`block_on`].
+///
+/// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+/// [`run_until`]: #method.run_until
+/// [`block_on`]: #method.block_on
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+/// use tio::task::{self, LocalSet};
+///
+/// let local = LocalSet::new();
+/// let val = local.block_on(async {
+///     let shared = Rc::new(1);
+///     let cloned = shared.clone();
+///     task::spawn_local(async move { *cloned + 1 }).await.unwrap()
+/// });
+/// assert_eq!(2, val);
+/// ```
+pub struct LocalSet {
+    shared: Arc<Shared>,
+    _marker: PhantomData<Rc<()>>,
+}
+
+impl LocalSet {
+    /// Creates an empty `LocalSet`.
+    #[inline]
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded();
+        Self {
+            shared: Arc::new(Shared {
+                sender,
+                receiver,
+                waker: AtomicWaker::new(),
+            }),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Spawns a `!Send` task onto this set.
+    ///
+    /// The task does not run until the set is driven by [`run_until`] or [`block_on`].
+    ///
+    /// [`run_until`]: #method.run_until
+    /// [`block_on`]: #method.block_on
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tio::task::LocalSet;
+    ///
+    /// let local = LocalSet::new();
+    /// let handle = local.spawn_local(async { 1 });
+    /// assert_eq!(1, local.block_on(handle).unwrap());
+    /// ```
+    #[track_caller]
+    pub fn spawn_local<F, R>(&self, fut: F) -> JoinHandle<R>
+    where
+        R: 'static,
+        F: 'static + Future<Output = R>,
+    {
+        spawn_with(&self.shared, None, fut)
+    }
+
+    #[inline]
+    #[track_caller]
+    pub(crate) fn spawn_named<F, R>(&self, name: Option<String>, fut: F) -> JoinHandle<R>
+    where
+        R: 'static,
+        F: 'static + Future<Output = R>,
+    {
+        spawn_with(&self.shared, name, fut)
+    }
+
+    /// Runs a future to completion, driving the tasks of this set in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// use tio::task::{self, LocalSet};
+    ///
+    /// let local = LocalSet::new();
+    /// let val = local.run_until(async {
+    ///     task::spawn_local(async { 1 }).await.unwrap()
+    /// }).await;
+    /// assert_eq!(1, val);
+    /// #
+    /// # })
+    /// ```
+    pub async fn run_until<F>(&self, fut: F) -> F::Output
+    where
+        F: Future,
+    {
+        pin_mut!(fut);
+        poll_fn(|cx| {
+            self.shared.waker.register(cx.waker());
+            let _guard = CurrentGuard::new(&self.shared);
+            if let Poll::Ready(output) = fut.as_mut().poll(cx) {
+                return Poll::Ready(output);
+            }
+            for _ in 0..BUDGET {
+                match self.shared.receiver.try_recv() {
+                    Ok(task) => tag::run(task),
+                    Err(_) => break,
+                }
+            }
+            if !self.shared.receiver.is_empty() {
+                cx.waker().wake_by_ref();
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Blocks the current thread on a future, driving the tasks of this set in the meantime.
+    ///
+    /// See also: [`task::block_on`].
+    ///
+    /// [`task::block_on`]: fn.block_on.html
+    #[inline]
+    #[track_caller]
+    pub fn block_on<F>(&self, fut: F) -> F::Output
+    where
+        F: Future,
+    {
+        super::block_on(self.run_until(fut))
+    }
+}
+
+impl Default for LocalSet {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for LocalSet {
+    fn drop(&mut self) {
+        // local tasks must be dropped by the thread spawning them
+        while let Ok(task) = self.shared.receiver.try_recv() {
+            drop(task);
+        }
+    }
+}
+
+#[track_caller]
+fn spawn_with<F, R>(shared: &Arc<Shared>, name: Option<String>, fut: F) -> JoinHandle<R>
+where
+    R: 'static,
+    F: 'static + Future<Output = R>,
+{
+    let shared = shared.clone();
+    let (tag, abort, fut) = Tag::wrap(name, fut);
+    let (task, handler) = async_task::spawn_local(fut, move |t| shared.schedule(t), tag);
+    task.schedule();
+    JoinHandle(handler, abort)
+}
+
+/// Spawns a `!Send` task onto the [`LocalSet`] running on the current thread.
+///
+/// # Panics
+///
+/// This function panics if it is not called within [`LocalSet::run_until`] or
+/// [`LocalSet::block_on`].
+///
+/// [`LocalSet`]: struct.LocalSet.html
+/// [`LocalSet::run_until`]: struct.LocalSet.html#method.run_until
+/// [`LocalSet::block_on`]: struct.LocalSet.html#method.block_on
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+/// use tio::task::{self, LocalSet};
+///
+/// LocalSet::new().block_on(async {
+///     let data = Rc::new("hello");
+///     let handle = task::spawn_local(async move { data.len() });
+///     assert_eq!(5, handle.await.unwrap());
+/// });
+/// ```
+#[track_caller]
+pub fn spawn_local<F, R>(fut: F) -> JoinHandle<R>
+where
+    R: 'static,
+    F: 'static + Future<Output = R>,
+{
+    spawn_local_named(None, fut)
+}
+
+#[track_caller]
+pub(crate) fn spawn_local_named<F, R>(name: Option<String>, fut: F) -> JoinHandle<R>
+where
+    R: 'static,
+    F: 'static + Future<Output = R>,
+{
+    // no closure here, it would hide the caller location from `Tag::wrap`
+    match CURRENT.with(|current| current.borrow().clone()) {
+        Some(shared) => spawn_with(&shared, name, fut),
+        None => panic!("`spawn_local` called outside of a `LocalSet`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{spawn_local, LocalSet};
+    use crate::task::{block_on, yield_now};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn basic() {
+        let local = LocalSet::new();
+        assert_eq!(1, local.block_on(local.spawn_local(async { 1 })).unwrap());
+    }
+
+    #[test]
+    fn shared_state() {
+        let counter = Rc::new(Cell::new(0));
+        LocalSet::new().block_on(async {
+            let handles = (0..10)
+                .map(|_| {
+                    let counter = counter.clone();
+                    spawn_local(async move {
+                        yield_now().await;
+                        counter.set(counter.get() + 1);
+                    })
+                })
+                .collect::<Vec<_>>();
+            for handle in handles {
+                handle.await.unwrap();
+            }
+        });
+        assert_eq!(10, counter.get());
+    }
+
+    #[test]
+    #[should_panic]
+    fn outside_local_set() {
+        block_on(async {
+            spawn_local(async {});
+        })
+    }
+
+    #[test]
+    #[should_panic]
+    fn unwind_caught() {
+        let local = LocalSet::new();
+        local
+            .block_on(local.spawn_local(async { panic!("task panic") }))
+            .unwrap()
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static POLICY: AtomicU8 = AtomicU8::new(PanicPolicy::Propagate as u8);
+
+/// How a panic inside a task is handled.
+///
+/// The policy is set with [`set_panic_policy`] and applies to every task, including tasks which
+/// are already running.
+///
+/// [`set_panic_policy`]: fn.set_panic_policy.html
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum PanicPolicy {
+    /// Catches the panic and hands it over to the [`JoinHandle`] as a [`JoinError`] which
+    /// [is panic]. This is the default.
+    ///
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    /// [`JoinError`]: struct.JoinError.html
+    /// [is panic]: struct.JoinError.html#method.is_panic
+    #[default]
+    Propagate,
+
+    /// Like [`Propagate`], but also logs the panic with the id and name of the task, so panics
+    /// of detached tasks don't go unnoticed.
+    ///
+    /// [`Propagate`]: #variant.Propagate
+    Log,
+
+    /// Logs the panic and aborts the process.
+    Abort,
+}
+
+/// Sets the [`PanicPolicy`] of tasks.
+///
+/// [`PanicPolicy`]: enum.PanicPolicy.html
+///
+/// # Examples
+///
+/// ```
+/// use tio::task::{self, PanicPolicy};
+///
+/// task::set_panic_policy(PanicPolicy::Log);
+/// assert_eq!(PanicPolicy::Log, task::panic_policy());
+/// ```
+#[inline]
+pub fn set_panic_policy(policy: PanicPolicy) {
+    POLICY.store(policy as u8, Ordering::Relaxed)
+}
+
+/// Returns the current [`PanicPolicy`] of tasks.
+///
+/// [`PanicPolicy`]: enum.PanicPolicy.html
+#[inline]
+pub fn panic_policy() -> PanicPolicy {
+    match POLICY.load(Ordering::Relaxed) {
+        0 => PanicPolicy::Propagate,
+        1 => PanicPolicy::Log,
+        _ => PanicPolicy::Abort,
+    }
+}
@@ -0,0 +1,185 @@
+use futures::future::{poll_fn, LocalBoxFuture};
+use futures::pin_mut;
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::FutureExt;
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::rc::Rc;
+use std::task::Poll;
+
+/// A handle to spawn tasks which may borrow from the enclosing stack frame.
+///
+/// This type is passed to the body of [`task::scope`]. It can be cloned and moved into
+/// the spawned tasks, so they can spawn more tasks on the same scope.
+///
+/// [`task::scope`]: fn.scope.html
+#[derive(Clone)]
+pub struct Scope<'a> {
+    spawned: Rc<RefCell<Vec<LocalBoxFuture<'a, ()>>>>,
+}
+
+impl<'a> Scope<'a> {
+    /// Spawns a task within this scope.
+    ///
+    /// The task runs concurrently with the body of [`task::scope`] and the other tasks of
+    /// the scope, and it is guaranteed to be finished or dropped before the scope
+    /// completes. A task spawned after the scope has completed is never run.
+    ///
+    /// [`task::scope`]: fn.scope.html
+    #[inline]
+    pub fn spawn<F>(&self, fut: F)
+    where
+        F: 'a + Future<Output = ()>,
+    {
+        self.spawned.borrow_mut().push(fut.boxed_local())
+    }
+}
+
+impl Debug for Scope<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scope")
+            .field("spawned", &self.spawned.borrow().len())
+            .finish()
+    }
+}
+
+/// Runs an async body which spawns tasks borrowing from the current stack frame.
+///
+/// Unlike [`spawn`], tasks spawned on the [`Scope`] don't need to be `'static`. The body
+/// and the spawned tasks run concurrently, and the returned future completes with the
+/// output of the body once the body and every task have finished, so no borrow can
+/// outlive the scope. If the returned future is dropped, the body and the unfinished
+/// tasks are dropped as well. A panic in the body or in any task is propagated after the
+/// others are dropped.
+///
+/// Scoped tasks are polled by the returned future itself rather than by the thread pool, so
+/// they run concurrently but not in parallel. Use [`spawn`] for CPU-bound parallelism.
+///
+/// [`spawn`]: fn.spawn.html
+/// [`Scope`]: struct.Scope.html
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::task;
+///
+/// let inputs = vec![1, 2, 3];
+/// let mut outputs = vec![0; 3];
+///
+/// let (inputs_ref, outputs_ref) = (&inputs, &mut outputs);
+/// let spawned = task::scope(|s| async move {
+///     for (input, output) in inputs_ref.iter().zip(outputs_ref.iter_mut()) {
+///         s.spawn(async move {
+///             task::yield_now().await;
+///             *output = input * 2;
+///         });
+///     }
+///     inputs_ref.len()
+/// })
+/// .await;
+///
+/// assert_eq!(3, spawned);
+/// assert_eq!(vec![2, 4, 6], outputs);
+/// #
+/// # })
+/// ```
+pub async fn scope<'a, F, Fut>(f: F) -> Fut::Output
+where
+    F: FnOnce(Scope<'a>) -> Fut,
+    Fut: Future,
+{
+    let scope = Scope {
+        spawned: Rc::new(RefCell::new(Vec::new())),
+    };
+    let body = f(scope.clone());
+    pin_mut!(body);
+    let mut output = None;
+    let mut tasks = FuturesUnordered::new();
+    poll_fn(|cx| loop {
+        if output.is_none() {
+            if let Poll::Ready(out) = body.as_mut().poll(cx) {
+                output = Some(out);
+            }
+        }
+        tasks.extend(scope.spawned.borrow_mut().drain(..));
+        while let Poll::Ready(Some(())) = tasks.poll_next_unpin(cx) {}
+        // the tasks may have spawned more tasks
+        if !scope.spawned.borrow().is_empty() {
+            continue;
+        }
+        if output.is_some() && tasks.is_empty() {
+            break Poll::Ready(output.take().unwrap());
+        }
+        break Poll::Pending;
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scope;
+    use crate::task::{block_on, yield_now};
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn borrow() {
+        let counter = AtomicUsize::new(0);
+        let counter = &counter;
+        let ret = block_on(scope(|s| async move {
+            for _ in 0..10 {
+                s.spawn(async move {
+                    yield_now().await;
+                    counter.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+            1
+        }));
+        assert_eq!(1, ret);
+        assert_eq!(10, counter.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn concurrent_body() {
+        let done = Cell::new(false);
+        let done = &done;
+        block_on(scope(|s| async move {
+            s.spawn(async move {
+                yield_now().await;
+                done.set(true);
+            });
+            // the body keeps running while the task does
+            while !done.get() {
+                yield_now().await;
+            }
+        }));
+        assert!(done.get());
+    }
+
+    #[test]
+    fn nested_spawn() {
+        let counter = AtomicUsize::new(0);
+        let counter = &counter;
+        block_on(scope(|s| async move {
+            let inner = s.clone();
+            s.spawn(async move {
+                yield_now().await;
+                inner.spawn(async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                });
+            });
+        }));
+        assert_eq!(1, counter.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    #[should_panic]
+    fn unwind() {
+        block_on(scope(|s| async move {
+            s.spawn(async { panic!("task panic") });
+        }))
+    }
+}
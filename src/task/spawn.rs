@@ -1,96 +1,21 @@
-use super::{JoinHandle, Task};
-use crossbeam_deque::{Injector, Stealer, Worker};
-use crossbeam_queue::ArrayQueue;
-use crossbeam_utils::sync::{Parker, Unparker};
-use futures::FutureExt;
-use once_cell::sync::Lazy;
+use super::JoinHandle;
+use crate::runtime::context;
 use std::future::Future;
-use std::iter;
-use std::panic::AssertUnwindSafe;
-use std::sync::Arc;
-use std::thread;
-
-struct Pool {
-    injector: Injector<Task>,
-    unparkers: ArrayQueue<Unparker>,
-}
-
-impl Pool {
-    fn new(cpus: usize) -> Self {
-        Self {
-            injector: Injector::new(),
-            unparkers: ArrayQueue::new(cpus),
-        }
-    }
-}
-
-static POOL: Lazy<Arc<Pool>> = Lazy::new(|| {
-    let nums = num_cpus::get();
-    let pool = Arc::new(Pool::new(nums));
-    let workers = (0..nums).map(|_| Worker::new_fifo()).collect::<Vec<_>>();
-    let stealers = workers
-        .iter()
-        .map(|worker| worker.stealer())
-        .collect::<Vec<Stealer<Task>>>();
-    for (index, worker) in workers.into_iter().enumerate() {
-        let stealers = stealers.clone();
-        let pool = pool.clone();
-        thread::Builder::new()
-            .name(format!("tio/async{}", index))
-            .spawn(move || {
-                let parker = Parker::new();
-                loop {
-                    if let Some(task) = find_task(&worker, &pool.injector, &stealers) {
-                        task.run();
-                    } else {
-                        pool.unparkers
-                            .push(parker.unparker().clone())
-                            .expect("fail to push unparker");
-                        parker.park()
-                    }
-                }
-            })
-            .expect("fail to start thread");
-    }
-    pool
-});
-
-#[inline]
-fn find_task<T>(
-    local: &Worker<T>,
-    global: &Injector<T>,
-    stealers: &[Stealer<T>],
-) -> Option<T> {
-    // Pop a task from the local queue, if not empty.
-    local.pop().or_else(|| {
-        // Otherwise, we need to look for a task elsewhere.
-        iter::repeat_with(|| {
-            // Try stealing a batch of tasks from the global queue.
-            global
-                .steal_batch_and_pop(local)
-                // Or try stealing a task from one of the other threads.
-                .or_else(|| stealers.iter().map(|s| s.steal()).collect())
-        })
-        // Loop while no task was stolen and any steal operation needs to be retried.
-        .find(|s| !s.is_retry())
-        // Extract the stolen task, if there is one.
-        .and_then(|s| s.success())
-    })
-}
-
-#[inline]
-fn schedule(t: Task) {
-    POOL.injector.push(t);
-    if let Ok(unparker) = POOL.unparkers.pop() {
-        unparker.unpark()
-    }
-}
 
 /// Spawns a task.
 ///
 /// This function is similar to [`std::thread::spawn`], except it spawns an asynchronous task.
 ///
+/// The task is spawned onto the runtime of the current context, see [`runtime`]. Tasks run on
+/// a work-stealing thread pool. Each worker thread has a local run queue, which takes the tasks
+/// spawned or woken on that worker; tasks from other threads go to a global queue. Idle workers
+/// steal from the global queue first, then from the other workers. There is one worker per CPU,
+/// unless the `TIO_WORKER_THREADS` environment variable or [`Builder::worker_threads`] sets
+/// the number.
+///
 /// [`std::thread`]: https://doc.rust-lang.org/std/thread/fn.spawn.html
+/// [`runtime`]: ../runtime/index.html
+/// [`Builder::worker_threads`]: ../runtime/struct.Builder.html#method.worker_threads
 ///
 /// # Examples
 ///
@@ -103,20 +28,27 @@ fn schedule(t: Task) {
 ///     1 + 2
 /// });
 ///
-/// assert_eq!(handle.await, 3);
+/// assert_eq!(handle.await.unwrap(), 3);
 /// #
 /// # })
 /// ```
 #[cfg_attr(feature = "docs", doc(cfg(feature = "async-rt")))]
+#[track_caller]
 pub fn spawn<F, R>(fut: F) -> JoinHandle<R>
 where
     R: 'static + Send,
     F: 'static + Send + Future<Output = R>,
 {
-    let fut = AssertUnwindSafe(fut).catch_unwind();
-    let (task, handler) = async_task::spawn(fut, schedule, ());
-    task.schedule();
-    JoinHandle(handler)
+    spawn_with(None, fut)
+}
+
+#[track_caller]
+pub(crate) fn spawn_with<F, R>(name: Option<String>, fut: F) -> JoinHandle<R>
+where
+    R: 'static + Send,
+    F: 'static + Send + Future<Output = R>,
+{
+    context::current().spawn(name, fut)
 }
 
 #[cfg(test)]
@@ -126,7 +58,7 @@ mod tests {
 
     #[test]
     fn basic() {
-        assert_eq!(1, block_on(spawn(async { 1 })));
+        assert_eq!(1, block_on(spawn(async { 1 })).unwrap());
     }
 
     #[test]
@@ -141,6 +73,28 @@ mod tests {
     #[test]
     #[should_panic]
     fn unwind_caught() {
-        block_on(spawn(async { panic!("task panic") }))
+        block_on(spawn(async { panic!("task panic") })).unwrap()
+    }
+
+    #[test]
+    fn nested() {
+        let sum = block_on(spawn(async {
+            let handles = (0..100)
+                .map(|i| spawn(async move { i }))
+                .collect::<Vec<_>>();
+            let mut sum = 0;
+            for handle in handles {
+                sum += handle.await.unwrap();
+            }
+            sum
+        }));
+        assert_eq!((0..100).sum::<i32>(), sum.unwrap());
+    }
+
+    #[test]
+    fn detach() {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        spawn(async move { sender.send(1).unwrap() }).detach();
+        assert_eq!(1, receiver.recv().unwrap());
     }
 }
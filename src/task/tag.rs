@@ -0,0 +1,401 @@
+use super::abort::{poll_abortable, AbortHandle, Aborted};
+use super::{coop, panic_policy, PanicPolicy, Task};
+use futures::future::poll_fn;
+use futures::{pin_mut, FutureExt};
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::num::NonZeroU64;
+use std::panic::{AssertUnwindSafe, Location};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::Result;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<Info>>> = const { RefCell::new(None) };
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A unique identifier of a task.
+///
+/// Identifiers are never reused during the lifetime of a process.
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::task;
+///
+/// let handle = task::spawn_blocking(|| task::id());
+/// let id = handle.id();
+/// assert_eq!(Some(id), handle.await.unwrap());
+/// #
+/// # })
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct TaskId(NonZeroU64);
+
+impl TaskId {
+    #[inline]
+    fn generate() -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Self(NonZeroU64::new(id).expect("task id overflow"))
+    }
+
+    /// Returns the id as a number.
+    #[inline]
+    pub fn as_u64(self) -> u64 {
+        self.0.get()
+    }
+}
+
+impl Debug for TaskId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "TaskId({})", self.0)
+    }
+}
+
+impl Display for TaskId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// Metadata of a task, shared by the task itself and its handle.
+pub(crate) struct Info {
+    id: TaskId,
+    name: Option<Arc<str>>,
+    location: &'static Location<'static>,
+    finished: AtomicBool,
+    #[cfg(feature = "task-dump")]
+    running: AtomicBool,
+    #[cfg(feature = "task-dump")]
+    polls: AtomicU64,
+}
+
+impl Info {
+    #[inline]
+    pub(crate) fn id(&self) -> TaskId {
+        self.id
+    }
+
+    #[inline]
+    pub(crate) fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    pub(crate) fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    #[cfg(feature = "task-dump")]
+    #[inline]
+    pub(crate) fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    #[cfg(feature = "task-dump")]
+    #[inline]
+    pub(crate) fn polls(&self) -> u64 {
+        self.polls.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(feature = "console")]
+impl Drop for Info {
+    #[inline]
+    fn drop(&mut self) {
+        crate::console::emit(crate::console::Event::Drop(self.id));
+    }
+}
+
+impl Display for Info {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "task {} '{}'", self.id, name),
+            None => write!(f, "task {}", self.id),
+        }
+    }
+}
+
+/// The output of a task body, `Err(Aborted)` if it is aborted by an [`AbortHandle`].
+pub(crate) type Output<T> = Result<std::result::Result<T, Aborted>>;
+
+/// Data attached to every task.
+pub(crate) struct Tag {
+    info: Arc<Info>,
+    // the NUMA node the task is hinted to run on
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    node: Option<usize>,
+}
+
+impl Tag {
+    /// Wraps a future into a task body, making it abortable, catching panics and recording its
+    /// completion.
+    #[inline]
+    #[track_caller]
+    pub(crate) fn wrap<F>(
+        name: Option<String>,
+        fut: F,
+    ) -> (Self, AbortHandle, impl Future<Output = Output<F::Output>>)
+    where
+        F: Future,
+    {
+        let (abort, registration) = AbortHandle::new_pair();
+        let info = Arc::new(Info {
+            id: TaskId::generate(),
+            name: name.map(Into::into),
+            location: Location::caller(),
+            finished: AtomicBool::new(false),
+            #[cfg(feature = "task-dump")]
+            running: AtomicBool::new(false),
+            #[cfg(feature = "task-dump")]
+            polls: AtomicU64::new(0),
+        });
+        #[cfg(feature = "console")]
+        crate::console::emit(crate::console::Event::Spawn(
+            info.id,
+            info.name(),
+            info.location,
+        ));
+        trace_event!(target: "tio::task", "spawn {} at {}", info, info.location);
+        let tag = Self {
+            info: info.clone(),
+            #[cfg(all(target_os = "linux", feature = "affinity"))]
+            node: None,
+        };
+        let fut = async move {
+            pin_mut!(fut);
+            poll_fn(|cx| poll_abortable(&registration, fut.as_mut(), cx)).await
+        };
+        // the task is finished once its body is dropped: completed, panicked, or cancelled
+        // before it completes, which drops the body without calling the closure
+        let finish = Finish(info.clone());
+        let fut = AssertUnwindSafe(fut).catch_unwind().map(move |output| {
+            let _finish = finish;
+            if let Err(err) = &output {
+                match panic_policy() {
+                    PanicPolicy::Propagate => (),
+                    PanicPolicy::Log => {
+                        log::error!(
+                            "{} spawned at {} panicked: {}",
+                            info,
+                            info.location,
+                            panic_message(&**err)
+                        )
+                    }
+                    PanicPolicy::Abort => {
+                        log::error!(
+                            "{} spawned at {} panicked: {}",
+                            info,
+                            info.location,
+                            panic_message(&**err)
+                        );
+                        process::abort()
+                    }
+                }
+            }
+            let _outcome = match output {
+                Ok(Ok(_)) => "complete",
+                Ok(Err(Aborted)) => "abort",
+                Err(_) => "panic",
+            };
+            #[cfg(feature = "console")]
+            crate::console::emit(crate::console::Event::Exit(info.id, _outcome));
+            trace_event!(target: "tio::task", "{} {}", _outcome, info);
+            output
+        });
+        (tag, abort, fut)
+    }
+
+    #[inline]
+    pub(crate) fn info(&self) -> &Arc<Info> {
+        &self.info
+    }
+
+    /// Returns the NUMA node the task is hinted to run on.
+    #[cfg(feature = "async-rt")]
+    #[inline]
+    pub(crate) fn node(&self) -> Option<usize> {
+        #[cfg(all(target_os = "linux", feature = "affinity"))]
+        return self.node;
+        #[cfg(not(all(target_os = "linux", feature = "affinity")))]
+        None
+    }
+
+    #[cfg(all(target_os = "linux", feature = "affinity"))]
+    #[inline]
+    pub(crate) fn set_node(&mut self, node: usize) {
+        self.node = Some(node);
+    }
+}
+
+/// Extracts the message of a panic payload.
+#[inline]
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+/// Marks a task as finished when dropped.
+struct Finish(Arc<Info>);
+
+impl Drop for Finish {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.finished.store(true, Ordering::Release)
+    }
+}
+
+/// Restores the previous current task when dropped.
+struct CurrentGuard(Option<Arc<Info>>);
+
+impl Drop for CurrentGuard {
+    #[inline]
+    fn drop(&mut self) {
+        let _info = CURRENT.with(|current| current.replace(self.0.take()));
+        #[cfg(feature = "task-dump")]
+        if let Some(info) = _info {
+            info.running.store(false, Ordering::Relaxed)
+        }
+    }
+}
+
+/// Runs a task, marking it as the current task of this thread.
+#[inline]
+pub(crate) fn run(task: Task) {
+    let info = task.tag().info.clone();
+    #[cfg(feature = "task-dump")]
+    {
+        info.running.store(true, Ordering::Relaxed);
+        info.polls.fetch_add(1, Ordering::Relaxed);
+    }
+    #[cfg(any(feature = "trace-log", feature = "console"))]
+    let (id, start) = (info.id, std::time::Instant::now());
+    let _guard = CurrentGuard(CURRENT.with(|current| current.replace(Some(info))));
+    coop::budget(|| task.run());
+    #[cfg(feature = "console")]
+    crate::console::emit(crate::console::Event::Poll(id, start.elapsed()));
+    trace_event!(target: "tio::task", "poll task {} in {:?}", id, start.elapsed());
+}
+
+/// Returns the metadata of the task which is running on the current thread.
+#[cfg(all(debug_assertions, feature = "detect-blocking"))]
+#[inline]
+pub(crate) fn current() -> Option<Arc<Info>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Returns the id of the task which is running on the current thread.
+///
+/// Returns [`None`] outside of a spawned task.
+///
+/// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+///
+/// # Examples
+///
+/// ```
+/// use tio::task;
+///
+/// assert_eq!(None, task::id());
+/// ```
+#[inline]
+pub fn id() -> Option<TaskId> {
+    CURRENT.with(|current| current.borrow().as_ref().map(|info| info.id))
+}
+
+/// Returns the name of the task which is running on the current thread.
+///
+/// Returns [`None`] outside of a spawned task, or if the task is unnamed.
+///
+/// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::task;
+///
+/// let name = task::Builder::new()
+///     .name("worker")
+///     .spawn(async { task::name() })
+///     .await
+///     .unwrap();
+/// assert_eq!(Some("worker".into()), name);
+/// #
+/// # })
+/// ```
+#[inline]
+pub fn name() -> Option<Arc<str>> {
+    CURRENT.with(|current| current.borrow().as_ref().and_then(|info| info.name.clone()))
+}
+
+#[cfg(all(test, feature = "trace-log"))]
+mod tests {
+    use crate::runtime::Builder;
+    use log::{Log, Metadata, Record};
+    use std::sync::{Mutex, PoisonError};
+    use std::time::Duration;
+
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Log for Recorder {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.target() == "tio::task"
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            if self.enabled(record.metadata()) {
+                self.0
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(record.args().to_string())
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+    #[test]
+    fn trace_events() {
+        log::set_logger(&RECORDER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        let runtime = Builder::new().build().unwrap();
+        let handle = crate::task::Builder::new()
+            .name("traced")
+            .spawn_on_handle(runtime.handle(), async {});
+        let id = handle.id();
+        runtime.block_on(handle).unwrap();
+        // a poll is recorded after its output is published, so wait for the workers
+        runtime.shutdown_timeout(Duration::from_secs(10));
+        let records = RECORDER
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let spawn = format!("spawn task {} 'traced' at", id);
+        assert!(records.iter().any(|record| record.starts_with(&spawn)));
+        let poll = format!("poll task {} in", id);
+        assert!(records.iter().any(|record| record.starts_with(&poll)));
+        let complete = format!("complete task {} 'traced'", id);
+        assert!(records.contains(&complete));
+    }
+}
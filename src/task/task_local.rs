@@ -0,0 +1,246 @@
+use futures::future::poll_fn;
+use futures::pin_mut;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::mem;
+use std::thread;
+
+/// Declares a new task-local key of type [`task::LocalKey`].
+///
+/// The value of a task-local key is set for the duration of a future with [`LocalKey::scope`].
+/// It is visible across every await point of that future, but not from tasks it spawns.
+///
+/// [`task::LocalKey`]: task/struct.LocalKey.html
+/// [`LocalKey::scope`]: task/struct.LocalKey.html#method.scope
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use tio::task;
+///
+/// tio::task_local! {
+///     static REQUEST_ID: u64;
+/// }
+///
+/// REQUEST_ID.scope(42, async {
+///     task::yield_now().await;
+///     assert_eq!(42, REQUEST_ID.get());
+/// }).await;
+/// #
+/// # })
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    () => {};
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty; $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis static $name: $crate::task::LocalKey<$t> = {
+            std::thread_local! {
+                static __KEY: std::cell::RefCell<Option<$t>> = const {
+                    std::cell::RefCell::new(None)
+                };
+            }
+            $crate::task::LocalKey { inner: __KEY }
+        };
+
+        $crate::task_local!($($rest)*);
+    };
+}
+
+/// A key for task-local data.
+///
+/// This type is created by the [`task_local!`] macro.
+///
+/// [`task_local!`]: ../macro.task_local.html
+pub struct LocalKey<T: 'static> {
+    #[doc(hidden)]
+    pub inner: thread::LocalKey<RefCell<Option<T>>>,
+}
+
+impl<T: 'static> LocalKey<T> {
+    /// Sets the value of this key for the duration of a future.
+    ///
+    /// The value is dropped once the future completes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # tio::task::block_on(async {
+    /// #
+    /// tio::task_local! {
+    ///     static NAME: String;
+    /// }
+    ///
+    /// let len = NAME.scope("tio".to_string(), async {
+    ///     NAME.with(|name| name.len())
+    /// }).await;
+    /// assert_eq!(3, len);
+    /// #
+    /// # })
+    /// ```
+    pub async fn scope<F>(&'static self, value: T, fut: F) -> F::Output
+    where
+        F: Future,
+    {
+        let mut slot = Some(value);
+        pin_mut!(fut);
+        poll_fn(|cx| self.enter(&mut slot, || fut.as_mut().poll(cx))).await
+    }
+
+    /// Sets the value of this key for the duration of a closure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// tio::task_local! {
+    ///     static DEPTH: usize;
+    /// }
+    ///
+    /// DEPTH.sync_scope(1, || assert_eq!(1, DEPTH.get()));
+    /// ```
+    pub fn sync_scope<F, R>(&'static self, value: T, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let mut slot = Some(value);
+        self.enter(&mut slot, f)
+    }
+
+    /// Accesses the current value of this key.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the key is not set by [`scope`] or [`sync_scope`].
+    ///
+    /// [`scope`]: #method.scope
+    /// [`sync_scope`]: #method.sync_scope
+    #[inline]
+    pub fn with<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.try_with(f)
+            .expect("cannot access a task-local value outside of its scope")
+    }
+
+    /// Accesses the current value of this key, returning an [`AccessError`] if it is not set.
+    ///
+    /// [`AccessError`]: struct.AccessError.html
+    #[inline]
+    pub fn try_with<F, R>(&'static self, f: F) -> Result<R, AccessError>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.inner
+            .try_with(|cell| cell.borrow().as_ref().map(f))
+            .ok()
+            .flatten()
+            .ok_or(AccessError(()))
+    }
+
+    /// Returns a copy of the current value of this key.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the key is not set.
+    #[inline]
+    pub fn get(&'static self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+
+    /// Swaps `slot` into the key while running `f`, then swaps it back.
+    fn enter<F, R>(&'static self, slot: &mut Option<T>, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        struct Guard<'a, T: 'static> {
+            key: &'static thread::LocalKey<RefCell<Option<T>>>,
+            slot: &'a mut Option<T>,
+        }
+
+        impl<T: 'static> Drop for Guard<'_, T> {
+            fn drop(&mut self) {
+                self.key
+                    .with(|cell| mem::swap(self.slot, &mut *cell.borrow_mut()));
+            }
+        }
+
+        self.inner
+            .with(|cell| mem::swap(slot, &mut *cell.borrow_mut()));
+        let _guard = Guard {
+            key: &self.inner,
+            slot,
+        };
+        f()
+    }
+}
+
+impl<T: 'static> Debug for LocalKey<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad("LocalKey { .. }")
+    }
+}
+
+/// An error returned by [`LocalKey::try_with`] outside of the scope of the key.
+///
+/// [`LocalKey::try_with`]: struct.LocalKey.html#method.try_with
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct AccessError(());
+
+impl Debug for AccessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessError").finish()
+    }
+}
+
+impl Display for AccessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("task-local value not set")
+    }
+}
+
+impl Error for AccessError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::task::{block_on, spawn_blocking, yield_now};
+
+    crate::task_local! {
+        static NUMBER: u32;
+        static NAME: &'static str;
+    }
+
+    #[test]
+    fn across_await() {
+        block_on(NUMBER.scope(1, async {
+            yield_now().await;
+            assert_eq!(1, NUMBER.get());
+        }));
+        assert!(NUMBER.try_with(|_| ()).is_err());
+    }
+
+    #[test]
+    fn nested() {
+        block_on(NUMBER.scope(1, async {
+            NUMBER.scope(2, async { assert_eq!(2, NUMBER.get()) }).await;
+            assert_eq!(1, NUMBER.get());
+            NAME.sync_scope("tio", || assert_eq!("tio", NAME.get()));
+        }));
+    }
+
+    #[test]
+    fn not_across_spawn() {
+        block_on(NUMBER.scope(1, async {
+            let handle = spawn_blocking(|| NUMBER.try_with(|_| ()).is_err());
+            assert!(handle.await.unwrap());
+        }));
+    }
+}
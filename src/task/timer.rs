@@ -1,5 +1,8 @@
-use futures::Stream;
-use futures_timer::Delay;
+use crate::runtime::time::Delay;
+use futures::future::{self, Either};
+use futures::{pin_mut, Stream};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -36,6 +39,52 @@ pub async fn sleep(dur: Duration) {
     Delay::new(dur).await
 }
 
+/// Awaits a future, or times out once `dur` elapses.
+///
+/// The future is dropped if it times out.
+///
+/// # Examples
+///
+/// ```
+/// # tio::task::block_on(async {
+/// #
+/// use std::time::Duration;
+///
+/// use futures::future;
+/// use tio::task;
+///
+/// let never = future::pending::<()>();
+/// assert!(task::timeout(Duration::from_millis(10), never).await.is_err());
+/// #
+/// # })
+/// ```
+#[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+pub async fn timeout<F: Future>(
+    dur: Duration,
+    fut: F,
+) -> Result<F::Output, TimeoutError> {
+    pin_mut!(fut);
+    match future::select(fut, Delay::new(dur)).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(TimeoutError),
+    }
+}
+
+/// An error returned by [`timeout`] when the future times out.
+///
+/// [`timeout`]: fn.timeout.html
+#[cfg_attr(feature = "docs", doc(cfg(feature = "timer")))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TimeoutError;
+
+impl Display for TimeoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("future has timed out")
+    }
+}
+
+impl Error for TimeoutError {}
+
 /// A stream representing notifications at fixed interval
 ///
 /// This stream is created by the [`interval`] function. See its
@@ -124,7 +173,7 @@ mod tests {
             let mut interval = task::interval(gap);
             let mut counter = 0;
             let start = Instant::now();
-            while let Some(_) = interval.next().await {
+            while interval.next().await.is_some() {
                 counter += 1;
                 if counter >= times {
                     break;
@@ -135,4 +184,16 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn timeout() {
+        task::block_on(async {
+            let dur = Duration::from_millis(10);
+            assert_eq!(Ok(1), task::timeout(dur, future::ready(1)).await);
+            let start = Instant::now();
+            let slow = task::sleep(Duration::from_secs(10));
+            assert_eq!(Err(task::TimeoutError), task::timeout(dur, slow).await);
+            assert!(start.elapsed() >= dur);
+        })
+    }
 }
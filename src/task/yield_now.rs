@@ -37,3 +37,33 @@ pub async fn yield_now() {
     })
     .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::yield_now;
+    use crate::task::{spawn_local, LocalSet};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn reschedule_to_back() {
+        let order = Rc::new(RefCell::new(Vec::new()));
+        LocalSet::new().block_on(async {
+            let first = {
+                let order = order.clone();
+                spawn_local(async move {
+                    order.borrow_mut().push(1);
+                    yield_now().await;
+                    order.borrow_mut().push(3);
+                })
+            };
+            let second = {
+                let order = order.clone();
+                spawn_local(async move { order.borrow_mut().push(2) })
+            };
+            first.await.unwrap();
+            second.await.unwrap();
+        });
+        assert_eq!(vec![1, 2, 3], *order.borrow());
+    }
+}
@@ -0,0 +1,18 @@
+//! Utilities for testing async code deterministically.
+//!
+//! The utilities are meant to run on a [seeded] runtime, which runs its tasks in a
+//! reproducible order and jumps its clock over the sleeps, so a test of timeouts and retries
+//! takes no real time and fails the same way on every run.
+//!
+//! [seeded]: ../runtime/struct.Builder.html#method.seed
+//!
+//! # Organization
+//!
+//! * [`io`] injects faults into the readers and the writers
+//! * [`net`] simulates a network in memory, with latency, reordering and partitions
+//!
+//! [`io`]: io/index.html
+//! [`net`]: net/index.html
+
+pub mod io;
+pub mod net;
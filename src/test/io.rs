@@ -0,0 +1,366 @@
+//! Fault injection into the readers and the writers.
+//!
+//! A [`FaultyIo`] wraps any reader or writer, and makes its reads and writes misbehave like
+//! the ones of a real socket may: they spuriously return `Pending`, they read or write less
+//! than the length of the buffer, or they fail. The faults are scripted, or drawn at random
+//! from a seeded generator, so a failing test is reproducible.
+//!
+//! [`FaultyIo`]: struct.FaultyIo.html
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> { tio::task::block_on(async {
+//! #
+//! use futures::io::{AsyncReadExt, Cursor};
+//! use std::io::ErrorKind;
+//! use tio::test::io::{Fault, FaultyIo};
+//!
+//! // a protocol reading a frame of 8 bytes must survive the short reads
+//! let mut reader = FaultyIo::new(Cursor::new(b"hello world"), 42)
+//!     .would_block(0.5)
+//!     .short(0.5);
+//! let mut frame = [0; 8];
+//! reader.read_exact(&mut frame).await?;
+//! assert_eq!(b"hello wo", &frame);
+//!
+//! // and fail cleanly on a reset
+//! let mut reader = FaultyIo::new(Cursor::new(b"hello world"), 42)
+//!     .script_reads(vec![Fault::Short(3), Fault::Error(ErrorKind::ConnectionReset)]);
+//! let err = reader.read_exact(&mut frame).await.unwrap_err();
+//! assert_eq!(ErrorKind::ConnectionReset, err.kind());
+//! #
+//! # Ok(()) }) }
+//! ```
+
+use futures::io::{AsyncRead, AsyncWrite, IoSlice, IoSliceMut};
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A fault of a read or a write of a [`FaultyIo`].
+///
+/// [`FaultyIo`]: struct.FaultyIo.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Fault {
+    /// Passes the operation through, unchanged.
+    None,
+
+    /// Returns `Pending`, waking the task at once, like a socket whose readiness was stale.
+    WouldBlock,
+
+    /// Reads or writes at most the given number of bytes, at least one.
+    Short(usize),
+
+    /// Fails the operation, with an error of the given kind.
+    Error(io::ErrorKind),
+}
+
+/// A reader or a writer injecting faults into the operations of another one.
+///
+/// Every read and every write draws a fault: the next one of its script if any, or a random
+/// one by the probabilities. An operation on an empty buffer is never faulted, and the flushes
+/// and the closes are passed through.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: index.html
+#[must_use = "readers do nothing unless polled"]
+#[derive(Debug)]
+pub struct FaultyIo<T> {
+    inner: T,
+    rng: u64,
+    would_block: f64,
+    short: f64,
+    error: Option<(f64, io::ErrorKind)>,
+    reads: VecDeque<Fault>,
+    writes: VecDeque<Fault>,
+}
+
+impl<T> FaultyIo<T> {
+    /// Wraps `inner`, drawing the random faults from a generator seeded by `seed`.
+    ///
+    /// No fault is injected until it is configured.
+    #[inline]
+    pub fn new(inner: T, seed: u64) -> Self {
+        Self {
+            inner,
+            rng: seed,
+            would_block: 0.,
+            short: 0.,
+            error: None,
+            reads: VecDeque::new(),
+            writes: VecDeque::new(),
+        }
+    }
+
+    /// Sets the probability of an operation to return `Pending` spuriously.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `probability` is not between 0 and 1.
+    #[inline]
+    pub fn would_block(mut self, probability: f64) -> Self {
+        self.would_block = check(probability);
+        self
+    }
+
+    /// Sets the probability of an operation to be short, of a random length.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `probability` is not between 0 and 1.
+    #[inline]
+    pub fn short(mut self, probability: f64) -> Self {
+        self.short = check(probability);
+        self
+    }
+
+    /// Sets the probability of an operation to fail with an error of `kind`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `probability` is not between 0 and 1.
+    #[inline]
+    pub fn error(mut self, probability: f64, kind: io::ErrorKind) -> Self {
+        self.error = Some((check(probability), kind));
+        self
+    }
+
+    /// Appends faults to the script of the reads, which are injected in order before the
+    /// random ones.
+    #[inline]
+    pub fn script_reads(mut self, faults: impl IntoIterator<Item = Fault>) -> Self {
+        self.reads.extend(faults);
+        self
+    }
+
+    /// Appends faults to the script of the writes, which are injected in order before the
+    /// random ones.
+    #[inline]
+    pub fn script_writes(mut self, faults: impl IntoIterator<Item = Fault>) -> Self {
+        self.writes.extend(faults);
+        self
+    }
+
+    /// Gets a reference to the underlying I/O object.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying I/O object, bypassing the faults.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps this `FaultyIo`, returning the underlying I/O object.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Draws a random number, by splitmix64.
+    fn next(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns whether an event of `probability` happens.
+    #[inline]
+    fn happens(&mut self, probability: f64) -> bool {
+        probability > 0.
+            && ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    /// Draws the fault of an operation on `len` bytes, returning the bytes to pass through.
+    fn fault(
+        &mut self,
+        write: bool,
+        len: usize,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<usize>> {
+        if len == 0 {
+            return Poll::Ready(Ok(0));
+        }
+        let script = if write {
+            &mut self.writes
+        } else {
+            &mut self.reads
+        };
+        let fault = match script.pop_front() {
+            Some(fault) => fault,
+            None => self.draw(len),
+        };
+        match fault {
+            Fault::None => Poll::Ready(Ok(len)),
+            Fault::WouldBlock => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Fault::Short(n) => Poll::Ready(Ok(n.clamp(1, len))),
+            Fault::Error(kind) => {
+                Poll::Ready(Err(io::Error::new(kind, "injected fault")))
+            }
+        }
+    }
+
+    /// Draws a random fault of an operation on `len` bytes.
+    fn draw(&mut self, len: usize) -> Fault {
+        if self.happens(self.would_block) {
+            return Fault::WouldBlock;
+        }
+        if let Some((probability, kind)) = self.error {
+            if self.happens(probability) {
+                return Fault::Error(kind);
+            }
+        }
+        if len > 1 && self.happens(self.short) {
+            return Fault::Short(1 + (self.next() % (len as u64 - 1)) as usize);
+        }
+        Fault::None
+    }
+}
+
+#[inline]
+fn check(probability: f64) -> f64 {
+    assert!(
+        (0. ..=1.).contains(&probability),
+        "the probability is not between 0 and 1"
+    );
+    probability
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for FaultyIo<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = futures::ready!(self.fault(false, buf.len(), cx))?;
+        Pin::new(&mut self.inner).poll_read(cx, &mut buf[..n])
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match bufs.iter_mut().find(|buf| !buf.is_empty()) {
+            Some(buf) => self.poll_read(cx, buf),
+            None => Poll::Ready(Ok(0)),
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for FaultyIo<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = futures::ready!(self.fault(true, buf.len(), cx))?;
+        Pin::new(&mut self.inner).poll_write(cx, &buf[..n])
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match bufs.iter().find(|buf| !buf.is_empty()) {
+            Some(buf) => self.poll_write(cx, buf),
+            None => Poll::Ready(Ok(0)),
+        }
+    }
+
+    #[inline]
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fault, FaultyIo};
+    use crate::task::block_on;
+    use futures::io::{AsyncReadExt, AsyncWriteExt, Cursor};
+    use std::io::{self, ErrorKind};
+
+    #[test]
+    fn script() -> io::Result<()> {
+        block_on(async {
+            let mut reader =
+                FaultyIo::new(Cursor::new(b"hello world"), 0).script_reads(vec![
+                    Fault::Short(2),
+                    Fault::WouldBlock,
+                    Fault::None,
+                    Fault::Error(ErrorKind::ConnectionReset),
+                ]);
+            let mut buf = [0; 16];
+            assert_eq!(2, reader.read(&mut buf).await?);
+            // pending, then passed through
+            assert_eq!(9, reader.read(&mut buf).await?);
+            assert_eq!(b"llo world", &buf[..9]);
+            let err = reader.read(&mut buf).await.unwrap_err();
+            assert_eq!(ErrorKind::ConnectionReset, err.kind());
+            assert_eq!(0, reader.read(&mut buf).await?);
+
+            let mut writer = FaultyIo::new(Vec::new(), 0)
+                .script_writes(vec![Fault::Short(0), Fault::Short(100)]);
+            assert_eq!(1, writer.write(b"hello").await?);
+            assert_eq!(4, writer.write(b"ello").await?);
+            assert_eq!(b"hello", &writer.into_inner()[..]);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn random() -> io::Result<()> {
+        block_on(async {
+            let data = (0..=255).collect::<Vec<u8>>();
+            let mut writer = FaultyIo::new(Vec::new(), 7).would_block(0.3).short(0.8);
+            writer.write_all(&data).await?;
+            let mut reader = FaultyIo::new(Cursor::new(writer.into_inner()), 7)
+                .would_block(0.3)
+                .short(0.8);
+            let mut read = Vec::new();
+            reader.read_to_end(&mut read).await?;
+            assert_eq!(data, read);
+
+            let mut reader = FaultyIo::new(Cursor::new(data), 7)
+                .error(0.5, ErrorKind::Interrupted)
+                .short(1.);
+            let mut lens = Vec::new();
+            let mut buf = [0; 64];
+            for _ in 0..8 {
+                lens.push(reader.read(&mut buf).await.map_err(|err| err.kind()));
+            }
+            assert!(lens.contains(&Err(ErrorKind::Interrupted)));
+            assert!(lens.iter().any(|len| matches!(len, Ok(n) if *n < 64)));
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[should_panic(expected = "the probability is not between 0 and 1")]
+    fn probability() {
+        let _ = FaultyIo::new(Vec::<u8>::new(), 0).short(2.);
+    }
+}
@@ -0,0 +1,880 @@
+//! A network simulated in memory.
+//!
+//! A [`Sim`] is a network of in-memory sockets, lookalikes of the ones of [`tio::net`]. The
+//! hosts are identified by their IP addresses, they need no setup: a socket is bound to any
+//! address, and a stream connects from any address. Every segment and datagram is delivered
+//! after a random latency, so datagrams are reordered, and the links between two hosts can be
+//! partitioned and repaired.
+//!
+//! The latencies are drawn from a generator seeded by the simulation, and they elapse on the
+//! clock of the runtime: on a [seeded] runtime, a simulation is deterministic and takes no real
+//! time.
+//!
+//! [`Sim`]: struct.Sim.html
+//! [`tio::net`]: ../../net/index.html
+//! [seeded]: ../../runtime/struct.Builder.html#method.seed
+//!
+//! # Examples
+//!
+//! ```
+//! use futures::io::{AsyncReadExt, AsyncWriteExt};
+//! use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+//! use std::time::Duration;
+//! use tio::runtime::Builder;
+//! use tio::task;
+//! use tio::test::net::Sim;
+//!
+//! let server: SocketAddr = "10.0.0.1:80".parse().unwrap();
+//! let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+//! let runtime = Builder::new_current_thread().seed(7).build().unwrap();
+//! runtime.block_on(async {
+//!     let sim = Sim::new(7);
+//!     sim.latency(Duration::from_millis(10), Duration::from_millis(50));
+//!     let listener = sim.tcp_bind(server)?;
+//!     task::spawn(async move {
+//!         let (mut stream, _) = listener.accept().await?;
+//!         stream.write_all(b"hello").await?;
+//!         stream.close().await
+//!     });
+//!
+//!     let mut stream = sim.tcp_connect(client, server).await?;
+//!     let mut reply = String::new();
+//!     stream.read_to_string(&mut reply).await?;
+//!     assert_eq!("hello", reply);
+//!
+//!     // a connection across a partition times out
+//!     sim.partition(client, server.ip());
+//!     assert!(sim.tcp_connect(client, server).await.is_err());
+//!     std::io::Result::Ok(())
+//! })
+//! .unwrap();
+//! ```
+
+use crate::task;
+use futures::channel::mpsc;
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::StreamExt;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+const SIM_LOCK_POISONED: &str = "sim lock poisoned";
+const PIPE_LOCK_POISONED: &str = "pipe lock poisoned";
+
+/// The first ephemeral port.
+const EPHEMERAL_PORTS: u16 = 49152;
+
+/// How often a segment held back by a partition is retransmitted.
+const RETRANSMIT: Duration = Duration::from_millis(200);
+
+/// The max length of a datagram.
+const MAX_DATAGRAM: usize = 64 * 1024;
+
+type Datagrams<A> = mpsc::UnboundedReceiver<(Vec<u8>, A)>;
+
+/// A simulated network.
+///
+/// It is cheap to clone, the clones share the same network.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: index.html
+#[derive(Clone)]
+pub struct Sim(Arc<Mutex<State>>);
+
+struct State {
+    rng: u64,
+    latency: (Duration, Duration),
+    // the pairs of hosts partitioned, the lower address first
+    partitions: HashSet<(IpAddr, IpAddr)>,
+    listeners: HashMap<SocketAddr, mpsc::UnboundedSender<TcpStream>>,
+    udp: HashMap<SocketAddr, mpsc::UnboundedSender<(Vec<u8>, SocketAddr)>>,
+    unix: HashMap<PathBuf, mpsc::UnboundedSender<(Vec<u8>, PathBuf)>>,
+    connections: HashSet<(SocketAddr, SocketAddr)>,
+    next_port: u16,
+}
+
+impl Sim {
+    /// Creates a network whose latencies are drawn from a generator seeded by `seed`.
+    ///
+    /// Every latency is 1 ms by default.
+    pub fn new(seed: u64) -> Self {
+        Self(Arc::new(Mutex::new(State {
+            rng: seed,
+            latency: (Duration::from_millis(1), Duration::from_millis(1)),
+            partitions: HashSet::new(),
+            listeners: HashMap::new(),
+            udp: HashMap::new(),
+            unix: HashMap::new(),
+            connections: HashSet::new(),
+            next_port: EPHEMERAL_PORTS,
+        })))
+    }
+
+    /// Sets the range of the latencies of the segments and the datagrams sent from now on.
+    ///
+    /// A datagram sent after another one arrives first if its latency is shorter; the bytes
+    /// of a stream stay ordered, a segment waits for the ones before it.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `min` is greater than `max`.
+    pub fn latency(&self, min: Duration, max: Duration) {
+        assert!(min <= max, "the min latency is greater than the max one");
+        self.state().latency = (min, max);
+    }
+
+    /// Partitions the hosts `a` and `b`.
+    ///
+    /// While they are partitioned, the datagrams between them are dropped and the connections
+    /// between them time out, the segments of the established streams are held back until the
+    /// partition is repaired, like the retransmissions of TCP.
+    pub fn partition(&self, a: IpAddr, b: IpAddr) {
+        self.state().partitions.insert(link(a, b));
+    }
+
+    /// Repairs the partition of the hosts `a` and `b`.
+    pub fn repair(&self, a: IpAddr, b: IpAddr) {
+        self.state().partitions.remove(&link(a, b));
+    }
+
+    /// Returns whether the hosts `a` and `b` are partitioned.
+    pub fn is_partitioned(&self, a: IpAddr, b: IpAddr) -> bool {
+        self.state().partitions.contains(&link(a, b))
+    }
+
+    /// Creates a TCP listener bound to `addr`, or to an ephemeral port if its port is zero.
+    ///
+    /// # Errors
+    ///
+    /// This method fails of `AddrInUse` if a listener is bound to the address already.
+    pub fn tcp_bind(&self, addr: SocketAddr) -> io::Result<TcpListener> {
+        let mut state = self.state();
+        let addr =
+            state.bind_port(addr, |state, addr| state.listeners.contains_key(addr))?;
+        let (sender, receiver) = mpsc::unbounded();
+        state.listeners.insert(addr, sender);
+        Ok(TcpListener {
+            sim: self.clone(),
+            addr,
+            incoming: futures::lock::Mutex::new(receiver),
+        })
+    }
+
+    /// Opens a TCP connection from the host `from` to a listener, taking a round trip.
+    ///
+    /// # Errors
+    ///
+    /// This method fails of `ConnectionRefused` if no listener is bound to `to`, and of
+    /// `TimedOut` if the hosts are partitioned.
+    pub async fn tcp_connect(
+        &self,
+        from: IpAddr,
+        to: SocketAddr,
+    ) -> io::Result<TcpStream> {
+        task::sleep(self.delay()).await;
+        if self.is_partitioned(from, to.ip()) {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "connection timed out",
+            ));
+        }
+        let (local, listener) = {
+            let mut state = self.state();
+            let local = state.bind_port(SocketAddr::new(from, 0), |state, addr| {
+                state.connections.contains(&(*addr, to))
+            })?;
+            (local, state.listeners.get(&to).cloned())
+        };
+        let listener = match listener {
+            Some(listener) => listener,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    "connection refused",
+                ))
+            }
+        };
+        let (up, down) = (Arc::new(Pipe::default()), Arc::new(Pipe::default()));
+        let server = TcpStream::new(self, to, local, up.clone(), down.clone());
+        if listener.unbounded_send(server).is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "connection refused",
+            ));
+        }
+        task::sleep(self.delay()).await;
+        Ok(TcpStream::new(self, local, to, down, up))
+    }
+
+    /// Creates a UDP socket bound to `addr`, or to an ephemeral port if its port is zero.
+    ///
+    /// # Errors
+    ///
+    /// This method fails of `AddrInUse` if a socket is bound to the address already.
+    pub fn udp_bind(&self, addr: SocketAddr) -> io::Result<UdpSocket> {
+        let mut state = self.state();
+        let addr = state.bind_port(addr, |state, addr| state.udp.contains_key(addr))?;
+        let (sender, receiver) = mpsc::unbounded();
+        state.udp.insert(addr, sender);
+        Ok(UdpSocket {
+            sim: self.clone(),
+            addr,
+            datagrams: futures::lock::Mutex::new(receiver),
+        })
+    }
+
+    /// Creates a Unix datagram socket bound to `path`.
+    ///
+    /// The Unix sockets are local to one host, they are never partitioned.
+    ///
+    /// # Errors
+    ///
+    /// This method fails of `AddrInUse` if a socket is bound to the path already.
+    pub fn unix_datagram_bind(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> io::Result<UnixDatagram> {
+        let path = path.as_ref().to_path_buf();
+        let mut state = self.state();
+        if state.unix.contains_key(&path) {
+            return Err(addr_in_use());
+        }
+        let (sender, receiver) = mpsc::unbounded();
+        state.unix.insert(path.clone(), sender);
+        Ok(UnixDatagram {
+            sim: self.clone(),
+            path,
+            datagrams: futures::lock::Mutex::new(receiver),
+        })
+    }
+
+    #[inline]
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.0.lock().expect(SIM_LOCK_POISONED)
+    }
+
+    /// Draws a latency, by splitmix64.
+    fn delay(&self) -> Duration {
+        let mut state = self.state();
+        let (min, max) = state.latency;
+        state.rng = state.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        let span = (max - min).as_nanos() as u64;
+        min + Duration::from_nanos(z.checked_rem(span + 1).unwrap_or(z))
+    }
+
+    /// Sends a segment of a stream, held back while the hosts are partitioned.
+    fn transmit(
+        &self,
+        from: IpAddr,
+        to: IpAddr,
+        pipe: &Arc<Pipe>,
+        seq: u64,
+        data: Segment,
+    ) {
+        let (sim, pipe, delay) = (self.clone(), pipe.clone(), self.delay());
+        task::spawn(async move {
+            task::sleep(delay).await;
+            while sim.is_partitioned(from, to) {
+                task::sleep(RETRANSMIT).await;
+            }
+            pipe.deliver(seq, data)
+        })
+        .detach()
+    }
+
+    /// Sends a datagram, dropped if the hosts are partitioned or the target is unbound.
+    fn send_datagram<A, F>(&self, data: Vec<u8>, from: A, partitioned: bool, target: F)
+    where
+        A: 'static + Send,
+        F: 'static
+            + Send
+            + FnOnce(&State) -> Option<mpsc::UnboundedSender<(Vec<u8>, A)>>,
+    {
+        let (sim, delay) = (self.clone(), self.delay());
+        task::spawn(async move {
+            task::sleep(delay).await;
+            if partitioned {
+                return;
+            }
+            let target = target(&sim.state());
+            if let Some(target) = target {
+                let _ = target.unbounded_send((data, from));
+            }
+        })
+        .detach()
+    }
+}
+
+impl Debug for Sim {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("Sim")
+            .field("latency", &state.latency)
+            .field("partitions", &state.partitions)
+            .finish()
+    }
+}
+
+impl State {
+    /// Picks an ephemeral port for an address of port zero, failing if `used` the address.
+    fn bind_port<F>(&mut self, mut addr: SocketAddr, used: F) -> io::Result<SocketAddr>
+    where
+        F: Fn(&Self, &SocketAddr) -> bool,
+    {
+        if addr.port() != 0 {
+            return if used(self, &addr) {
+                Err(addr_in_use())
+            } else {
+                Ok(addr)
+            };
+        }
+        for _ in EPHEMERAL_PORTS..=u16::MAX {
+            addr.set_port(self.next_port);
+            self.next_port = self.next_port.checked_add(1).unwrap_or(EPHEMERAL_PORTS);
+            if !used(self, &addr) {
+                return Ok(addr);
+            }
+        }
+        Err(addr_in_use())
+    }
+}
+
+#[inline]
+fn link(a: IpAddr, b: IpAddr) -> (IpAddr, IpAddr) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[inline]
+fn addr_in_use() -> io::Error {
+    io::Error::new(io::ErrorKind::AddrInUse, "address in use")
+}
+
+/// The bytes of a segment, or `None` for the end of the stream.
+type Segment = Option<Vec<u8>>;
+
+/// One direction of a stream, reassembling the segments in order.
+#[derive(Default)]
+struct Pipe(Mutex<PipeState>);
+
+#[derive(Default)]
+struct PipeState {
+    segments: BTreeMap<u64, Segment>,
+    next: u64,
+    buf: Vec<u8>,
+    pos: usize,
+    eof: bool,
+    waker: Option<Waker>,
+}
+
+impl Pipe {
+    fn deliver(&self, seq: u64, segment: Segment) {
+        let mut state = self.0.lock().expect(PIPE_LOCK_POISONED);
+        state.segments.insert(seq, segment);
+        if let Some(waker) = state.waker.take() {
+            waker.wake()
+        }
+    }
+
+    fn poll_read(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.0.lock().expect(PIPE_LOCK_POISONED);
+        loop {
+            if state.pos < state.buf.len() {
+                let n = buf.len().min(state.buf.len() - state.pos);
+                buf[..n].copy_from_slice(&state.buf[state.pos..state.pos + n]);
+                state.pos += n;
+                return Poll::Ready(Ok(n));
+            }
+            let next = state.next;
+            match state.segments.remove(&next) {
+                Some(Some(data)) => {
+                    state.next += 1;
+                    state.buf = data;
+                    state.pos = 0;
+                }
+                Some(None) => {
+                    state.next += 1;
+                    state.eof = true;
+                }
+                None if state.eof => return Poll::Ready(Ok(0)),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+/// A simulated TCP listener.
+pub struct TcpListener {
+    sim: Sim,
+    addr: SocketAddr,
+    incoming: futures::lock::Mutex<mpsc::UnboundedReceiver<TcpStream>>,
+}
+
+impl TcpListener {
+    /// Accepts a new incoming connection, returning the stream and the address of the peer.
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        match self.incoming.lock().await.next().await {
+            Some(stream) => {
+                let peer = stream.peer;
+                Ok((stream, peer))
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "listener closed",
+            )),
+        }
+    }
+
+    /// Returns the local address of this listener.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+}
+
+impl Drop for TcpListener {
+    fn drop(&mut self) {
+        self.sim.state().listeners.remove(&self.addr);
+    }
+}
+
+impl Debug for TcpListener {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpListener")
+            .field("addr", &self.addr)
+            .finish()
+    }
+}
+
+/// A simulated TCP stream.
+///
+/// The stream is closed by [`close`], or when it is dropped.
+///
+/// [`close`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncWriteExt.html#method.close
+pub struct TcpStream {
+    sim: Sim,
+    local: SocketAddr,
+    peer: SocketAddr,
+    read: Arc<Pipe>,
+    write: Arc<Pipe>,
+    seq: u64,
+    closed: bool,
+}
+
+impl TcpStream {
+    fn new(
+        sim: &Sim,
+        local: SocketAddr,
+        peer: SocketAddr,
+        read: Arc<Pipe>,
+        write: Arc<Pipe>,
+    ) -> Self {
+        sim.state().connections.insert((local, peer));
+        Self {
+            sim: sim.clone(),
+            local,
+            peer,
+            read,
+            write,
+            seq: 0,
+            closed: false,
+        }
+    }
+
+    /// Returns the local address of this stream.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local)
+    }
+
+    /// Returns the address of the peer of this stream.
+    #[inline]
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.peer)
+    }
+
+    fn send(&mut self, segment: Segment) {
+        let seq = self.seq;
+        self.seq += 1;
+        let (from, to) = (self.local.ip(), self.peer.ip());
+        self.sim.transmit(from, to, &self.write, seq, segment)
+    }
+}
+
+impl AsyncRead for TcpStream {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.read.poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.closed {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "stream closed",
+            )));
+        }
+        if !buf.is_empty() {
+            self.send(Some(buf.to_vec()));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        if !self.closed {
+            self.closed = true;
+            self.send(None);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for TcpStream {
+    fn drop(&mut self) {
+        self.sim
+            .state()
+            .connections
+            .remove(&(self.local, self.peer));
+        if !self.closed {
+            self.closed = true;
+            self.send(None);
+        }
+    }
+}
+
+impl Debug for TcpStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpStream")
+            .field("local", &self.local)
+            .field("peer", &self.peer)
+            .finish()
+    }
+}
+
+/// Receives a datagram from a queue, truncated to the length of `buf`.
+async fn recv_datagram<A>(
+    datagrams: &futures::lock::Mutex<Datagrams<A>>,
+    buf: &mut [u8],
+) -> io::Result<(usize, A)> {
+    match datagrams.lock().await.next().await {
+        Some((data, from)) => {
+            let n = buf.len().min(data.len());
+            buf[..n].copy_from_slice(&data[..n]);
+            Ok((n, from))
+        }
+        None => Err(io::Error::new(io::ErrorKind::NotConnected, "socket closed")),
+    }
+}
+
+#[inline]
+fn check_len(buf: &[u8]) -> io::Result<()> {
+    if buf.len() > MAX_DATAGRAM {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "datagram too long",
+        ));
+    }
+    Ok(())
+}
+
+/// A simulated UDP socket.
+pub struct UdpSocket {
+    sim: Sim,
+    addr: SocketAddr,
+    datagrams: futures::lock::Mutex<Datagrams<SocketAddr>>,
+}
+
+impl UdpSocket {
+    /// Sends a datagram to `target`, returning the number of bytes sent.
+    ///
+    /// The datagram is dropped silently if nothing is bound to the target, or if the hosts are
+    /// partitioned.
+    ///
+    /// # Errors
+    ///
+    /// This method fails of `InvalidInput` if the datagram is longer than 64 KiB.
+    pub async fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        check_len(buf)?;
+        let partitioned = self.sim.is_partitioned(self.addr.ip(), target.ip());
+        self.sim
+            .send_datagram(buf.to_vec(), self.addr, partitioned, move |state| {
+                state.udp.get(&target).cloned()
+            });
+        Ok(buf.len())
+    }
+
+    /// Receives a datagram, returning the number of bytes read and the address of the sender.
+    ///
+    /// The bytes of the datagram which do not fit in `buf` are discarded.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        recv_datagram(&self.datagrams, buf).await
+    }
+
+    /// Returns the local address of this socket.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+}
+
+impl Drop for UdpSocket {
+    fn drop(&mut self) {
+        self.sim.state().udp.remove(&self.addr);
+    }
+}
+
+impl Debug for UdpSocket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UdpSocket")
+            .field("addr", &self.addr)
+            .finish()
+    }
+}
+
+/// A simulated Unix datagram socket.
+pub struct UnixDatagram {
+    sim: Sim,
+    path: PathBuf,
+    datagrams: futures::lock::Mutex<Datagrams<PathBuf>>,
+}
+
+impl UnixDatagram {
+    /// Sends a datagram to the socket bound to `path`, returning the number of bytes sent.
+    ///
+    /// # Errors
+    ///
+    /// This method fails of `NotFound` if no socket is bound to the path, and of
+    /// `InvalidInput` if the datagram is longer than 64 KiB.
+    pub async fn send_to(
+        &self,
+        buf: &[u8],
+        path: impl AsRef<Path>,
+    ) -> io::Result<usize> {
+        check_len(buf)?;
+        let target = path.as_ref().to_path_buf();
+        if !self.sim.state().unix.contains_key(&target) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no such socket"));
+        }
+        self.sim
+            .send_datagram(buf.to_vec(), self.path.clone(), false, move |state| {
+                state.unix.get(&target).cloned()
+            });
+        Ok(buf.len())
+    }
+
+    /// Receives a datagram, returning the number of bytes read and the path of the sender.
+    ///
+    /// The bytes of the datagram which do not fit in `buf` are discarded.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, PathBuf)> {
+        recv_datagram(&self.datagrams, buf).await
+    }
+
+    /// Returns the path this socket is bound to.
+    #[inline]
+    pub fn local_addr(&self) -> io::Result<PathBuf> {
+        Ok(self.path.clone())
+    }
+}
+
+impl Drop for UnixDatagram {
+    fn drop(&mut self) {
+        self.sim.state().unix.remove(&self.path);
+    }
+}
+
+impl Debug for UnixDatagram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnixDatagram")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sim;
+    use crate::runtime::Builder;
+    use crate::task;
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use std::io;
+    use std::net::{IpAddr, SocketAddr};
+    use std::time::{Duration, Instant};
+
+    fn run<F, T>(seed: u64, fut: F) -> T
+    where
+        F: std::future::Future<Output = T>,
+    {
+        let runtime = Builder::new_current_thread().seed(seed).build().unwrap();
+        runtime.block_on(fut)
+    }
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn stream_order() -> io::Result<()> {
+        let start = Instant::now();
+        run(1, async {
+            let sim = Sim::new(1);
+            sim.latency(Duration::from_millis(1), Duration::from_secs(10));
+            let listener = sim.tcp_bind(addr("10.0.0.1:80"))?;
+            let client = task::spawn({
+                let sim = sim.clone();
+                async move {
+                    let mut stream = sim
+                        .tcp_connect(addr("10.0.0.2:0").ip(), addr("10.0.0.1:80"))
+                        .await?;
+                    for i in 0..100u8 {
+                        stream.write_all(&[i]).await?;
+                    }
+                    stream.close().await
+                }
+            });
+            let (mut stream, peer) = listener.accept().await?;
+            assert_eq!(addr("10.0.0.2:49152"), peer);
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).await?;
+            assert_eq!((0..100).collect::<Vec<_>>(), received);
+            client.await.unwrap()
+        })?;
+        // virtual time
+        assert!(start.elapsed() < Duration::from_secs(10));
+        Ok(())
+    }
+
+    #[test]
+    fn connect() {
+        run(2, async {
+            let sim = Sim::new(2);
+            let client: IpAddr = "10.0.0.2".parse().unwrap();
+            let err = sim
+                .tcp_connect(client, addr("10.0.0.1:80"))
+                .await
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::ConnectionRefused, err.kind());
+            let _listener = sim.tcp_bind(addr("10.0.0.1:80")).unwrap();
+            let err = sim.tcp_bind(addr("10.0.0.1:80")).unwrap_err();
+            assert_eq!(io::ErrorKind::AddrInUse, err.kind());
+            sim.partition(client, "10.0.0.1".parse().unwrap());
+            let err = sim
+                .tcp_connect(client, addr("10.0.0.1:80"))
+                .await
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+        })
+    }
+
+    #[test]
+    fn partition() -> io::Result<()> {
+        run(3, async {
+            let sim = Sim::new(3);
+            let (a, b) = (addr("10.0.0.1:80"), addr("10.0.0.2:0"));
+            let listener = sim.tcp_bind(a)?;
+            let mut client = sim.tcp_connect(b.ip(), a).await?;
+            let (mut server, _) = listener.accept().await?;
+            sim.partition(a.ip(), b.ip());
+            client.write_all(b"held").await?;
+            let repair = task::spawn({
+                let sim = sim.clone();
+                async move {
+                    task::sleep(Duration::from_secs(60)).await;
+                    sim.repair(a.ip(), b.ip());
+                }
+            });
+            let mut buf = [0; 4];
+            server.read_exact(&mut buf).await?;
+            assert_eq!(b"held", &buf);
+            // delivered once repaired
+            assert!(repair.await.is_ok());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn datagrams() -> io::Result<()> {
+        let order = |seed| {
+            run(seed, async move {
+                let sim = Sim::new(seed);
+                sim.latency(Duration::from_millis(1), Duration::from_millis(100));
+                let a = sim.udp_bind(addr("10.0.0.1:53"))?;
+                let b = sim.udp_bind(addr("10.0.0.2:0"))?;
+                for i in 0..20u8 {
+                    b.send_to(&[i], a.local_addr()?).await?;
+                }
+                // dropped across a partition
+                sim.partition(addr("10.0.0.1:0").ip(), addr("10.0.0.2:0").ip());
+                b.send_to(b"lost", a.local_addr()?).await?;
+                let mut order = Vec::new();
+                let mut buf = [0; 4];
+                for _ in 0..20 {
+                    let (n, from) = a.recv_from(&mut buf).await?;
+                    assert_eq!((1, b.local_addr()?), (n, from));
+                    order.push(buf[0]);
+                }
+                io::Result::Ok(order)
+            })
+        };
+        let first = order(4)?;
+        // reordered, deterministically
+        assert_ne!((0..20).collect::<Vec<_>>(), first);
+        assert_eq!(first, order(4)?);
+        Ok(())
+    }
+
+    #[test]
+    fn unix() -> io::Result<()> {
+        run(5, async {
+            let sim = Sim::new(5);
+            let a = sim.unix_datagram_bind("/tmp/a.sock")?;
+            let b = sim.unix_datagram_bind("/tmp/b.sock")?;
+            b.send_to(b"ping", "/tmp/a.sock").await?;
+            let mut buf = [0; 2];
+            let (n, from) = a.recv_from(&mut buf).await?;
+            assert_eq!((2, b.local_addr()?), (n, from));
+            assert_eq!(b"pi", &buf);
+            let err = a.send_to(b"x", "/tmp/none.sock").await.unwrap_err();
+            assert_eq!(io::ErrorKind::NotFound, err.kind());
+            Ok(())
+        })
+    }
+}